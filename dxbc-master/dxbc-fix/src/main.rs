@@ -0,0 +1,118 @@
+extern crate dxbc;
+
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "\
+usage: dxbc-fix [options] <file>...
+
+Verifies the checksums of DXBC containers and rewrites the wrong ones in place.
+
+options:
+    -c, --check        only verify, don't modify any file
+    -q, --quiet        only report files that fail
+    -h, --help         print this help";
+
+// magic, checksum, a reserved dword, size and chunk count
+const HEADER_SIZE: usize = 32;
+
+struct Options {
+    check: bool,
+    quiet: bool,
+    inputs: Vec<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        check: false,
+        quiet: false,
+        inputs: Vec::new(),
+    };
+
+    for arg in env::args().skip(1) {
+        match &*arg {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-c" | "--check" => options.check = true,
+            "-q" | "--quiet" => options.quiet = true,
+            _ if arg.starts_with('-') => {
+                return Err(format!("unknown option `{}`", arg));
+            }
+            _ => options.inputs.push(arg),
+        }
+    }
+
+    if options.inputs.is_empty() {
+        return Err("no input files".to_owned());
+    }
+
+    Ok(options)
+}
+
+enum Status {
+    Valid,
+    Fixed,
+}
+
+fn process_file(path: &str, check: bool) -> Result<Status, String> {
+    let mut bytes = fs::read(path).map_err(|err| format!("cannot read: {}", err))?;
+
+    if bytes.len() < HEADER_SIZE || &bytes[..4] != b"DXBC" {
+        return Err("not a DXBC container".to_owned());
+    }
+
+    // the checksum covers everything after it, so trailing bytes would be hashed too
+    let size = u32::from_le_bytes([bytes[24], bytes[25], bytes[26], bytes[27]]) as usize;
+    if size != bytes.len() {
+        return Err(format!("header size {} doesn't match the file size {}", size, bytes.len()));
+    }
+
+    if check {
+        return dxbc::verify(&bytes).map(|_| Status::Valid).map_err(|err| err.to_string());
+    }
+
+    if !dxbc::fix_checksum(&mut bytes).map_err(|err| err.to_string())? {
+        return Ok(Status::Valid);
+    }
+
+    fs::write(path, &bytes).map_err(|err| format!("cannot write: {}", err))?;
+    Ok(Status::Fixed)
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let mut failed = 0;
+    for path in &options.inputs {
+        match process_file(path, options.check) {
+            Ok(Status::Valid) => {
+                if !options.quiet {
+                    println!("{}: ok", path);
+                }
+            }
+            Ok(Status::Fixed) => {
+                if !options.quiet {
+                    println!("{}: fixed", path);
+                }
+            }
+            Err(err) => {
+                eprintln!("error: {}: {}", path, err);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed != 0 {
+        eprintln!("{} of {} files failed", failed, options.inputs.len());
+        process::exit(1);
+    }
+}
@@ -0,0 +1,1601 @@
+//! Assembler for the shader model 4 and 5 text format, the counterpart of
+//! `disasm::disassemble`.
+//!
+//! The reflection comments fxc puts in front of a listing are read back as
+//! well, so a disassembled shader assembles into the same RDEF, signature
+//! and SHEX chunks again. Listings of `disasm::disassemble_lossless` keep
+//! everything else, they assemble into the same container.
+
+use disasm;
+use dr;
+use dr::builder::{
+    Builder, ComponentMode, DxbcModule, Immediate, Instruction, MinPrecision, Modifier, NumComponent, OpcodeEx,
+    Operand, OperandType, ShexChunk, X, Y, Z, W,
+};
+use dr::shex::{ResourceDimension, ResourceReturnType};
+use d3d11tokenizedprogramformat::*;
+
+use std::collections::HashMap;
+use std::{error, fmt};
+
+/// Assembly error, `line` is 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl error::Error for Error {}
+
+type Result<T> = ::std::result::Result<T, String>;
+
+/// Assembles a shader listing into a checksummed DXBC container.
+pub fn assemble(source: &str) -> ::std::result::Result<DxbcModule, Error> {
+    let mut assembler = Assembler::new();
+    let mut line = 0;
+
+    for (idx, text) in source.lines().enumerate() {
+        line = idx + 1;
+        assembler.line(text).map_err(|message| Error { line, message })?;
+    }
+
+    assembler.finish().map_err(|message| Error { line, message })
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Section {
+    None,
+    Buffers,
+    Bindings,
+    Inputs,
+    Outputs,
+    PatchConstants,
+    Reflection,
+    Chunk,
+}
+
+// How the component suffix of an operand is encoded, destinations and
+// declarations use masks while sources select or swizzle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Role {
+    Destination,
+    Source,
+}
+
+struct Assembler<'a> {
+    section: Section,
+    author: &'a str,
+    constant_buffers: Vec<dr::ConstantBuffer<'a>>,
+    buffer: Option<dr::ConstantBuffer<'a>>,
+    // structs of the buffer definitions currently being read, innermost last
+    structs: Vec<(Option<&'a str>, Vec<dr::ShaderTypeMember<'a>>)>,
+    bindings: Vec<dr::ResourceBinding<'a>>,
+    inputs: Vec<dr::InputOutputElement>,
+    outputs: Vec<dr::InputOutputElement>,
+    // outputs listed with their stream go into an `OSG5` chunk
+    streams: bool,
+    // read as inputs, hull shaders turn them into outputs
+    patch_constants: Option<Vec<dr::InputOutputElement>>,
+    version: Option<(u32, u8, u8)>,
+    shex: ShexChunk,
+    // the binding table doesn't list sample counts, strides and sampler
+    // modes, they are taken from the declarations
+    samples: HashMap<(u32, u32), u32>,
+    comparison_samplers: Vec<u32>,
+    // lines of an immediate constant buffer until its closing brace
+    icb: Option<String>,
+    // RDEF header fields and binding flags of the `Reflection:` comments
+    flags: u32,
+    rd11: Option<[u32; 7]>,
+    user_packed: Vec<&'a str>,
+    unused: Vec<&'a str>,
+    // chunks of the `Chunk STAT:` comments, copied as they are
+    chunks: Vec<([u8; 4], Vec<u8>)>,
+}
+
+impl<'a> Assembler<'a> {
+    fn new() -> Self {
+        Assembler {
+            section: Section::None,
+            author: "",
+            constant_buffers: Vec::new(),
+            buffer: None,
+            structs: Vec::new(),
+            bindings: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            streams: false,
+            patch_constants: None,
+            version: None,
+            shex: ShexChunk::new(),
+            samples: HashMap::new(),
+            comparison_samplers: Vec::new(),
+            icb: None,
+            flags: 0x100,
+            rd11: None,
+            user_packed: Vec::new(),
+            unused: Vec::new(),
+            chunks: Vec::new(),
+        }
+    }
+
+    fn line(&mut self, text: &'a str) -> Result<()> {
+        if let Some(mut icb) = self.icb.take() {
+            icb.push_str(text);
+            icb.push(' ');
+            return self.immediate_constant_buffer(icb);
+        }
+
+        let text = text.trim_start();
+        if let Some(comment) = text.strip_prefix("//") {
+            return self.comment(comment);
+        }
+
+        let text = strip_comment(text);
+
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        if self.version.is_none() {
+            return self.version(text);
+        }
+
+        self.section = Section::None;
+        self.instruction(text)
+    }
+
+    fn comment(&mut self, text: &'a str) -> Result<()> {
+        let trimmed = text.trim();
+
+        if let Some(author) = trimmed.strip_prefix("Generated by ") {
+            self.author = author;
+            return Ok(());
+        }
+
+        self.section = match trimmed {
+            "Buffer Definitions:" => Section::Buffers,
+            "Resource Bindings:" => Section::Bindings,
+            "Input signature:" => Section::Inputs,
+            "Output signature:" => Section::Outputs,
+            "Patch Constant signature:" => {
+                self.patch_constants = Some(Vec::new());
+                Section::PatchConstants
+            }
+            "Reflection:" => Section::Reflection,
+            _ if trimmed.starts_with("Chunk ") && trimmed.ends_with(':') => {
+                let fourcc = &trimmed.as_bytes()["Chunk ".len()..trimmed.len() - 1];
+                if fourcc.len() != 4 {
+                    return Err(format!("malformed chunk name `{}`", trimmed));
+                }
+                self.chunks.push(([fourcc[0], fourcc[1], fourcc[2], fourcc[3]], Vec::new()));
+                Section::Chunk
+            }
+            _ if trimmed.starts_with("Approximately ") => Section::None,
+            _ => {
+                return match self.section {
+                    Section::None => Ok(()),
+                    Section::Buffers => self.buffer_definition(trimmed),
+                    Section::Outputs if trimmed.starts_with("Stream Name ") => {
+                        self.streams = true;
+                        Ok(())
+                    }
+                    _ if trimmed.is_empty() || trimmed.starts_with("Name ") || trimmed.starts_with("---") => Ok(()),
+                    Section::Bindings => self.binding(trimmed),
+                    Section::Inputs | Section::Outputs | Section::PatchConstants => {
+                        self.signature_element(text)
+                    }
+                    Section::Reflection => self.reflection(trimmed),
+                    Section::Chunk => self.chunk(trimmed),
+                };
+            }
+        };
+
+        Ok(())
+    }
+
+    fn buffer_definition(&mut self, text: &'a str) -> Result<()> {
+        if text.is_empty() || text == "{" {
+            return Ok(());
+        }
+
+        if text == "}" {
+            let mut buffer = self.buffer.take().ok_or("unexpected `}`")?;
+            let end = buffer.variables.iter().map(|var| var.start_offset + var.byte_size).max().unwrap_or(0);
+            buffer.byte_size = (end + 15) & !15;
+            self.constant_buffers.push(buffer);
+            return Ok(());
+        }
+
+        let kinds = [
+            ("cbuffer ", dr::ConstantBufferType::ConstantBuffer as u32),
+            ("tbuffer ", dr::ConstantBufferType::TextureBuffer as u32),
+        ];
+
+        for &(keyword, ty) in &kinds {
+            if let Some(name) = text.strip_prefix(keyword) {
+                self.buffer = Some(dr::ConstantBuffer {
+                    name: name.trim(),
+                    variables: Vec::new(),
+                    byte_size: 0,
+                    flags: 0,
+                    ty,
+                });
+                return Ok(());
+            }
+        }
+
+        if text.starts_with("struct") && !text.contains(';') {
+            let name = text["struct".len()..].trim();
+            self.structs.push((if name.is_empty() { None } else { Some(name) }, Vec::new()));
+            return Ok(());
+        }
+
+        let (declaration, info) = match text.find("//") {
+            Some(pos) => (text[..pos].trim().trim_end_matches(';'), &text[pos + 2..]),
+            None => return Err(format!("expected a variable declaration, found `{}`", text)),
+        };
+
+        let offset = field(info, "Offset:").ok_or("missing variable offset")?;
+
+        let (name, ty) = if let Some(declaration) = declaration.strip_prefix('}') {
+            let (name, count) = split_array(declaration.trim())?;
+            let (struct_name, members) = self.structs.pop().ok_or("unexpected `}`")?;
+            (name, struct_type(struct_name, members, count))
+        } else {
+            let pos = declaration.rfind(' ').ok_or_else(|| format!("missing variable name in `{}`", declaration))?;
+            let (name, count) = split_array(&declaration[pos + 1..])?;
+            (name, variable_type(declaration[..pos].trim(), count)?)
+        };
+
+        if let Some(&mut (_, ref mut members)) = self.structs.last_mut() {
+            members.push(dr::ShaderTypeMember { name, ty, offset });
+            return Ok(());
+        }
+
+        let buffer = self.buffer.as_mut().ok_or("variable outside of a buffer")?;
+        buffer.variables.push(dr::ShaderVariable {
+            name,
+            start_offset: offset,
+            byte_size: field(info, "Size:").ok_or("missing variable size")?,
+            flags: if info.contains("[unused]") { dr::ShaderVariableFlags::NONE } else { dr::ShaderVariableFlags::USED },
+            ty,
+            default_value: None,
+            start_texture: !0,
+            texture_size: 0,
+            start_sampler: !0,
+            sampler_size: 0,
+        });
+
+        Ok(())
+    }
+
+    fn binding(&mut self, text: &'a str) -> Result<()> {
+        let fields: Vec<&'a str> = text.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(format!("malformed resource binding `{}`", text));
+        }
+
+        let (ty, format, dimension, bind, count) = (fields[1], fields[2], fields[3], fields[4], fields[5]);
+
+        let input_type = match (ty, format) {
+            ("cbuffer", _) => 0,
+            ("tbuffer", _) => 1,
+            ("texture", "struct") => 5,
+            ("texture", "byte") => 7,
+            ("texture", _) => 2,
+            ("sampler", _) => 3,
+            ("UAV", "struct") => 6,
+            ("UAV", "byte") => 8,
+            ("UAV", _) => 4,
+            _ => return Err(format!("unknown binding type `{}`", ty)),
+        };
+
+        let (return_type, input_flags) = match format {
+            "NA" => (0, 0),
+            "struct" | "byte" => (D3D10_SB_RETURN_TYPE_MIXED, 0),
+            _ => {
+                let digits = format.len() - format.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+                let (name, components) = format.split_at(format.len() - digits);
+                let components = if components.is_empty() { 1 } else { parse_u32(components)? };
+                let return_type = find_name(1..10, name, disasm::return_type_name)
+                    .ok_or_else(|| format!("unknown format `{}`", format))?;
+
+                (return_type, (components.max(1) - 1) << 2)
+            }
+        };
+
+        let view_dimension = match dimension {
+            "NA" => 0,
+            "r/o" if format == "byte" => 11,
+            "r/o" | "r/w" | "buf" => 1,
+            "1d" => 2,
+            "1darray" => 3,
+            "2d" => 4,
+            "2darray" => 5,
+            "2dMS" => 6,
+            "2dMSarray" => 7,
+            "3d" => 8,
+            "cube" => 9,
+            "cubearray" => 10,
+            _ => return Err(format!("unknown dimension `{}`", dimension)),
+        };
+
+        let bind_point = parse_u32(bind.trim_start_matches(|c: char| c.is_ascii_alphabetic()))?;
+
+        self.bindings.push(dr::ResourceBinding {
+            name: fields[0],
+            input_type,
+            return_type,
+            view_dimension,
+            sample_count: 0,
+            bind_point,
+            bind_count: parse_u32(count)?,
+            input_flags,
+        });
+
+        Ok(())
+    }
+
+    // `Flags 0x00000100`, `Descriptors 60 24 32 40 36 12 0` or the names of
+    // the user packed or unused bindings.
+    fn reflection(&mut self, text: &'a str) -> Result<()> {
+        let (key, value) = match text.find("  ") {
+            Some(pos) => (&text[..pos], text[pos..].trim()),
+            None => return Err(format!("malformed reflection entry `{}`", text)),
+        };
+
+        match key {
+            "Flags" => self.flags = parse_literal(value)?,
+            "Descriptors" => {
+                let sizes = value.split_whitespace().map(parse_u32).collect::<Result<Vec<u32>>>()?;
+                if sizes.len() != 7 {
+                    return Err(format!("expected 7 descriptor sizes, found `{}`", value));
+                }
+                let mut rd11 = [0; 7];
+                rd11.copy_from_slice(&sizes);
+                self.rd11 = Some(rd11);
+            }
+            "User packed" => self.user_packed.extend(value.split_whitespace()),
+            "Unused" => self.unused.extend(value.split_whitespace()),
+            _ => return Err(format!("unknown reflection entry `{}`", key)),
+        }
+
+        Ok(())
+    }
+
+    // A row of hex bytes of the chunk being read.
+    fn chunk(&mut self, text: &str) -> Result<()> {
+        let data = match self.chunks.last_mut() {
+            Some(&mut (_, ref mut data)) => data,
+            None => return Err("chunk bytes outside of a chunk".to_owned()),
+        };
+
+        for byte in text.split_whitespace() {
+            data.push(u8::from_str_radix(byte, 16).map_err(|_| format!("malformed chunk byte `{}`", byte))?);
+        }
+
+        Ok(())
+    }
+
+    // Signature rows are fixed width tables whose mask columns contain
+    // spaces, everything after the semantic index is read by position.
+    fn signature_element(&mut self, text: &str) -> Result<()> {
+        let mut text = text.trim_start();
+        let mut stream = 0;
+        if self.section == Section::Outputs && self.streams {
+            let stream_end = text.find(' ').ok_or_else(|| format!("malformed signature element `{}`", text))?;
+            stream = parse_u32(&text[..stream_end])?;
+            text = text[stream_end..].trim_start();
+        }
+
+        let output = self.section == Section::Outputs;
+        let name_end = text.find(' ').ok_or_else(|| format!("malformed signature element `{}`", text))?;
+        let rest = &text[name_end..];
+        let index_start = rest.len() - rest.trim_start().len();
+        let index_end = rest[index_start..].find(' ').map(|end| index_start + end).unwrap_or(rest.len());
+
+        let columns = format!("{:<40}", &rest[index_end..]);
+        if !columns.is_ascii() {
+            return Err(format!("malformed signature element `{}`", text));
+        }
+
+        let mask = columns[1..7].trim();
+        let register = columns[7..16].trim();
+        let system_value = columns[16..25].trim();
+        let format = columns[25..33].trim();
+        let used = &columns[33..40];
+
+        let semantic_type = match system_value {
+            "NONE" | "TARGET" | "DEPTH" | "COVERAGE" | "DEPTHGE" | "DEPTHLE" => 0,
+            "POS" => 1,
+            "CLIPDST" => 2,
+            "CULLDST" => 3,
+            "RTINDEX" => 4,
+            "VPINDEX" => 5,
+            "VERTID" => 6,
+            "PRIMID" => 7,
+            "INSTID" => 8,
+            "FFACE" => 9,
+            "SAMPLE" => 10,
+            "QUADEDGE" => 11,
+            "QUADINT" => 12,
+            "TRIEDGE" => 13,
+            "TRIINT" => 14,
+            "LINEDET" => 15,
+            "LINEDEN" => 16,
+            _ => return Err(format!("unknown system value `{}`", system_value)),
+        };
+
+        let component_type = match format {
+            "unknown" => 0,
+            "uint" => 1,
+            "int" => 2,
+            "float" => 3,
+            _ => return Err(format!("unknown format `{}`", format)),
+        };
+
+        // elements without a register are single component system values
+        let (register, component_mask, used) = if mask == "N/A" {
+            (!0, 1, if used.trim() == "YES" { 1 } else { 0 })
+        } else {
+            (parse_u32(register)?, positional_mask(&columns[1..7]), positional_mask(used))
+        };
+
+        let element = dr::InputOutputElement {
+            name: text[..name_end].to_owned(),
+            semantic_index: parse_u32(&rest[index_start..index_end])?,
+            semantic_type: dr::SemanticName::from_word(semantic_type),
+            component_type: dr::RegisterComponentType::from_word(component_type),
+            register,
+            component_mask,
+            rw_mask: if output { 0xf & !used } else { used },
+            stream,
+            min_precision: dr::ElementPrecision::Default,
+        };
+
+        match (self.section, &mut self.patch_constants) {
+            (Section::Inputs, _) => self.inputs.push(element),
+            (Section::Outputs, _) => self.outputs.push(element),
+            (_, &mut Some(ref mut elements)) => elements.push(element),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn version(&mut self, text: &str) -> Result<()> {
+        let parts: Vec<&str> = text.split('_').collect();
+        let program_type = match parts.len() {
+            3 => find_name(0..6, parts[0], disasm::shader_prefix),
+            _ => None,
+        };
+
+        match program_type {
+            Some(program_type) => {
+                let major = parse_u32(parts[1])? as u8;
+                let minor = parse_u32(parts[2])? as u8;
+                self.version = Some((program_type, major, minor));
+                self.shex.set_version(program_type as u16, major, minor);
+                Ok(())
+            }
+            None => Err(format!("expected a shader version like `ps_5_0`, found `{}`", text)),
+        }
+    }
+
+    fn emit(&mut self, opcode: u32, controls: u32, operands: Vec<Operand>, data: Vec<u32>) {
+        self.shex.add_instruction(Instruction::Generic { opcode, controls, extended: Vec::new(), operands, data });
+    }
+
+    fn instruction(&mut self, text: &str) -> Result<()> {
+        let (mnemonic, arguments) = split_mnemonic(text);
+
+        if mnemonic == "dcl_immediateConstantBuffer" {
+            return self.immediate_constant_buffer(format!("{} ", arguments));
+        }
+
+        if mnemonic.starts_with("dcl_") {
+            return self.declaration(mnemonic, arguments);
+        }
+
+        if let Some(message) = shader_message(mnemonic) {
+            return self.shader_message(message, arguments);
+        }
+
+        let (name, groups) = split_groups(mnemonic)?;
+        let (opcode, suffixes) = find_opcode(&name).ok_or_else(|| format!("unknown instruction `{}`", mnemonic))?;
+
+        let mut controls = 0;
+        for suffix in suffixes {
+            controls |= match (suffix, opcode) {
+                ("z", _) => ENCODE_D3D10_SB_INSTRUCTION_TEST_BOOLEAN(D3D10_SB_INSTRUCTION_TEST_ZERO),
+                ("nz", _) => ENCODE_D3D10_SB_INSTRUCTION_TEST_BOOLEAN(D3D10_SB_INSTRUCTION_TEST_NONZERO),
+                ("sat", _) => ENCODE_D3D10_SB_INSTRUCTION_SATURATE(1),
+                ("uglobal", _) => ENCODE_D3D11_SB_SYNC_FLAGS(D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GLOBAL),
+                ("ugroup", _) => ENCODE_D3D11_SB_SYNC_FLAGS(D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GROUP),
+                ("g", _) => ENCODE_D3D11_SB_SYNC_FLAGS(D3D11_SB_SYNC_THREAD_GROUP_SHARED_MEMORY),
+                ("t", _) => ENCODE_D3D11_SB_SYNC_FLAGS(D3D11_SB_SYNC_THREADS_IN_GROUP),
+                ("rcpFloat", D3D10_SB_OPCODE_RESINFO) => {
+                    ENCODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(D3D10_SB_RESINFO_INSTRUCTION_RETURN_RCPFLOAT)
+                }
+                ("uint", D3D10_SB_OPCODE_RESINFO) => {
+                    ENCODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(D3D10_SB_RESINFO_INSTRUCTION_RETURN_UINT)
+                }
+                ("uint", _) => ENCODE_D3D10_SB_INSTRUCTION_RETURN_TYPE(D3D10_SB_INSTRUCTION_RETURN_UINT),
+                _ => 0,
+            };
+        }
+
+        let extended = groups
+            .iter()
+            .map(|group| extended_opcode(group))
+            .collect::<Result<Vec<_>>>()?;
+
+        let destinations = destination_count(opcode);
+        let operands = split_operands(arguments)
+            .iter()
+            .enumerate()
+            .map(|(idx, operand)| parse_operand(operand, if idx < destinations { Role::Destination } else { Role::Source }))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.shex.add_instruction(Instruction::Generic { opcode, controls, extended, operands, data: Vec::new() });
+        Ok(())
+    }
+
+    fn declaration(&mut self, mnemonic: &str, arguments: &str) -> Result<()> {
+        let args = split_operands(arguments);
+        let arg = |idx: usize| -> Result<&str> {
+            args.get(idx).map(|arg| &**arg).ok_or_else(|| format!("missing operand {} of `{}`", idx + 1, mnemonic))
+        };
+        let opcode = |name: &str| find_name(0..256, name, |op| disasm::opcode_name(op).unwrap_or(""));
+
+        if mnemonic.starts_with("dcl_resource_") && mnemonic != "dcl_resource_raw" && mnemonic != "dcl_resource_structured" {
+            let (dimension, samples) = resource_dimension(&mnemonic["dcl_resource_".len()..])?;
+            let (return_type, register) = split_return_type(arg(0)?)?;
+
+            self.samples.insert((D3D10_SB_OPERAND_TYPE_RESOURCE, register_index(register)?), samples);
+            self.emit(
+                D3D10_SB_OPCODE_DCL_RESOURCE,
+                ENCODE_D3D10_SB_RESOURCE_DIMENSION(dimension) | ENCODE_D3D10_SB_RESOURCE_SAMPLE_COUNT(samples),
+                vec![parse_operand(register, Role::Destination)?],
+                vec![return_type],
+            );
+            return Ok(());
+        }
+
+        if mnemonic.starts_with("dcl_uav_") {
+            let (name, flags) = uav_flags(mnemonic);
+
+            if let Some(dimension) = name.strip_prefix("dcl_uav_typed_") {
+                let (dimension, _) = resource_dimension(dimension)?;
+                let (return_type, register) = split_return_type(arg(0)?)?;
+
+                self.emit(
+                    D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_TYPED,
+                    flags | ENCODE_D3D10_SB_RESOURCE_DIMENSION(dimension),
+                    vec![parse_operand(register, Role::Destination)?],
+                    vec![return_type],
+                );
+            } else if name == "dcl_uav_raw" {
+                self.emit(D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_RAW, flags, vec![parse_operand(arg(0)?, Role::Destination)?], Vec::new());
+            } else if name == "dcl_uav_structured" {
+                let stride = parse_u32(arg(1)?)?;
+                self.samples.insert((D3D11_SB_OPERAND_TYPE_UNORDERED_ACCESS_VIEW, register_index(arg(0)?)?), stride);
+                self.emit(
+                    D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_STRUCTURED,
+                    flags,
+                    vec![parse_operand(arg(0)?, Role::Destination)?],
+                    vec![stride],
+                );
+            } else {
+                return Err(format!("unknown declaration `{}`", mnemonic));
+            }
+
+            return Ok(());
+        }
+
+        match mnemonic {
+            "dcl_globalFlags" => {
+                let mut flags = 0;
+                for name in arguments.split('|').map(str::trim).filter(|name| !name.is_empty()) {
+                    flags |= match name {
+                        "refactoringAllowed" => D3D10_SB_GLOBAL_FLAG_REFACTORING_ALLOWED,
+                        "enableDoublePrecisionFloatOps" => D3D11_SB_GLOBAL_FLAG_ENABLE_DOUBLE_PRECISION_FLOAT_OPS,
+                        "forceEarlyDepthStencil" => D3D11_SB_GLOBAL_FLAG_FORCE_EARLY_DEPTH_STENCIL,
+                        "enableRawAndStructuredBuffers" => D3D11_SB_GLOBAL_FLAG_ENABLE_RAW_AND_STRUCTURED_BUFFERS,
+                        "skipOptimization" => D3D11_1_SB_GLOBAL_FLAG_SKIP_OPTIMIZATION,
+                        "enableMinimumPrecision" => D3D11_1_SB_GLOBAL_FLAG_ENABLE_MINIMUM_PRECISION,
+                        "enable11_1DoubleExtensions" => D3D11_1_SB_GLOBAL_FLAG_ENABLE_DOUBLE_EXTENSIONS,
+                        "enable11_1ShaderExtensions" => D3D11_1_SB_GLOBAL_FLAG_ENABLE_SHADER_EXTENSIONS,
+                        _ => return Err(format!("unknown global flag `{}`", name)),
+                    };
+                }
+
+                self.emit(D3D10_SB_OPCODE_DCL_GLOBAL_FLAGS, ENCODE_D3D10_SB_GLOBAL_FLAGS(flags), Vec::new(), Vec::new());
+            }
+            "dcl_constantbuffer" => {
+                let register = arg(0)?;
+                if !register.starts_with("CB") {
+                    return Err(format!("expected a constant buffer register, found `{}`", register));
+                }
+
+                // the declaration always reads all four components
+                let (ty, indices, _) = parse_register(&format!("cb{}", &register[2..]))?;
+                let operand = Operand::new(OperandType::Raw(ty, indices), Modifier::None, NumComponent::D4(ComponentMode::Swizzle(X, Y, Z, W)));
+
+                let pattern = match arg(1)? {
+                    "immediateIndexed" => D3D10_SB_CONSTANT_BUFFER_IMMEDIATE_INDEXED,
+                    "dynamicIndexed" => D3D10_SB_CONSTANT_BUFFER_DYNAMIC_INDEXED,
+                    pattern => return Err(format!("unknown access pattern `{}`", pattern)),
+                };
+
+                self.emit(
+                    D3D10_SB_OPCODE_DCL_CONSTANT_BUFFER,
+                    ENCODE_D3D10_SB_D3D10_SB_CONSTANT_BUFFER_ACCESS_PATTERN(pattern),
+                    vec![operand],
+                    Vec::new(),
+                );
+            }
+            "dcl_sampler" => {
+                let mode = match arg(1)? {
+                    "mode_default" => D3D10_SB_SAMPLER_MODE_DEFAULT,
+                    "mode_comparison" => D3D10_SB_SAMPLER_MODE_COMPARISON,
+                    "mode_mono" => D3D10_SB_SAMPLER_MODE_MONO,
+                    mode => return Err(format!("unknown sampler mode `{}`", mode)),
+                };
+
+                if mode == D3D10_SB_SAMPLER_MODE_COMPARISON {
+                    self.comparison_samplers.push(register_index(arg(0)?)?);
+                }
+
+                self.emit(
+                    D3D10_SB_OPCODE_DCL_SAMPLER,
+                    ENCODE_D3D10_SB_SAMPLER_MODE(mode),
+                    vec![parse_operand(arg(0)?, Role::Destination)?],
+                    Vec::new(),
+                );
+            }
+            "dcl_resource_raw" => {
+                self.emit(D3D11_SB_OPCODE_DCL_RESOURCE_RAW, 0, vec![parse_operand(arg(0)?, Role::Destination)?], Vec::new());
+            }
+            "dcl_resource_structured" => {
+                let stride = parse_u32(arg(1)?)?;
+                self.samples.insert((D3D10_SB_OPERAND_TYPE_RESOURCE, register_index(arg(0)?)?), stride);
+                self.emit(D3D11_SB_OPCODE_DCL_RESOURCE_STRUCTURED, 0, vec![parse_operand(arg(0)?, Role::Destination)?], vec![stride]);
+            }
+            "dcl_tgsm_raw" => {
+                self.emit(
+                    D3D11_SB_OPCODE_DCL_THREAD_GROUP_SHARED_MEMORY_RAW,
+                    0,
+                    vec![parse_operand(arg(0)?, Role::Destination)?],
+                    vec![parse_u32(arg(1)?)?],
+                );
+            }
+            "dcl_tgsm_structured" => {
+                self.emit(
+                    D3D11_SB_OPCODE_DCL_THREAD_GROUP_SHARED_MEMORY_STRUCTURED,
+                    0,
+                    vec![parse_operand(arg(0)?, Role::Destination)?],
+                    vec![parse_u32(arg(1)?)?, parse_u32(arg(2)?)?],
+                );
+            }
+            "dcl_input" | "dcl_output" | "dcl_stream" => {
+                let opcode = opcode(mnemonic).unwrap();
+                self.emit(opcode, 0, vec![parse_operand(arg(0)?, Role::Destination)?], Vec::new());
+            }
+            "dcl_input_ps" | "dcl_input_ps_siv" | "dcl_input_ps_sgv" => {
+                // the interpolation mode is separated from the register by a space
+                let declaration = arg(0)?;
+                let pos = declaration.rfind(' ').ok_or_else(|| format!("missing interpolation mode in `{}`", declaration))?;
+                let mode = find_name(0..8, &declaration[..pos], disasm::interpolation_name)
+                    .ok_or_else(|| format!("unknown interpolation mode `{}`", &declaration[..pos]))?;
+
+                let data = match mnemonic {
+                    "dcl_input_ps" => Vec::new(),
+                    _ => vec![system_value(arg(1)?)?],
+                };
+
+                self.emit(
+                    opcode(mnemonic).unwrap(),
+                    ENCODE_D3D10_SB_INPUT_INTERPOLATION_MODE(mode),
+                    vec![parse_operand(&declaration[pos + 1..], Role::Destination)?],
+                    data,
+                );
+            }
+            "dcl_input_siv" | "dcl_input_sgv" | "dcl_output_siv" | "dcl_output_sgv" => {
+                self.emit(
+                    opcode(mnemonic).unwrap(),
+                    0,
+                    vec![parse_operand(arg(0)?, Role::Destination)?],
+                    vec![system_value(arg(1)?)?],
+                );
+            }
+            "dcl_indexrange" => {
+                let declaration = arg(0)?;
+                let pos = declaration.rfind(' ').ok_or_else(|| format!("missing register count in `{}`", declaration))?;
+
+                self.emit(
+                    D3D10_SB_OPCODE_DCL_INDEX_RANGE,
+                    0,
+                    vec![parse_operand(&declaration[..pos], Role::Destination)?],
+                    vec![parse_u32(&declaration[pos + 1..])?],
+                );
+            }
+            "dcl_temps" | "dcl_maxout" | "dcl_gsinstances" | "dcl_hs_fork_phase_instance_count" | "dcl_hs_join_phase_instance_count" => {
+                let opcode = opcode(mnemonic).unwrap();
+                self.emit(opcode, 0, Vec::new(), vec![parse_u32(arg(0)?)?]);
+            }
+            "dcl_indexableTemp" => {
+                let register = arg(0)?;
+                let (ty, indices, _) = parse_register(register)?;
+                let (index, count) = match (ty, &*indices) {
+                    (D3D10_SB_OPERAND_TYPE_INDEXABLE_TEMP, &[Immediate::U32(index), Immediate::U32(count)]) => (index, count),
+                    _ => return Err(format!("expected an indexable temp like `x0[4]`, found `{}`", register)),
+                };
+
+                self.emit(D3D10_SB_OPCODE_DCL_INDEXABLE_TEMP, 0, Vec::new(), vec![index, count, parse_u32(arg(1)?)?]);
+            }
+            "dcl_inputprimitive" => {
+                let primitive = find_name(0..D3D11_SB_PRIMITIVE_32_CONTROL_POINT_PATCH + 1, arg(0)?, disasm::primitive_name)
+                    .ok_or_else(|| format!("unknown primitive `{}`", arguments))?;
+                self.emit(D3D10_SB_OPCODE_DCL_GS_INPUT_PRIMITIVE, ENCODE_D3D10_SB_GS_INPUT_PRIMITIVE(primitive), Vec::new(), Vec::new());
+            }
+            "dcl_outputtopology" => {
+                let topology = find_name(0..16, arg(0)?, disasm::topology_name)
+                    .ok_or_else(|| format!("unknown topology `{}`", arguments))?;
+                self.emit(
+                    D3D10_SB_OPCODE_DCL_GS_OUTPUT_PRIMITIVE_TOPOLOGY,
+                    ENCODE_D3D10_SB_GS_OUTPUT_PRIMITIVE_TOPOLOGY(topology),
+                    Vec::new(),
+                    Vec::new(),
+                );
+            }
+            "dcl_input_control_point_count" => {
+                let count = parse_u32(arg(0)?)?;
+                self.emit(D3D11_SB_OPCODE_DCL_INPUT_CONTROL_POINT_COUNT, ENCODE_D3D11_SB_INPUT_CONTROL_POINT_COUNT(count), Vec::new(), Vec::new());
+            }
+            "dcl_output_control_point_count" => {
+                let count = parse_u32(arg(0)?)?;
+                self.emit(D3D11_SB_OPCODE_DCL_OUTPUT_CONTROL_POINT_COUNT, ENCODE_D3D11_SB_OUTPUT_CONTROL_POINT_COUNT(count), Vec::new(), Vec::new());
+            }
+            "dcl_tessellator_domain" => {
+                let domain = match arg(0)? {
+                    "domain_isoline" => D3D11_SB_TESSELLATOR_DOMAIN_ISOLINE,
+                    "domain_tri" => D3D11_SB_TESSELLATOR_DOMAIN_TRI,
+                    "domain_quad" => D3D11_SB_TESSELLATOR_DOMAIN_QUAD,
+                    "domain_undefined" => D3D11_SB_TESSELLATOR_DOMAIN_UNDEFINED,
+                    domain => return Err(format!("unknown tessellator domain `{}`", domain)),
+                };
+                self.emit(D3D11_SB_OPCODE_DCL_TESS_DOMAIN, ENCODE_D3D11_SB_TESS_DOMAIN(domain), Vec::new(), Vec::new());
+            }
+            "dcl_tessellator_partitioning" => {
+                let partitioning = match arg(0)? {
+                    "partitioning_integer" => D3D11_SB_TESSELLATOR_PARTITIONING_INTEGER,
+                    "partitioning_pow2" => D3D11_SB_TESSELLATOR_PARTITIONING_POW2,
+                    "partitioning_fractional_odd" => D3D11_SB_TESSELLATOR_PARTITIONING_FRACTIONAL_ODD,
+                    "partitioning_fractional_even" => D3D11_SB_TESSELLATOR_PARTITIONING_FRACTIONAL_EVEN,
+                    "partitioning_undefined" => D3D11_SB_TESSELLATOR_PARTITIONING_UNDEFINED,
+                    partitioning => return Err(format!("unknown tessellator partitioning `{}`", partitioning)),
+                };
+                self.emit(D3D11_SB_OPCODE_DCL_TESS_PARTITIONING, ENCODE_D3D11_SB_TESS_PARTITIONING(partitioning), Vec::new(), Vec::new());
+            }
+            "dcl_tessellator_output_primitive" => {
+                let primitive = match arg(0)? {
+                    "output_point" => D3D11_SB_TESSELLATOR_OUTPUT_POINT,
+                    "output_line" => D3D11_SB_TESSELLATOR_OUTPUT_LINE,
+                    "output_triangle_cw" => D3D11_SB_TESSELLATOR_OUTPUT_TRIANGLE_CW,
+                    "output_triangle_ccw" => D3D11_SB_TESSELLATOR_OUTPUT_TRIANGLE_CCW,
+                    "output_undefined" => D3D11_SB_TESSELLATOR_OUTPUT_UNDEFINED,
+                    primitive => return Err(format!("unknown tessellator output primitive `{}`", primitive)),
+                };
+                self.emit(D3D11_SB_OPCODE_DCL_TESS_OUTPUT_PRIMITIVE, ENCODE_D3D11_SB_TESS_OUTPUT_PRIMITIVE(primitive), Vec::new(), Vec::new());
+            }
+            "dcl_hs_max_tessfactor" => {
+                let values = literal_values(arg(0)?)?;
+                if values.len() != 1 {
+                    return Err(format!("expected a single tessellation factor, found `{}`", arguments));
+                }
+                self.emit(D3D11_SB_OPCODE_DCL_HS_MAX_TESSFACTOR, 0, Vec::new(), values);
+            }
+            "dcl_thread_group" => {
+                self.emit(
+                    D3D11_SB_OPCODE_DCL_THREAD_GROUP,
+                    0,
+                    Vec::new(),
+                    vec![parse_u32(arg(0)?)?, parse_u32(arg(1)?)?, parse_u32(arg(2)?)?],
+                );
+            }
+            "dcl_function_body" => {
+                let body = parse_u32(prefixed(arg(0)?, "fb")?)?;
+                self.emit(D3D11_SB_OPCODE_DCL_FUNCTION_BODY, 0, Vec::new(), vec![body]);
+            }
+            "dcl_function_table" => {
+                let (table, bodies) = split_list(arguments)?;
+                let bodies = bodies.iter().map(|body| prefixed(body, "fb").and_then(parse_u32)).collect::<Result<Vec<_>>>()?;
+
+                let mut data = vec![parse_u32(prefixed(table, "ft")?)?, bodies.len() as u32];
+                data.extend(bodies);
+                self.emit(D3D11_SB_OPCODE_DCL_FUNCTION_TABLE, 0, Vec::new(), data);
+            }
+            "dcl_interface" | "dcl_interface_dynamicindexed" => {
+                let (interface, tables) = split_list(arguments)?;
+                let tables = tables.iter().map(|table| prefixed(table, "ft").and_then(parse_u32)).collect::<Result<Vec<_>>>()?;
+
+                let (ty, indices, _) = parse_register(interface)?;
+                let (index, array_length, table_length) = match (ty, &*indices) {
+                    (D3D11_SB_OPERAND_TYPE_INTERFACE, &[Immediate::U32(index), Immediate::U32(array), Immediate::U32(table)]) => (index, array, table),
+                    _ => return Err(format!("expected an interface like `fp0[1][2]`, found `{}`", interface)),
+                };
+
+                let mut data = vec![
+                    index,
+                    table_length,
+                    ENCODE_D3D11_SB_INTERFACE_ARRAY_LENGTH(array_length) | ENCODE_D3D11_SB_INTERFACE_TABLE_LENGTH(tables.len() as u32),
+                ];
+                data.extend(tables);
+
+                let indexed = (mnemonic == "dcl_interface_dynamicindexed") as u32;
+                self.emit(D3D11_SB_OPCODE_DCL_INTERFACE, ENCODE_D3D11_SB_INTERFACE_INDEXED_BIT(indexed), Vec::new(), data);
+            }
+            _ => return Err(format!("unknown declaration `{}`", mnemonic)),
+        }
+
+        Ok(())
+    }
+
+    fn shader_message(&mut self, (id, format): (u32, u32), arguments: &str) -> Result<()> {
+        let (string, rest) = parse_string(arguments)?;
+        let rest = rest.trim();
+
+        let mut operands = Vec::new();
+        if !rest.is_empty() {
+            if !rest.starts_with(',') {
+                return Err(format!("expected `,` after the message, found `{}`", rest));
+            }
+
+            for operand in split_operands(&rest[1..]) {
+                operands.push(parse_operand(&operand, Role::Source)?);
+            }
+        }
+
+        let operand_words: Vec<u32> = operands.iter().flat_map(|operand| operand.dwords()).collect();
+        let mut data = vec![id, format, string.len() as u32, operands.len() as u32, operand_words.len() as u32];
+        data.extend(operand_words);
+
+        // nul terminated and padded to whole tokens
+        for chunk in string.chunks(4) {
+            data.push(chunk.iter().rev().fold(0, |word, &c| (word << 8) | c as u32));
+        }
+        if string.len() % 4 == 0 {
+            data.push(0);
+        }
+
+        self.shex.add_instruction(Instruction::CustomData { class: D3D11_SB_CUSTOMDATA_SHADER_MESSAGE, data });
+        Ok(())
+    }
+
+    // The buffer spans several lines, `text` collects them until the braces
+    // are balanced.
+    fn immediate_constant_buffer(&mut self, text: String) -> Result<()> {
+        let depth = text.chars().fold(0i32, |depth, c| match c {
+            '{' => depth + 1,
+            '}' => depth - 1,
+            _ => depth,
+        });
+
+        if depth > 0 {
+            self.icb = Some(text);
+            return Ok(());
+        }
+
+        let body = text.trim();
+        if !body.starts_with('{') || !body.ends_with('}') {
+            return Err("malformed immediate constant buffer".to_owned());
+        }
+
+        let mut data = Vec::new();
+        for row in body[1..body.len() - 1].split('}') {
+            let row = row.trim().trim_start_matches(',').trim().trim_start_matches('{');
+            for value in row.split(',').map(str::trim).filter(|value| !value.is_empty()) {
+                data.push(parse_literal(value)?);
+            }
+        }
+
+        self.shex.add_instruction(Instruction::CustomData { class: D3D10_SB_CUSTOMDATA_DCL_IMMEDIATE_CONSTANT_BUFFER, data });
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<DxbcModule> {
+        let (program_type, major, minor) = self.version.ok_or("missing shader version")?;
+
+        if self.buffer.is_some() || !self.structs.is_empty() {
+            return Err("unterminated buffer definition".to_owned());
+        }
+
+        if self.icb.is_some() {
+            return Err("unterminated immediate constant buffer".to_owned());
+        }
+
+        for &(names, flag) in &[(&self.user_packed, dr::ShaderInputFlags::USER_PACKED), (&self.unused, dr::ShaderInputFlags::UNUSED)] {
+            for name in names {
+                match self.bindings.iter_mut().find(|binding| binding.name == *name) {
+                    Some(binding) => binding.input_flags |= flag.bits(),
+                    None => return Err(format!("unknown resource binding `{}`", name)),
+                }
+            }
+        }
+
+        for binding in &mut self.bindings {
+            match binding.input_type {
+                2 | 4 => {
+                    binding.sample_count = match binding.view_dimension {
+                        6 | 7 => *self.samples.get(&(D3D10_SB_OPERAND_TYPE_RESOURCE, binding.bind_point)).unwrap_or(&0),
+                        _ => !0,
+                    };
+                }
+                3 if self.comparison_samplers.contains(&binding.bind_point) => {
+                    binding.input_flags |= dr::ShaderInputFlags::COMPARISON_SAMPLER.bits();
+                }
+                5 => binding.sample_count = *self.samples.get(&(D3D10_SB_OPERAND_TYPE_RESOURCE, binding.bind_point)).unwrap_or(&0),
+                6 => {
+                    binding.sample_count = *self.samples.get(&(D3D11_SB_OPERAND_TYPE_UNORDERED_ACCESS_VIEW, binding.bind_point)).unwrap_or(&0)
+                }
+                _ => {}
+            }
+        }
+
+        let shader_ty = match program_type {
+            D3D10_SB_PIXEL_SHADER => 0xffff,
+            D3D10_SB_VERTEX_SHADER => 0xfffe,
+            D3D10_SB_GEOMETRY_SHADER => 0x4753,
+            D3D11_SB_HULL_SHADER => 0x4853,
+            D3D11_SB_DOMAIN_SHADER => 0x4453,
+            _ => 0x4353,
+        };
+
+        let rdef = dr::RdefChunk {
+            constant_buffers: self.constant_buffers,
+            resource_bindings: self.bindings,
+            shader_ty,
+            minor,
+            major,
+            flags: self.flags,
+            author: self.author,
+            rd11: if major >= 5 { Some(self.rd11.unwrap_or(dr::RD11_HEADER)) } else { None },
+        };
+
+        let mut builder = Builder::new();
+        builder.set_rdef(rdef);
+        builder.set_isgn(dr::IOsgnChunk { elements: self.inputs });
+        if self.streams {
+            builder.set_osg5(dr::IOsgnChunk { elements: self.outputs });
+        } else {
+            builder.set_osgn(dr::IOsgnChunk { elements: self.outputs });
+        }
+        if let Some(mut elements) = self.patch_constants {
+            if program_type == D3D11_SB_HULL_SHADER {
+                for element in &mut elements {
+                    element.rw_mask = 0xf & !element.rw_mask;
+                }
+            }
+            builder.set_pcsg(dr::IOsgnChunk { elements });
+        }
+        builder.set_shex(self.shex);
+        for (fourcc, data) in self.chunks {
+            builder.add_chunk(fourcc, data);
+        }
+
+        builder.module().map_err(|err| format!("cannot build the container: {}", err))
+    }
+}
+
+fn parse_u32(text: &str) -> Result<u32> {
+    text.trim().parse().map_err(|_| format!("expected a number, found `{}`", text))
+}
+
+// Reverse lookup of one of the disassembler's name tables.
+fn find_name<F, S>(range: ::std::ops::Range<u32>, name: &str, names: F) -> Option<u32>
+where
+    F: Fn(u32) -> S,
+    S: AsRef<str>,
+{
+    range.into_iter().find(|&value| names(value).as_ref() == name)
+}
+
+// Number following `key` in the `// Offset: 0 Size: 16` comments.
+fn field(info: &str, key: &str) -> Option<u32> {
+    let pos = info.find(key)?;
+    let value: String = info[pos + key.len()..].trim_start().chars().take_while(|c| c.is_ascii_digit()).collect();
+    value.parse().ok()
+}
+
+fn positional_mask(field: &str) -> u8 {
+    let field = &field[field.len().saturating_sub(4)..];
+    field
+        .bytes()
+        .enumerate()
+        .filter(|&(_, c)| c != b' ')
+        .fold(0, |mask, (idx, _)| mask | (1 << idx))
+}
+
+fn split_array(text: &str) -> Result<(&str, u16)> {
+    match text.find('[') {
+        Some(pos) if text.ends_with(']') => {
+            let count = parse_u32(&text[pos + 1..text.len() - 1])?;
+            Ok((&text[..pos], count as u16))
+        }
+        _ => Ok((text, 0)),
+    }
+}
+
+fn variable_type(text: &str, count: u16) -> Result<dr::ShaderType<'_>> {
+    use dr::ShaderVariableClass as Class;
+    use dr::ShaderVariableType as Type;
+
+    let (matrix_class, name) = if let Some(name) = text.strip_prefix("row_major ") {
+        (Class::MatrixRows, name.trim())
+    } else {
+        (Class::MatrixColumns, text)
+    };
+
+    let bases = [
+        ("uint8", Type::UInt8),
+        ("uint", Type::UInt),
+        ("int", Type::Int),
+        ("float", Type::Float),
+        ("double", Type::Double),
+        ("bool", Type::Bool),
+    ];
+
+    let unsupported = || format!("unsupported variable type `{}`", text);
+    let &(base, ty) = bases.iter().find(|&&(base, _)| name.starts_with(base)).ok_or_else(unsupported)?;
+
+    let dimensions = &name[base.len()..];
+    let (class, rows, columns) = if dimensions.is_empty() {
+        (Class::Scalar, 1, 1)
+    } else if let Some(pos) = dimensions.find('x') {
+        (matrix_class, parse_u32(&dimensions[..pos])?, parse_u32(&dimensions[pos + 1..])?)
+    } else {
+        (Class::Vector, 1, parse_u32(dimensions).map_err(|_| unsupported())?)
+    };
+
+    Ok(dr::ShaderType {
+        class,
+        ty,
+        rows: rows as u16,
+        columns: columns as u16,
+        count,
+        members: Vec::new(),
+        name: Some(name),
+    })
+}
+
+fn struct_type<'a>(name: Option<&'a str>, members: Vec<dr::ShaderTypeMember<'a>>, count: u16) -> dr::ShaderType<'a> {
+    // structs report the number of components of all their members
+    let columns = members
+        .iter()
+        .map(|member| member.ty.rows * member.ty.columns * member.ty.count.max(1))
+        .sum();
+
+    dr::ShaderType {
+        class: dr::ShaderVariableClass::Struct,
+        ty: dr::ShaderVariableType::Void,
+        rows: 1,
+        columns,
+        count,
+        members,
+        name,
+    }
+}
+
+fn system_value(name: &str) -> Result<u32> {
+    find_name(0..D3D12_SB_NAME_CULLPRIMITIVE + 1, name, disasm::system_value_name)
+        .map(ENCODE_D3D10_SB_NAME)
+        .ok_or_else(|| format!("unknown system value `{}`", name))
+}
+
+fn prefixed<'a>(text: &'a str, prefix: &str) -> Result<&'a str> {
+    text.strip_prefix(prefix).ok_or_else(|| format!("expected `{}` register, found `{}`", prefix, text))
+}
+
+// `ft0 = {fb0, fb1}`
+fn split_list(text: &str) -> Result<(&str, Vec<&str>)> {
+    let pos = text.find('=').ok_or_else(|| format!("missing `=` in `{}`", text))?;
+    let list = text[pos + 1..].trim();
+    if !list.starts_with('{') || !list.ends_with('}') {
+        return Err(format!("expected a list in braces, found `{}`", list));
+    }
+
+    let items = list[1..list.len() - 1].split(',').map(str::trim).filter(|item| !item.is_empty()).collect();
+    Ok((text[..pos].trim(), items))
+}
+
+fn resource_dimension(text: &str) -> Result<(u32, u32)> {
+    let (name, samples) = match text.find('(') {
+        Some(pos) if text.ends_with(')') => (&text[..pos], parse_u32(&text[pos + 1..text.len() - 1])?),
+        _ => (text, 0),
+    };
+
+    let dimension = find_name(0..13, name, disasm::resource_dimension_name)
+        .ok_or_else(|| format!("unknown resource dimension `{}`", name))?;
+
+    Ok((dimension, samples))
+}
+
+// `(float,float,float,float) t0` into the return type token and the register.
+fn split_return_type(text: &str) -> Result<(u32, &str)> {
+    let end = match text.find(')') {
+        Some(end) if text.starts_with('(') => end,
+        _ => return Err(format!("missing return type in `{}`", text)),
+    };
+
+    let mut token = 0;
+    let types: Vec<&str> = text[1..end].split(',').map(str::trim).collect();
+    if types.len() != 4 {
+        return Err(format!("expected four return types, found `{}`", &text[..end + 1]));
+    }
+
+    for (component, name) in types.iter().enumerate() {
+        let ty = find_name(1..10, name, disasm::return_type_name).ok_or_else(|| format!("unknown return type `{}`", name))?;
+        token |= ENCODE_D3D10_SB_RESOURCE_RETURN_TYPE(ty, component as u32);
+    }
+
+    Ok((token, text[end + 1..].trim()))
+}
+
+fn uav_flags(mnemonic: &str) -> (&str, u32) {
+    let mut name = mnemonic;
+    let mut flags = 0;
+
+    if name.ends_with("_opc") {
+        name = &name[..name.len() - 4];
+        flags |= ENCODE_D3D11_SB_UAV_FLAGS(D3D11_SB_UAV_HAS_ORDER_PRESERVING_COUNTER);
+    }
+
+    if name.ends_with("_glc") {
+        name = &name[..name.len() - 4];
+        flags |= ENCODE_D3D11_SB_ACCESS_COHERENCY_FLAGS(D3D11_SB_GLOBALLY_COHERENT_ACCESS);
+    }
+
+    (name, flags)
+}
+
+fn register_index(text: &str) -> Result<u32> {
+    match parse_register(text)?.1.first() {
+        Some(&Immediate::U32(index)) => Ok(index),
+        _ => Err(format!("expected a register index in `{}`", text)),
+    }
+}
+
+// Strips a trailing `//` comment, ignoring slashes in message strings.
+fn strip_comment(text: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    let bytes = text.as_bytes();
+
+    for (pos, &c) in bytes.iter().enumerate() {
+        match c {
+            _ if escaped => escaped = false,
+            b'\\' if quoted => escaped = true,
+            b'"' => quoted = !quoted,
+            b'/' if !quoted && bytes.get(pos + 1) == Some(&b'/') => return text[..pos].trim(),
+            _ => {}
+        }
+    }
+
+    text.trim()
+}
+
+fn shader_message(mnemonic: &str) -> Option<(u32, u32)> {
+    match mnemonic {
+        "message" => Some((D3D11_SB_SHADER_MESSAGE_ID_MESSAGE, D3D11_SB_SHADER_MESSAGE_FORMAT_ANSI_TEXT)),
+        "printf" => Some((D3D11_SB_SHADER_MESSAGE_ID_MESSAGE, D3D11_SB_SHADER_MESSAGE_FORMAT_ANSI_PRINTF)),
+        "error" => Some((D3D11_SB_SHADER_MESSAGE_ID_ERROR, D3D11_SB_SHADER_MESSAGE_FORMAT_ANSI_TEXT)),
+        "errorf" => Some((D3D11_SB_SHADER_MESSAGE_ID_ERROR, D3D11_SB_SHADER_MESSAGE_FORMAT_ANSI_PRINTF)),
+        _ => None,
+    }
+}
+
+// Reads a quoted string with the escapes the disassembler prints, returns
+// its bytes and the text after the closing quote.
+fn parse_string(text: &str) -> Result<(Vec<u8>, &str)> {
+    if !text.starts_with('"') {
+        return Err(format!("expected a quoted string, found `{}`", text));
+    }
+
+    let mut string = Vec::new();
+    let mut bytes = text.bytes().enumerate().skip(1);
+
+    while let Some((pos, c)) = bytes.next() {
+        match c {
+            b'"' => return Ok((string, &text[pos + 1..])),
+            b'\\' => {
+                let escape = bytes.next().map(|(_, c)| c);
+                string.push(match escape {
+                    Some(b'n') => b'\n',
+                    Some(b't') => b'\t',
+                    Some(b'x') => {
+                        let digits = text.get(pos + 2..pos + 4).ok_or("truncated `\\x` escape")?;
+                        bytes.next();
+                        bytes.next();
+                        u8::from_str_radix(digits, 16).map_err(|_| format!("malformed escape `\\x{}`", digits))?
+                    }
+                    Some(c) => c,
+                    None => break,
+                });
+            }
+            _ => string.push(c),
+        }
+    }
+
+    Err(format!("unterminated string `{}`", text))
+}
+
+// Splits off the mnemonic, parenthesized groups like `(structured_buffer,
+// stride=16)` belong to it even though they may contain spaces.
+fn split_mnemonic(text: &str) -> (&str, &str) {
+    let mut depth = 0;
+
+    for (pos, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if c.is_whitespace() && depth == 0 => return (&text[..pos], text[pos..].trim()),
+            _ => {}
+        }
+    }
+
+    (text, "")
+}
+
+// `resinfo_indexable(texture2d)(float,float,float,float)_uint` into
+// `resinfo_indexable_uint` and the contents of the groups.
+fn split_groups(mnemonic: &str) -> Result<(String, Vec<&str>)> {
+    let mut name = String::new();
+    let mut groups = Vec::new();
+    let mut rest = mnemonic;
+
+    let unbalanced = || format!("unbalanced parentheses in `{}`", mnemonic);
+
+    while let Some(start) = rest.find('(') {
+        let end = start + 1 + rest[start + 1..].find(')').ok_or_else(unbalanced)?;
+        let group = &rest[start + 1..end];
+        if rest[..start].contains(')') || group.contains('(') {
+            return Err(unbalanced());
+        }
+        name.push_str(&rest[..start]);
+        groups.push(group);
+        rest = &rest[end + 1..];
+    }
+
+    if rest.contains(')') {
+        return Err(unbalanced());
+    }
+    name.push_str(rest);
+    Ok((name, groups))
+}
+
+// Finds the longest opcode mnemonic `name` starts with whose remainder only
+// consists of the suffixes fxc appends.
+fn find_opcode(name: &str) -> Option<(u32, Vec<&str>)> {
+    const SUFFIXES: [&str; 11] = ["z", "nz", "sat", "aoffimmi", "indexable", "uint", "rcpFloat", "uglobal", "ugroup", "g", "t"];
+
+    (0..256)
+        .filter_map(|opcode| disasm::opcode_name(opcode).map(|mnemonic| (opcode, mnemonic)))
+        .filter(|&(_, mnemonic)| name.starts_with(mnemonic))
+        .filter_map(|(opcode, mnemonic)| {
+            let rest = &name[mnemonic.len()..];
+            if rest.is_empty() {
+                return Some((opcode, mnemonic.len(), Vec::new()));
+            }
+
+            if !rest.starts_with('_') {
+                return None;
+            }
+
+            let suffixes: Vec<&str> = rest[1..].split('_').collect();
+            if suffixes.iter().all(|suffix| SUFFIXES.contains(suffix)) {
+                Some((opcode, mnemonic.len(), suffixes))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|&(_, len, _)| len)
+        .map(|(opcode, _, suffixes)| (opcode, suffixes))
+}
+
+fn extended_opcode(group: &str) -> Result<OpcodeEx> {
+    let parts: Vec<&str> = group.split(',').map(str::trim).collect();
+
+    if parts.len() == 2 && parts[1].starts_with("stride=") {
+        let stride = parse_u32(&parts[1]["stride=".len()..])?;
+        return Ok(OpcodeEx::Dimension(ResourceDimension::StructuredBuffer, stride));
+    }
+
+    if parts.len() == 1 {
+        let (dimension, _) = resource_dimension(parts[0])?;
+        let dimension = ResourceDimension::from_word(dimension).ok_or_else(|| format!("unknown resource dimension `{}`", group))?;
+        return Ok(OpcodeEx::Dimension(dimension, 0));
+    }
+
+    if parts.len() == 3 {
+        let offsets = parts
+            .iter()
+            .map(|offset| offset.parse::<i32>().map(|offset| offset as u32).map_err(|_| format!("malformed offset `{}`", offset)))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(OpcodeEx::UvOffset(offsets[0], offsets[1], offsets[2]));
+    }
+
+    if parts.len() == 4 {
+        let types = parts
+            .iter()
+            .map(|name| find_name(1..10, name, disasm::return_type_name).and_then(ResourceReturnType::from_word))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("unknown return types `{}`", group))?;
+        return Ok(OpcodeEx::ResourceReturnType(types[0], types[1], types[2], types[3]));
+    }
+
+    Err(format!("unknown instruction modifier `({})`", group))
+}
+
+fn destination_count(opcode: u32) -> usize {
+    match opcode {
+        D3D10_SB_OPCODE_IF | D3D10_SB_OPCODE_BREAKC | D3D10_SB_OPCODE_CONTINUEC | D3D10_SB_OPCODE_RETC |
+        D3D10_SB_OPCODE_DISCARD | D3D10_SB_OPCODE_CALL | D3D10_SB_OPCODE_CALLC | D3D10_SB_OPCODE_CASE |
+        D3D10_SB_OPCODE_SWITCH | D3D10_SB_OPCODE_LABEL | D3D11_SB_OPCODE_EMIT_STREAM | D3D11_SB_OPCODE_CUT_STREAM |
+        D3D11_SB_OPCODE_EMITTHENCUT_STREAM | D3D11_SB_OPCODE_INTERFACE_CALL => 0,
+
+        D3D10_SB_OPCODE_IMUL | D3D10_SB_OPCODE_UMUL | D3D10_SB_OPCODE_UDIV | D3D11_SB_OPCODE_UADDC |
+        D3D11_SB_OPCODE_USUBB | D3D10_SB_OPCODE_SINCOS | D3D11_SB_OPCODE_SWAPC => 2,
+        D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC..=D3D11_SB_OPCODE_IMM_ATOMIC_UMIN => {
+            match opcode {
+                D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC | D3D11_SB_OPCODE_IMM_ATOMIC_CONSUME => 1,
+                _ => 2,
+            }
+        }
+
+        _ => 1,
+    }
+}
+
+// Operands separated by top level commas, literals and indices contain
+// commas of their own.
+fn split_operands(text: &str) -> Vec<String> {
+    let mut operands = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+
+    for c in text.chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                operands.push(current.trim().to_owned());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+
+    if !current.trim().is_empty() {
+        operands.push(current.trim().to_owned());
+    }
+
+    operands
+}
+
+fn parse_literal(text: &str) -> Result<u32> {
+    let (sign, body) = match text.strip_prefix('-') {
+        Some(body) => (0x80000000, body),
+        None => (0, text),
+    };
+
+    match body {
+        "1.#QNAN0" => return Ok(sign | 0x7fc00000),
+        "1.#INF00" => return Ok(sign | 0x7f800000),
+        _ => {}
+    }
+
+    if let Some(hex) = body.strip_prefix("0x") {
+        return u32::from_str_radix(hex, 16).map_err(|_| format!("malformed literal `{}`", text));
+    }
+
+    if body.contains('.') || body.contains('e') {
+        return text.parse::<f32>().map(f32::to_bits).map_err(|_| format!("malformed literal `{}`", text));
+    }
+
+    text.parse::<i64>().map(|value| value as u32).map_err(|_| format!("malformed literal `{}`", text))
+}
+
+fn parse_double(text: &str) -> Result<u64> {
+    let (sign, body) = match text.strip_prefix('-') {
+        Some(body) => (1 << 63, body),
+        None => (0, text),
+    };
+
+    match body {
+        "1.#QNAN0" => Ok(sign | 0x7ff8000000000000),
+        "1.#INF00" => Ok(sign | 0x7ff0000000000000),
+        _ if body.starts_with("0x") => u64::from_str_radix(&body[2..], 16).map_err(|_| format!("malformed literal `{}`", text)),
+        _ => text.parse::<f64>().map(f64::to_bits).map_err(|_| format!("malformed literal `{}`", text)),
+    }
+}
+
+// Values of `l(1.000000, 2)`.
+fn literal_values(text: &str) -> Result<Vec<u32>> {
+    if !text.starts_with("l(") || !text.ends_with(')') {
+        return Err(format!("expected a literal, found `{}`", text));
+    }
+
+    text[2..text.len() - 1].split(',').map(|value| parse_literal(value.trim())).collect()
+}
+
+fn parse_operand(text: &str, role: Role) -> Result<Operand> {
+    let mut text = text.trim();
+    let mut precision = MinPrecision::Default;
+
+    if let Some(pos) = text.find(" {") {
+        precision = match &text[pos + 1..] {
+            "{min16f}" => MinPrecision::Float16,
+            "{min2_8f}" => MinPrecision::Float2_8,
+            "{min16i}" => MinPrecision::Sint16,
+            "{min16u}" => MinPrecision::Uint16,
+            other => return Err(format!("unknown precision `{}`", other)),
+        };
+        text = &text[..pos];
+    }
+
+    let neg = text.starts_with('-');
+    if neg {
+        text = &text[1..];
+    }
+
+    let abs = text.len() > 1 && text.starts_with('|') && text.ends_with('|');
+    if abs {
+        text = &text[1..text.len() - 1];
+    }
+
+    let modifier = match (neg, abs) {
+        (false, false) => Modifier::None,
+        (true, false) => Modifier::Neg,
+        (false, true) => Modifier::Abs,
+        (true, true) => Modifier::AbsNeg,
+    };
+
+    let mut operand = if text.starts_with("l(") {
+        let values = literal_values(text)?;
+        match values.len() {
+            1 => Operand::new(OperandType::Imm32(values[0]), modifier, NumComponent::D1),
+            4 => Operand::new(OperandType::Imm32x4(values[0], values[1], values[2], values[3]), modifier, NumComponent::D1),
+            _ => return Err(format!("literals need one or four values, found `{}`", text)),
+        }
+    } else if text.starts_with("d(") && text.ends_with(')') {
+        let values = text[2..text.len() - 1]
+            .split(',')
+            .map(|value| parse_double(value.trim()))
+            .collect::<Result<Vec<_>>>()?;
+        Operand::new(OperandType::Imm64(values), modifier, NumComponent::D1)
+    } else {
+        let (ty, indices, components) = parse_register(text)?;
+        let components = component_selection(ty, components, role)?;
+        Operand::new(OperandType::Raw(ty, indices), modifier, components)
+    };
+
+    operand.set_min_precision(precision);
+    Ok(operand)
+}
+
+// Splits a register into its operand type, indices and component suffix.
+fn parse_register(text: &str) -> Result<(u32, Vec<Immediate>, Option<&str>)> {
+    let boundary = |rest: &str| rest.chars().next().is_none_or(|c| c.is_ascii_digit() || c == '[' || c == '.');
+
+    let (ty, prefix) = (0..64)
+        .map(|ty| (ty, disasm::operand_prefix(ty)))
+        .filter(|&(_, prefix)| prefix != "?" && text.starts_with(prefix) && boundary(&text[prefix.len()..]))
+        .max_by_key(|&(_, prefix)| prefix.len())
+        .ok_or_else(|| format!("unknown register `{}`", text))?;
+
+    let mut rest = &text[prefix.len()..];
+    let mut indices = Vec::new();
+
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits > 0 {
+        indices.push(Immediate::U32(parse_u32(&rest[..digits])?));
+        rest = &rest[digits..];
+    }
+
+    while rest.starts_with('[') {
+        let mut depth = 0;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '[' => depth += 1,
+                    ']' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })
+            .map(|(end, _)| end)
+            .ok_or_else(|| format!("unbalanced brackets in `{}`", text))?;
+
+        indices.push(parse_index(&rest[1..end])?);
+        rest = &rest[end + 1..];
+    }
+
+    if indices.len() > 3 {
+        return Err(format!("too many indices in `{}`", text));
+    }
+
+    match rest {
+        "" => Ok((ty, indices, None)),
+        _ if rest.starts_with('.') => Ok((ty, indices, Some(&rest[1..]))),
+        _ => Err(format!("unexpected `{}` in `{}`", rest, text)),
+    }
+}
+
+// `2`, `r1.x + 2` or `vOutputControlPointID`
+fn parse_index(text: &str) -> Result<Immediate> {
+    match text.rfind(" + ") {
+        Some(pos) => {
+            let register = parse_operand(&text[..pos], Role::Source)?;
+            match parse_u32(&text[pos + 3..])? {
+                0 => Ok(Immediate::Relative(register)),
+                offset => Ok(Immediate::U32Relative(offset, register)),
+            }
+        }
+        None if text.trim_start().starts_with(|c: char| c.is_ascii_digit()) => Ok(Immediate::U32(parse_u32(text)?)),
+        None => Ok(Immediate::Relative(parse_operand(text, Role::Source)?)),
+    }
+}
+
+fn component_selection(ty: u32, components: Option<&str>, role: Role) -> Result<NumComponent> {
+    let components = match components {
+        Some(components) => components,
+        None => {
+            return Ok(match ty {
+                D3D10_SB_OPERAND_TYPE_INPUT_PRIMITIVEID |
+                D3D10_SB_OPERAND_TYPE_OUTPUT_DEPTH |
+                D3D10_SB_OPERAND_TYPE_OUTPUT_COVERAGE_MASK |
+                D3D11_SB_OPERAND_TYPE_OUTPUT_DEPTH_GREATER_EQUAL |
+                D3D11_SB_OPERAND_TYPE_OUTPUT_DEPTH_LESS_EQUAL |
+                D3D11_SB_OPERAND_TYPE_INPUT_COVERAGE_MASK |
+                D3D11_SB_OPERAND_TYPE_INPUT_THREAD_ID_IN_GROUP_FLATTENED |
+                D3D11_SB_OPERAND_TYPE_INPUT_GS_INSTANCE_ID |
+                D3D11_SB_OPERAND_TYPE_INPUT_FORK_INSTANCE_ID |
+                D3D11_SB_OPERAND_TYPE_INPUT_JOIN_INSTANCE_ID |
+                D3D11_SB_OPERAND_TYPE_OUTPUT_CONTROL_POINT_ID |
+                D3D11_SB_OPERAND_TYPE_CYCLE_COUNTER => NumComponent::D1,
+                _ => NumComponent::D0,
+            });
+        }
+    };
+
+    let components = components
+        .chars()
+        .map(|c| match c {
+            'x' => Ok(X),
+            'y' => Ok(Y),
+            'z' => Ok(Z),
+            'w' => Ok(W),
+            _ => Err(format!("unknown component `{}`", c)),
+        })
+        .collect::<Result<Vec<u8>>>()?;
+
+    let mode = match (role, components.len()) {
+        (Role::Destination, 1..=4) => ComponentMode::Mask(components.iter().fold(0, |mask, &c| mask | c)),
+        (Role::Source, 1) => ComponentMode::Select(components[0]),
+        (Role::Source, 4) => ComponentMode::Swizzle(components[0], components[1], components[2], components[3]),
+        _ => return Err(format!("sources select one or swizzle four components, found `.{}`", components.len())),
+    };
+
+    Ok(NumComponent::D4(mode))
+}
@@ -0,0 +1,1902 @@
+use binary::*;
+use cfg::{self, Cfg};
+use dr;
+use dr::rdef::{ShaderInputFlags, ShaderType, ShaderVariableClass, ShaderVariableType};
+use dr::shex::{OperandType, ResourceDimension, ResourceReturnType, ShexHeader, SparseInstruction, TestBoolean};
+use dr::builder::{Modifier, OpcodeEx};
+use d3d11tokenizedprogramformat::*;
+use disasm::{opcode_name, COMPONENTS};
+use ir::{self, Components, Declaration, Index, Operand, Program, Register};
+use expr::{Expr, Expressions, Op, Statement};
+use ssa::{self, FunctionSsa, Ssa, Temp};
+use types::{Interface, Type, Types};
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Write};
+use std::ops::Range;
+use std::{error, ptr};
+
+/// Most registers of a file D3D allows, also the size of a constant buffer
+/// in registers.
+pub(crate) const MAX_REGISTERS: u32 = 4096;
+
+/// Constant buffer slots of a shader stage.
+pub(crate) const MAX_CONSTANT_BUFFERS: u32 = 14;
+
+/// Longest expression folded into another one, longer ones keep their temp
+/// so lines stay readable.
+const MAX_FOLDED: usize = 80;
+
+/// Why the GLSL, HLSL, MSL or WGSL backend can't translate a shader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    ControlFlow(cfg::Error),
+    /// Instruction the target language can't express, with what's missing
+    /// if it's only some of its forms.
+    Unsupported(usize, u32, &'static str),
+    /// Operand of an instruction that is missing or not a register, with
+    /// its index.
+    InvalidOperand(usize, u32, usize),
+    /// More registers of a file declared than D3D allows.
+    TooManyRegisters(OperandType, u32),
+    /// Part of the shader outside of its instructions the target can't
+    /// express, with the target that is missing it.
+    UnsupportedFeature(&'static str, &'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = |opcode: u32| opcode_name(opcode).unwrap_or("unknown");
+
+        match *self {
+            Error::ControlFlow(ref err) => write!(f, "{}", err),
+            Error::Unsupported(index, opcode, reason) => {
+                write!(f, "cannot translate `{}`{} at instruction {}", name(opcode), reason, index)
+            }
+            Error::InvalidOperand(index, opcode, operand) => {
+                write!(f, "operand {} of `{}` at instruction {} is invalid", operand, name(opcode), index)
+            }
+            Error::TooManyRegisters(file, count) => {
+                write!(f, "{} registers of {:?} declared, at most {} are allowed", count, file, MAX_REGISTERS)
+            }
+            Error::UnsupportedFeature(feature, reason) => write!(f, "cannot translate {}{}", feature, reason),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::ControlFlow(_) => "malformed control flow",
+            Error::Unsupported(..) => "unsupported instruction",
+            Error::InvalidOperand(..) => "invalid operand",
+            Error::TooManyRegisters(..) => "too many registers",
+            Error::UnsupportedFeature(..) => "unsupported feature",
+        }
+    }
+}
+
+impl From<cfg::Error> for Error {
+    fn from(err: cfg::Error) -> Self {
+        Error::ControlFlow(err)
+    }
+}
+
+pub(crate) fn swizzle(components: &[u8]) -> String {
+    components.iter().map(|&c| COMPONENTS[c as usize]).collect()
+}
+
+pub(crate) fn mask_components(mask: u8) -> Vec<u8> {
+    (0..4).filter(|c| mask & (1 << c) != 0).collect()
+}
+
+// Registers only ever hold 32 bits, booleans are stored as integers.
+pub(crate) fn storage(ty: Type) -> Type {
+    match ty {
+        Type::Bool => Type::Uint,
+        ty => ty,
+    }
+}
+
+// Type instructions that only move bits around operate in, from the type
+// of the value they write if temps are typed.
+fn bits_type(opcode: u32, written: Option<Type>) -> Type {
+    match opcode {
+        D3D10_SB_OPCODE_AND | D3D10_SB_OPCODE_OR | D3D10_SB_OPCODE_XOR | D3D10_SB_OPCODE_NOT if written == Some(Type::Int) => {
+            Type::Int
+        }
+        D3D10_SB_OPCODE_AND | D3D10_SB_OPCODE_OR | D3D10_SB_OPCODE_XOR | D3D10_SB_OPCODE_NOT => Type::Uint,
+        D3D10_SB_OPCODE_ISHL if written == Some(Type::Uint) => Type::Uint,
+        D3D10_SB_OPCODE_ISHL => Type::Int,
+        D3D11_SB_OPCODE_ATOMIC_AND..=D3D11_SB_OPCODE_IMM_ATOMIC_UMIN => Type::Uint,
+        D3D10_SB_OPCODE_MOV | D3D10_SB_OPCODE_MOVC | D3D11_SB_OPCODE_SWAPC => written.unwrap_or(Type::Float),
+        _ => Type::Float,
+    }
+}
+
+pub(crate) fn negate(value: &str) -> String {
+    if value.starts_with('-') {
+        format!("-({})", value)
+    } else {
+        format!("-{}", value)
+    }
+}
+
+// Expression that can be an operand without parentheses, names and
+// literals with calls, indices, swizzles or casts.
+pub(crate) fn atom(expr: &str) -> String {
+    let mut depth = 0i32;
+    let postfix = expr.chars().all(|c| {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            _ => {}
+        }
+        depth > 0 || c.is_ascii_alphanumeric() || "_.()[]".contains(c)
+    });
+
+    if postfix && depth == 0 && !expr.is_empty() {
+        expr.to_owned()
+    } else {
+        format!("({})", expr)
+    }
+}
+
+// Expression without the parentheses around all of it.
+fn unwrap(expr: String) -> String {
+    let mut depth = 0;
+    let outer = expr.starts_with('(') && expr.char_indices().all(|(n, c)| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        depth > 0 || n + 1 == expr.len()
+    });
+
+    if outer {
+        expr[1..expr.len() - 1].to_owned()
+    } else {
+        expr
+    }
+}
+
+pub(crate) fn identifier(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect()
+}
+
+// Metal and WebGPU have no normalized or double textures, those read as
+// floats.
+pub(crate) fn return_type(ty: u32) -> Type {
+    match ty {
+        x if x == ResourceReturnType::Sint as u32 => Type::Int,
+        x if x == ResourceReturnType::Uint as u32 => Type::Uint,
+        _ => Type::Float,
+    }
+}
+
+// Number of address and texel offset components of a resource.
+pub(crate) fn texture_coordinates(dimension: ResourceDimension) -> (usize, usize) {
+    match dimension {
+        ResourceDimension::Buffer => (1, 0),
+        ResourceDimension::Texture1D => (1, 1),
+        ResourceDimension::Texture1DArray => (2, 1),
+        ResourceDimension::Texture2D => (2, 2),
+        ResourceDimension::Texture2DMS => (2, 2),
+        ResourceDimension::Texture2DArray => (3, 2),
+        ResourceDimension::Texture2DMSArray => (3, 2),
+        ResourceDimension::Texture3D => (3, 3),
+        ResourceDimension::TextureCube => (3, 0),
+        ResourceDimension::TextureCubeArray => (4, 0),
+        _ => (1, 0),
+    }
+}
+
+// Metal and WGSL take the array slice as a separate argument, the last
+// address component of arrays.
+pub(crate) fn arrayed(dimension: ResourceDimension) -> bool {
+    matches!(
+        dimension,
+        ResourceDimension::Texture1DArray |
+            ResourceDimension::Texture2DArray |
+            ResourceDimension::Texture2DMSArray |
+            ResourceDimension::TextureCubeArray
+    )
+}
+
+pub(crate) fn label(operand: &Operand) -> u64 {
+    operand.register().and_then(|register| register.index.first()).map_or(0, Index::offset)
+}
+
+pub(crate) fn resource_index(register: &Register) -> u32 {
+    register.index.first().map_or(0, Index::offset) as u32
+}
+
+// Registers of `file` each instruction of `opcodes` accesses.
+pub(crate) fn used_resources(program: &Program, opcodes: &[u32], file: OperandType) -> HashSet<u32> {
+    program
+        .instructions
+        .iter()
+        .filter(|instruction| opcodes.contains(&instruction.opcode))
+        .flat_map(|instruction| instruction.operands.iter().filter_map(Operand::register))
+        .filter(|register| register.file == file)
+        .map(resource_index)
+        .collect()
+}
+
+// Interpolation mode of each pixel shader input register.
+pub(crate) fn interpolation(program: &Program) -> HashMap<u32, u32> {
+    program
+        .decls
+        .iter()
+        .filter_map(|declaration| match *declaration {
+            Declaration::InputPs { ref register, interpolation } |
+            Declaration::InputPsSgv { ref register, interpolation, .. } |
+            Declaration::InputPsSiv { ref register, interpolation, .. } => Some((resource_index(register), interpolation)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Registers of `file` that are declared or hold signature elements.
+pub(crate) fn declared_registers(program: &Program, file: OperandType, elements: &[Element]) -> BTreeSet<u32> {
+    let mut registers: BTreeSet<u32> = program
+        .decls
+        .iter()
+        .filter_map(Declaration::register)
+        .filter(|register| register.file == file && register.index.len() == 1)
+        .map(resource_index)
+        .collect();
+    registers.extend(elements.iter().map(|element| element.register).filter(|&register| register != !0));
+    registers
+}
+
+// Instructions of the entry point, and the label and instructions of each
+// subroutine. Subroutines follow the main program, each starting at its
+// label.
+pub(crate) fn functions(program: &Program) -> (Range<usize>, Vec<(u64, Range<usize>)>) {
+    let labels: Vec<usize> = program
+        .instructions
+        .iter()
+        .enumerate()
+        .filter(|&(_, instruction)| instruction.opcode == D3D10_SB_OPCODE_LABEL)
+        .map(|(i, _)| i)
+        .collect();
+    let end = labels.first().cloned().unwrap_or(program.instructions.len());
+
+    let subroutines = labels
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| {
+            let stop = labels.get(n + 1).cloned().unwrap_or(program.instructions.len());
+            let name = program.instructions[start].operands.first().map_or(0, label);
+            (name, start + 1..stop)
+        })
+        .collect();
+
+    (0..end, subroutines)
+}
+
+pub(crate) struct Element {
+    pub name: String,
+    pub semantic_index: u32,
+    pub semantic_type: dr::SemanticName,
+    pub ty: Type,
+    pub register: u32,
+    pub mask: u8,
+}
+
+impl Element {
+    fn from_element(element: &dr::InputOutputElement) -> Self {
+        let ty = match element.component_type {
+            dr::RegisterComponentType::Uint32 => Type::Uint,
+            dr::RegisterComponentType::Int32 => Type::Int,
+            _ => Type::Float,
+        };
+
+        Element {
+            name: element.name.clone(),
+            semantic_index: element.semantic_index,
+            semantic_type: element.semantic_type,
+            ty: match element.semantic_type {
+                dr::SemanticName::IsFrontFace => Type::Bool,
+                _ => ty,
+            },
+            register: element.register,
+            mask: element.component_mask & 0xf,
+        }
+    }
+
+    pub fn member(&self) -> String {
+        format!("{}{}", identifier(&self.name), self.semantic_index)
+    }
+
+    pub fn width(&self) -> usize {
+        match self.semantic_type {
+            dr::SemanticName::Position => 4,
+            _ => mask_components(self.mask).len(),
+        }
+    }
+
+    // Register holding the element, for those without one in the
+    // signature.
+    pub fn system_register(&self) -> Option<&'static str> {
+        match self.semantic_type {
+            dr::SemanticName::Depth | dr::SemanticName::DepthGreaterEqual | dr::SemanticName::DepthLessEqual => {
+                Some("oDepth")
+            }
+            dr::SemanticName::Coverage => Some("oMask"),
+            _ => None,
+        }
+    }
+}
+
+// A component of a constant buffer variable, `lane` is the vector
+// component of `base` if it isn't a scalar expression by itself.
+#[derive(Clone)]
+pub(crate) struct Constant {
+    pub base: String,
+    pub lane: Option<u8>,
+    pub ty: Type,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Layout {
+    Vector,
+    RowMajor,
+    ColumnMajor,
+}
+
+// Variable of a constant buffer that can be indexed with a register,
+// arrays and matrices. Metal and WGSL declare them as arrays of `registers`
+// vectors without a `count`.
+pub(crate) struct ConstantArray {
+    pub buffer: u32,
+    pub name: String,
+    pub start: u32,
+    pub registers: u32,
+    pub count: u32,
+    pub layout: Layout,
+    pub ty: Type,
+}
+
+pub(crate) struct ResourceBinding {
+    pub name: String,
+    pub input_type: u32,
+    pub bind_point: u32,
+    pub bind_count: u32,
+    pub flags: ShaderInputFlags,
+}
+
+impl ResourceBinding {
+    fn file(&self) -> OperandType {
+        match self.input_type {
+            0 => OperandType::ConstantBuffer,
+            3 => OperandType::Sampler,
+            4 | 6 | 8 | 9 | 10 | 11 => OperandType::UnorderedAccessView,
+            _ => OperandType::Resource,
+        }
+    }
+
+    // Components of the texels of a typed resource.
+    pub fn components(&self) -> usize {
+        (((self.flags & ShaderInputFlags::TEXTURE_COMPONENTS).bits() >> 2) + 1) as usize
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ResourceKind {
+    Typed,
+    Raw,
+    Structured,
+}
+
+pub(crate) struct Resource {
+    pub name: String,
+    pub dimension: ResourceDimension,
+    pub kind: ResourceKind,
+    pub ty: Type,
+    /// Stride of structured memory, in dwords.
+    pub stride: u32,
+    /// Whether the texture is declared as a depth texture for the
+    /// comparison instructions.
+    pub depth: bool,
+    /// Whether the memory is declared as atomics, which can't be accessed
+    /// any other way.
+    pub atomic: bool,
+}
+
+impl Resource {
+    pub fn new(name: String, dimension: ResourceDimension, kind: ResourceKind) -> Self {
+        Resource { name, dimension, kind, ty: Type::Float, stride: 1, depth: false, atomic: false }
+    }
+}
+
+pub(crate) fn type_size(ty: &ShaderType) -> u32 {
+    let element = match ty.class {
+        ShaderVariableClass::MatrixRows => 16 * (ty.rows as u32).saturating_sub(1) + 4 * ty.columns as u32,
+        ShaderVariableClass::MatrixColumns => 16 * (ty.columns as u32).saturating_sub(1) + 4 * ty.rows as u32,
+        ShaderVariableClass::Struct => ty
+            .members
+            .iter()
+            .map(|member| member.offset.saturating_add(type_size(&member.ty)))
+            .max()
+            .unwrap_or(0),
+        _ => 4 * ty.columns as u32,
+    };
+
+    if ty.count > 1 {
+        element.div_ceil(16).saturating_mul(16).saturating_mul(ty.count as u32 - 1).saturating_add(element)
+    } else {
+        element
+    }
+}
+
+pub(crate) fn variable_type(ty: ShaderVariableType) -> Option<Type> {
+    match ty {
+        ShaderVariableType::Float => Some(Type::Float),
+        ShaderVariableType::Int => Some(Type::Int),
+        ShaderVariableType::UInt | ShaderVariableType::UInt8 => Some(Type::Uint),
+        ShaderVariableType::Bool => Some(Type::Bool),
+        _ => None,
+    }
+}
+
+// HLSL spelling of a type, for the variables Metal and WGSL declare
+// differently.
+pub(crate) fn type_name(ty: &ShaderType) -> String {
+    let scalar = match variable_type(ty.ty) {
+        Some(Type::Int) => "int",
+        Some(Type::Uint) => "uint",
+        Some(Type::Bool) => "bool",
+        _ => "float",
+    };
+    match ty.class {
+        ShaderVariableClass::Struct => ty.name.map_or("struct".to_owned(), identifier),
+        ShaderVariableClass::Vector => format!("{}{}", scalar, ty.columns),
+        ShaderVariableClass::MatrixRows => format!("row_major {}{}x{}", scalar, ty.rows, ty.columns),
+        ShaderVariableClass::MatrixColumns => format!("{}{}x{}", scalar, ty.rows, ty.columns),
+        _ => scalar.to_owned(),
+    }
+}
+
+// Everything the shader declares outside of its code.
+#[derive(Default)]
+pub(crate) struct Shader {
+    pub program_type: u32,
+    pub inputs: Vec<Element>,
+    pub outputs: Vec<Element>,
+    pub structs: Vec<String>,
+    /// Slot, name and declaration of the constant buffers described in
+    /// RDEF.
+    pub constant_buffers: Vec<(u32, String, String)>,
+    pub constants: HashMap<(u32, u32), Constant>,
+    pub arrays: Vec<ConstantArray>,
+    pub bindings: Vec<ResourceBinding>,
+}
+
+impl Shader {
+    // Types the code sees the signature registers and constants as, inputs
+    // and outputs are always copied through float vectors.
+    fn interface(&self) -> Interface {
+        let mut interface = Interface::default();
+        let registers = |elements: &[Element]| {
+            elements
+                .iter()
+                .filter(|element| element.register != !0)
+                .flat_map(|element| mask_components(element.mask).into_iter().map(move |c| ((element.register, c), Type::Float)))
+                .collect()
+        };
+        interface.inputs = registers(&self.inputs);
+        interface.outputs = registers(&self.outputs);
+        interface.constants = self.constants.iter().map(|(&dword, constant)| (dword, storage(constant.ty))).collect();
+        interface
+    }
+
+    pub fn binding(&self, file: OperandType, index: u32) -> Option<&ResourceBinding> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.file() == file && binding.bind_point == index && binding.bind_count <= 1)
+    }
+
+    // Name of a resource from RDEF, or of its register.
+    pub fn resource_name(&self, file: OperandType, index: u32) -> String {
+        match self.binding(file, index) {
+            Some(binding) => identifier(&binding.name),
+            None => format!("{}{}", ::disasm::operand_prefix(file as u32), index),
+        }
+    }
+}
+
+fn signature(chunk: &dr::IOsgnChunk) -> Vec<Element> {
+    chunk
+        .elements
+        .iter()
+        .filter(|element| element.component_mask & 0xf != 0)
+        .map(Element::from_element)
+        .collect()
+}
+
+// What the consumers of the backends collect from the chunks before
+// writing the shader.
+#[derive(Default)]
+pub(crate) struct Chunks {
+    pub shader: Shader,
+    pub program: Option<Program>,
+}
+
+impl Chunks {
+    // Writes the program into `source`, write errors end the parse.
+    pub fn finalize<F>(&self, source: &mut String, write: F) -> Action
+    where
+        F: FnOnce(&Shader, &Program) -> Result<String, Error>,
+    {
+        if let Some(ref program) = self.program {
+            match write(&self.shader, program) {
+                Ok(text) => *source = text,
+                Err(err) => return Action::Error(Box::new(::binary::Error::Consumer(Box::new(err)))),
+            }
+        }
+
+        Action::Continue
+    }
+
+    pub fn consume_rdef<L: Language>(&mut self, rdef: &dr::RdefChunk) -> Action {
+        for binding in &rdef.resource_bindings {
+            self.shader.bindings.push(ResourceBinding {
+                name: binding.name.to_owned(),
+                input_type: binding.input_type,
+                bind_point: binding.bind_point,
+                bind_count: binding.bind_count,
+                flags: ShaderInputFlags::from_bits_truncate(binding.input_flags),
+            });
+
+            // a slot holds one buffer, and only the slots D3D11 has are
+            // worth laying out
+            let slot = binding.bind_point;
+            let bound = self.shader.constant_buffers.iter().any(|&(other, _, _)| other == slot);
+            if binding.input_type != 0 || slot >= MAX_CONSTANT_BUFFERS || bound {
+                continue;
+            }
+            if let Some(buffer) = rdef.constant_buffers.iter().find(|buffer| buffer.name == binding.name) {
+                L::add_constant_buffer(&mut self.shader, buffer, slot);
+            }
+        }
+
+        Action::Continue
+    }
+
+    pub fn consume_isgn(&mut self, isgn: &dr::IOsgnChunk) -> Action {
+        self.shader.inputs = signature(isgn);
+        Action::Continue
+    }
+
+    pub fn consume_osgn(&mut self, osgn: &dr::IOsgnChunk) -> Action {
+        self.shader.outputs = signature(osgn);
+        Action::Continue
+    }
+
+    pub fn consume_shex(&mut self, header: &ShexHeader) -> Action {
+        self.shader.program_type = header.program_type as u32;
+        self.program = Some(Program::new(header.program_type, header.major, header.minor));
+
+        Action::Continue
+    }
+
+    pub fn consume_instruction(&mut self, offset: u32, instruction: SparseInstruction) -> Action {
+        let program = match self.program {
+            Some(ref mut program) => program,
+            None => return Action::Continue,
+        };
+
+        match ir::lower(offset, &instruction) {
+            Ok(Some(statement)) => program.add_statement(statement),
+            Ok(None) => {}
+            Err(err) => return Action::Error(Box::new(err)),
+        }
+
+        Action::Continue
+    }
+}
+
+// Builds an expression from the sources of an instruction.
+pub(crate) type Expression<'f> = &'f dyn Fn(&[String]) -> String;
+
+#[derive(Clone)]
+pub(crate) struct Destination {
+    pub base: String,
+    /// Written components, empty for scalar registers.
+    pub components: Vec<u8>,
+    pub ty: Type,
+    /// Variable and type of each written component, when temps are split
+    /// by type.
+    pub parts: Vec<(String, Type)>,
+}
+
+impl Destination {
+    pub fn width(&self) -> usize {
+        self.components.len().max(1)
+    }
+
+    pub fn first(&self) -> u8 {
+        self.components.first().cloned().unwrap_or(0)
+    }
+
+    // Components the sources are read for, the first of a scalar.
+    pub fn written(&self) -> Vec<u8> {
+        if self.components.is_empty() {
+            vec![0]
+        } else {
+            self.components.clone()
+        }
+    }
+
+    // Parts of a temp destination written to the same variable.
+    pub fn split(&self) -> Vec<Destination> {
+        let mut parts: Vec<Destination> = Vec::new();
+        for (&component, &(ref base, ty)) in self.components.iter().zip(&self.parts) {
+            match parts.iter_mut().find(|part| part.base == *base) {
+                Some(part) => {
+                    part.components.push(component);
+                    part.parts.push((base.clone(), ty));
+                }
+                None => parts.push(Destination { base: base.clone(), components: vec![component], ty, parts: vec![(base.clone(), ty)] }),
+            }
+        }
+
+        if parts.is_empty() {
+            vec![self.clone()]
+        } else {
+            parts
+        }
+    }
+}
+
+/// Spelling of a target language, for the parts of the backends that work
+/// the same in all of them.
+pub(crate) trait Language: Sized {
+    /// Opens a loop that only ends with a `break`.
+    const LOOP: &'static str;
+    /// Ends the invocation of a pixel shader.
+    const DISCARD: &'static str;
+
+    fn vector(ty: Type, width: usize) -> String;
+
+    /// Reinterprets the bits of `expr`, booleans become all ones or zeros.
+    fn convert(expr: String, from: Type, to: Type, width: usize) -> String;
+
+    fn literal(bits: u32, ty: Type) -> String;
+
+    /// Register files that are single values or vectors without an index,
+    /// with their name, type, width and how they are passed in. Those
+    /// passed in no way can't be read.
+    fn system_value(file: OperandType) -> Option<(&'static str, Type, usize, &'static str)>;
+
+    fn add_constant_buffer(shader: &mut Shader, buffer: &dr::ConstantBuffer, slot: u32);
+
+    /// Declares an immutable local.
+    fn local(name: &str, ty: Type, width: usize, value: &str) -> String;
+
+    /// Writes what `ret` returns from the entry point or a subroutine.
+    fn ret(writer: &mut Writer<Self>) -> Result<(), Error>;
+
+    /// Writes any instruction besides the control flow.
+    fn operation(writer: &mut Writer<Self>, i: usize) -> Result<(), Error>;
+
+    /// Whether temps are kept in one variable for each type their values
+    /// have, instead of float vectors read and written through bitcasts.
+    const TYPED_TEMPS: bool = false;
+
+    /// Whether values read once are written into the expression of their
+    /// reader instead of a temp, only with `TYPED_TEMPS`.
+    const FOLD_EXPRESSIONS: bool = false;
+
+    /// `vector` times the matrix with `rows` of `width` floats, if the
+    /// language has matrices.
+    fn matrix_multiply(_vector: String, _rows: &[String], _width: usize) -> Option<String> {
+        None
+    }
+
+    /// Clamps a float value to `[0, 1]` for the `_sat` instructions.
+    fn saturate(value: String) -> String {
+        format!("saturate({})", value)
+    }
+
+    /// Vector of `width` copies of a scalar.
+    fn splat(ty: Type, width: usize, value: String) -> String {
+        format!("{}({})", Self::vector(ty, width), value)
+    }
+
+    fn modify(value: String, modifier: Modifier, _ty: Type) -> String {
+        match modifier {
+            Modifier::None => value,
+            Modifier::Neg => negate(&value),
+            Modifier::Abs => format!("abs({})", value),
+            Modifier::AbsNeg => format!("-abs({})", value),
+        }
+    }
+
+    /// Runs `statement` if `condition` holds.
+    fn guarded(condition: &str, statement: &str) -> String {
+        format!("if ({}) {}", condition, statement)
+    }
+
+    /// Variable holding a register, or a system value with `system`.
+    fn variable(_writer: &Writer<Self>, name: &str, _system: bool) -> String {
+        name.to_owned()
+    }
+
+    fn store(writer: &mut Writer<Self>, dst: &Destination, value: String) {
+        let text = if dst.components.is_empty() {
+            format!("{} = {};", dst.base, value)
+        } else {
+            format!("{}.{} = {};", dst.base, swizzle(&dst.components), value)
+        };
+        writer.line(&text);
+    }
+
+    /// Arguments of the calls to subroutines.
+    fn arguments(_writer: &Writer<Self>) -> String {
+        String::new()
+    }
+
+    /// Writes `switch`, `case`, `default` and `endswitch`, which fall
+    /// through like in C unless the language says otherwise.
+    fn switch(writer: &mut Writer<Self>, i: usize) -> Result<(), Error> {
+        match writer.program.instructions[i].opcode {
+            D3D10_SB_OPCODE_SWITCH => {
+                let text = format!("switch ({}) {{", writer.src(writer.operand(i, 0)?, &[0], Type::Int));
+                writer.line(&text);
+                writer.indent += 2;
+            }
+            D3D10_SB_OPCODE_CASE => {
+                let text = format!("case {}:", writer.src(writer.operand(i, 0)?, &[0], Type::Int));
+                writer.dedent(i, 1)?;
+                writer.line(&text);
+                writer.indent += 1;
+            }
+            D3D10_SB_OPCODE_DEFAULT => {
+                writer.dedent(i, 1)?;
+                writer.line("default:");
+                writer.indent += 1;
+            }
+            _ => {
+                writer.dedent(i, 2)?;
+                writer.line("}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Assignment of a folded instruction, written into the operand of its reader
+// or before it when the reader didn't take it.
+struct Deferred {
+    reader: usize,
+    operand: usize,
+    // instructions in the expression and the temps they read
+    instructions: Vec<usize>,
+    reads: HashSet<Temp>,
+    components: Vec<u8>,
+    ty: Type,
+    expr: String,
+    line: String,
+    used: Cell<bool>,
+    missed: Cell<bool>,
+}
+
+// Folded instructions the writers can substitute, those read by a single
+// temp operand of a componentwise instruction or a dot product for
+// components they write.
+fn readers(program: &Program, ssa: &FunctionSsa, folded: &BTreeMap<usize, (usize, Expr)>) -> HashMap<usize, (usize, usize)> {
+    let mut readers = HashMap::new();
+
+    for (&i, &(reader, _)) in folded {
+        let opcode = program.instructions[reader].opcode;
+        let lanes: Vec<u8> = match opcode {
+            D3D10_SB_OPCODE_DP2 => vec![0, 1],
+            D3D10_SB_OPCODE_DP3 => vec![0, 1, 2],
+            D3D10_SB_OPCODE_DP4 => vec![0, 1, 2, 3],
+            _ if ssa::is_componentwise(opcode) && ssa::destinations(opcode) == 1 => {
+                match program.instructions[reader].operands.first().and_then(Operand::register) {
+                    Some(register) => mask_components(register.components.mask()),
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+
+        let count = ssa::destinations(program.instructions[i].opcode);
+        let dst = program.instructions[i].operands.iter().take(count).filter_map(Operand::register).find(|register| register.file == OperandType::Temp);
+        let dst = match dst {
+            Some(dst) => dst,
+            None => continue,
+        };
+        let written = mask_components(dst.components.mask());
+
+        let values: Vec<usize> = ssa.writes.get(&i).map_or(Vec::new(), |accesses| accesses.iter().map(|access| access.value).collect());
+        let operand = ssa.reads.get(&reader).and_then(|accesses| {
+            accesses.iter().find(|access| values.contains(&access.value)).and_then(|access| access.operand)
+        });
+        let operand = match operand {
+            Some(operand) => operand,
+            None => continue,
+        };
+
+        let register = match program.instructions[reader].operands[operand].register() {
+            Some(register) if register.file == OperandType::Temp && register.index[..] == dst.index[..] => register,
+            _ => continue,
+        };
+        let picks: Vec<u8> = match register.components {
+            Components::None | Components::Scalar => vec![0],
+            Components::Mask(_) => lanes,
+            _ => lanes.iter().map(|&lane| register.components.swizzle()[lane as usize]).collect(),
+        };
+
+        if picks.iter().all(|pick| written.contains(pick)) {
+            readers.insert(i, (reader, operand));
+        }
+    }
+
+    readers
+}
+
+// Expressions of the instructions that are a single statement.
+fn single_statements(blocks: Vec<Vec<Statement>>) -> Vec<(usize, Expr)> {
+    let mut counts = HashMap::new();
+    let mut exprs = Vec::new();
+
+    for statement in blocks.into_iter().flatten() {
+        match statement {
+            Statement::Assign { instruction, expr, .. } | Statement::Store { instruction, expr, .. } => {
+                *counts.entry(instruction).or_insert(0) += 1;
+                exprs.push((instruction, expr));
+            }
+            Statement::Effect { .. } => {}
+        }
+    }
+
+    exprs.retain(|&(instruction, _)| counts[&instruction] == 1);
+    exprs
+}
+
+pub(crate) struct Writer<'a, L: Language> {
+    pub shader: &'a Shader,
+    pub program: &'a Program,
+    pub types: Types,
+    /// SSA form of the temps, if they are split by type.
+    ssa: Option<Ssa>,
+    /// Instruction being written.
+    current: usize,
+    /// Instructions folded into their reader in the expression trees, and
+    /// those the writer folds, with the reader and the operand.
+    folded: BTreeMap<usize, usize>,
+    folds: HashMap<usize, (usize, usize)>,
+    /// Expressions of the folded instructions waiting for their reader.
+    deferred: BTreeMap<usize, Deferred>,
+    /// Expression trees of the instructions with one destination.
+    statements: HashMap<usize, Expr>,
+    /// Components, type and value of the assignments of the instruction
+    /// being written.
+    assignments: Vec<(Vec<u8>, Type, String)>,
+    pub resources: HashMap<(OperandType, u32), Resource>,
+    pub helpers: BTreeSet<&'static str>,
+    /// Whether registers live outside of the entry point for the
+    /// subroutines.
+    pub global: bool,
+    pub in_subroutine: bool,
+    pub body: String,
+    pub indent: usize,
+    pub language: L,
+}
+
+impl<'a, L: Language> Writer<'a, L> {
+    // The backends take the nesting of the control flow and the register
+    // counts for granted, those are checked up front.
+    pub fn new(shader: &'a Shader, program: &'a Program, language: L) -> Result<Self, Error> {
+        Cfg::build(program)?;
+        for declaration in &program.decls {
+            if let Declaration::Temps(count) = *declaration {
+                if count > MAX_REGISTERS {
+                    return Err(Error::TooManyRegisters(OperandType::Temp, count));
+                }
+            }
+        }
+
+        // subroutines share the temps with the entry point, each function
+        // would type them differently
+        let global = program.instructions.iter().any(|instruction| instruction.opcode == D3D10_SB_OPCODE_LABEL);
+        let mut types = Types::new(program);
+        let mut ssa = None;
+        let mut folded = BTreeMap::new();
+        let mut folds = HashMap::new();
+        let mut statements = HashMap::new();
+        if L::TYPED_TEMPS && !global {
+            let (built, functions) = if L::FOLD_EXPRESSIONS {
+                let expressions = Expressions::build(program)?;
+                (expressions.ssa, expressions.functions)
+            } else {
+                (Ssa::build(program)?, Vec::new())
+            };
+
+            if built.functions.len() == 1 {
+                if let Some(function) = functions.into_iter().next() {
+                    folds = readers(program, &built.functions[0], &function.folded);
+                    for (i, expr) in single_statements(function.blocks) {
+                        statements.insert(i, expr);
+                    }
+                    for (&i, (_, expr)) in &function.folded {
+                        statements.insert(i, expr.clone());
+                    }
+                    folded = function.folded.into_iter().map(|(i, (reader, _))| (i, reader)).collect();
+                }
+                types = Types::infer(program, &built, &shader.interface());
+                ssa = Some(built);
+            }
+        }
+
+        Ok(Writer {
+            shader,
+            program,
+            types,
+            ssa,
+            current: 0,
+            folded,
+            folds,
+            deferred: BTreeMap::new(),
+            statements,
+            assignments: Vec::new(),
+            resources: HashMap::new(),
+            helpers: BTreeSet::new(),
+            global,
+            in_subroutine: false,
+            body: String::new(),
+            indent: 1,
+            language,
+        })
+    }
+
+    pub fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.body.push_str("    ");
+        }
+        self.body.push_str(text);
+        self.body.push('\n');
+    }
+
+    // Closes `levels` blocks opened before instruction `i`.
+    pub fn dedent(&mut self, i: usize, levels: usize) -> Result<(), Error> {
+        let opcode = self.program.instructions[i].opcode;
+        self.indent = self.indent.checked_sub(levels).ok_or(cfg::Error::Unexpected(i, opcode))?;
+        Ok(())
+    }
+
+    pub fn operand(&self, i: usize, n: usize) -> Result<&'a Operand, Error> {
+        let instruction = &self.program.instructions[i];
+        instruction.operands.get(n).ok_or(Error::InvalidOperand(i, instruction.opcode, n))
+    }
+
+    pub fn register(&self, i: usize, n: usize) -> Result<&'a Register, Error> {
+        let opcode = self.program.instructions[i].opcode;
+        self.operand(i, n)?.register().ok_or(Error::InvalidOperand(i, opcode, n))
+    }
+
+    pub fn unsupported(&self, i: usize, reason: &'static str) -> Error {
+        Error::Unsupported(i, self.program.instructions[i].opcode, reason)
+    }
+
+    pub fn resource(&self, register: &Register) -> Option<&Resource> {
+        let index = register.index.first()?.offset() as u32;
+        self.resources.get(&(register.file, index))
+    }
+
+    pub fn resource_name(&self, register: &Register) -> String {
+        match self.resource(register) {
+            Some(resource) => resource.name.clone(),
+            None => self.shader.resource_name(register.file, resource_index(register)),
+        }
+    }
+
+    pub fn dimension(&self, register: &Register) -> ResourceDimension {
+        self.resource(register).map_or(ResourceDimension::Texture2D, |resource| resource.dimension)
+    }
+
+    pub fn index(&self, index: &Index) -> String {
+        match index.relative() {
+            Some(relative) => {
+                let value = self.register_source(relative, &[0], Type::Int);
+                match index.offset() {
+                    0 => value,
+                    offset => format!("{} + {}", value, offset),
+                }
+            }
+            None => format!("{}", index.offset()),
+        }
+    }
+
+    // Type for the operands of instructions that only move bits around,
+    // none of the languages has bitwise operators on floats. Typed temps
+    // move them in the type of the value written.
+    fn untyped(&self, instruction: usize) -> Type {
+        let written = self.program.instructions[instruction]
+            .operands
+            .first()
+            .and_then(Operand::register)
+            .filter(|register| register.file == OperandType::Temp)
+            .and_then(|register| {
+                let component = mask_components(register.components.mask()).into_iter().next()?;
+                self.temp_type(instruction, resource_index(register), component, true)
+            });
+
+        bits_type(self.program.instructions[instruction].opcode, written)
+    }
+
+    // Storage type of the value of a temp component read or written by an
+    // instruction, if temps are split by type.
+    fn temp_type(&self, instruction: usize, register: u32, component: u8, written: bool) -> Option<Type> {
+        let ssa = &self.ssa.as_ref()?.functions[0];
+        let temp = Temp { register, component };
+        let accesses = if written { ssa.writes.get(&instruction)? } else { ssa.reads.get(&instruction)? };
+        let access = accesses.iter().find(|access| access.temp == temp && (!written || access.operand.is_some()))?;
+        Some(storage(self.types.functions[0].values[access.value]))
+    }
+
+    // Variable holding the values of a type of a temp register.
+    fn temp_name(register: u32, ty: Type) -> String {
+        match ty {
+            Type::Int => format!("r{}_i", register),
+            Type::Uint | Type::Bool => format!("r{}_u", register),
+            Type::Float => format!("r{}", register),
+        }
+    }
+
+    fn temp_part(&self, register: u32, component: u8, written: bool) -> Constant {
+        let ty = self.temp_type(self.current, register, component, written).unwrap_or(Type::Float);
+        Constant { base: L::variable(self, &Self::temp_name(register, ty), false), lane: Some(component), ty }
+    }
+
+    /// Temp variables to declare with their type, the float ones only
+    /// unless temps are split by type.
+    pub fn temps(&self) -> Vec<(String, Type)> {
+        let count = self
+            .program
+            .decls
+            .iter()
+            .filter_map(|declaration| match *declaration {
+                Declaration::Temps(count) => Some(count),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+
+        let used: BTreeSet<(u32, usize)> = match self.ssa {
+            Some(ref ssa) => ssa.functions[0]
+                .values
+                .iter()
+                .zip(&self.types.functions[0].values)
+                .map(|(value, &ty)| (value.temp.register, [Type::Float, Type::Int, Type::Uint].iter().position(|&t| t == storage(ty)).unwrap()))
+                .collect(),
+            None => (0..count).map(|register| (register, 0)).collect(),
+        };
+
+        used.into_iter()
+            .filter(|&(register, _)| register < count)
+            .map(|(register, ty)| {
+                let ty = [Type::Float, Type::Int, Type::Uint][ty];
+                (Self::temp_name(register, ty), ty)
+            })
+            .collect()
+    }
+
+    pub fn source_type(&self, instruction: usize, operand: usize) -> Type {
+        let ty = self.types.source_type(self.program, instruction, operand);
+        storage(ty.unwrap_or_else(|| self.untyped(instruction)))
+    }
+
+    pub fn result_type(&self, instruction: usize, component: u8) -> Type {
+        let ty = self.types.result_type(self.program, instruction, component);
+        storage(ty.unwrap_or_else(|| self.untyped(instruction)))
+    }
+
+    // Source and result types for writing one part of a split destination.
+    fn part_types(&self, instruction: usize, sources: &[usize], dst: &Destination) -> (Vec<Type>, Type) {
+        let opcode = self.program.instructions[instruction].opcode;
+        let untyped = if dst.parts.is_empty() { self.untyped(instruction) } else { bits_type(opcode, Some(dst.ty)) };
+
+        let sources = sources
+            .iter()
+            .map(|&n| storage(self.types.source_type(self.program, instruction, n).unwrap_or(untyped)))
+            .collect();
+        let result = storage(self.types.result_type(self.program, instruction, dst.first()).unwrap_or(untyped));
+        (sources, result)
+    }
+
+    // Components of a constant buffer register.
+    fn constant(&self, register: &Register, component: u8) -> Constant {
+        let unknown = Constant { base: L::literal(0, Type::Uint), lane: None, ty: Type::Uint };
+        let (buffer, index) = match (register.index.first(), register.index.get(1)) {
+            (Some(buffer), Some(index)) => (buffer.offset() as u32, index),
+            _ => return unknown,
+        };
+
+        if !self.shader.constant_buffers.iter().any(|&(slot, _, _)| slot == buffer) {
+            return Constant {
+                base: format!("cb{}[{}]", buffer, self.index(index)),
+                lane: Some(component),
+                ty: Type::Float,
+            };
+        }
+
+        let row = index.offset() as u32;
+        let relative = match index.relative() {
+            Some(relative) => relative,
+            None => {
+                let dword = row.saturating_mul(4).saturating_add(component as u32);
+                return self.shader.constants.get(&(buffer, dword)).cloned().unwrap_or(unknown);
+            }
+        };
+
+        let array = self.shader.arrays.iter().find(|array| {
+            let registers = array.registers.saturating_mul(array.count.max(1));
+            array.buffer == buffer && array.start <= row && row < array.start.saturating_add(registers)
+        });
+        let array = match array {
+            Some(array) => array,
+            None => return unknown,
+        };
+
+        let value = self.register_source(relative, &[0], Type::Int);
+        let element = match row - array.start {
+            0 => value,
+            offset => format!("{} + {}", value, offset),
+        };
+        let (base, lane) = match (array.layout, array.count) {
+            (Layout::Vector, _) => (format!("{}[{}]", array.name, element), Some(component)),
+            (Layout::RowMajor, 0) => (format!("{}[{}]", array.name, element), Some(component)),
+            (Layout::ColumnMajor, 0) => (format!("{}[{}][{}]", array.name, component, element), None),
+            (Layout::RowMajor, _) => {
+                let registers = array.registers;
+                (format!("{}[({}) / {}][({}) % {}]", array.name, element, registers, element, registers), Some(component))
+            }
+            (Layout::ColumnMajor, _) => {
+                let registers = array.registers;
+                (format!("{}[({}) / {}][{}][({}) % {}]", array.name, element, registers, component, element, registers), None)
+            }
+        };
+
+        Constant { base, lane, ty: array.ty }
+    }
+
+    // Parts read from a register for each of the given source components.
+    fn register_parts(&self, register: &Register, picks: &[u8]) -> Vec<Constant> {
+        let part = |base: String, lane: Option<u8>, ty: Type| Constant { base, lane, ty };
+        let unknown = |file: OperandType| format!("{} /* {:?} */", L::literal(0, Type::Uint), file);
+
+        match (register.file, register.index.first(), register.index.get(1)) {
+            (OperandType::ConstantBuffer, _, _) => picks.iter().map(|&c| self.constant(register, c)).collect(),
+            (OperandType::ImmediateConstantBuffer, Some(index), _) => {
+                let base = format!("icb[{}]", self.index(index));
+                picks.iter().map(|&c| part(base.clone(), Some(c), Type::Uint)).collect()
+            }
+            (OperandType::IndexableTemp, Some(array), Some(index)) => {
+                let name = L::variable(self, &format!("x{}", array.offset()), false);
+                let base = format!("{}[{}]", name, self.index(index));
+                picks.iter().map(|&c| part(base.clone(), Some(c), Type::Float)).collect()
+            }
+            (OperandType::Temp, _, _) if self.ssa.is_some() => {
+                picks.iter().map(|&c| self.temp_part(resource_index(register), c, false)).collect()
+            }
+            (OperandType::Temp, _, _) | (OperandType::Input, _, _) | (OperandType::Output, _, _) => {
+                let prefix = match register.file {
+                    OperandType::Temp => "r",
+                    OperandType::Input => "v",
+                    _ => "o",
+                };
+                let base = L::variable(self, &format!("{}{}", prefix, resource_index(register)), false);
+                picks.iter().map(|&c| part(base.clone(), Some(c), Type::Float)).collect()
+            }
+            (file, _, _) => match L::system_value(file) {
+                Some((name, ty, width, _)) => {
+                    let lane = |c: u8| if width == 1 { None } else { Some(c) };
+                    picks.iter().map(|&c| part(L::variable(self, name, true), lane(c), ty)).collect()
+                }
+                None => picks.iter().map(|_| part(unknown(file), None, Type::Uint)).collect(),
+            },
+        }
+    }
+
+    pub fn register_source(&self, register: &Register, components: &[u8], ty: Type) -> String {
+        if let Some(value) = self.folded_source(register, components, ty) {
+            return value;
+        }
+
+        let swizzle_of = register.components.swizzle();
+        let picks: Vec<u8> = match register.components {
+            Components::None | Components::Scalar => components.iter().map(|_| 0).collect(),
+            Components::Mask(_) => components.to_vec(),
+            _ => components.iter().map(|&c| swizzle_of[c as usize & 3]).collect(),
+        };
+        let parts = self.register_parts(register, &picks);
+        let first = match parts.first() {
+            Some(first) => first,
+            None => return L::literal(0, ty),
+        };
+        let width = parts.len();
+
+        let same_base = parts.iter().all(|part| part.base == first.base && part.ty == first.ty);
+        let lanes: Option<Vec<u8>> = parts.iter().map(|part| part.lane).collect();
+        let value = match lanes {
+            Some(ref lanes) if same_base => L::convert(format!("{}.{}", first.base, swizzle(lanes)), first.ty, ty, width),
+            _ if same_base && width == 1 => L::convert(first.base.clone(), first.ty, ty, 1),
+            _ if same_base && parts.iter().all(|part| part.lane.is_none()) => {
+                L::splat(storage(ty), width, L::convert(first.base.clone(), first.ty, ty, 1))
+            }
+            _ => {
+                let values: Vec<String> = parts
+                    .iter()
+                    .map(|part| {
+                        let value = match part.lane {
+                            Some(lane) => format!("{}.{}", part.base, COMPONENTS[lane as usize]),
+                            None => part.base.clone(),
+                        };
+                        L::convert(value, part.ty, ty, 1)
+                    })
+                    .collect();
+                format!("{}({})", L::vector(storage(ty), width), values.join(", "))
+            }
+        };
+
+        L::modify(value, register.modifier, ty)
+    }
+
+    // Expression of the folded instruction an operand of the current one
+    // reads, if it has the components.
+    fn folded_source(&self, register: &Register, components: &[u8], ty: Type) -> Option<String> {
+        let operands = &self.program.instructions.get(self.current)?.operands;
+        let deferred = self.deferred.values().find(|deferred| {
+            deferred.reader == self.current && operands[deferred.operand].register().is_some_and(|read| ptr::eq(read, register))
+        })?;
+
+        let picks: Vec<u8> = match register.components {
+            Components::None | Components::Scalar => components.iter().map(|_| 0).collect(),
+            Components::Mask(_) => components.to_vec(),
+            _ => components.iter().map(|&c| register.components.swizzle()[c as usize & 3]).collect(),
+        };
+        let lanes: Option<Vec<u8>> =
+            picks.iter().map(|pick| deferred.components.iter().position(|c| c == pick).map(|lane| lane as u8)).collect();
+        let lanes = match lanes {
+            Some(lanes) => lanes,
+            None => {
+                deferred.missed.set(true);
+                return None;
+            }
+        };
+        deferred.used.set(true);
+
+        let width = deferred.components.len();
+        let value = if lanes.len() == width && lanes.iter().enumerate().all(|(n, &lane)| n == lane as usize) {
+            deferred.expr.clone()
+        } else if width == 1 {
+            L::splat(storage(deferred.ty), lanes.len(), atom(&deferred.expr))
+        } else {
+            format!("{}.{}", atom(&deferred.expr), swizzle(&lanes))
+        };
+
+        let value = atom(&L::convert(value, deferred.ty, ty, lanes.len()));
+        Some(atom(&L::modify(value, register.modifier, ty)))
+    }
+
+    /// Source operand as a value of `ty` with one component for each of the
+    /// destination `components`.
+    pub fn src(&self, operand: &Operand, components: &[u8], ty: Type) -> String {
+        let literals: Vec<String> = match *operand {
+            Operand::Register(ref register) => return self.register_source(register, components, ty),
+            Operand::Imm32(ref values) => components
+                .iter()
+                .map(|&c| L::literal(values.get(c as usize).or_else(|| values.first()).cloned().unwrap_or(0), ty))
+                .collect(),
+            Operand::Imm64(ref values) => components
+                .iter()
+                .map(|&c| format!("{:?}", f64::from_bits(values.get(c as usize).or_else(|| values.first()).cloned().unwrap_or(0))))
+                .collect(),
+        };
+
+        match literals.len() {
+            0 => L::literal(0, ty),
+            1 => literals[0].clone(),
+            width => format!("{}({})", L::vector(storage(ty), width), literals.join(", ")),
+        }
+    }
+
+    // Sources `operands` of instruction `i` read for `components`.
+    pub fn sources(&self, i: usize, operands: &[usize], components: &[u8]) -> Result<Vec<String>, Error> {
+        operands.iter().map(|&n| Ok(self.src(self.operand(i, n)?, components, self.source_type(i, n)))).collect()
+    }
+
+    pub fn dst(&self, operand: &Operand) -> Option<Destination> {
+        let register = operand.register()?;
+
+        if register.file == OperandType::Null {
+            return None;
+        }
+
+        if let Some((name, ty, 1, _)) = L::system_value(register.file) {
+            return Some(Destination { base: L::variable(self, name, true), components: Vec::new(), ty, parts: Vec::new() });
+        }
+
+        let components = mask_components(register.components.mask());
+        if components.is_empty() {
+            return None;
+        }
+
+        if register.file == OperandType::Temp && self.ssa.is_some() {
+            let parts: Vec<(String, Type)> = components
+                .iter()
+                .map(|&c| {
+                    let part = self.temp_part(resource_index(register), c, true);
+                    (part.base, part.ty)
+                })
+                .collect();
+            let (base, ty) = parts[0].clone();
+            return Some(Destination { base, components, ty, parts });
+        }
+
+        let part = self.register_parts(register, &components).into_iter().next()?;
+        Some(Destination { base: part.base, components, ty: part.ty, parts: Vec::new() })
+    }
+
+    pub fn assign(&mut self, instruction: usize, dst: &Destination, ty: Type, expr: String) {
+        let expr = unwrap(expr);
+        let expr = if self.program.instructions[instruction].saturate && ty == Type::Float {
+            L::saturate(expr)
+        } else {
+            expr
+        };
+
+        if dst.parts.iter().all(|part| *part == dst.parts[0]) {
+            self.assignments.push((dst.components.clone(), storage(ty), expr.clone()));
+            let expr = L::convert(expr, storage(ty), dst.ty, dst.width());
+            L::store(self, dst, expr);
+            return;
+        }
+        self.assignments.push((Vec::new(), ty, String::new()));
+
+        // components of temps split by type go to different variables
+        let mut groups: Vec<(String, Type, Vec<u8>, Vec<u8>)> = Vec::new();
+        for (n, (&component, part)) in dst.components.iter().zip(&dst.parts).enumerate() {
+            match groups.iter().position(|group| group.0 == part.0 && group.1 == part.1) {
+                Some(group) => {
+                    groups[group].2.push(component);
+                    groups[group].3.push(n as u8);
+                }
+                None => groups.push((part.0.clone(), part.1, vec![component], vec![n as u8])),
+            }
+        }
+
+        self.line("{");
+        self.indent += 1;
+        let text = L::local("result", storage(ty), dst.width(), &expr);
+        self.line(&text);
+        for (base, to, components, lanes) in groups {
+            let value = L::convert(format!("result.{}", swizzle(&lanes)), storage(ty), to, lanes.len());
+            let part = Destination { base, components, ty: to, parts: Vec::new() };
+            L::store(self, &part, value);
+        }
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    // Writes a scalar result to every written component, not all languages
+    // widen scalars on assignment.
+    pub fn assign_scalar(&mut self, instruction: usize, dst: &Destination, ty: Type, expr: String) {
+        let expr = if dst.width() > 1 { L::splat(storage(ty), dst.width(), expr) } else { expr };
+        self.assign(instruction, dst, ty, expr);
+    }
+
+    // Instructions computing each destination component from the same
+    // components of their sources.
+    pub fn componentwise(&mut self, i: usize, sources: &[usize], f: Expression) -> Result<(), Error> {
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            for dst in dst.split() {
+                let (types, ty) = self.part_types(i, sources, &dst);
+                let args = sources
+                    .iter()
+                    .zip(types)
+                    .map(|(&n, ty)| Ok(self.src(self.operand(i, n)?, &dst.written(), ty)))
+                    .collect::<Result<Vec<String>, Error>>()?;
+                self.assign(i, &dst, ty, f(&args));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn call(&mut self, i: usize, function: &str) -> Result<(), Error> {
+        let count = self.program.instructions[i].operands.len();
+        let sources: Vec<usize> = (1..count.max(2)).collect();
+        self.componentwise(i, &sources, &|args| {
+            let args: Vec<String> = args.iter().cloned().map(unwrap).collect();
+            format!("{}({})", function, args.join(", "))
+        })
+    }
+
+    pub fn binary(&mut self, i: usize, op: &str) -> Result<(), Error> {
+        self.componentwise(i, &[1, 2], &|args| format!("{} {} {}", args[0], op, args[1]))
+    }
+
+    // Evaluates `f` once per destination component, for operations that
+    // have no vector form.
+    pub fn scalar(&mut self, i: usize, sources: &[usize], f: Expression) -> Result<(), Error> {
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let ty = self.result_type(i, dst.first());
+            let parts = dst
+                .written()
+                .iter()
+                .map(|&c| Ok(f(&self.sources(i, sources, &[c])?)))
+                .collect::<Result<Vec<String>, Error>>()?;
+
+            let expr = if parts.len() == 1 {
+                parts[0].clone()
+            } else {
+                format!("{}({})", L::vector(ty, parts.len()), parts.join(", "))
+            };
+            self.assign(i, &dst, ty, expr);
+        }
+
+        Ok(())
+    }
+
+    pub fn dot(&mut self, i: usize, count: u8) -> Result<(), Error> {
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let components: Vec<u8> = (0..count).collect();
+            let a = self.src(self.operand(i, 1)?, &components, Type::Float);
+            let b = self.src(self.operand(i, 2)?, &components, Type::Float);
+            self.assign_scalar(i, &dst, Type::Float, format!("dot({}, {})", a, b));
+        }
+
+        Ok(())
+    }
+
+    // Instructions writing two destinations may overwrite their own sources,
+    // so every source is copied into a local before either result is stored.
+    // The `sources` picked sources are followed by the unsigned type of the
+    // destination, for turning vector conditions into integers. With
+    // `scalar` the expressions are built for each component.
+    pub fn multiple(&mut self, i: usize, dsts: &[Expression], sources: usize, scalar: bool) -> Result<(), Error> {
+        let count = dsts.len();
+        let destinations = (0..count).map(|n| self.operand(i, n).map(|operand| self.dst(operand))).collect::<Result<Vec<_>, Error>>()?;
+        let operands = count + sources;
+
+        self.line("{");
+        self.indent += 1;
+
+        let mut args = Vec::new();
+        for operand in count..operands {
+            let ty = self.source_type(i, operand);
+            let name = format!("src{}", args.len());
+            let text = L::local(&name, ty, 4, &self.src(self.operand(i, operand)?, &[0, 1, 2, 3], ty));
+            self.line(&text);
+            args.push(name);
+        }
+
+        for (dst, f) in destinations.iter().zip(dsts) {
+            if let Some(ref dst) = *dst {
+                let components = dst.written();
+                let ty = self.result_type(i, dst.first());
+                let pick = |components: &[u8]| -> String {
+                    let mut picked: Vec<String> = args.iter().map(|arg| format!("{}.{}", arg, swizzle(components))).collect();
+                    picked.push(L::vector(Type::Uint, components.len()));
+                    f(&picked)
+                };
+
+                let expr = if scalar && components.len() > 1 {
+                    let parts: Vec<String> = components.iter().map(|&c| pick(&[c])).collect();
+                    format!("{}({})", L::vector(ty, parts.len()), parts.join(", "))
+                } else {
+                    pick(&components)
+                };
+                self.assign(i, dst, ty, expr);
+            }
+        }
+
+        self.dedent(i, 1)?;
+        self.line("}");
+        Ok(())
+    }
+
+    pub fn condition(&self, i: usize) -> Result<String, Error> {
+        let value = self.src(self.operand(i, 0)?, &[0], Type::Uint);
+
+        Ok(match self.program.instructions[i].test {
+            Some(TestBoolean::Zero) => format!("{} == 0u", value),
+            _ => format!("{} != 0u", value),
+        })
+    }
+
+    pub fn immediate_offset(&self, i: usize, offsets: usize) -> Option<String> {
+        for extended in &self.program.instructions[i].extended {
+            if let OpcodeEx::UvOffset(u, v, w) = *extended {
+                if offsets == 0 || (u, v, w) == (0, 0, 0) {
+                    return None;
+                }
+                let values: Vec<i32> = [u, v, w].iter().take(offsets).map(|&x| x as i32).collect();
+                return Some(if offsets == 1 {
+                    L::literal(values[0] as u32, Type::Int)
+                } else {
+                    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                    format!("{}({})", L::vector(Type::Int, offsets), values.join(", "))
+                });
+            }
+        }
+
+        None
+    }
+
+    // Components of a resource read for each destination component.
+    pub fn resource_picks(&self, resource: &Operand, components: &[u8]) -> Vec<u8> {
+        let swizzle = resource.register().map_or([0, 1, 2, 3], |register| register.components.swizzle());
+        components.iter().map(|&c| swizzle[c as usize & 3]).collect()
+    }
+
+    // Writes `value.picks` with picks from the resource swizzle.
+    pub fn assign_resource(&mut self, i: usize, resource: usize, value: String) -> Result<(), Error> {
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let picks = self.resource_picks(self.operand(i, resource)?, &dst.written());
+            let ty = self.result_type(i, dst.first());
+            self.assign(i, &dst, ty, format!("{}.{}", value, swizzle(&picks)));
+        }
+
+        Ok(())
+    }
+
+    // Depth textures return a single value where D3D returns the depth in
+    // the red channel.
+    pub fn depth_texel(&self, texture: &Register, value: String) -> String {
+        if self.resource(texture).is_some_and(|resource| resource.depth) {
+            format!("{}({}, 0.0, 0.0, 1.0)", L::vector(Type::Float, 4), value)
+        } else {
+            value
+        }
+    }
+
+    // System values read by the code that aren't in the signature, with
+    // their name, width and how they are passed in.
+    pub fn system_values(&self) -> BTreeSet<(&'static str, usize, &'static str)> {
+        let mut system_values = BTreeSet::new();
+        let mut collect = |register: &Register| {
+            if let Some((name, _, width, semantic)) = L::system_value(register.file) {
+                if !semantic.is_empty() {
+                    system_values.insert((name, width, semantic));
+                }
+            }
+        };
+        for instruction in &self.program.instructions {
+            for register in instruction.operands.iter().filter_map(Operand::register) {
+                collect(register);
+                for relative in register.index.iter().filter_map(Index::relative) {
+                    collect(relative);
+                }
+            }
+        }
+        system_values
+    }
+
+    // Rows of the immediate constant buffer as indented vectors, if there
+    // is one.
+    pub fn immediate_constants(&self) -> Option<Vec<String>> {
+        self.program.decls.iter().find_map(|declaration| match *declaration {
+            Declaration::ImmediateConstantBuffer(ref values) => Some(
+                values
+                    .chunks(4)
+                    .map(|chunk| {
+                        let values: Vec<String> = (0..4).map(|i| L::literal(chunk.get(i).cloned().unwrap_or(0), Type::Uint)).collect();
+                        format!("    {}({})", L::vector(Type::Uint, 4), values.join(", "))
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+    }
+
+    // Writes the helper functions the code calls, out of `helpers`.
+    pub fn write_helpers(&self, helpers: &[(&str, &str)], out: &mut String) {
+        for &(name, text) in helpers {
+            if self.helpers.contains(name) {
+                writeln!(out, "{}", text).unwrap();
+            }
+        }
+    }
+
+    // Body of the entry point or a subroutine.
+    pub fn function(&mut self, instructions: Range<usize>, subroutine: bool) -> Result<String, Error> {
+        self.in_subroutine = subroutine;
+        self.body.clear();
+        self.indent = 1;
+        for i in instructions {
+            self.instruction(i)?;
+        }
+        Ok(std::mem::take(&mut self.body))
+    }
+
+    pub fn instruction(&mut self, i: usize) -> Result<(), Error> {
+        self.current = i;
+        match self.program.instructions[i].opcode {
+            D3D10_SB_OPCODE_IF => {
+                let text = format!("if ({}) {{", self.condition(i)?);
+                self.line(&text);
+                self.indent += 1;
+            }
+            D3D10_SB_OPCODE_ELSE => {
+                self.dedent(i, 1)?;
+                self.line("} else {");
+                self.indent += 1;
+            }
+            D3D10_SB_OPCODE_ENDIF | D3D10_SB_OPCODE_ENDLOOP => {
+                self.dedent(i, 1)?;
+                self.line("}");
+            }
+            D3D10_SB_OPCODE_LOOP => {
+                self.line(L::LOOP);
+                self.indent += 1;
+            }
+            D3D10_SB_OPCODE_BREAK => self.line("break;"),
+            D3D10_SB_OPCODE_BREAKC => {
+                let text = L::guarded(&self.condition(i)?, "break;");
+                self.line(&text);
+            }
+            D3D10_SB_OPCODE_CONTINUE => self.line("continue;"),
+            D3D10_SB_OPCODE_CONTINUEC => {
+                let text = L::guarded(&self.condition(i)?, "continue;");
+                self.line(&text);
+            }
+            D3D10_SB_OPCODE_SWITCH | D3D10_SB_OPCODE_CASE | D3D10_SB_OPCODE_DEFAULT | D3D10_SB_OPCODE_ENDSWITCH => {
+                return L::switch(self, i);
+            }
+            D3D10_SB_OPCODE_RET => return L::ret(self),
+            D3D10_SB_OPCODE_RETC => {
+                let text = format!("if ({}) {{", self.condition(i)?);
+                self.line(&text);
+                self.indent += 1;
+                L::ret(self)?;
+                self.dedent(i, 1)?;
+                self.line("}");
+            }
+            D3D10_SB_OPCODE_DISCARD => {
+                let text = L::guarded(&self.condition(i)?, L::DISCARD);
+                self.line(&text);
+            }
+            D3D10_SB_OPCODE_CALL => {
+                let text = format!("label{}({});", label(self.operand(i, 0)?), L::arguments(self));
+                self.line(&text);
+            }
+            D3D10_SB_OPCODE_CALLC => {
+                let call = format!("label{}({});", label(self.operand(i, 1)?), L::arguments(self));
+                let text = L::guarded(&self.condition(i)?, &call);
+                self.line(&text);
+            }
+            D3D10_SB_OPCODE_NOP | D3D10_SB_OPCODE_LABEL => {}
+            _ => {
+                let start = self.body.len();
+                self.assignments.clear();
+                if !self.idiom(i)? {
+                    L::operation(self, i)?;
+                }
+                self.fold(i, start);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Keeps the assignment of a folded instruction for its reader, and
+    // writes those of the instructions folded into `i` it didn't take. The
+    // ones reading temps `i` writes are written before it.
+    fn fold(&mut self, i: usize, start: usize) {
+        let accesses = |map: &HashMap<usize, Vec<ssa::Access>>| -> HashSet<Temp> {
+            map.get(&i).map_or(HashSet::new(), |accesses| accesses.iter().map(|access| access.temp).collect())
+        };
+        let (mut reads, writes) = match self.ssa {
+            Some(ref ssa) => (accesses(&ssa.functions[0].reads), accesses(&ssa.functions[0].writes)),
+            None => return,
+        };
+
+        let mut instructions = vec![i];
+        let mut lines = String::new();
+        let taken: Vec<usize> = self.deferred.iter().filter(|&(_, deferred)| deferred.reader == i).map(|(&j, _)| j).collect();
+        for j in taken {
+            let deferred = self.deferred.remove(&j).unwrap();
+            if !deferred.used.get() || deferred.missed.get() {
+                lines.push_str(&deferred.line);
+            } else {
+                instructions.extend(deferred.instructions);
+                reads.extend(deferred.reads);
+            }
+        }
+
+        let deferred = match self.folds.get(&i) {
+            Some(&(reader, operand))
+                if self.assignments.len() == 1 && !self.assignments[0].2.is_empty() && self.assignments[0].2.len() <= MAX_FOLDED =>
+            {
+                let (components, ty, expr) = self.assignments.pop().unwrap();
+                Some(Deferred { reader, operand, instructions, reads, components, ty, expr, line: String::new(), used: Cell::new(false), missed: Cell::new(false) })
+            }
+            _ => None,
+        };
+
+        match deferred {
+            Some(mut deferred) if lines.is_empty() && self.body[start..].matches('\n').count() == 1 => {
+                deferred.line = self.body.split_off(start);
+                self.deferred.insert(i, deferred);
+            }
+            _ => {
+                let overwritten: Vec<usize> = self
+                    .deferred
+                    .iter()
+                    .filter(|&(_, deferred)| !deferred.reads.is_disjoint(&writes))
+                    .map(|(&j, _)| j)
+                    .collect();
+                let mut before = String::new();
+                for j in overwritten {
+                    before.push_str(&self.deferred.remove(&j).unwrap().line);
+                }
+                before.push_str(&lines);
+                self.body.insert_str(start, &before);
+            }
+        }
+    }
+
+    // Writes instruction `i` as the idiom its expression was recognized as,
+    // when all of it can be written.
+    fn idiom(&mut self, i: usize) -> Result<bool, Error> {
+        let (idiom, lanes) = match self.statements.get(&i) {
+            Some(Expr::Swizzle(inner, lanes)) => (&**inner, Some(lanes)),
+            Some(expr) => (expr, None),
+            None => return Ok(false),
+        };
+        match *idiom {
+            Expr::Op(Op::Clamp, _) | Expr::Op(Op::Normalize, _) | Expr::Op(Op::Length, _) | Expr::Op(Op::MatrixMultiply, _) => {}
+            _ => return Ok(false),
+        }
+
+        let value = match self.render(idiom) {
+            Some(value) if !self.program.instructions[i].saturate => value,
+            _ => return Ok(false),
+        };
+        let value = match lanes {
+            Some(lanes) if idiom.width() == 1 => L::splat(Type::Float, lanes.len(), atom(&value)),
+            Some(lanes) => format!("{}.{}", atom(&value), swizzle(lanes)),
+            None => value,
+        };
+
+        let dst = match self.dst(self.operand(i, 0)?) {
+            Some(dst) => dst,
+            None => return Ok(false),
+        };
+
+        // the temps read by the instructions the idiom was made of are only
+        // known to hold their values if they are all still waiting
+        let waiting: HashSet<usize> = self
+            .deferred
+            .values()
+            .filter(|deferred| deferred.reader == i)
+            .flat_map(|deferred| deferred.instructions.iter().cloned())
+            .collect();
+        for (&j, &first) in &self.folded {
+            let mut reader = first;
+            while reader != i && self.folded.contains_key(&reader) {
+                reader = self.folded[&reader];
+            }
+            if reader == i && !waiting.contains(&j) {
+                return Ok(false);
+            }
+        }
+
+        for deferred in self.deferred.values() {
+            if deferred.reader == i {
+                deferred.used.set(true);
+            }
+        }
+        self.assign(i, &dst, Type::Float, value);
+        Ok(true)
+    }
+
+    // Float value of an expression tree, the instructions in it must have
+    // been folded.
+    fn render(&self, expr: &Expr) -> Option<String> {
+        let literal = |values: &[u32]| {
+            if values.iter().all(|&value| value == values[0]) {
+                let value = L::literal(values[0], Type::Float);
+                if values.len() == 1 { value } else { L::splat(Type::Float, values.len(), value) }
+            } else {
+                let values: Vec<String> = values.iter().map(|&value| L::literal(value, Type::Float)).collect();
+                format!("{}({})", L::vector(Type::Float, values.len()), values.join(", "))
+            }
+        };
+
+        Some(match *expr {
+            Expr::Value(value) => {
+                let (base, component, ty) = self.value(value)?;
+                L::convert(format!("{}.{}", base, COMPONENTS[component as usize]), ty, Type::Float, 1)
+            }
+            Expr::Register(ref register, _) => match register.components {
+                Components::Swizzle(_) => self.register_source(register, &[0, 1, 2, 3], Type::Float),
+                _ => self.register_source(register, &[0], Type::Float),
+            },
+            Expr::Imm32(ref values) => literal(values),
+            Expr::Imm64(_) => return None,
+            Expr::Swizzle(ref inner, ref lanes) => match **inner {
+                Expr::Register(ref register, _) if inner.width() == 4 => self.register_source(register, lanes, Type::Float),
+                ref inner if inner.width() == 1 => L::splat(Type::Float, lanes.len(), atom(&self.render(inner)?)),
+                ref inner => format!("{}.{}", atom(&self.render(inner)?), swizzle(lanes)),
+            },
+            Expr::Vector(ref parts) => {
+                // lanes of the same temp variable stay one swizzle
+                let mut values: Vec<(String, Vec<u8>, Type)> = Vec::new();
+                let mut rendered = Vec::new();
+                for part in parts {
+                    if let Expr::Value(value) = *part {
+                        let (base, component, ty) = self.value(value)?;
+                        if let Some(last) = values.last_mut() {
+                            if last.0 == base && last.2 == ty && rendered.last().is_some_and(Option::is_none) {
+                                last.1.push(component);
+                                continue;
+                            }
+                        }
+                        values.push((base, vec![component], ty));
+                        rendered.push(None);
+                    } else {
+                        rendered.push(Some(self.render(part)?));
+                    }
+                }
+
+                let mut values = values.into_iter();
+                let parts: Vec<String> = rendered
+                    .into_iter()
+                    .map(|part| {
+                        part.unwrap_or_else(|| {
+                            let (base, components, ty) = values.next().unwrap();
+                            L::convert(format!("{}.{}", base, swizzle(&components)), ty, Type::Float, components.len())
+                        })
+                    })
+                    .collect();
+                format!("{}({})", L::vector(Type::Float, expr.width()), parts.join(", "))
+            }
+            Expr::Neg(ref inner) => L::modify(atom(&self.render(inner)?), Modifier::Neg, Type::Float),
+            Expr::Abs(ref inner) => L::modify(self.render(inner)?, Modifier::Abs, Type::Float),
+            Expr::Op(Op::Instruction { instruction, .. }, _) => {
+                let deferred = self.deferred.get(&instruction)?;
+                if deferred.components.len() != expr.width() {
+                    return None;
+                }
+                L::convert(atom(&deferred.expr), deferred.ty, Type::Float, expr.width())
+            }
+            Expr::Op(Op::Clamp, ref args) => {
+                let value = self.render(&args[0])?;
+                let bound = |arg: &Expr, bits: u32| match *arg {
+                    Expr::Imm32(ref values) => values.iter().all(|&value| value == bits),
+                    _ => false,
+                };
+                if bound(&args[1], 0) && bound(&args[2], 0x3f80_0000) {
+                    L::saturate(value)
+                } else {
+                    format!("clamp({}, {}, {})", value, self.render(&args[1])?, self.render(&args[2])?)
+                }
+            }
+            Expr::Op(Op::Normalize, ref args) => format!("normalize({})", self.render(&args[0])?),
+            Expr::Op(Op::Length, ref args) => format!("length({})", self.render(&args[0])?),
+            Expr::Op(Op::MatrixMultiply, ref args) => {
+                let rows = args[1..].iter().map(|row| self.render(row)).collect::<Option<Vec<String>>>()?;
+                L::matrix_multiply(self.render(&args[0])?, &rows, args[1].width())?
+            }
+        })
+    }
+
+    // Variable, component and storage type of a temp value.
+    fn value(&self, value: usize) -> Option<(String, u8, Type)> {
+        let temp = self.ssa.as_ref()?.functions[0].values.get(value)?.temp;
+        let ty = storage(self.types.functions[0].values[value]);
+        Some((L::variable(self, &Self::temp_name(temp.register, ty), false), temp.component, ty))
+    }
+}
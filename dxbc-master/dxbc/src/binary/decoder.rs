@@ -1,6 +1,5 @@
 use super::error::Error;
 
-use std::slice;
 use std::str;
 
 use byteorder::{ByteOrder, LittleEndian};
@@ -29,7 +28,7 @@ impl<'a> Decoder<'a> {
     pub fn seek(&self, offset: usize) -> Self {
         Decoder {
             bytes: self.bytes,
-            offset: offset,
+            offset,
             limit: self.limit
         }
     }
@@ -38,93 +37,132 @@ impl<'a> Decoder<'a> {
         self.offset >= self.bytes.len()
     }
 
-    pub fn scoped_decoder(&self, len: usize) -> Self {
-        Decoder {
+    /// Restricts the decoder to read at most `limit` more bytes.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = Some(limit);
+    }
+
+    pub fn clear_limit(&mut self) {
+        self.limit = None;
+    }
+
+    pub fn get_limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn has_limit(&self) -> bool {
+        self.limit.is_some()
+    }
+
+    pub fn limit_reached(&self) -> bool {
+        self.limit == Some(0)
+    }
+
+    pub fn scoped_decoder(&self, len: usize) -> DecoderResult<Self> {
+        self.check(len)?;
+
+        Ok(Decoder {
             bytes: &self.bytes[self.offset..(self.offset + len)],
             offset: 0,
             limit: None,
-        }
+        })
     }
 
-    pub fn skip(&mut self, n: usize) {
-        self.offset += n;
+    pub fn skip(&mut self, n: usize) -> DecoderResult<()> {
+        self.advance(n).map(|_| ())
     }
 
     pub fn get_offset(&self) -> usize {
         self.offset
     }
 
-    pub fn bytes(&mut self, n: usize) -> &'a [u8] {
+    // Verifies that `n` bytes can be read at the current offset without
+    // running past the limit or the end of the stream.
+    fn check(&self, n: usize) -> DecoderResult<()> {
+        if let Some(limit) = self.limit {
+            if n > limit {
+                return Err(Error::LimitReached(self.offset));
+            }
+        }
+
+        match self.offset.checked_add(n) {
+            Some(end) if end <= self.bytes.len() => Ok(()),
+            _ => Err(Error::StreamExpected(self.offset)),
+        }
+    }
+
+    fn advance(&mut self, n: usize) -> DecoderResult<&'a [u8]> {
+        self.check(n)?;
+
         let slice = &self.bytes[self.offset..(self.offset + n)];
 
         self.offset += n;
+        if let Some(limit) = self.limit {
+            self.limit = Some(limit - n);
+        }
 
-        slice
+        Ok(slice)
     }
 
-    pub fn words(&mut self, n: usize) -> &'a [u32] {
-        let byte_len = 4 * n;
-        let slice = &self.bytes[self.offset..(self.offset + byte_len)];
+    pub fn bytes(&mut self, n: usize) -> DecoderResult<&'a [u8]> {
+        self.advance(n)
+    }
 
-        self.offset += byte_len;
+    pub fn words(&mut self, n: usize) -> DecoderResult<Vec<u32>> {
+        let byte_len = n.checked_mul(4).ok_or(Error::StreamExpected(self.offset))?;
+        let slice = self.advance(byte_len)?;
 
-        unsafe {
-            slice::from_raw_parts(slice.as_ptr() as _, n)
-        }
+        Ok(slice.chunks(4).map(LittleEndian::read_u32).collect())
+    }
+
+    pub fn read_u64(&mut self) -> DecoderResult<u64> {
+        self.advance(8).map(LittleEndian::read_u64)
     }
 
-    pub fn read_u64(&mut self) -> u64 {
-        let val = LittleEndian::read_u64(&self.bytes[self.offset..]);
-        self.offset += 8;
-        val
+    pub fn read_u32(&mut self) -> DecoderResult<u32> {
+        self.advance(4).map(LittleEndian::read_u32)
     }
 
-    pub fn read_u32(&mut self) -> u32 {
-        let val = LittleEndian::read_u32(&self.bytes[self.offset..]);
-        self.offset += 4;
-        val
+    /// Returns the address of the next word, tokens referencing it have to
+    /// be read unaligned.
+    pub fn read_u32_address(&mut self) -> DecoderResult<*const u32> {
+        self.advance(4).map(|slice| slice.as_ptr() as _)
     }
 
-    pub fn read_u32_address(&mut self) -> *const u32 {
-        let ptr = self.bytes[self.offset..].as_ptr() as _;
-        self.offset += 4;
-        ptr
+    pub fn read_u16(&mut self) -> DecoderResult<u16> {
+        self.advance(2).map(LittleEndian::read_u16)
     }
 
-    pub fn read_u16(&mut self) -> u16 {
-        let val = LittleEndian::read_u16(&self.bytes[self.offset..]);
-        self.offset += 2;
-        val
+    pub fn read_u8(&mut self) -> DecoderResult<u8> {
+        self.advance(1).map(|slice| slice[0])
     }
 
-    pub fn read_u8(&mut self) -> u8 {
-        let val = self.bytes[self.offset];
-        self.offset += 1;
-        val
+    fn null_terminated(&self) -> DecoderResult<&'a [u8]> {
+        let remaining = self.bytes.get(self.offset..).ok_or(Error::StreamExpected(self.offset))?;
+        let null = remaining.iter().position(|&b| b == 0).ok_or(Error::StreamExpected(self.bytes.len()))?;
+
+        self.check(null + 1)?;
+
+        Ok(&remaining[..null])
     }
 
     pub fn str(&mut self) -> DecoderResult<&'a str> {
-        let null = self.bytes[self.offset..].iter().position(|&b| b == 0).unwrap_or(self.bytes.len());
+        let bytes = self.null_terminated()?;
 
-        let string = str::from_utf8(
-            &self.bytes[self.offset..(self.offset + null)]
-        ).map_err(|e| Error::DecodeStrFailed(self.offset, e));
+        let string = str::from_utf8(bytes).map_err(|e| Error::DecodeStrFailed(self.offset, e))?;
 
-        self.offset += null + 1;
+        self.advance(bytes.len() + 1)?;
 
-        string
+        Ok(string)
     }
 
     pub fn string(&mut self) -> DecoderResult<String> {
-        let null = self.bytes[self.offset..].iter().position(|&b| b == 0).unwrap_or(self.bytes.len());
+        let bytes = self.null_terminated()?;
 
-        let string = String::from_utf8(
-            self.bytes[self.offset..(self.offset + null)]
-                .to_vec()
-        ).map_err(|e| Error::DecodeStringFailed(self.offset, e));
+        let string = String::from_utf8(bytes.to_vec()).map_err(|e| Error::DecodeStringFailed(self.offset, e))?;
 
-        self.offset += null + 1;
+        self.advance(bytes.len() + 1)?;
 
-        string
+        Ok(string)
     }
 }
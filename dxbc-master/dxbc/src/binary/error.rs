@@ -8,13 +8,28 @@ pub enum Error {
     LimitReached(usize),
     DecodeStringFailed(usize, FromUtf8Error),
     DecodeStrFailed(usize, Utf8Error),
+    InvalidToken(usize, u32),
+    /// Opcode that doesn't exist in the shader model of the program, with
+    /// its major and minor version.
+    UnavailableOpcode(usize, u32, u8, u8),
+    /// What a consumer found wrong with a well-formed shader, such as
+    /// something a backend can't translate.
+    Consumer(Box<dyn error::Error>),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            Error::StreamExpected(index) => write!(f, "expected more bytes in the stream at index {}", index),
+            Error::LimitReached(index) => write!(f, "reached decoding limit at index {}", index),
             Error::DecodeStringFailed(index, ref e) => write!(f, "cannot decode string at index {}: {}", index, e),
-            _ => write!(f, "unimplemented")
+            Error::DecodeStrFailed(index, ref e) => write!(f, "cannot decode string at index {}: {}", index, e),
+            Error::InvalidToken(index, token) => write!(f, "invalid token {:#010x} at index {}", token, index),
+            Error::UnavailableOpcode(index, opcode, major, minor) => {
+                let name = ::disasm::opcode_name(opcode).unwrap_or("unknown");
+                write!(f, "`{}` is not available in shader model {}.{} at index {}", name, major, minor, index)
+            }
+            Error::Consumer(ref e) => write!(f, "{}", e),
         }
     }
 }
@@ -22,7 +37,12 @@ impl fmt::Display for Error {
 impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
-            _ => "unknown operand value for the given kind",
+            Error::StreamExpected(_) => "expected more bytes in the stream",
+            Error::LimitReached(_) => "reached decoding limit",
+            Error::DecodeStringFailed(..) | Error::DecodeStrFailed(..) => "cannot decode string",
+            Error::InvalidToken(..) => "invalid token",
+            Error::UnavailableOpcode(..) => "opcode not available in this shader model",
+            Error::Consumer(_) => "consumer error",
         }
     }
 }
@@ -3,8 +3,7 @@ use super::error;
 use dr;
 use super::decoder;
 
-use std::mem;
-use std::str;
+use std::fmt;
 
 #[derive(Debug)]
 pub enum State {
@@ -19,6 +18,25 @@ pub enum State {
     DecoderError(error::Error),
 }
 
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            State::Complete => write!(f, "parsing completed"),
+            State::ConsumerStopRequested => write!(f, "consumer requested to stop parsing"),
+            State::ConsumerError(ref err) => write!(f, "consumer error: {}", err),
+            State::HeaderIncorrect => write!(f, "incorrect DXBC header"),
+            State::ChunkIncorrect => write!(f, "incorrect chunk"),
+            State::DecoderError(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<error::Error> for State {
+    fn from(err: error::Error) -> Self {
+        State::DecoderError(err)
+    }
+}
+
 pub enum Action {
     Continue,
     Stop,
@@ -29,13 +47,25 @@ pub trait Consumer {
     fn initialize(&mut self) -> Action;
     fn finalize(&mut self) -> Action;
 
-    fn consume_header(&mut self, header: &dr::DxbcHeader) -> Action { Action::Continue }
-    fn consume_rdef(&mut self, rdef: &dr::RdefChunk) -> Action { Action::Continue }
-    fn consume_isgn(&mut self, isgn: &dr::IOsgnChunk) -> Action { Action::Continue }
-    fn consume_osgn(&mut self, osgn: &dr::IOsgnChunk) -> Action { Action::Continue }
-    fn consume_shex(&mut self, osgn: &dr::ShexHeader) -> Action { Action::Continue }
-    fn consume_stat(&mut self, osgn: &dr::IStatChunk) -> Action { Action::Continue }
-    fn consume_instruction(&mut self, offset: u32, instruction: dr::SparseInstruction) -> Action { Action::Continue }
+    fn consume_header(&mut self, _header: &dr::DxbcHeader) -> Action { Action::Continue }
+    fn consume_rdef(&mut self, _rdef: &dr::RdefChunk) -> Action { Action::Continue }
+    fn consume_isgn(&mut self, _isgn: &dr::IOsgnChunk) -> Action { Action::Continue }
+    fn consume_osgn(&mut self, _osgn: &dr::IOsgnChunk) -> Action { Action::Continue }
+    /// Input signature with minimum precisions, in place of `ISGN`.
+    fn consume_isg1(&mut self, isg1: &dr::IOsgnChunk) -> Action { self.consume_isgn(isg1) }
+    /// Output signature with streams and minimum precisions, in place of `OSGN`.
+    fn consume_osg1(&mut self, osg1: &dr::IOsgnChunk) -> Action { self.consume_osgn(osg1) }
+    /// Output signature with streams, in place of `OSGN`.
+    fn consume_osg5(&mut self, osg5: &dr::IOsgnChunk) -> Action { self.consume_osgn(osg5) }
+    /// Patch constant signature of hull and domain shaders.
+    fn consume_pcsg(&mut self, _pcsg: &dr::IOsgnChunk) -> Action { Action::Continue }
+    fn consume_shex(&mut self, _osgn: &dr::ShexHeader) -> Action { Action::Continue }
+    /// Shader model 4 programs, which share the encoding of `SHEX`.
+    fn consume_shdr(&mut self, shdr: &dr::ShexHeader) -> Action { self.consume_shex(shdr) }
+    fn consume_stat(&mut self, _osgn: &dr::IStatChunk) -> Action { Action::Continue }
+    fn consume_instruction(&mut self, _offset: u32, _instruction: dr::SparseInstruction) -> Action { Action::Continue }
+    /// Chunks the parser doesn't decode, such as `SFI0` or `ILDB`, with their raw contents.
+    fn consume_unknown(&mut self, _fourcc: &[u8], _data: &[u8]) -> Action { Action::Continue }
 }
 
 fn try_consume(action: Action) -> Result<(), State> {
@@ -48,11 +78,11 @@ fn try_consume(action: Action) -> Result<(), State> {
 
 pub struct Parser<'c, 'd> {
     decoder: decoder::Decoder<'d>,
-    consumer: &'c mut Consumer,
+    consumer: &'c mut dyn Consumer,
 }
 
 impl<'c, 'd> Parser<'c, 'd> {
-    pub fn new(binary: &'d [u8], consumer: &'c mut Consumer) -> Self {
+    pub fn new(binary: &'d [u8], consumer: &'c mut dyn Consumer) -> Self {
         Parser {
             decoder: decoder::Decoder::new(binary),
             consumer,
@@ -63,16 +93,16 @@ impl<'c, 'd> Parser<'c, 'd> {
         try_consume(self.consumer.initialize())?;
 
         let header = self.parse_header()?;
-        try_consume(self.consumer.consume_header(header))?;
+        try_consume(self.consumer.consume_header(&header))?;
 
-        let chunk_offsets = self.decoder.words(header.chunk_count as usize);
+        let chunk_offsets = self.decoder.words(header.chunk_count as usize)?;
 
-        for &chunk_offset in chunk_offsets {
+        for chunk_offset in chunk_offsets {
             self.decoder.seek_mut(chunk_offset as usize);
-            let fourcc = self.decoder.bytes(4);
-            let chunk_length = self.decoder.read_u32();
+            let fourcc = self.decoder.bytes(4)?;
+            let chunk_length = self.decoder.read_u32()?;
 
-            let mut decoder = self.decoder.scoped_decoder(chunk_length as usize);
+            let mut decoder = self.decoder.scoped_decoder(chunk_length as usize)?;
 
             match fourcc {
                 b"RDEF" => {
@@ -87,15 +117,35 @@ impl<'c, 'd> Parser<'c, 'd> {
                     let osgn = dr::IOsgnChunk::parse(&mut decoder)?;
                     try_consume(self.consumer.consume_osgn(&osgn))?;
                 },
-                b"SHEX" => {
+                b"ISG1" => {
+                    let isg1 = dr::IOsgnChunk::parse_format(&mut decoder, dr::SignatureFormat::Precision)?;
+                    try_consume(self.consumer.consume_isg1(&isg1))?;
+                },
+                b"OSG1" => {
+                    let osg1 = dr::IOsgnChunk::parse_format(&mut decoder, dr::SignatureFormat::Precision)?;
+                    try_consume(self.consumer.consume_osg1(&osg1))?;
+                },
+                b"OSG5" => {
+                    let osg5 = dr::IOsgnChunk::parse_format(&mut decoder, dr::SignatureFormat::Stream)?;
+                    try_consume(self.consumer.consume_osg5(&osg5))?;
+                },
+                b"PCSG" => {
+                    let pcsg = dr::IOsgnChunk::parse(&mut decoder)?;
+                    try_consume(self.consumer.consume_pcsg(&pcsg))?;
+                },
+                b"SHEX" | b"SHDR" => {
                     let shex = dr::ShexHeader::parse(&mut decoder)?;
-                    try_consume(self.consumer.consume_shex(&shex))?;
+                    if fourcc == b"SHDR" {
+                        try_consume(self.consumer.consume_shdr(&shex))?;
+                    } else {
+                        try_consume(self.consumer.consume_shex(&shex))?;
+                    }
 
-                    let mut decoder = decoder.scoped_decoder(shex.instruction_length as usize * 4);
+                    let mut decoder = decoder.scoped_decoder(shex.instruction_length as usize * 4)?;
 
                     while !decoder.eof() {
                         let offset = decoder.get_offset();
-                        let instruction = dr::SparseInstruction::parse(&mut decoder);
+                        let instruction = dr::SparseInstruction::parse(&mut decoder, &shex)?;
 
                         try_consume(self.consumer.consume_instruction(offset as u32, instruction))?;
                     }
@@ -105,13 +155,8 @@ impl<'c, 'd> Parser<'c, 'd> {
                     try_consume(self.consumer.consume_stat(&stat))?;
                 }
                 _ => {
-                    eprintln!(
-                        "{}: Incorrect or unimplemented chunk type '{}'",
-                        chunk_offset,
-                        unsafe {
-                            str::from_utf8_unchecked(fourcc)
-                        },
-                    );
+                    let data = decoder.bytes(chunk_length as usize)?;
+                    try_consume(self.consumer.consume_unknown(fourcc, data))?;
                 }
             }
         }
@@ -121,12 +166,8 @@ impl<'c, 'd> Parser<'c, 'd> {
         Ok(())
     }
 
-    fn parse_header(&mut self) -> Result<&'d dr::DxbcHeader, State> {
-        let bytes = self.decoder.bytes(mem::size_of::<dr::DxbcHeader>());
-
-        let header: &'d dr::DxbcHeader = unsafe {
-            mem::transmute(bytes.as_ptr())
-        };
+    fn parse_header(&mut self) -> Result<dr::DxbcHeader, State> {
+        let header = dr::DxbcHeader::parse(&mut self.decoder)?;
 
         if header.magic == *b"DXBC" {
             Ok(header)
@@ -0,0 +1,556 @@
+use dr::shex::OperandType;
+use d3d11tokenizedprogramformat::*;
+use disasm::opcode_name;
+use ir::{Index, Operand, Program};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+use std::error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// `else`, `endif`, `endloop`, `endswitch`, `case` or `default` that
+    /// doesn't belong to the innermost open construct.
+    Unexpected(usize, u32),
+    /// Construct that isn't closed before the end of its function.
+    Unterminated(usize, u32),
+    BreakOutsideLoop(usize),
+    ContinueOutsideLoop(usize),
+    /// Instruction between `switch` and its first `case`.
+    OutsideCase(usize),
+    UndefinedLabel(usize, u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = |opcode: u32| opcode_name(opcode).unwrap_or("unknown");
+
+        match *self {
+            Error::Unexpected(index, opcode) => write!(f, "unexpected `{}` at instruction {}", name(opcode), index),
+            Error::Unterminated(index, opcode) => write!(f, "`{}` at instruction {} is never closed", name(opcode), index),
+            Error::BreakOutsideLoop(index) => write!(f, "break outside of a loop or switch at instruction {}", index),
+            Error::ContinueOutsideLoop(index) => write!(f, "continue outside of a loop at instruction {}", index),
+            Error::OutsideCase(index) => write!(f, "instruction {} is not inside a case", index),
+            Error::UndefinedLabel(index, label) => write!(f, "call of undefined label l{} at instruction {}", label, index),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Unexpected(..) => "unexpected control flow instruction",
+            Error::Unterminated(..) => "unterminated control flow construct",
+            Error::BreakOutsideLoop(_) => "break outside of a loop or switch",
+            Error::ContinueOutsideLoop(_) => "continue outside of a loop",
+            Error::OutsideCase(_) => "instruction outside of a case",
+            Error::UndefinedLabel(..) => "call of an undefined label",
+        }
+    }
+}
+
+/// Instructions `start..end` of the program, entered only at `start` and
+/// left only after `end - 1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    pub successors: Vec<usize>,
+    pub predecessors: Vec<usize>,
+}
+
+/// Structured control flow of a function, blocks are indices into
+/// `Function::blocks`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Region {
+    Block(usize),
+    Sequence(Vec<Region>),
+    /// `header` ends with the `if`, a `then` followed by an `else` branch
+    /// ends with the `else` instruction.
+    If { header: usize, then: Box<Region>, otherwise: Option<Box<Region>> },
+    /// The body starts with the `loop` and ends with the `endloop`.
+    Loop { body: Box<Region> },
+    /// `header` ends with the `switch`.
+    Switch { header: usize, cases: Vec<Case> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    /// Values of the `case` labels sharing the body, `None` for `default`.
+    pub labels: Vec<Option<u32>>,
+    /// Starts with the labels, falls through into the next case unless it
+    /// ends with a `break`.
+    pub body: Region,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FunctionKind {
+    Main,
+    /// Code following `label l#`.
+    Subroutine(u32),
+    /// Hull shader phase, the opcode of the phase instruction.
+    Phase(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub kind: FunctionKind,
+    /// The entry block comes first, blocks are in program order.
+    pub blocks: Vec<Block>,
+    pub region: Region,
+    /// Labels called by `call` and `callc`, with the calling instruction.
+    pub calls: Vec<(usize, u32)>,
+}
+
+impl Function {
+    /// Block containing the instruction.
+    pub fn block_of(&self, instruction: usize) -> Option<usize> {
+        match self.blocks.binary_search_by(|block| block.start.cmp(&instruction)) {
+            Ok(block) => Some(block),
+            Err(0) => None,
+            Err(block) if instruction < self.blocks[block - 1].end => Some(block - 1),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Control flow of a program, one graph for each function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cfg {
+    pub functions: Vec<Function>,
+}
+
+impl Cfg {
+    /// Splits the program into functions and basic blocks and checks that
+    /// the control flow instructions nest properly.
+    pub fn build(program: &Program) -> Result<Cfg, Error> {
+        let opcodes: Vec<u32> = program.instructions.iter().map(|instruction| instruction.opcode).collect();
+
+        let mut starts = vec![0];
+        starts.extend((1..opcodes.len()).filter(|&i| is_function_start(opcodes[i])));
+        starts.push(opcodes.len());
+
+        let mut functions = Vec::new();
+        for range in starts.windows(2) {
+            let (start, end) = (range[0], range[1]);
+            let kind = match opcodes.get(start) {
+                Some(&D3D10_SB_OPCODE_LABEL) => FunctionKind::Subroutine(label(&program.instructions[start].operands).unwrap_or(0)),
+                Some(&opcode) if is_function_start(opcode) => FunctionKind::Phase(opcode),
+                _ => FunctionKind::Main,
+            };
+
+            let calls = (start..end)
+                .filter(|&i| opcodes[i] == D3D10_SB_OPCODE_CALL || opcodes[i] == D3D10_SB_OPCODE_CALLC)
+                .filter_map(|i| label(&program.instructions[i].operands).map(|label| (i, label)))
+                .collect();
+
+            let nesting = Nesting::parse(&opcodes, start, end)?;
+            let mut builder = FunctionBuilder::new(program, &opcodes, &nesting, start, end);
+            builder.connect();
+            let region = builder.sequence(0, builder.blocks.len(), None);
+
+            functions.push(Function {
+                kind,
+                blocks: builder.blocks,
+                region,
+                calls,
+            });
+        }
+
+        let labels: Vec<u32> = functions
+            .iter()
+            .filter_map(|function| match function.kind {
+                FunctionKind::Subroutine(label) => Some(label),
+                _ => None,
+            })
+            .collect();
+
+        for function in &functions {
+            if let Some(&(index, label)) = function.calls.iter().find(|&&(_, label)| !labels.contains(&label)) {
+                return Err(Error::UndefinedLabel(index, label));
+            }
+        }
+
+        Ok(Cfg { functions })
+    }
+
+    /// Graphviz listing of the graph, each function in its own cluster with
+    /// its blocks showing their instructions. Calls are dashed edges.
+    pub fn to_dot(&self, program: &Program) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph cfg {{");
+        let _ = writeln!(dot, "    node [shape=box, fontname=\"monospace\"];");
+
+        let mut entries = HashMap::new();
+        for (f, function) in self.functions.iter().enumerate() {
+            if let FunctionKind::Subroutine(label) = function.kind {
+                entries.insert(label, f);
+            }
+        }
+
+        let mut phases = 0;
+        for (f, function) in self.functions.iter().enumerate() {
+            let name = match function.kind {
+                FunctionKind::Main => "main".to_owned(),
+                FunctionKind::Subroutine(label) => format!("l{}", label),
+                FunctionKind::Phase(opcode) => {
+                    phases += 1;
+                    format!("{} {}", opcode_name(opcode).unwrap_or("phase"), phases - 1)
+                }
+            };
+
+            let _ = writeln!(dot, "    subgraph cluster_{} {{", f);
+            let _ = writeln!(dot, "        label=\"{}\";", name);
+
+            for (b, block) in function.blocks.iter().enumerate() {
+                let mut text = String::new();
+                for instruction in &program.instructions[block.start..block.end] {
+                    for c in instruction.to_string().trim_end().chars() {
+                        match c {
+                            '"' | '\\' => { text.push('\\'); text.push(c); }
+                            _ => text.push(c),
+                        }
+                    }
+                    text.push_str("\\l");
+                }
+
+                let _ = writeln!(dot, "        f{}_b{} [label=\"{}\"];", f, b, text);
+            }
+
+            for (b, block) in function.blocks.iter().enumerate() {
+                for successor in &block.successors {
+                    let _ = writeln!(dot, "        f{}_b{} -> f{}_b{};", f, b, f, successor);
+                }
+            }
+
+            let _ = writeln!(dot, "    }}");
+        }
+
+        for (f, function) in self.functions.iter().enumerate() {
+            for &(index, label) in &function.calls {
+                if let (Some(b), Some(callee)) = (function.block_of(index), entries.get(&label)) {
+                    let _ = writeln!(dot, "    f{}_b{} -> f{}_b0 [style=dashed];", f, b, callee);
+                }
+            }
+        }
+
+        let _ = writeln!(dot, "}}");
+        dot
+    }
+}
+
+fn is_function_start(opcode: u32) -> bool {
+    matches!(opcode, D3D10_SB_OPCODE_LABEL |
+        D3D11_SB_OPCODE_HS_CONTROL_POINT_PHASE |
+        D3D11_SB_OPCODE_HS_FORK_PHASE |
+        D3D11_SB_OPCODE_HS_JOIN_PHASE)
+}
+
+fn is_case(opcode: u32) -> bool {
+    opcode == D3D10_SB_OPCODE_CASE || opcode == D3D10_SB_OPCODE_DEFAULT
+}
+
+// Index of the `l#` operand of `label`, `call` and `callc`.
+fn label(operands: &[Operand]) -> Option<u32> {
+    let register = operands.iter().filter_map(Operand::register).find(|register| register.file == OperandType::Label)?;
+
+    match register.index.first() {
+        Some(&Index::U32(index)) => Some(index),
+        _ => None,
+    }
+}
+
+enum Open {
+    If(usize),
+    Loop(usize),
+    Switch(usize, Vec<usize>),
+}
+
+// Matching instructions of the control flow constructs of a function, all
+// indices are program instruction indices.
+#[derive(Default)]
+struct Nesting {
+    // `if`, `else`, `loop` and `switch` to their `endif`, `endloop` or
+    // `endswitch`, and the other way around
+    ends: HashMap<usize, usize>,
+    opens: HashMap<usize, usize>,
+    elses: HashMap<usize, usize>,
+    // first label of each case
+    cases: HashMap<usize, Vec<usize>>,
+    // switches with a `default` label
+    defaults: HashSet<usize>,
+    // `break`, `breakc`, `continue` and `continuec` to their `loop` or
+    // `switch`
+    targets: HashMap<usize, usize>,
+}
+
+impl Nesting {
+    fn parse(opcodes: &[u32], start: usize, end: usize) -> Result<Nesting, Error> {
+        let mut nesting = Nesting::default();
+        let mut stack = Vec::new();
+
+        for i in start..end {
+            let opcode = opcodes[i];
+
+            if let Some(Open::Switch(_, cases)) = stack.last() {
+                if cases.is_empty() && !is_case(opcode) && opcode != D3D10_SB_OPCODE_ENDSWITCH {
+                    return Err(Error::OutsideCase(i));
+                }
+            }
+
+            match opcode {
+                D3D10_SB_OPCODE_IF => stack.push(Open::If(i)),
+                D3D10_SB_OPCODE_LOOP => stack.push(Open::Loop(i)),
+                D3D10_SB_OPCODE_SWITCH => stack.push(Open::Switch(i, Vec::new())),
+                D3D10_SB_OPCODE_ELSE => match stack.last() {
+                    Some(&Open::If(open)) if !nesting.elses.contains_key(&open) => {
+                        nesting.elses.insert(open, i);
+                    }
+                    _ => return Err(Error::Unexpected(i, opcode)),
+                },
+                D3D10_SB_OPCODE_CASE | D3D10_SB_OPCODE_DEFAULT => match stack.last_mut() {
+                    Some(&mut Open::Switch(open, ref mut cases)) => {
+                        // consecutive labels share their body
+                        if cases.is_empty() || !is_case(opcodes[i - 1]) {
+                            cases.push(i);
+                        }
+                        if opcode == D3D10_SB_OPCODE_DEFAULT {
+                            nesting.defaults.insert(open);
+                        }
+                    }
+                    _ => return Err(Error::Unexpected(i, opcode)),
+                },
+                D3D10_SB_OPCODE_ENDIF | D3D10_SB_OPCODE_ENDLOOP | D3D10_SB_OPCODE_ENDSWITCH => {
+                    let open = match (stack.pop(), opcode) {
+                        (Some(Open::If(open)), D3D10_SB_OPCODE_ENDIF) => {
+                            if let Some(&otherwise) = nesting.elses.get(&open) {
+                                nesting.ends.insert(otherwise, i);
+                            }
+                            open
+                        }
+                        (Some(Open::Loop(open)), D3D10_SB_OPCODE_ENDLOOP) => open,
+                        (Some(Open::Switch(open, cases)), D3D10_SB_OPCODE_ENDSWITCH) => {
+                            nesting.cases.insert(open, cases);
+                            open
+                        }
+                        _ => return Err(Error::Unexpected(i, opcode)),
+                    };
+
+                    nesting.ends.insert(open, i);
+                    nesting.opens.insert(i, open);
+                }
+                D3D10_SB_OPCODE_BREAK | D3D10_SB_OPCODE_BREAKC => {
+                    let target = stack.iter().rev().filter_map(|open| match *open {
+                        Open::Loop(open) | Open::Switch(open, _) => Some(open),
+                        Open::If(_) => None,
+                    }).next();
+
+                    nesting.targets.insert(i, target.ok_or(Error::BreakOutsideLoop(i))?);
+                }
+                D3D10_SB_OPCODE_CONTINUE | D3D10_SB_OPCODE_CONTINUEC => {
+                    let target = stack.iter().rev().filter_map(|open| match *open {
+                        Open::Loop(open) => Some(open),
+                        _ => None,
+                    }).next();
+
+                    nesting.targets.insert(i, target.ok_or(Error::ContinueOutsideLoop(i))?);
+                }
+                _ => {}
+            }
+        }
+
+        match stack.pop() {
+            Some(Open::If(open)) | Some(Open::Loop(open)) | Some(Open::Switch(open, _)) => {
+                Err(Error::Unterminated(open, opcodes[open]))
+            }
+            None => Ok(nesting),
+        }
+    }
+}
+
+struct FunctionBuilder<'a> {
+    program: &'a Program,
+    opcodes: &'a [u32],
+    nesting: &'a Nesting,
+    end: usize,
+    blocks: Vec<Block>,
+    // block of each instruction of the function
+    block_of: Vec<usize>,
+    start: usize,
+}
+
+impl<'a> FunctionBuilder<'a> {
+    fn new(program: &'a Program, opcodes: &'a [u32], nesting: &'a Nesting, start: usize, end: usize) -> Self {
+        // blocks start at the targets of branches and after instructions
+        // that branch
+        let leader = |i: usize| {
+            if i == start {
+                return true;
+            }
+
+            let split_before = match opcodes[i] {
+                D3D10_SB_OPCODE_ENDIF | D3D10_SB_OPCODE_LOOP | D3D10_SB_OPCODE_ENDSWITCH => true,
+                D3D10_SB_OPCODE_CASE | D3D10_SB_OPCODE_DEFAULT => !is_case(opcodes[i - 1]),
+                _ => false,
+            };
+            let split_after = matches!(opcodes[i - 1], D3D10_SB_OPCODE_IF | D3D10_SB_OPCODE_ELSE | D3D10_SB_OPCODE_ENDLOOP | D3D10_SB_OPCODE_SWITCH |
+                D3D10_SB_OPCODE_BREAK | D3D10_SB_OPCODE_BREAKC | D3D10_SB_OPCODE_CONTINUE | D3D10_SB_OPCODE_CONTINUEC |
+                D3D10_SB_OPCODE_RET | D3D10_SB_OPCODE_RETC);
+
+            split_before || split_after
+        };
+
+        let mut blocks: Vec<Block> = Vec::new();
+        let mut block_of = Vec::with_capacity(end - start);
+        for i in start..end {
+            if leader(i) {
+                blocks.push(Block {
+                    start: i,
+                    end: i,
+                    successors: Vec::new(),
+                    predecessors: Vec::new(),
+                });
+            }
+
+            let last = blocks.len() - 1;
+            blocks[last].end = i + 1;
+            block_of.push(last);
+        }
+
+        FunctionBuilder {
+            program,
+            opcodes,
+            nesting,
+            start,
+            end,
+            blocks,
+            block_of,
+        }
+    }
+
+    // Block starting at the instruction, `None` past the end of the
+    // function.
+    fn target(&self, instruction: usize) -> Option<usize> {
+        if instruction < self.end {
+            Some(self.block_of[instruction - self.start])
+        } else {
+            None
+        }
+    }
+
+    fn break_target(&self, instruction: usize) -> Option<usize> {
+        let open = self.nesting.targets[&instruction];
+        let close = self.nesting.ends[&open];
+
+        match self.opcodes[open] {
+            D3D10_SB_OPCODE_LOOP => self.target(close + 1),
+            _ => self.target(close),
+        }
+    }
+
+    fn connect(&mut self) {
+        for b in 0..self.blocks.len() {
+            let last = self.blocks[b].end - 1;
+            let next = if b + 1 < self.blocks.len() { Some(b + 1) } else { None };
+            let nesting = self.nesting;
+
+            let successors = match self.opcodes[last] {
+                D3D10_SB_OPCODE_IF => {
+                    let otherwise = match nesting.elses.get(&last) {
+                        Some(&otherwise) => otherwise + 1,
+                        None => nesting.ends[&last],
+                    };
+                    vec![next, self.target(otherwise)]
+                }
+                D3D10_SB_OPCODE_ELSE => vec![self.target(nesting.ends[&last])],
+                D3D10_SB_OPCODE_ENDLOOP => vec![self.target(nesting.opens[&last])],
+                D3D10_SB_OPCODE_BREAK => vec![self.break_target(last)],
+                D3D10_SB_OPCODE_BREAKC => vec![self.break_target(last), next],
+                D3D10_SB_OPCODE_CONTINUE => vec![self.target(nesting.targets[&last])],
+                D3D10_SB_OPCODE_CONTINUEC => vec![self.target(nesting.targets[&last]), next],
+                D3D10_SB_OPCODE_RET => vec![],
+                D3D10_SB_OPCODE_SWITCH => {
+                    let cases = &nesting.cases[&last];
+                    let mut successors: Vec<Option<usize>> = cases.iter().map(|&case| self.target(case)).collect();
+
+                    if !nesting.defaults.contains(&last) {
+                        successors.push(self.target(nesting.ends[&last]));
+                    }
+                    successors
+                }
+                _ => vec![next],
+            };
+
+            for successor in successors.into_iter().flatten() {
+                if !self.blocks[b].successors.contains(&successor) {
+                    self.blocks[b].successors.push(successor);
+                    self.blocks[successor].predecessors.push(b);
+                }
+            }
+        }
+    }
+
+    // Regions of blocks `from..to`, `entered` is a loop header whose `loop`
+    // is already represented by the enclosing region.
+    fn sequence(&self, from: usize, to: usize, entered: Option<usize>) -> Region {
+        let mut items = Vec::new();
+        let mut b = from;
+
+        while b < to {
+            let first = self.blocks[b].start;
+            if self.opcodes[first] == D3D10_SB_OPCODE_LOOP && entered != Some(b) {
+                let close = self.block_of[self.nesting.ends[&first] - self.start];
+                items.push(Region::Loop { body: Box::new(self.sequence(b, close + 1, Some(b))) });
+                b = close + 1;
+                continue;
+            }
+
+            let last = self.blocks[b].end - 1;
+            match self.opcodes[last] {
+                D3D10_SB_OPCODE_IF => {
+                    let endif = self.block_of[self.nesting.ends[&last] - self.start];
+                    let (then, otherwise) = match self.nesting.elses.get(&last) {
+                        Some(&otherwise) => {
+                            let otherwise = self.block_of[otherwise - self.start];
+                            (self.sequence(b + 1, otherwise + 1, None), Some(Box::new(self.sequence(otherwise + 1, endif, None))))
+                        }
+                        None => (self.sequence(b + 1, endif, None), None),
+                    };
+
+                    items.push(Region::If { header: b, then: Box::new(then), otherwise });
+                    b = endif;
+                }
+                D3D10_SB_OPCODE_SWITCH => {
+                    let endswitch = self.block_of[self.nesting.ends[&last] - self.start];
+                    let starts: Vec<usize> = self.nesting.cases[&last].iter().map(|&case| self.block_of[case - self.start]).collect();
+
+                    let cases = starts
+                        .iter()
+                        .enumerate()
+                        .map(|(c, &start)| {
+                            let end = starts.get(c + 1).cloned().unwrap_or(endswitch);
+                            let labels = (self.blocks[start].start..self.blocks[start].end)
+                                .take_while(|&i| is_case(self.opcodes[i]))
+                                .map(|i| match self.program.instructions[i].operands.first() {
+                                    Some(Operand::Imm32(values)) if self.opcodes[i] == D3D10_SB_OPCODE_CASE => values.first().cloned(),
+                                    _ => None,
+                                })
+                                .collect();
+
+                            Case { labels, body: self.sequence(start, end, None) }
+                        })
+                        .collect();
+
+                    items.push(Region::Switch { header: b, cases });
+                    b = endswitch;
+                }
+                _ => {
+                    items.push(Region::Block(b));
+                    b += 1;
+                }
+            }
+        }
+
+        Region::Sequence(items)
+    }
+}
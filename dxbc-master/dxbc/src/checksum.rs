@@ -1,9 +1,29 @@
 use md5;
 
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::{error, fmt};
+
+// the checksum is stored right after the magic
+const CHECKSUM_OFFSET: usize = 4;
+// the hashed contents start after the checksum
+const CONTENTS_OFFSET: usize = CHECKSUM_OFFSET + 16;
+
+/// Checksum of the contents of `module`, everything past the checksum in its header.
+///
+/// Panics if `module` is shorter than the header up to the checksum, see `try_checksum`.
 pub fn checksum(module: &[u8]) -> [u32; 4] {
+    match try_checksum(module) {
+        Ok(checksum) => checksum,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+/// Checksum of the contents of `module`, fails if it is too short to have any.
+pub fn try_checksum(module: &[u8]) -> Result<[u32; 4], ChecksumMismatch> {
     let mut cxt = md5::Context::new();
 
-    let module = &module[0x14..];
+    let module = module.get(CONTENTS_OFFSET..).ok_or(ChecksumMismatch::Truncated(module.len()))?;
     let bits = (module.len() * 8) as u32;
     let full_chunk_sz = module.len() as u32 & 0xffffffc0;
     cxt.consume(&module[..full_chunk_sz as usize]);
@@ -16,11 +36,8 @@ pub fn checksum(module: &[u8]) -> [u32; 4] {
         cxt.consume(&last_data[..last_chunk_sz as usize]);
         cxt.consume(&md5::PADDING[..padding_sz as usize]);
 
+        // the length goes into an extra block of zeros
         let mut input = [0u32; 16];
-        unsafe {
-            ::std::ptr::copy(cxt.input.as_ptr(), input.as_mut_ptr() as *mut u8, 64);
-        }
-
         input[0] = bits;
         input[15] = (bits >> 2) | 1;
 
@@ -50,6 +67,65 @@ pub fn checksum(module: &[u8]) -> [u32; 4] {
         md5::transform(&mut cxt.buffer, &input);
     }
 
-    cxt.buffer
+    Ok(cxt.buffer)
 }
 
+
+/// Why the checksum of a container can't be confirmed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChecksumMismatch {
+    /// Container of the given length is too short to hold a checksum.
+    Truncated(usize),
+    /// Checksum stored in a container header differs from the one of its contents.
+    Contents {
+        stored: [u32; 4],
+        computed: [u32; 4],
+    },
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChecksumMismatch::Truncated(len) => write!(f, "container of {} bytes is too short for a checksum", len),
+            ChecksumMismatch::Contents { stored, computed } => write!(
+                f,
+                "checksum mismatch: stored {:08x}{:08x}{:08x}{:08x}, computed {:08x}{:08x}{:08x}{:08x}",
+                stored[0], stored[1], stored[2], stored[3],
+                computed[0], computed[1], computed[2], computed[3],
+            ),
+        }
+    }
+}
+
+impl error::Error for ChecksumMismatch {}
+
+fn stored_checksum(module: &[u8]) -> Result<[u32; 4], ChecksumMismatch> {
+    let bytes = module.get(CHECKSUM_OFFSET..CONTENTS_OFFSET).ok_or(ChecksumMismatch::Truncated(module.len()))?;
+
+    let mut stored = [0; 4];
+    LittleEndian::read_u32_into(bytes, &mut stored);
+    Ok(stored)
+}
+
+/// Compares the checksum in the header of `module` with the one of its contents.
+pub fn verify(module: &[u8]) -> Result<(), ChecksumMismatch> {
+    let stored = stored_checksum(module)?;
+    let computed = try_checksum(module)?;
+
+    if stored == computed {
+        Ok(())
+    } else {
+        Err(ChecksumMismatch::Contents { stored, computed })
+    }
+}
+
+/// Rewrites the checksum in the header of `module`, returns whether it changed.
+pub fn fix_checksum(module: &mut [u8]) -> Result<bool, ChecksumMismatch> {
+    let computed = try_checksum(module)?;
+    if stored_checksum(module)? == computed {
+        return Ok(false);
+    }
+
+    LittleEndian::write_u32_into(&computed, &mut module[CHECKSUM_OFFSET..CONTENTS_OFFSET]);
+    Ok(true)
+}
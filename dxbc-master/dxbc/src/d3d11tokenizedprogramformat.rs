@@ -53,7 +53,7 @@ pub fn DECODE_D3D10_SB_TOKENIZED_PROGRAM_MINOR_VERSION(VerTok: DWORD) -> DWORD {
 #[inline]
 pub fn ENCODE_D3D10_SB_TOKENIZED_PROGRAM_VERSION_TOKEN(ProgType: DWORD, MajorVer: DWORD, MinorVer: DWORD) -> DWORD {
     ((ProgType << D3D10_SB_TOKENIZED_PROGRAM_TYPE_SHIFT) & D3D10_SB_TOKENIZED_PROGRAM_TYPE_MASK) |
-    (((MajorVer << D3D10_SB_TOKENIZED_PROGRAM_MAJOR_VERSION_SHIFT) & D3D10_SB_TOKENIZED_PROGRAM_MAJOR_VERSION_MASK)) |
+    ((MajorVer << D3D10_SB_TOKENIZED_PROGRAM_MAJOR_VERSION_SHIFT) & D3D10_SB_TOKENIZED_PROGRAM_MAJOR_VERSION_MASK) |
     (MinorVer & D3D10_SB_TOKENIZED_PROGRAM_MINOR_VERSION_MASK)
 }
 #[inline]
@@ -1106,6 +1106,9 @@ ENUM!{enum D3D10_SB_NAME {
     D3D11_SB_NAME_FINAL_TRI_INSIDE_TESSFACTOR = 20,
     D3D11_SB_NAME_FINAL_LINE_DETAIL_TESSFACTOR = 21,
     D3D11_SB_NAME_FINAL_LINE_DENSITY_TESSFACTOR = 22,
+    D3D12_SB_NAME_BARYCENTRICS = 23,
+    D3D12_SB_NAME_SHADINGRATE = 24,
+    D3D12_SB_NAME_CULLPRIMITIVE = 25,
 }}
 ENUM!{enum D3D10_SB_RESOURCE_DIMENSION {
     D3D10_SB_RESOURCE_DIMENSION_UNKNOWN = 0,
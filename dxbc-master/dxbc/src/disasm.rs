@@ -0,0 +1,800 @@
+use binary::*;
+use dr;
+use dr::shex::*;
+use ir;
+use d3d11tokenizedprogramformat::*;
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::fmt::Write;
+use std::slice;
+
+const OPCODE_NAMES: [&str; 232] = [
+    "add", "and", "break", "breakc", "call", "callc", "case", "continue", "continuec", "cut",
+    "default", "deriv_rtx", "deriv_rty", "discard", "div", "dp2", "dp3", "dp4", "else", "emit",
+    "emitThenCut", "endif", "endloop", "endswitch", "eq", "exp", "frc", "ftoi", "ftou", "ge",
+    "iadd", "if", "ieq", "ige", "ilt", "imad", "imax", "imin", "imul", "ine",
+    "ineg", "ishl", "ishr", "itof", "label", "ld", "ld_ms", "log", "loop", "lt",
+    "mad", "min", "max", "customdata", "mov", "movc", "mul", "ne", "nop", "not",
+    "or", "resinfo", "ret", "retc", "round_ne", "round_ni", "round_pi", "round_z", "rsq", "sample",
+    "sample_c", "sample_c_lz", "sample_l", "sample_d", "sample_b", "sqrt", "switch", "sincos", "udiv", "ult",
+    "uge", "umul", "umad", "umax", "umin", "ushr", "utof", "xor", "dcl_resource", "dcl_constantbuffer",
+    "dcl_sampler", "dcl_indexrange", "dcl_outputtopology", "dcl_inputprimitive", "dcl_maxout", "dcl_input", "dcl_input_sgv", "dcl_input_siv", "dcl_input_ps", "dcl_input_ps_sgv",
+    "dcl_input_ps_siv", "dcl_output", "dcl_output_sgv", "dcl_output_siv", "dcl_temps", "dcl_indexableTemp", "dcl_globalFlags", "", "lod", "gather4",
+    "samplepos", "sampleinfo", "", "hs_decls", "hs_control_point_phase", "hs_fork_phase", "hs_join_phase", "emit_stream", "cut_stream", "emitThenCut_stream",
+    "fcall", "bufinfo", "deriv_rtx_coarse", "deriv_rtx_fine", "deriv_rty_coarse", "deriv_rty_fine", "gather4_c", "gather4_po", "gather4_po_c", "rcp",
+    "f32tof16", "f16tof32", "uaddc", "usubb", "countbits", "firstbit_hi", "firstbit_lo", "firstbit_shi", "ubfe", "ibfe",
+    "bfi", "bfrev", "swapc", "dcl_stream", "dcl_function_body", "dcl_function_table", "dcl_interface", "dcl_input_control_point_count", "dcl_output_control_point_count", "dcl_tessellator_domain",
+    "dcl_tessellator_partitioning", "dcl_tessellator_output_primitive", "dcl_hs_max_tessfactor", "dcl_hs_fork_phase_instance_count", "dcl_hs_join_phase_instance_count", "dcl_thread_group", "dcl_uav_typed", "dcl_uav_raw", "dcl_uav_structured", "dcl_tgsm_raw",
+    "dcl_tgsm_structured", "dcl_resource_raw", "dcl_resource_structured", "ld_uav_typed", "store_uav_typed", "ld_raw", "store_raw", "ld_structured", "store_structured", "atomic_and",
+    "atomic_or", "atomic_xor", "atomic_cmp_store", "atomic_iadd", "atomic_imax", "atomic_imin", "atomic_umax", "atomic_umin", "imm_atomic_alloc", "imm_atomic_consume",
+    "imm_atomic_iadd", "imm_atomic_and", "imm_atomic_or", "imm_atomic_xor", "imm_atomic_exch", "imm_atomic_cmp_exch", "imm_atomic_imax", "imm_atomic_imin", "imm_atomic_umax", "imm_atomic_umin",
+    "sync", "dadd", "dmax", "dmin", "dmul", "deq", "dge", "dlt", "dne", "dmov",
+    "dmovc", "dtof", "ftod", "eval_snapped", "eval_sample_index", "eval_centroid", "dcl_gsinstances", "abort", "debug_break", "",
+    "ddiv", "dfma", "drcp", "msad", "dtoi", "dtou", "itod", "utod", "", "gather4_s",
+    "gather4_c_s", "gather4_po_s", "gather4_po_c_s", "ld_s", "ld_ms_s", "ld_uav_typed_s", "ld_raw_s", "ld_structured_s", "sample_l_s", "sample_c_lz_s",
+    "sample_cl_s", "sample_b_cl_s",
+];
+
+/// Returns the assembly mnemonic of an opcode, `None` for reserved or unknown
+/// opcodes.
+pub fn opcode_name(opcode: u32) -> Option<&'static str> {
+    match OPCODE_NAMES.get(opcode as usize) {
+        Some(name) if !name.is_empty() => Some(name),
+        _ => None,
+    }
+}
+
+pub(crate) const COMPONENTS: [char; 4] = ['x', 'y', 'z', 'w'];
+
+// Signature masks are printed positionally, `xy  ` or `  zw`.
+fn signature_mask(mask: u8) -> String {
+    (0..4).map(|c| if mask & (1 << c) != 0 { COMPONENTS[c] } else { ' ' }).collect()
+}
+
+pub fn operand_prefix(ty: u32) -> &'static str {
+    match ty {
+        D3D10_SB_OPERAND_TYPE_TEMP => "r",
+        D3D10_SB_OPERAND_TYPE_INPUT => "v",
+        D3D10_SB_OPERAND_TYPE_OUTPUT => "o",
+        D3D10_SB_OPERAND_TYPE_INDEXABLE_TEMP => "x",
+        D3D10_SB_OPERAND_TYPE_SAMPLER => "s",
+        D3D10_SB_OPERAND_TYPE_RESOURCE => "t",
+        D3D10_SB_OPERAND_TYPE_CONSTANT_BUFFER => "cb",
+        D3D10_SB_OPERAND_TYPE_IMMEDIATE_CONSTANT_BUFFER => "icb",
+        D3D10_SB_OPERAND_TYPE_LABEL => "l",
+        D3D10_SB_OPERAND_TYPE_INPUT_PRIMITIVEID => "vPrim",
+        D3D10_SB_OPERAND_TYPE_OUTPUT_DEPTH => "oDepth",
+        D3D10_SB_OPERAND_TYPE_NULL => "null",
+        D3D10_SB_OPERAND_TYPE_RASTERIZER => "rasterizer",
+        D3D10_SB_OPERAND_TYPE_OUTPUT_COVERAGE_MASK => "oMask",
+        D3D11_SB_OPERAND_TYPE_STREAM => "m",
+        D3D11_SB_OPERAND_TYPE_FUNCTION_BODY => "fb",
+        D3D11_SB_OPERAND_TYPE_FUNCTION_TABLE => "ft",
+        D3D11_SB_OPERAND_TYPE_INTERFACE => "fp",
+        D3D11_SB_OPERAND_TYPE_FUNCTION_INPUT => "fi",
+        D3D11_SB_OPERAND_TYPE_FUNCTION_OUTPUT => "fo",
+        D3D11_SB_OPERAND_TYPE_OUTPUT_CONTROL_POINT_ID => "vOutputControlPointID",
+        D3D11_SB_OPERAND_TYPE_INPUT_FORK_INSTANCE_ID => "vForkInstanceID",
+        D3D11_SB_OPERAND_TYPE_INPUT_JOIN_INSTANCE_ID => "vJoinInstanceID",
+        D3D11_SB_OPERAND_TYPE_INPUT_CONTROL_POINT => "vicp",
+        D3D11_SB_OPERAND_TYPE_OUTPUT_CONTROL_POINT => "vocp",
+        D3D11_SB_OPERAND_TYPE_INPUT_PATCH_CONSTANT => "vpc",
+        D3D11_SB_OPERAND_TYPE_INPUT_DOMAIN_POINT => "vDomain",
+        D3D11_SB_OPERAND_TYPE_THIS_POINTER => "this",
+        D3D11_SB_OPERAND_TYPE_UNORDERED_ACCESS_VIEW => "u",
+        D3D11_SB_OPERAND_TYPE_THREAD_GROUP_SHARED_MEMORY => "g",
+        D3D11_SB_OPERAND_TYPE_INPUT_THREAD_ID => "vThreadID",
+        D3D11_SB_OPERAND_TYPE_INPUT_THREAD_GROUP_ID => "vThreadGroupID",
+        D3D11_SB_OPERAND_TYPE_INPUT_THREAD_ID_IN_GROUP => "vThreadIDInGroup",
+        D3D11_SB_OPERAND_TYPE_INPUT_COVERAGE_MASK => "vCoverage",
+        D3D11_SB_OPERAND_TYPE_INPUT_THREAD_ID_IN_GROUP_FLATTENED => "vThreadIDInGroupFlattened",
+        D3D11_SB_OPERAND_TYPE_INPUT_GS_INSTANCE_ID => "vGSInstanceID",
+        D3D11_SB_OPERAND_TYPE_OUTPUT_DEPTH_GREATER_EQUAL => "oDepthGE",
+        D3D11_SB_OPERAND_TYPE_OUTPUT_DEPTH_LESS_EQUAL => "oDepthLE",
+        D3D11_SB_OPERAND_TYPE_CYCLE_COUNTER => "vCycleCounter",
+        _ => "?",
+    }
+}
+
+pub(crate) fn resource_dimension_name(dimension: u32) -> &'static str {
+    match dimension {
+        D3D10_SB_RESOURCE_DIMENSION_BUFFER => "buffer",
+        D3D10_SB_RESOURCE_DIMENSION_TEXTURE1D => "texture1d",
+        D3D10_SB_RESOURCE_DIMENSION_TEXTURE2D => "texture2d",
+        D3D10_SB_RESOURCE_DIMENSION_TEXTURE2DMS => "texture2dms",
+        D3D10_SB_RESOURCE_DIMENSION_TEXTURE3D => "texture3d",
+        D3D10_SB_RESOURCE_DIMENSION_TEXTURECUBE => "texturecube",
+        D3D10_SB_RESOURCE_DIMENSION_TEXTURE1DARRAY => "texture1darray",
+        D3D10_SB_RESOURCE_DIMENSION_TEXTURE2DARRAY => "texture2darray",
+        D3D10_SB_RESOURCE_DIMENSION_TEXTURE2DMSARRAY => "texture2dmsarray",
+        D3D10_SB_RESOURCE_DIMENSION_TEXTURECUBEARRAY => "texturecubearray",
+        D3D11_SB_RESOURCE_DIMENSION_RAW_BUFFER => "raw_buffer",
+        D3D11_SB_RESOURCE_DIMENSION_STRUCTURED_BUFFER => "structured_buffer",
+        _ => "unknown",
+    }
+}
+
+pub(crate) fn return_type_name(ty: u32) -> &'static str {
+    match ty {
+        D3D10_SB_RETURN_TYPE_UNORM => "unorm",
+        D3D10_SB_RETURN_TYPE_SNORM => "snorm",
+        D3D10_SB_RETURN_TYPE_SINT => "sint",
+        D3D10_SB_RETURN_TYPE_UINT => "uint",
+        D3D10_SB_RETURN_TYPE_FLOAT => "float",
+        D3D10_SB_RETURN_TYPE_MIXED => "mixed",
+        D3D11_SB_RETURN_TYPE_DOUBLE => "double",
+        D3D11_SB_RETURN_TYPE_CONTINUED => "continued",
+        D3D11_SB_RETURN_TYPE_UNUSED => "unused",
+        _ => "unknown",
+    }
+}
+
+pub(crate) fn system_value_name(name: u32) -> &'static str {
+    match name {
+        D3D10_SB_NAME_POSITION => "position",
+        D3D10_SB_NAME_CLIP_DISTANCE => "clip_distance",
+        D3D10_SB_NAME_CULL_DISTANCE => "cull_distance",
+        D3D10_SB_NAME_RENDER_TARGET_ARRAY_INDEX => "rendertarget_array_index",
+        D3D10_SB_NAME_VIEWPORT_ARRAY_INDEX => "viewport_array_index",
+        D3D10_SB_NAME_VERTEX_ID => "vertex_id",
+        D3D10_SB_NAME_PRIMITIVE_ID => "primitive_id",
+        D3D10_SB_NAME_INSTANCE_ID => "instance_id",
+        D3D10_SB_NAME_IS_FRONT_FACE => "is_front_face",
+        D3D10_SB_NAME_SAMPLE_INDEX => "sampleIndex",
+        D3D11_SB_NAME_FINAL_QUAD_U_EQ_0_EDGE_TESSFACTOR => "finalQuadUeq0EdgeTessFactor",
+        D3D11_SB_NAME_FINAL_QUAD_V_EQ_0_EDGE_TESSFACTOR => "finalQuadVeq0EdgeTessFactor",
+        D3D11_SB_NAME_FINAL_QUAD_U_EQ_1_EDGE_TESSFACTOR => "finalQuadUeq1EdgeTessFactor",
+        D3D11_SB_NAME_FINAL_QUAD_V_EQ_1_EDGE_TESSFACTOR => "finalQuadVeq1EdgeTessFactor",
+        D3D11_SB_NAME_FINAL_QUAD_U_INSIDE_TESSFACTOR => "finalQuadUInsideTessFactor",
+        D3D11_SB_NAME_FINAL_QUAD_V_INSIDE_TESSFACTOR => "finalQuadVInsideTessFactor",
+        D3D11_SB_NAME_FINAL_TRI_U_EQ_0_EDGE_TESSFACTOR => "finalTriUeq0EdgeTessFactor",
+        D3D11_SB_NAME_FINAL_TRI_V_EQ_0_EDGE_TESSFACTOR => "finalTriVeq0EdgeTessFactor",
+        D3D11_SB_NAME_FINAL_TRI_W_EQ_0_EDGE_TESSFACTOR => "finalTriWeq0EdgeTessFactor",
+        D3D11_SB_NAME_FINAL_TRI_INSIDE_TESSFACTOR => "finalTriInsideTessFactor",
+        D3D11_SB_NAME_FINAL_LINE_DETAIL_TESSFACTOR => "finalLineDetailTessFactor",
+        D3D11_SB_NAME_FINAL_LINE_DENSITY_TESSFACTOR => "finalLineDensityTessFactor",
+        D3D12_SB_NAME_BARYCENTRICS => "barycentrics",
+        D3D12_SB_NAME_SHADINGRATE => "shadingrate",
+        D3D12_SB_NAME_CULLPRIMITIVE => "cullprimitive",
+        _ => "undefined",
+    }
+}
+
+pub(crate) fn interpolation_name(mode: u32) -> &'static str {
+    match mode {
+        D3D10_SB_INTERPOLATION_CONSTANT => "constant",
+        D3D10_SB_INTERPOLATION_LINEAR => "linear",
+        D3D10_SB_INTERPOLATION_LINEAR_CENTROID => "linear centroid",
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE => "linear noperspective",
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_CENTROID => "linear noperspective centroid",
+        D3D10_SB_INTERPOLATION_LINEAR_SAMPLE => "linear sample",
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_SAMPLE => "linear noperspective sample",
+        _ => "undefined",
+    }
+}
+
+pub(crate) fn primitive_name(primitive: u32) -> String {
+    match primitive {
+        D3D10_SB_PRIMITIVE_POINT => "point".to_owned(),
+        D3D10_SB_PRIMITIVE_LINE => "line".to_owned(),
+        D3D10_SB_PRIMITIVE_TRIANGLE => "triangle".to_owned(),
+        D3D10_SB_PRIMITIVE_LINE_ADJ => "lineadj".to_owned(),
+        D3D10_SB_PRIMITIVE_TRIANGLE_ADJ => "triangleadj".to_owned(),
+        D3D11_SB_PRIMITIVE_1_CONTROL_POINT_PATCH..=D3D11_SB_PRIMITIVE_32_CONTROL_POINT_PATCH => {
+            format!("patch{}", primitive - D3D11_SB_PRIMITIVE_1_CONTROL_POINT_PATCH + 1)
+        }
+        _ => "undefined".to_owned(),
+    }
+}
+
+pub(crate) fn topology_name(topology: u32) -> &'static str {
+    match topology {
+        D3D10_SB_PRIMITIVE_TOPOLOGY_POINTLIST => "pointlist",
+        D3D10_SB_PRIMITIVE_TOPOLOGY_LINELIST => "linelist",
+        D3D10_SB_PRIMITIVE_TOPOLOGY_LINESTRIP => "linestrip",
+        D3D10_SB_PRIMITIVE_TOPOLOGY_TRIANGLELIST => "trianglelist",
+        D3D10_SB_PRIMITIVE_TOPOLOGY_TRIANGLESTRIP => "trianglestrip",
+        D3D10_SB_PRIMITIVE_TOPOLOGY_LINELIST_ADJ => "linelist_adj",
+        D3D10_SB_PRIMITIVE_TOPOLOGY_LINESTRIP_ADJ => "linestrip_adj",
+        D3D10_SB_PRIMITIVE_TOPOLOGY_TRIANGLELIST_ADJ => "trianglelist_adj",
+        D3D10_SB_PRIMITIVE_TOPOLOGY_TRIANGLESTRIP_ADJ => "trianglestrip_adj",
+        _ => "undefined",
+    }
+}
+
+pub(crate) fn shader_prefix(program_type: u32) -> &'static str {
+    match program_type {
+        D3D10_SB_PIXEL_SHADER => "ps",
+        D3D10_SB_VERTEX_SHADER => "vs",
+        D3D10_SB_GEOMETRY_SHADER => "gs",
+        D3D11_SB_HULL_SHADER => "hs",
+        D3D11_SB_DOMAIN_SHADER => "ds",
+        D3D11_SB_COMPUTE_SHADER => "cs",
+        _ => "xs",
+    }
+}
+
+fn variable_type_name(ty: &dr::ShaderType) -> String {
+    use dr::ShaderVariableClass as Class;
+    use dr::ShaderVariableType as Type;
+
+    if ty.class == Class::Struct {
+        return match ty.name {
+            Some(name) => format!("struct {}", name),
+            None => "struct".to_owned(),
+        };
+    }
+
+    let base = match ty.ty {
+        Type::Void => "void",
+        Type::Bool => "bool",
+        Type::Int => "int",
+        Type::Float => "float",
+        Type::UInt => "uint",
+        Type::UInt8 => "uint8",
+        Type::Double => "double",
+        Type::String => "String",
+        Type::Texture => "texture",
+        Type::Texture1D => "Texture1D",
+        Type::Texture2D => "Texture2D",
+        Type::Texture3D => "Texture3D",
+        Type::TextureCube => "TextureCube",
+        Type::Sampler => "SamplerState",
+        _ => "<unknown>",
+    };
+
+    match ty.class {
+        Class::Vector => format!("{}{}", base, ty.columns),
+        Class::MatrixRows => format!("row_major {}{}x{}", base, ty.rows, ty.columns),
+        Class::MatrixColumns => format!("{}{}x{}", base, ty.rows, ty.columns),
+        Class::InterfacePointer => ty.name.unwrap_or(base).to_owned(),
+        _ => base.to_owned(),
+    }
+}
+
+fn array_suffix(ty: &dr::ShaderType) -> String {
+    if ty.count > 0 {
+        format!("[{}]", ty.count)
+    } else {
+        String::new()
+    }
+}
+
+/// Produces the same text listing as the Microsoft shader compiler's
+/// disassembler (`fxc /dumpbin`).
+///
+/// The reflection comment header is written as the chunks arrive, the listing
+/// can be retrieved with `source` once parsing finished.
+pub struct FxcConsumer {
+    source: String,
+    lossless: bool,
+    indent: usize,
+    instruction_slots: Option<u32>,
+    // patch constants are outputs of hull shaders and inputs of domain
+    // shaders, the table waits for the program type
+    patch_constants: Option<dr::IOsgnChunk>,
+    // chunks printed as bytes after the listing
+    chunks: Vec<(String, Vec<u8>)>,
+}
+
+impl Default for FxcConsumer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FxcConsumer {
+    pub fn new() -> Self {
+        FxcConsumer {
+            source: String::new(),
+            lossless: false,
+            indent: 0,
+            instruction_slots: None,
+            patch_constants: None,
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Adds what the listing of fxc loses as comments, so `asm::assemble`
+    /// rebuilds the same container: the reflection data fxc doesn't print,
+    /// the chunks without a listing such as `STAT` and float literals six
+    /// decimals don't hold.
+    pub fn lossless() -> Self {
+        FxcConsumer { lossless: true, ..FxcConsumer::new() }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.source.push_str("  ");
+        }
+        self.source.push_str(text);
+        self.source.push('\n');
+    }
+
+    fn comment(&mut self, text: &str) {
+        let _ = writeln!(self.source, "//{}", text);
+    }
+
+    // Parts of the RDEF header and binding flags the tables don't show,
+    // only those differing from what the assembler assumes.
+    fn write_reflection(&mut self, rdef: &dr::RdefChunk) {
+        let names = |flag: dr::ShaderInputFlags| -> Vec<&str> {
+            rdef.resource_bindings
+                .iter()
+                .filter(|binding| dr::ShaderInputFlags::from_bits_truncate(binding.input_flags).contains(flag))
+                .map(|binding| binding.name)
+                .collect()
+        };
+        let user_packed = names(dr::ShaderInputFlags::USER_PACKED);
+        let unused = names(dr::ShaderInputFlags::UNUSED);
+        let sizes = rdef.rd11.filter(|&rd11| rd11 != dr::RD11_HEADER);
+
+        if rdef.flags == 0x100 && sizes.is_none() && user_packed.is_empty() && unused.is_empty() {
+            return;
+        }
+
+        self.comment("");
+        self.comment(" Reflection:");
+        self.comment("");
+        if rdef.flags != 0x100 {
+            self.comment(&format!(" Flags            0x{:08x}", rdef.flags));
+        }
+        if let Some(sizes) = sizes {
+            let sizes: Vec<String> = sizes.iter().map(u32::to_string).collect();
+            self.comment(&format!(" Descriptors      {}", sizes.join(" ")));
+        }
+        if !user_packed.is_empty() {
+            self.comment(&format!(" User packed      {}", user_packed.join(" ")));
+        }
+        if !unused.is_empty() {
+            self.comment(&format!(" Unused           {}", unused.join(" ")));
+        }
+        self.comment("");
+    }
+
+    fn write_members(&mut self, members: &[dr::ShaderTypeMember], depth: usize) {
+        for member in members {
+            self.write_variable(member.name, &member.ty, member.offset, None, depth);
+        }
+    }
+
+    fn write_variable(&mut self, name: &str, ty: &dr::ShaderType, offset: u32, size: Option<(u32, bool)>, depth: usize) {
+        let indent = "    ".repeat(depth);
+
+        if ty.class == dr::ShaderVariableClass::Struct {
+            self.comment(&format!("   {}{}", indent, variable_type_name(ty)));
+            self.comment(&format!("   {}{{", indent));
+            self.comment("");
+            self.write_members(&ty.members, depth + 1);
+            self.comment("");
+        }
+
+        let declaration = if ty.class == dr::ShaderVariableClass::Struct {
+            format!("{}}} {}{};", indent, name, array_suffix(ty))
+        } else {
+            format!("{}{} {}{};", indent, variable_type_name(ty), name, array_suffix(ty))
+        };
+
+        match size {
+            Some((size, used)) => self.comment(&format!(
+                "   {:<35}// Offset: {:>4} Size: {:>5}{}",
+                declaration,
+                offset,
+                size,
+                if used { "" } else { " [unused]" },
+            )),
+            None => self.comment(&format!("   {:<35}// Offset: {:>4}", declaration, offset)),
+        }
+    }
+
+    // Signatures with streams get a leading stream column.
+    fn write_signature(&mut self, title: &str, signature: &dr::IOsgnChunk, output: bool, streams: bool) {
+        self.comment("");
+        self.comment(&format!(" {}:", title));
+        self.comment("");
+        if streams {
+            self.comment(" Stream Name                 Index   Mask Register SysValue  Format   Used");
+            self.comment(" ------ -------------------- ----- ------ -------- -------- ------- ------");
+        } else {
+            self.comment(" Name                 Index   Mask Register SysValue  Format   Used");
+            self.comment(" -------------------- ----- ------ -------- -------- ------- ------");
+        }
+
+        for element in &signature.elements {
+            let system_value = match element.semantic_type {
+                dr::SemanticName::Undefined => {
+                    // fxc reports pixel shader outputs by their semantic
+                    match &*element.name.to_lowercase() {
+                        "sv_target" => "TARGET",
+                        "sv_depth" => "DEPTH",
+                        "sv_coverage" => "COVERAGE",
+                        "sv_depthgreaterequal" => "DEPTHGE",
+                        "sv_depthlessequal" => "DEPTHLE",
+                        _ => "NONE",
+                    }
+                }
+                dr::SemanticName::Position => "POS",
+                dr::SemanticName::ClipDistance => "CLIPDST",
+                dr::SemanticName::CullDistance => "CULLDST",
+                dr::SemanticName::RenderTargetArrayIndex => "RTINDEX",
+                dr::SemanticName::ViewportArrayIndex => "VPINDEX",
+                dr::SemanticName::VertexId => "VERTID",
+                dr::SemanticName::PrimitiveId => "PRIMID",
+                dr::SemanticName::InstanceId => "INSTID",
+                dr::SemanticName::IsFrontFace => "FFACE",
+                dr::SemanticName::SampleIndex => "SAMPLE",
+                dr::SemanticName::FinalQuadEdgeTessfactor => "QUADEDGE",
+                dr::SemanticName::FinalQuadInsideTessfactor => "QUADINT",
+                dr::SemanticName::FinalTriEdgeTessfactor => "TRIEDGE",
+                dr::SemanticName::FinalTriInsideTessfactor => "TRIINT",
+                dr::SemanticName::FinalLineDetailTessfactor => "LINEDET",
+                dr::SemanticName::FinalLineDensityTessfactor => "LINEDEN",
+                dr::SemanticName::Target => "TARGET",
+                dr::SemanticName::Depth => "DEPTH",
+                dr::SemanticName::Coverage => "COVERAGE",
+                dr::SemanticName::DepthGreaterEqual => "DEPTHGE",
+                dr::SemanticName::DepthLessEqual => "DEPTHLE",
+                dr::SemanticName::Barycentrics => "BARYCEN",
+                dr::SemanticName::ShadingRate => "SHDINGRATE",
+                dr::SemanticName::CullPrimitive => "CULLPRIM",
+                dr::SemanticName::StencilRef => "STENCILREF",
+                dr::SemanticName::InnerCoverage => "INNERCOV",
+                dr::SemanticName::Other(_) => "UNKNOWN",
+            };
+
+            let format = match element.component_type {
+                dr::RegisterComponentType::Float32 => "float",
+                dr::RegisterComponentType::Int32 => "int",
+                dr::RegisterComponentType::Uint32 => "uint",
+                dr::RegisterComponentType::Float16 => "fp16",
+                dr::RegisterComponentType::Int16 => "int16",
+                dr::RegisterComponentType::Uint16 => "uint16",
+                dr::RegisterComponentType::Float64 => "double",
+                dr::RegisterComponentType::Int64 => "int64",
+                dr::RegisterComponentType::Uint64 => "uint64",
+                dr::RegisterComponentType::Unknown |
+                dr::RegisterComponentType::Other(_) => "unknown",
+            };
+
+            // for outputs the stored mask lists the components that are
+            // never written
+            let used = if output {
+                element.component_mask & !element.rw_mask
+            } else {
+                element.rw_mask
+            };
+
+            let (mask, register, used) = if element.register == !0 {
+                let register = match system_value {
+                    "DEPTH" => "oDepth",
+                    "DEPTHGE" => "oDepthGE",
+                    "DEPTHLE" => "oDepthLE",
+                    "COVERAGE" => if output { "oMask" } else { "vCoverage" },
+                    _ => "N/A",
+                };
+                let used = if used != 0 { "YES" } else { "NO" };
+
+                ("N/A".to_owned(), register.to_owned(), used.to_owned())
+            } else {
+                (signature_mask(element.component_mask), element.register.to_string(), signature_mask(used))
+            };
+
+            let stream = if streams { format!(" {:>6}", element.stream) } else { String::new() };
+
+            self.comment(&format!(
+                "{} {:<20} {:>5} {:>6} {:>8} {:>8} {:>7} {:>6}",
+                stream,
+                element.name,
+                element.semantic_index,
+                mask,
+                register,
+                system_value,
+                format,
+                used,
+            ));
+        }
+
+        self.comment("");
+    }
+
+    // Messages of `printf` and `errorf` calls: message id, format, string
+    // length, operand count and size, the operands and the string itself.
+    fn write_shader_message(&mut self, offset: usize, data: &[u32]) {
+        if data.len() < 5 {
+            return self.comment(" malformed shader message");
+        }
+
+        let mnemonic = match (data[0], data[1]) {
+            (D3D11_SB_SHADER_MESSAGE_ID_MESSAGE, D3D11_SB_SHADER_MESSAGE_FORMAT_ANSI_TEXT) => "message",
+            (D3D11_SB_SHADER_MESSAGE_ID_MESSAGE, D3D11_SB_SHADER_MESSAGE_FORMAT_ANSI_PRINTF) => "printf",
+            (D3D11_SB_SHADER_MESSAGE_ID_ERROR, D3D11_SB_SHADER_MESSAGE_FORMAT_ANSI_TEXT) => "error",
+            (D3D11_SB_SHADER_MESSAGE_ID_ERROR, D3D11_SB_SHADER_MESSAGE_FORMAT_ANSI_PRINTF) => "errorf",
+            (id, _) => return self.comment(&format!(" shader message {:#x}", id)),
+        };
+
+        let (chars, operand_words) = (data[2] as usize, data[4] as usize);
+        let string = match data.get(5 + operand_words..) {
+            Some(string) if string.len() * 4 > chars => string,
+            _ => return self.comment(" malformed shader message"),
+        };
+
+        let bytes = unsafe { slice::from_raw_parts(data[5..].as_ptr() as *const u8, operand_words * 4) };
+        let operands = match ir::lower_operands(offset, bytes) {
+            Ok(operands) => operands,
+            Err(_) => return self.comment(" malformed shader message"),
+        };
+
+        let bytes = unsafe { slice::from_raw_parts(string.as_ptr() as *const u8, chars) };
+        let mut text = format!("{} \"", mnemonic);
+        for &c in bytes {
+            match c {
+                b'"' => text.push_str("\\\""),
+                b'\\' => text.push_str("\\\\"),
+                b'\n' => text.push_str("\\n"),
+                b'\t' => text.push_str("\\t"),
+                0x20..=0x7e => text.push(c as char),
+                _ => text.push_str(&format!("\\x{:02x}", c)),
+            }
+        }
+        text.push('"');
+
+        for operand in operands {
+            let _ = if self.lossless { write!(text, ", {:#}", operand) } else { write!(text, ", {}", operand) };
+        }
+
+        self.line(&text);
+    }
+
+}
+
+impl Consumer for FxcConsumer {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        if let Some(pcsg) = self.patch_constants.take() {
+            self.write_signature("Patch Constant signature", &pcsg, false, false);
+        }
+
+        if let Some(slots) = self.instruction_slots {
+            let _ = writeln!(self.source, "// Approximately {} instruction slots used", slots);
+        }
+
+        for (fourcc, data) in std::mem::take(&mut self.chunks) {
+            self.comment("");
+            self.comment(&format!(" Chunk {}:", fourcc));
+            self.comment("");
+            for row in data.chunks(16) {
+                let bytes: Vec<String> = row.iter().map(|byte| format!("{:02x}", byte)).collect();
+                self.comment(&format!(" {}", bytes.join(" ")));
+            }
+        }
+
+        Action::Continue
+    }
+
+    fn consume_rdef(&mut self, rdef: &dr::RdefChunk) -> Action {
+        self.comment("");
+        self.comment(&format!(" Generated by {}", rdef.author));
+        self.comment("");
+
+        let buffers: Vec<&dr::ConstantBuffer> = rdef.constant_buffers
+            .iter()
+            .filter(|buffer| buffer.ty == dr::ConstantBufferType::ConstantBuffer as u32 ||
+                buffer.ty == dr::ConstantBufferType::TextureBuffer as u32)
+            .collect();
+
+        if !buffers.is_empty() {
+            self.comment("");
+            self.comment(" Buffer Definitions: ");
+            self.comment("");
+
+            for buffer in buffers {
+                let kind = if buffer.ty == dr::ConstantBufferType::TextureBuffer as u32 { "tbuffer" } else { "cbuffer" };
+                self.comment(&format!(" {} {}", kind, buffer.name));
+                self.comment(" {");
+                self.comment("");
+
+                for variable in &buffer.variables {
+                    let used = variable.flags.contains(dr::ShaderVariableFlags::USED);
+                    self.write_variable(variable.name, &variable.ty, variable.start_offset, Some((variable.byte_size, used)), 0);
+                }
+
+                self.comment("");
+                self.comment(" }");
+                self.comment("");
+            }
+        }
+
+        if !rdef.resource_bindings.is_empty() {
+            self.comment("");
+            self.comment(" Resource Bindings:");
+            self.comment("");
+            self.comment(" Name                                 Type  Format         Dim      HLSL Bind  Count");
+            self.comment(" ------------------------------ ---------- ------- ----------- -------------- ------");
+
+            for binding in &rdef.resource_bindings {
+                let flags = dr::ShaderInputFlags::from_bits_truncate(binding.input_flags);
+                let components = ((flags & dr::ShaderInputFlags::TEXTURE_COMPONENTS).bits() >> 2) + 1;
+
+                let (ty, prefix) = match binding.input_type {
+                    0 => ("cbuffer", "cb"),
+                    1 => ("tbuffer", "t"),
+                    2 | 5 | 7 => ("texture", "t"),
+                    3 => ("sampler", "s"),
+                    _ => ("UAV", "u"),
+                };
+
+                let format = match binding.input_type {
+                    0 | 1 | 3 => "NA".to_owned(),
+                    5 | 6 | 9 | 10 | 11 => "struct".to_owned(),
+                    7 | 8 => "byte".to_owned(),
+                    _ => {
+                        let name = match binding.return_type {
+                            D3D10_SB_RETURN_TYPE_SINT => "sint",
+                            ty => return_type_name(ty),
+                        };
+
+                        if components > 1 {
+                            format!("{}{}", name, components)
+                        } else {
+                            name.to_owned()
+                        }
+                    }
+                };
+
+                let dimension = match binding.input_type {
+                    0 | 1 | 3 => "NA",
+                    5 | 7 => "r/o",
+                    6 | 8 | 9 | 10 | 11 => "r/w",
+                    _ => match binding.view_dimension {
+                        1 | 11 => "buf",
+                        2 => "1d",
+                        3 => "1darray",
+                        4 => "2d",
+                        5 => "2darray",
+                        6 => "2dMS",
+                        7 => "2dMSarray",
+                        8 => "3d",
+                        9 => "cube",
+                        10 => "cubearray",
+                        _ => "NA",
+                    },
+                };
+
+                self.comment(&format!(
+                    " {:<30} {:>10} {:>7} {:>11} {:>14} {:>6} ",
+                    binding.name,
+                    ty,
+                    format,
+                    dimension,
+                    format!("{}{}", prefix, binding.bind_point),
+                    binding.bind_count,
+                ));
+            }
+
+            self.comment("");
+        }
+
+        if self.lossless {
+            self.write_reflection(rdef);
+        }
+
+        Action::Continue
+    }
+
+    fn consume_isgn(&mut self, isgn: &dr::IOsgnChunk) -> Action {
+        self.comment("");
+        self.write_signature("Input signature", isgn, false, false);
+        Action::Continue
+    }
+
+    fn consume_osgn(&mut self, osgn: &dr::IOsgnChunk) -> Action {
+        self.write_signature("Output signature", osgn, true, false);
+        Action::Continue
+    }
+
+    fn consume_osg5(&mut self, osg5: &dr::IOsgnChunk) -> Action {
+        self.write_signature("Output signature", osg5, true, true);
+        Action::Continue
+    }
+
+    fn consume_pcsg(&mut self, pcsg: &dr::IOsgnChunk) -> Action {
+        self.patch_constants = Some(pcsg.clone());
+        Action::Continue
+    }
+
+    fn consume_shex(&mut self, header: &dr::ShexHeader) -> Action {
+        if let Some(pcsg) = self.patch_constants.take() {
+            let output = header.program_type as u32 == D3D11_SB_HULL_SHADER;
+            self.write_signature("Patch Constant signature", &pcsg, output, false);
+        }
+
+        self.line(&format!("{}_{}_{}", shader_prefix(header.program_type as u32), header.major, header.minor));
+        Action::Continue
+    }
+
+    fn consume_stat(&mut self, stat: &dr::IStatChunk) -> Action {
+        self.instruction_slots = Some(stat.instruction_count);
+        if !self.lossless {
+            return Action::Continue;
+        }
+
+        let counts = stat.counts();
+        let mut data = vec![0; counts.len() * 4];
+        LittleEndian::write_u32_into(&counts, &mut data);
+        self.chunks.push(("STAT".to_owned(), data));
+        Action::Continue
+    }
+
+    fn consume_unknown(&mut self, fourcc: &[u8], data: &[u8]) -> Action {
+        if !self.lossless {
+            return Action::Continue;
+        }
+        self.chunks.push((String::from_utf8_lossy(fourcc).into_owned(), data.to_vec()));
+        Action::Continue
+    }
+
+    fn consume_instruction(&mut self, offset: u32, instruction: dr::SparseInstruction) -> Action {
+        let ty = instruction.opcode.get_opcode_type();
+
+        let text = match instruction.operands {
+            Operands::CustomData(ref data) if data.class == D3D11_SB_CUSTOMDATA_SHADER_MESSAGE => {
+                self.write_shader_message(offset as usize, &data.data);
+                return Action::Continue;
+            }
+            Operands::Unknown => format!("// unknown opcode {}", ty),
+            _ => match ir::lower(offset, &instruction) {
+                Ok(Some(ref statement)) if self.lossless => format!("{:#}", statement),
+                Ok(Some(statement)) => statement.to_string(),
+                Ok(None) => return Action::Continue,
+                Err(err) => return Action::Error(Box::new(err)),
+            },
+        };
+
+        match ty {
+            D3D10_SB_OPCODE_ELSE | D3D10_SB_OPCODE_ENDIF | D3D10_SB_OPCODE_ENDLOOP | D3D10_SB_OPCODE_ENDSWITCH => {
+                self.indent = self.indent.saturating_sub(1);
+            }
+            _ => {}
+        }
+
+        for line in text.lines() {
+            self.line(line);
+        }
+
+        match ty {
+            D3D10_SB_OPCODE_IF | D3D10_SB_OPCODE_ELSE | D3D10_SB_OPCODE_LOOP | D3D10_SB_OPCODE_SWITCH => {
+                self.indent += 1;
+            }
+            _ => {}
+        }
+
+        Action::Continue
+    }
+}
+
+/// Disassembles a shader into the text format printed by fxc.
+pub fn disassemble(bytes: &[u8]) -> Result<String, State> {
+    listing(bytes, FxcConsumer::new())
+}
+
+/// Same as `disassemble` with the comments of `FxcConsumer::lossless`, the
+/// listing assembles back into the same container.
+pub fn disassemble_lossless(bytes: &[u8]) -> Result<String, State> {
+    listing(bytes, FxcConsumer::lossless())
+}
+
+fn listing(bytes: &[u8], mut consumer: FxcConsumer) -> Result<String, State> {
+    {
+        let mut parser = Parser::new(bytes, &mut consumer);
+        parser.parse()?;
+    }
+
+    Ok(consumer.source)
+}
@@ -1,33 +1,83 @@
-use dr::shex::{ResourceDimension, ResourceReturnType};
-use dr::{IStatChunk, IOsgnChunk, RdefChunk};
+use dr::shex::{
+    ConstantBufferIndexPattern, InterpolationMode, NameToken, ResinfoReturnType, ResourceDimension,
+    ResourceReturnType, SamplerMode, TestBoolean,
+};
+use dr::{IStatChunk, IOsgnChunk, RdefChunk, ShaderType, SignatureFormat, DescriptorSizes};
 
+use binary::State;
 use byteorder::{ByteOrder, LittleEndian};
-use checksum;
 use d3d11tokenizedprogramformat::*;
 
-use std::{slice, mem};
+use std::{error, fmt, slice, mem};
+use std::collections::HashMap;
 
 const DXBC_MAGIC: u32 = 0x43425844;
 const RDEF_MAGIC: u32 = 0x46454452;
 const RD11_MAGIC: u32 = 0x31314452;
 const ISGN_MAGIC: u32 = 0x4e475349;
 const OSGN_MAGIC: u32 = 0x4e47534f;
+const ISG1_MAGIC: u32 = 0x31475349;
+const OSG1_MAGIC: u32 = 0x3147534f;
+const OSG5_MAGIC: u32 = 0x3547534f;
+const PCSG_MAGIC: u32 = 0x47534350;
 const SHEX_MAGIC: u32 = 0x58454853;
+const SHDR_MAGIC: u32 = 0x52444853;
 const STAT_MAGIC: u32 = 0x54415453;
 
 pub struct Builder<'a> {
     rdef: Option<RdefChunk<'a>>,
-    isgn: Option<IOsgnChunk>,
-    osgn: Option<IOsgnChunk>,
+    // signatures with the magic of the chunk they are written to
+    isgn: Option<(u32, IOsgnChunk)>,
+    osgn: Option<(u32, IOsgnChunk)>,
+    pcsg: Option<IOsgnChunk>,
     stat: Option<IStatChunk>,
     shex: Option<ShexChunk>,
-    code: Vec<u32>,
+    // chunks written as they are, after all the others
+    chunks: Vec<([u8; 4], Vec<u8>)>,
 }
 
+/// Why a container couldn't be built.
+#[derive(Debug)]
+pub enum BuildError {
+    /// The `RD11` sizes of the reflection chunk can't be written.
+    Descriptors(State),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::Descriptors(ref state) => write!(f, "invalid reflection descriptor sizes: {}", state),
+        }
+    }
+}
+
+impl error::Error for BuildError {}
+
 pub struct DxbcModule {
     pub dwords: Vec<u32>,
 }
 
+// Strings written with `write_packed_str` are stored back to back and shared
+// between everything referencing the same text. Bytes that don't fill a
+// whole dword yet wait in `tail` until `flush_strs` pads them.
+#[derive(Default)]
+pub struct StringTable {
+    offsets: HashMap<String, u32>,
+    tail: Vec<u8>,
+}
+
+impl StringTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for DxbcModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DxbcModule {
     pub fn new() -> Self {
         DxbcModule {
@@ -47,6 +97,11 @@ impl DxbcModule {
         self.dwords.push(val);
     }
 
+    pub fn write_u64(&mut self, val: u64) {
+        self.write_u32(val as u32);
+        self.write_u32((val >> 32) as u32);
+    }
+
     pub fn set_u32_slice(&mut self, offset: usize, val: &[u32]) {
         self.dwords[offset..].copy_from_slice(val);
     }
@@ -60,11 +115,11 @@ impl DxbcModule {
 
         // NOTE: fxc pads with 0xABAB.. pattern
         for chunk in text.as_bytes().chunks(4) {
-            let data = match chunk {
-                &[d, c, b, a] => ((a as u32) << 24) | ((b as u32) << 16) | ((c as u32) << 8) | d as u32,
-                &[c, b, a] => ((a as u32) << 16) | ((b as u32) << 8) | (c as u32),
-                &[b, a] => ((a as u32) << 8) | (b as u32),
-                &[a] => a as u32,
+            let data = match *chunk {
+                [d, c, b, a] => ((a as u32) << 24) | ((b as u32) << 16) | ((c as u32) << 8) | d as u32,
+                [c, b, a] => ((a as u32) << 16) | ((b as u32) << 8) | (c as u32),
+                [b, a] => ((a as u32) << 8) | (b as u32),
+                [a] => a as u32,
                 _ => unreachable!()
             };
 
@@ -83,6 +138,39 @@ impl DxbcModule {
         len
     }
 
+    /// Appends a null terminated string to the string run, returning its
+    /// byte offset relative to `chunk_start`.
+    pub fn write_packed_str(&mut self, strings: &mut StringTable, chunk_start: usize, text: &str) -> u32 {
+        if let Some(&offset) = strings.offsets.get(text) {
+            return offset;
+        }
+
+        let offset = (4 * (self.position() - chunk_start) + strings.tail.len()) as u32;
+        strings.offsets.insert(text.to_owned(), offset);
+        strings.tail.extend_from_slice(text.as_bytes());
+        strings.tail.push(0);
+
+        while strings.tail.len() >= 4 {
+            let word = LittleEndian::read_u32(&strings.tail);
+            self.write_u32(word);
+            strings.tail.drain(..4);
+        }
+
+        offset
+    }
+
+    /// Pads the pending string bytes to a dword, like fxc with 0xab.
+    pub fn flush_strs(&mut self, strings: &mut StringTable) {
+        if strings.tail.is_empty() {
+            return;
+        }
+
+        strings.tail.resize(4, 0xab);
+        let word = LittleEndian::read_u32(&strings.tail);
+        self.write_u32(word);
+        strings.tail.clear();
+    }
+
     pub fn write_stat(&mut self, stat: &IStatChunk) {
         self.write_u32(STAT_MAGIC);
 
@@ -90,35 +178,27 @@ impl DxbcModule {
         self.write_u32(0);
         let chunk_start = self.position();
 
-        self.write_u32(stat.instruction_count);
-        self.write_u32(stat.temp_register_count);
-        self.write_u32(stat.def_count);
-        self.write_u32(stat.dcl_count);
-        self.write_u32(stat.float_instruction_count);
-        self.write_u32(stat.int_instruction_count);
-        self.write_u32(stat.uint_instruction_count);
-        self.write_u32(stat.static_flow_control_count);
-        self.write_u32(stat.dynamic_flow_control_count);
-        self.write_u32(stat.macro_instruction_count);
-        self.write_u32(stat.temp_array_count);
-        self.write_u32(stat.array_instruction_count);
-        self.write_u32(stat.cut_instruction_count);
-        self.write_u32(stat.emit_instruction_count);
-        self.write_u32(stat.texture_normal_instructions);
-        self.write_u32(stat.texture_load_instructions);
-        self.write_u32(stat.texture_comp_instructions);
-        self.write_u32(stat.texture_bias_instructions);
-        self.write_u32(stat.texture_gradient_instructions);
-
-        for _ in 0..18 {
-            self.write_u32(0);
+        for count in stat.counts() {
+            self.write_u32(count);
         }
 
         let end_pos = self.position();
         self.set_u32(stat_size_pos, 4 * (end_pos - chunk_start) as u32);
     }
 
-    pub fn write_rdef(&mut self, rdef: &RdefChunk) {
+    // Chunk contents are padded with zeros to whole dwords.
+    pub fn write_chunk(&mut self, fourcc: [u8; 4], data: &[u8]) {
+        self.write_u32(LittleEndian::read_u32(&fourcc));
+        self.write_u32(data.len() as u32);
+
+        for word in data.chunks(4) {
+            let mut bytes = [0; 4];
+            bytes[..word.len()].copy_from_slice(word);
+            self.write_u32(LittleEndian::read_u32(&bytes));
+        }
+    }
+
+    pub fn write_rdef(&mut self, rdef: &RdefChunk) -> Result<(), BuildError> {
         self.write_u32(RDEF_MAGIC);
         let rdef_size_pos = self.position();
         self.write_u32(0);
@@ -131,8 +211,8 @@ impl DxbcModule {
         self.write_u32(0);
 
         let version_tok = (((rdef.shader_ty as u32) << 16) & 0xffff0000) |
-                          (((rdef.major as u32) << 4) & 0x000000f0) |
-                          (rdef.minor as u32 & 0x0000000f);
+                          (((rdef.major as u32) << 8) & 0x0000ff00) |
+                          (rdef.minor as u32 & 0x000000ff);
         self.write_u32(version_tok);
         self.write_u32(rdef.flags);
         let author_pos = self.position();
@@ -140,43 +220,164 @@ impl DxbcModule {
 
         if let Some(rd11) = rdef.rd11 {
             self.write_u32(RD11_MAGIC);
-            self.write_u32(60);
-            self.write_u32(24);
-            self.write_u32(32);
-            self.write_u32(40);
-            self.write_u32(36);
-            self.write_u32(12);
-            self.write_u32(0);
-            // self.write_u32(rd11[0]);
-            // self.write_u32(rd11[1]);
-            // self.write_u32(rd11[2]);
-            // self.write_u32(rd11[3]);
-            // self.write_u32(rd11[4]);
-            // self.write_u32(rd11[5]);
-            // self.write_u32(rd11[6]);
+            for &word in &rd11 {
+                self.write_u32(word);
+            }
         }
 
-        let constant_buffers_loc = (self.position() - chunk_start) as u32;
-        self.set_u32(constant_buffers_pos, constant_buffers_loc);
-        for constant_buffer in &rdef.constant_buffers {
-            // TODO: 
-        }
+        let sizes = DescriptorSizes::from_rd11(rdef.rd11).map_err(BuildError::Descriptors)?;
+        let mut strings = StringTable::new();
 
-        let resource_bindings_loc = (self.position() - chunk_start) as u32;
+        // fxc lays out the binding descriptors first, followed by all the
+        // names of bindings and buffers
+        let resource_bindings_loc = 4 * (self.position() - chunk_start) as u32;
         self.set_u32(resource_bindings_pos, resource_bindings_loc);
+        let mut binding_name_positions = Vec::new();
         for resource_binding in &rdef.resource_bindings {
-            // TODO: 
+            binding_name_positions.push(self.position());
+            self.write_u32(0);
+            self.write_u32(resource_binding.input_type);
+            self.write_u32(resource_binding.return_type);
+            self.write_u32(resource_binding.view_dimension);
+            self.write_u32(resource_binding.sample_count);
+            self.write_u32(resource_binding.bind_point);
+            self.write_u32(resource_binding.bind_count);
+            self.write_u32(resource_binding.input_flags);
+        }
+
+        for (resource_binding, pos) in rdef.resource_bindings.iter().zip(binding_name_positions) {
+            let name = self.write_packed_str(&mut strings, chunk_start, resource_binding.name);
+            self.set_u32(pos, name);
         }
 
-        let author_loc = 4 * (self.position() - chunk_start) as u32;
+        let constant_buffer_names: Vec<u32> = rdef.constant_buffers
+            .iter()
+            .map(|constant_buffer| self.write_packed_str(&mut strings, chunk_start, constant_buffer.name))
+            .collect();
+        self.flush_strs(&mut strings);
+
+        if !rdef.constant_buffers.is_empty() {
+            let constant_buffers_loc = 4 * (self.position() - chunk_start) as u32;
+            self.set_u32(constant_buffers_pos, constant_buffers_loc);
+        }
+
+        let mut variables_positions = Vec::new();
+        for (constant_buffer, &name) in rdef.constant_buffers.iter().zip(&constant_buffer_names) {
+            self.write_u32(name);
+            self.write_u32(constant_buffer.variables.len() as u32);
+            variables_positions.push(self.position());
+            self.write_u32(0);
+            self.write_u32(constant_buffer.byte_size);
+            self.write_u32(constant_buffer.flags);
+            self.write_u32(constant_buffer.ty);
+        }
+
+        // each buffer is followed by its variable descriptors, then the name
+        // and type of every variable, identical types are only written once
+        let mut types = Vec::new();
+        for (constant_buffer, variables_pos) in rdef.constant_buffers.iter().zip(variables_positions) {
+            self.flush_strs(&mut strings);
+            let variables_loc = 4 * (self.position() - chunk_start) as u32;
+            self.set_u32(variables_pos, variables_loc);
+
+            let mut variable_positions = Vec::new();
+            for variable in &constant_buffer.variables {
+                variable_positions.push(self.position());
+                self.write_u32(0);
+                self.write_u32(variable.start_offset);
+                self.write_u32(variable.byte_size);
+                self.write_u32(variable.flags.bits());
+                self.write_u32(0);
+                // default values are not emitted
+                self.write_u32(0);
+
+                if sizes.variable >= 40 {
+                    self.write_u32(variable.start_texture);
+                    self.write_u32(variable.texture_size);
+                    self.write_u32(variable.start_sampler);
+                    self.write_u32(variable.sampler_size);
+                }
+            }
+
+            for (variable, pos) in constant_buffer.variables.iter().zip(variable_positions) {
+                let name = self.write_packed_str(&mut strings, chunk_start, variable.name);
+                let ty = self.write_type(&variable.ty, &mut types, &mut strings, chunk_start, sizes);
+                self.set_u32(pos, name);
+                self.set_u32(pos + 4, ty);
+            }
+        }
+
+        let author_loc = self.write_packed_str(&mut strings, chunk_start, rdef.author);
         self.set_u32(author_pos, author_loc);
-        self.write_str(rdef.author);
+        self.flush_strs(&mut strings);
 
         let end_pos = self.position();
         self.set_u32(rdef_size_pos, 4 * (end_pos - chunk_start) as u32);
+
+        Ok(())
+    }
+
+    fn write_type<'a>(
+        &mut self,
+        ty: &'a ShaderType<'a>,
+        types: &mut Vec<(&'a ShaderType<'a>, u32)>,
+        strings: &mut StringTable,
+        chunk_start: usize,
+        sizes: DescriptorSizes,
+    ) -> u32 {
+        if let Some(&(_, offset)) = types.iter().find(|&&(known, _)| known == ty) {
+            return offset;
+        }
+
+        let name = match ty.name {
+            Some(name) if sizes.ty >= 36 => self.write_packed_str(strings, chunk_start, name),
+            _ => 0,
+        };
+
+        let mut members = Vec::new();
+        for member in &ty.members {
+            let name = self.write_packed_str(strings, chunk_start, member.name);
+            let member_ty = self.write_type(&member.ty, types, strings, chunk_start, sizes);
+            members.push((name, member_ty, member.offset));
+        }
+        self.flush_strs(strings);
+
+        let member_offset = if members.is_empty() {
+            0
+        } else {
+            4 * (self.position() - chunk_start) as u32
+        };
+        for (name, member_ty, offset) in members {
+            self.write_u32(name);
+            self.write_u32(member_ty);
+            self.write_u32(offset);
+        }
+
+        let offset = 4 * (self.position() - chunk_start) as u32;
+        self.write_u32(((ty.ty as u32) << 16) | ty.class as u32);
+        self.write_u32(((ty.columns as u32) << 16) | ty.rows as u32);
+        self.write_u32(((ty.members.len() as u32) << 16) | ty.count as u32);
+        self.write_u32(member_offset);
+
+        if sizes.ty >= 36 {
+            for _ in 0..4 {
+                self.write_u32(0);
+            }
+            self.write_u32(name);
+        }
+
+        types.push((ty, offset));
+        offset
     }
 
+    /// Writes a signature chunk, the element layout follows from `magic`.
     pub fn write_iosgn(&mut self, chunk: &IOsgnChunk, magic: u32) {
+        let format = match magic {
+            OSG5_MAGIC => SignatureFormat::Stream,
+            ISG1_MAGIC | OSG1_MAGIC => SignatureFormat::Precision,
+            _ => SignatureFormat::Basic,
+        };
+
         self.write_u32(magic);
         let chunk_sz_pos = self.position();
         self.write_u32(0);
@@ -187,21 +388,28 @@ impl DxbcModule {
 
         let mut string_positions = Vec::new();
         for element in &chunk.elements {
+            if format != SignatureFormat::Basic {
+                self.write_u32(element.stream);
+            }
             string_positions.push(self.position());
             self.write_u32(0);
             self.write_u32(element.semantic_index);
-            self.write_u32(element.semantic_type as u32);
-            self.write_u32(element.component_type as u32);
+            self.write_u32(element.semantic_type.to_word());
+            self.write_u32(element.component_type.to_word());
             self.write_u32(element.register);
             let mask_tok = ((element.rw_mask as u32) << 8) | (element.component_mask as u32);
             self.write_u32(mask_tok);
+            if format == SignatureFormat::Precision {
+                self.write_u32(element.min_precision.to_word());
+            }
         }
 
+        let mut strings = StringTable::new();
         for (element, pos) in chunk.elements.iter().zip(string_positions) {
-            let name_offset = self.position() - chunk_start;
-            self.set_u32(pos, 4 * name_offset as u32);
-            self.write_str(&element.name);
+            let name_offset = self.write_packed_str(&mut strings, chunk_start, &element.name);
+            self.set_u32(pos, name_offset);
         }
+        self.flush_strs(&mut strings);
 
         let chunk_sz = self.position() - chunk_start;
         self.set_u32(chunk_sz_pos, 4 * chunk_sz as u32);
@@ -215,15 +423,33 @@ impl DxbcModule {
         self.write_iosgn(chunk, OSGN_MAGIC);
     }
 
+    pub fn write_isg1(&mut self, chunk: &IOsgnChunk) {
+        self.write_iosgn(chunk, ISG1_MAGIC);
+    }
+
+    pub fn write_osg1(&mut self, chunk: &IOsgnChunk) {
+        self.write_iosgn(chunk, OSG1_MAGIC);
+    }
+
+    pub fn write_osg5(&mut self, chunk: &IOsgnChunk) {
+        self.write_iosgn(chunk, OSG5_MAGIC);
+    }
+
+    pub fn write_pcsg(&mut self, chunk: &IOsgnChunk) {
+        self.write_iosgn(chunk, PCSG_MAGIC);
+    }
+
+    /// Writes the program as `SHDR` for shader model 4 and `SHEX` otherwise,
+    /// like the compiler does.
     pub fn write_shex(&mut self, chunk: &ShexChunk) {
-        self.write_u32(SHEX_MAGIC);
+        self.write_u32(if chunk.major < 5 { SHDR_MAGIC } else { SHEX_MAGIC });
         let chunk_sz_pos = self.position();
         self.write_u32(0);
         let chunk_start = self.position();
 
         self.write_u32(
             ENCODE_D3D10_SB_TOKENIZED_PROGRAM_VERSION_TOKEN(
-                D3D10_SB_VERTEX_SHADER, 5, 0
+                chunk.program_type as u32, chunk.major as u32, chunk.minor as u32
             )
         );
 
@@ -244,12 +470,12 @@ impl DxbcModule {
             ENCODE_D3D10_SB_TOKENIZED_INSTRUCTION_LENGTH(instruction_len) |
             ENCODE_D3D10_SB_INSTRUCTION_SATURATE(saturated as u32);
 
-        if extended.len() != 0 {
+        if !extended.is_empty() {
             opcode |= ENCODE_D3D10_SB_OPCODE_EXTENDED(1);
         }
 
         if let Some(test) = test {
-            opcode |= ENCODE_D3D10_SB_INSTRUCTION_TEST_BOOLEAN(test as u32);
+            opcode |= ENCODE_D3D10_SB_INSTRUCTION_TEST_BOOLEAN(test);
         }
 
         self.write_u32(opcode);
@@ -291,14 +517,20 @@ impl DxbcModule {
         }
     }
 
-    pub fn write_operand(&mut self, op: u32, modifier: Modifier, component_mode: NumComponent, immediates: &[Immediate]) {
+    pub fn write_operand(&mut self, op: u32, modifier: Modifier, precision: MinPrecision, component_mode: NumComponent, immediates: &[Immediate]) {
         debug_assert!(immediates.len() < 4);
 
         let mut operand = ENCODE_D3D10_SB_OPERAND_TYPE(op);
 
-        if let Modifier::None = modifier {
+        let operand_modifier = match modifier {
+            Modifier::None => D3D10_SB_OPERAND_MODIFIER_NONE,
+            Modifier::Neg => D3D10_SB_OPERAND_MODIFIER_NEG,
+            Modifier::Abs => D3D10_SB_OPERAND_MODIFIER_ABS,
+            Modifier::AbsNeg => D3D10_SB_OPERAND_MODIFIER_ABSNEG,
+        };
 
-        } else {
+        let extended = operand_modifier != D3D10_SB_OPERAND_MODIFIER_NONE || precision != MinPrecision::Default;
+        if extended {
             operand |= ENCODE_D3D10_SB_OPERAND_EXTENDED(1);
         }
 
@@ -332,6 +564,9 @@ impl DxbcModule {
 
         operand |= ENCODE_D3D10_SB_OPERAND_INDEX_DIMENSION(immediate_arity);
 
+        // components are given as the X, Y, Z and W bits
+        let index = |comp: u8| comp.trailing_zeros().saturating_sub(4);
+
         match component_mode {
             NumComponent::D0 | NumComponent::D1 => {},
             NumComponent::D4(mode) => match mode {
@@ -343,28 +578,31 @@ impl DxbcModule {
                 ComponentMode::Swizzle(x, y, z, w) => {
                     operand |=
                         ENCODE_D3D10_SB_OPERAND_4_COMPONENT_SELECTION_MODE(D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_MODE) |
-                        ENCODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE(x as u32 >> 5, y as u32 >> 5, z as u32 >> 5, w as u32 >> 5);
+                        ENCODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE(index(x), index(y), index(z), index(w));
                 },
                 ComponentMode::Select(comp) => {
                     operand |=
                         ENCODE_D3D10_SB_OPERAND_4_COMPONENT_SELECTION_MODE(D3D10_SB_OPERAND_4_COMPONENT_SELECT_1_MODE) |
-                        ENCODE_D3D10_SB_OPERAND_4_COMPONENT_SELECT_1(comp as u32 >> 5);
+                        ENCODE_D3D10_SB_OPERAND_4_COMPONENT_SELECT_1(index(comp));
                 },
             },
         }
 
         self.write_u32(operand);
 
-        let operand_modifier = match modifier {
-            Modifier::None => None,
-            Modifier::Neg => Some(D3D10_SB_OPERAND_MODIFIER_NEG),
-            Modifier::Abs => Some(D3D10_SB_OPERAND_MODIFIER_ABS),
-            Modifier::AbsNeg => Some(D3D10_SB_OPERAND_MODIFIER_ABSNEG),
-        };
+        if extended {
+            let precision = match precision {
+                MinPrecision::Default => D3D11_SB_OPERAND_MIN_PRECISION_DEFAULT,
+                MinPrecision::Float16 => D3D11_SB_OPERAND_MIN_PRECISION_FLOAT_16,
+                MinPrecision::Float2_8 => D3D11_SB_OPERAND_MIN_PRECISION_FLOAT_2_8,
+                MinPrecision::Sint16 => D3D11_SB_OPERAND_MIN_PRECISION_SINT_16,
+                MinPrecision::Uint16 => D3D11_SB_OPERAND_MIN_PRECISION_UINT_16,
+            };
 
-        if let Some(modifier) = operand_modifier {
             self.write_u32(
-                ENCODE_D3D10_SB_EXTENDED_OPERAND_MODIFIER(modifier)
+                ENCODE_D3D10_SB_EXTENDED_OPERAND_TYPE(D3D10_SB_EXTENDED_OPERAND_MODIFIER) |
+                ENCODE_D3D10_SB_EXTENDED_OPERAND_MODIFIER(operand_modifier) |
+                ENCODE_D3D11_SB_OPERAND_MIN_PRECISION(precision)
             );
         }
 
@@ -375,11 +613,10 @@ impl DxbcModule {
                     None
                 }
                 &Immediate::U64(val) => {
-                    // TODO: u64
-                    self.write_u32(val as u32);
+                    self.write_u64(val);
                     None
                 }
-                &Immediate::Relative(ref rel) => {
+                Immediate::Relative(rel) => {
                     Some(rel)
                 }
                 &Immediate::U32Relative(val, ref rel) => {
@@ -387,16 +624,13 @@ impl DxbcModule {
                     Some(rel)
                 }
                 &Immediate::U64Relative(val, ref rel) => {
-                    // TODO: u64
-                    self.write_u32(val as u32);
+                    self.write_u64(val);
                     Some(rel)
                 }
             };
 
             if let Some(relative) = relative {
-                let operand_ty = relative.get_type();
-
-                self.write_operand(operand_ty, relative.modifiers, relative.component_mode, &[]);
+                relative.encode(self);
             }
         }
     }
@@ -411,15 +645,22 @@ impl DxbcModule {
     }
 }
 
+impl<'a> Default for Builder<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> Builder<'a> {
     pub fn new() -> Self {
         Builder {
             rdef: None,
             isgn: None,
             osgn: None,
+            pcsg: None,
             shex: None,
             stat: None,
-            code: Vec::new(),
+            chunks: Vec::new(),
         }
     }
 
@@ -428,11 +669,31 @@ impl<'a> Builder<'a> {
     }
 
     pub fn set_isgn(&mut self, isgn: IOsgnChunk) {
-        self.isgn = Some(isgn);
+        self.isgn = Some((ISGN_MAGIC, isgn));
     }
 
     pub fn set_osgn(&mut self, osgn: IOsgnChunk) {
-        self.osgn = Some(osgn);
+        self.osgn = Some((OSGN_MAGIC, osgn));
+    }
+
+    /// Input signature with minimum precisions, replaces `set_isgn`.
+    pub fn set_isg1(&mut self, isg1: IOsgnChunk) {
+        self.isgn = Some((ISG1_MAGIC, isg1));
+    }
+
+    /// Output signature with streams and minimum precisions, replaces
+    /// `set_osgn`.
+    pub fn set_osg1(&mut self, osg1: IOsgnChunk) {
+        self.osgn = Some((OSG1_MAGIC, osg1));
+    }
+
+    /// Output signature with streams, replaces `set_osgn`.
+    pub fn set_osg5(&mut self, osg5: IOsgnChunk) {
+        self.osgn = Some((OSG5_MAGIC, osg5));
+    }
+
+    pub fn set_pcsg(&mut self, pcsg: IOsgnChunk) {
+        self.pcsg = Some(pcsg);
     }
 
     pub fn set_shex(&mut self, shex: ShexChunk) {
@@ -443,11 +704,17 @@ impl<'a> Builder<'a> {
         self.stat = Some(stat);
     }
 
+    /// Appends a chunk the builder doesn't know the contents of, such as
+    /// `SFI0`.
+    pub fn add_chunk(&mut self, fourcc: [u8; 4], data: Vec<u8>) {
+        self.chunks.push((fourcc, data));
+    }
+
     pub fn set_profile(&mut self) {
 
     }
 
-    pub fn module(&self) -> Result<DxbcModule, ()> {
+    pub fn module(&self) -> Result<DxbcModule, BuildError> {
         let mut module = DxbcModule::new();
 
         module.write_u32(DXBC_MAGIC);
@@ -462,38 +729,67 @@ impl<'a> Builder<'a> {
         let size_pos = module.position();
         module.write_u32(0);
 
-        let mut chunk_count = 4;
+        let chunk_count = self.rdef.is_some() as u32 +
+            self.isgn.is_some() as u32 +
+            self.osgn.is_some() as u32 +
+            self.pcsg.is_some() as u32 +
+            self.shex.is_some() as u32 +
+            self.stat.is_some() as u32 +
+            self.chunks.len() as u32;
         module.write_u32(chunk_count);
-        let chunk_count_pos = module.position();
+        let mut chunk_offset_pos = module.position();
         for _ in 0..chunk_count {
             module.write_u32(0);
         }
 
-
         if let Some(ref rdef) = self.rdef {
             let pos = module.position() * 4;
-            module.set_u32(chunk_count_pos, pos as u32);
-            module.write_rdef(rdef);
+            module.set_u32(chunk_offset_pos, pos as u32);
+            chunk_offset_pos += 1;
+            module.write_rdef(rdef)?;
         }
 
-        if let Some(ref isgn) = self.isgn {
+        if let Some((magic, ref isgn)) = self.isgn {
             let pos = module.position() * 4;
-            module.set_u32(chunk_count_pos + 1, pos as u32);
-            module.write_isgn(isgn);
+            module.set_u32(chunk_offset_pos, pos as u32);
+            chunk_offset_pos += 1;
+            module.write_iosgn(isgn, magic);
         }
 
-        if let Some(ref osgn) = self.osgn {
+        if let Some((magic, ref osgn)) = self.osgn {
             let pos = module.position() * 4;
-            module.set_u32(chunk_count_pos + 2, pos as u32);
-            module.write_osgn(osgn);
+            module.set_u32(chunk_offset_pos, pos as u32);
+            chunk_offset_pos += 1;
+            module.write_iosgn(osgn, magic);
+        }
+
+        if let Some(ref pcsg) = self.pcsg {
+            let pos = module.position() * 4;
+            module.set_u32(chunk_offset_pos, pos as u32);
+            chunk_offset_pos += 1;
+            module.write_pcsg(pcsg);
         }
 
         if let Some(ref shex) = self.shex {
             let pos = module.position() * 4;
-            module.set_u32(chunk_count_pos + 3, pos as u32);
+            module.set_u32(chunk_offset_pos, pos as u32);
+            chunk_offset_pos += 1;
             module.write_shex(shex);
         }
 
+        if let Some(ref stat) = self.stat {
+            let pos = module.position() * 4;
+            module.set_u32(chunk_offset_pos, pos as u32);
+            chunk_offset_pos += 1;
+            module.write_stat(stat);
+        }
+
+        for &(fourcc, ref data) in &self.chunks {
+            let pos = module.position() * 4;
+            module.set_u32(chunk_offset_pos, pos as u32);
+            chunk_offset_pos += 1;
+            module.write_chunk(fourcc, data);
+        }
 
         // finally, patch in size and checksum
         let len = 4 * module.dwords.len() as u32;
@@ -522,14 +818,16 @@ bitflags! {
     }
 }
 
-#[repr(u32)]
-#[derive(Debug, Copy, Clone)]
-pub enum Semantic {
-    Undefined = 0,
-    Position = 1,
+bitflags! {
+    pub struct SyncFlags: u32 {
+        const THREADS_IN_GROUP = 1 << 11;
+        const THREAD_GROUP_SHARED_MEMORY = 1 << 12;
+        const UNORDERED_ACCESS_VIEW_MEMORY_GROUP = 1 << 13;
+        const UNORDERED_ACCESS_VIEW_MEMORY_GLOBAL = 1 << 14;
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Modifier {
     None,
     Neg,
@@ -537,6 +835,15 @@ pub enum Modifier {
     AbsNeg
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MinPrecision {
+    Default,
+    Float16,
+    Float2_8,
+    Sint16,
+    Uint16,
+}
+
 bitflags! {
     pub struct Component: u8 {
         const X = 0x10;
@@ -565,10 +872,23 @@ pub enum NumComponent {
     D4(ComponentMode),
 }
 
+/// Index of an indexable register file, relative indices are offset by the
+/// selected component of the given register.
 #[derive(Debug)]
 pub enum Address {
     Constant(u32),
-    Relative(IndexOperandType)
+    Relative(IndexOperandType, u8),
+    ConstantRelative(u32, IndexOperandType, u8),
+}
+
+impl Address {
+    fn to_immediate(&self) -> Immediate {
+        match *self {
+            Address::Constant(idx) => Immediate::U32(idx),
+            Address::Relative(ref reg, comp) => Immediate::Relative(reg.to_operand(comp)),
+            Address::ConstantRelative(idx, ref reg, comp) => Immediate::U32Relative(idx, reg.to_operand(comp)),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -585,6 +905,28 @@ pub enum OperandType {
     IndexableRegister(u32, Address),
     ConstantBuffer(u32, Address),
     CustomData(Vec<u32>),
+    /// Any register file, given as its operand type and indices.
+    Raw(u32, Vec<Immediate>),
+    /// 64-bit immediates, one or four values.
+    Imm64(Vec<u64>),
+    /// Discarded destination.
+    Null,
+    /// Hull and domain shader control point inputs, `vicp[vertex][register]`.
+    InputControlPoint(Address, u32),
+    /// Hull shader control point outputs, `vocp[vertex][register]`.
+    OutputControlPoint(Address, u32),
+    /// Patch constants read by fork, join and domain phases, `vpc`.
+    InputPatchConstant(Address),
+    InputDomainPoint,
+    OutputControlPointId,
+    InputForkInstanceId,
+    InputJoinInstanceId,
+    UnorderedAccessView(u32),
+    ThreadGroupSharedMemory(u32),
+    InputThreadId,
+    InputThreadGroupId,
+    InputThreadIdInGroup,
+    InputThreadIdInGroupFlattened,
 }
 
 #[derive(Debug)]
@@ -601,90 +943,520 @@ pub enum IndexOperandType {
     Register(u32),
     Input(u32),
     Output(u32),
+    /// Scalar system values, the component is ignored.
+    OutputControlPointId,
+    ForkInstanceId,
+    JoinInstanceId,
 }
 
-#[derive(Debug)]
-pub enum Instruction {
-    DclGlobalFlags { flags: GlobalFlags },
-    DclTemps { count: u32 },
-    DclOutputSiv { register: Operand, semantic: Semantic },
-    DclInput { register: Operand },
-    Add { dest: Operand, a: Operand, b: Operand, saturated: bool },
-    Mul { dest: Operand, a: Operand, b: Operand, saturated: bool },
-    Ret
+impl IndexOperandType {
+    fn to_operand(&self, comp: u8) -> Operand {
+        let component_mode = NumComponent::D4(ComponentMode::Select(comp));
+        match *self {
+            IndexOperandType::Register(reg) => Operand::register(reg, Modifier::None, component_mode),
+            IndexOperandType::Input(reg) => Operand::input(reg, Modifier::None, component_mode),
+            IndexOperandType::Output(reg) => Operand::output(reg, Modifier::None, component_mode),
+            IndexOperandType::OutputControlPointId => Operand::new(OperandType::OutputControlPointId, Modifier::None, NumComponent::D1),
+            IndexOperandType::ForkInstanceId => Operand::new(OperandType::InputForkInstanceId, Modifier::None, NumComponent::D1),
+            IndexOperandType::JoinInstanceId => Operand::new(OperandType::InputJoinInstanceId, Modifier::None, NumComponent::D1),
+        }
+    }
+}
+
+// Instructions that only differ in their opcode and operands are generated,
+// grouped by the opcode specific bits they take:
+//  - `saturate`: `saturated` flag
+//  - `resource`: `saturated` flag and extended opcodes for offsets and the
+//    resource dimension and return type of `_indexable` variants
+//  - `conditional`: the `test` of `_z` and `_nz` variants
+//  - `plain` and `bare`: nothing, `bare` ones don't have operands either
+//  - `custom`: written by `custom_parts` and `encode_custom`
+macro_rules! instructions {
+    (
+        saturate { $($sat:ident = $sat_op:ident { $($sat_field:ident),* },)* }
+        resource { $($res:ident = $res_op:ident { $($res_field:ident),* },)* }
+        conditional { $($cond:ident = $cond_op:ident { $($cond_field:ident),* },)* }
+        plain { $($plain:ident = $plain_op:ident { $($plain_field:ident),* },)* }
+        bare { $($bare:ident = $bare_op:ident,)* }
+        custom { $($custom:ident = $custom_op:ident { $($custom_field:ident: $custom_ty:ty),* },)* }
+    ) => {
+        #[derive(Debug)]
+        pub enum Instruction {
+            $($sat { $($sat_field: Operand,)* saturated: bool },)*
+            $($res { $($res_field: Operand,)* saturated: bool, extended: Vec<OpcodeEx> },)*
+            $($cond { $($cond_field: Operand,)* test: TestBoolean },)*
+            $($plain { $($plain_field: Operand),* },)*
+            $($bare,)*
+            $($custom { $($custom_field: $custom_ty),* },)*
+            /// Any instruction, `controls` holds the opcode specific bits of the
+            /// opcode token and `data` the tokens following the operands.
+            Generic { opcode: u32, controls: u32, extended: Vec<OpcodeEx>, operands: Vec<Operand>, data: Vec<u32> },
+            CustomData { class: u32, data: Vec<u32> },
+        }
+
+        impl Instruction {
+            fn get_opcode(&self) -> u32 {
+                match *self {
+                    $(Instruction::$sat { .. } => $sat_op,)*
+                    $(Instruction::$res { .. } => $res_op,)*
+                    $(Instruction::$cond { .. } => $cond_op,)*
+                    $(Instruction::$plain { .. } => $plain_op,)*
+                    $(Instruction::$bare => $bare_op,)*
+                    $(Instruction::$custom { .. } => $custom_op,)*
+                    Instruction::Generic { opcode, .. } => opcode,
+                    Instruction::CustomData { .. } => D3D10_SB_OPCODE_CUSTOMDATA,
+                }
+            }
+
+            // Opcode specific bits, extended opcodes and operands.
+            fn parts(&self) -> (u32, &[OpcodeEx], Vec<&Operand>) {
+                match *self {
+                    $(Instruction::$sat { $(ref $sat_field,)* saturated } => {
+                        (ENCODE_D3D10_SB_INSTRUCTION_SATURATE(saturated as u32), &[][..], vec![$($sat_field),*])
+                    })*
+                    $(Instruction::$res { $(ref $res_field,)* saturated, ref extended } => {
+                        (ENCODE_D3D10_SB_INSTRUCTION_SATURATE(saturated as u32), &extended[..], vec![$($res_field),*])
+                    })*
+                    $(Instruction::$cond { $(ref $cond_field,)* test } => {
+                        (ENCODE_D3D10_SB_INSTRUCTION_TEST_BOOLEAN(test as u32), &[][..], vec![$($cond_field),*])
+                    })*
+                    $(Instruction::$plain { $(ref $plain_field),* } => (0, &[][..], vec![$($plain_field),*]),)*
+                    Instruction::Generic { controls, ref extended, ref operands, .. } => {
+                        (controls, &extended[..], operands.iter().collect())
+                    }
+                    _ => {
+                        let (controls, extended) = self.custom_parts();
+                        (controls, extended, Vec::new())
+                    }
+                }
+            }
+        }
+    }
 }
 
-#[derive(Copy, Clone)]
+instructions! {
+    saturate {
+        Add = D3D10_SB_OPCODE_ADD { dst, a, b },
+        And = D3D10_SB_OPCODE_AND { dst, a, b },
+        Mul = D3D10_SB_OPCODE_MUL { dst, a, b },
+        Mad = D3D10_SB_OPCODE_MAD { dst, a, b, c },
+        Mov = D3D10_SB_OPCODE_MOV { dst, src },
+        Itof = D3D10_SB_OPCODE_ITOF { dst, src },
+        Utof = D3D10_SB_OPCODE_UTOF { dst, src },
+        Ftou = D3D10_SB_OPCODE_FTOU { dst, src },
+        DerivRtx = D3D10_SB_OPCODE_DERIV_RTX { dst, src },
+        DerivRty = D3D10_SB_OPCODE_DERIV_RTY { dst, src },
+        Div = D3D10_SB_OPCODE_DIV { dst, a, b },
+        Dp2 = D3D10_SB_OPCODE_DP2 { dst, a, b },
+        Dp3 = D3D10_SB_OPCODE_DP3 { dst, a, b },
+        Dp4 = D3D10_SB_OPCODE_DP4 { dst, a, b },
+        Eq = D3D10_SB_OPCODE_EQ { dst, a, b },
+        Exp = D3D10_SB_OPCODE_EXP { dst, src },
+        Frc = D3D10_SB_OPCODE_FRC { dst, src },
+        Ftoi = D3D10_SB_OPCODE_FTOI { dst, src },
+        Ge = D3D10_SB_OPCODE_GE { dst, a, b },
+        Iadd = D3D10_SB_OPCODE_IADD { dst, a, b },
+        Ieq = D3D10_SB_OPCODE_IEQ { dst, a, b },
+        Ige = D3D10_SB_OPCODE_IGE { dst, a, b },
+        Ilt = D3D10_SB_OPCODE_ILT { dst, a, b },
+        Imad = D3D10_SB_OPCODE_IMAD { dst, a, b, c },
+        Imax = D3D10_SB_OPCODE_IMAX { dst, a, b },
+        Imin = D3D10_SB_OPCODE_IMIN { dst, a, b },
+        Imul = D3D10_SB_OPCODE_IMUL { dst_hi, dst_lo, a, b },
+        Ine = D3D10_SB_OPCODE_INE { dst, a, b },
+        Ineg = D3D10_SB_OPCODE_INEG { dst, src },
+        Ishl = D3D10_SB_OPCODE_ISHL { dst, a, b },
+        Ishr = D3D10_SB_OPCODE_ISHR { dst, a, b },
+        Log = D3D10_SB_OPCODE_LOG { dst, src },
+        Lt = D3D10_SB_OPCODE_LT { dst, a, b },
+        Min = D3D10_SB_OPCODE_MIN { dst, a, b },
+        Max = D3D10_SB_OPCODE_MAX { dst, a, b },
+        Movc = D3D10_SB_OPCODE_MOVC { dst, a, b, c },
+        Ne = D3D10_SB_OPCODE_NE { dst, a, b },
+        Not = D3D10_SB_OPCODE_NOT { dst, src },
+        Or = D3D10_SB_OPCODE_OR { dst, a, b },
+        RoundNe = D3D10_SB_OPCODE_ROUND_NE { dst, src },
+        RoundNi = D3D10_SB_OPCODE_ROUND_NI { dst, src },
+        RoundPi = D3D10_SB_OPCODE_ROUND_PI { dst, src },
+        RoundZ = D3D10_SB_OPCODE_ROUND_Z { dst, src },
+        Rsq = D3D10_SB_OPCODE_RSQ { dst, src },
+        Sqrt = D3D10_SB_OPCODE_SQRT { dst, src },
+        Sincos = D3D10_SB_OPCODE_SINCOS { dst_sin, dst_cos, src },
+        Udiv = D3D10_SB_OPCODE_UDIV { dst_quotient, dst_remainder, a, b },
+        Ult = D3D10_SB_OPCODE_ULT { dst, a, b },
+        Uge = D3D10_SB_OPCODE_UGE { dst, a, b },
+        Umul = D3D10_SB_OPCODE_UMUL { dst_hi, dst_lo, a, b },
+        Umad = D3D10_SB_OPCODE_UMAD { dst, a, b, c },
+        Umax = D3D10_SB_OPCODE_UMAX { dst, a, b },
+        Umin = D3D10_SB_OPCODE_UMIN { dst, a, b },
+        Ushr = D3D10_SB_OPCODE_USHR { dst, a, b },
+        Xor = D3D10_SB_OPCODE_XOR { dst, a, b },
+        DerivRtxCoarse = D3D11_SB_OPCODE_DERIV_RTX_COARSE { dst, src },
+        DerivRtxFine = D3D11_SB_OPCODE_DERIV_RTX_FINE { dst, src },
+        DerivRtyCoarse = D3D11_SB_OPCODE_DERIV_RTY_COARSE { dst, src },
+        DerivRtyFine = D3D11_SB_OPCODE_DERIV_RTY_FINE { dst, src },
+        Rcp = D3D11_SB_OPCODE_RCP { dst, src },
+        F32tof16 = D3D11_SB_OPCODE_F32TOF16 { dst, src },
+        F16tof32 = D3D11_SB_OPCODE_F16TOF32 { dst, src },
+        Uaddc = D3D11_SB_OPCODE_UADDC { dst, dst_carry, a, b },
+        Usubb = D3D11_SB_OPCODE_USUBB { dst, dst_borrow, a, b },
+        Countbits = D3D11_SB_OPCODE_COUNTBITS { dst, src },
+        FirstbitHi = D3D11_SB_OPCODE_FIRSTBIT_HI { dst, src },
+        FirstbitLo = D3D11_SB_OPCODE_FIRSTBIT_LO { dst, src },
+        FirstbitShi = D3D11_SB_OPCODE_FIRSTBIT_SHI { dst, src },
+        Ubfe = D3D11_SB_OPCODE_UBFE { dst, a, b, c },
+        Ibfe = D3D11_SB_OPCODE_IBFE { dst, a, b, c },
+        Bfi = D3D11_SB_OPCODE_BFI { dst, a, b, c, d },
+        Bfrev = D3D11_SB_OPCODE_BFREV { dst, src },
+        Swapc = D3D11_SB_OPCODE_SWAPC { dst_a, dst_b, src_select, a, b },
+        Dadd = D3D11_SB_OPCODE_DADD { dst, a, b },
+        Dmax = D3D11_SB_OPCODE_DMAX { dst, a, b },
+        Dmin = D3D11_SB_OPCODE_DMIN { dst, a, b },
+        Dmul = D3D11_SB_OPCODE_DMUL { dst, a, b },
+        Deq = D3D11_SB_OPCODE_DEQ { dst, a, b },
+        Dge = D3D11_SB_OPCODE_DGE { dst, a, b },
+        Dlt = D3D11_SB_OPCODE_DLT { dst, a, b },
+        Dne = D3D11_SB_OPCODE_DNE { dst, a, b },
+        Dmov = D3D11_SB_OPCODE_DMOV { dst, src },
+        Dmovc = D3D11_SB_OPCODE_DMOVC { dst, a, b, c },
+        Dtof = D3D11_SB_OPCODE_DTOF { dst, src },
+        Ftod = D3D11_SB_OPCODE_FTOD { dst, src },
+        EvalSnapped = D3D11_SB_OPCODE_EVAL_SNAPPED { dst, src, src_offset },
+        EvalSampleIndex = D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX { dst, src, src_sample_index },
+        EvalCentroid = D3D11_SB_OPCODE_EVAL_CENTROID { dst, src },
+        Ddiv = D3D11_1_SB_OPCODE_DDIV { dst, a, b },
+        Dfma = D3D11_1_SB_OPCODE_DFMA { dst, a, b, c },
+        Drcp = D3D11_1_SB_OPCODE_DRCP { dst, src },
+        Msad = D3D11_1_SB_OPCODE_MSAD { dst, a, b, c },
+        Dtoi = D3D11_1_SB_OPCODE_DTOI { dst, src },
+        Dtou = D3D11_1_SB_OPCODE_DTOU { dst, src },
+        Itod = D3D11_1_SB_OPCODE_ITOD { dst, src },
+        Utod = D3D11_1_SB_OPCODE_UTOD { dst, src },
+        CheckAccessFullyMapped = D3DWDDM1_3_SB_OPCODE_CHECK_ACCESS_FULLY_MAPPED { dst, src },
+    }
+    resource {
+        Sample = D3D10_SB_OPCODE_SAMPLE { dst, src_address, src_resource, src_sampler },
+        SampleL = D3D10_SB_OPCODE_SAMPLE_L { dst, src_address, src_resource, src_sampler, src_lod },
+        SampleC = D3D10_SB_OPCODE_SAMPLE_C { dst, src_address, src_resource, src_sampler, src_reference },
+        SampleCLz = D3D10_SB_OPCODE_SAMPLE_C_LZ { dst, src_address, src_resource, src_sampler, src_reference },
+        SampleD = D3D10_SB_OPCODE_SAMPLE_D { dst, src_address, src_resource, src_sampler, src_x_derivative, src_y_derivative },
+        SampleB = D3D10_SB_OPCODE_SAMPLE_B { dst, src_address, src_resource, src_sampler, src_bias },
+        Ld = D3D10_SB_OPCODE_LD { dst, src_address, src_resource },
+        LdMs = D3D10_SB_OPCODE_LD_MS { dst, src_address, src_resource, src_sample_index },
+        Lod = D3D10_1_SB_OPCODE_LOD { dst, src_address, src_resource, src_sampler },
+        Gather4 = D3D10_1_SB_OPCODE_GATHER4 { dst, src_address, src_resource, src_sampler },
+        SamplePos = D3D10_1_SB_OPCODE_SAMPLE_POS { dst, src_resource, src_sample_index },
+        Bufinfo = D3D11_SB_OPCODE_BUFINFO { dst, src_resource },
+        Gather4C = D3D11_SB_OPCODE_GATHER4_C { dst, src_address, src_resource, src_sampler, src_reference },
+        Gather4Po = D3D11_SB_OPCODE_GATHER4_PO { dst, src_address, src_offset, src_resource, src_sampler },
+        Gather4PoC = D3D11_SB_OPCODE_GATHER4_PO_C { dst, src_address, src_offset, src_resource, src_sampler, src_reference },
+        LdUavTyped = D3D11_SB_OPCODE_LD_UAV_TYPED { dst, src_address, src_uav },
+        LdRaw = D3D11_SB_OPCODE_LD_RAW { dst, src_byte_offset, src },
+        LdStructured = D3D11_SB_OPCODE_LD_STRUCTURED { dst, src_index, src_byte_offset, src },
+        Gather4Feedback = D3DWDDM1_3_SB_OPCODE_GATHER4_FEEDBACK { dst, dst_feedback, src_address, src_resource, src_sampler },
+        Gather4CFeedback = D3DWDDM1_3_SB_OPCODE_GATHER4_C_FEEDBACK { dst, dst_feedback, src_address, src_resource, src_sampler, src_reference },
+        Gather4PoFeedback = D3DWDDM1_3_SB_OPCODE_GATHER4_PO_FEEDBACK { dst, dst_feedback, src_address, src_offset, src_resource, src_sampler },
+        Gather4PoCFeedback = D3DWDDM1_3_SB_OPCODE_GATHER4_PO_C_FEEDBACK { dst, dst_feedback, src_address, src_offset, src_resource, src_sampler, src_reference },
+        LdFeedback = D3DWDDM1_3_SB_OPCODE_LD_FEEDBACK { dst, dst_feedback, src_address, src_resource },
+        LdMsFeedback = D3DWDDM1_3_SB_OPCODE_LD_MS_FEEDBACK { dst, dst_feedback, src_address, src_resource, src_sample_index },
+        LdUavTypedFeedback = D3DWDDM1_3_SB_OPCODE_LD_UAV_TYPED_FEEDBACK { dst, dst_feedback, src_address, src_uav },
+        LdRawFeedback = D3DWDDM1_3_SB_OPCODE_LD_RAW_FEEDBACK { dst, dst_feedback, src_byte_offset, src },
+        LdStructuredFeedback = D3DWDDM1_3_SB_OPCODE_LD_STRUCTURED_FEEDBACK { dst, dst_feedback, src_index, src_byte_offset, src },
+        SampleLFeedback = D3DWDDM1_3_SB_OPCODE_SAMPLE_L_FEEDBACK { dst, dst_feedback, src_address, src_resource, src_sampler, src_lod },
+        SampleCLzFeedback = D3DWDDM1_3_SB_OPCODE_SAMPLE_C_LZ_FEEDBACK { dst, dst_feedback, src_address, src_resource, src_sampler, src_reference },
+        SampleClampFeedback = D3DWDDM1_3_SB_OPCODE_SAMPLE_CLAMP_FEEDBACK { dst, dst_feedback, src_address, src_resource, src_sampler, src_clamp },
+        SampleBClampFeedback = D3DWDDM1_3_SB_OPCODE_SAMPLE_B_CLAMP_FEEDBACK { dst, dst_feedback, src_address, src_resource, src_sampler, src_bias, src_clamp },
+        SampleDClampFeedback = D3DWDDM1_3_SB_OPCODE_SAMPLE_D_CLAMP_FEEDBACK { dst, dst_feedback, src_address, src_resource, src_sampler, src_x_derivative, src_y_derivative, src_clamp },
+        SampleCClampFeedback = D3DWDDM1_3_SB_OPCODE_SAMPLE_C_CLAMP_FEEDBACK { dst, dst_feedback, src_address, src_resource, src_sampler, src_reference, src_clamp },
+    }
+    conditional {
+        If = D3D10_SB_OPCODE_IF { src },
+        BreakC = D3D10_SB_OPCODE_BREAKC { src },
+        Callc = D3D10_SB_OPCODE_CALLC { src, label },
+        Continuec = D3D10_SB_OPCODE_CONTINUEC { src },
+        Discard = D3D10_SB_OPCODE_DISCARD { src },
+        Retc = D3D10_SB_OPCODE_RETC { src },
+    }
+    plain {
+        DclInput = D3D10_SB_OPCODE_DCL_INPUT { register },
+        DclOutput = D3D10_SB_OPCODE_DCL_OUTPUT { register },
+        DclStream = D3D11_SB_OPCODE_DCL_STREAM { register },
+        DclResourceRaw = D3D11_SB_OPCODE_DCL_RESOURCE_RAW { register },
+        Call = D3D10_SB_OPCODE_CALL { label },
+        Case = D3D10_SB_OPCODE_CASE { src },
+        Label = D3D10_SB_OPCODE_LABEL { label },
+        Switch = D3D10_SB_OPCODE_SWITCH { src },
+        EmitStream = D3D11_SB_OPCODE_EMIT_STREAM { stream },
+        CutStream = D3D11_SB_OPCODE_CUT_STREAM { stream },
+        EmitThenCutStream = D3D11_SB_OPCODE_EMITTHENCUT_STREAM { stream },
+        StoreUavTyped = D3D11_SB_OPCODE_STORE_UAV_TYPED { dst_uav, src_address, src_value },
+        StoreRaw = D3D11_SB_OPCODE_STORE_RAW { dst, src_byte_offset, src_value },
+        StoreStructured = D3D11_SB_OPCODE_STORE_STRUCTURED { dst, src_index, src_byte_offset, src_value },
+        AtomicAnd = D3D11_SB_OPCODE_ATOMIC_AND { dst, src_address, src_value },
+        AtomicOr = D3D11_SB_OPCODE_ATOMIC_OR { dst, src_address, src_value },
+        AtomicXor = D3D11_SB_OPCODE_ATOMIC_XOR { dst, src_address, src_value },
+        AtomicCmpStore = D3D11_SB_OPCODE_ATOMIC_CMP_STORE { dst, src_address, src_compare, src_value },
+        AtomicIadd = D3D11_SB_OPCODE_ATOMIC_IADD { dst, src_address, src_value },
+        AtomicImax = D3D11_SB_OPCODE_ATOMIC_IMAX { dst, src_address, src_value },
+        AtomicImin = D3D11_SB_OPCODE_ATOMIC_IMIN { dst, src_address, src_value },
+        AtomicUmax = D3D11_SB_OPCODE_ATOMIC_UMAX { dst, src_address, src_value },
+        AtomicUmin = D3D11_SB_OPCODE_ATOMIC_UMIN { dst, src_address, src_value },
+        ImmAtomicAlloc = D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC { dst, src_uav },
+        ImmAtomicConsume = D3D11_SB_OPCODE_IMM_ATOMIC_CONSUME { dst, src_uav },
+        ImmAtomicIadd = D3D11_SB_OPCODE_IMM_ATOMIC_IADD { dst, dst_uav, src_address, src_value },
+        ImmAtomicAnd = D3D11_SB_OPCODE_IMM_ATOMIC_AND { dst, dst_uav, src_address, src_value },
+        ImmAtomicOr = D3D11_SB_OPCODE_IMM_ATOMIC_OR { dst, dst_uav, src_address, src_value },
+        ImmAtomicXor = D3D11_SB_OPCODE_IMM_ATOMIC_XOR { dst, dst_uav, src_address, src_value },
+        ImmAtomicExch = D3D11_SB_OPCODE_IMM_ATOMIC_EXCH { dst, dst_uav, src_address, src_value },
+        ImmAtomicCmpExch = D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH { dst, dst_uav, src_address, src_compare, src_value },
+        ImmAtomicImax = D3D11_SB_OPCODE_IMM_ATOMIC_IMAX { dst, dst_uav, src_address, src_value },
+        ImmAtomicImin = D3D11_SB_OPCODE_IMM_ATOMIC_IMIN { dst, dst_uav, src_address, src_value },
+        ImmAtomicUmax = D3D11_SB_OPCODE_IMM_ATOMIC_UMAX { dst, dst_uav, src_address, src_value },
+        ImmAtomicUmin = D3D11_SB_OPCODE_IMM_ATOMIC_UMIN { dst, dst_uav, src_address, src_value },
+    }
+    bare {
+        Else = D3D10_SB_OPCODE_ELSE,
+        EndIf = D3D10_SB_OPCODE_ENDIF,
+        Loop = D3D10_SB_OPCODE_LOOP,
+        EndLoop = D3D10_SB_OPCODE_ENDLOOP,
+        Break = D3D10_SB_OPCODE_BREAK,
+        Ret = D3D10_SB_OPCODE_RET,
+        Continue = D3D10_SB_OPCODE_CONTINUE,
+        Cut = D3D10_SB_OPCODE_CUT,
+        Default = D3D10_SB_OPCODE_DEFAULT,
+        Emit = D3D10_SB_OPCODE_EMIT,
+        EmitThenCut = D3D10_SB_OPCODE_EMITTHENCUT,
+        EndSwitch = D3D10_SB_OPCODE_ENDSWITCH,
+        Nop = D3D10_SB_OPCODE_NOP,
+        HsDecls = D3D11_SB_OPCODE_HS_DECLS,
+        HsControlPointPhase = D3D11_SB_OPCODE_HS_CONTROL_POINT_PHASE,
+        HsForkPhase = D3D11_SB_OPCODE_HS_FORK_PHASE,
+        HsJoinPhase = D3D11_SB_OPCODE_HS_JOIN_PHASE,
+        Abort = D3D11_SB_OPCODE_ABORT,
+        DebugBreak = D3D11_SB_OPCODE_DEBUG_BREAK,
+    }
+    custom {
+        DclGlobalFlags = D3D10_SB_OPCODE_DCL_GLOBAL_FLAGS { flags: GlobalFlags },
+        DclTemps = D3D10_SB_OPCODE_DCL_TEMPS { count: u32 },
+        DclIndexableTemp = D3D10_SB_OPCODE_DCL_INDEXABLE_TEMP { register_index: u32, register_count: u32, num_components: u32 },
+        DclInputPs = D3D10_SB_OPCODE_DCL_INPUT_PS { register: Operand, interpolation: InterpolationMode },
+        DclInputPsSiv = D3D10_SB_OPCODE_DCL_INPUT_PS_SIV { register: Operand, interpolation: InterpolationMode, semantic: NameToken },
+        DclInputPsSgv = D3D10_SB_OPCODE_DCL_INPUT_PS_SGV { register: Operand, interpolation: InterpolationMode, semantic: NameToken },
+        DclInputSiv = D3D10_SB_OPCODE_DCL_INPUT_SIV { register: Operand, semantic: NameToken },
+        DclInputSgv = D3D10_SB_OPCODE_DCL_INPUT_SGV { register: Operand, semantic: NameToken },
+        DclOutputSiv = D3D10_SB_OPCODE_DCL_OUTPUT_SIV { register: Operand, semantic: NameToken },
+        DclOutputSgv = D3D10_SB_OPCODE_DCL_OUTPUT_SGV { register: Operand, semantic: NameToken },
+        DclIndexRange = D3D10_SB_OPCODE_DCL_INDEX_RANGE { register: Operand, register_count: u32 },
+        DclConstantBuffer = D3D10_SB_OPCODE_DCL_CONSTANT_BUFFER { register: Operand, access: ConstantBufferIndexPattern },
+        DclSampler = D3D10_SB_OPCODE_DCL_SAMPLER { register: Operand, mode: SamplerMode },
+        DclResource = D3D10_SB_OPCODE_DCL_RESOURCE { register: Operand, dimension: ResourceDimension, sample_count: u32, return_type: [ResourceReturnType; 4] },
+        DclResourceStructured = D3D11_SB_OPCODE_DCL_RESOURCE_STRUCTURED { register: Operand, stride: u32 },
+        DclUnorderedAccessViewTyped = D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_TYPED { register: Operand, dimension: ResourceDimension, globally_coherent: bool, return_type: [ResourceReturnType; 4] },
+        DclUnorderedAccessViewRaw = D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_RAW { register: Operand, globally_coherent: bool },
+        DclUnorderedAccessViewStructured = D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_STRUCTURED { register: Operand, stride: u32, globally_coherent: bool, has_counter: bool },
+        DclThreadGroupSharedMemoryRaw = D3D11_SB_OPCODE_DCL_THREAD_GROUP_SHARED_MEMORY_RAW { register: Operand, byte_count: u32 },
+        DclThreadGroupSharedMemoryStructured = D3D11_SB_OPCODE_DCL_THREAD_GROUP_SHARED_MEMORY_STRUCTURED { register: Operand, stride: u32, count: u32 },
+        DclGsInputPrimitive = D3D10_SB_OPCODE_DCL_GS_INPUT_PRIMITIVE { primitive: u32 },
+        DclGsOutputPrimitiveTopology = D3D10_SB_OPCODE_DCL_GS_OUTPUT_PRIMITIVE_TOPOLOGY { primitive_topology: u32 },
+        DclMaxOutputVertexCount = D3D10_SB_OPCODE_DCL_MAX_OUTPUT_VERTEX_COUNT { count: u32 },
+        DclGsInstanceCount = D3D11_SB_OPCODE_DCL_GS_INSTANCE_COUNT { instance_count: u32 },
+        DclInputControlPointCount = D3D11_SB_OPCODE_DCL_INPUT_CONTROL_POINT_COUNT { count: u32 },
+        DclOutputControlPointCount = D3D11_SB_OPCODE_DCL_OUTPUT_CONTROL_POINT_COUNT { count: u32 },
+        DclTessDomain = D3D11_SB_OPCODE_DCL_TESS_DOMAIN { domain: u32 },
+        DclTessPartitioning = D3D11_SB_OPCODE_DCL_TESS_PARTITIONING { partitioning: u32 },
+        DclTessOutputPrimitive = D3D11_SB_OPCODE_DCL_TESS_OUTPUT_PRIMITIVE { primitive: u32 },
+        DclHsMaxTessfactor = D3D11_SB_OPCODE_DCL_HS_MAX_TESSFACTOR { max_tessfactor: f32 },
+        DclHsForkPhaseInstanceCount = D3D11_SB_OPCODE_DCL_HS_FORK_PHASE_INSTANCE_COUNT { instance_count: u32 },
+        DclHsJoinPhaseInstanceCount = D3D11_SB_OPCODE_DCL_HS_JOIN_PHASE_INSTANCE_COUNT { instance_count: u32 },
+        DclThreadGroup = D3D11_SB_OPCODE_DCL_THREAD_GROUP { x: u32, y: u32, z: u32 },
+        DclFunctionBody = D3D11_SB_OPCODE_DCL_FUNCTION_BODY { function_body: u32 },
+        DclFunctionTable = D3D11_SB_OPCODE_DCL_FUNCTION_TABLE { function_table: u32, function_bodies: Vec<u32> },
+        DclInterface = D3D11_SB_OPCODE_DCL_INTERFACE { interface: u32, dynamically_indexed: bool, expected_table_length: u32, array_length: u32, function_tables: Vec<u32> },
+        Resinfo = D3D10_SB_OPCODE_RESINFO { dst: Operand, src_mip_level: Operand, src_resource: Operand, return_type: ResinfoReturnType, saturated: bool, extended: Vec<OpcodeEx> },
+        SampleInfo = D3D10_1_SB_OPCODE_SAMPLE_INFO { dst: Operand, src_resource: Operand, uint_return_type: bool, saturated: bool },
+        Sync = D3D11_SB_OPCODE_SYNC { flags: SyncFlags },
+        InterfaceCall = D3D11_SB_OPCODE_INTERFACE_CALL { function_index: u32, interface: Operand },
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum OpcodeEx {
     UvOffset(u32, u32, u32),
     Dimension(ResourceDimension, u32),
     ResourceReturnType(ResourceReturnType, ResourceReturnType, ResourceReturnType, ResourceReturnType),
 }
 
-
-
-
 impl Instruction {
-    fn get_opcode(&self) -> u32 {
-        match self {
-            Instruction::Add { .. } => D3D10_SB_OPCODE_ADD,
-            Instruction::Mul { .. } => D3D10_SB_OPCODE_MUL,
-            Instruction::DclGlobalFlags { .. } => D3D10_SB_OPCODE_DCL_GLOBAL_FLAGS,
-            Instruction::DclOutputSiv { .. } => D3D10_SB_OPCODE_DCL_OUTPUT_SIV,
-            Instruction::DclInput { .. } => D3D10_SB_OPCODE_DCL_INPUT,
-            Instruction::DclTemps { .. } => D3D10_SB_OPCODE_DCL_TEMPS,
-            Instruction::Ret => D3D10_SB_OPCODE_RET,
-        }
-    }
+    fn custom_parts(&self) -> (u32, &[OpcodeEx]) {
+        let controls = match *self {
+            Instruction::DclGlobalFlags { flags } => flags.bits(),
+            Instruction::DclInputPs { interpolation, .. } |
+            Instruction::DclInputPsSiv { interpolation, .. } |
+            Instruction::DclInputPsSgv { interpolation, .. } => ENCODE_D3D10_SB_INPUT_INTERPOLATION_MODE(interpolation as u32),
+            Instruction::DclConstantBuffer { access, .. } => ENCODE_D3D10_SB_D3D10_SB_CONSTANT_BUFFER_ACCESS_PATTERN(access as u32),
+            Instruction::DclSampler { mode, .. } => ENCODE_D3D10_SB_SAMPLER_MODE(mode as u32),
+            Instruction::DclResource { dimension, sample_count, .. } => {
+                ENCODE_D3D10_SB_RESOURCE_DIMENSION(dimension as u32) | ENCODE_D3D10_SB_RESOURCE_SAMPLE_COUNT(sample_count)
+            }
+            Instruction::DclUnorderedAccessViewTyped { dimension, globally_coherent, .. } => {
+                ENCODE_D3D10_SB_RESOURCE_DIMENSION(dimension as u32) | coherency(globally_coherent)
+            }
+            Instruction::DclUnorderedAccessViewRaw { globally_coherent, .. } => coherency(globally_coherent),
+            Instruction::DclUnorderedAccessViewStructured { globally_coherent, has_counter, .. } => {
+                coherency(globally_coherent) |
+                    if has_counter { ENCODE_D3D11_SB_UAV_FLAGS(D3D11_SB_UAV_HAS_ORDER_PRESERVING_COUNTER) } else { 0 }
+            }
+            Instruction::DclGsInputPrimitive { primitive } => ENCODE_D3D10_SB_GS_INPUT_PRIMITIVE(primitive),
+            Instruction::DclGsOutputPrimitiveTopology { primitive_topology } => {
+                ENCODE_D3D10_SB_GS_OUTPUT_PRIMITIVE_TOPOLOGY(primitive_topology)
+            }
+            Instruction::DclInputControlPointCount { count } => ENCODE_D3D11_SB_INPUT_CONTROL_POINT_COUNT(count),
+            Instruction::DclOutputControlPointCount { count } => ENCODE_D3D11_SB_OUTPUT_CONTROL_POINT_COUNT(count),
+            Instruction::DclTessDomain { domain } => ENCODE_D3D11_SB_TESS_DOMAIN(domain),
+            Instruction::DclTessPartitioning { partitioning } => ENCODE_D3D11_SB_TESS_PARTITIONING(partitioning),
+            Instruction::DclTessOutputPrimitive { primitive } => ENCODE_D3D11_SB_TESS_OUTPUT_PRIMITIVE(primitive),
+            Instruction::DclInterface { dynamically_indexed, .. } => ENCODE_D3D11_SB_INTERFACE_INDEXED_BIT(dynamically_indexed as u32),
+            Instruction::Resinfo { return_type, saturated, .. } => {
+                ENCODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(return_type as u32) |
+                    ENCODE_D3D10_SB_INSTRUCTION_SATURATE(saturated as u32)
+            }
+            Instruction::SampleInfo { uint_return_type, saturated, .. } => {
+                let return_type = if uint_return_type { D3D10_SB_INSTRUCTION_RETURN_UINT } else { D3D10_SB_INSTRUCTION_RETURN_FLOAT };
+                ENCODE_D3D10_SB_INSTRUCTION_RETURN_TYPE(return_type) | ENCODE_D3D10_SB_INSTRUCTION_SATURATE(saturated as u32)
+            }
+            Instruction::Sync { flags } => flags.bits(),
+            _ => 0,
+        };
 
-    fn encode_opcode(&self, module: &mut DxbcModule) {
-        let opcode = self.get_opcode();
+        let extended = match *self {
+            Instruction::Resinfo { ref extended, .. } => &extended[..],
+            _ => &[][..],
+        };
 
-        match self {
-            Instruction::Add { dest, a, b, saturated } |
-            Instruction::Mul { dest, a, b, saturated } => { module.write_opcode(opcode, 0, None, *saturated, &[]); }
+        (controls, extended)
+    }
 
-            Instruction::DclGlobalFlags { flags } => {
-                let opcode_pos = module.position();
-                module.write_opcode(opcode, 0, None, false, &[]);
+    // Operands and trailing tokens of the instructions with custom layouts.
+    fn encode_custom(&self, module: &mut DxbcModule) {
+        let return_type = |types: &[ResourceReturnType; 4]| {
+            types
+                .iter()
+                .enumerate()
+                .fold(0, |token, (idx, &ty)| token | ENCODE_D3D10_SB_RESOURCE_RETURN_TYPE(ty as u32, idx as u32))
+        };
 
-                let opcode = module.get_u32(opcode_pos);
-                module.set_u32(opcode_pos, opcode | (flags.bits() & 0x00fff800));
+        match *self {
+            Instruction::DclTemps { count } |
+            Instruction::DclMaxOutputVertexCount { count } => module.write_u32(count),
+            Instruction::DclGsInstanceCount { instance_count } |
+            Instruction::DclHsForkPhaseInstanceCount { instance_count } |
+            Instruction::DclHsJoinPhaseInstanceCount { instance_count } => module.write_u32(instance_count),
+            Instruction::DclIndexableTemp { register_index, register_count, num_components } => {
+                module.write_u32(register_index);
+                module.write_u32(register_count);
+                module.write_u32(num_components);
+            }
+            Instruction::DclInputPs { ref register, .. } |
+            Instruction::DclConstantBuffer { ref register, .. } |
+            Instruction::DclSampler { ref register, .. } |
+            Instruction::DclUnorderedAccessViewRaw { ref register, .. } => register.encode(module),
+            Instruction::DclInputPsSiv { ref register, semantic, .. } |
+            Instruction::DclInputPsSgv { ref register, semantic, .. } |
+            Instruction::DclInputSiv { ref register, semantic } |
+            Instruction::DclInputSgv { ref register, semantic } |
+            Instruction::DclOutputSiv { ref register, semantic } |
+            Instruction::DclOutputSgv { ref register, semantic } => {
+                register.encode(module);
+                module.write_u32(ENCODE_D3D10_SB_NAME(semantic.to_word()));
+            }
+            Instruction::DclIndexRange { ref register, register_count: value } |
+            Instruction::DclResourceStructured { ref register, stride: value } |
+            Instruction::DclUnorderedAccessViewStructured { ref register, stride: value, .. } |
+            Instruction::DclThreadGroupSharedMemoryRaw { ref register, byte_count: value } => {
+                register.encode(module);
+                module.write_u32(value);
+            }
+            Instruction::DclThreadGroupSharedMemoryStructured { ref register, stride, count } => {
+                register.encode(module);
+                module.write_u32(stride);
+                module.write_u32(count);
+            }
+            Instruction::DclResource { ref register, return_type: ref types, .. } |
+            Instruction::DclUnorderedAccessViewTyped { ref register, return_type: ref types, .. } => {
+                register.encode(module);
+                module.write_u32(return_type(types));
+            }
+            Instruction::DclHsMaxTessfactor { max_tessfactor } => module.write_u32(max_tessfactor.to_bits()),
+            Instruction::DclThreadGroup { x, y, z } => {
+                module.write_u32(x);
+                module.write_u32(y);
+                module.write_u32(z);
+            }
+            Instruction::DclFunctionBody { function_body } => module.write_u32(function_body),
+            Instruction::DclFunctionTable { function_table, ref function_bodies } => {
+                module.write_u32(function_table);
+                module.write_u32(function_bodies.len() as u32);
+                for &body in function_bodies {
+                    module.write_u32(body);
+                }
+            }
+            Instruction::DclInterface { interface, expected_table_length, array_length, ref function_tables, .. } => {
+                module.write_u32(interface);
+                module.write_u32(expected_table_length);
+                module.write_u32(
+                    ENCODE_D3D11_SB_INTERFACE_ARRAY_LENGTH(array_length) |
+                    ENCODE_D3D11_SB_INTERFACE_TABLE_LENGTH(function_tables.len() as u32)
+                );
+                for &table in function_tables {
+                    module.write_u32(table);
+                }
+            }
+            Instruction::Resinfo { ref dst, ref src_mip_level, ref src_resource, .. } => {
+                dst.encode(module);
+                src_mip_level.encode(module);
+                src_resource.encode(module);
+            }
+            Instruction::SampleInfo { ref dst, ref src_resource, .. } => {
+                dst.encode(module);
+                src_resource.encode(module);
+            }
+            Instruction::InterfaceCall { function_index, ref interface } => {
+                module.write_u32(function_index);
+                interface.encode(module);
+            }
+            Instruction::Generic { ref data, .. } => {
+                for &word in data {
+                    module.write_u32(word);
+                }
             }
-            Instruction::DclOutputSiv { .. } |
-            Instruction::DclInput { .. } |
-            Instruction::DclTemps { .. } |
-            Instruction::Ret => { module.write_opcode(opcode, 0, None, false, &[]); }
             _ => {}
         }
-
     }
 
     fn encode(&self, module: &mut DxbcModule) {
+        // custom data stores its length in a separate token
+        if let &Instruction::CustomData { class, ref data } = self {
+            module.write_u32(ENCODE_D3D10_SB_OPCODE_TYPE(D3D10_SB_OPCODE_CUSTOMDATA) | ENCODE_D3D10_SB_CUSTOMDATA_CLASS(class));
+            module.write_u32(2 + data.len() as u32);
+            for &word in data {
+                module.write_u32(word);
+            }
+            return;
+        }
+
         let start = module.position();
+        let (controls, extended, operands) = self.parts();
 
-        self.encode_opcode(module);
+        module.write_opcode(self.get_opcode(), 0, None, false, extended);
+        let opcode = module.get_u32(start);
+        module.set_u32(start, opcode | controls);
 
-        match self {
-            Instruction::Add { dest, a, b, .. } |
-            Instruction::Mul { dest, a, b, .. } => {
-                dest.encode(module);
-                a.encode(module);
-                b.encode(module);
-            }
-            &Instruction::DclTemps { count: val } => {
-                module.write_u32(val)
-            }
-            &Instruction::DclOutputSiv { ref register, semantic } => {
-                register.encode(module);
-                module.write_u32(semantic as u32)
-            }
-            &Instruction::DclInput { ref register } => {
-                register.encode(module);
-            }
-            _ => {}
+        for operand in operands {
+            operand.encode(module);
         }
 
+        self.encode_custom(module);
+
         // patch in instruction length
         let end = module.position();
         let sz = end - start;
@@ -694,10 +1466,19 @@ impl Instruction {
     }
 }
 
+fn coherency(globally_coherent: bool) -> u32 {
+    if globally_coherent {
+        ENCODE_D3D11_SB_ACCESS_COHERENCY_FLAGS(D3D11_SB_GLOBALLY_COHERENT_ACCESS)
+    } else {
+        0
+    }
+}
+
 #[derive(Debug)]
 pub struct Operand {
     ty: OperandType,
     modifiers: Modifier,
+    precision: MinPrecision,
     component_mode: NumComponent
 }
 
@@ -706,10 +1487,15 @@ impl Operand {
         Operand {
             ty,
             modifiers,
+            precision: MinPrecision::Default,
             component_mode
         }
     }
 
+    pub fn set_min_precision(&mut self, precision: MinPrecision) {
+        self.precision = precision;
+    }
+
     pub fn register(reg: u32, modifiers: Modifier, component_mode: NumComponent) -> Self {
         Self::new(OperandType::Register(reg), modifiers, component_mode)
     }
@@ -722,50 +1508,152 @@ impl Operand {
         Self::new(OperandType::Output(reg), modifiers, component_mode)
     }
 
+    /// Encoded tokens of the operand, for operands embedded in custom data.
+    pub fn dwords(&self) -> Vec<u32> {
+        let mut module = DxbcModule::new();
+        self.encode(&mut module);
+        module.dwords
+    }
+
     fn encode(&self, module: &mut DxbcModule) {
         match &self.ty {
             &OperandType::Register(reg) => {
-                module.write_operand(D3D10_SB_OPERAND_TYPE_TEMP, self.modifiers, self.component_mode, &[Immediate::U32(reg)])
+                module.write_operand(D3D10_SB_OPERAND_TYPE_TEMP, self.modifiers, self.precision, self.component_mode, &[Immediate::U32(reg)])
             },
             &OperandType::Input(reg) => {
-                module.write_operand(D3D10_SB_OPERAND_TYPE_INPUT, self.modifiers, self.component_mode, &[Immediate::U32(reg)])
+                module.write_operand(D3D10_SB_OPERAND_TYPE_INPUT, self.modifiers, self.precision, self.component_mode, &[Immediate::U32(reg)])
             },
             &OperandType::Output(reg) => {
-                module.write_operand(D3D10_SB_OPERAND_TYPE_OUTPUT, self.modifiers, self.component_mode, &[Immediate::U32(reg)])
+                module.write_operand(D3D10_SB_OPERAND_TYPE_OUTPUT, self.modifiers, self.precision, self.component_mode, &[Immediate::U32(reg)])
+            },
+            &OperandType::Imm32(imm) => {
+                module.write_operand(D3D10_SB_OPERAND_TYPE_IMMEDIATE32, self.modifiers, self.precision, NumComponent::D1, &[]);
+                module.write_u32(imm);
+            },
+            &OperandType::Imm32x4(imm0, imm1, imm2, imm3) => {
+                module.write_operand(D3D10_SB_OPERAND_TYPE_IMMEDIATE32, self.modifiers, self.precision, NumComponent::D4(ComponentMode::Mask(0)), &[]);
+                module.write_u32(imm0);
+                module.write_u32(imm1);
+                module.write_u32(imm2);
+                module.write_u32(imm3);
+            },
+            OperandType::Imm64(imms) => {
+                let component_mode = if imms.len() == 1 { NumComponent::D1 } else { NumComponent::D4(ComponentMode::Mask(0)) };
+                module.write_operand(D3D10_SB_OPERAND_TYPE_IMMEDIATE64, self.modifiers, self.precision, component_mode, &[]);
+                for &imm in imms {
+                    module.write_u64(imm);
+                }
+            },
+            &OperandType::Raw(ty, ref indices) => {
+                module.write_operand(ty, self.modifiers, self.precision, self.component_mode, indices)
+            },
+            // there are no 2 or 3 component immediates, the rest is zeroed
+            &OperandType::Imm32x2(imm0, imm1) => {
+                module.write_operand(D3D10_SB_OPERAND_TYPE_IMMEDIATE32, self.modifiers, self.precision, NumComponent::D4(ComponentMode::Mask(0)), &[]);
+                module.write_u32(imm0);
+                module.write_u32(imm1);
+                module.write_u32(0);
+                module.write_u32(0);
+            },
+            &OperandType::Imm32x3(imm0, imm1, imm2) => {
+                module.write_operand(D3D10_SB_OPERAND_TYPE_IMMEDIATE32, self.modifiers, self.precision, NumComponent::D4(ComponentMode::Mask(0)), &[]);
+                module.write_u32(imm0);
+                module.write_u32(imm1);
+                module.write_u32(imm2);
+                module.write_u32(0);
+            },
+            &OperandType::Resource(reg) => {
+                module.write_operand(D3D10_SB_OPERAND_TYPE_RESOURCE, self.modifiers, self.precision, self.component_mode, &[Immediate::U32(reg)])
+            },
+            &OperandType::Sampler(reg) => {
+                module.write_operand(D3D10_SB_OPERAND_TYPE_SAMPLER, self.modifiers, self.precision, self.component_mode, &[Immediate::U32(reg)])
+            },
+            &OperandType::IndexableRegister(reg, ref index) => {
+                module.write_operand(D3D10_SB_OPERAND_TYPE_INDEXABLE_TEMP, self.modifiers, self.precision, self.component_mode, &[Immediate::U32(reg), index.to_immediate()])
+            },
+            &OperandType::ConstantBuffer(reg, ref index) => {
+                module.write_operand(D3D10_SB_OPERAND_TYPE_CONSTANT_BUFFER, self.modifiers, self.precision, self.component_mode, &[Immediate::U32(reg), index.to_immediate()])
+            },
+            &OperandType::Null => {
+                module.write_operand(D3D10_SB_OPERAND_TYPE_NULL, self.modifiers, self.precision, NumComponent::D0, &[])
+            },
+            OperandType::CustomData(data) => {
+                for &word in data {
+                    module.write_u32(word);
+                }
+            },
+            &OperandType::InputControlPoint(ref vertex, reg) => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_INPUT_CONTROL_POINT, self.modifiers, self.precision, self.component_mode, &[vertex.to_immediate(), Immediate::U32(reg)])
+            },
+            &OperandType::OutputControlPoint(ref vertex, reg) => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_OUTPUT_CONTROL_POINT, self.modifiers, self.precision, self.component_mode, &[vertex.to_immediate(), Immediate::U32(reg)])
+            },
+            OperandType::InputPatchConstant(reg) => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_INPUT_PATCH_CONSTANT, self.modifiers, self.precision, self.component_mode, &[reg.to_immediate()])
+            },
+            &OperandType::InputDomainPoint => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_INPUT_DOMAIN_POINT, self.modifiers, self.precision, self.component_mode, &[])
+            },
+            &OperandType::OutputControlPointId => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_OUTPUT_CONTROL_POINT_ID, self.modifiers, self.precision, self.component_mode, &[])
+            },
+            &OperandType::InputForkInstanceId => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_INPUT_FORK_INSTANCE_ID, self.modifiers, self.precision, self.component_mode, &[])
+            },
+            &OperandType::InputJoinInstanceId => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_INPUT_JOIN_INSTANCE_ID, self.modifiers, self.precision, self.component_mode, &[])
+            },
+            &OperandType::UnorderedAccessView(reg) => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_UNORDERED_ACCESS_VIEW, self.modifiers, self.precision, self.component_mode, &[Immediate::U32(reg)])
+            },
+            &OperandType::ThreadGroupSharedMemory(reg) => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_THREAD_GROUP_SHARED_MEMORY, self.modifiers, self.precision, self.component_mode, &[Immediate::U32(reg)])
+            },
+            &OperandType::InputThreadId => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_INPUT_THREAD_ID, self.modifiers, self.precision, self.component_mode, &[])
+            },
+            &OperandType::InputThreadGroupId => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_INPUT_THREAD_GROUP_ID, self.modifiers, self.precision, self.component_mode, &[])
+            },
+            &OperandType::InputThreadIdInGroup => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_INPUT_THREAD_ID_IN_GROUP, self.modifiers, self.precision, self.component_mode, &[])
+            },
+            &OperandType::InputThreadIdInGroupFlattened => {
+                module.write_operand(D3D11_SB_OPERAND_TYPE_INPUT_THREAD_ID_IN_GROUP_FLATTENED, self.modifiers, self.precision, self.component_mode, &[])
             },
-            OperandType::Imm32(imm) => {},
-            OperandType::Imm32x2(imm0, imm1) => {},
-            OperandType::Imm32x3(imm0, imm1, imm2) => {},
-            OperandType::Imm32x4(imm0, imm1, imm2, imm3) => {},
-            OperandType::Resource(reg) => {},
-            OperandType::Sampler(reg) => {},
-            OperandType::IndexableRegister(reg, index) => {},
-            OperandType::ConstantBuffer(reg, index) => {},
-            OperandType::CustomData(data) => {},
-        }
-    }
-
-    fn get_type(&self) -> u32 {
-        match &self.ty {
-            &OperandType::Register(..) => D3D10_SB_OPERAND_TYPE_TEMP,
-            &OperandType::Input(..) => D3D10_SB_OPERAND_TYPE_INPUT,
-            &OperandType::Output(..) => D3D10_SB_OPERAND_TYPE_OUTPUT,
-            _ => 100000,
         }
     }
 }
 
 pub struct ShexChunk {
+    program_type: u16,
+    major: u8,
+    minor: u8,
     instructions: Vec<Instruction>,
 }
 
+impl Default for ShexChunk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ShexChunk {
     pub fn new() -> Self {
         ShexChunk {
+            program_type: D3D10_SB_VERTEX_SHADER as u16,
+            major: 5,
+            minor: 0,
             instructions: Vec::new(),
         }
     }
 
+    pub fn set_version(&mut self, program_type: u16, major: u8, minor: u8) {
+        self.program_type = program_type;
+        self.major = major;
+        self.minor = minor;
+    }
+
     pub fn add_instruction(&mut self, instruction: Instruction) {
         self.instructions.push(instruction);
     }
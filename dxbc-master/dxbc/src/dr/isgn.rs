@@ -1,64 +1,212 @@
 use binary::*;
 
-use std::mem;
-
-#[repr(u32)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum RegisterComponentType {
-    Unknown = 0,
-    Uint32 = 1,
-    Int32 = 2,
-    Float32 = 3,
+    Unknown,
+    Uint32,
+    Int32,
+    Float32,
+    Uint16,
+    Int16,
+    Float16,
+    Uint64,
+    Int64,
+    Float64,
+    /// Type this crate doesn't know, kept as stored.
+    Other(u32),
 }
 
 impl RegisterComponentType {
     pub fn from_word(word: u32) -> Self {
         match word {
-            0...3 => unsafe { mem::transmute(word) },
-            _ => unreachable!()
+            0 => RegisterComponentType::Unknown,
+            1 => RegisterComponentType::Uint32,
+            2 => RegisterComponentType::Int32,
+            3 => RegisterComponentType::Float32,
+            4 => RegisterComponentType::Uint16,
+            5 => RegisterComponentType::Int16,
+            6 => RegisterComponentType::Float16,
+            7 => RegisterComponentType::Uint64,
+            8 => RegisterComponentType::Int64,
+            9 => RegisterComponentType::Float64,
+            _ => RegisterComponentType::Other(word),
+        }
+    }
+
+    pub fn to_word(self) -> u32 {
+        match self {
+            RegisterComponentType::Unknown => 0,
+            RegisterComponentType::Uint32 => 1,
+            RegisterComponentType::Int32 => 2,
+            RegisterComponentType::Float32 => 3,
+            RegisterComponentType::Uint16 => 4,
+            RegisterComponentType::Int16 => 5,
+            RegisterComponentType::Float16 => 6,
+            RegisterComponentType::Uint64 => 7,
+            RegisterComponentType::Int64 => 8,
+            RegisterComponentType::Float64 => 9,
+            RegisterComponentType::Other(word) => word,
         }
     }
 }
 
-#[repr(u32)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SemanticName {
-    Undefined = 0,
-    Position = 1,
-    ClipDistance = 2,
-    CullDistance = 3,
-    RenderTargetArrayIndex = 4,
-    ViewportArrayIndex = 5,
-    VertexId = 6,
-    PrimitiveId = 7,
-    InstanceId = 8,
-    IsFrontFace = 9,
-    SampleIndex = 10,
-    FinalQuadEdgeTessfactor = 11,
-    FinalQuadInsideTessfactor = 12,
-    FinalTriEdgeTessfactor = 13,
-    FinalTriInsideTessfactor = 14,
-    FinalLineDetailTessfactor = 15,
-    FinalLineDensityTessfactor = 16,
-    Target = 64,
-    Depth = 65,
-    Coverage = 66,
-    DepthGreaterEqual = 67,
-    DepthLessEqual = 68,
+    Undefined,
+    Position,
+    ClipDistance,
+    CullDistance,
+    RenderTargetArrayIndex,
+    ViewportArrayIndex,
+    VertexId,
+    PrimitiveId,
+    InstanceId,
+    IsFrontFace,
+    SampleIndex,
+    FinalQuadEdgeTessfactor,
+    FinalQuadInsideTessfactor,
+    FinalTriEdgeTessfactor,
+    FinalTriInsideTessfactor,
+    FinalLineDetailTessfactor,
+    FinalLineDensityTessfactor,
+    Barycentrics,
+    ShadingRate,
+    CullPrimitive,
+    Target,
+    Depth,
+    Coverage,
+    DepthGreaterEqual,
+    DepthLessEqual,
+    StencilRef,
+    InnerCoverage,
+    /// System value this crate doesn't know, kept as stored.
+    Other(u32),
 }
 
 impl SemanticName {
     pub fn from_word(word: u32) -> Self {
         match word {
-            0...16 |
-            64...68 => unsafe { mem::transmute(word) },
-            _ => unreachable!()
+            0 => SemanticName::Undefined,
+            1 => SemanticName::Position,
+            2 => SemanticName::ClipDistance,
+            3 => SemanticName::CullDistance,
+            4 => SemanticName::RenderTargetArrayIndex,
+            5 => SemanticName::ViewportArrayIndex,
+            6 => SemanticName::VertexId,
+            7 => SemanticName::PrimitiveId,
+            8 => SemanticName::InstanceId,
+            9 => SemanticName::IsFrontFace,
+            10 => SemanticName::SampleIndex,
+            11 => SemanticName::FinalQuadEdgeTessfactor,
+            12 => SemanticName::FinalQuadInsideTessfactor,
+            13 => SemanticName::FinalTriEdgeTessfactor,
+            14 => SemanticName::FinalTriInsideTessfactor,
+            15 => SemanticName::FinalLineDetailTessfactor,
+            16 => SemanticName::FinalLineDensityTessfactor,
+            23 => SemanticName::Barycentrics,
+            24 => SemanticName::ShadingRate,
+            25 => SemanticName::CullPrimitive,
+            64 => SemanticName::Target,
+            65 => SemanticName::Depth,
+            66 => SemanticName::Coverage,
+            67 => SemanticName::DepthGreaterEqual,
+            68 => SemanticName::DepthLessEqual,
+            69 => SemanticName::StencilRef,
+            70 => SemanticName::InnerCoverage,
+            _ => SemanticName::Other(word),
+        }
+    }
+
+    pub fn to_word(self) -> u32 {
+        match self {
+            SemanticName::Undefined => 0,
+            SemanticName::Position => 1,
+            SemanticName::ClipDistance => 2,
+            SemanticName::CullDistance => 3,
+            SemanticName::RenderTargetArrayIndex => 4,
+            SemanticName::ViewportArrayIndex => 5,
+            SemanticName::VertexId => 6,
+            SemanticName::PrimitiveId => 7,
+            SemanticName::InstanceId => 8,
+            SemanticName::IsFrontFace => 9,
+            SemanticName::SampleIndex => 10,
+            SemanticName::FinalQuadEdgeTessfactor => 11,
+            SemanticName::FinalQuadInsideTessfactor => 12,
+            SemanticName::FinalTriEdgeTessfactor => 13,
+            SemanticName::FinalTriInsideTessfactor => 14,
+            SemanticName::FinalLineDetailTessfactor => 15,
+            SemanticName::FinalLineDensityTessfactor => 16,
+            SemanticName::Barycentrics => 23,
+            SemanticName::ShadingRate => 24,
+            SemanticName::CullPrimitive => 25,
+            SemanticName::Target => 64,
+            SemanticName::Depth => 65,
+            SemanticName::Coverage => 66,
+            SemanticName::DepthGreaterEqual => 67,
+            SemanticName::DepthLessEqual => 68,
+            SemanticName::StencilRef => 69,
+            SemanticName::InnerCoverage => 70,
+            SemanticName::Other(word) => word,
+        }
+    }
+}
+
+/// Minimum precision of a signature element, only stored by `ISG1` and
+/// `OSG1`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ElementPrecision {
+    Default,
+    Float16,
+    Float2_8,
+    Sint16,
+    Uint16,
+    Any16,
+    Any10,
+    /// Precision this crate doesn't know, kept as stored.
+    Other(u32),
+}
+
+impl ElementPrecision {
+    pub fn from_word(word: u32) -> Self {
+        match word {
+            0 => ElementPrecision::Default,
+            1 => ElementPrecision::Float16,
+            2 => ElementPrecision::Float2_8,
+            4 => ElementPrecision::Sint16,
+            5 => ElementPrecision::Uint16,
+            0xf0 => ElementPrecision::Any16,
+            0xf1 => ElementPrecision::Any10,
+            _ => ElementPrecision::Other(word),
+        }
+    }
+
+    pub fn to_word(self) -> u32 {
+        match self {
+            ElementPrecision::Default => 0,
+            ElementPrecision::Float16 => 1,
+            ElementPrecision::Float2_8 => 2,
+            ElementPrecision::Sint16 => 4,
+            ElementPrecision::Uint16 => 5,
+            ElementPrecision::Any16 => 0xf0,
+            ElementPrecision::Any10 => 0xf1,
+            ElementPrecision::Other(word) => word,
         }
     }
 }
 
+/// Element layout of a signature chunk.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// `ISGN`, `OSGN` and `PCSG`.
+    Basic,
+    /// `OSG5`, elements start with their stream.
+    Stream,
+    /// `ISG1` and `OSG1`, elements have a stream and a minimum precision.
+    Precision,
+}
+
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct InputOutputElement {
     pub name: String,
     pub semantic_index: u32,
@@ -67,20 +215,32 @@ pub struct InputOutputElement {
     pub register: u32,
     pub component_mask: u8,
     pub rw_mask: u8,
+    /// Geometry shader stream, 0 in formats without streams.
+    pub stream: u32,
+    pub min_precision: ElementPrecision,
 }
 
 impl InputOutputElement {
-    pub fn parse<'a>(decoder: &mut decoder::Decoder<'a>) -> Result<Self, State> {
-        let name_offset = decoder.read_u32();
-        let semantic_index = decoder.read_u32();
-        let semantic_type = SemanticName::from_word(decoder.read_u32());
-        let component_type = RegisterComponentType::from_word(decoder.read_u32());
-        let register = decoder.read_u32();
-        let component_mask = decoder.read_u8();
-        let rw_mask = decoder.read_u8();
-        decoder.skip(2);
-
-        let name = decoder.seek(name_offset as usize).string().map_err(|e| State::DecoderError(e))?;
+    pub fn parse<'a>(decoder: &mut decoder::Decoder<'a>, format: SignatureFormat) -> Result<Self, State> {
+        let stream = match format {
+            SignatureFormat::Basic => 0,
+            _ => decoder.read_u32()?,
+        };
+        let name_offset = decoder.read_u32()?;
+        let semantic_index = decoder.read_u32()?;
+        let semantic_type = SemanticName::from_word(decoder.read_u32()?);
+        let component_type = RegisterComponentType::from_word(decoder.read_u32()?);
+        let register = decoder.read_u32()?;
+        let component_mask = decoder.read_u8()?;
+        let rw_mask = decoder.read_u8()?;
+        decoder.skip(2)?;
+
+        let min_precision = match format {
+            SignatureFormat::Precision => ElementPrecision::from_word(decoder.read_u32()?),
+            _ => ElementPrecision::Default,
+        };
+
+        let name = decoder.seek(name_offset as usize).string()?;
 
         Ok(Self {
             name,
@@ -90,24 +250,30 @@ impl InputOutputElement {
             register,
             component_mask,
             rw_mask,
+            stream,
+            min_precision,
         })
     }
 }
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IOsgnChunk {
     pub elements: Vec<InputOutputElement>,
 }
 
 impl IOsgnChunk {
-    pub fn parse<'b>(decoder: &'b mut decoder::Decoder) -> Result<IOsgnChunk, State> {
-        let element_count = decoder.read_u32();
-        let _unknown = decoder.read_u32();
+    pub fn parse(decoder: &mut decoder::Decoder) -> Result<IOsgnChunk, State> {
+        IOsgnChunk::parse_format(decoder, SignatureFormat::Basic)
+    }
+
+    pub fn parse_format(decoder: &mut decoder::Decoder, format: SignatureFormat) -> Result<IOsgnChunk, State> {
+        let element_count = decoder.read_u32()?;
+        let _unknown = decoder.read_u32()?;
 
         let mut elements = Vec::new();
         for _ in 0..element_count {
-            elements.push(InputOutputElement::parse(decoder)?);
+            elements.push(InputOutputElement::parse(decoder, format)?);
         }
 
         Ok(IOsgnChunk {
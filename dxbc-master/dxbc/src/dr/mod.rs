@@ -12,6 +12,8 @@ pub enum ResourceReturnType {
     Continued = 8
 }
 
+use binary::*;
+
 pub mod rdef;
 pub mod isgn;
 pub mod shex;
@@ -23,9 +25,10 @@ pub use self::isgn::*;
 pub use self::shex::*;
 pub use self::stat::*;
 pub use self::builder::*;
+// `shex` and `builder` both name their operand types, readers get the decoded ones.
+pub use self::shex::{Immediate, OperandType};
 
 
-#[repr(C)]
 #[derive(Debug)]
 pub struct DxbcHeader {
     pub magic: [u8; 4],
@@ -34,3 +37,18 @@ pub struct DxbcHeader {
     pub size: u32,
     pub chunk_count: u32,
 }
+
+impl DxbcHeader {
+    pub fn parse(decoder: &mut decoder::Decoder) -> Result<DxbcHeader, State> {
+        let mut magic = [0; 4];
+        magic.copy_from_slice(decoder.bytes(4)?);
+
+        Ok(DxbcHeader {
+            magic,
+            checksum: [decoder.read_u32()?, decoder.read_u32()?, decoder.read_u32()?, decoder.read_u32()?],
+            _unknown: decoder.read_u32()?,
+            size: decoder.read_u32()?,
+            chunk_count: decoder.read_u32()?,
+        })
+    }
+}
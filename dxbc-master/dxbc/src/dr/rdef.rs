@@ -1,6 +1,7 @@
-use super::{ResourceReturnType};
 use binary::*;
 
+use std::collections::HashMap;
+
 bitflags! {
     pub struct ShaderInputFlags: u32 {
         const NONE = 0x0;
@@ -57,7 +58,7 @@ pub enum ShaderInputType {
 }
 
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ShaderVariableClass {
     Scalar,
     Vector,
@@ -69,8 +70,24 @@ pub enum ShaderVariableClass {
     InterfacePointer,
 }
 
+impl ShaderVariableClass {
+    pub fn from_word(word: u32) -> Option<Self> {
+        Some(match word {
+            0 => ShaderVariableClass::Scalar,
+            1 => ShaderVariableClass::Vector,
+            2 => ShaderVariableClass::MatrixRows,
+            3 => ShaderVariableClass::MatrixColumns,
+            4 => ShaderVariableClass::Object,
+            5 => ShaderVariableClass::Struct,
+            6 => ShaderVariableClass::InterfaceClass,
+            7 => ShaderVariableClass::InterfacePointer,
+            _ => return None,
+        })
+    }
+}
+
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ShaderVariableType {
     Void = 0,
     Bool = 1,
@@ -120,6 +137,60 @@ pub enum ShaderVariableType {
     ConsumeStructuredBuffer,
 }
 
+impl ShaderVariableType {
+    pub fn from_word(word: u32) -> Option<Self> {
+        Some(match word {
+            0 => ShaderVariableType::Void,
+            1 => ShaderVariableType::Bool,
+            2 => ShaderVariableType::Int,
+            3 => ShaderVariableType::Float,
+            4 => ShaderVariableType::String,
+            5 => ShaderVariableType::Texture,
+            6 => ShaderVariableType::Texture1D,
+            7 => ShaderVariableType::Texture2D,
+            8 => ShaderVariableType::Texture3D,
+            9 => ShaderVariableType::TextureCube,
+            10 => ShaderVariableType::Sampler,
+            15 => ShaderVariableType::PixelShader,
+            16 => ShaderVariableType::VertexShader,
+            19 => ShaderVariableType::UInt,
+            20 => ShaderVariableType::UInt8,
+            21 => ShaderVariableType::GeometryShader,
+            22 => ShaderVariableType::Rasterizer,
+            23 => ShaderVariableType::DepthStencil,
+            24 => ShaderVariableType::Blend,
+            25 => ShaderVariableType::Buffer,
+            26 => ShaderVariableType::CBuffer,
+            27 => ShaderVariableType::TBuffer,
+            28 => ShaderVariableType::Texture1DArray,
+            29 => ShaderVariableType::Texture2DArray,
+            30 => ShaderVariableType::RenderTargetView,
+            31 => ShaderVariableType::DepthStencilView,
+            32 => ShaderVariableType::Texture2DMultiSampled,
+            33 => ShaderVariableType::Texture2DMultiSampledArray,
+            34 => ShaderVariableType::TextureCubeArray,
+            35 => ShaderVariableType::HullShader,
+            36 => ShaderVariableType::DomainShader,
+            37 => ShaderVariableType::InterfacePointer,
+            38 => ShaderVariableType::ComputeShader,
+            39 => ShaderVariableType::Double,
+            40 => ShaderVariableType::ReadWriteTexture1D,
+            41 => ShaderVariableType::ReadWriteTexture1DArray,
+            42 => ShaderVariableType::ReadWriteTexture2D,
+            43 => ShaderVariableType::ReadWriteTexture2DArray,
+            44 => ShaderVariableType::ReadWriteTexture3D,
+            45 => ShaderVariableType::ReadWriteBuffer,
+            46 => ShaderVariableType::ByteAddressBuffer,
+            47 => ShaderVariableType::ReadWriteByteAddressBuffer,
+            48 => ShaderVariableType::StructuredBuffer,
+            49 => ShaderVariableType::ReadWriteStructuredBuffer,
+            50 => ShaderVariableType::AppendStructuredBuffer,
+            51 => ShaderVariableType::ConsumeStructuredBuffer,
+            _ => return None,
+        })
+    }
+}
+
 #[repr(u32)]
 #[derive(Debug)]
 pub enum ViewDimension {
@@ -143,30 +214,245 @@ pub enum ShaderModel {
     V5_0
 }
 
+// Type descriptors may reference each other, refuse to follow chains
+// deeper than any real shader would produce.
+const MAX_TYPE_DEPTH: u32 = 64;
+// Members share type descriptors, so the decoded types of a chunk may be
+// far larger than the chunk itself; bound the number of types expanded.
+const MAX_TYPE_COUNT: usize = 1 << 16;
+
+/// RD11 header written by the shader model 5 compilers.
+pub const RD11_HEADER: [u32; 7] = [60, 24, 32, 40, 36, 12, 0];
+
+/// Sizes of the variable, type and member descriptors. Shader model 5
+/// binaries record them in the RD11 header, older compilers may still
+/// emit the shorter shader model 4 layout there.
+#[derive(Debug, Copy, Clone)]
+pub struct DescriptorSizes {
+    pub variable: u32,
+    pub ty: u32,
+    pub member: u32,
+}
+
+impl DescriptorSizes {
+    pub fn from_rd11(rd11: Option<[u32; 7]>) -> Result<Self, State> {
+        let sizes = match rd11 {
+            Some(rd11) => DescriptorSizes {
+                variable: rd11[3],
+                ty: rd11[4],
+                member: rd11[5],
+            },
+            None => DescriptorSizes {
+                variable: 24,
+                ty: 16,
+                member: 12,
+            },
+        };
+
+        // descriptors are read with these strides, they can't be shorter
+        // than the shader model 4 layout
+        if sizes.variable < 24 || sizes.ty < 16 || sizes.member < 12 {
+            return Err(State::ChunkIncorrect);
+        }
+
+        Ok(sizes)
+    }
+}
+
+/// Types decoded from a chunk, keyed by descriptor offset.
+pub struct ShaderTypes<'a> {
+    types: HashMap<u32, ShaderType<'a>>,
+    expanded: usize,
+}
+
+impl<'a> Default for ShaderTypes<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> ShaderTypes<'a> {
+    pub fn new() -> Self {
+        ShaderTypes {
+            types: HashMap::new(),
+            expanded: 0,
+        }
+    }
+
+    // Accounts for `ty` in the types handed out so far.
+    fn expand(&mut self, ty: &ShaderType<'a>) -> Result<(), State> {
+        self.expanded += ty.type_count();
+        if self.expanded > MAX_TYPE_COUNT {
+            return Err(State::ChunkIncorrect);
+        }
+
+        Ok(())
+    }
+}
+
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ShaderTypeMember<'a> {
-    name: &'a str,
-    ty: ShaderType<'a>,
+    pub name: &'a str,
+    pub ty: ShaderType<'a>,
+    pub offset: u32,
 }
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ShaderType<'a> {
-    class: ShaderVariableClass,
-    ty: ShaderVariableType,
-    rows: u16,
-    columns: u16,
-    count: u16,
-    members: Vec<ShaderTypeMember<'a>>,
+    pub class: ShaderVariableClass,
+    pub ty: ShaderVariableType,
+    pub rows: u16,
+    pub columns: u16,
+    /// Number of array elements, zero if the type is not an array.
+    pub count: u16,
+    pub members: Vec<ShaderTypeMember<'a>>,
+    /// Type name, only present in shader model 5 and above.
+    pub name: Option<&'a str>,
+}
+
+impl<'a> ShaderType<'a> {
+    /// Decodes the type at `offset`, descriptors already in `types` are
+    /// reused rather than decoded again.
+    pub fn parse(decoder: &decoder::Decoder<'a>, offset: u32, sizes: DescriptorSizes, types: &mut ShaderTypes<'a>) -> Result<Self, State> {
+        ShaderType::parse_nested(decoder, offset, sizes, types, 0)
+    }
+
+    fn parse_nested(decoder: &decoder::Decoder<'a>, offset: u32, sizes: DescriptorSizes, types: &mut ShaderTypes<'a>, depth: u32) -> Result<Self, State> {
+        if depth > MAX_TYPE_DEPTH {
+            return Err(State::ChunkIncorrect);
+        }
+
+        if let Some(ty) = types.types.get(&offset).cloned() {
+            types.expand(&ty)?;
+            return Ok(ty);
+        }
+
+        let mut decoder = decoder.seek(offset as usize);
+
+        let class_offset = decoder.get_offset();
+        let class = decoder.read_u16()?;
+        let ty_offset = decoder.get_offset();
+        let ty = decoder.read_u16()?;
+        let rows = decoder.read_u16()?;
+        let columns = decoder.read_u16()?;
+        let count = decoder.read_u16()?;
+        let member_count = decoder.read_u16()?;
+        let member_offset = decoder.read_u32()?;
+
+        let class = ShaderVariableClass::from_word(class as u32)
+            .ok_or(Error::InvalidToken(class_offset, class as u32))?;
+        let ty = ShaderVariableType::from_word(ty as u32)
+            .ok_or(Error::InvalidToken(ty_offset, ty as u32))?;
+
+        let name = if sizes.ty >= 36 {
+            decoder.skip(16)?;
+            match decoder.read_u32()? {
+                0 => None,
+                name_offset => Some(decoder.seek(name_offset as usize).str()?),
+            }
+        } else {
+            None
+        };
+
+        let mut members = Vec::new();
+        for i in 0..member_count as usize {
+            let mut member_decoder = decoder.seek(member_offset as usize + i * sizes.member as usize);
+            let name_offset = member_decoder.read_u32()?;
+            let type_offset = member_decoder.read_u32()?;
+            let offset = member_decoder.read_u32()?;
+
+            members.push(ShaderTypeMember {
+                name: decoder.seek(name_offset as usize).str()?,
+                ty: ShaderType::parse_nested(&decoder, type_offset, sizes, types, depth + 1)?,
+                offset,
+            });
+        }
+
+        let ty = ShaderType {
+            class,
+            ty,
+            rows,
+            columns,
+            count,
+            members,
+            name,
+        };
+
+        // members were accounted for when decoded
+        types.expanded += 1;
+        if types.expanded > MAX_TYPE_COUNT {
+            return Err(State::ChunkIncorrect);
+        }
+
+        types.types.insert(offset, ty.clone());
+        Ok(ty)
+    }
+
+    /// Number of types making up this one, itself included.
+    pub fn type_count(&self) -> usize {
+        1 + self.members.iter().map(|member| member.ty.type_count()).sum::<usize>()
+    }
 }
 
 #[repr(C)]
 #[derive(Debug)]
 pub struct ShaderVariable<'a> {
-    name: &'a str,
-    byte_size: u32,
-    flags: ShaderVariableFlags,
+    pub name: &'a str,
+    pub start_offset: u32,
+    pub byte_size: u32,
+    pub flags: ShaderVariableFlags,
+    pub ty: ShaderType<'a>,
+    pub default_value: Option<&'a [u8]>,
+    // only present in the shader model 5 layout, unused slots are !0 with
+    // a size of 0
+    pub start_texture: u32,
+    pub texture_size: u32,
+    pub start_sampler: u32,
+    pub sampler_size: u32,
+}
+
+impl<'a> ShaderVariable<'a> {
+    pub fn parse(decoder: &mut decoder::Decoder<'a>, sizes: DescriptorSizes, types: &mut ShaderTypes<'a>) -> Result<Self, State> {
+        let name_offset = decoder.read_u32()?;
+        let start_offset = decoder.read_u32()?;
+        let byte_size = decoder.read_u32()?;
+        let flags = decoder.read_u32()?;
+        let type_offset = decoder.read_u32()?;
+        let default_value_offset = decoder.read_u32()?;
+
+        let (start_texture, texture_size, start_sampler, sampler_size) = if sizes.variable >= 40 {
+            (
+                decoder.read_u32()?,
+                decoder.read_u32()?,
+                decoder.read_u32()?,
+                decoder.read_u32()?,
+            )
+        } else {
+            (!0, 0, !0, 0)
+        };
+
+        let name = decoder.seek(name_offset as usize).str()?;
+        let ty = ShaderType::parse(decoder, type_offset, sizes, types)?;
+        let default_value = match default_value_offset {
+            0 => None,
+            offset => Some(decoder.seek(offset as usize).bytes(byte_size as usize)?),
+        };
+
+        Ok(Self {
+            name,
+            start_offset,
+            byte_size,
+            flags: ShaderVariableFlags::from_bits_truncate(flags),
+            ty,
+            default_value,
+            start_texture,
+            texture_size,
+            start_sampler,
+            sampler_size,
+        })
+    }
 }
 
 #[repr(C)]
@@ -180,16 +466,21 @@ pub struct ConstantBuffer<'a> {
 }
 
 impl<'a> ConstantBuffer<'a> {
-    pub fn parse(decoder: &mut decoder::Decoder<'a>) -> Result<Self, State> {
-        let name_offset = decoder.read_u32();
-        let var_count = decoder.read_u32();
-        let var_offset = decoder.read_u32();
-        let byte_size = decoder.read_u32();
-        let flags = decoder.read_u32();
-        let ty = decoder.read_u32();
-
-        let name = decoder.seek(name_offset as usize).str().map_err(|e| State::DecoderError(e))?;
-        let variables = Vec::new();
+    pub fn parse(decoder: &mut decoder::Decoder<'a>, sizes: DescriptorSizes, types: &mut ShaderTypes<'a>) -> Result<Self, State> {
+        let name_offset = decoder.read_u32()?;
+        let var_count = decoder.read_u32()?;
+        let var_offset = decoder.read_u32()?;
+        let byte_size = decoder.read_u32()?;
+        let flags = decoder.read_u32()?;
+        let ty = decoder.read_u32()?;
+
+        let name = decoder.seek(name_offset as usize).str()?;
+
+        let mut variables = Vec::new();
+        for i in 0..var_count as usize {
+            let mut var_decoder = decoder.seek(var_offset as usize + i * sizes.variable as usize);
+            variables.push(ShaderVariable::parse(&mut var_decoder, sizes, types)?);
+        }
 
         Ok(Self {
             name,
@@ -199,6 +490,13 @@ impl<'a> ConstantBuffer<'a> {
             ty,
         })
     }
+
+    /// Finds the variable covering the given byte offset into the buffer.
+    pub fn variable_at(&self, offset: u32) -> Option<&ShaderVariable<'a>> {
+        self.variables
+            .iter()
+            .find(|var| offset >= var.start_offset && offset - var.start_offset < var.byte_size)
+    }
 }
 
 #[repr(C)]
@@ -216,16 +514,16 @@ pub struct ResourceBinding<'a> {
 
 impl<'a> ResourceBinding<'a> {
     pub fn parse(decoder: &mut decoder::Decoder<'a>) -> Result<Self, State> {
-        let name_offset = decoder.read_u32();
-        let input_type = decoder.read_u32();
-        let return_type = decoder.read_u32();
-        let view_dimension = decoder.read_u32();
-        let sample_count = decoder.read_u32();
-        let bind_point = decoder.read_u32();
-        let bind_count = decoder.read_u32();
-        let input_flags = decoder.read_u32();
+        let name_offset = decoder.read_u32()?;
+        let input_type = decoder.read_u32()?;
+        let return_type = decoder.read_u32()?;
+        let view_dimension = decoder.read_u32()?;
+        let sample_count = decoder.read_u32()?;
+        let bind_point = decoder.read_u32()?;
+        let bind_count = decoder.read_u32()?;
+        let input_flags = decoder.read_u32()?;
 
-        let name = decoder.seek(name_offset as usize).str().map_err(|e| State::DecoderError(e))?;
+        let name = decoder.seek(name_offset as usize).str()?;
 
         Ok(Self {
             name,
@@ -255,41 +553,44 @@ pub struct RdefChunk<'a> {
 
 impl<'a> RdefChunk<'a> {
     pub fn parse<'b>(decoder: &'b mut decoder::Decoder) -> Result<RdefChunk<'b>, State> {
-        let cb_count = decoder.read_u32();
-        let cb_offset = decoder.read_u32();
+        let cb_count = decoder.read_u32()?;
+        let cb_offset = decoder.read_u32()?;
 
-        let bind_count = decoder.read_u32();
-        let bind_offset = decoder.read_u32();
+        let bind_count = decoder.read_u32()?;
+        let bind_offset = decoder.read_u32()?;
 
-        let minor = decoder.read_u8();
-        let major = decoder.read_u8();
+        let minor = decoder.read_u8()?;
+        let major = decoder.read_u8()?;
 
-        let shader_ty = decoder.read_u16();
+        let shader_ty = decoder.read_u16()?;
 
-        let flags = decoder.read_u32();
-        let author_offset = decoder.read_u32();
+        let flags = decoder.read_u32()?;
+        let author_offset = decoder.read_u32()?;
 
         let rd11 = if major >= 5 {
-            let magic = decoder.read_u32();
+            let _magic = decoder.read_u32()?;
             // assert_eq!(magic, b"RD11");
 
             Some([
-                decoder.read_u32(),
-                decoder.read_u32(),
-                decoder.read_u32(),
-                decoder.read_u32(),
-                decoder.read_u32(),
-                decoder.read_u32(),
-                decoder.read_u32(),
+                decoder.read_u32()?,
+                decoder.read_u32()?,
+                decoder.read_u32()?,
+                decoder.read_u32()?,
+                decoder.read_u32()?,
+                decoder.read_u32()?,
+                decoder.read_u32()?,
             ])
         } else {
             None
         };
 
+        let sizes = DescriptorSizes::from_rd11(rd11)?;
+
         decoder.seek_mut(cb_offset as usize);
+        let mut types = ShaderTypes::new();
         let mut constant_buffers = Vec::new();
         for _ in 0..cb_count {
-            constant_buffers.push(ConstantBuffer::parse(decoder)?);
+            constant_buffers.push(ConstantBuffer::parse(decoder, sizes, &mut types)?);
         }
 
         decoder.seek_mut(bind_offset as usize);
@@ -298,7 +599,7 @@ impl<'a> RdefChunk<'a> {
             resource_bindings.push(ResourceBinding::parse(decoder)?);
         }
 
-        let author = decoder.seek(author_offset as usize).str().map_err(|e| State::DecoderError(e))?;
+        let author = decoder.seek(author_offset as usize).str()?;
 
         Ok(RdefChunk {
             constant_buffers,
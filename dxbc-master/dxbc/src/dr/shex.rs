@@ -2,19 +2,17 @@ use d3d11tokenizedprogramformat::*;
 
 use binary::*;
 
-use std::slice;
-use std::mem;
 use std::marker::PhantomData;
 
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ConstantBufferIndexPattern {
     Immediate = 0,
     Dynamic = 1,
 }
 
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum OperandType {
     Temp = 0,
     Input = 1,
@@ -78,13 +76,14 @@ pub enum ComponentName {
 }
 
 impl ComponentName {
+    /// Only the low two bits are looked at, like in the swizzle and select
+    /// fields component names come from.
     pub fn from_word(word: u8) -> ComponentName {
-        match word {
+        match word & 3 {
             0 => ComponentName::X,
             1 => ComponentName::Y,
             2 => ComponentName::Z,
-            3 => ComponentName::W,
-            _ => unreachable!()
+            _ => ComponentName::W,
         }
     }
 }
@@ -137,32 +136,36 @@ bitflags! {
     }
 }
 
-#[repr(u32)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum NameToken {
-    Undefined = 0,
-    Position = 1,
-    ClipDistance = 2,
-    CullDistance = 3,
-    RenderTargetArrayIndex = 4,
-    ViewportArrayIndex = 5,
-    VertexId = 6,
-    PrimitiveId = 7,
-    InstanceId = 8,
-    IsFrontFace = 9,
-    SampleIndex = 10,
-    FinalQuadUEq0EdgeTessfactor = 11,
-    FinalQuadVEq0EdgeTessfactor = 12,
-    FinalQuadUEq1EdgeTessfactor = 13,
-    FinalQuadVEq1EdgeTessfactor = 14,
-    FinalQuadUInsideTessfactor = 15,
-    FinalQuadVInsideTessfactor = 16,
-    FinalTriUEq0EdgeTessfactor = 17,
-    FinalTriVEq0EdgeTessfactor = 18,
-    FinalTriWEq0EdgeTessfactor = 19,
-    FinalTriinsidetessfactor = 20,
-    FinalLineDetailTessfactor = 21,
-    FinalLineDensityTessfactor = 22,
+    Undefined,
+    Position,
+    ClipDistance,
+    CullDistance,
+    RenderTargetArrayIndex,
+    ViewportArrayIndex,
+    VertexId,
+    PrimitiveId,
+    InstanceId,
+    IsFrontFace,
+    SampleIndex,
+    FinalQuadUEq0EdgeTessfactor,
+    FinalQuadVEq0EdgeTessfactor,
+    FinalQuadUEq1EdgeTessfactor,
+    FinalQuadVEq1EdgeTessfactor,
+    FinalQuadUInsideTessfactor,
+    FinalQuadVInsideTessfactor,
+    FinalTriUEq0EdgeTessfactor,
+    FinalTriVEq0EdgeTessfactor,
+    FinalTriWEq0EdgeTessfactor,
+    FinalTriinsidetessfactor,
+    FinalLineDetailTessfactor,
+    FinalLineDensityTessfactor,
+    Barycentrics,
+    ShadingRate,
+    CullPrimitive,
+    /// System value this crate doesn't know, kept as stored.
+    Other(u32),
 }
 
 impl NameToken {
@@ -191,7 +194,42 @@ impl NameToken {
             20 => NameToken::FinalTriinsidetessfactor,
             21 => NameToken::FinalLineDetailTessfactor,
             22 => NameToken::FinalLineDensityTessfactor,
-            _ => unimplemented!(),
+            23 => NameToken::Barycentrics,
+            24 => NameToken::ShadingRate,
+            25 => NameToken::CullPrimitive,
+            _ => NameToken::Other(word),
+        }
+    }
+
+    pub fn to_word(self) -> u32 {
+        match self {
+            NameToken::Undefined => 0,
+            NameToken::Position => 1,
+            NameToken::ClipDistance => 2,
+            NameToken::CullDistance => 3,
+            NameToken::RenderTargetArrayIndex => 4,
+            NameToken::ViewportArrayIndex => 5,
+            NameToken::VertexId => 6,
+            NameToken::PrimitiveId => 7,
+            NameToken::InstanceId => 8,
+            NameToken::IsFrontFace => 9,
+            NameToken::SampleIndex => 10,
+            NameToken::FinalQuadUEq0EdgeTessfactor => 11,
+            NameToken::FinalQuadVEq0EdgeTessfactor => 12,
+            NameToken::FinalQuadUEq1EdgeTessfactor => 13,
+            NameToken::FinalQuadVEq1EdgeTessfactor => 14,
+            NameToken::FinalQuadUInsideTessfactor => 15,
+            NameToken::FinalQuadVInsideTessfactor => 16,
+            NameToken::FinalTriUEq0EdgeTessfactor => 17,
+            NameToken::FinalTriVEq0EdgeTessfactor => 18,
+            NameToken::FinalTriWEq0EdgeTessfactor => 19,
+            NameToken::FinalTriinsidetessfactor => 20,
+            NameToken::FinalLineDetailTessfactor => 21,
+            NameToken::FinalLineDensityTessfactor => 22,
+            NameToken::Barycentrics => 23,
+            NameToken::ShadingRate => 24,
+            NameToken::CullPrimitive => 25,
+            NameToken::Other(word) => word,
         }
     }
 }
@@ -206,19 +244,19 @@ pub enum OperandModifier {
 }
 
 impl OperandModifier {
-    pub fn from_word(word: u32) -> Self {
+    pub fn from_word(word: u32) -> Option<Self> {
         match DECODE_D3D10_SB_OPERAND_MODIFIER(word) {
-            0 => OperandModifier::None,
-            1 => OperandModifier::Neg,
-            2 => OperandModifier::Abs,
-            3 => OperandModifier::AbsNeg,
-            _ => unreachable!(),
+            0 => Some(OperandModifier::None),
+            1 => Some(OperandModifier::Neg),
+            2 => Some(OperandModifier::Abs),
+            3 => Some(OperandModifier::AbsNeg),
+            _ => None,
         }
     }
 }
 
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub enum SamplerMode {
     Default,
     Comparison,
@@ -226,18 +264,18 @@ pub enum SamplerMode {
 }
 
 impl SamplerMode {
-    pub fn from_word(word: u32) -> Self {
+    pub fn from_word(word: u32) -> Option<Self> {
         match word {
-            0 => SamplerMode::Default,
-            1 => SamplerMode::Comparison,
-            2 => SamplerMode::Mono,
-            _ => unreachable!(),
+            0 => Some(SamplerMode::Default),
+            1 => Some(SamplerMode::Comparison),
+            2 => Some(SamplerMode::Mono),
+            _ => None,
         }
     }
 }
 
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum TestBoolean {
     Zero,
     NonZero,
@@ -270,15 +308,15 @@ impl<'a> OpcodeToken0<'a> {
     }
 
     pub fn is_extended(&self) -> bool {
-        DECODE_IS_D3D10_SB_OPCODE_EXTENDED(unsafe { *self.word }) != 0
+        DECODE_IS_D3D10_SB_OPCODE_EXTENDED(unsafe { self.word.read_unaligned() }) != 0
     }
 
     pub fn is_saturated(&self) -> bool {
-        DECODE_IS_D3D10_SB_INSTRUCTION_SATURATE_ENABLED(unsafe { *self.word }) != 0
+        DECODE_IS_D3D10_SB_INSTRUCTION_SATURATE_ENABLED(unsafe { self.word.read_unaligned() }) != 0
     }
 
     pub fn get_test_type(&self) -> TestBoolean {
-        TestBoolean::from_word(unsafe { *self.word })
+        TestBoolean::from_word(unsafe { self.word.read_unaligned() })
     }
 
     pub fn get_extended_opcode(&self) -> Option<OpcodeToken1<'a>> {
@@ -293,30 +331,48 @@ impl<'a> OpcodeToken0<'a> {
     }
 
     pub fn get_opcode_type(&self) -> u32 {
-        DECODE_D3D10_SB_OPCODE_TYPE(unsafe { *self.word })
+        DECODE_D3D10_SB_OPCODE_TYPE(unsafe { self.word.read_unaligned() })
     }
 
     pub fn get_instruction_length(&self) -> u32 {
-        DECODE_D3D10_SB_TOKENIZED_INSTRUCTION_LENGTH(unsafe { *self.word })
+        DECODE_D3D10_SB_TOKENIZED_INSTRUCTION_LENGTH(unsafe { self.word.read_unaligned() })
+    }
+
+    pub fn get_resource_dimension(&self) -> Option<ResourceDimension> {
+        ResourceDimension::from_word(DECODE_D3D10_SB_RESOURCE_DIMENSION(unsafe { self.word.read_unaligned() }))
+    }
+
+    pub fn get_sampler_mode(&self) -> Option<SamplerMode> {
+        SamplerMode::from_word(DECODE_D3D10_SB_SAMPLER_MODE(unsafe { self.word.read_unaligned() }))
+    }
+
+    pub fn get_interpolation_mode(&self) -> Option<InterpolationMode> {
+        InterpolationMode::from_word(DECODE_D3D10_SB_INPUT_INTERPOLATION_MODE(unsafe { self.word.read_unaligned() }))
+    }
+
+    pub fn get_resinfo_return_type(&self) -> Option<ResinfoReturnType> {
+        ResinfoReturnType::from_word(DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(unsafe { self.word.read_unaligned() }))
     }
 
-    pub fn get_resource_dimension(&self) -> ResourceDimension {
-        ResourceDimension::from_word(DECODE_D3D10_SB_RESOURCE_DIMENSION(unsafe { *(self.word.offset(0)) }))
+    pub fn is_uint_return_type(&self) -> bool {
+        DECODE_D3D10_SB_INSTRUCTION_RETURN_TYPE(unsafe { self.word.read_unaligned() }) == D3D10_SB_INSTRUCTION_RETURN_UINT
     }
 
-    pub fn get_sampler_mode(&self) -> SamplerMode {
-        SamplerMode::from_word(DECODE_D3D10_SB_SAMPLER_MODE(unsafe { *(self.word.offset(0)) }))
+    /// `_glc` of unordered access view declarations.
+    pub fn is_globally_coherent(&self) -> bool {
+        DECODE_D3D11_SB_ACCESS_COHERENCY_FLAGS(unsafe { self.word.read_unaligned() }) & D3D11_SB_GLOBALLY_COHERENT_ACCESS != 0
     }
 
-    pub fn get_interpolation_mode(&self) -> InterpolationMode {
-        InterpolationMode::from_word(DECODE_D3D10_SB_INPUT_INTERPOLATION_MODE(unsafe { *self.word }))
+    /// `_opc` of structured unordered access view declarations.
+    pub fn has_order_preserving_counter(&self) -> bool {
+        DECODE_D3D11_SB_UAV_FLAGS(unsafe { self.word.read_unaligned() }) & D3D11_SB_UAV_HAS_ORDER_PRESERVING_COUNTER != 0
     }
 }
 
 impl<'a> fmt::Debug for OpcodeToken0<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("OpcodeToken0")
-            .field("Raw", &unsafe { *self.word })
+            .field("Raw", &unsafe { self.word.read_unaligned() })
             .field("Type", &self.get_opcode_type())
             .field("InstructionLength", &self.get_instruction_length())
             .field("IsSaturated", &self.is_saturated())
@@ -327,7 +383,7 @@ impl<'a> fmt::Debug for OpcodeToken0<'a> {
 }
 
 #[repr(u32)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub enum InterpolationMode {
     Undefined = 0,
     Constant = 1,
@@ -340,15 +396,42 @@ pub enum InterpolationMode {
 }
 
 impl InterpolationMode {
-    pub fn from_word(word: u32) -> Self {
-        assert!(word <= InterpolationMode::LinearNoPerspectiveSample as u32);
+    pub fn from_word(word: u32) -> Option<Self> {
+        match word {
+            0 => Some(InterpolationMode::Undefined),
+            1 => Some(InterpolationMode::Constant),
+            2 => Some(InterpolationMode::Linear),
+            3 => Some(InterpolationMode::LinearCentroid),
+            4 => Some(InterpolationMode::LinearNoPerspective),
+            5 => Some(InterpolationMode::LinearNoPerspectiveCentroid),
+            6 => Some(InterpolationMode::LinearSample),
+            7 => Some(InterpolationMode::LinearNoPerspectiveSample),
+            _ => None,
+        }
+    }
+}
 
-        unsafe { mem::transmute(word) }
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResinfoReturnType {
+    Float = 0,
+    RcpFloat = 1,
+    Uint = 2,
+}
+
+impl ResinfoReturnType {
+    pub fn from_word(word: u32) -> Option<Self> {
+        match word {
+            0 => Some(ResinfoReturnType::Float),
+            1 => Some(ResinfoReturnType::RcpFloat),
+            2 => Some(ResinfoReturnType::Uint),
+            _ => None,
+        }
     }
 }
 
 #[repr(u32)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ResourceDimension {
     Unknown = 0,
     Buffer = 1,
@@ -366,22 +449,22 @@ pub enum ResourceDimension {
 }
 
 impl ResourceDimension {
-    pub fn from_word(word: u32) -> Self {
+    pub fn from_word(word: u32) -> Option<Self> {
         match word {
-            0 => ResourceDimension::Unknown,
-            1 => ResourceDimension::Buffer,
-            2 => ResourceDimension::Texture1D,
-            3 => ResourceDimension::Texture2D,
-            4 => ResourceDimension::Texture2DMS,
-            5 => ResourceDimension::Texture3D,
-            6 => ResourceDimension::TextureCube,
-            7 => ResourceDimension::Texture1DArray,
-            8 => ResourceDimension::Texture2DArray,
-            9 => ResourceDimension::Texture2DMSArray,
-            10 => ResourceDimension::TextureCubeArray,
-            11 => ResourceDimension::RawBuffer,
-            12 => ResourceDimension::StructuredBuffer,
-            _ => unreachable!()
+            0 => Some(ResourceDimension::Unknown),
+            1 => Some(ResourceDimension::Buffer),
+            2 => Some(ResourceDimension::Texture1D),
+            3 => Some(ResourceDimension::Texture2D),
+            4 => Some(ResourceDimension::Texture2DMS),
+            5 => Some(ResourceDimension::Texture3D),
+            6 => Some(ResourceDimension::TextureCube),
+            7 => Some(ResourceDimension::Texture1DArray),
+            8 => Some(ResourceDimension::Texture2DArray),
+            9 => Some(ResourceDimension::Texture2DMSArray),
+            10 => Some(ResourceDimension::TextureCubeArray),
+            11 => Some(ResourceDimension::RawBuffer),
+            12 => Some(ResourceDimension::StructuredBuffer),
+            _ => None,
         }
     }
 }
@@ -396,19 +479,19 @@ pub enum ExtendedOpcodeType {
 }
 
 impl ExtendedOpcodeType {
-    pub fn from_word(word: u32) -> Self {
+    pub fn from_word(word: u32) -> Option<Self> {
         match DECODE_D3D10_SB_EXTENDED_OPCODE_TYPE(word) {
-            0 => ExtendedOpcodeType::Empty,
-            1 => ExtendedOpcodeType::SampleControls,
-            2 => ExtendedOpcodeType::ResourceDim,
-            3 => ExtendedOpcodeType::ResourceReturnType,
-            _ => unreachable!(),
+            0 => Some(ExtendedOpcodeType::Empty),
+            1 => Some(ExtendedOpcodeType::SampleControls),
+            2 => Some(ExtendedOpcodeType::ResourceDim),
+            3 => Some(ExtendedOpcodeType::ResourceReturnType),
+            _ => None,
         }
     }
 }
 
 #[repr(u32)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ResourceReturnType {
     Unorm = 1,
     Snorm = 2,
@@ -422,18 +505,18 @@ pub enum ResourceReturnType {
 }
 
 impl ResourceReturnType {
-    pub fn from_word(word: u32) -> Self {
+    pub fn from_word(word: u32) -> Option<Self> {
         match word {
-            1 => ResourceReturnType::Unorm,
-            2 => ResourceReturnType::Snorm,
-            3 => ResourceReturnType::Sint,
-            4 => ResourceReturnType::Uint,
-            5 => ResourceReturnType::Float,
-            6 => ResourceReturnType::Mixed,
-            7 => ResourceReturnType::Double,
-            8 => ResourceReturnType::Continued,
-            9 => ResourceReturnType::Unused,
-            _ => unreachable!(),
+            1 => Some(ResourceReturnType::Unorm),
+            2 => Some(ResourceReturnType::Snorm),
+            3 => Some(ResourceReturnType::Sint),
+            4 => Some(ResourceReturnType::Uint),
+            5 => Some(ResourceReturnType::Float),
+            6 => Some(ResourceReturnType::Mixed),
+            7 => Some(ResourceReturnType::Double),
+            8 => Some(ResourceReturnType::Continued),
+            9 => Some(ResourceReturnType::Unused),
+            _ => None,
         }
     }
 }
@@ -453,8 +536,20 @@ impl<'a> ResourceReturnTypeToken0<'a> {
         }
     }
 
-    pub fn get_return_type(&self, name: ComponentName) -> ResourceReturnType {
-        ResourceReturnType::from_word(DECODE_D3D10_SB_RESOURCE_RETURN_TYPE(unsafe { *(self.word.offset(0)) }, name as u32))
+    pub fn parse<'b>(decoder: &mut decoder::Decoder<'b>) -> DecoderResult<ResourceReturnTypeToken0<'b>> {
+        let offset = decoder.get_offset();
+        let token = ResourceReturnTypeToken0::from_word(decoder.read_u32_address()?);
+
+        let names = [ComponentName::X, ComponentName::Y, ComponentName::Z, ComponentName::W];
+        if names.iter().any(|&name| token.get_return_type(name).is_none()) {
+            return Err(Error::InvalidToken(offset, unsafe { token.word.read_unaligned() }));
+        }
+
+        Ok(token)
+    }
+
+    pub fn get_return_type(&self, name: ComponentName) -> Option<ResourceReturnType> {
+        ResourceReturnType::from_word(DECODE_D3D10_SB_RESOURCE_RETURN_TYPE(unsafe { self.word.read_unaligned() }, name as u32))
     }
 }
 
@@ -473,17 +568,24 @@ impl<'a> OpcodeToken1<'a> {
         }
     }
 
-    pub fn get_extended_opcode_type(&self) -> ExtendedOpcodeType {
-        ExtendedOpcodeType::from_word(DECODE_D3D10_SB_TOKENIZED_INSTRUCTION_LENGTH(unsafe { *self.word }))
+    pub fn get_extended_opcode_type(&self) -> Option<ExtendedOpcodeType> {
+        ExtendedOpcodeType::from_word(unsafe { self.word.read_unaligned() })
+    }
+
+    pub fn get_address_offset(&self, coord: u32) -> i32 {
+        let offset = DECODE_IMMEDIATE_D3D10_SB_ADDRESS_OFFSET(coord, unsafe { self.word.read_unaligned() });
+
+        // 4-bit two's complement
+        ((offset << 28) as i32) >> 28
     }
 
     pub fn get_opcode_modifier(&self) -> u32 {
-        DECODE_D3D10_SB_TOKENIZED_INSTRUCTION_LENGTH(unsafe { *self.word })
+        DECODE_D3D10_SB_TOKENIZED_INSTRUCTION_LENGTH(unsafe { self.word.read_unaligned() })
     }
 
 
     pub fn is_extended(&self) -> bool {
-        DECODE_IS_D3D10_SB_OPCODE_EXTENDED(unsafe { *self.word }) != 0
+        DECODE_IS_D3D10_SB_OPCODE_EXTENDED(unsafe { self.word.read_unaligned() }) != 0
     }
 
     pub fn get_extended_opcode(&self) -> Option<OpcodeToken1<'a>> {
@@ -523,8 +625,8 @@ impl<'a> OperandToken1<'a> {
         }
     }
 
-    pub fn get_operand_modifier(&self) -> OperandModifier {
-        OperandModifier::from_word(unsafe { *self.word })
+    pub fn get_operand_modifier(&self) -> Option<OperandModifier> {
+        OperandModifier::from_word(unsafe { self.word.read_unaligned() })
     }
 }
 
@@ -543,19 +645,39 @@ impl<'a> OperandToken0<'a> {
         }
     }
 
-    pub fn parse<'b>(decoder: &mut decoder::Decoder<'b>) -> OperandToken0<'b> {
-        let operand = OperandToken0::from_word(decoder.read_u32_address());
+    pub fn parse<'b>(decoder: &mut decoder::Decoder<'b>) -> DecoderResult<OperandToken0<'b>> {
+        let offset = decoder.get_offset();
+        let operand = OperandToken0::from_word(decoder.read_u32_address()?);
+
+        // reject tokens the accessors below can't represent instead of
+        // panicking on them
+        let word = unsafe { operand.word.read_unaligned() };
+        if DECODE_D3D10_SB_OPERAND_TYPE(word) > OperandType::CycleCounter as u32 ||
+            DECODE_D3D10_SB_OPERAND_NUM_COMPONENTS(word) > D3D10_SB_OPERAND_4_COMPONENT ||
+            (DECODE_D3D10_SB_OPERAND_NUM_COMPONENTS(word) == D3D10_SB_OPERAND_4_COMPONENT &&
+                DECODE_D3D10_SB_OPERAND_4_COMPONENT_SELECTION_MODE(word) > D3D10_SB_OPERAND_4_COMPONENT_SELECT_1_MODE) ||
+            (0..DECODE_D3D10_SB_OPERAND_INDEX_DIMENSION(word)).any(|i| {
+                DECODE_D3D10_SB_OPERAND_INDEX_REPRESENTATION(i, word) > D3D10_SB_OPERAND_INDEX_IMMEDIATE64_PLUS_RELATIVE
+            }) {
+            return Err(Error::InvalidToken(offset, word));
+        }
 
         if operand.is_extended() {
-            decoder.skip(4);
+            let offset = decoder.get_offset();
+            let extended = decoder.read_u32()?;
+            if OperandModifier::from_word(extended).is_none() {
+                return Err(Error::InvalidToken(offset, extended));
+            }
         }
 
         let len = match operand.get_index_dimension() {
             IndexDimension::D0 => {
                 let ty = operand.get_operand_type();
 
+                // 64-bit immediates take two tokens per component
                 match ty {
-                    OperandType::Immediate32 | OperandType::Immediate64 => operand.get_num_components_u32(),
+                    OperandType::Immediate32 => operand.get_num_components_u32(),
+                    OperandType::Immediate64 => 2 * operand.get_num_components_u32(),
                     _ => 0
                 }
             }
@@ -570,28 +692,30 @@ impl<'a> OperandToken0<'a> {
 
             match repr {
                 IndexRepresentation::Immediate32 => {
-                    decoder.skip(4);
+                    decoder.skip(4)?;
                 },
                 IndexRepresentation::Immediate64 => {
-                    decoder.skip(8);
+                    decoder.skip(8)?;
                 },
                 IndexRepresentation::Relative => {
-                    let _ = OperandToken0::parse(decoder);
+                    let _ = OperandToken0::parse(decoder)?;
                 }
                 IndexRepresentation::Immediate32PlusRelative => {
-                    decoder.skip(4);
-                    let _ = OperandToken0::parse(decoder);
+                    decoder.skip(4)?;
+                    let _ = OperandToken0::parse(decoder)?;
                 },
                 IndexRepresentation::Immediate64PlusRelative => {
-                    decoder.skip(8);
-                    let _ = OperandToken0::parse(decoder);
+                    decoder.skip(8)?;
+                    let _ = OperandToken0::parse(decoder)?;
                 },
             }
         }
 
-        operand
+        Ok(operand)
     }
 
+    /// Number of tokens following this one that belong to the operand.
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> u32 {
         let mut len = 0;
         if self.is_extended() {
@@ -602,8 +726,10 @@ impl<'a> OperandToken0<'a> {
             IndexDimension::D0 => {
                 let ty = self.get_operand_type();
 
+                // 64-bit immediates take two tokens per component
                 match ty {
-                    OperandType::Immediate32 | OperandType::Immediate64 => self.get_num_components_u32(),
+                    OperandType::Immediate32 => self.get_num_components_u32(),
+                    OperandType::Immediate64 => 2 * self.get_num_components_u32(),
                     _ => 0
                 }
             }
@@ -624,15 +750,15 @@ impl<'a> OperandToken0<'a> {
                     len += 2;
                 },
                 IndexRepresentation::Relative => {
-                    len += OperandToken0::from_word(unsafe { self.word.offset(len as isize) }).len();
+                    len += 1 + OperandToken0::from_word(unsafe { self.word.offset(1 + len as isize) }).len();
                 }
                 IndexRepresentation::Immediate32PlusRelative => {
                     len += 1;
-                    len += OperandToken0::from_word(unsafe { self.word.offset(len as isize) }).len();
+                    len += 1 + OperandToken0::from_word(unsafe { self.word.offset(1 + len as isize) }).len();
                 },
                 IndexRepresentation::Immediate64PlusRelative => {
                     len += 2;
-                    len += OperandToken0::from_word(unsafe { self.word.offset(len as isize) }).len();
+                    len += 1 + OperandToken0::from_word(unsafe { self.word.offset(1 + len as isize) }).len();
                 },
             }
         }
@@ -677,13 +803,14 @@ impl<'a> OperandToken0<'a> {
         };
 
         for i in 0..len {
-            immediates.push(self.get_immediate(i));
+            immediates.extend(self.get_immediate(i));
         }
 
         immediates
     }
 
-    pub fn get_immediate(&self, index: u32) -> Immediate<'a> {
+    /// Index `index` of the operand, `None` past its index dimension.
+    pub fn get_immediate(&self, index: u32) -> Option<Immediate<'a>> {
         let len = match self.get_index_dimension() {
             IndexDimension::D0 => {
                 let ty = self.get_operand_type();
@@ -709,31 +836,31 @@ impl<'a> OperandToken0<'a> {
             if i == index {
                 match repr {
                     Immediate32 => {
-                        return Immediate::U32(
-                            unsafe { *imm.offset(offset as isize) }
-                        );
+                        return Some(Immediate::U32(
+                            unsafe { imm.offset(offset as isize).read_unaligned() }
+                        ));
                     },
                     Immediate64 => {
-                        return Immediate::U64(
-                            unsafe { *(imm.offset(offset as isize) as *const u64) }
-                        );
+                        return Some(Immediate::U64(
+                            unsafe { (imm.offset(offset as isize) as *const u64).read_unaligned() }
+                        ));
                     },
                     Relative => {
-                        return Immediate::Relative(
+                        return Some(Immediate::Relative(
                             OperandToken0::from_word(unsafe { imm.offset(offset as isize) })
-                        );
+                        ));
                     },
                     Immediate32PlusRelative => {
-                        return Immediate::U32Relative(
-                            unsafe { *imm.offset(offset as isize) },
+                        return Some(Immediate::U32Relative(
+                            unsafe { imm.offset(offset as isize).read_unaligned() },
                             OperandToken0::from_word(unsafe { imm.offset(1 + offset as isize) })
-                        );
+                        ));
                     },
                     Immediate64PlusRelative => {
-                        return Immediate::U64Relative(
-                            unsafe { *(imm.offset(offset as isize) as *const u64) },
+                        return Some(Immediate::U64Relative(
+                            unsafe { (imm.offset(offset as isize) as *const u64).read_unaligned() },
                             OperandToken0::from_word(unsafe { imm.offset(2 + offset as isize) })
-                        );
+                        ));
                     },
                 }
             } else {
@@ -745,29 +872,29 @@ impl<'a> OperandToken0<'a> {
                         offset += 2;
                     },
                     Relative => {
-                        offset += OperandToken0::from_word(unsafe { imm.offset(offset as isize) }).len();
+                        offset += 1 + OperandToken0::from_word(unsafe { imm.offset(offset as isize) }).len();
                     },
                     Immediate32PlusRelative => {
                         offset += 1;
-                        offset += OperandToken0::from_word(unsafe { imm.offset(offset as isize) }).len();
+                        offset += 1 + OperandToken0::from_word(unsafe { imm.offset(offset as isize) }).len();
                     },
                     Immediate64PlusRelative => {
                         offset += 2;
-                        offset += OperandToken0::from_word(unsafe { imm.offset(offset as isize) }).len();
+                        offset += 1 + OperandToken0::from_word(unsafe { imm.offset(offset as isize) }).len();
                     },
                 }
             }
         }
 
-        unreachable!()
+        None
     }
 
     pub fn is_extended(&self) -> bool {
-        DECODE_IS_D3D10_SB_OPERAND_EXTENDED(unsafe { *self.word }) != 0
+        DECODE_IS_D3D10_SB_OPERAND_EXTENDED(unsafe { self.word.read_unaligned() }) != 0
     }
 
     pub fn get_num_components(&self) -> NumComponents {
-        match DECODE_D3D10_SB_OPERAND_NUM_COMPONENTS(unsafe { *self.word }) {
+        match DECODE_D3D10_SB_OPERAND_NUM_COMPONENTS(unsafe { self.word.read_unaligned() }) {
             0 => NumComponents::Zero,
             1 => NumComponents::One,
             2 => NumComponents::Four,
@@ -777,7 +904,7 @@ impl<'a> OperandToken0<'a> {
     }
 
     pub fn get_num_components_u32(&self) -> u32 {
-        match DECODE_D3D10_SB_OPERAND_NUM_COMPONENTS(unsafe { *self.word }) {
+        match DECODE_D3D10_SB_OPERAND_NUM_COMPONENTS(unsafe { self.word.read_unaligned() }) {
             0 => 0,
             1 => 1,
             2 => 4,
@@ -785,24 +912,24 @@ impl<'a> OperandToken0<'a> {
         }
     }
 
-    pub fn get_component_select_mode(&self) -> ComponentSelectMode {
-        match DECODE_D3D10_SB_OPERAND_4_COMPONENT_SELECTION_MODE(unsafe { *self.word }) {
-            0 => ComponentSelectMode::Mask,
-            1 => ComponentSelectMode::Swizzle,
-            2 => ComponentSelectMode::Select1,
-            _ => unreachable!()
+    pub fn get_component_select_mode(&self) -> Option<ComponentSelectMode> {
+        match DECODE_D3D10_SB_OPERAND_4_COMPONENT_SELECTION_MODE(unsafe { self.word.read_unaligned() }) {
+            0 => Some(ComponentSelectMode::Mask),
+            1 => Some(ComponentSelectMode::Swizzle),
+            2 => Some(ComponentSelectMode::Select1),
+            _ => None,
         }
     }
 
     pub fn get_component_mask(&self) -> ComponentMask {
-        ComponentMask::from_bits_truncate(DECODE_D3D10_SB_OPERAND_4_COMPONENT_MASK(unsafe { *self.word }))
+        ComponentMask::from_bits_truncate(DECODE_D3D10_SB_OPERAND_4_COMPONENT_MASK(unsafe { self.word.read_unaligned() }))
     }
 
     pub fn get_component_swizzle(&self) -> ComponentSwizzle {
-        let x = DECODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_SOURCE(unsafe { *self.word }, D3D10_SB_4_COMPONENT_X) as u8;
-        let y = DECODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_SOURCE(unsafe { *self.word }, D3D10_SB_4_COMPONENT_Y) as u8;
-        let z = DECODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_SOURCE(unsafe { *self.word }, D3D10_SB_4_COMPONENT_Z) as u8;
-        let w = DECODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_SOURCE(unsafe { *self.word }, D3D10_SB_4_COMPONENT_W) as u8;
+        let x = DECODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_SOURCE(unsafe { self.word.read_unaligned() }, D3D10_SB_4_COMPONENT_X) as u8;
+        let y = DECODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_SOURCE(unsafe { self.word.read_unaligned() }, D3D10_SB_4_COMPONENT_Y) as u8;
+        let z = DECODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_SOURCE(unsafe { self.word.read_unaligned() }, D3D10_SB_4_COMPONENT_Z) as u8;
+        let w = DECODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_SOURCE(unsafe { self.word.read_unaligned() }, D3D10_SB_4_COMPONENT_W) as u8;
 
         ComponentSwizzle(
             ComponentName::from_word(x),
@@ -813,7 +940,7 @@ impl<'a> OperandToken0<'a> {
     }
 
     pub fn get_operand_type(&self) -> OperandType {
-        match DECODE_D3D10_SB_OPERAND_TYPE(unsafe { *self.word }) {
+        match DECODE_D3D10_SB_OPERAND_TYPE(unsafe { self.word.read_unaligned() }) {
             0 => OperandType::Temp,
             1 => OperandType::Input,
             2 => OperandType::Output,
@@ -860,7 +987,7 @@ impl<'a> OperandToken0<'a> {
     }
 
     pub fn get_index_dimension(&self) -> IndexDimension {
-        match DECODE_D3D10_SB_OPERAND_INDEX_DIMENSION(unsafe { *self.word }) {
+        match DECODE_D3D10_SB_OPERAND_INDEX_DIMENSION(unsafe { self.word.read_unaligned() }) {
             0 => IndexDimension::D0,
             1 => IndexDimension::D1,
             2 => IndexDimension::D2,
@@ -870,7 +997,7 @@ impl<'a> OperandToken0<'a> {
     }
 
     pub fn get_index_representation(&self, index: u32) -> IndexRepresentation {
-        match DECODE_D3D10_SB_OPERAND_INDEX_REPRESENTATION(index, unsafe { *self.word }) {
+        match DECODE_D3D10_SB_OPERAND_INDEX_REPRESENTATION(index, unsafe { self.word.read_unaligned() }) {
             0 => IndexRepresentation::Immediate32,
             1 => IndexRepresentation::Immediate64,
             2 => IndexRepresentation::Relative,
@@ -886,7 +1013,7 @@ use std::fmt;
 impl<'a> fmt::Debug for OperandToken0<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("OperandToken0")
-            .field("Raw", &unsafe { *self.word })
+            .field("Raw", &unsafe { self.word.read_unaligned() })
             .field("NumComponents", &self.get_num_components())
             .field("ComponentSelect", &self.get_component_select_mode())
             .field("ComponentMask", &self.get_component_mask())
@@ -946,7 +1073,7 @@ pub struct DclInput<'a> {
 impl<'a> DclInput<'a> {
     pub fn get_input_register(&self) -> u32 {
         match self.operand.get_immediate(0) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
@@ -960,7 +1087,7 @@ pub struct DclInputPs<'a> {
 impl<'a> DclInputPs<'a> {
     pub fn get_input_register(&self) -> u32 {
         match self.operand.get_immediate(0) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
@@ -974,7 +1101,7 @@ pub struct DclOutput<'a> {
 impl<'a> DclOutput<'a> {
     pub fn get_output_register(&self) -> u32 {
         match self.operand.get_immediate(0) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
@@ -997,14 +1124,14 @@ impl<'a> DclConstantBuffer<'a> {
 
     pub fn get_binding(&self) -> u32 {
         match self.operand.get_immediate(0) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
 
     pub fn get_size(&self) -> u32 {
         match self.operand.get_immediate(1) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
@@ -1019,7 +1146,7 @@ pub struct DclResource<'a> {
 impl<'a> DclResource<'a> {
     pub fn get_register(&self) -> u32 {
         match self.register.get_immediate(0) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
@@ -1033,7 +1160,7 @@ pub struct DclSampler<'a> {
 impl<'a> DclSampler<'a> {
     pub fn get_register(&self) -> u32 {
         match self.operand.get_immediate(0) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
@@ -1042,38 +1169,38 @@ impl<'a> DclSampler<'a> {
 #[derive(Debug)]
 pub struct DclOutputSiv<'a> {
     pub operand: OperandToken0<'a>,
-    pub operand_2: OperandToken0<'a>,
+    pub name: NameToken,
 }
 
 impl<'a> DclOutputSiv<'a> {
     pub fn get_output_register(&self) -> u32 {
         match self.operand.get_immediate(0) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
 
     pub fn get_system_name(&self) -> NameToken {
-        NameToken::from_word(DECODE_D3D10_SB_NAME(unsafe { *self.operand_2.word }))
+        self.name
     }
 }
 
 #[derive(Debug)]
 pub struct DclOutputSgv<'a> {
     pub operand: OperandToken0<'a>,
-    pub operand_2: OperandToken0<'a>,
+    pub name: NameToken,
 }
 
 impl<'a> DclOutputSgv<'a> {
     pub fn get_output_register(&self) -> u32 {
         match self.operand.get_immediate(0) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
 
     pub fn get_system_name(&self) -> NameToken {
-        NameToken::from_word(DECODE_D3D10_SB_NAME(unsafe { *self.operand_2.word }))
+        self.name
     }
 }
 
@@ -1082,38 +1209,38 @@ impl<'a> DclOutputSgv<'a> {
 #[derive(Debug)]
 pub struct DclInputPsSiv<'a> {
     pub operand: OperandToken0<'a>,
-    pub operand_2: OperandToken0<'a>,
+    pub name: NameToken,
 }
 
 impl<'a> DclInputPsSiv<'a> {
     pub fn get_input_register(&self) -> u32 {
         match self.operand.get_immediate(0) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
 
     pub fn get_system_name(&self) -> NameToken {
-        NameToken::from_word(DECODE_D3D10_SB_NAME(unsafe { *self.operand_2.word }))
+        self.name
     }
 }
 
 #[derive(Debug)]
 pub struct DclInputPsSgv<'a> {
     pub operand: OperandToken0<'a>,
-    pub operand_2: OperandToken0<'a>,
+    pub name: NameToken,
 }
 
 impl<'a> DclInputPsSgv<'a> {
     pub fn get_input_register(&self) -> u32 {
         match self.operand.get_immediate(0) {
-            Immediate::U32(reg) => reg,
+            Some(Immediate::U32(reg)) => reg,
             _ => !0
         }
     }
 
     pub fn get_system_name(&self) -> NameToken {
-        NameToken::from_word(DECODE_D3D10_SB_NAME(unsafe { *self.operand_2.word }))
+        self.name
     }
 }
 
@@ -1215,273 +1342,3227 @@ pub struct Sample<'a> {
     pub src_sampler: OperandToken0<'a>,
 }
 
-#[repr(C)]
 #[derive(Debug)]
-pub struct ShexHeader {
-    pub minor: u8,
-    pub major: u8,
-    pub program_type: u16,
-    pub instruction_length: u32,
+pub struct Call<'a> {
+    pub label: OperandToken0<'a>,
 }
 
-impl ShexHeader {
-    pub fn parse<'b>(decoder: &'b mut decoder::Decoder) -> Result<Self, State> {
-        let version = decoder.read_u8();
-        let minor = version & 0x0f;
-        let major = version >> 0x4;
-        decoder.skip(1);
-        let program_type = decoder.read_u16();
-        let instruction_length = decoder.read_u32() - 2;
+#[derive(Debug)]
+pub struct Callc<'a> {
+    pub src: OperandToken0<'a>,
+    pub label: OperandToken0<'a>,
+}
 
-        Ok(ShexHeader {
-            minor,
-            major,
-            program_type,
-            instruction_length
-        })
-    }
+#[derive(Debug)]
+pub struct Case<'a> {
+    pub src: OperandToken0<'a>,
 }
 
 #[derive(Debug)]
-pub struct SparseInstruction<'a> {
-    pub opcode: OpcodeToken0<'a>,
-    pub operands: Operands<'a>,
+pub struct Continuec<'a> {
+    pub src: OperandToken0<'a>,
 }
 
 #[derive(Debug)]
-pub enum Operands<'a> {
-    DclGlobalFlags(DclGlobalFlags),
-    DclInput(DclInput<'a>),
-    DclInputPs(DclInputPs<'a>),
-    DclOutput(DclOutput<'a>),
-    DclConstantBuffer(DclConstantBuffer<'a>),
-    DclResource(DclResource<'a>),
-    DclSampler(DclSampler<'a>),
-    DclOutputSiv(DclOutputSiv<'a>),
-    DclOutputSgv(DclOutputSgv<'a>),
-    DclInputPsSiv(DclInputPsSiv<'a>),
-    DclInputPsSgv(DclInputPsSgv<'a>),
-    DclTemps(DclTemps),
-    DclIndexableTemp(DclIndexableTemp),
-    Add(Add<'a>),
-    And(And<'a>),
-    Mul(Mul<'a>),
-    Mad(Mad<'a>),
-    Mov(Mov<'a>),
-    Itof(Itof<'a>),
-    Utof(Utof<'a>),
-    Ftou(Ftou<'a>),
-    If(If<'a>),
-    Else,
-    EndIf,
-    Loop,
-    EndLoop,
-    Break,
-    BreakC(BreakC<'a>),
-    Sample(Sample<'a>),
-    SampleL(SampleL<'a>),
-    Ret,
-    Unknown
+pub struct DerivRtx<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
 }
 
-impl<'a> SparseInstruction<'a> {
-    pub fn parse<'b>(decoder: &'b mut decoder::Decoder) -> SparseInstruction<'b> {
-        let opcode = OpcodeToken0::from_word(decoder.read_u32_address());
-        let ty = opcode.get_opcode_type();
-        let len = opcode.get_instruction_length();
-
-        let mut ex = opcode.get_extended_opcode();
-        while let Some(opc) = ex {
-            // println!("{:?}", opcode);
-            // println!("{:?}", opc);
-            ex = opc.get_extended_opcode();
-            decoder.skip(4);
-        }
+#[derive(Debug)]
+pub struct DerivRty<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
 
-        let operands = match ty {
-            D3D10_SB_OPCODE_DCL_GLOBAL_FLAGS => {
-                Operands::DclGlobalFlags(DclGlobalFlags {
-                    global_flags: DECODE_D3D10_SB_GLOBAL_FLAGS(unsafe { *opcode.word }),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_INPUT => {
-                Operands::DclInput(DclInput {
-                    operand: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_INPUT_PS => {
-                Operands::DclInputPs(DclInputPs {
-                    operand: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_INPUT_PS_SIV => {
-                Operands::DclInputPsSiv(DclInputPsSiv {
-                    operand: OperandToken0::parse(decoder),
-                    operand_2: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_INPUT_PS_SGV => {
-                Operands::DclInputPsSgv(DclInputPsSgv {
-                    operand: OperandToken0::parse(decoder),
-                    operand_2: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_OUTPUT => {
-                Operands::DclOutput(DclOutput {
-                    operand: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_CONSTANT_BUFFER => {
-                Operands::DclConstantBuffer(DclConstantBuffer {
-                    operand: OperandToken0::parse(decoder),
-                    access: DECODE_D3D10_SB_CONSTANT_BUFFER_ACCESS_PATTERN(unsafe { *opcode.word }),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_RESOURCE => {
-                Operands::DclResource(DclResource {
-                    register: OperandToken0::parse(decoder),
-                    return_type: ResourceReturnTypeToken0::from_word(decoder.read_u32_address()),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_SAMPLER => {
-                Operands::DclSampler(DclSampler {
-                    operand: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_TEMPS => {
-                Operands::DclTemps(DclTemps {
-                    register_count: decoder.read_u32(),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_INDEXABLE_TEMP => {
-                Operands::DclIndexableTemp(DclIndexableTemp {
-                    register_index: decoder.read_u32(),
-                    register_count: decoder.read_u32(),
-                    num_components: decoder.read_u32(),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_OUTPUT_SIV => {
-                Operands::DclOutputSiv(DclOutputSiv {
-                    operand: OperandToken0::parse(decoder),
-                    operand_2: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_DCL_OUTPUT_SGV => {
-                Operands::DclOutputSgv(DclOutputSgv {
-                    operand: OperandToken0::parse(decoder),
-                    operand_2: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_ADD => {
-                Operands::Add(Add {
-                    dst: OperandToken0::parse(decoder),
-                    a: OperandToken0::parse(decoder),
-                    b: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_AND => {
-                Operands::And(And {
-                    dst: OperandToken0::parse(decoder),
-                    a: OperandToken0::parse(decoder),
-                    b: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_MUL => {
-                Operands::Mul(Mul {
-                    dst: OperandToken0::parse(decoder),
-                    a: OperandToken0::parse(decoder),
-                    b: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_MAD => {
-                Operands::Mad(Mad {
-                    dst: OperandToken0::parse(decoder),
-                    a: OperandToken0::parse(decoder),
-                    b: OperandToken0::parse(decoder),
-                    c: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_MOV => {
-                Operands::Mov(Mov {
-                    dst: OperandToken0::parse(decoder),
-                    src: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_ITOF => {
-                Operands::Itof(Itof {
-                    dst: OperandToken0::parse(decoder),
-                    src: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_UTOF => {
-                Operands::Utof(Utof {
-                    dst: OperandToken0::parse(decoder),
-                    src: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_FTOU => {
-                Operands::Ftou(Ftou {
-                    dst: OperandToken0::parse(decoder),
-                    src: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_IF => {
-                Operands::If(If {
-                    src: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_ELSE => {
-                Operands::Else
-            }
-            D3D10_SB_OPCODE_ENDIF => {
-                Operands::EndIf
-            }
-            D3D10_SB_OPCODE_LOOP => {
-                Operands::Loop
-            }
-            D3D10_SB_OPCODE_ENDLOOP => {
-                Operands::EndLoop
-            }
-            D3D10_SB_OPCODE_BREAK => {
-                Operands::Break
-            }
-            D3D10_SB_OPCODE_BREAKC => {
-                Operands::BreakC(BreakC {
-                    src: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_SAMPLE => {
-                Operands::Sample(Sample {
-                    dst: OperandToken0::parse(decoder),
-                    src_address: OperandToken0::parse(decoder),
-                    src_resource: OperandToken0::parse(decoder),
-                    src_sampler: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_SAMPLE_L => {
-                Operands::SampleL(SampleL {
-                    dst: OperandToken0::parse(decoder),
-                    src_address: OperandToken0::parse(decoder),
-                    src_resource: OperandToken0::parse(decoder),
-                    src_sampler: OperandToken0::parse(decoder),
-                    src_lod: OperandToken0::parse(decoder),
-                })
-            }
-            D3D10_SB_OPCODE_RET => {
-                Operands::Ret
-            }
-            _ => {
-                if len > 1 {
-                    decoder.skip(4 * (len as usize  - 1));
-                }
+#[derive(Debug)]
+pub struct Discard<'a> {
+    pub src: OperandToken0<'a>,
+}
 
-                Operands::Unknown
-            }
-        };
+#[derive(Debug)]
+pub struct Div<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
 
-        SparseInstruction {
+#[derive(Debug)]
+pub struct Dp2<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dp3<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dp4<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Eq<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Exp<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Frc<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ftoi<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ge<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Iadd<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ieq<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ige<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ilt<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Imad<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+    pub c: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Imax<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Imin<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Imul<'a> {
+    pub dst_hi: OperandToken0<'a>,
+    pub dst_lo: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ine<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ineg<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ishl<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ishr<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Label<'a> {
+    pub label: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ld<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct LdMs<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sample_index: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Log<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Lt<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Min<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Max<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct CustomData {
+    pub class: u32,
+    pub data: Vec<u32>,
+}
+
+#[derive(Debug)]
+pub struct Movc<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+    pub c: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ne<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Not<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Or<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Resinfo<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_mip_level: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Retc<'a> {
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct RoundNe<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct RoundNi<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct RoundPi<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct RoundZ<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Rsq<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleC<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_reference: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleCLz<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_reference: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleD<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_x_derivative: OperandToken0<'a>,
+    pub src_y_derivative: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleB<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_bias: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Sqrt<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Switch<'a> {
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Sincos<'a> {
+    pub dst_sin: OperandToken0<'a>,
+    pub dst_cos: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Udiv<'a> {
+    pub dst_quotient: OperandToken0<'a>,
+    pub dst_remainder: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ult<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Uge<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Umul<'a> {
+    pub dst_hi: OperandToken0<'a>,
+    pub dst_lo: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Umad<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+    pub c: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Umax<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Umin<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ushr<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Xor<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DclIndexRange<'a> {
+    pub operand: OperandToken0<'a>,
+    pub register_count: u32,
+}
+
+#[derive(Debug)]
+pub struct DclGsOutputPrimitiveTopology {
+    pub primitive_topology: u32,
+}
+
+#[derive(Debug)]
+pub struct DclGsInputPrimitive {
+    pub primitive: u32,
+}
+
+#[derive(Debug)]
+pub struct DclMaxOutputVertexCount {
+    pub count: u32,
+}
+
+#[derive(Debug)]
+pub struct DclInputSgv<'a> {
+    pub operand: OperandToken0<'a>,
+    pub name: NameToken,
+}
+
+impl<'a> DclInputSgv<'a> {
+    pub fn get_input_register(&self) -> u32 {
+        match self.operand.get_immediate(0) {
+            Some(Immediate::U32(reg)) => reg,
+            _ => !0
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DclInputSiv<'a> {
+    pub operand: OperandToken0<'a>,
+    pub name: NameToken,
+}
+
+impl<'a> DclInputSiv<'a> {
+    pub fn get_input_register(&self) -> u32 {
+        match self.operand.get_immediate(0) {
+            Some(Immediate::U32(reg)) => reg,
+            _ => !0
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Lod<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Gather4<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SamplePos<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sample_index: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleInfo<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct EmitStream<'a> {
+    pub stream: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct CutStream<'a> {
+    pub stream: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct EmitThenCutStream<'a> {
+    pub stream: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct InterfaceCall<'a> {
+    pub function_index: u32,
+    pub interface: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Bufinfo<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DerivRtxCoarse<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DerivRtxFine<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DerivRtyCoarse<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DerivRtyFine<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Gather4C<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_reference: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Gather4Po<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_offset: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Gather4PoC<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_offset: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_reference: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Rcp<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct F32tof16<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct F16tof32<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Uaddc<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_carry: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Usubb<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_borrow: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Countbits<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct FirstbitHi<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct FirstbitLo<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct FirstbitShi<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ubfe<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+    pub c: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ibfe<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+    pub c: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Bfi<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+    pub c: OperandToken0<'a>,
+    pub d: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Bfrev<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Swapc<'a> {
+    pub dst_a: OperandToken0<'a>,
+    pub dst_b: OperandToken0<'a>,
+    pub src_select: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DclStream<'a> {
+    pub operand: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DclFunctionBody {
+    pub function_body: u32,
+}
+
+#[derive(Debug)]
+pub struct DclFunctionTable {
+    pub function_table: u32,
+    pub function_bodies: Vec<u32>,
+}
+
+#[derive(Debug)]
+pub struct DclInterface {
+    pub interface: u32,
+    pub dynamically_indexed: bool,
+    pub expected_table_length: u32,
+    pub array_length: u32,
+    pub function_tables: Vec<u32>,
+}
+
+#[derive(Debug)]
+pub struct DclInputControlPointCount {
+    pub count: u32,
+}
+
+#[derive(Debug)]
+pub struct DclOutputControlPointCount {
+    pub count: u32,
+}
+
+#[derive(Debug)]
+pub struct DclTessDomain {
+    pub domain: u32,
+}
+
+#[derive(Debug)]
+pub struct DclTessPartitioning {
+    pub partitioning: u32,
+}
+
+#[derive(Debug)]
+pub struct DclTessOutputPrimitive {
+    pub primitive: u32,
+}
+
+#[derive(Debug)]
+pub struct DclHsMaxTessfactor {
+    pub max_tessfactor: f32,
+}
+
+#[derive(Debug)]
+pub struct DclHsForkPhaseInstanceCount {
+    pub instance_count: u32,
+}
+
+#[derive(Debug)]
+pub struct DclHsJoinPhaseInstanceCount {
+    pub instance_count: u32,
+}
+
+#[derive(Debug)]
+pub struct DclThreadGroup {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+#[derive(Debug)]
+pub struct DclUnorderedAccessViewTyped<'a> {
+    pub operand: OperandToken0<'a>,
+    pub return_type: ResourceReturnTypeToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DclUnorderedAccessViewRaw<'a> {
+    pub operand: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DclUnorderedAccessViewStructured<'a> {
+    pub operand: OperandToken0<'a>,
+    pub stride: u32,
+}
+
+#[derive(Debug)]
+pub struct DclThreadGroupSharedMemoryRaw<'a> {
+    pub operand: OperandToken0<'a>,
+    pub byte_count: u32,
+}
+
+#[derive(Debug)]
+pub struct DclThreadGroupSharedMemoryStructured<'a> {
+    pub operand: OperandToken0<'a>,
+    pub stride: u32,
+    pub count: u32,
+}
+
+#[derive(Debug)]
+pub struct DclResourceRaw<'a> {
+    pub operand: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DclResourceStructured<'a> {
+    pub operand: OperandToken0<'a>,
+    pub stride: u32,
+}
+
+#[derive(Debug)]
+pub struct LdUavTyped<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_uav: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct StoreUavTyped<'a> {
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct LdRaw<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_byte_offset: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct StoreRaw<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_byte_offset: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct LdStructured<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_index: OperandToken0<'a>,
+    pub src_byte_offset: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct StoreStructured<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_index: OperandToken0<'a>,
+    pub src_byte_offset: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct AtomicAnd<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct AtomicOr<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct AtomicXor<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct AtomicCmpStore<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_compare: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct AtomicIadd<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct AtomicImax<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct AtomicImin<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct AtomicUmax<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct AtomicUmin<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicAlloc<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_uav: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicConsume<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src_uav: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicIadd<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicAnd<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicOr<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicXor<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicExch<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicCmpExch<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_compare: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicImax<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicImin<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicUmax<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct ImmAtomicUmin<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_uav: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_value: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Sync {
+    pub flags: u32,
+}
+
+impl Sync {
+    pub fn is_unordered_access_view_memory_global(&self) -> bool {
+        (self.flags & D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GLOBAL) != 0
+    }
+
+    pub fn is_unordered_access_view_memory_group(&self) -> bool {
+        (self.flags & D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GROUP) != 0
+    }
+
+    pub fn is_thread_group_shared_memory(&self) -> bool {
+        (self.flags & D3D11_SB_SYNC_THREAD_GROUP_SHARED_MEMORY) != 0
+    }
+
+    pub fn is_threads_in_group(&self) -> bool {
+        (self.flags & D3D11_SB_SYNC_THREADS_IN_GROUP) != 0
+    }
+}
+
+#[derive(Debug)]
+pub struct Dadd<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dmax<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dmin<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dmul<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Deq<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dge<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dlt<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dne<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dmov<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dmovc<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+    pub c: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dtof<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Ftod<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct EvalSnapped<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+    pub src_offset: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct EvalSampleIndex<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+    pub src_sample_index: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct EvalCentroid<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct DclGsInstanceCount {
+    pub instance_count: u32,
+}
+
+#[derive(Debug)]
+pub struct Ddiv<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dfma<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+    pub c: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Drcp<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Msad<'a> {
+    pub dst: OperandToken0<'a>,
+    pub a: OperandToken0<'a>,
+    pub b: OperandToken0<'a>,
+    pub c: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dtoi<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Dtou<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Itod<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Utod<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Gather4Feedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Gather4CFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_reference: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Gather4PoFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_offset: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct Gather4PoCFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_offset: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_reference: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct LdFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct LdMsFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sample_index: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct LdUavTypedFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_uav: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct LdRawFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_byte_offset: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct LdStructuredFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_index: OperandToken0<'a>,
+    pub src_byte_offset: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleLFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_lod: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleCLzFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_reference: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleClampFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_clamp: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleBClampFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_bias: OperandToken0<'a>,
+    pub src_clamp: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleDClampFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_x_derivative: OperandToken0<'a>,
+    pub src_y_derivative: OperandToken0<'a>,
+    pub src_clamp: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct SampleCClampFeedback<'a> {
+    pub dst: OperandToken0<'a>,
+    pub dst_feedback: OperandToken0<'a>,
+    pub src_address: OperandToken0<'a>,
+    pub src_resource: OperandToken0<'a>,
+    pub src_sampler: OperandToken0<'a>,
+    pub src_reference: OperandToken0<'a>,
+    pub src_clamp: OperandToken0<'a>,
+}
+
+#[derive(Debug)]
+pub struct CheckAccessFullyMapped<'a> {
+    pub dst: OperandToken0<'a>,
+    pub src: OperandToken0<'a>,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct ShexHeader {
+    pub minor: u8,
+    pub major: u8,
+    pub program_type: u16,
+    pub instruction_length: u32,
+}
+
+impl ShexHeader {
+    pub fn parse(decoder: &mut decoder::Decoder) -> Result<Self, State> {
+        let version = decoder.read_u8()?;
+        let minor = version & 0x0f;
+        let major = version >> 0x4;
+        decoder.skip(1)?;
+        let program_type = decoder.read_u16()?;
+        let instruction_length = decoder.read_u32()?;
+
+        // the length includes the version and length tokens
+        if instruction_length < 2 {
+            return Err(State::ChunkIncorrect);
+        }
+        let instruction_length = instruction_length - 2;
+
+        Ok(ShexHeader {
+            minor,
+            major,
+            program_type,
+            instruction_length
+        })
+    }
+
+    /// Whether `opcode` exists in the shader model of the program. Compute
+    /// shaders 4.x already have the raw and structured memory opcodes of 5.0,
+    /// opcodes past the known ones are left to the decoder.
+    pub fn supports(&self, opcode: u32) -> bool {
+        let version = (self.major, self.minor);
+
+        match opcode {
+            D3D10_1_SB_OPCODE_LOD..=D3D10_1_SB_OPCODE_SAMPLE_INFO => version >= (4, 1),
+            D3D11_SB_OPCODE_DCL_THREAD_GROUP |
+            D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_RAW |
+            D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_STRUCTURED |
+            D3D11_SB_OPCODE_DCL_THREAD_GROUP_SHARED_MEMORY_RAW |
+            D3D11_SB_OPCODE_DCL_THREAD_GROUP_SHARED_MEMORY_STRUCTURED |
+            D3D11_SB_OPCODE_DCL_RESOURCE_RAW |
+            D3D11_SB_OPCODE_DCL_RESOURCE_STRUCTURED |
+            D3D11_SB_OPCODE_LD_RAW |
+            D3D11_SB_OPCODE_STORE_RAW |
+            D3D11_SB_OPCODE_LD_STRUCTURED |
+            D3D11_SB_OPCODE_STORE_STRUCTURED |
+            D3D11_SB_OPCODE_SYNC => {
+                version >= (5, 0) || (version >= (4, 0) && self.program_type as u32 == D3D11_SB_COMPUTE_SHADER)
+            }
+            D3D10_1_SB_OPCODE_RESERVED1..=D3DWDDM1_3_SB_OPCODE_RESERVED0 => version >= (5, 0),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SparseInstruction<'a> {
+    pub opcode: OpcodeToken0<'a>,
+    pub operands: Operands<'a>,
+}
+
+#[derive(Debug)]
+pub enum Operands<'a> {
+    DclGlobalFlags(DclGlobalFlags),
+    DclInput(DclInput<'a>),
+    DclInputPs(DclInputPs<'a>),
+    DclOutput(DclOutput<'a>),
+    DclConstantBuffer(DclConstantBuffer<'a>),
+    DclResource(DclResource<'a>),
+    DclSampler(DclSampler<'a>),
+    DclOutputSiv(DclOutputSiv<'a>),
+    DclOutputSgv(DclOutputSgv<'a>),
+    DclInputPsSiv(DclInputPsSiv<'a>),
+    DclInputPsSgv(DclInputPsSgv<'a>),
+    DclTemps(DclTemps),
+    DclIndexableTemp(DclIndexableTemp),
+    Add(Add<'a>),
+    And(And<'a>),
+    Mul(Mul<'a>),
+    Mad(Mad<'a>),
+    Mov(Mov<'a>),
+    Itof(Itof<'a>),
+    Utof(Utof<'a>),
+    Ftou(Ftou<'a>),
+    If(If<'a>),
+    Else,
+    EndIf,
+    Loop,
+    EndLoop,
+    Break,
+    BreakC(BreakC<'a>),
+    Sample(Sample<'a>),
+    SampleL(SampleL<'a>),
+    Ret,
+    Call(Call<'a>),
+    Callc(Callc<'a>),
+    Case(Case<'a>),
+    Continue,
+    Continuec(Continuec<'a>),
+    Cut,
+    Default,
+    DerivRtx(DerivRtx<'a>),
+    DerivRty(DerivRty<'a>),
+    Discard(Discard<'a>),
+    Div(Div<'a>),
+    Dp2(Dp2<'a>),
+    Dp3(Dp3<'a>),
+    Dp4(Dp4<'a>),
+    Emit,
+    EmitThenCut,
+    EndSwitch,
+    Eq(Eq<'a>),
+    Exp(Exp<'a>),
+    Frc(Frc<'a>),
+    Ftoi(Ftoi<'a>),
+    Ge(Ge<'a>),
+    Iadd(Iadd<'a>),
+    Ieq(Ieq<'a>),
+    Ige(Ige<'a>),
+    Ilt(Ilt<'a>),
+    Imad(Imad<'a>),
+    Imax(Imax<'a>),
+    Imin(Imin<'a>),
+    Imul(Imul<'a>),
+    Ine(Ine<'a>),
+    Ineg(Ineg<'a>),
+    Ishl(Ishl<'a>),
+    Ishr(Ishr<'a>),
+    Label(Label<'a>),
+    Ld(Ld<'a>),
+    LdMs(LdMs<'a>),
+    Log(Log<'a>),
+    Lt(Lt<'a>),
+    Min(Min<'a>),
+    Max(Max<'a>),
+    CustomData(CustomData),
+    Movc(Movc<'a>),
+    Ne(Ne<'a>),
+    Nop,
+    Not(Not<'a>),
+    Or(Or<'a>),
+    Resinfo(Resinfo<'a>),
+    Retc(Retc<'a>),
+    RoundNe(RoundNe<'a>),
+    RoundNi(RoundNi<'a>),
+    RoundPi(RoundPi<'a>),
+    RoundZ(RoundZ<'a>),
+    Rsq(Rsq<'a>),
+    SampleC(SampleC<'a>),
+    SampleCLz(SampleCLz<'a>),
+    SampleD(SampleD<'a>),
+    SampleB(SampleB<'a>),
+    Sqrt(Sqrt<'a>),
+    Switch(Switch<'a>),
+    Sincos(Sincos<'a>),
+    Udiv(Udiv<'a>),
+    Ult(Ult<'a>),
+    Uge(Uge<'a>),
+    Umul(Umul<'a>),
+    Umad(Umad<'a>),
+    Umax(Umax<'a>),
+    Umin(Umin<'a>),
+    Ushr(Ushr<'a>),
+    Xor(Xor<'a>),
+    DclIndexRange(DclIndexRange<'a>),
+    DclGsOutputPrimitiveTopology(DclGsOutputPrimitiveTopology),
+    DclGsInputPrimitive(DclGsInputPrimitive),
+    DclMaxOutputVertexCount(DclMaxOutputVertexCount),
+    DclInputSgv(DclInputSgv<'a>),
+    DclInputSiv(DclInputSiv<'a>),
+    Lod(Lod<'a>),
+    Gather4(Gather4<'a>),
+    SamplePos(SamplePos<'a>),
+    SampleInfo(SampleInfo<'a>),
+    HsDecls,
+    HsControlPointPhase,
+    HsForkPhase,
+    HsJoinPhase,
+    EmitStream(EmitStream<'a>),
+    CutStream(CutStream<'a>),
+    EmitThenCutStream(EmitThenCutStream<'a>),
+    InterfaceCall(InterfaceCall<'a>),
+    Bufinfo(Bufinfo<'a>),
+    DerivRtxCoarse(DerivRtxCoarse<'a>),
+    DerivRtxFine(DerivRtxFine<'a>),
+    DerivRtyCoarse(DerivRtyCoarse<'a>),
+    DerivRtyFine(DerivRtyFine<'a>),
+    Gather4C(Gather4C<'a>),
+    Gather4Po(Gather4Po<'a>),
+    Gather4PoC(Gather4PoC<'a>),
+    Rcp(Rcp<'a>),
+    F32tof16(F32tof16<'a>),
+    F16tof32(F16tof32<'a>),
+    Uaddc(Uaddc<'a>),
+    Usubb(Usubb<'a>),
+    Countbits(Countbits<'a>),
+    FirstbitHi(FirstbitHi<'a>),
+    FirstbitLo(FirstbitLo<'a>),
+    FirstbitShi(FirstbitShi<'a>),
+    Ubfe(Ubfe<'a>),
+    Ibfe(Ibfe<'a>),
+    Bfi(Bfi<'a>),
+    Bfrev(Bfrev<'a>),
+    Swapc(Swapc<'a>),
+    DclStream(DclStream<'a>),
+    DclFunctionBody(DclFunctionBody),
+    DclFunctionTable(DclFunctionTable),
+    DclInterface(DclInterface),
+    DclInputControlPointCount(DclInputControlPointCount),
+    DclOutputControlPointCount(DclOutputControlPointCount),
+    DclTessDomain(DclTessDomain),
+    DclTessPartitioning(DclTessPartitioning),
+    DclTessOutputPrimitive(DclTessOutputPrimitive),
+    DclHsMaxTessfactor(DclHsMaxTessfactor),
+    DclHsForkPhaseInstanceCount(DclHsForkPhaseInstanceCount),
+    DclHsJoinPhaseInstanceCount(DclHsJoinPhaseInstanceCount),
+    DclThreadGroup(DclThreadGroup),
+    DclUnorderedAccessViewTyped(DclUnorderedAccessViewTyped<'a>),
+    DclUnorderedAccessViewRaw(DclUnorderedAccessViewRaw<'a>),
+    DclUnorderedAccessViewStructured(DclUnorderedAccessViewStructured<'a>),
+    DclThreadGroupSharedMemoryRaw(DclThreadGroupSharedMemoryRaw<'a>),
+    DclThreadGroupSharedMemoryStructured(DclThreadGroupSharedMemoryStructured<'a>),
+    DclResourceRaw(DclResourceRaw<'a>),
+    DclResourceStructured(DclResourceStructured<'a>),
+    LdUavTyped(LdUavTyped<'a>),
+    StoreUavTyped(StoreUavTyped<'a>),
+    LdRaw(LdRaw<'a>),
+    StoreRaw(StoreRaw<'a>),
+    LdStructured(LdStructured<'a>),
+    StoreStructured(StoreStructured<'a>),
+    AtomicAnd(AtomicAnd<'a>),
+    AtomicOr(AtomicOr<'a>),
+    AtomicXor(AtomicXor<'a>),
+    AtomicCmpStore(AtomicCmpStore<'a>),
+    AtomicIadd(AtomicIadd<'a>),
+    AtomicImax(AtomicImax<'a>),
+    AtomicImin(AtomicImin<'a>),
+    AtomicUmax(AtomicUmax<'a>),
+    AtomicUmin(AtomicUmin<'a>),
+    ImmAtomicAlloc(ImmAtomicAlloc<'a>),
+    ImmAtomicConsume(ImmAtomicConsume<'a>),
+    ImmAtomicIadd(ImmAtomicIadd<'a>),
+    ImmAtomicAnd(ImmAtomicAnd<'a>),
+    ImmAtomicOr(ImmAtomicOr<'a>),
+    ImmAtomicXor(ImmAtomicXor<'a>),
+    ImmAtomicExch(ImmAtomicExch<'a>),
+    ImmAtomicCmpExch(ImmAtomicCmpExch<'a>),
+    ImmAtomicImax(ImmAtomicImax<'a>),
+    ImmAtomicImin(ImmAtomicImin<'a>),
+    ImmAtomicUmax(ImmAtomicUmax<'a>),
+    ImmAtomicUmin(ImmAtomicUmin<'a>),
+    Sync(Sync),
+    Dadd(Dadd<'a>),
+    Dmax(Dmax<'a>),
+    Dmin(Dmin<'a>),
+    Dmul(Dmul<'a>),
+    Deq(Deq<'a>),
+    Dge(Dge<'a>),
+    Dlt(Dlt<'a>),
+    Dne(Dne<'a>),
+    Dmov(Dmov<'a>),
+    Dmovc(Dmovc<'a>),
+    Dtof(Dtof<'a>),
+    Ftod(Ftod<'a>),
+    EvalSnapped(EvalSnapped<'a>),
+    EvalSampleIndex(EvalSampleIndex<'a>),
+    EvalCentroid(EvalCentroid<'a>),
+    DclGsInstanceCount(DclGsInstanceCount),
+    Abort,
+    DebugBreak,
+    Ddiv(Ddiv<'a>),
+    Dfma(Dfma<'a>),
+    Drcp(Drcp<'a>),
+    Msad(Msad<'a>),
+    Dtoi(Dtoi<'a>),
+    Dtou(Dtou<'a>),
+    Itod(Itod<'a>),
+    Utod(Utod<'a>),
+    Gather4Feedback(Gather4Feedback<'a>),
+    Gather4CFeedback(Gather4CFeedback<'a>),
+    Gather4PoFeedback(Gather4PoFeedback<'a>),
+    Gather4PoCFeedback(Gather4PoCFeedback<'a>),
+    LdFeedback(LdFeedback<'a>),
+    LdMsFeedback(LdMsFeedback<'a>),
+    LdUavTypedFeedback(LdUavTypedFeedback<'a>),
+    LdRawFeedback(LdRawFeedback<'a>),
+    LdStructuredFeedback(LdStructuredFeedback<'a>),
+    SampleLFeedback(SampleLFeedback<'a>),
+    SampleCLzFeedback(SampleCLzFeedback<'a>),
+    SampleClampFeedback(SampleClampFeedback<'a>),
+    SampleBClampFeedback(SampleBClampFeedback<'a>),
+    SampleDClampFeedback(SampleDClampFeedback<'a>),
+    SampleCClampFeedback(SampleCClampFeedback<'a>),
+    CheckAccessFullyMapped(CheckAccessFullyMapped<'a>),
+    Unknown
+}
+
+impl<'a> SparseInstruction<'a> {
+    /// Decodes the next instruction of a program with the given header,
+    /// opcodes from a later shader model than the header's are an error.
+    pub fn parse<'b>(decoder: &'b mut decoder::Decoder, header: &ShexHeader) -> Result<SparseInstruction<'b>, State> {
+        let offset = decoder.get_offset();
+        let opcode = OpcodeToken0::from_word(decoder.read_u32_address()?);
+        let ty = opcode.get_opcode_type();
+
+        if !header.supports(ty) {
+            return Err(State::DecoderError(Error::UnavailableOpcode(offset, ty, header.major, header.minor)));
+        }
+
+        // custom data blocks store their length in the second token
+        let len = if ty == D3D10_SB_OPCODE_CUSTOMDATA {
+            decoder.read_u32()?
+        } else {
+            opcode.get_instruction_length()
+        };
+
+        // operands may not run past the declared instruction length
+        let consumed = decoder.get_offset() - offset;
+        match (len as usize * 4).checked_sub(consumed) {
+            Some(limit) => decoder.set_limit(limit),
+            None => return Err(State::DecoderError(Error::InvalidToken(offset, unsafe { opcode.word.read_unaligned() }))),
+        }
+
+        let mut extended = opcode.is_extended();
+        while extended {
+            let token_offset = decoder.get_offset();
+            let opc = OpcodeToken1::from_word(decoder.read_u32_address()?);
+            if opc.get_extended_opcode_type().is_none() {
+                return Err(State::DecoderError(Error::InvalidToken(token_offset, unsafe { opc.word.read_unaligned() })));
+            }
+            extended = opc.is_extended();
+        }
+
+        // opcode controls that the accessors of `OpcodeToken0` decode
+        let controls = match ty {
+            D3D10_SB_OPCODE_DCL_RESOURCE | D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_TYPED => {
+                opcode.get_resource_dimension().is_some()
+            }
+            D3D10_SB_OPCODE_DCL_SAMPLER => opcode.get_sampler_mode().is_some(),
+            D3D10_SB_OPCODE_DCL_INPUT_PS | D3D10_SB_OPCODE_DCL_INPUT_PS_SIV | D3D10_SB_OPCODE_DCL_INPUT_PS_SGV => {
+                opcode.get_interpolation_mode().is_some()
+            }
+            D3D10_SB_OPCODE_RESINFO => opcode.get_resinfo_return_type().is_some(),
+            _ => true,
+        };
+        if !controls {
+            return Err(State::DecoderError(Error::InvalidToken(offset, unsafe { opcode.word.read_unaligned() })));
+        }
+
+        let operands = match ty {
+            D3D10_SB_OPCODE_DCL_GLOBAL_FLAGS => {
+                Operands::DclGlobalFlags(DclGlobalFlags {
+                    global_flags: DECODE_D3D10_SB_GLOBAL_FLAGS(unsafe { opcode.word.read_unaligned() }),
+                })
+            }
+            D3D10_SB_OPCODE_DCL_INPUT => {
+                Operands::DclInput(DclInput {
+                    operand: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DCL_INPUT_PS => {
+                Operands::DclInputPs(DclInputPs {
+                    operand: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DCL_INPUT_PS_SIV => {
+                Operands::DclInputPsSiv(DclInputPsSiv {
+                    operand: OperandToken0::parse(decoder)?,
+                    name: NameToken::from_word(DECODE_D3D10_SB_NAME(decoder.read_u32()?)),
+                })
+            }
+            D3D10_SB_OPCODE_DCL_INPUT_PS_SGV => {
+                Operands::DclInputPsSgv(DclInputPsSgv {
+                    operand: OperandToken0::parse(decoder)?,
+                    name: NameToken::from_word(DECODE_D3D10_SB_NAME(decoder.read_u32()?)),
+                })
+            }
+            D3D10_SB_OPCODE_DCL_OUTPUT => {
+                Operands::DclOutput(DclOutput {
+                    operand: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DCL_CONSTANT_BUFFER => {
+                Operands::DclConstantBuffer(DclConstantBuffer {
+                    operand: OperandToken0::parse(decoder)?,
+                    access: DECODE_D3D10_SB_CONSTANT_BUFFER_ACCESS_PATTERN(unsafe { opcode.word.read_unaligned() }),
+                })
+            }
+            D3D10_SB_OPCODE_DCL_RESOURCE => {
+                Operands::DclResource(DclResource {
+                    register: OperandToken0::parse(decoder)?,
+                    return_type: ResourceReturnTypeToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DCL_SAMPLER => {
+                Operands::DclSampler(DclSampler {
+                    operand: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DCL_TEMPS => {
+                Operands::DclTemps(DclTemps {
+                    register_count: decoder.read_u32()?,
+                })
+            }
+            D3D10_SB_OPCODE_DCL_INDEXABLE_TEMP => {
+                Operands::DclIndexableTemp(DclIndexableTemp {
+                    register_index: decoder.read_u32()?,
+                    register_count: decoder.read_u32()?,
+                    num_components: decoder.read_u32()?,
+                })
+            }
+            D3D10_SB_OPCODE_DCL_OUTPUT_SIV => {
+                Operands::DclOutputSiv(DclOutputSiv {
+                    operand: OperandToken0::parse(decoder)?,
+                    name: NameToken::from_word(DECODE_D3D10_SB_NAME(decoder.read_u32()?)),
+                })
+            }
+            D3D10_SB_OPCODE_DCL_OUTPUT_SGV => {
+                Operands::DclOutputSgv(DclOutputSgv {
+                    operand: OperandToken0::parse(decoder)?,
+                    name: NameToken::from_word(DECODE_D3D10_SB_NAME(decoder.read_u32()?)),
+                })
+            }
+            D3D10_SB_OPCODE_ADD => {
+                Operands::Add(Add {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_AND => {
+                Operands::And(And {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_MUL => {
+                Operands::Mul(Mul {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_MAD => {
+                Operands::Mad(Mad {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                    c: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_MOV => {
+                Operands::Mov(Mov {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_ITOF => {
+                Operands::Itof(Itof {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_UTOF => {
+                Operands::Utof(Utof {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_FTOU => {
+                Operands::Ftou(Ftou {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_IF => {
+                Operands::If(If {
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_ELSE => {
+                Operands::Else
+            }
+            D3D10_SB_OPCODE_ENDIF => {
+                Operands::EndIf
+            }
+            D3D10_SB_OPCODE_LOOP => {
+                Operands::Loop
+            }
+            D3D10_SB_OPCODE_ENDLOOP => {
+                Operands::EndLoop
+            }
+            D3D10_SB_OPCODE_BREAK => {
+                Operands::Break
+            }
+            D3D10_SB_OPCODE_BREAKC => {
+                Operands::BreakC(BreakC {
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_SAMPLE => {
+                Operands::Sample(Sample {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_SAMPLE_L => {
+                Operands::SampleL(SampleL {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_lod: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_RET => {
+                Operands::Ret
+            }
+            D3D10_SB_OPCODE_CALL => {
+                Operands::Call(Call {
+                    label: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_CALLC => {
+                Operands::Callc(Callc {
+                    src: OperandToken0::parse(decoder)?,
+                    label: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_CASE => {
+                Operands::Case(Case {
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_CONTINUE => {
+                Operands::Continue
+            }
+            D3D10_SB_OPCODE_CONTINUEC => {
+                Operands::Continuec(Continuec {
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_CUT => {
+                Operands::Cut
+            }
+            D3D10_SB_OPCODE_DEFAULT => {
+                Operands::Default
+            }
+            D3D10_SB_OPCODE_DERIV_RTX => {
+                Operands::DerivRtx(DerivRtx {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DERIV_RTY => {
+                Operands::DerivRty(DerivRty {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DISCARD => {
+                Operands::Discard(Discard {
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DIV => {
+                Operands::Div(Div {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DP2 => {
+                Operands::Dp2(Dp2 {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DP3 => {
+                Operands::Dp3(Dp3 {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DP4 => {
+                Operands::Dp4(Dp4 {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_EMIT => {
+                Operands::Emit
+            }
+            D3D10_SB_OPCODE_EMITTHENCUT => {
+                Operands::EmitThenCut
+            }
+            D3D10_SB_OPCODE_ENDSWITCH => {
+                Operands::EndSwitch
+            }
+            D3D10_SB_OPCODE_EQ => {
+                Operands::Eq(Eq {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_EXP => {
+                Operands::Exp(Exp {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_FRC => {
+                Operands::Frc(Frc {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_FTOI => {
+                Operands::Ftoi(Ftoi {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_GE => {
+                Operands::Ge(Ge {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_IADD => {
+                Operands::Iadd(Iadd {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_IEQ => {
+                Operands::Ieq(Ieq {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_IGE => {
+                Operands::Ige(Ige {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_ILT => {
+                Operands::Ilt(Ilt {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_IMAD => {
+                Operands::Imad(Imad {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                    c: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_IMAX => {
+                Operands::Imax(Imax {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_IMIN => {
+                Operands::Imin(Imin {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_IMUL => {
+                Operands::Imul(Imul {
+                    dst_hi: OperandToken0::parse(decoder)?,
+                    dst_lo: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_INE => {
+                Operands::Ine(Ine {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_INEG => {
+                Operands::Ineg(Ineg {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_ISHL => {
+                Operands::Ishl(Ishl {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_ISHR => {
+                Operands::Ishr(Ishr {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_LABEL => {
+                Operands::Label(Label {
+                    label: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_LD => {
+                Operands::Ld(Ld {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_LD_MS => {
+                Operands::LdMs(LdMs {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sample_index: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_LOG => {
+                Operands::Log(Log {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_LT => {
+                Operands::Lt(Lt {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_MIN => {
+                Operands::Min(Min {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_MAX => {
+                Operands::Max(Max {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_CUSTOMDATA => {
+                Operands::CustomData(CustomData {
+                    class: DECODE_D3D10_SB_CUSTOMDATA_CLASS(unsafe { opcode.word.read_unaligned() }),
+                    data: decoder.words(len as usize - 2)?,
+                })
+            }
+            D3D10_SB_OPCODE_MOVC => {
+                Operands::Movc(Movc {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                    c: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_NE => {
+                Operands::Ne(Ne {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_NOP => {
+                Operands::Nop
+            }
+            D3D10_SB_OPCODE_NOT => {
+                Operands::Not(Not {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_OR => {
+                Operands::Or(Or {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_RESINFO => {
+                let word = unsafe { opcode.word.read_unaligned() };
+                if DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(word) > D3D10_SB_RESINFO_INSTRUCTION_RETURN_UINT {
+                    return Err(State::DecoderError(Error::InvalidToken(offset, word)));
+                }
+
+                Operands::Resinfo(Resinfo {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_mip_level: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_RETC => {
+                Operands::Retc(Retc {
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_ROUND_NE => {
+                Operands::RoundNe(RoundNe {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_ROUND_NI => {
+                Operands::RoundNi(RoundNi {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_ROUND_PI => {
+                Operands::RoundPi(RoundPi {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_ROUND_Z => {
+                Operands::RoundZ(RoundZ {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_RSQ => {
+                Operands::Rsq(Rsq {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_SAMPLE_C => {
+                Operands::SampleC(SampleC {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_reference: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_SAMPLE_C_LZ => {
+                Operands::SampleCLz(SampleCLz {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_reference: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_SAMPLE_D => {
+                Operands::SampleD(SampleD {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_x_derivative: OperandToken0::parse(decoder)?,
+                    src_y_derivative: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_SAMPLE_B => {
+                Operands::SampleB(SampleB {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_bias: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_SQRT => {
+                Operands::Sqrt(Sqrt {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_SWITCH => {
+                Operands::Switch(Switch {
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_SINCOS => {
+                Operands::Sincos(Sincos {
+                    dst_sin: OperandToken0::parse(decoder)?,
+                    dst_cos: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_UDIV => {
+                Operands::Udiv(Udiv {
+                    dst_quotient: OperandToken0::parse(decoder)?,
+                    dst_remainder: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_ULT => {
+                Operands::Ult(Ult {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_UGE => {
+                Operands::Uge(Uge {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_UMUL => {
+                Operands::Umul(Umul {
+                    dst_hi: OperandToken0::parse(decoder)?,
+                    dst_lo: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_UMAD => {
+                Operands::Umad(Umad {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                    c: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_UMAX => {
+                Operands::Umax(Umax {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_UMIN => {
+                Operands::Umin(Umin {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_USHR => {
+                Operands::Ushr(Ushr {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_XOR => {
+                Operands::Xor(Xor {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_SB_OPCODE_DCL_INDEX_RANGE => {
+                Operands::DclIndexRange(DclIndexRange {
+                    operand: OperandToken0::parse(decoder)?,
+                    register_count: decoder.read_u32()?,
+                })
+            }
+            D3D10_SB_OPCODE_DCL_GS_OUTPUT_PRIMITIVE_TOPOLOGY => {
+                Operands::DclGsOutputPrimitiveTopology(DclGsOutputPrimitiveTopology {
+                    primitive_topology: DECODE_D3D10_SB_GS_OUTPUT_PRIMITIVE_TOPOLOGY(unsafe { opcode.word.read_unaligned() }),
+                })
+            }
+            D3D10_SB_OPCODE_DCL_GS_INPUT_PRIMITIVE => {
+                Operands::DclGsInputPrimitive(DclGsInputPrimitive {
+                    primitive: DECODE_D3D10_SB_GS_INPUT_PRIMITIVE(unsafe { opcode.word.read_unaligned() }),
+                })
+            }
+            D3D10_SB_OPCODE_DCL_MAX_OUTPUT_VERTEX_COUNT => {
+                Operands::DclMaxOutputVertexCount(DclMaxOutputVertexCount {
+                    count: decoder.read_u32()?,
+                })
+            }
+            D3D10_SB_OPCODE_DCL_INPUT_SGV => {
+                Operands::DclInputSgv(DclInputSgv {
+                    operand: OperandToken0::parse(decoder)?,
+                    name: NameToken::from_word(DECODE_D3D10_SB_NAME(decoder.read_u32()?)),
+                })
+            }
+            D3D10_SB_OPCODE_DCL_INPUT_SIV => {
+                Operands::DclInputSiv(DclInputSiv {
+                    operand: OperandToken0::parse(decoder)?,
+                    name: NameToken::from_word(DECODE_D3D10_SB_NAME(decoder.read_u32()?)),
+                })
+            }
+            D3D10_1_SB_OPCODE_LOD => {
+                Operands::Lod(Lod {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_1_SB_OPCODE_GATHER4 => {
+                Operands::Gather4(Gather4 {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_1_SB_OPCODE_SAMPLE_POS => {
+                Operands::SamplePos(SamplePos {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sample_index: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D10_1_SB_OPCODE_SAMPLE_INFO => {
+                Operands::SampleInfo(SampleInfo {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_HS_DECLS => {
+                Operands::HsDecls
+            }
+            D3D11_SB_OPCODE_HS_CONTROL_POINT_PHASE => {
+                Operands::HsControlPointPhase
+            }
+            D3D11_SB_OPCODE_HS_FORK_PHASE => {
+                Operands::HsForkPhase
+            }
+            D3D11_SB_OPCODE_HS_JOIN_PHASE => {
+                Operands::HsJoinPhase
+            }
+            D3D11_SB_OPCODE_EMIT_STREAM => {
+                Operands::EmitStream(EmitStream {
+                    stream: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_CUT_STREAM => {
+                Operands::CutStream(CutStream {
+                    stream: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_EMITTHENCUT_STREAM => {
+                Operands::EmitThenCutStream(EmitThenCutStream {
+                    stream: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_INTERFACE_CALL => {
+                Operands::InterfaceCall(InterfaceCall {
+                    function_index: decoder.read_u32()?,
+                    interface: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_BUFINFO => {
+                Operands::Bufinfo(Bufinfo {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DERIV_RTX_COARSE => {
+                Operands::DerivRtxCoarse(DerivRtxCoarse {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DERIV_RTX_FINE => {
+                Operands::DerivRtxFine(DerivRtxFine {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DERIV_RTY_COARSE => {
+                Operands::DerivRtyCoarse(DerivRtyCoarse {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DERIV_RTY_FINE => {
+                Operands::DerivRtyFine(DerivRtyFine {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_GATHER4_C => {
+                Operands::Gather4C(Gather4C {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_reference: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_GATHER4_PO => {
+                Operands::Gather4Po(Gather4Po {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_offset: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_GATHER4_PO_C => {
+                Operands::Gather4PoC(Gather4PoC {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_offset: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_reference: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_RCP => {
+                Operands::Rcp(Rcp {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_F32TOF16 => {
+                Operands::F32tof16(F32tof16 {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_F16TOF32 => {
+                Operands::F16tof32(F16tof32 {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_UADDC => {
+                Operands::Uaddc(Uaddc {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_carry: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_USUBB => {
+                Operands::Usubb(Usubb {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_borrow: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_COUNTBITS => {
+                Operands::Countbits(Countbits {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_FIRSTBIT_HI => {
+                Operands::FirstbitHi(FirstbitHi {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_FIRSTBIT_LO => {
+                Operands::FirstbitLo(FirstbitLo {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_FIRSTBIT_SHI => {
+                Operands::FirstbitShi(FirstbitShi {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_UBFE => {
+                Operands::Ubfe(Ubfe {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                    c: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IBFE => {
+                Operands::Ibfe(Ibfe {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                    c: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_BFI => {
+                Operands::Bfi(Bfi {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                    c: OperandToken0::parse(decoder)?,
+                    d: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_BFREV => {
+                Operands::Bfrev(Bfrev {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_SWAPC => {
+                Operands::Swapc(Swapc {
+                    dst_a: OperandToken0::parse(decoder)?,
+                    dst_b: OperandToken0::parse(decoder)?,
+                    src_select: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_STREAM => {
+                Operands::DclStream(DclStream {
+                    operand: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_FUNCTION_BODY => {
+                Operands::DclFunctionBody(DclFunctionBody {
+                    function_body: decoder.read_u32()?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_FUNCTION_TABLE => {
+                let function_table = decoder.read_u32()?;
+                let table_length = decoder.read_u32()?;
+
+                Operands::DclFunctionTable(DclFunctionTable {
+                    function_table,
+                    function_bodies: decoder.words(table_length as usize)?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_INTERFACE => {
+                let interface = decoder.read_u32()?;
+                let expected_table_length = decoder.read_u32()?;
+                let lengths = decoder.read_u32()?;
+
+                Operands::DclInterface(DclInterface {
+                    interface,
+                    dynamically_indexed: DECODE_D3D11_SB_INTERFACE_INDEXED_BIT(unsafe { opcode.word.read_unaligned() }) != 0,
+                    expected_table_length,
+                    array_length: DECODE_D3D11_SB_INTERFACE_ARRAY_LENGTH(lengths),
+                    function_tables: decoder.words(DECODE_D3D11_SB_INTERFACE_TABLE_LENGTH(lengths) as usize)?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_INPUT_CONTROL_POINT_COUNT => {
+                Operands::DclInputControlPointCount(DclInputControlPointCount {
+                    count: DECODE_D3D11_SB_INPUT_CONTROL_POINT_COUNT(unsafe { opcode.word.read_unaligned() }),
+                })
+            }
+            D3D11_SB_OPCODE_DCL_OUTPUT_CONTROL_POINT_COUNT => {
+                Operands::DclOutputControlPointCount(DclOutputControlPointCount {
+                    count: DECODE_D3D11_SB_OUTPUT_CONTROL_POINT_COUNT(unsafe { opcode.word.read_unaligned() }),
+                })
+            }
+            D3D11_SB_OPCODE_DCL_TESS_DOMAIN => {
+                Operands::DclTessDomain(DclTessDomain {
+                    domain: DECODE_D3D11_SB_TESS_DOMAIN(unsafe { opcode.word.read_unaligned() }),
+                })
+            }
+            D3D11_SB_OPCODE_DCL_TESS_PARTITIONING => {
+                Operands::DclTessPartitioning(DclTessPartitioning {
+                    partitioning: DECODE_D3D11_SB_TESS_PARTITIONING(unsafe { opcode.word.read_unaligned() }),
+                })
+            }
+            D3D11_SB_OPCODE_DCL_TESS_OUTPUT_PRIMITIVE => {
+                Operands::DclTessOutputPrimitive(DclTessOutputPrimitive {
+                    primitive: DECODE_D3D11_SB_TESS_OUTPUT_PRIMITIVE(unsafe { opcode.word.read_unaligned() }),
+                })
+            }
+            D3D11_SB_OPCODE_DCL_HS_MAX_TESSFACTOR => {
+                Operands::DclHsMaxTessfactor(DclHsMaxTessfactor {
+                    max_tessfactor: f32::from_bits(decoder.read_u32()?),
+                })
+            }
+            D3D11_SB_OPCODE_DCL_HS_FORK_PHASE_INSTANCE_COUNT => {
+                Operands::DclHsForkPhaseInstanceCount(DclHsForkPhaseInstanceCount {
+                    instance_count: decoder.read_u32()?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_HS_JOIN_PHASE_INSTANCE_COUNT => {
+                Operands::DclHsJoinPhaseInstanceCount(DclHsJoinPhaseInstanceCount {
+                    instance_count: decoder.read_u32()?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_THREAD_GROUP => {
+                Operands::DclThreadGroup(DclThreadGroup {
+                    x: decoder.read_u32()?,
+                    y: decoder.read_u32()?,
+                    z: decoder.read_u32()?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_TYPED => {
+                Operands::DclUnorderedAccessViewTyped(DclUnorderedAccessViewTyped {
+                    operand: OperandToken0::parse(decoder)?,
+                    return_type: ResourceReturnTypeToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_RAW => {
+                Operands::DclUnorderedAccessViewRaw(DclUnorderedAccessViewRaw {
+                    operand: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_STRUCTURED => {
+                Operands::DclUnorderedAccessViewStructured(DclUnorderedAccessViewStructured {
+                    operand: OperandToken0::parse(decoder)?,
+                    stride: decoder.read_u32()?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_THREAD_GROUP_SHARED_MEMORY_RAW => {
+                Operands::DclThreadGroupSharedMemoryRaw(DclThreadGroupSharedMemoryRaw {
+                    operand: OperandToken0::parse(decoder)?,
+                    byte_count: decoder.read_u32()?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_THREAD_GROUP_SHARED_MEMORY_STRUCTURED => {
+                Operands::DclThreadGroupSharedMemoryStructured(DclThreadGroupSharedMemoryStructured {
+                    operand: OperandToken0::parse(decoder)?,
+                    stride: decoder.read_u32()?,
+                    count: decoder.read_u32()?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_RESOURCE_RAW => {
+                Operands::DclResourceRaw(DclResourceRaw {
+                    operand: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_RESOURCE_STRUCTURED => {
+                Operands::DclResourceStructured(DclResourceStructured {
+                    operand: OperandToken0::parse(decoder)?,
+                    stride: decoder.read_u32()?,
+                })
+            }
+            D3D11_SB_OPCODE_LD_UAV_TYPED => {
+                Operands::LdUavTyped(LdUavTyped {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_uav: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_STORE_UAV_TYPED => {
+                Operands::StoreUavTyped(StoreUavTyped {
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_LD_RAW => {
+                Operands::LdRaw(LdRaw {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_byte_offset: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_STORE_RAW => {
+                Operands::StoreRaw(StoreRaw {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_byte_offset: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_LD_STRUCTURED => {
+                Operands::LdStructured(LdStructured {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_index: OperandToken0::parse(decoder)?,
+                    src_byte_offset: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_STORE_STRUCTURED => {
+                Operands::StoreStructured(StoreStructured {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_index: OperandToken0::parse(decoder)?,
+                    src_byte_offset: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_ATOMIC_AND => {
+                Operands::AtomicAnd(AtomicAnd {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_ATOMIC_OR => {
+                Operands::AtomicOr(AtomicOr {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_ATOMIC_XOR => {
+                Operands::AtomicXor(AtomicXor {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE => {
+                Operands::AtomicCmpStore(AtomicCmpStore {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_compare: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_ATOMIC_IADD => {
+                Operands::AtomicIadd(AtomicIadd {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_ATOMIC_IMAX => {
+                Operands::AtomicImax(AtomicImax {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_ATOMIC_IMIN => {
+                Operands::AtomicImin(AtomicImin {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_ATOMIC_UMAX => {
+                Operands::AtomicUmax(AtomicUmax {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_ATOMIC_UMIN => {
+                Operands::AtomicUmin(AtomicUmin {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC => {
+                Operands::ImmAtomicAlloc(ImmAtomicAlloc {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_uav: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_CONSUME => {
+                Operands::ImmAtomicConsume(ImmAtomicConsume {
+                    dst: OperandToken0::parse(decoder)?,
+                    src_uav: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_IADD => {
+                Operands::ImmAtomicIadd(ImmAtomicIadd {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_AND => {
+                Operands::ImmAtomicAnd(ImmAtomicAnd {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_OR => {
+                Operands::ImmAtomicOr(ImmAtomicOr {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_XOR => {
+                Operands::ImmAtomicXor(ImmAtomicXor {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_EXCH => {
+                Operands::ImmAtomicExch(ImmAtomicExch {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => {
+                Operands::ImmAtomicCmpExch(ImmAtomicCmpExch {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_compare: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_IMAX => {
+                Operands::ImmAtomicImax(ImmAtomicImax {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_IMIN => {
+                Operands::ImmAtomicImin(ImmAtomicImin {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_UMAX => {
+                Operands::ImmAtomicUmax(ImmAtomicUmax {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_UMIN => {
+                Operands::ImmAtomicUmin(ImmAtomicUmin {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_uav: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_value: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_SYNC => {
+                Operands::Sync(Sync {
+                    flags: DECODE_D3D11_SB_SYNC_FLAGS(unsafe { opcode.word.read_unaligned() }),
+                })
+            }
+            D3D11_SB_OPCODE_DADD => {
+                Operands::Dadd(Dadd {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DMAX => {
+                Operands::Dmax(Dmax {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DMIN => {
+                Operands::Dmin(Dmin {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DMUL => {
+                Operands::Dmul(Dmul {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DEQ => {
+                Operands::Deq(Deq {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DGE => {
+                Operands::Dge(Dge {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DLT => {
+                Operands::Dlt(Dlt {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DNE => {
+                Operands::Dne(Dne {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DMOV => {
+                Operands::Dmov(Dmov {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DMOVC => {
+                Operands::Dmovc(Dmovc {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                    c: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DTOF => {
+                Operands::Dtof(Dtof {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_FTOD => {
+                Operands::Ftod(Ftod {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_EVAL_SNAPPED => {
+                Operands::EvalSnapped(EvalSnapped {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                    src_offset: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX => {
+                Operands::EvalSampleIndex(EvalSampleIndex {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                    src_sample_index: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_EVAL_CENTROID => {
+                Operands::EvalCentroid(EvalCentroid {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_SB_OPCODE_DCL_GS_INSTANCE_COUNT => {
+                Operands::DclGsInstanceCount(DclGsInstanceCount {
+                    instance_count: decoder.read_u32()?,
+                })
+            }
+            D3D11_SB_OPCODE_ABORT => {
+                Operands::Abort
+            }
+            D3D11_SB_OPCODE_DEBUG_BREAK => {
+                Operands::DebugBreak
+            }
+            D3D11_1_SB_OPCODE_DDIV => {
+                Operands::Ddiv(Ddiv {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_1_SB_OPCODE_DFMA => {
+                Operands::Dfma(Dfma {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                    c: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_1_SB_OPCODE_DRCP => {
+                Operands::Drcp(Drcp {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_1_SB_OPCODE_MSAD => {
+                Operands::Msad(Msad {
+                    dst: OperandToken0::parse(decoder)?,
+                    a: OperandToken0::parse(decoder)?,
+                    b: OperandToken0::parse(decoder)?,
+                    c: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_1_SB_OPCODE_DTOI => {
+                Operands::Dtoi(Dtoi {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_1_SB_OPCODE_DTOU => {
+                Operands::Dtou(Dtou {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_1_SB_OPCODE_ITOD => {
+                Operands::Itod(Itod {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3D11_1_SB_OPCODE_UTOD => {
+                Operands::Utod(Utod {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_GATHER4_FEEDBACK => {
+                Operands::Gather4Feedback(Gather4Feedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_GATHER4_C_FEEDBACK => {
+                Operands::Gather4CFeedback(Gather4CFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_reference: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_GATHER4_PO_FEEDBACK => {
+                Operands::Gather4PoFeedback(Gather4PoFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_offset: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_GATHER4_PO_C_FEEDBACK => {
+                Operands::Gather4PoCFeedback(Gather4PoCFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_offset: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_reference: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_LD_FEEDBACK => {
+                Operands::LdFeedback(LdFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_LD_MS_FEEDBACK => {
+                Operands::LdMsFeedback(LdMsFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sample_index: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_LD_UAV_TYPED_FEEDBACK => {
+                Operands::LdUavTypedFeedback(LdUavTypedFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_uav: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_LD_RAW_FEEDBACK => {
+                Operands::LdRawFeedback(LdRawFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_byte_offset: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_LD_STRUCTURED_FEEDBACK => {
+                Operands::LdStructuredFeedback(LdStructuredFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_index: OperandToken0::parse(decoder)?,
+                    src_byte_offset: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_SAMPLE_L_FEEDBACK => {
+                Operands::SampleLFeedback(SampleLFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_lod: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_SAMPLE_C_LZ_FEEDBACK => {
+                Operands::SampleCLzFeedback(SampleCLzFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_reference: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_SAMPLE_CLAMP_FEEDBACK => {
+                Operands::SampleClampFeedback(SampleClampFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_clamp: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_SAMPLE_B_CLAMP_FEEDBACK => {
+                Operands::SampleBClampFeedback(SampleBClampFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_bias: OperandToken0::parse(decoder)?,
+                    src_clamp: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_SAMPLE_D_CLAMP_FEEDBACK => {
+                Operands::SampleDClampFeedback(SampleDClampFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_x_derivative: OperandToken0::parse(decoder)?,
+                    src_y_derivative: OperandToken0::parse(decoder)?,
+                    src_clamp: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_SAMPLE_C_CLAMP_FEEDBACK => {
+                Operands::SampleCClampFeedback(SampleCClampFeedback {
+                    dst: OperandToken0::parse(decoder)?,
+                    dst_feedback: OperandToken0::parse(decoder)?,
+                    src_address: OperandToken0::parse(decoder)?,
+                    src_resource: OperandToken0::parse(decoder)?,
+                    src_sampler: OperandToken0::parse(decoder)?,
+                    src_reference: OperandToken0::parse(decoder)?,
+                    src_clamp: OperandToken0::parse(decoder)?,
+                })
+            }
+            D3DWDDM1_3_SB_OPCODE_CHECK_ACCESS_FULLY_MAPPED => {
+                Operands::CheckAccessFullyMapped(CheckAccessFullyMapped {
+                    dst: OperandToken0::parse(decoder)?,
+                    src: OperandToken0::parse(decoder)?,
+                })
+            }
+            _ => {
+                Operands::Unknown
+            }
+        };
+
+        // skip whatever the instruction carries beyond the decoded operands
+        if let Some(remaining) = decoder.get_limit() {
+            decoder.skip(remaining)?;
+        }
+        decoder.clear_limit();
+
+        Ok(SparseInstruction {
             opcode,
             operands,
-        }
+        })
     }
 }
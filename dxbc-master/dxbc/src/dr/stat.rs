@@ -22,30 +22,67 @@ pub struct IStatChunk {
     pub texture_comp_instructions: u32,
     pub texture_bias_instructions: u32,
     pub texture_gradient_instructions: u32,
+    /// Counts following the ones above, shader model 5 compilers write 18
+    /// more dwords.
+    pub rest: Vec<u32>,
 }
 
 impl IStatChunk {
     pub fn parse(decoder: &mut decoder::Decoder) -> Result<IStatChunk, State> {
-        Ok(IStatChunk {
-            instruction_count: decoder.read_u32(),
-            temp_register_count: decoder.read_u32(),
-            def_count: decoder.read_u32(),
-            dcl_count: decoder.read_u32(),
-            float_instruction_count: decoder.read_u32(),
-            int_instruction_count: decoder.read_u32(),
-            uint_instruction_count: decoder.read_u32(),
-            static_flow_control_count: decoder.read_u32(),
-            dynamic_flow_control_count: decoder.read_u32(),
-            macro_instruction_count: decoder.read_u32(),
-            temp_array_count: decoder.read_u32(),
-            array_instruction_count: decoder.read_u32(),
-            cut_instruction_count: decoder.read_u32(),
-            emit_instruction_count: decoder.read_u32(),
-            texture_normal_instructions: decoder.read_u32(),
-            texture_load_instructions: decoder.read_u32(),
-            texture_comp_instructions: decoder.read_u32(),
-            texture_bias_instructions: decoder.read_u32(),
-            texture_gradient_instructions: decoder.read_u32(),
-        })
+        let mut stat = IStatChunk {
+            instruction_count: decoder.read_u32()?,
+            temp_register_count: decoder.read_u32()?,
+            def_count: decoder.read_u32()?,
+            dcl_count: decoder.read_u32()?,
+            float_instruction_count: decoder.read_u32()?,
+            int_instruction_count: decoder.read_u32()?,
+            uint_instruction_count: decoder.read_u32()?,
+            static_flow_control_count: decoder.read_u32()?,
+            dynamic_flow_control_count: decoder.read_u32()?,
+            macro_instruction_count: decoder.read_u32()?,
+            temp_array_count: decoder.read_u32()?,
+            array_instruction_count: decoder.read_u32()?,
+            cut_instruction_count: decoder.read_u32()?,
+            emit_instruction_count: decoder.read_u32()?,
+            texture_normal_instructions: decoder.read_u32()?,
+            texture_load_instructions: decoder.read_u32()?,
+            texture_comp_instructions: decoder.read_u32()?,
+            texture_bias_instructions: decoder.read_u32()?,
+            texture_gradient_instructions: decoder.read_u32()?,
+            rest: Vec::new(),
+        };
+
+        while !decoder.eof() {
+            stat.rest.push(decoder.read_u32()?);
+        }
+
+        Ok(stat)
     }
-}
\ No newline at end of file
+
+    /// All counts in the order they are stored in the chunk.
+    pub fn counts(&self) -> Vec<u32> {
+        let mut counts = vec![
+            self.instruction_count,
+            self.temp_register_count,
+            self.def_count,
+            self.dcl_count,
+            self.float_instruction_count,
+            self.int_instruction_count,
+            self.uint_instruction_count,
+            self.static_flow_control_count,
+            self.dynamic_flow_control_count,
+            self.macro_instruction_count,
+            self.temp_array_count,
+            self.array_instruction_count,
+            self.cut_instruction_count,
+            self.emit_instruction_count,
+            self.texture_normal_instructions,
+            self.texture_load_instructions,
+            self.texture_comp_instructions,
+            self.texture_bias_instructions,
+            self.texture_gradient_instructions,
+        ];
+        counts.extend_from_slice(&self.rest);
+        counts
+    }
+}
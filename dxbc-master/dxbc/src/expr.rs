@@ -0,0 +1,773 @@
+use cfg::{self, Function as CfgFunction};
+use dr::shex::OperandType;
+use dr::builder::Modifier;
+use d3d11tokenizedprogramformat::*;
+use ir::{Components, Index, Operand, Program, Register};
+use ssa::{self, FunctionSsa, Ssa, Temp, Use};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const ZERO: u32 = 0;
+const ONE: u32 = 0x3f80_0000;
+
+/// Expression computing a vector of one to four lanes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// SSA value of a temp component that wasn't folded.
+    Value(usize),
+    /// All components of a register that isn't a temp, the expressions of
+    /// its relative indices in index order. Vector registers are given an
+    /// `xyzw` swizzle, the lanes read are picked by an enclosing `Swizzle`.
+    Register(Register, Vec<Expr>),
+    /// One value per lane, a single value is a scalar.
+    Imm32(Vec<u32>),
+    Imm64(Vec<u64>),
+    /// Lanes picked from a vector, lanes may repeat.
+    Swizzle(Box<Expr>, Vec<u8>),
+    /// Lanes of the parts one after the other.
+    Vector(Vec<Expr>),
+    Neg(Box<Expr>),
+    Abs(Box<Expr>),
+    Op(Op, Vec<Expr>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// Result of an instruction for one of its destinations given by operand
+    /// index, the arguments are its source operands.
+    Instruction {
+        opcode: u32,
+        instruction: usize,
+        destination: usize,
+    },
+    /// `clamp(x, min, max)`, `_sat` instructions clamp to 0.0 and 1.0.
+    Clamp,
+    Normalize,
+    Length,
+    /// `mul(vector, matrix)`, the vector is the first argument and the rows
+    /// follow.
+    MatrixMultiply,
+}
+
+impl Expr {
+    /// Number of lanes of the expression.
+    pub fn width(&self) -> usize {
+        match *self {
+            Expr::Value(_) => 1,
+            Expr::Register(ref register, _) => match register.components {
+                Components::Swizzle(_) => 4,
+                _ => 1,
+            },
+            Expr::Imm32(ref values) => values.len(),
+            Expr::Imm64(ref values) => values.len(),
+            Expr::Swizzle(_, ref lanes) => lanes.len(),
+            Expr::Vector(ref parts) => parts.iter().map(Expr::width).sum(),
+            Expr::Neg(ref expr) | Expr::Abs(ref expr) => expr.width(),
+            Expr::Op(op, ref args) => match op {
+                Op::Instruction { opcode, .. } => match shape(opcode) {
+                    Shape::Componentwise => args.iter().map(Expr::width).max().unwrap_or(1),
+                    Shape::Scalar => 1,
+                    Shape::Full => 4,
+                },
+                Op::Clamp | Op::Normalize => args[0].width(),
+                Op::Length => 1,
+                Op::MatrixMultiply => args[1].width(),
+            },
+        }
+    }
+
+    /// Lanes of the expression, dropping the swizzle when it picks every
+    /// lane in order.
+    pub fn swizzle(self, lanes: Vec<u8>) -> Expr {
+        if lanes.len() == self.width() && lanes.iter().enumerate().all(|(i, &lane)| i == lane as usize) {
+            return self;
+        }
+
+        match self {
+            Expr::Swizzle(expr, inner) => Expr::Swizzle(expr, lanes.iter().map(|&lane| inner[lane as usize]).collect()),
+            expr => Expr::Swizzle(Box::new(expr), lanes),
+        }
+    }
+
+    /// Vector of the parts, merging lanes picked from the same expression.
+    pub fn vector(parts: Vec<Expr>) -> Expr {
+        let mut merged: Vec<Expr> = Vec::new();
+
+        for part in parts {
+            // scalars merge into broadcasts
+            let part = match part {
+                Expr::Swizzle(..) => part,
+                part if part.width() == 1 => Expr::Swizzle(Box::new(part), vec![0]),
+                part => part,
+            };
+
+            if let Some(&mut Expr::Swizzle(ref expr, ref mut lanes)) = merged.last_mut() {
+                if let Expr::Swizzle(ref next, ref more) = part {
+                    if expr == next {
+                        lanes.extend(more.iter().cloned());
+                        continue;
+                    }
+                }
+            }
+            merged.push(part);
+        }
+
+        let mut merged: Vec<Expr> = merged
+            .into_iter()
+            .map(|part| match part {
+                Expr::Swizzle(expr, lanes) => expr.swizzle(lanes),
+                part => part,
+            })
+            .collect();
+
+        if merged.len() == 1 {
+            merged.pop().unwrap()
+        } else {
+            Expr::Vector(merged)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    /// Temp values written by a destination of the instruction, one for each
+    /// lane of the expression.
+    Assign {
+        instruction: usize,
+        values: Vec<usize>,
+        expr: Expr,
+    },
+    /// Write to a register that isn't a temp, the target is the register
+    /// with the written lanes.
+    Store {
+        instruction: usize,
+        target: Expr,
+        expr: Expr,
+    },
+    /// Instruction without destinations, with all its operands.
+    Effect {
+        instruction: usize,
+        operands: Vec<Expr>,
+    },
+}
+
+/// Statements of each block of a function, in the order of `cfg::Function`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub blocks: Vec<Vec<Statement>>,
+    /// Instructions folded into a later one, with the instruction reading
+    /// their results and the expression of the components they write.
+    pub folded: BTreeMap<usize, (usize, Expr)>,
+}
+
+/// Expression trees of a program. Temp values read once by the same operand
+/// of a later instruction in the same block are folded into it when their
+/// instruction has no side effects and the temps it reads keep their values
+/// up to where the expression ends up, idioms like `normalize` or matrix
+/// multiplies are recognized in what's left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expressions {
+    pub ssa: Ssa,
+    /// One for each function of the graph.
+    pub functions: Vec<Function>,
+}
+
+impl Expressions {
+    pub fn build(program: &Program) -> Result<Expressions, cfg::Error> {
+        let ssa = Ssa::build(program)?;
+
+        let functions = ssa
+            .cfg
+            .functions
+            .iter()
+            .zip(&ssa.functions)
+            .map(|(function, values)| FunctionBuilder::new(program, function, values).finish())
+            .collect();
+
+        Ok(Expressions { ssa, functions })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Shape {
+    /// Lane `i` of the result only depends on lane `i` of the sources.
+    Componentwise,
+    /// Scalar result replicated to the written components.
+    Scalar,
+    /// Four lanes, the written components pick from them.
+    Full,
+}
+
+fn shape(opcode: u32) -> Shape {
+    match opcode {
+        D3D10_SB_OPCODE_DP2 | D3D10_SB_OPCODE_DP3 | D3D10_SB_OPCODE_DP4 => Shape::Scalar,
+        _ if ssa::is_componentwise(opcode) => Shape::Componentwise,
+        _ => Shape::Full,
+    }
+}
+
+// Registers that can change while a value is alive.
+fn is_mutable(file: OperandType) -> bool {
+    matches!(file, OperandType::Output | OperandType::IndexableTemp | OperandType::OutputDepth |
+        OperandType::OutputCoverageMask | OperandType::UnorderedAccessView | OperandType::ThreadGroupSharedMemory |
+        OperandType::OutputDepthGreaterEqual | OperandType::OutputDepthLessEqual | OperandType::CycleCounter)
+}
+
+fn is_pure(program: &Program, instruction: usize) -> bool {
+    let instruction = &program.instructions[instruction];
+
+    match instruction.opcode {
+        D3D11_SB_OPCODE_INTERFACE_CALL => return false,
+        opcode if ssa::destinations(opcode) == 0 => return false,
+        _ => {}
+    }
+
+    fn reads_mutable(register: &Register) -> bool {
+        is_mutable(register.file) || register.index.iter().filter_map(Index::relative).any(reads_mutable)
+    }
+
+    !instruction.operands.iter().filter_map(Operand::register).any(reads_mutable)
+}
+
+// Lanes of the sources an instruction reads.
+fn source_lanes(program: &Program, instruction: usize) -> Vec<u8> {
+    let instruction = &program.instructions[instruction];
+
+    match instruction.opcode {
+        D3D10_SB_OPCODE_DP2 => vec![0, 1],
+        D3D10_SB_OPCODE_DP3 => vec![0, 1, 2],
+        opcode if shape(opcode) == Shape::Componentwise => {
+            let count = ssa::destinations(opcode).min(instruction.operands.len());
+            let mask = instruction.operands[..count]
+                .iter()
+                .filter_map(Operand::register)
+                .fold(0, |mask, register| mask | register.components.mask());
+            let lanes: Vec<u8> = (0..4).filter(|&c| mask & (1 << c) != 0).collect();
+            if lanes.is_empty() {
+                vec![0, 1, 2, 3]
+            } else {
+                lanes
+            }
+        }
+        _ => vec![0, 1, 2, 3],
+    }
+}
+
+fn float(value: u32) -> Expr {
+    Expr::Imm32(vec![value])
+}
+
+fn literal(expr: &Expr) -> Option<&[u32]> {
+    match *expr {
+        Expr::Imm32(ref values) => Some(values),
+        _ => None,
+    }
+}
+
+// Literals `lo` and `hi` with `lo <= hi` in every lane.
+fn ordered(lo: &Expr, hi: &Expr) -> bool {
+    match (literal(lo), literal(hi)) {
+        (Some(lo), Some(hi)) => (0..lo.len().max(hi.len())).all(|lane| {
+            let value = |values: &[u32]| f32::from_bits(values[lane.min(values.len() - 1)]);
+            value(lo) <= value(hi)
+        }),
+        _ => false,
+    }
+}
+
+// `min(max(x, lo), hi)`, and `max(min(x, hi), lo)` when the bounds are
+// ordered literals, the two differ for `lo > hi`.
+fn clamp(opcode: u32, args: &[Expr]) -> Option<Expr> {
+    let (outer, inner) = match opcode {
+        D3D10_SB_OPCODE_MIN => (D3D10_SB_OPCODE_MIN, D3D10_SB_OPCODE_MAX),
+        _ => (D3D10_SB_OPCODE_MAX, D3D10_SB_OPCODE_MIN),
+    };
+
+    for &(nested, bound) in &[(0, 1), (1, 0)] {
+        let operands = match args[nested] {
+            Expr::Op(Op::Instruction { opcode, .. }, ref operands) if opcode == inner => operands,
+            _ => continue,
+        };
+
+        let (x, limit) = if literal(&operands[0]).is_some() && literal(&operands[1]).is_none() {
+            (&operands[1], &operands[0])
+        } else {
+            (&operands[0], &operands[1])
+        };
+        let (lo, hi) = if outer == D3D10_SB_OPCODE_MIN { (limit, &args[bound]) } else { (&args[bound], limit) };
+
+        if outer == D3D10_SB_OPCODE_MIN || ordered(lo, hi) {
+            return Some(Expr::Op(Op::Clamp, vec![x.clone(), lo.clone(), hi.clone()]));
+        }
+    }
+
+    None
+}
+
+fn opcode(expr: &Expr) -> Option<u32> {
+    match *expr {
+        Expr::Op(Op::Instruction { opcode, .. }, _) => Some(opcode),
+        _ => None,
+    }
+}
+
+// Scalar the expression replicates to all its lanes.
+fn broadcast(expr: &Expr) -> Option<Expr> {
+    match *expr {
+        Expr::Swizzle(ref inner, ref lanes) if !lanes.is_empty() && lanes.iter().all(|&lane| lane == lanes[0]) => {
+            Some((**inner).clone().swizzle(vec![lanes[0]]))
+        }
+        ref expr if expr.width() == 1 => Some(expr.clone()),
+        _ => None,
+    }
+}
+
+// `dpN(a, a)`
+fn squared_length(expr: &Expr) -> Option<&Expr> {
+    match *expr {
+        Expr::Op(Op::Instruction { opcode, .. }, ref args) => match opcode {
+            D3D10_SB_OPCODE_DP2 | D3D10_SB_OPCODE_DP3 | D3D10_SB_OPCODE_DP4 if args[0] == args[1] => Some(&args[0]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Buffer, row and lanes of a constant buffer row.
+type Row = (u32, u32, usize);
+
+// Constant buffer row read by the expression.
+fn row(expr: &Expr) -> Option<Row> {
+    let (register, width) = match *expr {
+        Expr::Register(ref register, _) => (register, 4),
+        Expr::Swizzle(ref inner, ref lanes) => match **inner {
+            Expr::Register(ref register, _) if lanes.iter().enumerate().all(|(i, &lane)| i == lane as usize) => {
+                (register, lanes.len())
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    match (register.file, &register.index[..], register.components) {
+        (OperandType::ConstantBuffer, &[Index::U32(buffer), Index::U32(row)], Components::Swizzle(_)) => {
+            Some((buffer, row, width))
+        }
+        _ => None,
+    }
+}
+
+// Adds the `row * scalar` terms of a sum of products to `found`, rows added
+// on their own have no scalar.
+fn terms(expr: &Expr, found: &mut Vec<(Expr, Option<Expr>)>) -> bool {
+    fn product(a: &Expr, b: &Expr, found: &mut Vec<(Expr, Option<Expr>)>) -> bool {
+        for &(candidate, scalar) in &[(a, b), (b, a)] {
+            if row(candidate).is_some() {
+                if let Some(scalar) = broadcast(scalar) {
+                    found.push((candidate.clone(), Some(scalar)));
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    match *expr {
+        Expr::Op(Op::Instruction { opcode: D3D10_SB_OPCODE_ADD, .. }, ref args) => {
+            terms(&args[0], found) && terms(&args[1], found)
+        }
+        Expr::Op(Op::Instruction { opcode: D3D10_SB_OPCODE_MAD, .. }, ref args) => {
+            product(&args[0], &args[1], found) && terms(&args[2], found)
+        }
+        Expr::Op(Op::Instruction { opcode: D3D10_SB_OPCODE_MUL, .. }, ref args) => product(&args[0], &args[1], found),
+        ref expr if row(expr).is_some() => {
+            found.push((expr.clone(), None));
+            true
+        }
+        _ => false,
+    }
+}
+
+// `mad` chains summing consecutive rows of a constant buffer scaled by
+// scalars.
+fn matrix_multiply(expr: &Expr) -> Option<Expr> {
+    match opcode(expr) {
+        Some(D3D10_SB_OPCODE_ADD) | Some(D3D10_SB_OPCODE_MAD) => {}
+        _ => return None,
+    }
+
+    let mut found = Vec::new();
+    if !terms(expr, &mut found) || found.len() < 3 {
+        return None;
+    }
+
+    let mut rows: Vec<(Row, Expr, Option<Expr>)> = found
+        .into_iter()
+        .map(|(expr, scalar)| (row(&expr).unwrap(), expr, scalar))
+        .collect();
+    rows.sort_by_key(|&((_, row, _), _, _)| row);
+
+    let ((buffer, first, width), _, _) = rows[0];
+    let consecutive = rows
+        .iter()
+        .enumerate()
+        .all(|(i, &((b, row, w), _, _))| b == buffer && w == width && row == first + i as u32);
+    if !consecutive || rows.iter().filter(|(_, _, scalar)| scalar.is_none()).count() > 1 {
+        return None;
+    }
+
+    let vector = Expr::vector(rows.iter().map(|(_, _, scalar)| scalar.clone().unwrap_or_else(|| float(ONE))).collect());
+    let mut args = vec![rewrite(vector)];
+    args.extend(rows.into_iter().map(|(_, row, _)| row));
+    Some(Expr::Op(Op::MatrixMultiply, args))
+}
+
+/// Replaces instruction sequences by the idioms they implement.
+fn rewrite(expr: Expr) -> Expr {
+    if let Some(matrix) = matrix_multiply(&expr) {
+        return matrix;
+    }
+
+    let expr = match expr {
+        Expr::Register(register, relative) => Expr::Register(register, relative.into_iter().map(rewrite).collect()),
+        Expr::Swizzle(expr, lanes) => rewrite(*expr).swizzle(lanes),
+        Expr::Vector(parts) => Expr::vector(parts.into_iter().map(rewrite).collect()),
+        Expr::Neg(expr) => Expr::Neg(Box::new(rewrite(*expr))),
+        Expr::Abs(expr) => Expr::Abs(Box::new(rewrite(*expr))),
+        Expr::Op(op, args) => Expr::Op(op, args.into_iter().map(rewrite).collect()),
+        expr => expr,
+    };
+
+    let replacement = match expr {
+        Expr::Op(Op::Instruction { opcode: D3D10_SB_OPCODE_SQRT, .. }, ref args) => {
+            squared_length(&args[0]).map(|vector| Expr::Op(Op::Length, vec![vector.clone()]))
+        }
+        Expr::Op(Op::Instruction { opcode: D3D10_SB_OPCODE_MUL, .. }, ref args) => {
+            let normalize = |scale: &Expr, vector: &Expr| match broadcast(scale) {
+                Some(Expr::Op(Op::Instruction { opcode: D3D10_SB_OPCODE_RSQ, .. }, ref args)) => {
+                    squared_length(&args[0]).and_then(|squared| if squared == vector { Some(vector.clone()) } else { None })
+                }
+                _ => None,
+            };
+            normalize(&args[0], &args[1])
+                .or_else(|| normalize(&args[1], &args[0]))
+                .map(|vector| Expr::Op(Op::Normalize, vec![vector]))
+        }
+        Expr::Op(Op::Instruction { opcode: D3D10_SB_OPCODE_MIN, .. }, ref args) => clamp(D3D10_SB_OPCODE_MIN, args),
+        Expr::Op(Op::Instruction { opcode: D3D10_SB_OPCODE_MAX, .. }, ref args) => clamp(D3D10_SB_OPCODE_MAX, args),
+        _ => None,
+    };
+
+    replacement.unwrap_or(expr)
+}
+
+// Expression of an instruction destination folded into its reader.
+struct Folded {
+    expr: Expr,
+    shape: Shape,
+    lanes: Vec<u8>,
+}
+
+impl Folded {
+    // Expression of one written component.
+    fn component(&self, component: u8) -> Expr {
+        let lane = match self.shape {
+            Shape::Componentwise => self.lanes.iter().position(|&lane| lane == component).unwrap_or(0) as u8,
+            Shape::Scalar => 0,
+            Shape::Full => component,
+        };
+        self.expr.clone().swizzle(vec![lane])
+    }
+}
+
+struct FunctionBuilder<'a> {
+    program: &'a Program,
+    function: &'a CfgFunction,
+    ssa: &'a FunctionSsa,
+    // instructions folded into their reader
+    foldable: HashMap<usize, usize>,
+    folded: HashMap<usize, Folded>,
+    // expressions of the folded instructions on their own
+    exprs: HashMap<usize, Expr>,
+}
+
+impl<'a> FunctionBuilder<'a> {
+    fn new(program: &'a Program, function: &'a CfgFunction, ssa: &'a FunctionSsa) -> Self {
+        let mut builder = FunctionBuilder {
+            program,
+            function,
+            ssa,
+            foldable: HashMap::new(),
+            folded: HashMap::new(),
+            exprs: HashMap::new(),
+        };
+
+        for block in &function.blocks {
+            for i in block.start..block.end {
+                if let Some(reader) = builder.reader(i) {
+                    builder.foldable.insert(i, reader);
+                }
+            }
+        }
+
+        // the temps a folded instruction reads are read again where the
+        // expression ends up, after the readers folded in turn, unfolding
+        // one brings its writes back
+        loop {
+            let overwritten: Vec<usize> = builder
+                .foldable
+                .keys()
+                .cloned()
+                .filter(|&i| {
+                    let mut end = builder.foldable[&i];
+                    while let Some(&reader) = builder.foldable.get(&end) {
+                        end = reader;
+                    }
+                    builder.overwritten(i, end)
+                })
+                .collect();
+
+            if overwritten.is_empty() {
+                break;
+            }
+            for i in overwritten {
+                builder.foldable.remove(&i);
+            }
+        }
+
+        builder
+    }
+
+    // Whether a temp read by `i` is written before `end` by an instruction
+    // that isn't folded.
+    fn overwritten(&self, i: usize, end: usize) -> bool {
+        let read: HashSet<Temp> = match self.ssa.reads.get(&i) {
+            Some(accesses) => accesses.iter().map(|access| access.temp).collect(),
+            None => return false,
+        };
+
+        (i + 1..end).filter(|j| !self.foldable.contains_key(j)).any(|j| match self.ssa.writes.get(&j) {
+            Some(accesses) => accesses.iter().any(|access| read.contains(&access.temp)),
+            None => false,
+        })
+    }
+
+    // The instruction the results of `i` can be folded into.
+    fn reader(&self, i: usize) -> Option<usize> {
+        if !is_pure(self.program, i) {
+            return None;
+        }
+
+        let instruction = &self.program.instructions[i];
+        let count = ssa::destinations(instruction.opcode).min(instruction.operands.len());
+        let destinations: Vec<&Register> = instruction.operands[..count]
+            .iter()
+            .filter_map(Operand::register)
+            .filter(|register| register.file != OperandType::Null)
+            .collect();
+        if destinations.len() != 1 || destinations[0].file != OperandType::Temp {
+            return None;
+        }
+
+        let values: Vec<usize> = self.ssa.writes.get(&i)?.iter().map(|access| access.value).collect();
+        let mut reader = None;
+        for &value in &values {
+            match self.ssa.values[value].uses[..] {
+                [Use::Instruction(use_)] if reader.is_none() || reader == Some(use_) => reader = Some(use_),
+                _ => return None,
+            }
+        }
+        let reader = reader?;
+
+        if reader <= i || self.function.block_of(reader) != self.function.block_of(i) {
+            return None;
+        }
+
+        // all read by the same operand
+        let mut operands = self.ssa.reads[&reader]
+            .iter()
+            .filter(|access| values.contains(&access.value))
+            .map(|access| access.operand);
+        let operand = operands.next()?;
+        if operand.is_none() || operands.any(|other| other != operand) {
+            return None;
+        }
+
+        Some(reader)
+    }
+
+    fn finish(mut self) -> Function {
+        let mut blocks = Vec::with_capacity(self.function.blocks.len());
+
+        for block in &self.function.blocks {
+            let mut statements = Vec::new();
+            for i in block.start..block.end {
+                self.instruction(i, &mut statements);
+            }
+            blocks.push(statements);
+        }
+
+        let exprs = &mut self.exprs;
+        let folded = self.foldable.iter().filter_map(|(&i, &reader)| Some((i, (reader, exprs.remove(&i)?)))).collect();
+        Function { blocks, folded }
+    }
+
+    fn instruction(&mut self, i: usize, statements: &mut Vec<Statement>) {
+        let instruction = &self.program.instructions[i];
+        let count = ssa::destinations(instruction.opcode).min(instruction.operands.len());
+
+        if count == 0 {
+            let lanes = source_lanes(self.program, i);
+            let operands = (0..instruction.operands.len())
+                .map(|operand| rewrite(self.operand(i, operand, &lanes)))
+                .collect();
+            statements.push(Statement::Effect { instruction: i, operands });
+            return;
+        }
+
+        for destination in 0..count {
+            // nothing is written through a null register or an empty mask
+            let register = match instruction.operands[destination].register() {
+                Some(register) if register.file != OperandType::Null && register.components.mask() != 0 => register,
+                _ => continue,
+            };
+
+            let shape = shape(instruction.opcode);
+            let lanes = source_lanes(self.program, i);
+            let expr = self.result(i, destination, &lanes);
+
+            let folded = Folded { expr, shape, lanes };
+            let components: Vec<u8> = (0..4).filter(|&c| register.components.mask() & (1 << c) != 0).collect();
+            let expr = rewrite(Expr::vector(components.iter().map(|&c| folded.component(c)).collect()));
+
+            if self.foldable.contains_key(&i) {
+                self.exprs.insert(i, expr);
+                self.folded.insert(i, folded);
+                continue;
+            }
+
+            if register.file == OperandType::Temp {
+                let values = self.ssa.writes.get(&i)
+                    .into_iter()
+                    .flatten()
+                    .filter(|access| access.operand == Some(destination))
+                    .map(|access| access.value)
+                    .collect();
+                statements.push(Statement::Assign { instruction: i, values, expr });
+            } else {
+                let target = self.register(i, destination, register, Some(&components));
+                statements.push(Statement::Store { instruction: i, target, expr });
+            }
+        }
+    }
+
+    // Expression an instruction computes for a destination.
+    fn result(&mut self, i: usize, destination: usize, lanes: &[u8]) -> Expr {
+        let instruction = &self.program.instructions[i];
+        let count = ssa::destinations(instruction.opcode).min(instruction.operands.len());
+
+        let mut args: Vec<Expr> = (count..instruction.operands.len())
+            .map(|operand| self.operand(i, operand, lanes))
+            .collect();
+
+        let expr = if instruction.opcode == D3D10_SB_OPCODE_MOV && args.len() == 1 {
+            args.pop().unwrap()
+        } else {
+            Expr::Op(Op::Instruction { opcode: instruction.opcode, instruction: i, destination }, args)
+        };
+
+        if instruction.saturate {
+            Expr::Op(Op::Clamp, vec![expr, float(ZERO), float(ONE)])
+        } else {
+            expr
+        }
+    }
+
+    // Expression of a source operand for the lanes the instruction computes.
+    fn operand(&mut self, i: usize, operand: usize, lanes: &[u8]) -> Expr {
+        let componentwise = shape(self.program.instructions[i].opcode) == Shape::Componentwise;
+
+        match self.program.instructions[i].operands[operand] {
+            Operand::Imm32(ref values) => {
+                if values.len() == 4 {
+                    Expr::Imm32(lanes.iter().map(|&lane| values[lane as usize]).collect())
+                } else if componentwise {
+                    Expr::Imm32(vec![values[0]; lanes.len()])
+                } else {
+                    Expr::Imm32(values.clone())
+                }
+            }
+            Operand::Imm64(ref values) => Expr::Imm64(values.clone()),
+            Operand::Register(ref register) => {
+                let components = match register.components {
+                    Components::Swizzle(swizzle) => Some(lanes.iter().map(|&lane| swizzle[lane as usize]).collect()),
+                    Components::Select(component) if componentwise => Some(vec![component; lanes.len()]),
+                    Components::Select(component) => Some(vec![component]),
+                    Components::Mask(mask) => Some((0..4).filter(|&c| mask & (1 << c) != 0).collect()),
+                    Components::None | Components::Scalar => None,
+                };
+
+                let expr = self.register(i, operand, register, components.as_ref());
+                match register.modifier {
+                    Modifier::None => expr,
+                    Modifier::Neg => Expr::Neg(Box::new(expr)),
+                    Modifier::Abs => Expr::Abs(Box::new(expr)),
+                    Modifier::AbsNeg => Expr::Neg(Box::new(Expr::Abs(Box::new(expr)))),
+                }
+            }
+        }
+    }
+
+    // Register read by an operand, without its modifier.
+    fn register(&mut self, i: usize, operand: usize, register: &Register, components: Option<&Vec<u8>>) -> Expr {
+        if register.file == OperandType::Temp {
+            if let (Some(components), Some(&Index::U32(index))) = (components, register.index.first()) {
+                let parts = components
+                    .iter()
+                    .map(|&component| {
+                        let temp = Temp { register: index, component };
+                        match self.ssa.read(i, operand, temp) {
+                            Some(value) => self.value(value, component),
+                            None => Expr::Register(register.clone(), Vec::new()),
+                        }
+                    })
+                    .collect();
+                return Expr::vector(parts);
+            }
+        }
+
+        let relative = register
+            .index
+            .iter()
+            .filter_map(Index::relative)
+            .map(|relative| {
+                let components = match relative.components {
+                    Components::Select(component) => Some(vec![component]),
+                    Components::Swizzle(swizzle) => Some(vec![swizzle[0]]),
+                    _ => None,
+                };
+                self.register(i, operand, relative, components.as_ref())
+            })
+            .collect();
+
+        let mut whole = register.clone();
+        whole.modifier = Modifier::None;
+        match whole.components {
+            Components::Swizzle(_) | Components::Mask(_) | Components::Select(_) => {
+                whole.components = Components::Swizzle([0, 1, 2, 3]);
+                Expr::Register(whole, relative).swizzle(components.cloned().unwrap_or_else(|| vec![0, 1, 2, 3]))
+            }
+            Components::None | Components::Scalar => Expr::Register(whole, relative),
+        }
+    }
+
+    // Expression of an SSA value read by an instruction.
+    fn value(&mut self, value: usize, component: u8) -> Expr {
+        if let ssa::Def::Instruction(writer) = self.ssa.values[value].def {
+            if let Some(folded) = self.folded.get(&writer) {
+                return folded.component(component);
+            }
+        }
+        Expr::Value(value)
+    }
+}
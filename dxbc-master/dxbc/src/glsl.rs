@@ -0,0 +1,1766 @@
+use binary::*;
+use backend::{self, Chunks, Constant, ConstantArray, Element, Error, Language, Layout, Resource, ResourceKind, Shader,
+              Writer, MAX_REGISTERS};
+use dr;
+use dr::rdef::ShaderVariableClass;
+use dr::shex::{OperandType, ResourceDimension, ShexHeader, SparseInstruction};
+use dr::builder::GlobalFlags;
+use d3d11tokenizedprogramformat::*;
+use disasm::COMPONENTS;
+use ir::{Components, Declaration, Index, Operand, Program, Register};
+use types::Type;
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Binding of the storage buffers for raw and structured shader resource
+/// views, after the slots of the unordered access views.
+pub const RESOURCE_BUFFER_BINDING: u32 = 64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GlslVersion {
+    V330,
+    V430,
+    Es300,
+}
+
+impl GlslVersion {
+    fn directive(&self) -> &'static str {
+        match *self {
+            GlslVersion::V330 => "#version 330 core",
+            GlslVersion::V430 => "#version 430 core",
+            GlslVersion::Es300 => "#version 300 es",
+        }
+    }
+
+    fn is_es(&self) -> bool {
+        *self == GlslVersion::Es300
+    }
+
+    fn has_binding_layout(&self) -> bool {
+        *self == GlslVersion::V430
+    }
+
+    // End of the errors for what the version can't express.
+    fn reason(&self) -> &'static str {
+        match *self {
+            GlslVersion::V330 => " in GLSL 330",
+            GlslVersion::V430 => " in GLSL 430",
+            GlslVersion::Es300 => " in GLSL ES 300",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GlslOptions {
+    pub version: GlslVersion,
+}
+
+impl Default for GlslOptions {
+    fn default() -> Self {
+        GlslOptions {
+            version: GlslVersion::V330,
+        }
+    }
+}
+
+// Words GLSL reserves that are fine names in HLSL.
+const RESERVED: &[&str] = &[
+    "active", "asm", "attribute", "buffer", "cast", "centroid", "class", "coherent", "common", "enum", "extern",
+    "external", "filter", "fixed", "flat", "goto", "half", "highp", "input", "interface", "invariant", "layout",
+    "long", "lowp", "main", "mediump", "namespace", "noinline", "noperspective", "output", "partition", "patch",
+    "precise", "precision", "public", "readonly", "resource", "restrict", "sample", "shared", "short", "sizeof",
+    "smooth", "subroutine", "superp", "template", "texture", "this", "typedef", "union", "unsigned", "using",
+    "varying", "volatile", "writeonly",
+];
+
+fn name(name: &str) -> String {
+    let name = backend::identifier(name);
+    if name.starts_with("gl_") || RESERVED.contains(&name.as_str()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+fn scalar_name(ty: Type) -> &'static str {
+    match ty {
+        Type::Float => "float",
+        Type::Int => "int",
+        Type::Uint => "uint",
+        Type::Bool => "bool",
+    }
+}
+
+fn vector(ty: Type, width: usize) -> String {
+    let prefix = match ty {
+        Type::Float => "",
+        Type::Int => "i",
+        Type::Uint => "u",
+        Type::Bool => "b",
+    };
+
+    if width == 1 {
+        scalar_name(ty).to_owned()
+    } else {
+        format!("{}vec{}", prefix, width)
+    }
+}
+
+fn convert(expr: String, from: Type, to: Type, width: usize) -> String {
+    match (from, backend::storage(to)) {
+        (from, to) if from == to => expr,
+        (Type::Bool, to) => {
+            let mask = if width == 1 {
+                format!("({} ? 0xffffffffu : 0u)", expr)
+            } else {
+                format!("({}({}) * 0xffffffffu)", vector(Type::Uint, width), expr)
+            };
+            convert(mask, Type::Uint, to, width)
+        }
+        (Type::Float, Type::Int) => format!("floatBitsToInt({})", expr),
+        (Type::Float, _) => format!("floatBitsToUint({})", expr),
+        (Type::Int, Type::Float) => format!("intBitsToFloat({})", expr),
+        (_, Type::Float) => format!("uintBitsToFloat({})", expr),
+        (_, to) => format!("{}({})", vector(to, width), expr),
+    }
+}
+
+fn format_literal(bits: u32, ty: Type) -> String {
+    match backend::storage(ty) {
+        Type::Float => {
+            let val = f32::from_bits(bits);
+
+            // denormals and non-finite values don't survive a trip through
+            // a decimal literal, keep their exact bit pattern instead
+            if val.is_normal() || val == 0.0 {
+                format!("{:?}", val)
+            } else {
+                format!("uintBitsToFloat(0x{:08x}u)", bits)
+            }
+        }
+        Type::Int if bits == 0x8000_0000 => "int(0x80000000u)".to_owned(),
+        Type::Int => format!("{}", bits as i32),
+        _ => format!("{}u", bits),
+    }
+}
+
+// Sampler type of a texture, if the version has one.
+fn sampler_type(dimension: ResourceDimension, ty: Type, shadow: bool, version: GlslVersion) -> Option<String> {
+    let es = version.is_es();
+    let suffix = match dimension {
+        ResourceDimension::Buffer if !es && !shadow => "Buffer",
+        ResourceDimension::Texture1D if !es => "1D",
+        ResourceDimension::Texture1DArray if !es => "1DArray",
+        ResourceDimension::Texture2D => "2D",
+        ResourceDimension::Texture2DArray => "2DArray",
+        ResourceDimension::Texture2DMS if !es && !shadow => "2DMS",
+        ResourceDimension::Texture2DMSArray if !es && !shadow => "2DMSArray",
+        ResourceDimension::Texture3D if !shadow => "3D",
+        ResourceDimension::TextureCube => "Cube",
+        ResourceDimension::TextureCubeArray if version == GlslVersion::V430 => "CubeArray",
+        _ => return None,
+    };
+    let prefix = match ty {
+        Type::Int if !shadow => "i",
+        Type::Uint if !shadow => "u",
+        _ => "",
+    };
+
+    Some(format!("{}sampler{}{}", prefix, suffix, if shadow { "Shadow" } else { "" }))
+}
+
+fn image_type(dimension: ResourceDimension, ty: Type) -> Option<String> {
+    let suffix = match dimension {
+        ResourceDimension::Buffer => "Buffer",
+        ResourceDimension::Texture1D => "1D",
+        ResourceDimension::Texture1DArray => "1DArray",
+        ResourceDimension::Texture2D => "2D",
+        ResourceDimension::Texture2DArray => "2DArray",
+        ResourceDimension::Texture3D => "3D",
+        _ => return None,
+    };
+    let prefix = match ty {
+        Type::Int => "i",
+        Type::Uint => "u",
+        _ => "",
+    };
+
+    Some(format!("{}image{}", prefix, suffix))
+}
+
+// DXBC doesn't know the format of a view, images take the widest one of
+// their channel type, or the single channel one atomics need.
+fn image_format(ty: Type, atomic: bool) -> &'static str {
+    match (ty, atomic) {
+        (Type::Int, true) => "r32i",
+        (Type::Uint, true) => "r32ui",
+        (Type::Int, false) => "rgba32i",
+        (Type::Uint, false) => "rgba32ui",
+        _ => "rgba32f",
+    }
+}
+
+// Qualifier of a pixel shader input, or the feature the version is missing
+// for its interpolation mode.
+fn interpolation_qualifier(mode: u32, version: GlslVersion) -> Result<&'static str, &'static str> {
+    let es = version.is_es();
+    let sample = version == GlslVersion::V430;
+
+    match mode {
+        D3D10_SB_INTERPOLATION_CONSTANT => Ok("flat "),
+        D3D10_SB_INTERPOLATION_LINEAR_CENTROID => Ok("centroid "),
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE if !es => Ok("noperspective "),
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_CENTROID if !es => Ok("noperspective centroid "),
+        D3D10_SB_INTERPOLATION_LINEAR_SAMPLE if sample => Ok("sample "),
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_SAMPLE if sample => Ok("noperspective sample "),
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE | D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_CENTROID => {
+            Err("interpolation without perspective")
+        }
+        D3D10_SB_INTERPOLATION_LINEAR_SAMPLE | D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_SAMPLE => Err("interpolation at samples"),
+        _ => Ok(""),
+    }
+}
+
+impl Element {
+    // Variable GLSL passes a system value input in, with its type there and
+    // whether the version has it.
+    fn builtin_input(&self, program_type: u32, version: GlslVersion) -> Option<(&'static str, Type, bool)> {
+        let vertex = program_type == D3D10_SB_VERTEX_SHADER;
+        let pixel = program_type == D3D10_SB_PIXEL_SHADER;
+        let v430 = version == GlslVersion::V430;
+
+        Some(match self.semantic_type {
+            dr::SemanticName::VertexId if vertex => ("gl_VertexID", Type::Int, true),
+            dr::SemanticName::InstanceId if vertex => ("gl_InstanceID", Type::Int, true),
+            dr::SemanticName::Position if pixel => ("gl_FragCoord", Type::Float, true),
+            dr::SemanticName::IsFrontFace if pixel => ("gl_FrontFacing", Type::Bool, true),
+            dr::SemanticName::SampleIndex if pixel => ("gl_SampleID", Type::Int, v430),
+            dr::SemanticName::PrimitiveId if pixel => ("gl_PrimitiveID", Type::Int, !version.is_es()),
+            dr::SemanticName::RenderTargetArrayIndex if pixel => ("gl_Layer", Type::Int, v430),
+            dr::SemanticName::ViewportArrayIndex if pixel => ("gl_ViewportIndex", Type::Int, v430),
+            dr::SemanticName::ClipDistance if pixel => ("gl_ClipDistance", Type::Float, !version.is_es()),
+            _ => return None,
+        })
+    }
+
+    fn builtin_output(&self, program_type: u32) -> bool {
+        let pixel = program_type == D3D10_SB_PIXEL_SHADER;
+        match self.semantic_type {
+            _ if self.register == !0 => true,
+            dr::SemanticName::Position | dr::SemanticName::ClipDistance => !pixel,
+            _ => false,
+        }
+    }
+
+    fn input_name(&self, program_type: u32) -> String {
+        let prefix = if program_type == D3D10_SB_VERTEX_SHADER { "in" } else { "vs" };
+        format!("{}_{}", prefix, self.member())
+    }
+
+    fn output_name(&self, program_type: u32) -> String {
+        if program_type == D3D10_SB_PIXEL_SHADER {
+            format!("SV_Target{}", self.semantic_index)
+        } else {
+            format!("vs_{}", self.member())
+        }
+    }
+}
+
+// Padding members from byte `from` to `to`, whole registers as arrays.
+fn pad(text: &mut String, from: u32, to: u32, padding: &mut u32) {
+    let mut offset = from;
+    while offset < to {
+        if (offset & 15) == 0 && to - offset >= 16 {
+            let registers = (to - offset) / 16;
+            writeln!(text, "    uvec4 _pad{}[{}];", padding, registers).unwrap();
+            offset += registers * 16;
+        } else {
+            writeln!(text, "    uint _pad{};", padding).unwrap();
+            offset += 4;
+        }
+        *padding += 1;
+    }
+}
+
+// Uniform blocks use the std140 layout, which matches the HLSL packing for
+// aligned vectors. Gaps become padding members, vectors off their alignment
+// are split into scalars and variables spanning several registers are
+// declared as arrays of registers, any variable packed into their last
+// register is read from there.
+fn add_constant_buffer(shader: &mut Shader, buffer: &dr::ConstantBuffer, slot: u32) {
+    let buffer_name = name(buffer.name);
+    let mut text = format!("uniform {}_block\n{{\n", buffer_name);
+
+    let mut variables: Vec<&dr::ShaderVariable> = buffer.variables.iter().collect();
+    variables.sort_by_key(|variable| variable.start_offset);
+
+    let mut end = 0;
+    let mut padding = 0;
+    for variable in variables {
+        let ty = &variable.ty;
+        let offset = variable.start_offset;
+        let scalar = match ty.class {
+            ShaderVariableClass::Struct => Type::Float,
+            _ => match backend::variable_type(ty.ty) {
+                Some(scalar) => backend::storage(scalar),
+                None => continue,
+            },
+        };
+        // a buffer holds at most `MAX_REGISTERS` registers, anything past
+        // them is corrupt
+        if offset / 16 >= MAX_REGISTERS {
+            continue;
+        }
+        let variable_name = name(variable.name);
+
+        if offset < end {
+            if let Some(array) = shader.arrays.iter().rev().find(|array| array.buffer == slot) {
+                let element = offset / 16 - array.start;
+                writeln!(text, "    // {} {} is in {}[{}]", backend::type_name(ty), variable_name, array.name, element).unwrap();
+            }
+            continue;
+        }
+
+        pad(&mut text, end, offset, &mut padding);
+
+        let single = ty.count == 0 && (ty.class == ShaderVariableClass::Scalar || ty.class == ShaderVariableClass::Vector);
+        if single {
+            let width = ty.columns.clamp(1, 4) as usize;
+            let member = format!("{}.{}", buffer_name, variable_name);
+            let aligned = match width {
+                2 => offset % 8 == 0,
+                3 | 4 => offset % 16 == 0,
+                _ => true,
+            };
+
+            if aligned {
+                writeln!(text, "    {} {};", vector(scalar, width), variable_name).unwrap();
+                for c in 0..width as u32 {
+                    let lane = if width == 1 { None } else { Some(c as u8) };
+                    shader.constants.insert((slot, offset / 4 + c), Constant { base: member.clone(), lane, ty: scalar });
+                }
+            } else {
+                writeln!(text, "    // {} {}", backend::type_name(ty), variable_name).unwrap();
+                for c in 0..width as u32 {
+                    writeln!(text, "    {} {}_{};", scalar_name(scalar), variable_name, c).unwrap();
+                    let base = format!("{}_{}", member, c);
+                    shader.constants.insert((slot, offset / 4 + c), Constant { base, lane: None, ty: scalar });
+                }
+            }
+            end = offset + 4 * width as u32;
+        } else {
+            let start = offset / 16;
+            let registers = backend::type_size(ty).div_ceil(16).min(MAX_REGISTERS - start);
+            let array = if ty.count > 0 { format!("[{}]", ty.count) } else { String::new() };
+            writeln!(text, "    {} {}[{}]; // {} {}{}", vector(scalar, 4), variable_name, registers, backend::type_name(ty), variable_name, array)
+                .unwrap();
+
+            let array_name = format!("{}.{}", buffer_name, variable_name);
+            for dword in start * 4..(start + registers) * 4 {
+                let base = format!("{}[{}]", array_name, dword / 4 - start);
+                shader.constants.insert((slot, dword), Constant { base, lane: Some((dword % 4) as u8), ty: scalar });
+            }
+            shader.arrays.push(ConstantArray {
+                buffer: slot,
+                name: array_name,
+                start,
+                registers,
+                count: 0,
+                layout: Layout::Vector,
+                ty: scalar,
+            });
+            end = (start + registers) * 16;
+        }
+    }
+
+    writeln!(text, "}} {};", buffer_name).unwrap();
+    shader.constant_buffers.push((slot, buffer_name, text));
+}
+
+const HELPERS: [(&str, &str); 6] = [
+    ("dxbc_ubfe", "\
+uint dxbc_ubfe(uint width, uint offset, uint value)
+{
+    width &= 31u;
+    offset &= 31u;
+    if (width == 0u)
+        return 0u;
+    if (width + offset < 32u)
+        return (value << (32u - width - offset)) >> (32u - width);
+    return value >> offset;
+}
+"),
+    ("dxbc_ibfe", "\
+int dxbc_ibfe(uint width, uint offset, int value)
+{
+    width &= 31u;
+    offset &= 31u;
+    if (width == 0u)
+        return 0;
+    if (width + offset < 32u)
+        return (value << (32u - width - offset)) >> (32u - width);
+    return value >> offset;
+}
+"),
+    ("dxbc_bfi", "\
+uint dxbc_bfi(uint width, uint offset, uint insert, uint base)
+{
+    uint mask = ((1u << (width & 31u)) - 1u) << (offset & 31u);
+    return ((insert << (offset & 31u)) & mask) | (base & ~mask);
+}
+"),
+    ("dxbc_umul_hi", "\
+uint dxbc_umul_hi(uint a, uint b)
+{
+    uint hi, lo;
+    umulExtended(a, b, hi, lo);
+    return hi;
+}
+"),
+    ("dxbc_imul_hi", "\
+int dxbc_imul_hi(int a, int b)
+{
+    int hi, lo;
+    imulExtended(a, b, hi, lo);
+    return hi;
+}
+"),
+    // the standard D3D sample patterns in sixteenths of a pixel
+    ("dxbc_sample_position", "\
+const ivec2 dxbc_sample_positions[31] = ivec2[31](
+    ivec2(0, 0),
+    ivec2(4, 4), ivec2(-4, -4),
+    ivec2(-2, -6), ivec2(6, -2), ivec2(-6, 2), ivec2(2, 6),
+    ivec2(1, -3), ivec2(-1, 3), ivec2(5, 1), ivec2(-3, -5),
+    ivec2(-5, 5), ivec2(-7, -1), ivec2(3, 7), ivec2(7, -7),
+    ivec2(1, 1), ivec2(-1, -3), ivec2(-3, 2), ivec2(4, -1),
+    ivec2(-5, -2), ivec2(2, 5), ivec2(5, 3), ivec2(3, -5),
+    ivec2(-2, 6), ivec2(0, -7), ivec2(-4, -6), ivec2(-6, 4),
+    ivec2(-8, 0), ivec2(7, -4), ivec2(6, 7), ivec2(-7, -8)
+);
+
+vec2 dxbc_sample_position(uint count, uint index)
+{
+    if (index >= count || count > 16u || (count & (count - 1u)) != 0u)
+        return vec2(0.0);
+    return vec2(dxbc_sample_positions[count - 1u + index]) / 16.0;
+}
+"),
+];
+
+struct Glsl {
+    version: GlslVersion,
+    /// Binding, type and name of the samplers the code reads textures
+    /// with, combined with their sampler state or alone for `texelFetch`.
+    samplers: BTreeSet<(u32, String, String)>,
+}
+
+impl Language for Glsl {
+    const LOOP: &'static str = "while (true) {";
+    const DISCARD: &'static str = "discard;";
+
+    fn vector(ty: Type, width: usize) -> String {
+        vector(ty, width)
+    }
+
+    fn convert(expr: String, from: Type, to: Type, width: usize) -> String {
+        convert(expr, from, to, width)
+    }
+
+    fn literal(bits: u32, ty: Type) -> String {
+        format_literal(bits, ty)
+    }
+
+    fn system_value(file: OperandType) -> Option<(&'static str, Type, usize, &'static str)> {
+        Some(match file {
+            OperandType::InputPrimitiveId => ("vPrim", Type::Uint, 1, "gl_PrimitiveID"),
+            OperandType::InputCoverageMask => ("vCoverage", Type::Uint, 1, "gl_SampleMaskIn[0]"),
+            OperandType::InputThreadId => ("vThreadID", Type::Uint, 3, "gl_GlobalInvocationID"),
+            OperandType::InputThreadGroupId => ("vThreadGroupID", Type::Uint, 3, "gl_WorkGroupID"),
+            OperandType::InputThreadIdInGroup => ("vThreadIDInGroup", Type::Uint, 3, "gl_LocalInvocationID"),
+            OperandType::InputThreadIdInGroupFlattened => ("vThreadIDInGroupFlattened", Type::Uint, 1, "gl_LocalInvocationIndex"),
+            OperandType::InputGsinstanceid => ("vGSInstanceID", Type::Uint, 1, "gl_InvocationID"),
+            OperandType::OutputDepth | OperandType::OutputDepthGreaterEqual | OperandType::OutputDepthLessEqual => {
+                ("oDepth", Type::Float, 1, "")
+            }
+            OperandType::OutputCoverageMask => ("oMask", Type::Uint, 1, ""),
+            _ => return None,
+        })
+    }
+
+    fn add_constant_buffer(shader: &mut Shader, buffer: &dr::ConstantBuffer, slot: u32) {
+        add_constant_buffer(shader, buffer, slot)
+    }
+
+    fn local(name: &str, ty: Type, width: usize, value: &str) -> String {
+        format!("{} {} = {};", vector(ty, width), name, value)
+    }
+
+    fn ret(writer: &mut Writer<Self>) -> Result<(), Error> {
+        writer.ret();
+        Ok(())
+    }
+
+    fn operation(writer: &mut Writer<Self>, i: usize) -> Result<(), Error> {
+        writer.operation(i)
+    }
+
+    const TYPED_TEMPS: bool = true;
+    const FOLD_EXPRESSIONS: bool = true;
+
+    // matrices are built from columns, the rows become them and multiply
+    // the vector from the left
+    fn matrix_multiply(vector: String, rows: &[String], width: usize) -> Option<String> {
+        let matrix = if rows.len() == width { format!("mat{}", width) } else { format!("mat{}x{}", rows.len(), width) };
+        Some(format!("{}({}) * {}", matrix, rows.join(", "), backend::atom(&vector)))
+    }
+
+    fn saturate(value: String) -> String {
+        format!("clamp({}, 0.0, 1.0)", value)
+    }
+}
+
+impl<'a> Writer<'a, Glsl> {
+    // Fails instruction `i` unless the version has what it needs.
+    fn require(&self, i: usize, available: bool) -> Result<(), Error> {
+        if available {
+            Ok(())
+        } else {
+            Err(self.unsupported(i, self.language.version.reason()))
+        }
+    }
+
+    fn require_430(&self, i: usize) -> Result<(), Error> {
+        self.require(i, self.language.version == GlslVersion::V430)
+    }
+
+    // Name of the sampler reading `texture`, combined with the sampler
+    // state or alone for `texelFetch`, declared once the code is written.
+    fn sampler(&mut self, i: usize, texture: &Register, sampler: Option<&Register>, shadow: bool) -> Result<String, Error> {
+        let (dimension, ty) = self.resource(texture).map_or((ResourceDimension::Texture2D, Type::Float), |resource| {
+            (resource.dimension, resource.ty)
+        });
+        let sampler_type = match sampler_type(dimension, ty, shadow, self.language.version) {
+            Some(sampler_type) => sampler_type,
+            None => return Err(self.unsupported(i, self.language.version.reason())),
+        };
+
+        let texture_name = self.resource_name(texture);
+        let name = match sampler {
+            Some(sampler) => format!("{}_{}{}", texture_name, self.resource_name(sampler), if shadow { "_c" } else { "" }),
+            None => texture_name,
+        };
+        self.language.samplers.insert((backend::resource_index(texture), sampler_type, name.clone()));
+        Ok(name)
+    }
+
+    // D3D only uses the low five bits of a shift, GLSL leaves larger ones
+    // undefined.
+    fn shift(&mut self, i: usize, op: &str) -> Result<(), Error> {
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let count = match *self.operand(i, 2)? {
+                Operand::Imm32(ref values) => self.src(&Operand::Imm32(values.iter().map(|value| value & 31).collect()), &dst.written(), Type::Uint),
+                ref operand => format!("({} & 31u)", self.src(operand, &dst.written(), Type::Uint)),
+            };
+            let value = self.src(self.operand(i, 1)?, &dst.written(), self.source_type(i, 1));
+            let ty = self.result_type(i, dst.first());
+            self.assign(i, &dst, ty, format!("{} {} {}", value, op, count));
+        }
+
+        Ok(())
+    }
+
+    // Writes a comparison as the all ones or all zeros mask produced by the
+    // D3D comparison instructions, vectors compare with the relational
+    // functions.
+    fn compare(&mut self, i: usize, op: &str, function: &str) -> Result<(), Error> {
+        let width = self.dst(self.operand(i, 0)?).map_or(1, |dst| dst.width());
+        if width == 1 {
+            self.componentwise(i, &[1, 2], &|args| format!("({} {} {}) ? 0xffffffffu : 0u", args[0], op, args[1]))
+        } else {
+            let ty = vector(Type::Uint, width);
+            self.componentwise(i, &[1, 2], &|args| format!("{}({}({}, {})) * 0xffffffffu", ty, function, args[0], args[1]))
+        }
+    }
+
+    fn sample(&mut self, i: usize) -> Result<(), Error> {
+        let opcode = self.program.instructions[i].opcode;
+
+        let (address, offset, resource, sampler) = match opcode {
+            D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C => (1, Some(2), 3, 4),
+            _ => (1, None, 2, 3),
+        };
+        let extra = sampler + 1;
+        let gather = matches!(
+            opcode,
+            D3D10_1_SB_OPCODE_GATHER4 | D3D11_SB_OPCODE_GATHER4_C | D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C
+        );
+        let compare = matches!(
+            opcode,
+            D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ | D3D11_SB_OPCODE_GATHER4_C | D3D11_SB_OPCODE_GATHER4_PO_C
+        );
+        if gather {
+            self.require_430(i)?;
+        }
+
+        let texture = self.register(i, resource)?;
+        let sampler_register = self.register(i, sampler)?;
+        let dimension = self.dimension(texture);
+        let (coordinates, offsets) = backend::texture_coordinates(dimension);
+        let name = self.sampler(i, texture, Some(sampler_register), compare)?;
+
+        let coordinate_components: Vec<u8> = (0..coordinates as u8).collect();
+        let mut coordinates = self.src(self.operand(i, address)?, &coordinate_components, Type::Float);
+        let float = |operand: usize| -> Result<String, Error> { Ok(self.src(self.operand(i, operand)?, &[0], Type::Float)) };
+
+        // shadow lookups take the reference as the last coordinate, but for
+        // cube map arrays and gathers
+        let cube_array = dimension == ResourceDimension::TextureCubeArray;
+        let mut reference = None;
+        if compare {
+            let value = float(extra)?;
+            if gather || cube_array {
+                reference = Some(value);
+            } else if dimension == ResourceDimension::Texture1D {
+                coordinates = format!("vec3({}, 0.0, {})", coordinates, value);
+            } else {
+                coordinates = format!("{}({}, {})", vector(Type::Float, coordinate_components.len() + 1), coordinates, value);
+            }
+        }
+
+        let mut args = vec![name, coordinates];
+        args.extend(reference);
+        let mut trailing = None;
+
+        let function = match opcode {
+            D3D10_SB_OPCODE_SAMPLE | D3D10_SB_OPCODE_SAMPLE_C => "texture",
+            D3D10_SB_OPCODE_SAMPLE_B => {
+                trailing = Some(float(extra)?);
+                "texture"
+            }
+            D3D10_SB_OPCODE_SAMPLE_L => {
+                args.push(float(extra)?);
+                "textureLod"
+            }
+            D3D10_SB_OPCODE_SAMPLE_D => {
+                let gradients = match dimension {
+                    ResourceDimension::TextureCube | ResourceDimension::TextureCubeArray => 3,
+                    _ => offsets,
+                };
+                let components: Vec<u8> = (0..gradients as u8).collect();
+                args.push(self.src(self.operand(i, extra)?, &components, Type::Float));
+                args.push(self.src(self.operand(i, extra + 1)?, &components, Type::Float));
+                "textureGrad"
+            }
+            // shadow lookups of arrays and cube maps have no level, zero
+            // gradients pick the first one
+            D3D10_SB_OPCODE_SAMPLE_C_LZ => match dimension {
+                ResourceDimension::Texture2DArray | ResourceDimension::TextureCube => {
+                    let zero = vector(Type::Float, if dimension == ResourceDimension::TextureCube { 3 } else { 2 });
+                    args.push(format!("{}(0.0)", zero));
+                    args.push(format!("{}(0.0)", zero));
+                    "textureGrad"
+                }
+                ResourceDimension::TextureCubeArray => return Err(self.unsupported(i, " of a cube map array")),
+                _ => {
+                    args.push("0.0".to_owned());
+                    "textureLod"
+                }
+            },
+            _ => {
+                if !compare {
+                    trailing = Some(format!("{}", sampler_register.components.swizzle()[0] & 3));
+                }
+                "textureGather"
+            }
+        };
+
+        let offset_components: Vec<u8> = (0..offsets as u8).collect();
+        let offset = match offset {
+            Some(offset) => Some(self.src(self.operand(i, offset)?, &offset_components, Type::Int)),
+            None => self.immediate_offset(i, offsets),
+        };
+        let suffix = if offset.is_some() { "Offset" } else { "" };
+        args.extend(offset);
+        args.extend(trailing);
+
+        let value = format!("{}{}({})", function, suffix, args.join(", "));
+        if compare && !gather {
+            if let Some(dst) = self.dst(self.operand(i, 0)?) {
+                self.assign_scalar(i, &dst, Type::Float, value);
+            }
+            Ok(())
+        } else {
+            self.assign_resource(i, resource, value)
+        }
+    }
+
+    fn load(&mut self, i: usize) -> Result<(), Error> {
+        let texture = self.register(i, 2)?;
+        let dimension = self.dimension(texture);
+        let (coordinates, offsets) = backend::texture_coordinates(dimension);
+        let multisampled = self.program.instructions[i].opcode == D3D10_SB_OPCODE_LD_MS;
+        let name = self.sampler(i, texture, None, false)?;
+
+        let components: Vec<u8> = (0..coordinates as u8).collect();
+        let mut coordinates = self.src(self.operand(i, 1)?, &components, Type::Int);
+        let mut offset = self.immediate_offset(i, offsets);
+        // multisampled textures take no offsets
+        if multisampled {
+            if let Some(offset) = offset.take() {
+                coordinates = format!("{} + {}", coordinates, offset);
+            }
+        }
+
+        // the mip level is always in the last address component
+        let mut args = vec![name, coordinates];
+        if multisampled {
+            args.push(self.src(self.operand(i, 3)?, &[0], Type::Int));
+        } else if dimension != ResourceDimension::Buffer {
+            args.push(self.src(self.operand(i, 1)?, &[3], Type::Int));
+        }
+        let suffix = if offset.is_some() { "Offset" } else { "" };
+        args.extend(offset);
+
+        let value = format!("texelFetch{}({})", suffix, args.join(", "));
+        self.assign_resource(i, 2, value)
+    }
+
+    fn typed_address(&self, uav: &Register, address: &Operand) -> String {
+        let (coordinates, _) = backend::texture_coordinates(self.dimension(uav));
+        let components: Vec<u8> = (0..coordinates as u8).collect();
+        self.src(address, &components, Type::Int)
+    }
+
+    // Element of structured or raw memory holding a dword.
+    fn memory(&self, resource: &Register, index: Option<&Operand>, offset: &Operand, component: u8) -> String {
+        let name = self.resource_name(resource);
+        let dword = match *offset {
+            Operand::Imm32(ref values) => format!("{}u", values.first().cloned().unwrap_or(0) / 4 + component as u32),
+            _ => {
+                let offset = self.src(offset, &[0], Type::Uint);
+                match component {
+                    0 => format!("({} >> 2u)", offset),
+                    c => format!("({} >> 2u) + {}u", offset, c),
+                }
+            }
+        };
+
+        match index {
+            Some(index) => {
+                let stride = self.resource(resource).map_or(1, |resource| resource.stride);
+                format!("{}[{} * {}u + {}]", name, self.src(index, &[0], Type::Uint), stride, dword)
+            }
+            None => format!("{}[{}]", name, dword),
+        }
+    }
+
+    fn load_memory(&mut self, i: usize) -> Result<(), Error> {
+        let structured = self.program.instructions[i].opcode == D3D11_SB_OPCODE_LD_STRUCTURED;
+        let resource = if structured { 3 } else { 2 };
+        let register = self.register(i, resource)?;
+
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let picks = self.resource_picks(self.operand(i, resource)?, &dst.written());
+            let index = if structured { Some(self.operand(i, 1)?) } else { None };
+            let offset = self.operand(i, if structured { 2 } else { 1 })?;
+            let parts: Vec<String> = picks.iter().map(|&c| self.memory(register, index, offset, c)).collect();
+            let expr = if parts.len() == 1 {
+                parts[0].clone()
+            } else {
+                format!("{}({})", vector(Type::Uint, parts.len()), parts.join(", "))
+            };
+            self.assign(i, &dst, Type::Uint, expr);
+        }
+
+        Ok(())
+    }
+
+    fn store_memory(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 0)?;
+        let components = backend::mask_components(register.components.mask());
+        let structured = self.program.instructions[i].opcode == D3D11_SB_OPCODE_STORE_STRUCTURED;
+        let value = self.operand(i, if structured { 3 } else { 2 })?;
+
+        let index = if structured { Some(self.operand(i, 1)?) } else { None };
+        let offset = self.operand(i, if structured { 2 } else { 1 })?;
+        for (n, &c) in components.iter().enumerate() {
+            let text = format!("{} = {};", self.memory(register, index, offset, n as u8), self.src(value, &[c], Type::Uint));
+            self.line(&text);
+        }
+
+        Ok(())
+    }
+
+    fn store_typed(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 0)?;
+        let ty = self.resource(register).map_or(Type::Float, |resource| resource.ty);
+
+        let text = format!(
+            "imageStore({}, {}, {});",
+            self.resource_name(register),
+            self.typed_address(register, self.operand(i, 1)?),
+            self.src(self.operand(i, 2)?, &[0, 1, 2, 3], ty)
+        );
+        self.line(&text);
+        Ok(())
+    }
+
+    fn atomic(&mut self, i: usize) -> Result<(), Error> {
+        let opcode = self.program.instructions[i].opcode;
+        let immediate = opcode >= D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC;
+        let first = if immediate { 1 } else { 0 };
+        let register = self.register(i, first)?;
+
+        if opcode == D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC || opcode == D3D11_SB_OPCODE_IMM_ATOMIC_CONSUME {
+            return Err(self.unsupported(i, " without a hidden counter"));
+        }
+        let (kind, ty) = self.resource(register).map_or((ResourceKind::Raw, Type::Uint), |resource| (resource.kind, resource.ty));
+        let typed = register.file == OperandType::UnorderedAccessView && kind == ResourceKind::Typed;
+        // memory holds unsigned integers, images their channel type
+        let signed = matches!(
+            opcode,
+            D3D11_SB_OPCODE_ATOMIC_IMAX | D3D11_SB_OPCODE_IMM_ATOMIC_IMAX | D3D11_SB_OPCODE_ATOMIC_IMIN | D3D11_SB_OPCODE_IMM_ATOMIC_IMIN
+        );
+        let unsigned = matches!(
+            opcode,
+            D3D11_SB_OPCODE_ATOMIC_UMAX | D3D11_SB_OPCODE_IMM_ATOMIC_UMAX | D3D11_SB_OPCODE_ATOMIC_UMIN | D3D11_SB_OPCODE_IMM_ATOMIC_UMIN
+        );
+        let ty = if typed { ty } else { Type::Uint };
+        if (signed && ty != Type::Int) || (unsigned && ty != Type::Uint) {
+            return Err(self.unsupported(i, " on values of the other signedness"));
+        }
+
+        let values = match opcode {
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => 2,
+            _ => 1,
+        };
+        let mut args = Vec::new();
+        let address = self.operand(i, first + 1)?;
+        if typed {
+            args.push(self.resource_name(register));
+            args.push(self.typed_address(register, address));
+        } else if kind == ResourceKind::Structured {
+            let offset = match *address {
+                Operand::Register(ref address) => {
+                    let mut offset = address.clone();
+                    offset.components = Components::Select(address.components.swizzle()[1]);
+                    Operand::Register(offset)
+                }
+                Operand::Imm32(ref values) => Operand::Imm32(vec![values.get(1).cloned().unwrap_or(0)]),
+                ref other => other.clone(),
+            };
+            args.push(self.memory(register, Some(address), &offset, 0));
+        } else {
+            args.push(self.memory(register, None, address, 0));
+        }
+        for operand in (first + 2)..(first + 2 + values) {
+            args.push(self.src(self.operand(i, operand)?, &[0], ty));
+        }
+
+        let function = match opcode {
+            D3D11_SB_OPCODE_ATOMIC_AND | D3D11_SB_OPCODE_IMM_ATOMIC_AND => "And",
+            D3D11_SB_OPCODE_ATOMIC_OR | D3D11_SB_OPCODE_IMM_ATOMIC_OR => "Or",
+            D3D11_SB_OPCODE_ATOMIC_XOR | D3D11_SB_OPCODE_IMM_ATOMIC_XOR => "Xor",
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => "CompSwap",
+            D3D11_SB_OPCODE_IMM_ATOMIC_EXCH => "Exchange",
+            D3D11_SB_OPCODE_ATOMIC_IADD | D3D11_SB_OPCODE_IMM_ATOMIC_IADD => "Add",
+            D3D11_SB_OPCODE_ATOMIC_IMAX | D3D11_SB_OPCODE_IMM_ATOMIC_IMAX | D3D11_SB_OPCODE_ATOMIC_UMAX |
+            D3D11_SB_OPCODE_IMM_ATOMIC_UMAX => "Max",
+            _ => "Min",
+        };
+
+        let call = format!("{}{}({})", if typed { "imageAtomic" } else { "atomic" }, function, args.join(", "));
+        if !immediate {
+            let text = format!("{};", call);
+            self.line(&text);
+        } else if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            self.assign(i, &dst, ty, call);
+        }
+
+        Ok(())
+    }
+
+    fn resinfo(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 2)?;
+        let dimension = self.dimension(register);
+        let uav = register.file == OperandType::UnorderedAccessView;
+        let multisampled = dimension == ResourceDimension::Texture2DMS || dimension == ResourceDimension::Texture2DMSArray;
+        let mipmapped = !uav && !multisampled;
+
+        let sizes = match dimension {
+            ResourceDimension::Buffer | ResourceDimension::Texture1D => 1,
+            ResourceDimension::Texture1DArray |
+            ResourceDimension::Texture2D |
+            ResourceDimension::TextureCube |
+            ResourceDimension::Texture2DMS => 2,
+            _ => 3,
+        };
+
+        // only the mip level count needs a newer version
+        let levels = match self.dst(self.operand(i, 0)?) {
+            Some(dst) => mipmapped && self.resource_picks(self.operand(i, 2)?, &dst.written()).contains(&3),
+            None => return Ok(()),
+        };
+        if levels {
+            self.require_430(i)?;
+        }
+
+        let (size, name) = if uav {
+            let name = self.resource_name(register);
+            (format!("imageSize({})", name), name)
+        } else {
+            let name = self.sampler(i, register, None, false)?;
+            if mipmapped {
+                (format!("textureSize({}, {})", name, self.src(self.operand(i, 1)?, &[0], Type::Int)), name)
+            } else {
+                (format!("textureSize({})", name), name)
+            }
+        };
+
+        let return_type = DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(self.program.instructions[i].controls);
+        let rcp = return_type == D3D10_SB_RESINFO_INSTRUCTION_RETURN_RCPFLOAT;
+        let ty = if return_type == D3D10_SB_RESINFO_INSTRUCTION_RETURN_UINT { Type::Uint } else { Type::Float };
+        let value = |value: String, width: usize| format!("{}({})", vector(ty, width), value);
+
+        let mut parts = vec![if rcp {
+            format!("1.0 / {}", value(size, sizes))
+        } else {
+            value(size, sizes)
+        }];
+        for _ in sizes..3 {
+            parts.push(format_literal(0, ty));
+        }
+        parts.push(if levels {
+            value(format!("textureQueryLevels({})", name), 1)
+        } else {
+            format_literal(if ty == Type::Uint { 1 } else { 1.0f32.to_bits() }, ty)
+        });
+
+        let value = format!("{}({})", vector(ty, 4), parts.join(", "));
+        self.assign_resource(i, 2, value)
+    }
+
+    fn bufinfo(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let (kind, stride) = self.resource(register).map_or((ResourceKind::Raw, 1), |resource| (resource.kind, resource.stride));
+
+        let value = match kind {
+            ResourceKind::Raw => format!("uint({}.length()) * 4u", self.resource_name(register)),
+            ResourceKind::Structured => format!("uint({}.length()) / {}u", self.resource_name(register), stride),
+            ResourceKind::Typed if register.file == OperandType::UnorderedAccessView => {
+                format!("uint(imageSize({}))", self.resource_name(register))
+            }
+            ResourceKind::Typed => format!("uint(textureSize({}))", self.sampler(i, register, None, false)?),
+        };
+
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            self.assign_scalar(i, &dst, Type::Uint, value);
+        }
+
+        Ok(())
+    }
+
+    // Only the samples of the render target are known, textures need
+    // `textureSamples` from GLSL 450.
+    fn sample_info(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        if register.file != OperandType::Rasterizer {
+            return Err(self.unsupported(i, self.language.version.reason()));
+        }
+        self.require_430(i)?;
+
+        let ty = self.result_type(i, 0);
+        let zero = format_literal(0, ty);
+        let value = format!("{}({}(gl_NumSamples), {}, {}, {})", vector(ty, 4), scalar_name(ty), zero, zero, zero);
+        self.assign_resource(i, 1, value)
+    }
+
+    fn sample_position(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        if register.file != OperandType::Rasterizer {
+            return Err(self.unsupported(i, self.language.version.reason()));
+        }
+        self.require_430(i)?;
+
+        let index = self.src(self.operand(i, 2)?, &[0], Type::Uint);
+        self.helpers.insert("dxbc_sample_position");
+        let value = format!("vec4(dxbc_sample_position(uint(gl_NumSamples), {}), 0.0, 0.0)", index);
+        self.assign_resource(i, 1, value)
+    }
+
+    fn lod(&mut self, i: usize) -> Result<(), Error> {
+        self.require_430(i)?;
+        let texture = self.register(i, 2)?;
+        let dimension = self.dimension(texture);
+        let name = self.sampler(i, texture, Some(self.register(i, 3)?), false)?;
+
+        // the level doesn't depend on the array slice
+        let (coordinates, _) = backend::texture_coordinates(dimension);
+        let coordinates = if backend::arrayed(dimension) { coordinates - 1 } else { coordinates };
+        let components: Vec<u8> = (0..coordinates as u8).collect();
+
+        let value = format!("vec4(textureQueryLod({}, {}), 0.0, 0.0)", name, self.src(self.operand(i, 1)?, &components, Type::Float));
+        self.assign_resource(i, 2, value)
+    }
+
+    // Interpolates an input again, the functions only take input variables
+    // so the components come from the signature elements.
+    fn evaluate(&mut self, i: usize) -> Result<(), Error> {
+        self.require_430(i)?;
+        let register = self.register(i, 1)?;
+        let (function, extra) = match self.program.instructions[i].opcode {
+            D3D11_SB_OPCODE_EVAL_CENTROID => ("interpolateAtCentroid", None),
+            D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX => ("interpolateAtSample", Some(self.src(self.operand(i, 2)?, &[0], Type::Int))),
+            // snapped offsets are in sixteenths of a pixel
+            _ => ("interpolateAtOffset", Some(format!("vec2({}) / 16.0", self.src(self.operand(i, 2)?, &[0, 1], Type::Int)))),
+        };
+
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let swizzle_of = register.components.swizzle();
+            let index = backend::resource_index(register);
+            let program_type = self.shader.program_type;
+            let parts: Vec<String> = dst
+                .written()
+                .iter()
+                .map(|&c| {
+                    let pick = swizzle_of[c as usize] & 3;
+                    let element = self.shader.inputs.iter().find(|element| element.register == index && element.mask & (1 << pick) != 0);
+                    match element {
+                        Some(element) => {
+                            let lane = pick - element.mask.trailing_zeros() as u8;
+                            let mut args = vec![element.input_name(program_type)];
+                            args.extend(extra.clone());
+                            let value = format!("{}({})", function, args.join(", "));
+                            let value = if backend::mask_components(element.mask).len() == 1 {
+                                value
+                            } else {
+                                format!("{}.{}", value, COMPONENTS[lane as usize])
+                            };
+                            convert(value, element.ty, Type::Float, 1)
+                        }
+                        None => "0.0".to_owned(),
+                    }
+                })
+                .collect();
+
+            let expr = if parts.len() == 1 {
+                parts[0].clone()
+            } else {
+                format!("{}({})", vector(Type::Float, parts.len()), parts.join(", "))
+            };
+            self.assign(i, &dst, Type::Float, expr);
+        }
+
+        Ok(())
+    }
+
+    fn sync(&mut self, i: usize) -> Result<(), Error> {
+        self.require_430(i)?;
+        let flags = self.program.instructions[i].controls;
+
+        if flags & D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GLOBAL != 0 {
+            self.line("memoryBarrier();");
+        } else if flags & D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GROUP != 0 {
+            self.line("groupMemoryBarrier();");
+        }
+        if flags & D3D11_SB_SYNC_THREAD_GROUP_SHARED_MEMORY != 0 {
+            self.line("memoryBarrierShared();");
+        }
+        if flags & D3D11_SB_SYNC_THREADS_IN_GROUP != 0 {
+            self.line("barrier();");
+        }
+
+        Ok(())
+    }
+
+    // Geometry shader outputs are copied out for every vertex.
+    fn emit(&mut self, i: usize) -> Result<(), Error> {
+        let opcode = self.program.instructions[i].opcode;
+        let stream = match opcode {
+            D3D11_SB_OPCODE_EMIT_STREAM | D3D11_SB_OPCODE_CUT_STREAM | D3D11_SB_OPCODE_EMITTHENCUT_STREAM => {
+                self.require_430(i)?;
+                Some(backend::resource_index(self.register(i, 0)?))
+            }
+            _ => None,
+        };
+
+        if opcode != D3D10_SB_OPCODE_CUT && opcode != D3D11_SB_OPCODE_CUT_STREAM {
+            self.write_output_copies();
+            let text = match stream {
+                Some(stream) => format!("EmitStreamVertex({});", stream),
+                None => "EmitVertex();".to_owned(),
+            };
+            self.line(&text);
+        }
+        if opcode != D3D10_SB_OPCODE_EMIT && opcode != D3D11_SB_OPCODE_EMIT_STREAM {
+            let text = match stream {
+                Some(stream) => format!("EndStreamPrimitive({});", stream),
+                None => "EndPrimitive();".to_owned(),
+            };
+            self.line(&text);
+        }
+
+        Ok(())
+    }
+
+    fn write_output_copies(&mut self) {
+        let shader = self.shader;
+        let program_type = shader.program_type;
+        let mut clip_distance = 0;
+
+        for output in &shader.outputs {
+            let components = backend::mask_components(output.mask);
+            let value = format!("o{}.{}", output.register, backend::swizzle(&components));
+
+            match output.semantic_type {
+                _ if output.register == !0 => match output.system_register() {
+                    Some("oMask") => self.line("gl_SampleMask[0] = int(oMask);"),
+                    Some(name) => {
+                        let text = format!("gl_FragDepth = {};", name);
+                        self.line(&text);
+                    }
+                    None => {}
+                },
+                dr::SemanticName::Position if output.builtin_output(program_type) => {
+                    let text = if components.len() == 4 {
+                        format!("gl_Position = {};", value)
+                    } else {
+                        format!("gl_Position.{} = {};", backend::swizzle(&components), value)
+                    };
+                    self.line(&text);
+                }
+                dr::SemanticName::ClipDistance if output.builtin_output(program_type) => {
+                    for &c in &components {
+                        let text = format!("gl_ClipDistance[{}] = o{}.{};", clip_distance, output.register, COMPONENTS[c as usize]);
+                        self.line(&text);
+                        clip_distance += 1;
+                    }
+                }
+                _ => {
+                    let text = format!("{} = {};", output.output_name(program_type), convert(value, Type::Float, output.ty, components.len()));
+                    self.line(&text);
+                }
+            }
+        }
+    }
+
+    fn ret(&mut self) {
+        if !self.in_subroutine {
+            self.write_output_copies();
+        }
+        self.line("return;");
+    }
+
+    fn operation(&mut self, i: usize) -> Result<(), Error> {
+        match self.program.instructions[i].opcode {
+            D3D10_SB_OPCODE_ADD | D3D10_SB_OPCODE_IADD => self.binary(i, "+"),
+            D3D10_SB_OPCODE_MUL => self.binary(i, "*"),
+            D3D10_SB_OPCODE_DIV => self.binary(i, "/"),
+            D3D10_SB_OPCODE_AND => self.binary(i, "&"),
+            D3D10_SB_OPCODE_OR => self.binary(i, "|"),
+            D3D10_SB_OPCODE_XOR => self.binary(i, "^"),
+            D3D10_SB_OPCODE_ISHL => self.shift(i, "<<"),
+            D3D10_SB_OPCODE_ISHR | D3D10_SB_OPCODE_USHR => self.shift(i, ">>"),
+            D3D10_SB_OPCODE_MAD | D3D10_SB_OPCODE_IMAD | D3D10_SB_OPCODE_UMAD => {
+                self.componentwise(i, &[1, 2, 3], &|args| format!("{} * {} + {}", args[0], args[1], args[2]))
+            }
+            D3D10_SB_OPCODE_MOV => self.componentwise(i, &[1], &|args| args[0].clone()),
+            // the conditional operator only takes a scalar condition
+            D3D10_SB_OPCODE_MOVC => {
+                self.scalar(i, &[1, 2, 3], &|args| format!("({} != 0u) ? {} : {}", args[0], args[1], args[2]))
+            }
+            D3D10_SB_OPCODE_NOT => self.componentwise(i, &[1], &|args| format!("~{}", args[0])),
+            D3D10_SB_OPCODE_INEG => self.componentwise(i, &[1], &|args| backend::negate(&args[0])),
+            D3D10_SB_OPCODE_ITOF | D3D10_SB_OPCODE_UTOF | D3D10_SB_OPCODE_FTOI | D3D10_SB_OPCODE_FTOU => {
+                if let Some(dst) = self.dst(self.operand(i, 0)?) {
+                    let ty = self.result_type(i, dst.first());
+                    let value = self.src(self.operand(i, 1)?, &dst.written(), self.source_type(i, 1));
+                    self.assign(i, &dst, ty, format!("{}({})", vector(ty, dst.width()), value));
+                }
+                Ok(())
+            }
+            D3D10_SB_OPCODE_MIN | D3D10_SB_OPCODE_IMIN | D3D10_SB_OPCODE_UMIN => self.call(i, "min"),
+            D3D10_SB_OPCODE_MAX | D3D10_SB_OPCODE_IMAX | D3D10_SB_OPCODE_UMAX => self.call(i, "max"),
+            D3D10_SB_OPCODE_DP2 => self.dot(i, 2),
+            D3D10_SB_OPCODE_DP3 => self.dot(i, 3),
+            D3D10_SB_OPCODE_DP4 => self.dot(i, 4),
+            D3D10_SB_OPCODE_EXP => self.call(i, "exp2"),
+            D3D10_SB_OPCODE_LOG => self.call(i, "log2"),
+            D3D10_SB_OPCODE_FRC => self.call(i, "fract"),
+            D3D10_SB_OPCODE_SQRT => self.call(i, "sqrt"),
+            D3D10_SB_OPCODE_RSQ => self.call(i, "inversesqrt"),
+            D3D11_SB_OPCODE_RCP => self.componentwise(i, &[1], &|args| format!("1.0 / {}", args[0])),
+            D3D10_SB_OPCODE_ROUND_NE => self.call(i, "roundEven"),
+            D3D10_SB_OPCODE_ROUND_NI => self.call(i, "floor"),
+            D3D10_SB_OPCODE_ROUND_PI => self.call(i, "ceil"),
+            D3D10_SB_OPCODE_ROUND_Z => self.call(i, "trunc"),
+            // the coarse and fine variants need GLSL 450
+            D3D10_SB_OPCODE_DERIV_RTX | D3D11_SB_OPCODE_DERIV_RTX_COARSE | D3D11_SB_OPCODE_DERIV_RTX_FINE => self.call(i, "dFdx"),
+            D3D10_SB_OPCODE_DERIV_RTY | D3D11_SB_OPCODE_DERIV_RTY_COARSE | D3D11_SB_OPCODE_DERIV_RTY_FINE => self.call(i, "dFdy"),
+            D3D11_SB_OPCODE_COUNTBITS => {
+                self.require_430(i)?;
+                self.scalar(i, &[1], &|args| format!("uint(bitCount({}))", args[0]))
+            }
+            D3D11_SB_OPCODE_BFREV => {
+                self.require_430(i)?;
+                self.call(i, "bitfieldReverse")
+            }
+            D3D11_SB_OPCODE_FIRSTBIT_LO => {
+                self.require_430(i)?;
+                self.scalar(i, &[1], &|args| format!("uint(findLSB({}))", args[0]))
+            }
+            // D3D counts the bit position from the most significant end
+            D3D11_SB_OPCODE_FIRSTBIT_HI | D3D11_SB_OPCODE_FIRSTBIT_SHI => {
+                self.require_430(i)?;
+                self.scalar(i, &[1], &|args| format!("(findMSB({}) < 0) ? 0xffffffffu : uint(31 - findMSB({}))", args[0], args[0]))
+            }
+            D3D11_SB_OPCODE_F32TOF16 => {
+                self.require(i, self.language.version != GlslVersion::V330)?;
+                self.scalar(i, &[1], &|args| format!("packHalf2x16(vec2({}, 0.0))", args[0]))
+            }
+            D3D11_SB_OPCODE_F16TOF32 => {
+                self.require(i, self.language.version != GlslVersion::V330)?;
+                self.scalar(i, &[1], &|args| format!("unpackHalf2x16({}).x", args[0]))
+            }
+            D3D10_SB_OPCODE_EQ | D3D10_SB_OPCODE_IEQ => self.compare(i, "==", "equal"),
+            D3D10_SB_OPCODE_NE | D3D10_SB_OPCODE_INE => self.compare(i, "!=", "notEqual"),
+            D3D10_SB_OPCODE_LT | D3D10_SB_OPCODE_ILT | D3D10_SB_OPCODE_ULT => self.compare(i, "<", "lessThan"),
+            D3D10_SB_OPCODE_GE | D3D10_SB_OPCODE_IGE | D3D10_SB_OPCODE_UGE => self.compare(i, ">=", "greaterThanEqual"),
+            D3D11_SB_OPCODE_UBFE => {
+                self.helpers.insert("dxbc_ubfe");
+                self.scalar(i, &[1, 2, 3], &|args| format!("dxbc_ubfe({})", args.join(", ")))
+            }
+            D3D11_SB_OPCODE_IBFE => {
+                self.helpers.insert("dxbc_ibfe");
+                self.scalar(i, &[1, 2, 3], &|args| format!("dxbc_ibfe({})", args.join(", ")))
+            }
+            D3D11_SB_OPCODE_BFI => {
+                self.helpers.insert("dxbc_bfi");
+                self.scalar(i, &[1, 2, 3, 4], &|args| format!("dxbc_bfi({})", args.join(", ")))
+            }
+            D3D10_SB_OPCODE_SINCOS => self.multiple(i, &[
+                &|args| format!("sin({})", args[0]),
+                &|args| format!("cos({})", args[0]),
+            ], 1, false),
+            D3D10_SB_OPCODE_UDIV => self.multiple(i, &[
+                &|args| format!("{} / {}", args[0], args[1]),
+                &|args| format!("{} % {}", args[0], args[1]),
+            ], 2, false),
+            D3D11_SB_OPCODE_UADDC => self.multiple(i, &[
+                &|args| format!("{} + {}", args[0], args[1]),
+                &|args| format!("({} + {} < {}) ? 1u : 0u", args[0], args[1], args[0]),
+            ], 2, true),
+            D3D11_SB_OPCODE_USUBB => self.multiple(i, &[
+                &|args| format!("{} - {}", args[0], args[1]),
+                &|args| format!("({} < {}) ? 1u : 0u", args[0], args[1]),
+            ], 2, true),
+            D3D11_SB_OPCODE_SWAPC => self.multiple(i, &[
+                &|args| format!("({} != 0u) ? {} : {}", args[0], args[2], args[1]),
+                &|args| format!("({} != 0u) ? {} : {}", args[0], args[1], args[2]),
+            ], 3, true),
+            // the high half of a product needs the extended multiplications
+            D3D10_SB_OPCODE_IMUL => {
+                if self.dst(self.operand(i, 0)?).is_some() {
+                    self.require_430(i)?;
+                    self.helpers.insert("dxbc_imul_hi");
+                }
+                self.multiple(i, &[
+                    &|args| format!("dxbc_imul_hi({}, {})", args[0], args[1]),
+                    &|args| format!("{} * {}", args[0], args[1]),
+                ], 2, true)
+            }
+            D3D10_SB_OPCODE_UMUL => {
+                if self.dst(self.operand(i, 0)?).is_some() {
+                    self.require_430(i)?;
+                    self.helpers.insert("dxbc_umul_hi");
+                }
+                self.multiple(i, &[
+                    &|args| format!("dxbc_umul_hi({}, {})", args[0], args[1]),
+                    &|args| format!("{} * {}", args[0], args[1]),
+                ], 2, true)
+            }
+            D3D10_SB_OPCODE_SAMPLE | D3D10_SB_OPCODE_SAMPLE_B | D3D10_SB_OPCODE_SAMPLE_L | D3D10_SB_OPCODE_SAMPLE_D |
+            D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ | D3D10_1_SB_OPCODE_GATHER4 |
+            D3D11_SB_OPCODE_GATHER4_C | D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C => self.sample(i),
+            D3D10_SB_OPCODE_LD | D3D10_SB_OPCODE_LD_MS => self.load(i),
+            D3D11_SB_OPCODE_LD_UAV_TYPED => {
+                let register = self.register(i, 2)?;
+                let value = format!("imageLoad({}, {})", self.resource_name(register), self.typed_address(register, self.operand(i, 1)?));
+                self.assign_resource(i, 2, value)
+            }
+            D3D11_SB_OPCODE_STORE_UAV_TYPED => self.store_typed(i),
+            D3D11_SB_OPCODE_LD_RAW | D3D11_SB_OPCODE_LD_STRUCTURED => self.load_memory(i),
+            D3D11_SB_OPCODE_STORE_RAW | D3D11_SB_OPCODE_STORE_STRUCTURED => self.store_memory(i),
+            D3D11_SB_OPCODE_ATOMIC_AND..=D3D11_SB_OPCODE_IMM_ATOMIC_UMIN => self.atomic(i),
+            D3D11_SB_OPCODE_SYNC => self.sync(i),
+            D3D10_SB_OPCODE_RESINFO => self.resinfo(i),
+            D3D11_SB_OPCODE_BUFINFO => self.bufinfo(i),
+            D3D10_1_SB_OPCODE_SAMPLE_INFO => self.sample_info(i),
+            D3D10_1_SB_OPCODE_SAMPLE_POS => self.sample_position(i),
+            D3D10_1_SB_OPCODE_LOD => self.lod(i),
+            D3D11_SB_OPCODE_EVAL_CENTROID | D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX | D3D11_SB_OPCODE_EVAL_SNAPPED => self.evaluate(i),
+            D3D10_SB_OPCODE_EMIT | D3D10_SB_OPCODE_CUT | D3D10_SB_OPCODE_EMITTHENCUT | D3D11_SB_OPCODE_EMIT_STREAM |
+            D3D11_SB_OPCODE_CUT_STREAM | D3D11_SB_OPCODE_EMITTHENCUT_STREAM => self.emit(i),
+            _ => Err(self.unsupported(i, "")),
+        }
+    }
+
+    // Declares the storage buffers, images and shared memory, textures and
+    // samplers are only collected, they are declared by their use.
+    fn write_resources(&mut self, out: &mut String) -> Result<(), Error> {
+        let shader = self.shader;
+        let program = self.program;
+        let version = self.language.version;
+        let binding = |index: u32| {
+            if version.has_binding_layout() { format!("binding = {}, ", index) } else { String::new() }
+        };
+
+        let read = backend::used_resources(program, &[D3D11_SB_OPCODE_LD_UAV_TYPED], OperandType::UnorderedAccessView);
+        let atomics: Vec<u32> = (D3D11_SB_OPCODE_ATOMIC_AND..=D3D11_SB_OPCODE_IMM_ATOMIC_UMIN).collect();
+        let atomic_views = backend::used_resources(program, &atomics, OperandType::UnorderedAccessView);
+
+        let mut declared = false;
+        for declaration in &program.decls {
+            let register = match declaration.register() {
+                Some(register) => register,
+                None => continue,
+            };
+            let index = backend::resource_index(register);
+            let file = register.file;
+            let resource_name = match file {
+                OperandType::ThreadGroupSharedMemory => format!("g{}", index),
+                _ => name(&shader.resource_name(file, index)),
+            };
+            let mut resource = Resource::new(resource_name.clone(), ResourceDimension::Unknown, ResourceKind::Typed);
+
+            let storage = match *declaration {
+                Declaration::UnorderedAccessViewTyped { .. } => Some("images"),
+                Declaration::ResourceRaw { .. } | Declaration::ResourceStructured { .. } => Some("raw and structured buffers"),
+                Declaration::UnorderedAccessViewRaw { .. } | Declaration::UnorderedAccessViewStructured { .. } => {
+                    Some("raw and structured buffers")
+                }
+                Declaration::ThreadGroupSharedMemoryRaw { .. } | Declaration::ThreadGroupSharedMemoryStructured { .. } => {
+                    Some("group shared memory")
+                }
+                _ => None,
+            };
+            if let Some(feature) = storage {
+                if version != GlslVersion::V430 {
+                    return Err(Error::UnsupportedFeature(feature, version.reason()));
+                }
+            }
+
+            match *declaration {
+                Declaration::Sampler { .. } => {}
+                Declaration::Resource { dimension, return_type: ref returns, .. } => {
+                    resource.dimension = ResourceDimension::from_word(dimension).unwrap_or(ResourceDimension::Unknown);
+                    resource.ty = backend::return_type(returns[0]);
+                }
+                Declaration::UnorderedAccessViewTyped { dimension, return_type: ref returns, globally_coherent, .. } => {
+                    resource.dimension = ResourceDimension::from_word(dimension).unwrap_or(ResourceDimension::Unknown);
+                    resource.ty = backend::return_type(returns[0]);
+                    let image = match image_type(resource.dimension, resource.ty) {
+                        Some(image) => image,
+                        None => return Err(Error::UnsupportedFeature("images of this dimension", version.reason())),
+                    };
+                    let atomic = atomic_views.contains(&index);
+                    let format = image_format(resource.ty, atomic);
+                    writeln!(
+                        out,
+                        "layout({}{}) uniform {}{}{} {};",
+                        binding(index),
+                        format,
+                        if globally_coherent { "coherent " } else { "" },
+                        if read.contains(&index) || atomic { "" } else { "writeonly " },
+                        image,
+                        resource_name
+                    ).unwrap();
+                    declared = true;
+                }
+                Declaration::ResourceRaw { .. } | Declaration::ResourceStructured { .. } => {
+                    if let Declaration::ResourceStructured { stride, .. } = *declaration {
+                        resource.kind = ResourceKind::Structured;
+                        resource.stride = stride / 4;
+                    } else {
+                        resource.kind = ResourceKind::Raw;
+                    }
+                    let binding = index + RESOURCE_BUFFER_BINDING;
+                    writeln!(out, "layout(std430, binding = {}) readonly buffer {}_block\n{{\n    uint {}[];\n}};", binding, resource_name, resource_name)
+                        .unwrap();
+                    declared = true;
+                }
+                Declaration::UnorderedAccessViewRaw { globally_coherent, .. } |
+                Declaration::UnorderedAccessViewStructured { globally_coherent, .. } => {
+                    if let Declaration::UnorderedAccessViewStructured { stride, .. } = *declaration {
+                        resource.kind = ResourceKind::Structured;
+                        resource.stride = stride / 4;
+                    } else {
+                        resource.kind = ResourceKind::Raw;
+                    }
+                    let coherent = if globally_coherent { "coherent " } else { "" };
+                    writeln!(out, "layout(std430, binding = {}) {}buffer {}_block\n{{\n    uint {}[];\n}};", index, coherent, resource_name, resource_name)
+                        .unwrap();
+                    declared = true;
+                }
+                Declaration::ThreadGroupSharedMemoryRaw { byte_count, .. } => {
+                    resource.kind = ResourceKind::Raw;
+                    writeln!(out, "shared uint {}[{}];", resource_name, byte_count / 4).unwrap();
+                    declared = true;
+                }
+                Declaration::ThreadGroupSharedMemoryStructured { stride, count, .. } => {
+                    resource.kind = ResourceKind::Structured;
+                    resource.stride = stride / 4;
+                    writeln!(out, "shared uint {}[{}];", resource_name, count as u64 * stride as u64 / 4).unwrap();
+                    declared = true;
+                }
+                _ => continue,
+            }
+
+            self.resources.insert((file, index), resource);
+        }
+
+        if declared {
+            writeln!(out).unwrap();
+        }
+        Ok(())
+    }
+}
+
+fn write(shader: &Shader, program: &Program, version: GlslVersion) -> Result<String, Error> {
+    let mut writer = Writer::new(shader, program, Glsl { version, samplers: BTreeSet::new() })?;
+    let mut out = String::new();
+
+    let program_type = shader.program_type;
+    let vertex = program_type == D3D10_SB_VERTEX_SHADER;
+    let pixel = program_type == D3D10_SB_PIXEL_SHADER;
+    let v430 = version == GlslVersion::V430;
+    let unsupported = |feature: &'static str| Error::UnsupportedFeature(feature, version.reason());
+
+    let stage = match program_type {
+        D3D10_SB_VERTEX_SHADER | D3D10_SB_PIXEL_SHADER => None,
+        D3D11_SB_COMPUTE_SHADER if v430 => None,
+        D3D11_SB_COMPUTE_SHADER => return Err(unsupported("compute shaders")),
+        D3D10_SB_GEOMETRY_SHADER => Some("geometry"),
+        D3D11_SB_HULL_SHADER => Some("hull"),
+        _ => Some("domain"),
+    };
+
+    writeln!(out, "{}", version.directive()).unwrap();
+    if version.is_es() {
+        writeln!(out, "precision highp float;").unwrap();
+        writeln!(out, "precision highp int;").unwrap();
+    }
+    writeln!(out).unwrap();
+    if let Some(stage) = stage {
+        writeln!(out, "// {} shaders are only translated partially\n", stage).unwrap();
+    }
+
+    for declaration in &program.decls {
+        match *declaration {
+            Declaration::ThreadGroup(x, y, z) => {
+                writeln!(out, "layout(local_size_x = {}, local_size_y = {}, local_size_z = {}) in;\n", x, y, z).unwrap()
+            }
+            Declaration::GlobalFlags(flags) if flags.contains(GlobalFlags::FORCE_EARLY_DEPTH_STENCIL) => {
+                if v430 {
+                    writeln!(out, "layout(early_fragment_tests) in;\n").unwrap()
+                } else {
+                    writeln!(out, "// early depth and stencil tests need GLSL 420\n").unwrap()
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let binding = |index: u32| {
+        if version.has_binding_layout() { format!(", binding = {}", index) } else { String::new() }
+    };
+    for &(slot, _, ref text) in &shader.constant_buffers {
+        writeln!(out, "layout(std140{}) {}", binding(slot), text).unwrap();
+    }
+    for declaration in &program.decls {
+        if let Declaration::ConstantBuffer { ref register, .. } = *declaration {
+            let slot = backend::resource_index(register);
+            if !shader.constant_buffers.iter().any(|&(buffer, _, _)| buffer == slot) {
+                let size = register.index.get(1).map_or(0, Index::offset);
+                writeln!(out, "layout(std140{}) uniform cb{}_block\n{{\n    vec4 cb{}[{}];\n}};\n", binding(slot), slot, slot, size).unwrap();
+            }
+        }
+    }
+
+    let mut resources = String::new();
+    writer.write_resources(&mut resources)?;
+
+    // varyings, system values are passed in built-in variables
+    let system_values = writer.system_values();
+    for &(_, _, builtin) in &system_values {
+        let available = match builtin {
+            "gl_SampleMaskIn[0]" | "gl_InvocationID" => v430,
+            "gl_PrimitiveID" => !version.is_es(),
+            _ => true,
+        };
+        if !available {
+            return Err(unsupported(builtin));
+        }
+    }
+
+    let interpolation = backend::interpolation(program);
+    let mut varyings = String::new();
+    for input in &shader.inputs {
+        if input.register == !0 {
+            continue;
+        }
+        if let Some((builtin, _, available)) = input.builtin_input(program_type, version) {
+            if !available {
+                return Err(unsupported(builtin));
+            }
+            continue;
+        }
+
+        let ty = vector(input.ty, backend::mask_components(input.mask).len());
+        let name = input.input_name(program_type);
+        if vertex {
+            writeln!(varyings, "layout(location = {}) in {} {};", input.register, ty, name).unwrap();
+            continue;
+        }
+
+        // integers can't be interpolated
+        let qualifier = match interpolation.get(&input.register) {
+            _ if input.ty != Type::Float => "flat ",
+            Some(&mode) if pixel => interpolation_qualifier(mode, version).map_err(unsupported)?,
+            _ => "",
+        };
+        writeln!(varyings, "{}in {} {};", qualifier, ty, name).unwrap();
+    }
+    for output in &shader.outputs {
+        if output.builtin_output(program_type) {
+            let feature = match output.system_register() {
+                Some("oMask") if !v430 => Some("gl_SampleMask"),
+                None if output.semantic_type == dr::SemanticName::ClipDistance && version.is_es() => Some("gl_ClipDistance"),
+                _ => None,
+            };
+            if let Some(feature) = feature.filter(|_| output.register == !0 || !pixel) {
+                return Err(unsupported(feature));
+            }
+            continue;
+        }
+
+        let ty = vector(output.ty, backend::mask_components(output.mask).len());
+        let name = output.output_name(program_type);
+        if pixel {
+            writeln!(varyings, "layout(location = {}) out {} {};", output.semantic_index, ty, name).unwrap();
+        } else {
+            let qualifier = if output.ty != Type::Float { "flat " } else { "" };
+            writeln!(varyings, "{}out {} {};", qualifier, ty, name).unwrap();
+        }
+    }
+
+    if let Some(vectors) = writer.immediate_constants() {
+        writeln!(resources, "const uvec4 icb[{}] = uvec4[{}](\n{}\n);\n", vectors.len(), vectors.len(), vectors.join(",\n")).unwrap();
+    }
+
+    // registers, shared with the subroutines if there are any
+    let mut registers = Vec::new();
+    for register in backend::declared_registers(program, OperandType::Input, &shader.inputs) {
+        registers.push(format!("vec4 v{} = vec4(0.0);", register));
+    }
+    for &(name, width, builtin) in &system_values {
+        if writer.global {
+            registers.push(format!("{} {};", vector(Type::Uint, width), name));
+        } else {
+            let ty = vector(Type::Uint, width);
+            registers.push(format!("{} {} = {}({});", ty, name, ty, builtin));
+        }
+    }
+    for register in backend::declared_registers(program, OperandType::Output, &shader.outputs) {
+        registers.push(format!("vec4 o{} = vec4(0.0);", register));
+    }
+    for output in shader.outputs.iter().filter(|output| output.register == !0) {
+        match output.system_register() {
+            Some("oMask") => registers.push("uint oMask = 0u;".to_owned()),
+            Some(name) => registers.push(format!("float {} = 0.0;", name)),
+            None => {}
+        }
+    }
+    for declaration in &program.decls {
+        match *declaration {
+            Declaration::Temps(_) => {
+                for (name, ty) in writer.temps() {
+                    let vector = vector(ty, 4);
+                    registers.push(format!("{} {} = {}({});", vector, name, vector, format_literal(0, ty)));
+                }
+            }
+            Declaration::IndexableTemp { index, count, .. } => registers.push(format!("vec4 x{}[{}];", index, count)),
+            _ => {}
+        }
+    }
+
+    let (main, subroutines) = backend::functions(program);
+    let mut functions = String::new();
+    let mut prototypes = String::new();
+    for &(label, ref instructions) in &subroutines {
+        writeln!(prototypes, "void label{}();", label).unwrap();
+        let body = writer.function(instructions.clone(), true)?;
+        writeln!(functions, "void label{}()\n{{\n{}}}\n", label, body).unwrap();
+    }
+
+    let mut body = writer.function(main.clone(), false)?;
+    // outputs are copied out on return
+    if program.instructions[main].last().is_none_or(|instruction| instruction.opcode != D3D10_SB_OPCODE_RET) {
+        writer.ret();
+        body.push_str(&writer.body);
+    }
+
+    let precision = if version.is_es() { "highp " } else { "" };
+    let layout = |index: u32| {
+        if version.has_binding_layout() { format!("layout(binding = {}) ", index) } else { String::new() }
+    };
+    for &(index, ref ty, ref name) in &writer.language.samplers {
+        writeln!(out, "{}uniform {}{} {};", layout(index), precision, ty, name).unwrap();
+    }
+    if !writer.language.samplers.is_empty() {
+        writeln!(out).unwrap();
+    }
+    out.push_str(&resources);
+    if !varyings.is_empty() {
+        writeln!(out, "{}", varyings).unwrap();
+    }
+
+    if writer.global {
+        for register in &registers {
+            writeln!(out, "{}", register).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+    if !prototypes.is_empty() {
+        writeln!(out, "{}", prototypes).unwrap();
+    }
+
+    writer.write_helpers(&HELPERS, &mut out);
+    out.push_str(&functions);
+
+    writeln!(out, "void main()\n{{").unwrap();
+    if !writer.global {
+        for register in &registers {
+            writeln!(out, "    {}", register).unwrap();
+        }
+        if !registers.is_empty() {
+            writeln!(out).unwrap();
+        }
+    }
+
+    let mut clip_distance = 0;
+    for input in &shader.inputs {
+        if input.register == !0 {
+            continue;
+        }
+        let components = backend::mask_components(input.mask);
+        let target = format!("v{}.{}", input.register, backend::swizzle(&components));
+        let value = match input.builtin_input(program_type, version) {
+            // D3D has the w of the vertex where GLSL has its reciprocal
+            Some(("gl_FragCoord", _, _)) => {
+                let position = "vec4(gl_FragCoord.xyz, 1.0 / gl_FragCoord.w)".to_owned();
+                match components.len() {
+                    4 => position,
+                    _ => format!("{}.{}", position, backend::swizzle(&components)),
+                }
+            }
+            Some(("gl_ClipDistance", _, _)) => {
+                for &c in &components {
+                    writeln!(out, "    v{}.{} = gl_ClipDistance[{}];", input.register, COMPONENTS[c as usize], clip_distance).unwrap();
+                    clip_distance += 1;
+                }
+                continue;
+            }
+            Some((builtin, ty, _)) => convert(builtin.to_owned(), ty, Type::Float, 1),
+            None => convert(input.input_name(program_type), input.ty, Type::Float, components.len()),
+        };
+        writeln!(out, "    {} = {};", target, value).unwrap();
+    }
+    if writer.global {
+        for &(name, width, builtin) in &system_values {
+            writeln!(out, "    {} = {}({});", name, vector(Type::Uint, width), builtin).unwrap();
+        }
+    }
+    if !shader.inputs.is_empty() || (writer.global && !system_values.is_empty()) {
+        writeln!(out).unwrap();
+    }
+
+    out.push_str(&body);
+    writeln!(out, "}}").unwrap();
+
+    Ok(out)
+}
+
+/// Translates a parsed shader into GLSL source for the version in the
+/// options.
+///
+/// Textures become samplers combined with the sampler state they are read
+/// with, named `<texture>_<sampler>`, with `_c` for comparisons, or after
+/// the texture alone for `texelFetch`. With GLSL 430 they are bound at the
+/// texture register, constant buffers at theirs, raw and structured views
+/// are storage buffers and typed unordered access views images with the 32
+/// bit format of their channel type. Instructions and declarations the
+/// version has no counterpart for end the parse with a `backend::Error`.
+pub struct GlslConsumer {
+    options: GlslOptions,
+    chunks: Chunks,
+    source: String,
+}
+
+impl GlslConsumer {
+    pub fn new(options: GlslOptions) -> Self {
+        GlslConsumer {
+            options,
+            chunks: Chunks::default(),
+            source: String::new(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Consumer for GlslConsumer {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        let version = self.options.version;
+        self.chunks.finalize(&mut self.source, |shader, program| write(shader, program, version))
+    }
+
+    fn consume_rdef(&mut self, rdef: &dr::RdefChunk) -> Action {
+        self.chunks.consume_rdef::<Glsl>(rdef)
+    }
+
+    fn consume_isgn(&mut self, isgn: &dr::IOsgnChunk) -> Action {
+        self.chunks.consume_isgn(isgn)
+    }
+
+    fn consume_osgn(&mut self, osgn: &dr::IOsgnChunk) -> Action {
+        self.chunks.consume_osgn(osgn)
+    }
+
+    fn consume_shex(&mut self, header: &ShexHeader) -> Action {
+        self.chunks.consume_shex(header)
+    }
+
+    fn consume_instruction(&mut self, offset: u32, instruction: SparseInstruction) -> Action {
+        self.chunks.consume_instruction(offset, instruction)
+    }
+}
+
+pub fn translate(bytes: &[u8], options: GlslOptions) -> Result<String, State> {
+    let mut consumer = GlslConsumer::new(options);
+
+    {
+        let mut parser = Parser::new(bytes, &mut consumer);
+        parser.parse()?;
+    }
+
+    Ok(consumer.source)
+}
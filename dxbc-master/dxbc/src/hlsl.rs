@@ -0,0 +1,1380 @@
+use binary::*;
+use backend::{self, Chunks, Constant, ConstantArray, Element, Error, Language, Layout, Resource, ResourceKind,
+              Shader, Writer, MAX_REGISTERS};
+use dr;
+use dr::rdef::{ShaderType, ShaderVariableClass};
+use dr::shex::{OperandType, ResourceDimension, ResourceReturnType, ShexHeader, SparseInstruction};
+use dr::builder::GlobalFlags;
+use d3d11tokenizedprogramformat::*;
+use disasm::COMPONENTS;
+use ir::{Components, Declaration, Index, Operand, Program, Register};
+use types::Type;
+
+use std::fmt::Write;
+
+fn scalar_name(ty: Type) -> &'static str {
+    match ty {
+        Type::Float => "float",
+        Type::Int => "int",
+        Type::Uint => "uint",
+        Type::Bool => "bool",
+    }
+}
+
+fn vector(ty: Type, width: usize) -> String {
+    if width == 1 {
+        scalar_name(ty).to_owned()
+    } else {
+        format!("{}{}", scalar_name(ty), width)
+    }
+}
+
+fn convert(expr: String, from: Type, to: Type, width: usize) -> String {
+    match (from, backend::storage(to)) {
+        (from, to) if from == to => expr,
+        (Type::Bool, Type::Float) => format!("asfloat(({}){})", vector(Type::Uint, width), expr),
+        (_, Type::Float) => format!("asfloat({})", expr),
+        (Type::Float, Type::Int) => format!("asint({})", expr),
+        (Type::Float, _) => format!("asuint({})", expr),
+        (_, to) => format!("({}){}", vector(to, width), backend::atom(&expr)),
+    }
+}
+
+fn format_literal(bits: u32, ty: Type) -> String {
+    match backend::storage(ty) {
+        Type::Float => {
+            let val = f32::from_bits(bits);
+
+            // denormals and non-finite values don't survive a trip through
+            // a decimal literal, keep their exact bit pattern instead
+            if val.is_normal() || val == 0.0 {
+                format!("{:?}", val)
+            } else {
+                format!("asfloat(0x{:08x}u)", bits)
+            }
+        }
+        Type::Int if bits == 0x8000_0000 => "asint(0x80000000u)".to_owned(),
+        Type::Int => format!("{}", bits as i32),
+        _ => format!("{}u", bits),
+    }
+}
+
+fn return_type_name(ty: u32) -> &'static str {
+    match ty {
+        x if x == ResourceReturnType::Unorm as u32 => "unorm float",
+        x if x == ResourceReturnType::Snorm as u32 => "snorm float",
+        x if x == ResourceReturnType::Sint as u32 => "int",
+        x if x == ResourceReturnType::Uint as u32 => "uint",
+        x if x == ResourceReturnType::Double as u32 => "double",
+        _ => "float",
+    }
+}
+
+fn texture_name(dimension: ResourceDimension) -> &'static str {
+    match dimension {
+        ResourceDimension::Buffer => "Buffer",
+        ResourceDimension::Texture1D => "Texture1D",
+        ResourceDimension::Texture1DArray => "Texture1DArray",
+        ResourceDimension::Texture2DMS => "Texture2DMS",
+        ResourceDimension::Texture2DArray => "Texture2DArray",
+        ResourceDimension::Texture2DMSArray => "Texture2DMSArray",
+        ResourceDimension::Texture3D => "Texture3D",
+        ResourceDimension::TextureCube => "TextureCube",
+        ResourceDimension::TextureCubeArray => "TextureCubeArray",
+        _ => "Texture2D",
+    }
+}
+
+fn interpolation_modifier(mode: u32) -> &'static str {
+    match mode {
+        D3D10_SB_INTERPOLATION_CONSTANT => "nointerpolation ",
+        D3D10_SB_INTERPOLATION_LINEAR_CENTROID => "centroid ",
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE => "noperspective ",
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_CENTROID => "noperspective centroid ",
+        D3D10_SB_INTERPOLATION_LINEAR_SAMPLE => "sample ",
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_SAMPLE => "noperspective sample ",
+        _ => "",
+    }
+}
+
+impl Element {
+    fn semantic(&self) -> String {
+        if self.semantic_index == 0 && self.name.to_uppercase().starts_with("SV_") {
+            self.name.clone()
+        } else {
+            format!("{}{}", self.name, self.semantic_index)
+        }
+    }
+
+    fn declaration(&self) -> String {
+        format!("{} {} : {};", vector(self.ty, backend::mask_components(self.mask).len()), self.member(), self.semantic())
+    }
+}
+
+// Registers taken by one array element of a type.
+fn type_registers(ty: &ShaderType) -> u32 {
+    match ty.class {
+        ShaderVariableClass::MatrixRows => ty.rows as u32,
+        ShaderVariableClass::MatrixColumns => ty.columns as u32,
+        ShaderVariableClass::Struct => {
+            let size = ty
+                .members
+                .iter()
+                .map(|member| member.offset.saturating_add(backend::type_size(&member.ty)))
+                .max()
+                .unwrap_or(0);
+            size.div_ceil(16)
+        }
+        _ => 1,
+    }
+}
+
+// Declaration of a constant buffer variable or struct member with its
+// array suffix, struct types are written to `structs` first.
+fn type_declaration(name: &str, ty: &ShaderType, structs: &mut Vec<String>) -> Option<String> {
+    let base = match ty.class {
+        ShaderVariableClass::Struct => {
+            let type_name = match ty.name {
+                Some(type_name) => backend::identifier(type_name),
+                None => format!("{}_t", backend::identifier(name)),
+            };
+
+            let mut text = format!("struct {}\n{{\n", type_name);
+            for member in &ty.members {
+                writeln!(text, "    {}", type_declaration(member.name, &member.ty, structs)?).unwrap();
+            }
+            text.push_str("};\n");
+            if !structs.contains(&text) {
+                structs.push(text);
+            }
+
+            type_name
+        }
+        ShaderVariableClass::Scalar => scalar_name(backend::variable_type(ty.ty)?).to_owned(),
+        ShaderVariableClass::Vector => vector(backend::variable_type(ty.ty)?, ty.columns as usize),
+        ShaderVariableClass::MatrixRows => {
+            format!("row_major {}{}x{}", scalar_name(backend::variable_type(ty.ty)?), ty.rows, ty.columns)
+        }
+        ShaderVariableClass::MatrixColumns => {
+            format!("column_major {}{}x{}", scalar_name(backend::variable_type(ty.ty)?), ty.rows, ty.columns)
+        }
+        _ => return None,
+    };
+
+    if ty.count > 0 {
+        Some(format!("{} {}[{}];", base, backend::identifier(name), ty.count))
+    } else {
+        Some(format!("{} {};", base, backend::identifier(name)))
+    }
+}
+
+// Elements of the variables of one buffer that are looked at, every register
+// of a well-formed buffer is visited a few times at most. Corrupt types with
+// overlapping members or huge counts stop there.
+const CONSTANT_BUDGET: u32 = MAX_REGISTERS * 16;
+
+fn add_constants(shader: &mut Shader, buffer: u32, base: String, ty: &ShaderType, offset: u32, budget: &mut u32) {
+    let stride = type_registers(ty).saturating_mul(16);
+    let elements = if stride == 0 { 1 } else { (ty.count as u32).max(1) };
+
+    for element in 0..elements {
+        let offset = offset.saturating_add(element.saturating_mul(stride));
+        let row = offset / 16;
+        let component = (offset % 16) / 4;
+        if row >= MAX_REGISTERS || *budget == 0 {
+            break;
+        }
+        *budget -= 1;
+
+        let base = if ty.count > 0 { format!("{}[{}]", base, element) } else { base.clone() };
+        let scalar = match ty.class {
+            ShaderVariableClass::Struct => {
+                for member in &ty.members {
+                    let name = format!("{}.{}", base, backend::identifier(member.name));
+                    add_constants(shader, buffer, name, &member.ty, offset.saturating_add(member.offset), budget);
+                }
+                continue;
+            }
+            _ => match backend::variable_type(ty.ty) {
+                Some(scalar) => scalar,
+                None => continue,
+            },
+        };
+
+        let mut add = |dword: u32, base: String, lane: Option<u8>| {
+            shader.constants.insert((buffer, dword), Constant { base, lane, ty: scalar });
+        };
+
+        // registers have four components, anything wider is corrupt
+        let rows = (ty.rows as u32).min(4);
+        let columns = (ty.columns as u32).min(4);
+        match ty.class {
+            ShaderVariableClass::Scalar => add(row * 4 + component, base, None),
+            ShaderVariableClass::Vector => {
+                for c in 0..columns {
+                    add(row * 4 + component + c, base.clone(), Some(c as u8));
+                }
+            }
+            ShaderVariableClass::MatrixRows => {
+                for r in 0..rows {
+                    for c in 0..columns {
+                        add((row + r) * 4 + c, format!("{}[{}]", base, r), Some(c as u8));
+                    }
+                }
+            }
+            ShaderVariableClass::MatrixColumns => {
+                for c in 0..columns {
+                    for r in 0..rows {
+                        add((row + c) * 4 + r, format!("{}[{}][{}]", base, r, c), None);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn add_constant_buffer(shader: &mut Shader, buffer: &dr::ConstantBuffer, slot: u32) {
+    let buffer_name = backend::identifier(buffer.name);
+    let mut text = format!("cbuffer {} : register(b{})\n{{\n", buffer_name, slot);
+    let mut budget = CONSTANT_BUDGET;
+
+    for variable in &buffer.variables {
+        let declaration = match type_declaration(variable.name, &variable.ty, &mut shader.structs) {
+            Some(declaration) => declaration,
+            None => continue,
+        };
+
+        let row = variable.start_offset / 16;
+        let component = (variable.start_offset % 16) / 4;
+        let packoffset = if component == 0 {
+            format!("c{}", row)
+        } else {
+            format!("c{}.{}", row, COMPONENTS[component as usize])
+        };
+        writeln!(text, "    {} : packoffset({});", declaration.trim_end_matches(';'), packoffset).unwrap();
+
+        let name = backend::identifier(variable.name);
+        let ty = &variable.ty;
+        let layout = match ty.class {
+            ShaderVariableClass::Scalar | ShaderVariableClass::Vector => Layout::Vector,
+            ShaderVariableClass::MatrixRows => Layout::RowMajor,
+            ShaderVariableClass::MatrixColumns => Layout::ColumnMajor,
+            _ => Layout::Vector,
+        };
+        let indexable = ty.count > 0 || layout != Layout::Vector;
+        if let (true, Some(scalar), false) = (indexable, backend::variable_type(ty.ty), ty.class == ShaderVariableClass::Struct) {
+            shader.arrays.push(ConstantArray {
+                buffer: slot,
+                name: name.clone(),
+                start: row,
+                registers: type_registers(ty),
+                count: ty.count as u32,
+                layout,
+                ty: scalar,
+            });
+        }
+
+        add_constants(shader, slot, name, ty, variable.start_offset, &mut budget);
+    }
+
+    text.push_str("};\n");
+    shader.constant_buffers.push((slot, buffer_name, text));
+}
+
+const HELPERS: [(&str, &str); 5] = [
+    ("dxbc_ubfe", "\
+uint dxbc_ubfe(uint width, uint offset, uint value)
+{
+    width &= 31;
+    offset &= 31;
+    if (width == 0)
+        return 0;
+    if (width + offset < 32)
+        return (value << (32 - width - offset)) >> (32 - width);
+    return value >> offset;
+}
+"),
+    ("dxbc_ibfe", "\
+int dxbc_ibfe(uint width, uint offset, int value)
+{
+    width &= 31;
+    offset &= 31;
+    if (width == 0)
+        return 0;
+    if (width + offset < 32)
+        return (value << (32 - width - offset)) >> (32 - width);
+    return value >> offset;
+}
+"),
+    ("dxbc_bfi", "\
+uint dxbc_bfi(uint width, uint offset, uint insert, uint base)
+{
+    uint mask = ((1u << (width & 31)) - 1) << (offset & 31);
+    return ((insert << (offset & 31)) & mask) | (base & ~mask);
+}
+"),
+    ("dxbc_umul_hi", "\
+uint dxbc_umul_hi(uint a, uint b)
+{
+    uint lo = (a & 0xffffu) * (b & 0xffffu);
+    uint mid0 = (a >> 16) * (b & 0xffffu);
+    uint mid1 = (a & 0xffffu) * (b >> 16);
+    uint carry = ((lo >> 16) + (mid0 & 0xffffu) + (mid1 & 0xffffu)) >> 16;
+    return (a >> 16) * (b >> 16) + (mid0 >> 16) + (mid1 >> 16) + carry;
+}
+"),
+    ("dxbc_imul_hi", "\
+int dxbc_imul_hi(int a, int b)
+{
+    uint hi = dxbc_umul_hi(asuint(a), asuint(b));
+    return asint(hi - (a < 0 ? asuint(b) : 0u) - (b < 0 ? asuint(a) : 0u));
+}
+"),
+];
+
+struct Hlsl;
+
+impl Language for Hlsl {
+    const LOOP: &'static str = "[loop] while (true) {";
+    const DISCARD: &'static str = "discard;";
+
+    fn vector(ty: Type, width: usize) -> String {
+        vector(ty, width)
+    }
+
+    fn convert(expr: String, from: Type, to: Type, width: usize) -> String {
+        convert(expr, from, to, width)
+    }
+
+    fn literal(bits: u32, ty: Type) -> String {
+        format_literal(bits, ty)
+    }
+
+    fn system_value(file: OperandType) -> Option<(&'static str, Type, usize, &'static str)> {
+        Some(match file {
+            OperandType::InputPrimitiveId => ("vPrim", Type::Uint, 1, "SV_PrimitiveID"),
+            OperandType::InputCoverageMask => ("vCoverage", Type::Uint, 1, "SV_Coverage"),
+            OperandType::InputThreadId => ("vThreadID", Type::Uint, 3, "SV_DispatchThreadID"),
+            OperandType::InputThreadGroupId => ("vThreadGroupID", Type::Uint, 3, "SV_GroupID"),
+            OperandType::InputThreadIdInGroup => ("vThreadIDInGroup", Type::Uint, 3, "SV_GroupThreadID"),
+            OperandType::InputThreadIdInGroupFlattened => ("vThreadIDInGroupFlattened", Type::Uint, 1, "SV_GroupIndex"),
+            OperandType::InputGsinstanceid => ("vGSInstanceID", Type::Uint, 1, "SV_GSInstanceID"),
+            OperandType::OutputDepth | OperandType::OutputDepthGreaterEqual | OperandType::OutputDepthLessEqual => {
+                ("oDepth", Type::Float, 1, "")
+            }
+            OperandType::OutputCoverageMask => ("oMask", Type::Uint, 1, ""),
+            _ => return None,
+        })
+    }
+
+    fn add_constant_buffer(shader: &mut Shader, buffer: &dr::ConstantBuffer, slot: u32) {
+        add_constant_buffer(shader, buffer, slot)
+    }
+
+    fn local(name: &str, ty: Type, width: usize, value: &str) -> String {
+        format!("{} {} = {};", vector(ty, width), name, value)
+    }
+
+    fn ret(writer: &mut Writer<Self>) -> Result<(), Error> {
+        writer.ret();
+        Ok(())
+    }
+
+    fn operation(writer: &mut Writer<Self>, i: usize) -> Result<(), Error> {
+        writer.operation(i)
+    }
+
+    const TYPED_TEMPS: bool = true;
+    const FOLD_EXPRESSIONS: bool = true;
+
+    fn matrix_multiply(vector: String, rows: &[String], width: usize) -> Option<String> {
+        Some(format!("mul({}, float{}x{}({}))", vector, rows.len(), width, rows.join(", ")))
+    }
+
+    fn splat(ty: Type, width: usize, value: String) -> String {
+        format!("({}){}", vector(ty, width), value)
+    }
+}
+
+impl<'a> Writer<'a, Hlsl> {
+    // Writes a comparison as the all ones or all zeros mask produced by the
+    // D3D comparison instructions.
+    fn compare(&mut self, i: usize, op: &str) -> Result<(), Error> {
+        self.componentwise(i, &[1, 2], &|args| format!("({} {} {}) ? 0xffffffffu : 0u", args[0], op, args[1]))
+    }
+
+    fn sample(&mut self, i: usize) -> Result<(), Error> {
+        let opcode = self.program.instructions[i].opcode;
+
+        let (address, offset, resource, sampler) = match opcode {
+            D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C => (1, Some(2), 3, 4),
+            _ => (1, None, 2, 3),
+        };
+        let extra = sampler + 1;
+
+        let texture = self.register(i, resource)?;
+        let sampler_register = self.register(i, sampler)?;
+        let dimension = self.dimension(texture);
+        let (coordinates, offsets) = backend::texture_coordinates(dimension);
+        let coordinate_components: Vec<u8> = (0..coordinates as u8).collect();
+
+        let mut args = vec![
+            self.resource_name(sampler_register),
+            self.src(self.operand(i, address)?, &coordinate_components, Type::Float),
+        ];
+        let float = |operand: usize| -> Result<String, Error> { Ok(self.src(self.operand(i, operand)?, &[0], Type::Float)) };
+
+        let channel = sampler_register.components.swizzle()[0] as usize & 3;
+        let gather = ["Gather", "GatherGreen", "GatherBlue", "GatherAlpha"][channel];
+        let gather_cmp = ["GatherCmp", "GatherCmpGreen", "GatherCmpBlue", "GatherCmpAlpha"][channel];
+
+        let (function, compare) = match opcode {
+            D3D10_SB_OPCODE_SAMPLE => ("Sample", false),
+            D3D10_SB_OPCODE_SAMPLE_B => {
+                args.push(float(extra)?);
+                ("SampleBias", false)
+            }
+            D3D10_SB_OPCODE_SAMPLE_L => {
+                args.push(float(extra)?);
+                ("SampleLevel", false)
+            }
+            D3D10_SB_OPCODE_SAMPLE_D => {
+                let gradients = match dimension {
+                    ResourceDimension::TextureCube | ResourceDimension::TextureCubeArray => 3,
+                    _ => offsets,
+                };
+                let components: Vec<u8> = (0..gradients as u8).collect();
+                args.push(self.src(self.operand(i, extra)?, &components, Type::Float));
+                args.push(self.src(self.operand(i, extra + 1)?, &components, Type::Float));
+                ("SampleGrad", false)
+            }
+            D3D10_SB_OPCODE_SAMPLE_C => {
+                args.push(float(extra)?);
+                ("SampleCmp", true)
+            }
+            D3D10_SB_OPCODE_SAMPLE_C_LZ => {
+                args.push(float(extra)?);
+                ("SampleCmpLevelZero", true)
+            }
+            D3D10_1_SB_OPCODE_GATHER4 | D3D11_SB_OPCODE_GATHER4_PO => (gather, false),
+            _ => {
+                args.push(float(extra)?);
+                (gather_cmp, false)
+            }
+        };
+
+        let offset_components: Vec<u8> = (0..offsets as u8).collect();
+        let offset = match offset {
+            Some(offset) => Some(self.src(self.operand(i, offset)?, &offset_components, Type::Int)),
+            None => self.immediate_offset(i, offsets),
+        };
+        args.extend(offset);
+
+        let value = format!("{}.{}({})", self.resource_name(texture), function, args.join(", "));
+        if compare {
+            if let Some(dst) = self.dst(self.operand(i, 0)?) {
+                self.assign(i, &dst, Type::Float, value);
+            }
+            Ok(())
+        } else {
+            self.assign_resource(i, resource, value)
+        }
+    }
+
+    fn load(&mut self, i: usize) -> Result<(), Error> {
+        let texture = self.register(i, 2)?;
+        let dimension = self.dimension(texture);
+        let (coordinates, offsets) = backend::texture_coordinates(dimension);
+
+        // the mip level is always in the last address component
+        let mut components: Vec<u8> = (0..coordinates as u8).collect();
+        let multisampled = self.program.instructions[i].opcode == D3D10_SB_OPCODE_LD_MS;
+        if dimension != ResourceDimension::Buffer && !multisampled {
+            components.push(3);
+        }
+
+        let mut args = vec![self.src(self.operand(i, 1)?, &components, Type::Int)];
+        if multisampled {
+            args.push(self.src(self.operand(i, 3)?, &[0], Type::Int));
+        }
+        args.extend(self.immediate_offset(i, offsets));
+
+        let value = format!("{}.Load({})", self.resource_name(texture), args.join(", "));
+        self.assign_resource(i, 2, value)
+    }
+
+    fn typed_address(&self, uav: &Register, address: &Operand) -> String {
+        let (coordinates, _) = backend::texture_coordinates(self.dimension(uav));
+        let components: Vec<u8> = (0..coordinates as u8).collect();
+        format!("{}[{}]", self.resource_name(uav), self.src(address, &components, Type::Uint))
+    }
+
+    // Element of structured or raw memory holding a dword.
+    fn memory(&self, resource: &Register, index: Option<&Operand>, offset: &Operand, component: u8) -> String {
+        let name = self.resource_name(resource);
+        let dword = match *offset {
+            Operand::Imm32(ref values) => format!("{}", values.first().cloned().unwrap_or(0) / 4 + component as u32),
+            _ => {
+                let offset = self.src(offset, &[0], Type::Uint);
+                match component {
+                    0 => format!("({} >> 2)", offset),
+                    c => format!("({} >> 2) + {}", offset, c),
+                }
+            }
+        };
+
+        match (resource.file, index) {
+            (OperandType::ThreadGroupSharedMemory, Some(index)) => format!("{}[{}][{}]", name, self.src(index, &[0], Type::Uint), dword),
+            (OperandType::ThreadGroupSharedMemory, None) => format!("{}[{}]", name, dword),
+            (_, Some(index)) => format!("{}[{}].data[{}]", name, self.src(index, &[0], Type::Uint), dword),
+            (_, None) => format!("{}.Load({})", name, dword),
+        }
+    }
+
+    fn load_memory(&mut self, i: usize) -> Result<(), Error> {
+        let structured = self.program.instructions[i].opcode == D3D11_SB_OPCODE_LD_STRUCTURED;
+        let resource = if structured { 3 } else { 2 };
+        let register = self.register(i, resource)?;
+
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let picks = self.resource_picks(self.operand(i, resource)?, &dst.written());
+
+            let expr = if !structured && register.file != OperandType::ThreadGroupSharedMemory {
+                let address = self.src(self.operand(i, 1)?, &[0], Type::Uint);
+                let count = picks.iter().max().map_or(1, |&pick| pick + 1);
+                let load = if count == 1 { "Load".to_owned() } else { format!("Load{}", count) };
+                format!("{}.{}({}).{}", self.resource_name(register), load, address, backend::swizzle(&picks))
+            } else {
+                let index = if structured { Some(self.operand(i, 1)?) } else { None };
+                let offset = self.operand(i, if structured { 2 } else { 1 })?;
+                let parts: Vec<String> = picks.iter().map(|&c| self.memory(register, index, offset, c)).collect();
+                if parts.len() == 1 {
+                    parts[0].clone()
+                } else {
+                    format!("uint{}({})", parts.len(), parts.join(", "))
+                }
+            };
+            self.assign(i, &dst, Type::Uint, expr);
+        }
+
+        Ok(())
+    }
+
+    fn store_memory(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 0)?;
+        let components = backend::mask_components(register.components.mask());
+        let structured = self.program.instructions[i].opcode == D3D11_SB_OPCODE_STORE_STRUCTURED;
+        let value = self.operand(i, if structured { 3 } else { 2 })?;
+
+        if !structured && register.file != OperandType::ThreadGroupSharedMemory {
+            let store = if components.len() == 1 { "Store".to_owned() } else { format!("Store{}", components.len()) };
+            let text = format!(
+                "{}.{}({}, {});",
+                self.resource_name(register),
+                store,
+                self.src(self.operand(i, 1)?, &[0], Type::Uint),
+                self.src(value, &components, Type::Uint)
+            );
+            self.line(&text);
+            return Ok(());
+        }
+
+        let index = if structured { Some(self.operand(i, 1)?) } else { None };
+        let offset = self.operand(i, if structured { 2 } else { 1 })?;
+        for (n, &c) in components.iter().enumerate() {
+            let text = format!("{} = {};", self.memory(register, index, offset, n as u8), self.src(value, &[c], Type::Uint));
+            self.line(&text);
+        }
+
+        Ok(())
+    }
+
+    fn store_typed(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 0)?;
+        let ty = self.source_type(i, 2);
+        let width = self
+            .shader
+            .binding(register.file, backend::resource_index(register))
+            .map_or(4, |binding| binding.components() as u8);
+        let components: Vec<u8> = (0..width).collect();
+
+        let text = format!("{} = {};", self.typed_address(register, self.operand(i, 1)?), self.src(self.operand(i, 2)?, &components, ty));
+        self.line(&text);
+        Ok(())
+    }
+
+    fn atomic(&mut self, i: usize) -> Result<(), Error> {
+        let opcode = self.program.instructions[i].opcode;
+        let immediate = opcode >= D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC;
+        let first = if immediate { 1 } else { 0 };
+        let register = self.register(i, first)?;
+
+        if opcode == D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC || opcode == D3D11_SB_OPCODE_IMM_ATOMIC_CONSUME {
+            let function = if opcode == D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC { "IncrementCounter" } else { "DecrementCounter" };
+            if let Some(dst) = self.dst(self.operand(i, 0)?) {
+                let expr = format!("{}.{}()", self.resource_name(register), function);
+                self.assign(i, &dst, Type::Uint, expr);
+            }
+            return Ok(());
+        }
+
+        let function = match opcode {
+            D3D11_SB_OPCODE_ATOMIC_AND | D3D11_SB_OPCODE_IMM_ATOMIC_AND => "InterlockedAnd",
+            D3D11_SB_OPCODE_ATOMIC_OR | D3D11_SB_OPCODE_IMM_ATOMIC_OR => "InterlockedOr",
+            D3D11_SB_OPCODE_ATOMIC_XOR | D3D11_SB_OPCODE_IMM_ATOMIC_XOR => "InterlockedXor",
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE => "InterlockedCompareStore",
+            D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => "InterlockedCompareExchange",
+            D3D11_SB_OPCODE_IMM_ATOMIC_EXCH => "InterlockedExchange",
+            D3D11_SB_OPCODE_ATOMIC_IADD | D3D11_SB_OPCODE_IMM_ATOMIC_IADD => "InterlockedAdd",
+            D3D11_SB_OPCODE_ATOMIC_IMAX | D3D11_SB_OPCODE_IMM_ATOMIC_IMAX |
+            D3D11_SB_OPCODE_ATOMIC_UMAX | D3D11_SB_OPCODE_IMM_ATOMIC_UMAX => "InterlockedMax",
+            _ => "InterlockedMin",
+        };
+
+        let values = match opcode {
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => 2,
+            _ => 1,
+        };
+        let mut args: Vec<String> = self.sources(i, &((first + 2)..(first + 2 + values)).collect::<Vec<_>>(), &[0])?;
+        if immediate {
+            args.push("original".to_owned());
+        }
+
+        let address = self.operand(i, first + 1)?;
+        let kind = self.resource(register).map_or(ResourceKind::Raw, |resource| resource.kind);
+        let call = match (register.file, kind) {
+            (OperandType::UnorderedAccessView, ResourceKind::Raw) => {
+                let address = self.src(address, &[0], Type::Uint);
+                format!("{}.{}({}, {});", self.resource_name(register), function, address, args.join(", "))
+            }
+            (OperandType::UnorderedAccessView, ResourceKind::Typed) => {
+                format!("{}({}, {});", function, self.typed_address(register, address), args.join(", "))
+            }
+            _ => {
+                let structured = register.file != OperandType::ThreadGroupSharedMemory || kind == ResourceKind::Structured;
+                let target = if structured {
+                    let offset = match *address {
+                        Operand::Register(ref address) => {
+                            let mut offset = address.clone();
+                            offset.components = Components::Select(address.components.swizzle()[1]);
+                            Operand::Register(offset)
+                        }
+                        Operand::Imm32(ref values) => Operand::Imm32(vec![values.get(1).cloned().unwrap_or(0)]),
+                        ref other => other.clone(),
+                    };
+                    self.memory(register, Some(address), &offset, 0)
+                } else {
+                    self.memory(register, None, address, 0)
+                };
+                format!("{}({}, {});", function, target, args.join(", "))
+            }
+        };
+
+        if immediate {
+            self.line("{");
+            self.indent += 1;
+            self.line("uint original;");
+            self.line(&call);
+            if let Some(dst) = self.dst(self.operand(i, 0)?) {
+                self.assign(i, &dst, Type::Uint, "original".to_owned());
+            }
+            self.indent -= 1;
+            self.line("}");
+        } else {
+            self.line(&call);
+        }
+
+        Ok(())
+    }
+
+    fn resinfo(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 2)?;
+        let dimension = self.dimension(register);
+        let uav = register.file == OperandType::UnorderedAccessView;
+        let multisampled = dimension == ResourceDimension::Texture2DMS || dimension == ResourceDimension::Texture2DMSArray;
+
+        let (sizes, elements): (&[&str], bool) = match dimension {
+            ResourceDimension::Texture1D => (&["width"], false),
+            ResourceDimension::Texture1DArray => (&["width"], true),
+            ResourceDimension::Texture2D | ResourceDimension::TextureCube | ResourceDimension::Texture2DMS => (&["width", "height"], false),
+            ResourceDimension::Texture3D => (&["width", "height", "depth"], false),
+            _ => (&["width", "height"], true),
+        };
+
+        let mut outputs: Vec<&str> = sizes.to_vec();
+        if elements {
+            outputs.push("elements");
+        }
+        let mut args = Vec::new();
+        if !uav && !multisampled {
+            args.push(self.src(self.operand(i, 1)?, &[0], Type::Uint));
+            outputs.push("levels");
+        } else if multisampled {
+            outputs.push("samples");
+        }
+        args.extend(outputs.iter().map(|&output| output.to_owned()));
+
+        let controls = self.program.instructions[i].controls;
+        let rcp = DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(controls) == D3D10_SB_RESINFO_INSTRUCTION_RETURN_RCPFLOAT;
+        let uint = DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(controls) == D3D10_SB_RESINFO_INSTRUCTION_RETURN_UINT;
+        let mut parts: Vec<String> = sizes
+            .iter()
+            .map(|&size| if rcp { format!("1.0 / {}", size) } else { size.to_owned() })
+            .collect();
+        if elements {
+            parts.push("elements".to_owned());
+        }
+        while parts.len() < 3 {
+            parts.push("0".to_owned());
+        }
+        parts.push(if !uav && !multisampled { "levels" } else { "1" }.to_owned());
+
+        let ty = if uint { Type::Uint } else { Type::Float };
+        self.line("{");
+        self.indent += 1;
+        let text = format!("uint {};", outputs.join(", "));
+        self.line(&text);
+        let text = format!("{}.GetDimensions({});", self.resource_name(register), args.join(", "));
+        self.line(&text);
+        let value = format!("{}({})", vector(ty, 4), parts.join(", "));
+        self.assign_resource(i, 2, value)?;
+        self.indent -= 1;
+        self.line("}");
+        Ok(())
+    }
+
+    fn bufinfo(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let structured = self.resource(register).is_some_and(|resource| resource.kind == ResourceKind::Structured);
+
+        self.line("{");
+        self.indent += 1;
+        if structured {
+            self.line("uint count, stride;");
+            let text = format!("{}.GetDimensions(count, stride);", self.resource_name(register));
+            self.line(&text);
+        } else {
+            self.line("uint count;");
+            let text = format!("{}.GetDimensions(count);", self.resource_name(register));
+            self.line(&text);
+        }
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            self.assign(i, &dst, Type::Uint, "count".to_owned());
+        }
+        self.indent -= 1;
+        self.line("}");
+        Ok(())
+    }
+
+    fn sample_info(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let ty = self.result_type(i, 0);
+
+        if register.file == OperandType::Rasterizer {
+            let value = format!("{}(GetRenderTargetSampleCount(), 0, 0, 0)", vector(ty, 4));
+            return self.assign_resource(i, 1, value);
+        }
+
+        self.line("{");
+        self.indent += 1;
+        let elements = if self.dimension(register) == ResourceDimension::Texture2DMSArray { "elements, " } else { "" };
+        let text = format!("uint width, height, {}samples;", elements);
+        self.line(&text);
+        let text = format!("{}.GetDimensions(width, height, {}samples);", self.resource_name(register), elements);
+        self.line(&text);
+        let value = format!("{}(samples, 0, 0, 0)", vector(ty, 4));
+        self.assign_resource(i, 1, value)?;
+        self.indent -= 1;
+        self.line("}");
+        Ok(())
+    }
+
+    fn sample_position(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let index = self.src(self.operand(i, 2)?, &[0], Type::Int);
+
+        let position = if register.file == OperandType::Rasterizer {
+            format!("GetRenderTargetSamplePosition({})", index)
+        } else {
+            format!("{}.GetSamplePosition({})", self.resource_name(register), index)
+        };
+        self.assign_resource(i, 1, format!("float4({}, 0, 0)", position))
+    }
+
+    fn lod(&mut self, i: usize) -> Result<(), Error> {
+        let texture = self.register(i, 2)?;
+        let (coordinates, _) = backend::texture_coordinates(self.dimension(texture));
+        let components: Vec<u8> = (0..coordinates as u8).collect();
+        let args = format!(
+            "{}, {}",
+            self.resource_name(self.register(i, 3)?),
+            self.src(self.operand(i, 1)?, &components, Type::Float)
+        );
+
+        let name = self.resource_name(texture);
+        let value = format!("float4({}.CalculateLevelOfDetail({}), {}.CalculateLevelOfDetailUnclamped({}), 0, 0)", name, args, name, args);
+        self.assign_resource(i, 2, value)
+    }
+
+    // Interpolates an input again, the evaluation intrinsics only take
+    // input attributes so the components come from the signature elements.
+    fn evaluate(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let (function, extra) = match self.program.instructions[i].opcode {
+            D3D11_SB_OPCODE_EVAL_CENTROID => ("EvaluateAttributeAtCentroid", None),
+            D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX => ("EvaluateAttributeAtSample", Some(self.src(self.operand(i, 2)?, &[0], Type::Uint))),
+            _ => ("EvaluateAttributeSnapped", Some(self.src(self.operand(i, 2)?, &[0, 1], Type::Int))),
+        };
+
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let swizzle_of = register.components.swizzle();
+            let index = backend::resource_index(register);
+            let parts: Vec<String> = dst
+                .written()
+                .iter()
+                .map(|&c| {
+                    let pick = swizzle_of[c as usize] & 3;
+                    let element = self.shader.inputs.iter().find(|element| element.register == index && element.mask & (1 << pick) != 0);
+                    match element {
+                        Some(element) => {
+                            let lane = pick - element.mask.trailing_zeros() as u8;
+                            let mut args = vec![format!("input.{}", element.member())];
+                            args.extend(extra.clone());
+                            let value = format!("{}({})", function, args.join(", "));
+                            let value = if backend::mask_components(element.mask).len() == 1 {
+                                value
+                            } else {
+                                format!("{}.{}", value, COMPONENTS[lane as usize])
+                            };
+                            convert(value, element.ty, Type::Float, 1)
+                        }
+                        None => "0.0".to_owned(),
+                    }
+                })
+                .collect();
+
+            let expr = if parts.len() == 1 {
+                parts[0].clone()
+            } else {
+                format!("float{}({})", parts.len(), parts.join(", "))
+            };
+            self.assign(i, &dst, Type::Float, expr);
+        }
+
+        Ok(())
+    }
+
+    fn sync(&mut self, i: usize) {
+        let flags = self.program.instructions[i].controls;
+        let barrier = if flags & D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GLOBAL != 0 {
+            if flags & D3D11_SB_SYNC_THREAD_GROUP_SHARED_MEMORY != 0 { "AllMemoryBarrier" } else { "DeviceMemoryBarrier" }
+        } else {
+            "GroupMemoryBarrier"
+        };
+
+        if flags & D3D11_SB_SYNC_THREADS_IN_GROUP != 0 {
+            let text = format!("{}WithGroupSync();", barrier);
+            self.line(&text);
+        } else {
+            let text = format!("{}();", barrier);
+            self.line(&text);
+        }
+    }
+
+    fn write_output_copies(&mut self) {
+        for n in 0..self.shader.outputs.len() {
+            let output = &self.shader.outputs[n];
+            let components = backend::mask_components(output.mask);
+
+            let (value, from) = match output.system_register() {
+                Some(name) if output.register == !0 => {
+                    (name.to_owned(), if name == "oMask" { Type::Uint } else { Type::Float })
+                }
+                _ if output.register == !0 => continue,
+                _ => (format!("o{}.{}", output.register, backend::swizzle(&components)), Type::Float),
+            };
+
+            let text = format!("output.{} = {};", output.member(), convert(value, from, output.ty, components.len()));
+            self.line(&text);
+        }
+    }
+
+    fn ret(&mut self) {
+        if self.in_subroutine || self.shader.outputs.is_empty() {
+            self.line("return;");
+        } else {
+            self.line("Output output;");
+            self.write_output_copies();
+            self.line("return output;");
+        }
+    }
+
+    fn operation(&mut self, i: usize) -> Result<(), Error> {
+        match self.program.instructions[i].opcode {
+            D3D10_SB_OPCODE_ADD | D3D10_SB_OPCODE_IADD => self.binary(i, "+"),
+            D3D10_SB_OPCODE_MUL => self.binary(i, "*"),
+            D3D10_SB_OPCODE_DIV => self.binary(i, "/"),
+            D3D10_SB_OPCODE_AND => self.binary(i, "&"),
+            D3D10_SB_OPCODE_OR => self.binary(i, "|"),
+            D3D10_SB_OPCODE_XOR => self.binary(i, "^"),
+            D3D10_SB_OPCODE_ISHL => self.binary(i, "<<"),
+            D3D10_SB_OPCODE_ISHR | D3D10_SB_OPCODE_USHR => self.binary(i, ">>"),
+            D3D10_SB_OPCODE_MAD => self.call(i, "mad"),
+            D3D10_SB_OPCODE_IMAD | D3D10_SB_OPCODE_UMAD => {
+                self.componentwise(i, &[1, 2, 3], &|args| format!("{} * {} + {}", args[0], args[1], args[2]))
+            }
+            D3D10_SB_OPCODE_MOV => self.componentwise(i, &[1], &|args| args[0].clone()),
+            D3D10_SB_OPCODE_MOVC => {
+                self.componentwise(i, &[1, 2, 3], &|args| format!("({} != 0u) ? {} : {}", args[0], args[1], args[2]))
+            }
+            D3D10_SB_OPCODE_NOT => self.componentwise(i, &[1], &|args| format!("~{}", args[0])),
+            D3D10_SB_OPCODE_INEG => self.componentwise(i, &[1], &|args| backend::negate(&args[0])),
+            D3D10_SB_OPCODE_ITOF | D3D10_SB_OPCODE_UTOF | D3D10_SB_OPCODE_FTOI | D3D10_SB_OPCODE_FTOU => {
+                if let Some(dst) = self.dst(self.operand(i, 0)?) {
+                    let ty = self.result_type(i, 0);
+                    let value = self.src(self.operand(i, 1)?, &dst.written(), self.source_type(i, 1));
+                    self.assign(i, &dst, ty, format!("({}){}", vector(ty, dst.width()), value));
+                }
+                Ok(())
+            }
+            D3D10_SB_OPCODE_MIN | D3D10_SB_OPCODE_IMIN | D3D10_SB_OPCODE_UMIN => self.call(i, "min"),
+            D3D10_SB_OPCODE_MAX | D3D10_SB_OPCODE_IMAX | D3D10_SB_OPCODE_UMAX => self.call(i, "max"),
+            D3D10_SB_OPCODE_DP2 => self.dot(i, 2),
+            D3D10_SB_OPCODE_DP3 => self.dot(i, 3),
+            D3D10_SB_OPCODE_DP4 => self.dot(i, 4),
+            D3D10_SB_OPCODE_EXP => self.call(i, "exp2"),
+            D3D10_SB_OPCODE_LOG => self.call(i, "log2"),
+            D3D10_SB_OPCODE_FRC => self.call(i, "frac"),
+            D3D10_SB_OPCODE_SQRT => self.call(i, "sqrt"),
+            D3D10_SB_OPCODE_RSQ => self.call(i, "rsqrt"),
+            D3D11_SB_OPCODE_RCP => self.call(i, "rcp"),
+            D3D10_SB_OPCODE_ROUND_NE => self.call(i, "round"),
+            D3D10_SB_OPCODE_ROUND_NI => self.call(i, "floor"),
+            D3D10_SB_OPCODE_ROUND_PI => self.call(i, "ceil"),
+            D3D10_SB_OPCODE_ROUND_Z => self.call(i, "trunc"),
+            D3D10_SB_OPCODE_DERIV_RTX => self.call(i, "ddx"),
+            D3D10_SB_OPCODE_DERIV_RTY => self.call(i, "ddy"),
+            D3D11_SB_OPCODE_DERIV_RTX_COARSE => self.call(i, "ddx_coarse"),
+            D3D11_SB_OPCODE_DERIV_RTX_FINE => self.call(i, "ddx_fine"),
+            D3D11_SB_OPCODE_DERIV_RTY_COARSE => self.call(i, "ddy_coarse"),
+            D3D11_SB_OPCODE_DERIV_RTY_FINE => self.call(i, "ddy_fine"),
+            D3D11_SB_OPCODE_COUNTBITS => self.call(i, "countbits"),
+            D3D11_SB_OPCODE_BFREV => self.call(i, "reversebits"),
+            D3D11_SB_OPCODE_FIRSTBIT_LO => self.call(i, "firstbitlow"),
+            D3D11_SB_OPCODE_F32TOF16 => self.call(i, "f32tof16"),
+            D3D11_SB_OPCODE_F16TOF32 => self.call(i, "f16tof32"),
+            // D3D counts the bit position from the most significant end
+            D3D11_SB_OPCODE_FIRSTBIT_HI | D3D11_SB_OPCODE_FIRSTBIT_SHI => self.scalar(i, &[1], &|args| {
+                format!("(firstbithigh({}) == 0xffffffffu) ? 0xffffffffu : 31u - firstbithigh({})", args[0], args[0])
+            }),
+            D3D10_SB_OPCODE_EQ | D3D10_SB_OPCODE_IEQ => self.compare(i, "=="),
+            D3D10_SB_OPCODE_NE | D3D10_SB_OPCODE_INE => self.compare(i, "!="),
+            D3D10_SB_OPCODE_LT | D3D10_SB_OPCODE_ILT | D3D10_SB_OPCODE_ULT => self.compare(i, "<"),
+            D3D10_SB_OPCODE_GE | D3D10_SB_OPCODE_IGE | D3D10_SB_OPCODE_UGE => self.compare(i, ">="),
+            D3D11_SB_OPCODE_UBFE => {
+                self.helpers.insert("dxbc_ubfe");
+                self.scalar(i, &[1, 2, 3], &|args| format!("dxbc_ubfe({})", args.join(", ")))
+            }
+            D3D11_SB_OPCODE_IBFE => {
+                self.helpers.insert("dxbc_ibfe");
+                self.scalar(i, &[1, 2, 3], &|args| format!("dxbc_ibfe({})", args.join(", ")))
+            }
+            D3D11_SB_OPCODE_BFI => {
+                self.helpers.insert("dxbc_bfi");
+                self.scalar(i, &[1, 2, 3, 4], &|args| format!("dxbc_bfi({})", args.join(", ")))
+            }
+            D3D10_SB_OPCODE_SINCOS => self.multiple(i, &[
+                &|args| format!("sin({})", args[0]),
+                &|args| format!("cos({})", args[0]),
+            ], 1, false),
+            D3D10_SB_OPCODE_UDIV => self.multiple(i, &[
+                &|args| format!("{} / {}", args[0], args[1]),
+                &|args| format!("{} % {}", args[0], args[1]),
+            ], 2, false),
+            D3D11_SB_OPCODE_UADDC => self.multiple(i, &[
+                &|args| format!("{} + {}", args[0], args[1]),
+                &|args| format!("({} + {} < {}) ? 1u : 0u", args[0], args[1], args[0]),
+            ], 2, false),
+            D3D11_SB_OPCODE_USUBB => self.multiple(i, &[
+                &|args| format!("{} - {}", args[0], args[1]),
+                &|args| format!("({} < {}) ? 1u : 0u", args[0], args[1]),
+            ], 2, false),
+            D3D11_SB_OPCODE_SWAPC => self.multiple(i, &[
+                &|args| format!("({} != 0u) ? {} : {}", args[0], args[2], args[1]),
+                &|args| format!("({} != 0u) ? {} : {}", args[0], args[1], args[2]),
+            ], 3, false),
+            D3D10_SB_OPCODE_IMUL => {
+                if self.dst(self.operand(i, 0)?).is_some() {
+                    self.helpers.insert("dxbc_umul_hi");
+                    self.helpers.insert("dxbc_imul_hi");
+                }
+                self.multiple(i, &[
+                    &|args| format!("dxbc_imul_hi({}, {})", args[0], args[1]),
+                    &|args| format!("{} * {}", args[0], args[1]),
+                ], 2, true)
+            }
+            D3D10_SB_OPCODE_UMUL => {
+                if self.dst(self.operand(i, 0)?).is_some() {
+                    self.helpers.insert("dxbc_umul_hi");
+                }
+                self.multiple(i, &[
+                    &|args| format!("dxbc_umul_hi({}, {})", args[0], args[1]),
+                    &|args| format!("{} * {}", args[0], args[1]),
+                ], 2, true)
+            }
+            D3D10_SB_OPCODE_SAMPLE | D3D10_SB_OPCODE_SAMPLE_B | D3D10_SB_OPCODE_SAMPLE_L | D3D10_SB_OPCODE_SAMPLE_D |
+            D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ | D3D10_1_SB_OPCODE_GATHER4 |
+            D3D11_SB_OPCODE_GATHER4_C | D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C => self.sample(i),
+            D3D10_SB_OPCODE_LD | D3D10_SB_OPCODE_LD_MS => self.load(i),
+            D3D11_SB_OPCODE_LD_UAV_TYPED => {
+                let value = self.typed_address(self.register(i, 2)?, self.operand(i, 1)?);
+                self.assign_resource(i, 2, value)
+            }
+            D3D11_SB_OPCODE_STORE_UAV_TYPED => self.store_typed(i),
+            D3D11_SB_OPCODE_LD_RAW | D3D11_SB_OPCODE_LD_STRUCTURED => self.load_memory(i),
+            D3D11_SB_OPCODE_STORE_RAW | D3D11_SB_OPCODE_STORE_STRUCTURED => self.store_memory(i),
+            D3D11_SB_OPCODE_ATOMIC_AND..=D3D11_SB_OPCODE_IMM_ATOMIC_UMIN => self.atomic(i),
+            D3D11_SB_OPCODE_SYNC => {
+                self.sync(i);
+                Ok(())
+            }
+            D3D10_SB_OPCODE_RESINFO => self.resinfo(i),
+            D3D11_SB_OPCODE_BUFINFO => self.bufinfo(i),
+            D3D10_1_SB_OPCODE_SAMPLE_INFO => self.sample_info(i),
+            D3D10_1_SB_OPCODE_SAMPLE_POS => self.sample_position(i),
+            D3D10_1_SB_OPCODE_LOD => self.lod(i),
+            D3D11_SB_OPCODE_EVAL_CENTROID | D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX | D3D11_SB_OPCODE_EVAL_SNAPPED => self.evaluate(i),
+            D3D11_SB_OPCODE_ABORT => {
+                self.line("abort();");
+                Ok(())
+            }
+            _ => Err(self.unsupported(i, "")),
+        }
+    }
+
+    fn write_resources(&mut self, out: &mut String) {
+        let shader = self.shader;
+        let program = self.program;
+        let components = |file: OperandType, index: u32| -> usize {
+            shader.binding(file, index).map_or(4, |binding| binding.components())
+        };
+
+        let mut declared = false;
+        for declaration in &program.decls {
+            let register = match declaration.register() {
+                Some(register) => register,
+                None => continue,
+            };
+            let index = backend::resource_index(register);
+            let file = register.file;
+            let name = shader.resource_name(file, index);
+
+            let (text, dimension, kind, stride) = match *declaration {
+                Declaration::Sampler { mode, .. } => {
+                    let ty = if mode == D3D10_SB_SAMPLER_MODE_COMPARISON { "SamplerComparisonState" } else { "SamplerState" };
+                    (format!("{} {} : register(s{});", ty, name, index), ResourceDimension::Unknown, ResourceKind::Typed, 1)
+                }
+                Declaration::Resource { dimension, sample_count, return_type, .. } => {
+                    let dimension = ResourceDimension::from_word(dimension).unwrap_or(ResourceDimension::Unknown);
+                    let element = format!("{}{}", return_type_name(return_type[0]), components(file, index));
+                    let template = match dimension {
+                        ResourceDimension::Texture2DMS | ResourceDimension::Texture2DMSArray if sample_count > 0 => {
+                            format!("{}, {}", element, sample_count)
+                        }
+                        _ => element,
+                    };
+                    let text = format!("{}<{}> {} : register(t{});", texture_name(dimension), template, name, index);
+                    (text, dimension, ResourceKind::Typed, 1)
+                }
+                Declaration::UnorderedAccessViewTyped { dimension, return_type, globally_coherent, .. } => {
+                    let dimension = ResourceDimension::from_word(dimension).unwrap_or(ResourceDimension::Unknown);
+                    let text = format!(
+                        "{}RW{}<{}{}> {} : register(u{});",
+                        if globally_coherent { "globallycoherent " } else { "" },
+                        texture_name(dimension),
+                        return_type_name(return_type[0]),
+                        components(file, index),
+                        name,
+                        index
+                    );
+                    (text, dimension, ResourceKind::Typed, 1)
+                }
+                Declaration::ResourceRaw { .. } => {
+                    let text = format!("ByteAddressBuffer {} : register(t{});", name, index);
+                    (text, ResourceDimension::RawBuffer, ResourceKind::Raw, 1)
+                }
+                Declaration::UnorderedAccessViewRaw { globally_coherent, .. } => {
+                    let coherent = if globally_coherent { "globallycoherent " } else { "" };
+                    let text = format!("{}RWByteAddressBuffer {} : register(u{});", coherent, name, index);
+                    (text, ResourceDimension::RawBuffer, ResourceKind::Raw, 1)
+                }
+                Declaration::ResourceStructured { stride, .. } | Declaration::UnorderedAccessViewStructured { stride, .. } => {
+                    writeln!(out, "struct {}_element\n{{\n    uint data[{}];\n}};\n", name, stride / 4).unwrap();
+                    let (prefix, slot) = match *declaration {
+                        Declaration::UnorderedAccessViewStructured { globally_coherent: true, .. } => ("globallycoherent RW", 'u'),
+                        Declaration::UnorderedAccessViewStructured { .. } => ("RW", 'u'),
+                        _ => ("", 't'),
+                    };
+                    let text = format!("{}StructuredBuffer<{}_element> {} : register({}{});", prefix, name, name, slot, index);
+                    (text, ResourceDimension::StructuredBuffer, ResourceKind::Structured, stride / 4)
+                }
+                Declaration::ThreadGroupSharedMemoryRaw { byte_count, .. } => {
+                    let text = format!("groupshared uint g{}[{}];", index, byte_count / 4);
+                    (text, ResourceDimension::RawBuffer, ResourceKind::Raw, 1)
+                }
+                Declaration::ThreadGroupSharedMemoryStructured { stride, count, .. } => {
+                    let text = format!("groupshared uint g{}[{}][{}];", index, count, stride / 4);
+                    (text, ResourceDimension::StructuredBuffer, ResourceKind::Structured, stride / 4)
+                }
+                _ => continue,
+            };
+
+            let name = match file {
+                OperandType::ThreadGroupSharedMemory => format!("g{}", index),
+                _ => name,
+            };
+            self.resources.insert((file, index), Resource { stride, ..Resource::new(name, dimension, kind) });
+            writeln!(out, "{}", text).unwrap();
+            declared = true;
+        }
+
+        if declared {
+            writeln!(out).unwrap();
+        }
+    }
+}
+
+fn write(shader: &Shader, program: &Program) -> Result<String, Error> {
+    let mut writer = Writer::new(shader, program, Hlsl)?;
+    let mut out = String::new();
+
+    let stage = match shader.program_type {
+        D3D10_SB_VERTEX_SHADER | D3D10_SB_PIXEL_SHADER | D3D11_SB_COMPUTE_SHADER => None,
+        D3D10_SB_GEOMETRY_SHADER => Some("geometry"),
+        D3D11_SB_HULL_SHADER => Some("hull"),
+        _ => Some("domain"),
+    };
+    if let Some(stage) = stage {
+        writeln!(out, "// {} shaders are only translated partially\n", stage).unwrap();
+    }
+
+    let system_values = writer.system_values();
+
+    if !shader.inputs.is_empty() || !system_values.is_empty() {
+        let interpolation = backend::interpolation(program);
+
+        writeln!(out, "struct Input\n{{").unwrap();
+        for input in &shader.inputs {
+            let modifier = interpolation.get(&input.register).map_or("", |&mode| interpolation_modifier(mode));
+            writeln!(out, "    {}{}", modifier, input.declaration()).unwrap();
+        }
+        for &(name, width, semantic) in &system_values {
+            writeln!(out, "    {} {} : {};", vector(Type::Uint, width), name, semantic).unwrap();
+        }
+        writeln!(out, "}};\n").unwrap();
+    }
+
+    if !shader.outputs.is_empty() {
+        writeln!(out, "struct Output\n{{").unwrap();
+        for output in &shader.outputs {
+            writeln!(out, "    {}", output.declaration()).unwrap();
+        }
+        writeln!(out, "}};\n").unwrap();
+    }
+
+    for text in &shader.structs {
+        writeln!(out, "{}", text).unwrap();
+    }
+    for (_, _, text) in &shader.constant_buffers {
+        writeln!(out, "{}", text).unwrap();
+    }
+    for declaration in &program.decls {
+        if let Declaration::ConstantBuffer { ref register, .. } = *declaration {
+            let slot = backend::resource_index(register);
+            if !shader.constant_buffers.iter().any(|&(buffer, _, _)| buffer == slot) {
+                let size = register.index.get(1).map_or(0, Index::offset);
+                writeln!(out, "cbuffer cb{} : register(b{})\n{{\n    float4 cb{}[{}];\n}};\n", slot, slot, slot, size).unwrap();
+            }
+        }
+    }
+
+    writer.write_resources(&mut out);
+
+    if let Some(vectors) = writer.immediate_constants() {
+        writeln!(out, "static const uint4 icb[{}] =\n{{\n{}\n}};\n", vectors.len(), vectors.join(",\n")).unwrap();
+    }
+
+    // registers, shared with the subroutines if there are any
+    let mut registers = Vec::new();
+    for register in backend::declared_registers(program, OperandType::Input, &shader.inputs) {
+        registers.push(format!("float4 v{} = 0;", register));
+    }
+    for &(name, width, _) in &system_values {
+        registers.push(format!("{} {};", vector(Type::Uint, width), name));
+    }
+    for register in backend::declared_registers(program, OperandType::Output, &shader.outputs) {
+        registers.push(format!("float4 o{} = 0;", register));
+    }
+    for output in shader.outputs.iter().filter(|output| output.register == !0) {
+        match output.system_register() {
+            Some("oMask") => registers.push("uint oMask = 0;".to_owned()),
+            Some(name) => registers.push(format!("float {} = 0;", name)),
+            None => {}
+        }
+    }
+    for declaration in &program.decls {
+        match *declaration {
+            Declaration::Temps(_) => {
+                for (name, ty) in writer.temps() {
+                    registers.push(format!("{} {} = 0;", vector(ty, 4), name));
+                }
+            }
+            Declaration::IndexableTemp { index, count, .. } => registers.push(format!("float4 x{}[{}];", index, count)),
+            _ => {}
+        }
+    }
+
+    if writer.global {
+        for register in &registers {
+            writeln!(out, "static {}", register).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+
+    let (main, subroutines) = backend::functions(program);
+    let mut functions = String::new();
+    for &(label, ref instructions) in &subroutines {
+        writeln!(out, "void label{}();", label).unwrap();
+        let body = writer.function(instructions.clone(), true)?;
+        writeln!(functions, "void label{}()\n{{\n{}}}\n", label, body).unwrap();
+    }
+    if !subroutines.is_empty() {
+        writeln!(out).unwrap();
+    }
+
+    let body = writer.function(main, false)?;
+
+    writer.write_helpers(&HELPERS, &mut out);
+    out.push_str(&functions);
+
+    for declaration in &program.decls {
+        match *declaration {
+            Declaration::ThreadGroup(x, y, z) => writeln!(out, "[numthreads({}, {}, {})]", x, y, z).unwrap(),
+            Declaration::GlobalFlags(flags) if flags.contains(GlobalFlags::FORCE_EARLY_DEPTH_STENCIL) => {
+                writeln!(out, "[earlydepthstencil]").unwrap()
+            }
+            _ => {}
+        }
+    }
+
+    let result = if shader.outputs.is_empty() { "void" } else { "Output" };
+    let parameters = if !shader.inputs.is_empty() || !system_values.is_empty() { "Input input" } else { "" };
+    writeln!(out, "{} main({})\n{{", result, parameters).unwrap();
+
+    if !writer.global {
+        for register in &registers {
+            writeln!(out, "    {}", register).unwrap();
+        }
+        if !registers.is_empty() {
+            writeln!(out).unwrap();
+        }
+    }
+
+    for input in &shader.inputs {
+        if input.register == !0 {
+            continue;
+        }
+        let components = backend::mask_components(input.mask);
+        let value = convert(format!("input.{}", input.member()), input.ty, Type::Float, components.len());
+        let value = if input.ty == Type::Bool {
+            format!("asfloat(input.{} ? 0xffffffffu : 0u)", input.member())
+        } else {
+            value
+        };
+        writeln!(out, "    v{}.{} = {};", input.register, backend::swizzle(&components), value).unwrap();
+    }
+    for &(name, _, _) in &system_values {
+        writeln!(out, "    {} = input.{};", name, name).unwrap();
+    }
+    if !shader.inputs.is_empty() || !system_values.is_empty() {
+        writeln!(out).unwrap();
+    }
+
+    out.push_str(&body);
+    writeln!(out, "}}").unwrap();
+
+    Ok(out)
+}
+
+/// Translates a parsed shader into HLSL source that compiles back to an
+/// equivalent shader.
+///
+/// Names of constant buffers, their variables and resources come from RDEF
+/// when it is present, inputs and outputs become structs with the semantics
+/// of the signatures. Instructions HLSL has no counterpart for end the parse
+/// with a `backend::Error`.
+pub struct HlslConsumer {
+    chunks: Chunks,
+    source: String,
+}
+
+impl HlslConsumer {
+    pub fn new() -> Self {
+        HlslConsumer {
+            chunks: Chunks::default(),
+            source: String::new(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Default for HlslConsumer {
+    fn default() -> Self {
+        HlslConsumer::new()
+    }
+}
+
+impl Consumer for HlslConsumer {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        self.chunks.finalize(&mut self.source, write)
+    }
+
+    fn consume_rdef(&mut self, rdef: &dr::RdefChunk) -> Action {
+        self.chunks.consume_rdef::<Hlsl>(rdef)
+    }
+
+    fn consume_isgn(&mut self, isgn: &dr::IOsgnChunk) -> Action {
+        self.chunks.consume_isgn(isgn)
+    }
+
+    fn consume_osgn(&mut self, osgn: &dr::IOsgnChunk) -> Action {
+        self.chunks.consume_osgn(osgn)
+    }
+
+    fn consume_shex(&mut self, header: &ShexHeader) -> Action {
+        self.chunks.consume_shex(header)
+    }
+
+    fn consume_instruction(&mut self, offset: u32, instruction: SparseInstruction) -> Action {
+        self.chunks.consume_instruction(offset, instruction)
+    }
+}
+
+pub fn translate(bytes: &[u8]) -> Result<String, State> {
+    let mut consumer = HlslConsumer::new();
+
+    {
+        let mut parser = Parser::new(bytes, &mut consumer);
+        parser.parse()?;
+    }
+
+    Ok(consumer.source)
+}
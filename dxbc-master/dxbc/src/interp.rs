@@ -0,0 +1,1295 @@
+use cfg::{self, Cfg};
+use dr::shex::{OperandType, TestBoolean};
+use dr::builder::{Modifier, OpcodeEx};
+use d3d11tokenizedprogramformat::*;
+use disasm::opcode_name;
+use ir::{Components, Declaration, Index, Operand, Program, Register};
+
+use std::collections::HashMap;
+use std::{error, fmt};
+
+/// Four components of a register as raw bits.
+pub type Value = [u32; 4];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    ControlFlow(cfg::Error),
+    /// Instruction the interpreter has no implementation for.
+    Unsupported(usize, u32),
+    /// Register outside of its declared file.
+    OutOfBounds(usize, OperandType, u64),
+    /// `abort` was executed.
+    Aborted(usize),
+    /// More instructions executed than `Interpreter::limit`, with the one
+    /// that would have gone past it.
+    LimitReached(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::ControlFlow(ref err) => write!(f, "{}", err),
+            Error::Unsupported(index, opcode) => {
+                write!(f, "cannot execute `{}` at instruction {}", opcode_name(opcode).unwrap_or("unknown"), index)
+            }
+            Error::OutOfBounds(index, file, register) => {
+                write!(f, "register {} of {:?} is out of bounds at instruction {}", register, file, index)
+            }
+            Error::Aborted(index) => write!(f, "abort at instruction {}", index),
+            Error::LimitReached(index) => write!(f, "instruction limit reached at instruction {}", index),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::ControlFlow(_) => "malformed control flow",
+            Error::Unsupported(..) => "unsupported instruction",
+            Error::OutOfBounds(..) => "register out of bounds",
+            Error::Aborted(_) => "shader aborted",
+            Error::LimitReached(_) => "instruction limit reached",
+        }
+    }
+}
+
+impl From<cfg::Error> for Error {
+    fn from(err: cfg::Error) -> Self {
+        Error::ControlFlow(err)
+    }
+}
+
+/// Level of detail of a sample.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Lod {
+    /// Derived from the screen space derivatives, which a single invocation
+    /// doesn't have. Implementations usually take the first level.
+    Implicit,
+    Bias(f32),
+    Level(f32),
+    /// Derivatives of the coordinates along x and y.
+    Gradients([f32; 3], [f32; 3]),
+}
+
+/// Everything a sample, gather or `lod` instruction passes to the texture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub texture: u32,
+    pub sampler: u32,
+    /// Coordinates as the instruction gives them, array slices included.
+    pub coordinates: [f32; 4],
+    /// Immediate or programmable texel offset.
+    pub offset: [i32; 3],
+    pub lod: Lod,
+    /// Reference value of comparisons.
+    pub reference: Option<f32>,
+    /// Component gathered from the four texels, `None` for samples.
+    pub gather: Option<u8>,
+}
+
+/// Size of a resource as `resinfo` and `bufinfo` return it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Dimensions {
+    /// Width of textures, number of elements of buffers and bytes of raw
+    /// buffers.
+    pub width: u32,
+    /// Height of 2D textures, array size of 1D arrays.
+    pub height: u32,
+    /// Depth of 3D textures, array size of 2D arrays and cubes.
+    pub depth: u32,
+    pub levels: u32,
+    pub samples: u32,
+}
+
+/// Shader resource views of the interpreter. Texels are raw bits of the
+/// return type of the view, the default methods read zeros.
+pub trait Textures {
+    /// Texel of a sample or the gathered components of a gather.
+    /// Comparisons return their result in `x`.
+    fn sample(&mut self, _sample: &Sample) -> Value {
+        [0; 4]
+    }
+
+    /// Texel of `ld` and `ld2dms`, the address has the mip level in its last
+    /// component unless the texture is multisampled.
+    fn load(&mut self, _texture: u32, _address: [i32; 4], _offset: [i32; 3], _sample: u32) -> Value {
+        [0; 4]
+    }
+
+    /// Clamped and unclamped level of detail of a sample.
+    fn lod(&mut self, _sample: &Sample) -> (f32, f32) {
+        (0.0, 0.0)
+    }
+
+    /// Dword at byte `offset` of a raw or structured buffer.
+    fn read(&mut self, _texture: u32, _offset: u32) -> u32 {
+        0
+    }
+
+    fn dimensions(&mut self, _texture: u32, _level: u32) -> Dimensions {
+        Dimensions::default()
+    }
+}
+
+/// Unordered access views of the interpreter, reads give zeros and writes
+/// are dropped by default. Atomics are reads followed by writes.
+pub trait UnorderedAccessViews {
+    /// Texel of a typed view.
+    fn load(&mut self, _uav: u32, _address: [u32; 4]) -> Value {
+        [0; 4]
+    }
+
+    fn store(&mut self, _uav: u32, _address: [u32; 4], _value: Value) {}
+
+    /// Dword at byte `offset` of a raw or structured view.
+    fn read(&mut self, _uav: u32, _offset: u32) -> u32 {
+        0
+    }
+
+    fn write(&mut self, _uav: u32, _offset: u32, _value: u32) {}
+
+    fn dimensions(&mut self, _uav: u32) -> Dimensions {
+        Dimensions::default()
+    }
+
+    /// Hidden counter of the view, `imm_atomic_alloc` gets the value before
+    /// incrementing and `imm_atomic_consume` the value after decrementing.
+    fn counter(&mut self, _uav: u32, _increment: bool) -> u32 {
+        0
+    }
+}
+
+impl Textures for () {}
+
+impl UnorderedAccessViews for () {}
+
+// How an instruction interprets the bits of its operands, which decides
+// what source modifiers do and whether denormals are flushed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Kind {
+    Float,
+    Int,
+    /// Moved without looking at the value, modifiers still work on floats.
+    Bits,
+    Double,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Flow {
+    Next,
+    Jump(usize),
+    Call(u32),
+    Return,
+    Discard,
+}
+
+// Memory layout of raw and structured views, `None` stride for raw ones.
+#[derive(Debug, Copy, Clone)]
+struct Memory {
+    stride: Option<u32>,
+    typed: bool,
+}
+
+/// Runs a single invocation of a program on the CPU.
+///
+/// Registers hold raw bits, fill in the inputs and constant buffers before
+/// calling `run`. Floats follow the D3D rules: denormals are flushed on
+/// float operations, `_sat` clamps NaN to zero and `min`/`max` return the
+/// number if the other operand is NaN. There are no neighbouring pixels,
+/// derivatives are zero.
+pub struct Interpreter<'p> {
+    program: &'p Program,
+    pub temps: Vec<Value>,
+    pub indexable_temps: HashMap<u32, Vec<Value>>,
+    pub inputs: Vec<Value>,
+    pub outputs: Vec<Value>,
+    /// Constant buffers by slot, missing buffers and registers read zero.
+    pub constant_buffers: HashMap<u32, Vec<Value>>,
+    pub immediate_constant_buffer: Vec<Value>,
+    /// Register files without an index, like `vThreadID` or `oDepth`.
+    pub system_values: HashMap<OperandType, Value>,
+    /// Thread group shared memory in dwords.
+    pub shared_memory: HashMap<u32, Vec<u32>>,
+    /// Sample count of the rasterizer for `sampleinfo` and `samplepos`.
+    pub sample_count: u32,
+    /// Maximum number of instructions a run executes.
+    pub limit: usize,
+    /// Whether the last run ended in a `discard`.
+    pub discarded: bool,
+    /// Target of each `if`, `else`, `loop`, `endloop`, `switch`, `break`
+    /// and `continue`.
+    targets: HashMap<usize, usize>,
+    cases: HashMap<usize, Vec<(Option<u32>, usize)>>,
+    labels: HashMap<u32, usize>,
+    memory: HashMap<(OperandType, u32), Memory>,
+}
+
+fn float(bits: u32) -> f32 {
+    f32::from_bits(bits)
+}
+
+fn bits(value: f32) -> u32 {
+    value.to_bits()
+}
+
+fn flush(bits: u32) -> u32 {
+    if float(bits).is_subnormal() {
+        bits & 0x8000_0000
+    } else {
+        bits
+    }
+}
+
+fn saturate(bits: u32) -> u32 {
+    let value = float(bits);
+    if value.is_nan() {
+        0
+    } else {
+        self::bits(value.clamp(0.0, 1.0))
+    }
+}
+
+fn mask(condition: bool) -> u32 {
+    if condition {
+        !0
+    } else {
+        0
+    }
+}
+
+fn double(lanes: &[u32]) -> f64 {
+    f64::from_bits(lanes[0] as u64 | (lanes[1] as u64) << 32)
+}
+
+fn split(value: f64) -> [u32; 2] {
+    let bits = value.to_bits();
+    [bits as u32, (bits >> 32) as u32]
+}
+
+// Round to nearest even, out of range values become infinities.
+fn f32_to_f16(value: f32) -> u32 {
+    let bits = value.to_bits();
+    let sign = (bits >> 16) & 0x8000;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let round = |value: u32, rest: u32, half: u32| value + (rest > half || (rest == half && value & 1 == 1)) as u32;
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        sign | round(mantissa >> shift, mantissa & ((1 << shift) - 1), 1 << (shift - 1))
+    } else {
+        sign | round((exponent as u32) << 10 | mantissa >> 13, mantissa & 0x1fff, 0x1000)
+    }
+}
+
+fn f16_to_f32(half: u32) -> f32 {
+    let sign = (half & 0x8000) << 16;
+    let exponent = (half >> 10) & 0x1f;
+    let mantissa = half & 0x3ff;
+
+    f32::from_bits(match exponent {
+        0 if mantissa == 0 => sign,
+        0 => {
+            let shift = mantissa.leading_zeros() - 21;
+            sign | (113 - shift) << 23 | ((mantissa << shift) & 0x3ff) << 13
+        }
+        0x1f => sign | 0x7f80_0000 | mantissa << 13,
+        _ => sign | (exponent + 112) << 23 | mantissa << 13,
+    })
+}
+
+fn bitfield_extract(width: u32, offset: u32, value: u32, signed: bool) -> u32 {
+    let (width, offset) = (width & 31, offset & 31);
+    match width {
+        0 => 0,
+        _ if width + offset < 32 && signed => (((value << (32 - width - offset)) as i32) >> (32 - width)) as u32,
+        _ if width + offset < 32 => (value << (32 - width - offset)) >> (32 - width),
+        _ if signed => ((value as i32) >> offset) as u32,
+        _ => value >> offset,
+    }
+}
+
+fn bitfield_insert(width: u32, offset: u32, insert: u32, base: u32) -> u32 {
+    let (width, offset) = (width & 31, offset & 31);
+    let mask = ((1u32 << width) - 1) << offset;
+    ((insert << offset) & mask) | (base & !mask)
+}
+
+// Standard sample positions in sixteenths of a pixel, the pattern of `n`
+// samples starts at `n - 1`.
+const SAMPLE_POSITIONS: [(i32, i32); 31] = [
+    (0, 0),
+    (4, 4), (-4, -4),
+    (-2, -6), (6, -2), (-6, 2), (2, 6),
+    (1, -3), (-1, 3), (5, 1), (-3, -5), (-5, 5), (-7, -1), (3, 7), (7, -7),
+    (1, 1), (-1, -3), (-3, 2), (4, -1), (-5, -2), (2, 5), (5, 3), (3, -5),
+    (-2, 6), (0, -7), (-4, -6), (-6, 4), (-8, 0), (7, -4), (6, 7), (-7, -8),
+];
+
+fn sample_position(count: u32, index: u32) -> [f32; 2] {
+    if index >= count || count > 16 || !count.is_power_of_two() {
+        return [0.0; 2];
+    }
+
+    let (x, y) = SAMPLE_POSITIONS[(count - 1 + index) as usize];
+    [x as f32 / 16.0, y as f32 / 16.0]
+}
+
+fn register_index(register: &Register) -> u32 {
+    register.index.last().map_or(0, Index::offset) as u32
+}
+
+impl<'p> Interpreter<'p> {
+    /// Sizes the register files from the declarations, fails if the control
+    /// flow doesn't nest.
+    pub fn new(program: &'p Program) -> Result<Self, Error> {
+        Cfg::build(program)?;
+
+        let mut interpreter = Interpreter {
+            program,
+            temps: Vec::new(),
+            indexable_temps: HashMap::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            constant_buffers: HashMap::new(),
+            immediate_constant_buffer: Vec::new(),
+            system_values: HashMap::new(),
+            shared_memory: HashMap::new(),
+            sample_count: 1,
+            limit: 1_000_000,
+            discarded: false,
+            targets: HashMap::new(),
+            cases: HashMap::new(),
+            labels: HashMap::new(),
+            memory: HashMap::new(),
+        };
+
+        let mut inputs = 0;
+        let mut outputs = 0;
+        for declaration in &program.decls {
+            match *declaration {
+                Declaration::Temps(count) => interpreter.temps = vec![[0; 4]; count as usize],
+                Declaration::IndexableTemp { index, count, .. } => {
+                    interpreter.indexable_temps.insert(index, vec![[0; 4]; count as usize]);
+                }
+                Declaration::ImmediateConstantBuffer(ref values) => {
+                    interpreter.immediate_constant_buffer = values
+                        .chunks(4)
+                        .map(|chunk| {
+                            let mut value = [0; 4];
+                            value[..chunk.len()].copy_from_slice(chunk);
+                            value
+                        })
+                        .collect();
+                }
+                Declaration::ResourceRaw { ref register } | Declaration::UnorderedAccessViewRaw { ref register, .. } => {
+                    interpreter.memory.insert((register.file, register_index(register)), Memory { stride: None, typed: false });
+                }
+                Declaration::ResourceStructured { ref register, stride } |
+                Declaration::UnorderedAccessViewStructured { ref register, stride, .. } => {
+                    interpreter.memory.insert((register.file, register_index(register)), Memory { stride: Some(stride), typed: false });
+                }
+                Declaration::Resource { ref register, .. } | Declaration::UnorderedAccessViewTyped { ref register, .. } => {
+                    interpreter.memory.insert((register.file, register_index(register)), Memory { stride: None, typed: true });
+                }
+                Declaration::ThreadGroupSharedMemoryRaw { ref register, byte_count } => {
+                    let index = register_index(register);
+                    interpreter.shared_memory.insert(index, vec![0; byte_count as usize / 4]);
+                    interpreter.memory.insert((register.file, index), Memory { stride: None, typed: false });
+                }
+                Declaration::ThreadGroupSharedMemoryStructured { ref register, stride, count } => {
+                    let index = register_index(register);
+                    interpreter.shared_memory.insert(index, vec![0; (stride * count) as usize / 4]);
+                    interpreter.memory.insert((register.file, index), Memory { stride: Some(stride), typed: false });
+                }
+                _ => {}
+            }
+
+            if let Some(register) = declaration.register() {
+                match register.file {
+                    OperandType::Input => inputs = inputs.max(register_index(register) + 1),
+                    OperandType::Output => outputs = outputs.max(register_index(register) + 1),
+                    _ => {}
+                }
+            }
+        }
+        interpreter.inputs = vec![[0; 4]; inputs as usize];
+        interpreter.outputs = vec![[0; 4]; outputs as usize];
+
+        interpreter.link();
+        Ok(interpreter)
+    }
+
+    // Matches the control flow instructions, the program is known to nest.
+    fn link(&mut self) {
+        let mut open: Vec<(u32, usize)> = Vec::new();
+        let mut breaks = Vec::new();
+
+        for (i, instruction) in self.program.instructions.iter().enumerate() {
+            match instruction.opcode {
+                D3D10_SB_OPCODE_IF | D3D10_SB_OPCODE_LOOP => open.push((instruction.opcode, i)),
+                D3D10_SB_OPCODE_SWITCH => {
+                    open.push((instruction.opcode, i));
+                    self.cases.insert(i, Vec::new());
+                }
+                D3D10_SB_OPCODE_ELSE => {
+                    if let Some((_, start)) = open.pop() {
+                        self.targets.insert(start, i);
+                    }
+                    open.push((instruction.opcode, i));
+                }
+                D3D10_SB_OPCODE_ENDIF | D3D10_SB_OPCODE_ENDSWITCH => {
+                    if let Some((_, start)) = open.pop() {
+                        self.targets.insert(start, i);
+                    }
+                }
+                D3D10_SB_OPCODE_ENDLOOP => {
+                    if let Some((_, start)) = open.pop() {
+                        self.targets.insert(start, i);
+                        self.targets.insert(i, start);
+                    }
+                }
+                D3D10_SB_OPCODE_CASE | D3D10_SB_OPCODE_DEFAULT => {
+                    let value = instruction.operands.first().and_then(|operand| match *operand {
+                        Operand::Imm32(ref values) => values.first().cloned(),
+                        _ => None,
+                    });
+                    if let Some(&(_, switch)) = open.iter().rev().find(|&&(opcode, _)| opcode == D3D10_SB_OPCODE_SWITCH) {
+                        self.cases.get_mut(&switch).unwrap().push((value, i));
+                    }
+                }
+                D3D10_SB_OPCODE_BREAK | D3D10_SB_OPCODE_BREAKC => {
+                    let construct = open
+                        .iter()
+                        .rev()
+                        .find(|&&(opcode, _)| opcode == D3D10_SB_OPCODE_LOOP || opcode == D3D10_SB_OPCODE_SWITCH);
+                    if let Some(&(_, start)) = construct {
+                        breaks.push((i, start));
+                    }
+                }
+                D3D10_SB_OPCODE_CONTINUE | D3D10_SB_OPCODE_CONTINUEC => {
+                    if let Some(&(_, start)) = open.iter().rev().find(|&&(opcode, _)| opcode == D3D10_SB_OPCODE_LOOP) {
+                        self.targets.insert(i, start);
+                    }
+                }
+                D3D10_SB_OPCODE_LABEL => {
+                    if let Some(label) = instruction.operands.first().and_then(Operand::register) {
+                        self.labels.insert(register_index(label), i);
+                    }
+                    open.clear();
+                }
+                _ => {}
+            }
+        }
+
+        // breaks leave at the end of their construct
+        for (i, start) in breaks {
+            if let Some(&end) = self.targets.get(&start) {
+                self.targets.insert(i, end);
+            }
+        }
+    }
+
+    /// Executes the program from the start until it returns, the outputs
+    /// are zeroed before and returned after. Temps and shared memory are
+    /// cleared too, everything else keeps its value.
+    pub fn run(&mut self, textures: &mut dyn Textures, uavs: &mut dyn UnorderedAccessViews) -> Result<Vec<Value>, Error> {
+        for value in self.temps.iter_mut().chain(self.outputs.iter_mut()) {
+            *value = [0; 4];
+        }
+        for values in self.indexable_temps.values_mut() {
+            for value in values.iter_mut() {
+                *value = [0; 4];
+            }
+        }
+        for memory in self.shared_memory.values_mut() {
+            for value in memory.iter_mut() {
+                *value = 0;
+            }
+        }
+        self.discarded = false;
+
+        let instructions = &self.program.instructions;
+        let mut calls = Vec::new();
+        let mut pc = 0;
+        let mut steps = 0;
+
+        loop {
+            // functions end at the next label
+            if pc >= instructions.len() || instructions[pc].opcode == D3D10_SB_OPCODE_LABEL {
+                match calls.pop() {
+                    Some(next) => {
+                        pc = next;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+
+            steps += 1;
+            if steps > self.limit {
+                return Err(Error::LimitReached(pc));
+            }
+
+            match self.step(pc, textures, uavs)? {
+                Flow::Next => pc += 1,
+                Flow::Jump(target) => pc = target,
+                Flow::Call(label) => {
+                    calls.push(pc + 1);
+                    // labels are checked by the control flow graph
+                    pc = self.labels[&label] + 1;
+                }
+                Flow::Return => match calls.pop() {
+                    Some(next) => pc = next,
+                    None => break,
+                },
+                Flow::Discard => {
+                    self.discarded = true;
+                    break;
+                }
+            }
+        }
+
+        Ok(self.outputs.clone())
+    }
+
+    fn index(&self, i: usize, index: &Index) -> Result<u64, Error> {
+        match index.relative() {
+            Some(relative) => {
+                let value = self.fetch(i, relative)?;
+                let lane = relative.components.swizzle()[0] as usize;
+                Ok(index.offset().wrapping_add(value[lane] as i32 as u64))
+            }
+            None => Ok(index.offset()),
+        }
+    }
+
+    // All four components of a register.
+    fn fetch(&self, i: usize, register: &Register) -> Result<Value, Error> {
+        let out_of_bounds = |index: u64| Error::OutOfBounds(i, register.file, index);
+        let get = |values: &[Value], index: u64| values.get(index as usize).cloned().ok_or_else(|| out_of_bounds(index));
+
+        match register.file {
+            OperandType::Temp => get(&self.temps, self.index(i, &register.index[0])?),
+            OperandType::Input => get(&self.inputs, self.index(i, &register.index[register.index.len() - 1])?),
+            OperandType::Output => get(&self.outputs, self.index(i, &register.index[0])?),
+            OperandType::IndexableTemp => {
+                let array = register.index[0].offset() as u32;
+                let index = self.index(i, &register.index[1])?;
+                match self.indexable_temps.get(&array) {
+                    Some(values) => get(values, index),
+                    None => Err(out_of_bounds(array as u64)),
+                }
+            }
+            // constant buffers read zero out of bounds
+            OperandType::ConstantBuffer => {
+                let buffer = register.index[0].offset() as u32;
+                let index = self.index(i, &register.index[1])?;
+                Ok(self.constant_buffers.get(&buffer).and_then(|values| values.get(index as usize)).cloned().unwrap_or([0; 4]))
+            }
+            OperandType::ImmediateConstantBuffer => {
+                let index = self.index(i, &register.index[0])?;
+                Ok(self.immediate_constant_buffer.get(index as usize).cloned().unwrap_or([0; 4]))
+            }
+            file => Ok(self.system_values.get(&file).cloned().unwrap_or([0; 4])),
+        }
+    }
+
+    fn register_mut(&mut self, i: usize, register: &Register) -> Result<&mut Value, Error> {
+        let file = register.file;
+        let index = match file {
+            OperandType::IndexableTemp => self.index(i, &register.index[1])?,
+            _ if register.index.is_empty() => 0,
+            _ => self.index(i, &register.index[0])?,
+        };
+        let out_of_bounds = Error::OutOfBounds(i, file, index);
+
+        let values = match file {
+            OperandType::Temp => &mut self.temps,
+            OperandType::Output => &mut self.outputs,
+            OperandType::IndexableTemp => match self.indexable_temps.get_mut(&(register.index[0].offset() as u32)) {
+                Some(values) => values,
+                None => return Err(out_of_bounds),
+            },
+            _ => return Ok(self.system_values.entry(file).or_insert([0; 4])),
+        };
+        values.get_mut(index as usize).ok_or(out_of_bounds)
+    }
+
+    /// Source operand with its swizzle and modifier applied.
+    fn read(&self, i: usize, operand: &Operand, kind: Kind) -> Result<Value, Error> {
+        let register = match *operand {
+            Operand::Register(ref register) => register,
+            Operand::Imm32(ref values) => {
+                let mut value = [values[0]; 4];
+                value[..values.len()].copy_from_slice(values);
+                return Ok(value);
+            }
+            Operand::Imm64(ref values) => {
+                let mut value = [0; 4];
+                for (n, &double) in values.iter().take(2).enumerate() {
+                    value[2 * n] = double as u32;
+                    value[2 * n + 1] = (double >> 32) as u32;
+                }
+                return Ok(value);
+            }
+        };
+
+        let lanes = self.fetch(i, register)?;
+        let mut value = match register.components {
+            Components::Scalar => [lanes[0]; 4],
+            components => {
+                let swizzle = components.swizzle();
+                [lanes[swizzle[0] as usize], lanes[swizzle[1] as usize], lanes[swizzle[2] as usize], lanes[swizzle[3] as usize]]
+            }
+        };
+
+        for (c, lane) in value.iter_mut().enumerate() {
+            if kind == Kind::Float {
+                *lane = flush(*lane);
+            }
+            // doubles carry their sign in the high half
+            if kind == Kind::Double && c % 2 == 0 {
+                continue;
+            }
+
+            let integer = kind == Kind::Int;
+            *lane = match register.modifier {
+                Modifier::None => *lane,
+                Modifier::Neg if integer => lane.wrapping_neg(),
+                Modifier::Abs if integer => (*lane as i32).wrapping_abs() as u32,
+                Modifier::AbsNeg if integer => (*lane as i32).wrapping_abs().wrapping_neg() as u32,
+                Modifier::Neg => *lane ^ 0x8000_0000,
+                Modifier::Abs => *lane & 0x7fff_ffff,
+                Modifier::AbsNeg => *lane | 0x8000_0000,
+            };
+        }
+
+        Ok(value)
+    }
+
+    /// Writes the components of `value` in the mask of destination
+    /// `operand`, float results are flushed and saturated.
+    fn write(&mut self, i: usize, operand: usize, value: Value, kind: Kind) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let register = match instruction.operands[operand] {
+            Operand::Register(ref register) if register.file != OperandType::Null => register,
+            _ => return Ok(()),
+        };
+
+        let saturated = instruction.saturate && kind != Kind::Int;
+        let mask = register.components.mask();
+        let target = self.register_mut(i, register)?;
+        for c in 0..4 {
+            if mask & (1 << c) == 0 {
+                continue;
+            }
+
+            let lane = if kind == Kind::Float { flush(value[c]) } else { value[c] };
+            target[c] = if saturated { saturate(lane) } else { lane };
+        }
+
+        Ok(())
+    }
+
+    // Sources after the first `outputs` operands.
+    fn sources(&self, i: usize, outputs: usize, kind: Kind) -> Result<Vec<Value>, Error> {
+        let operands = &self.program.instructions[i].operands;
+        operands[outputs..].iter().map(|operand| self.read(i, operand, kind)).collect()
+    }
+
+    // Instructions computing each component from the same component of
+    // their sources.
+    fn map(&mut self, i: usize, kind: Kind, result: Kind, f: &dyn Fn(&[u32]) -> u32) -> Result<(), Error> {
+        let sources = self.sources(i, 1, kind)?;
+        let mut value = [0; 4];
+        for (c, lane) in value.iter_mut().enumerate() {
+            let args: Vec<u32> = sources.iter().map(|source| source[c]).collect();
+            *lane = f(&args);
+        }
+        self.write(i, 0, value, result)
+    }
+
+    fn map_float(&mut self, i: usize, f: &dyn Fn(&[f32]) -> f32) -> Result<(), Error> {
+        self.map(i, Kind::Float, Kind::Float, &|args| {
+            let args: Vec<f32> = args.iter().cloned().map(float).collect();
+            bits(f(&args))
+        })
+    }
+
+    fn compare_float(&mut self, i: usize, f: &dyn Fn(f32, f32) -> bool) -> Result<(), Error> {
+        self.map(i, Kind::Float, Kind::Int, &|args| mask(f(float(args[0]), float(args[1]))))
+    }
+
+    // Instructions with two destinations, both read all sources first.
+    fn map2(&mut self, i: usize, kind: Kind, result: Kind, f: &dyn Fn(&[u32]) -> (u32, u32)) -> Result<(), Error> {
+        let sources = self.sources(i, 2, kind)?;
+        let mut first = [0; 4];
+        let mut second = [0; 4];
+        for c in 0..4 {
+            let args: Vec<u32> = sources.iter().map(|source| source[c]).collect();
+            let (a, b) = f(&args);
+            first[c] = a;
+            second[c] = b;
+        }
+        self.write(i, 0, first, result)?;
+        self.write(i, 1, second, result)
+    }
+
+    fn dot(&mut self, i: usize, count: usize) -> Result<(), Error> {
+        let sources = self.sources(i, 1, Kind::Float)?;
+        let sum = (0..count).fold(0.0, |sum, c| sum + float(sources[0][c]) * float(sources[1][c]));
+        self.write(i, 0, [bits(sum); 4], Kind::Float)
+    }
+
+    // Double operations on the two doubles of the swizzled sources.
+    fn map_double(&mut self, i: usize, f: &dyn Fn(&[f64]) -> f64) -> Result<(), Error> {
+        let sources = self.sources(i, 1, Kind::Double)?;
+        let mut value = [0; 4];
+        for n in 0..2 {
+            let args: Vec<f64> = sources.iter().map(|source| double(&source[2 * n..])).collect();
+            value[2 * n..2 * n + 2].copy_from_slice(&split(f(&args)));
+        }
+        self.write(i, 0, value, Kind::Bits)
+    }
+
+    // Double operations with a 32 bit result for each double, written to
+    // the components of the mask in order.
+    fn narrow_double(&mut self, i: usize, kind: Kind, f: &dyn Fn(&[f64]) -> u32) -> Result<(), Error> {
+        let sources = self.sources(i, 1, Kind::Double)?;
+        let results: Vec<u32> = (0..2)
+            .map(|n| {
+                let args: Vec<f64> = sources.iter().map(|source| double(&source[2 * n..])).collect();
+                f(&args)
+            })
+            .collect();
+        self.write_packed(i, &results, kind)
+    }
+
+    fn write_packed(&mut self, i: usize, results: &[u32], kind: Kind) -> Result<(), Error> {
+        let mask = self.program.instructions[i].operands[0].register().map_or(0, |register| register.components.mask());
+        let mut value = [0; 4];
+        let mut next = results.iter();
+        for (c, lane) in value.iter_mut().enumerate() {
+            if mask & (1 << c) != 0 {
+                *lane = next.next().cloned().unwrap_or(0);
+            }
+        }
+        self.write(i, 0, value, kind)
+    }
+
+    // 32 bit values converted to doubles, the components of the source in
+    // order for each double of the mask.
+    fn widen(&mut self, i: usize, kind: Kind, f: &dyn Fn(u32) -> f64) -> Result<(), Error> {
+        let source = self.read(i, &self.program.instructions[i].operands[1], kind)?;
+        let mask = self.program.instructions[i].operands[0].register().map_or(0, |register| register.components.mask());
+        let mut value = [0; 4];
+        let mut next = 0;
+        for n in 0..2 {
+            if mask & (3 << (2 * n)) != 0 {
+                value[2 * n..2 * n + 2].copy_from_slice(&split(f(source[next])));
+                next += 1;
+            }
+        }
+        self.write(i, 0, value, Kind::Bits)
+    }
+
+    fn condition(&self, i: usize) -> Result<bool, Error> {
+        let instruction = &self.program.instructions[i];
+        let value = self.read(i, &instruction.operands[0], Kind::Int)?[0];
+        Ok(match instruction.test {
+            Some(TestBoolean::Zero) => value == 0,
+            _ => value != 0,
+        })
+    }
+
+    fn offset(&self, i: usize) -> [i32; 3] {
+        for extended in &self.program.instructions[i].extended {
+            if let OpcodeEx::UvOffset(u, v, w) = *extended {
+                return [u as i32, v as i32, w as i32];
+            }
+        }
+        [0; 3]
+    }
+
+    fn resource(&self, i: usize, operand: usize) -> (OperandType, u32) {
+        let register = self.program.instructions[i].operands[operand].register();
+        register.map_or((OperandType::Null, 0), |register| (register.file, register_index(register)))
+    }
+
+    // Writes a texel through the swizzle of the resource operand.
+    fn write_texel(&mut self, i: usize, resource: usize, texel: Value, kind: Kind) -> Result<(), Error> {
+        let swizzle = self.program.instructions[i].operands[resource].register().map_or([0, 1, 2, 3], |register| {
+            register.components.swizzle()
+        });
+        let value = [texel[swizzle[0] as usize], texel[swizzle[1] as usize], texel[swizzle[2] as usize], texel[swizzle[3] as usize]];
+        self.write(i, 0, value, kind)
+    }
+
+    fn sample(&mut self, i: usize, textures: &mut dyn Textures) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let opcode = instruction.opcode;
+        let operands = &instruction.operands;
+        let programmable = opcode == D3D11_SB_OPCODE_GATHER4_PO || opcode == D3D11_SB_OPCODE_GATHER4_PO_C;
+        let (resource, sampler) = if programmable { (3, 4) } else { (2, 3) };
+        let extra = sampler + 1;
+
+        let coordinates = self.read(i, &operands[1], Kind::Float)?;
+        let scalar = |operand: usize| -> Result<f32, Error> { Ok(float(self.read(i, &operands[operand], Kind::Float)?[0])) };
+        let gradient = |operand: usize| -> Result<[f32; 3], Error> {
+            let value = self.read(i, &operands[operand], Kind::Float)?;
+            Ok([float(value[0]), float(value[1]), float(value[2])])
+        };
+
+        let offset = if programmable {
+            let offset = self.read(i, &operands[2], Kind::Int)?;
+            [offset[0] as i32, offset[1] as i32, offset[2] as i32]
+        } else {
+            self.offset(i)
+        };
+        let sampler_register = operands[sampler].register();
+        let gather = match opcode {
+            D3D10_1_SB_OPCODE_GATHER4 | D3D11_SB_OPCODE_GATHER4_PO => {
+                Some(sampler_register.map_or(0, |register| register.components.swizzle()[0]))
+            }
+            D3D11_SB_OPCODE_GATHER4_C | D3D11_SB_OPCODE_GATHER4_PO_C => Some(0),
+            _ => None,
+        };
+
+        let (lod, reference) = match opcode {
+            D3D10_SB_OPCODE_SAMPLE_B => (Lod::Bias(scalar(extra)?), None),
+            D3D10_SB_OPCODE_SAMPLE_L => (Lod::Level(scalar(extra)?), None),
+            D3D10_SB_OPCODE_SAMPLE_D => (Lod::Gradients(gradient(extra)?, gradient(extra + 1)?), None),
+            D3D10_SB_OPCODE_SAMPLE_C | D3D11_SB_OPCODE_GATHER4_C | D3D11_SB_OPCODE_GATHER4_PO_C => {
+                (Lod::Implicit, Some(scalar(extra)?))
+            }
+            D3D10_SB_OPCODE_SAMPLE_C_LZ => (Lod::Level(0.0), Some(scalar(extra)?)),
+            _ => (Lod::Implicit, None),
+        };
+
+        let request = Sample {
+            texture: self.resource(i, resource).1,
+            sampler: sampler_register.map_or(0, register_index),
+            coordinates: [float(coordinates[0]), float(coordinates[1]), float(coordinates[2]), float(coordinates[3])],
+            offset,
+            lod,
+            reference,
+            gather,
+        };
+
+        if opcode == D3D10_1_SB_OPCODE_LOD {
+            let (clamped, unclamped) = textures.lod(&request);
+            return self.write_texel(i, resource, [bits(clamped), bits(unclamped), 0, 0], Kind::Float);
+        }
+
+        let texel = textures.sample(&request);
+        match opcode {
+            D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ => self.write(i, 0, [texel[0]; 4], Kind::Float),
+            _ => self.write_texel(i, resource, texel, Kind::Bits),
+        }
+    }
+
+    // Byte offset of a dword in raw or structured memory.
+    fn address(&self, file: OperandType, index: u32, address: &[u32]) -> u32 {
+        match self.memory.get(&(file, index)).and_then(|memory| memory.stride) {
+            Some(stride) => address[0].wrapping_mul(stride).wrapping_add(address[1]),
+            None => address[0],
+        }
+    }
+
+    fn read_memory(&self, file: OperandType, index: u32, offset: u32, textures: &mut dyn Textures, uavs: &mut dyn UnorderedAccessViews) -> u32 {
+        match file {
+            OperandType::Resource => textures.read(index, offset),
+            OperandType::UnorderedAccessView => uavs.read(index, offset),
+            _ => self
+                .shared_memory
+                .get(&index)
+                .and_then(|memory| memory.get(offset as usize / 4))
+                .cloned()
+                .unwrap_or(0),
+        }
+    }
+
+    fn write_memory(&mut self, file: OperandType, index: u32, offset: u32, value: u32, uavs: &mut dyn UnorderedAccessViews) {
+        match file {
+            OperandType::UnorderedAccessView => uavs.write(index, offset, value),
+            _ => {
+                if let Some(dword) = self.shared_memory.get_mut(&index).and_then(|memory| memory.get_mut(offset as usize / 4)) {
+                    *dword = value;
+                }
+            }
+        }
+    }
+
+    fn load_memory(&mut self, i: usize, textures: &mut dyn Textures, uavs: &mut dyn UnorderedAccessViews) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let structured = instruction.opcode == D3D11_SB_OPCODE_LD_STRUCTURED;
+        let resource = if structured { 3 } else { 2 };
+        let (file, index) = self.resource(i, resource);
+
+        let mut address = vec![self.read(i, &instruction.operands[1], Kind::Int)?[0]];
+        if structured {
+            address.push(self.read(i, &instruction.operands[2], Kind::Int)?[0]);
+        }
+        let offset = self.address(file, index, &address);
+
+        let mut texel = [0; 4];
+        for (c, dword) in texel.iter_mut().enumerate() {
+            *dword = self.read_memory(file, index, offset.wrapping_add(4 * c as u32), textures, uavs);
+        }
+        self.write_texel(i, resource, texel, Kind::Bits)
+    }
+
+    fn store_memory(&mut self, i: usize, uavs: &mut dyn UnorderedAccessViews) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let structured = instruction.opcode == D3D11_SB_OPCODE_STORE_STRUCTURED;
+        let (file, index) = self.resource(i, 0);
+        let mask = instruction.operands[0].register().map_or(0, |register| register.components.mask());
+
+        let mut address = vec![self.read(i, &instruction.operands[1], Kind::Int)?[0]];
+        if structured {
+            address.push(self.read(i, &instruction.operands[2], Kind::Int)?[0]);
+        }
+        let offset = self.address(file, index, &address);
+        let value = self.read(i, &instruction.operands[if structured { 3 } else { 2 }], Kind::Bits)?;
+
+        for (c, &dword) in value.iter().enumerate() {
+            if mask & (1 << c) != 0 {
+                self.write_memory(file, index, offset.wrapping_add(4 * c as u32), dword, uavs);
+            }
+        }
+        Ok(())
+    }
+
+    fn atomic(&mut self, i: usize, textures: &mut dyn Textures, uavs: &mut dyn UnorderedAccessViews) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let opcode = instruction.opcode;
+        let immediate = opcode >= D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC;
+        let target = if immediate { 1 } else { 0 };
+        let (file, index) = self.resource(i, target);
+
+        if opcode == D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC || opcode == D3D11_SB_OPCODE_IMM_ATOMIC_CONSUME {
+            let value = uavs.counter(index, opcode == D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC);
+            return self.write(i, 0, [value; 4], Kind::Int);
+        }
+
+        let address = self.read(i, &instruction.operands[target + 1], Kind::Int)?;
+        let sources = self.sources(i, target + 2, Kind::Int)?;
+        let typed = self.memory.get(&(file, index)).is_some_and(|memory| memory.typed);
+        let offset = self.address(file, index, &address);
+
+        let old = if typed {
+            uavs.load(index, address)[0]
+        } else {
+            self.read_memory(file, index, offset, textures, uavs)
+        };
+        let (a, b) = (old, sources[0][0]);
+        let new = match opcode {
+            D3D11_SB_OPCODE_ATOMIC_AND | D3D11_SB_OPCODE_IMM_ATOMIC_AND => a & b,
+            D3D11_SB_OPCODE_ATOMIC_OR | D3D11_SB_OPCODE_IMM_ATOMIC_OR => a | b,
+            D3D11_SB_OPCODE_ATOMIC_XOR | D3D11_SB_OPCODE_IMM_ATOMIC_XOR => a ^ b,
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => {
+                if a == b {
+                    sources[1][0]
+                } else {
+                    a
+                }
+            }
+            D3D11_SB_OPCODE_ATOMIC_IADD | D3D11_SB_OPCODE_IMM_ATOMIC_IADD => a.wrapping_add(b),
+            D3D11_SB_OPCODE_ATOMIC_IMAX | D3D11_SB_OPCODE_IMM_ATOMIC_IMAX => (a as i32).max(b as i32) as u32,
+            D3D11_SB_OPCODE_ATOMIC_IMIN | D3D11_SB_OPCODE_IMM_ATOMIC_IMIN => (a as i32).min(b as i32) as u32,
+            D3D11_SB_OPCODE_ATOMIC_UMAX | D3D11_SB_OPCODE_IMM_ATOMIC_UMAX => a.max(b),
+            D3D11_SB_OPCODE_ATOMIC_UMIN | D3D11_SB_OPCODE_IMM_ATOMIC_UMIN => a.min(b),
+            _ => b,
+        };
+
+        if typed {
+            uavs.store(index, address, [new; 4]);
+        } else {
+            self.write_memory(file, index, offset, new, uavs);
+        }
+        if immediate {
+            self.write(i, 0, [old; 4], Kind::Int)?;
+        }
+        Ok(())
+    }
+
+    fn resinfo(&mut self, i: usize, textures: &mut dyn Textures, uavs: &mut dyn UnorderedAccessViews) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let level = self.read(i, &instruction.operands[1], Kind::Int)?[0];
+        let (file, index) = self.resource(i, 2);
+        let dimensions = match file {
+            OperandType::UnorderedAccessView => uavs.dimensions(index),
+            _ => textures.dimensions(index, level),
+        };
+
+        let sizes = [dimensions.width, dimensions.height, dimensions.depth, dimensions.levels];
+        let texel = match DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(instruction.controls) {
+            D3D10_SB_RESINFO_INSTRUCTION_RETURN_UINT => return self.write_texel(i, 2, sizes, Kind::Int),
+            D3D10_SB_RESINFO_INSTRUCTION_RETURN_RCPFLOAT => {
+                let rcp = |size: u32| bits(1.0 / size as f32);
+                [rcp(sizes[0]), rcp(sizes[1]), rcp(sizes[2]), bits(sizes[3] as f32)]
+            }
+            _ => [bits(sizes[0] as f32), bits(sizes[1] as f32), bits(sizes[2] as f32), bits(sizes[3] as f32)],
+        };
+        self.write_texel(i, 2, texel, Kind::Float)
+    }
+
+    fn sample_info(&mut self, i: usize, textures: &mut dyn Textures) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let (file, index) = self.resource(i, 1);
+        let samples = match file {
+            OperandType::Rasterizer => self.sample_count,
+            _ => textures.dimensions(index, 0).samples,
+        };
+
+        if instruction.opcode == D3D10_1_SB_OPCODE_SAMPLE_POS {
+            let sample = self.read(i, &instruction.operands[2], Kind::Int)?[0];
+            let position = sample_position(samples, sample);
+            return self.write_texel(i, 1, [bits(position[0]), bits(position[1]), 0, 0], Kind::Float);
+        }
+
+        if DECODE_D3D10_SB_INSTRUCTION_RETURN_TYPE(instruction.controls) == D3D10_SB_INSTRUCTION_RETURN_UINT {
+            self.write_texel(i, 1, [samples, 0, 0, 0], Kind::Int)
+        } else {
+            self.write_texel(i, 1, [bits(samples as f32), 0, 0, 0], Kind::Float)
+        }
+    }
+
+    fn step(&mut self, i: usize, textures: &mut dyn Textures, uavs: &mut dyn UnorderedAccessViews) -> Result<Flow, Error> {
+        let program = self.program;
+        let instruction = &program.instructions[i];
+        let operands = &instruction.operands;
+        let jump = |target: Option<&usize>| Flow::Jump(target.map_or(i + 1, |&target| target + 1));
+
+        match instruction.opcode {
+            D3D10_SB_OPCODE_ADD => self.map_float(i, &|a| a[0] + a[1])?,
+            D3D10_SB_OPCODE_MUL => self.map_float(i, &|a| a[0] * a[1])?,
+            D3D10_SB_OPCODE_DIV => self.map_float(i, &|a| a[0] / a[1])?,
+            D3D10_SB_OPCODE_MAD => self.map_float(i, &|a| a[0] * a[1] + a[2])?,
+            D3D10_SB_OPCODE_MIN => self.map_float(i, &|a| a[0].min(a[1]))?,
+            D3D10_SB_OPCODE_MAX => self.map_float(i, &|a| a[0].max(a[1]))?,
+            D3D10_SB_OPCODE_FRC => self.map_float(i, &|a| a[0] - a[0].floor())?,
+            D3D10_SB_OPCODE_ROUND_NE => self.map_float(i, &|a| a[0].round_ties_even())?,
+            D3D10_SB_OPCODE_ROUND_NI => self.map_float(i, &|a| a[0].floor())?,
+            D3D10_SB_OPCODE_ROUND_PI => self.map_float(i, &|a| a[0].ceil())?,
+            D3D10_SB_OPCODE_ROUND_Z => self.map_float(i, &|a| a[0].trunc())?,
+            D3D10_SB_OPCODE_SQRT => self.map_float(i, &|a| a[0].sqrt())?,
+            D3D10_SB_OPCODE_RSQ => self.map_float(i, &|a| 1.0 / a[0].sqrt())?,
+            D3D11_SB_OPCODE_RCP => self.map_float(i, &|a| 1.0 / a[0])?,
+            D3D10_SB_OPCODE_EXP => self.map_float(i, &|a| a[0].exp2())?,
+            D3D10_SB_OPCODE_LOG => self.map_float(i, &|a| a[0].log2())?,
+            D3D10_SB_OPCODE_DERIV_RTX | D3D10_SB_OPCODE_DERIV_RTY | D3D11_SB_OPCODE_DERIV_RTX_COARSE |
+            D3D11_SB_OPCODE_DERIV_RTX_FINE | D3D11_SB_OPCODE_DERIV_RTY_COARSE | D3D11_SB_OPCODE_DERIV_RTY_FINE => {
+                self.map_float(i, &|_| 0.0)?
+            }
+            D3D10_SB_OPCODE_DP2 => self.dot(i, 2)?,
+            D3D10_SB_OPCODE_DP3 => self.dot(i, 3)?,
+            D3D10_SB_OPCODE_DP4 => self.dot(i, 4)?,
+            D3D10_SB_OPCODE_SINCOS => self.map2(i, Kind::Float, Kind::Float, &|a| (bits(float(a[0]).sin()), bits(float(a[0]).cos())))?,
+            D3D10_SB_OPCODE_EQ => self.compare_float(i, &|a, b| a == b)?,
+            D3D10_SB_OPCODE_NE => self.compare_float(i, &|a, b| a != b)?,
+            D3D10_SB_OPCODE_LT => self.compare_float(i, &|a, b| a < b)?,
+            D3D10_SB_OPCODE_GE => self.compare_float(i, &|a, b| a >= b)?,
+            D3D10_SB_OPCODE_MOV => self.map(i, Kind::Bits, Kind::Bits, &|a| a[0])?,
+            D3D10_SB_OPCODE_MOVC => self.map(i, Kind::Bits, Kind::Bits, &|a| if a[0] != 0 { a[1] } else { a[2] })?,
+            D3D11_SB_OPCODE_SWAPC => {
+                self.map2(i, Kind::Bits, Kind::Bits, &|a| if a[0] != 0 { (a[2], a[1]) } else { (a[1], a[2]) })?
+            }
+            D3D10_SB_OPCODE_ITOF => self.map(i, Kind::Int, Kind::Float, &|a| bits(a[0] as i32 as f32))?,
+            D3D10_SB_OPCODE_UTOF => self.map(i, Kind::Int, Kind::Float, &|a| bits(a[0] as f32))?,
+            // conversions saturate and take NaN to zero like D3D
+            D3D10_SB_OPCODE_FTOI => self.map(i, Kind::Float, Kind::Int, &|a| float(a[0]) as i32 as u32)?,
+            D3D10_SB_OPCODE_FTOU => self.map(i, Kind::Float, Kind::Int, &|a| float(a[0]) as u32)?,
+            D3D11_SB_OPCODE_F32TOF16 => self.map(i, Kind::Float, Kind::Int, &|a| f32_to_f16(float(a[0])))?,
+            D3D11_SB_OPCODE_F16TOF32 => self.map(i, Kind::Int, Kind::Float, &|a| bits(f16_to_f32(a[0])))?,
+            D3D10_SB_OPCODE_IADD => self.map(i, Kind::Int, Kind::Int, &|a| a[0].wrapping_add(a[1]))?,
+            D3D10_SB_OPCODE_IMAD | D3D10_SB_OPCODE_UMAD => {
+                self.map(i, Kind::Int, Kind::Int, &|a| a[0].wrapping_mul(a[1]).wrapping_add(a[2]))?
+            }
+            D3D10_SB_OPCODE_INEG => self.map(i, Kind::Int, Kind::Int, &|a| a[0].wrapping_neg())?,
+            D3D10_SB_OPCODE_IMIN => self.map(i, Kind::Int, Kind::Int, &|a| (a[0] as i32).min(a[1] as i32) as u32)?,
+            D3D10_SB_OPCODE_IMAX => self.map(i, Kind::Int, Kind::Int, &|a| (a[0] as i32).max(a[1] as i32) as u32)?,
+            D3D10_SB_OPCODE_UMIN => self.map(i, Kind::Int, Kind::Int, &|a| a[0].min(a[1]))?,
+            D3D10_SB_OPCODE_UMAX => self.map(i, Kind::Int, Kind::Int, &|a| a[0].max(a[1]))?,
+            D3D10_SB_OPCODE_ISHL => self.map(i, Kind::Int, Kind::Int, &|a| a[0] << (a[1] & 31))?,
+            D3D10_SB_OPCODE_ISHR => self.map(i, Kind::Int, Kind::Int, &|a| ((a[0] as i32) >> (a[1] & 31)) as u32)?,
+            D3D10_SB_OPCODE_USHR => self.map(i, Kind::Int, Kind::Int, &|a| a[0] >> (a[1] & 31))?,
+            D3D10_SB_OPCODE_AND => self.map(i, Kind::Int, Kind::Int, &|a| a[0] & a[1])?,
+            D3D10_SB_OPCODE_OR => self.map(i, Kind::Int, Kind::Int, &|a| a[0] | a[1])?,
+            D3D10_SB_OPCODE_XOR => self.map(i, Kind::Int, Kind::Int, &|a| a[0] ^ a[1])?,
+            D3D10_SB_OPCODE_NOT => self.map(i, Kind::Int, Kind::Int, &|a| !a[0])?,
+            D3D10_SB_OPCODE_IEQ => self.map(i, Kind::Int, Kind::Int, &|a| mask(a[0] == a[1]))?,
+            D3D10_SB_OPCODE_INE => self.map(i, Kind::Int, Kind::Int, &|a| mask(a[0] != a[1]))?,
+            D3D10_SB_OPCODE_ILT => self.map(i, Kind::Int, Kind::Int, &|a| mask((a[0] as i32) < a[1] as i32))?,
+            D3D10_SB_OPCODE_IGE => self.map(i, Kind::Int, Kind::Int, &|a| mask(a[0] as i32 >= a[1] as i32))?,
+            D3D10_SB_OPCODE_ULT => self.map(i, Kind::Int, Kind::Int, &|a| mask(a[0] < a[1]))?,
+            D3D10_SB_OPCODE_UGE => self.map(i, Kind::Int, Kind::Int, &|a| mask(a[0] >= a[1]))?,
+            D3D10_SB_OPCODE_IMUL => self.map2(i, Kind::Int, Kind::Int, &|a| {
+                let product = a[0] as i32 as i64 * a[1] as i32 as i64;
+                ((product >> 32) as u32, product as u32)
+            })?,
+            D3D10_SB_OPCODE_UMUL => self.map2(i, Kind::Int, Kind::Int, &|a| {
+                let product = a[0] as u64 * a[1] as u64;
+                ((product >> 32) as u32, product as u32)
+            })?,
+            // division by zero gives all bits set
+            D3D10_SB_OPCODE_UDIV => self.map2(i, Kind::Int, Kind::Int, &|a| match a[1] {
+                0 => (!0, !0),
+                b => (a[0] / b, a[0] % b),
+            })?,
+            D3D11_SB_OPCODE_UADDC => self.map2(i, Kind::Int, Kind::Int, &|a| {
+                let (sum, carry) = a[0].overflowing_add(a[1]);
+                (sum, carry as u32)
+            })?,
+            D3D11_SB_OPCODE_USUBB => self.map2(i, Kind::Int, Kind::Int, &|a| {
+                let (difference, borrow) = a[0].overflowing_sub(a[1]);
+                (difference, borrow as u32)
+            })?,
+            D3D11_SB_OPCODE_COUNTBITS => self.map(i, Kind::Int, Kind::Int, &|a| a[0].count_ones())?,
+            D3D11_SB_OPCODE_BFREV => self.map(i, Kind::Int, Kind::Int, &|a| a[0].reverse_bits())?,
+            D3D11_SB_OPCODE_FIRSTBIT_HI => self.map(i, Kind::Int, Kind::Int, &|a| if a[0] == 0 { !0 } else { a[0].leading_zeros() })?,
+            D3D11_SB_OPCODE_FIRSTBIT_LO => self.map(i, Kind::Int, Kind::Int, &|a| if a[0] == 0 { !0 } else { a[0].trailing_zeros() })?,
+            // negative numbers look for the first zero
+            D3D11_SB_OPCODE_FIRSTBIT_SHI => self.map(i, Kind::Int, Kind::Int, &|a| {
+                let value = if (a[0] as i32) < 0 { !a[0] } else { a[0] };
+                if value == 0 { !0 } else { value.leading_zeros() }
+            })?,
+            D3D11_SB_OPCODE_UBFE => self.map(i, Kind::Int, Kind::Int, &|a| bitfield_extract(a[0], a[1], a[2], false))?,
+            D3D11_SB_OPCODE_IBFE => self.map(i, Kind::Int, Kind::Int, &|a| bitfield_extract(a[0], a[1], a[2], true))?,
+            D3D11_SB_OPCODE_BFI => self.map(i, Kind::Int, Kind::Int, &|a| bitfield_insert(a[0], a[1], a[2], a[3]))?,
+            D3D11_SB_OPCODE_DMOV => self.map_double(i, &|a| a[0])?,
+            D3D11_SB_OPCODE_DADD => self.map_double(i, &|a| a[0] + a[1])?,
+            D3D11_SB_OPCODE_DMUL => self.map_double(i, &|a| a[0] * a[1])?,
+            D3D11_SB_OPCODE_DMIN => self.map_double(i, &|a| a[0].min(a[1]))?,
+            D3D11_SB_OPCODE_DMAX => self.map_double(i, &|a| a[0].max(a[1]))?,
+            D3D11_1_SB_OPCODE_DDIV => self.map_double(i, &|a| a[0] / a[1])?,
+            D3D11_1_SB_OPCODE_DFMA => self.map_double(i, &|a| a[0].mul_add(a[1], a[2]))?,
+            D3D11_1_SB_OPCODE_DRCP => self.map_double(i, &|a| 1.0 / a[0])?,
+            // the condition of each double is in its low component
+            D3D11_SB_OPCODE_DMOVC => {
+                let sources = self.sources(i, 1, Kind::Double)?;
+                let mut value = [0; 4];
+                for n in 0..2 {
+                    let source = if sources[0][2 * n] != 0 { &sources[1] } else { &sources[2] };
+                    value[2 * n..2 * n + 2].copy_from_slice(&source[2 * n..2 * n + 2]);
+                }
+                self.write(i, 0, value, Kind::Bits)?
+            }
+            D3D11_SB_OPCODE_DEQ => self.narrow_double(i, Kind::Int, &|a| mask(a[0] == a[1]))?,
+            D3D11_SB_OPCODE_DNE => self.narrow_double(i, Kind::Int, &|a| mask(a[0] != a[1]))?,
+            D3D11_SB_OPCODE_DLT => self.narrow_double(i, Kind::Int, &|a| mask(a[0] < a[1]))?,
+            D3D11_SB_OPCODE_DGE => self.narrow_double(i, Kind::Int, &|a| mask(a[0] >= a[1]))?,
+            D3D11_SB_OPCODE_DTOF => self.narrow_double(i, Kind::Float, &|a| bits(a[0] as f32))?,
+            D3D11_1_SB_OPCODE_DTOI => self.narrow_double(i, Kind::Int, &|a| a[0] as i32 as u32)?,
+            D3D11_1_SB_OPCODE_DTOU => self.narrow_double(i, Kind::Int, &|a| a[0] as u32)?,
+            D3D11_SB_OPCODE_FTOD => self.widen(i, Kind::Float, &|a| float(a) as f64)?,
+            D3D11_1_SB_OPCODE_ITOD => self.widen(i, Kind::Int, &|a| a as i32 as f64)?,
+            D3D11_1_SB_OPCODE_UTOD => self.widen(i, Kind::Int, &|a| a as f64)?,
+            D3D10_SB_OPCODE_SAMPLE | D3D10_SB_OPCODE_SAMPLE_B | D3D10_SB_OPCODE_SAMPLE_L | D3D10_SB_OPCODE_SAMPLE_D |
+            D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ | D3D10_1_SB_OPCODE_GATHER4 | D3D11_SB_OPCODE_GATHER4_C |
+            D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C | D3D10_1_SB_OPCODE_LOD => self.sample(i, textures)?,
+            D3D10_SB_OPCODE_LD | D3D10_SB_OPCODE_LD_MS => {
+                let address = self.read(i, &operands[1], Kind::Int)?;
+                let sample = match operands.get(3) {
+                    Some(operand) => self.read(i, operand, Kind::Int)?[0],
+                    None => 0,
+                };
+                let address = [address[0] as i32, address[1] as i32, address[2] as i32, address[3] as i32];
+                let texel = textures.load(self.resource(i, 2).1, address, self.offset(i), sample);
+                self.write_texel(i, 2, texel, Kind::Bits)?
+            }
+            D3D11_SB_OPCODE_LD_UAV_TYPED => {
+                let address = self.read(i, &operands[1], Kind::Int)?;
+                let texel = uavs.load(self.resource(i, 2).1, address);
+                self.write_texel(i, 2, texel, Kind::Bits)?
+            }
+            D3D11_SB_OPCODE_STORE_UAV_TYPED => {
+                let address = self.read(i, &operands[1], Kind::Int)?;
+                let value = self.read(i, &operands[2], Kind::Bits)?;
+                uavs.store(self.resource(i, 0).1, address, value);
+            }
+            D3D11_SB_OPCODE_LD_RAW | D3D11_SB_OPCODE_LD_STRUCTURED => self.load_memory(i, textures, uavs)?,
+            D3D11_SB_OPCODE_STORE_RAW | D3D11_SB_OPCODE_STORE_STRUCTURED => self.store_memory(i, uavs)?,
+            D3D11_SB_OPCODE_ATOMIC_AND..=D3D11_SB_OPCODE_IMM_ATOMIC_UMIN => self.atomic(i, textures, uavs)?,
+            D3D10_SB_OPCODE_RESINFO => self.resinfo(i, textures, uavs)?,
+            D3D11_SB_OPCODE_BUFINFO => {
+                let (file, index) = self.resource(i, 1);
+                let dimensions = match file {
+                    OperandType::UnorderedAccessView => uavs.dimensions(index),
+                    _ => textures.dimensions(index, 0),
+                };
+                self.write(i, 0, [dimensions.width; 4], Kind::Int)?
+            }
+            D3D10_1_SB_OPCODE_SAMPLE_INFO | D3D10_1_SB_OPCODE_SAMPLE_POS => self.sample_info(i, textures)?,
+            // a single sample is all there is
+            D3D11_SB_OPCODE_EVAL_SNAPPED | D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX | D3D11_SB_OPCODE_EVAL_CENTROID => {
+                let value = self.read(i, &operands[1], Kind::Bits)?;
+                self.write(i, 0, value, Kind::Bits)?
+            }
+            D3D10_SB_OPCODE_IF => {
+                if !self.condition(i)? {
+                    return Ok(jump(self.targets.get(&i)));
+                }
+            }
+            D3D10_SB_OPCODE_ELSE | D3D10_SB_OPCODE_ENDLOOP => return Ok(jump(self.targets.get(&i))),
+            D3D10_SB_OPCODE_BREAK | D3D10_SB_OPCODE_CONTINUE => return Ok(jump(self.targets.get(&i))),
+            D3D10_SB_OPCODE_BREAKC | D3D10_SB_OPCODE_CONTINUEC => {
+                if self.condition(i)? {
+                    return Ok(jump(self.targets.get(&i)));
+                }
+            }
+            D3D10_SB_OPCODE_SWITCH => {
+                let selector = self.read(i, &operands[0], Kind::Int)?[0];
+                let cases = &self.cases[&i];
+                let case = cases
+                    .iter()
+                    .find(|&&(value, _)| value == Some(selector))
+                    .or_else(|| cases.iter().find(|&&(value, _)| value.is_none()));
+                return Ok(match case {
+                    Some(&(_, case)) => Flow::Jump(case + 1),
+                    None => jump(self.targets.get(&i)),
+                });
+            }
+            D3D10_SB_OPCODE_RET => return Ok(Flow::Return),
+            D3D10_SB_OPCODE_RETC => {
+                if self.condition(i)? {
+                    return Ok(Flow::Return);
+                }
+            }
+            D3D10_SB_OPCODE_DISCARD => {
+                if self.condition(i)? {
+                    return Ok(Flow::Discard);
+                }
+            }
+            D3D10_SB_OPCODE_CALL => {
+                let label = operands[0].register().map_or(0, register_index);
+                return Ok(Flow::Call(label));
+            }
+            D3D10_SB_OPCODE_CALLC => {
+                if self.condition(i)? {
+                    let label = operands[1].register().map_or(0, register_index);
+                    return Ok(Flow::Call(label));
+                }
+            }
+            D3D11_SB_OPCODE_ABORT => return Err(Error::Aborted(i)),
+            // a single invocation has nothing to wait for
+            D3D10_SB_OPCODE_LOOP | D3D10_SB_OPCODE_ENDIF | D3D10_SB_OPCODE_ENDSWITCH | D3D10_SB_OPCODE_CASE |
+            D3D10_SB_OPCODE_DEFAULT | D3D10_SB_OPCODE_NOP | D3D11_SB_OPCODE_SYNC | D3D11_SB_OPCODE_DEBUG_BREAK => {}
+            opcode => return Err(Error::Unsupported(i, opcode)),
+        }
+
+        Ok(Flow::Next)
+    }
+}
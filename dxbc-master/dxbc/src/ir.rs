@@ -0,0 +1,1423 @@
+use binary::*;
+use dr::shex::{
+    ConstantBufferIndexPattern, Immediate, OpcodeToken0, OpcodeToken1, OperandToken0, OperandType, Operands,
+    ResourceDimension, ResourceReturnType, ShexHeader, SparseInstruction, TestBoolean,
+};
+use dr::builder::{self, ComponentMode, GlobalFlags, MinPrecision, Modifier, NumComponent, OpcodeEx, ShexChunk};
+use d3d11tokenizedprogramformat::*;
+use disasm::{
+    interpolation_name, opcode_name, operand_prefix, primitive_name, resource_dimension_name, return_type_name,
+    shader_prefix, system_value_name, topology_name, COMPONENTS,
+};
+
+use std::fmt::{self, Write};
+use std::slice;
+
+/// Index into a register file, relative indices add the value of a register
+/// to the immediate part.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Index {
+    U32(u32),
+    U64(u64),
+    Relative(Box<Register>),
+    U32Relative(u32, Box<Register>),
+    U64Relative(u64, Box<Register>),
+}
+
+impl Index {
+    /// The immediate part of the index.
+    pub fn offset(&self) -> u64 {
+        match *self {
+            Index::U32(index) | Index::U32Relative(index, _) => index as u64,
+            Index::U64(index) | Index::U64Relative(index, _) => index,
+            Index::Relative(_) => 0,
+        }
+    }
+
+    /// The register added to the index, if any.
+    pub fn relative(&self) -> Option<&Register> {
+        match *self {
+            Index::Relative(ref register) | Index::U32Relative(_, ref register) | Index::U64Relative(_, ref register) => {
+                Some(register)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Components of a register an operand reads or writes, components are
+/// numbered `x` = 0 to `w` = 3.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Components {
+    /// Registers without components like samplers in most places or `null`.
+    None,
+    /// Single component registers like `oDepth`.
+    Scalar,
+    /// Written components, one bit per component.
+    Mask(u8),
+    /// Source component for each of the four lanes.
+    Swizzle([u8; 4]),
+    /// A single component replicated to all lanes.
+    Select(u8),
+}
+
+impl Components {
+    /// Components written by a destination, one bit per component.
+    pub fn mask(&self) -> u8 {
+        match *self {
+            Components::None => 0,
+            Components::Scalar => 1,
+            Components::Mask(mask) => mask,
+            Components::Swizzle(_) => 0xf,
+            Components::Select(c) => 1 << c,
+        }
+    }
+
+    /// Source component read for each lane.
+    pub fn swizzle(&self) -> [u8; 4] {
+        match *self {
+            Components::Swizzle(swizzle) => swizzle,
+            Components::Select(c) => [c; 4],
+            _ => [0, 1, 2, 3],
+        }
+    }
+
+    fn from_word(word: u32) -> Self {
+        match DECODE_D3D10_SB_OPERAND_NUM_COMPONENTS(word) {
+            D3D10_SB_OPERAND_1_COMPONENT => Components::Scalar,
+            D3D10_SB_OPERAND_4_COMPONENT => match DECODE_D3D10_SB_OPERAND_4_COMPONENT_SELECTION_MODE(word) {
+                D3D10_SB_OPERAND_4_COMPONENT_MASK_MODE => {
+                    Components::Mask((DECODE_D3D10_SB_OPERAND_4_COMPONENT_MASK(word) >> 4) as u8)
+                }
+                D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_MODE => {
+                    let mut swizzle = [0; 4];
+                    for (c, source) in swizzle.iter_mut().enumerate() {
+                        *source = DECODE_D3D10_SB_OPERAND_4_COMPONENT_SWIZZLE_SOURCE(word, c as u32) as u8;
+                    }
+                    Components::Swizzle(swizzle)
+                }
+                _ => Components::Select(DECODE_D3D10_SB_OPERAND_4_COMPONENT_SELECT_1(word) as u8),
+            },
+            _ => Components::None,
+        }
+    }
+
+    fn to_builder(self) -> NumComponent {
+        // the builder takes components as the X, Y, Z and W bits
+        let bit = |c: u8| builder::X << c;
+
+        match self {
+            Components::None => NumComponent::D0,
+            Components::Scalar => NumComponent::D1,
+            Components::Mask(mask) => NumComponent::D4(ComponentMode::Mask(mask << 4)),
+            Components::Swizzle(s) => NumComponent::D4(ComponentMode::Swizzle(bit(s[0]), bit(s[1]), bit(s[2]), bit(s[3]))),
+            Components::Select(c) => NumComponent::D4(ComponentMode::Select(bit(c))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Register {
+    pub file: OperandType,
+    pub index: Vec<Index>,
+    pub components: Components,
+    pub modifier: Modifier,
+    pub precision: MinPrecision,
+}
+
+impl Register {
+    pub fn new(file: OperandType, index: Vec<Index>, components: Components) -> Self {
+        Register {
+            file,
+            index,
+            components,
+            modifier: Modifier::None,
+            precision: MinPrecision::Default,
+        }
+    }
+
+    fn to_builder(&self) -> builder::Operand {
+        let indices = self.index
+            .iter()
+            .map(|index| match *index {
+                Index::U32(index) => builder::Immediate::U32(index),
+                Index::U64(index) => builder::Immediate::U64(index),
+                Index::Relative(ref register) => builder::Immediate::Relative(register.to_builder()),
+                Index::U32Relative(index, ref register) => builder::Immediate::U32Relative(index, register.to_builder()),
+                Index::U64Relative(index, ref register) => builder::Immediate::U64Relative(index, register.to_builder()),
+            })
+            .collect();
+
+        let mut operand = builder::Operand::new(
+            builder::OperandType::Raw(self.file as u32, indices),
+            self.modifier,
+            self.components.to_builder(),
+        );
+        operand.set_min_precision(self.precision);
+        operand
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Register(Register),
+    /// 32-bit immediates, one or four values.
+    Imm32(Vec<u32>),
+    /// 64-bit immediates, one or four values.
+    Imm64(Vec<u64>),
+}
+
+impl Operand {
+    pub fn register(&self) -> Option<&Register> {
+        match *self {
+            Operand::Register(ref register) => Some(register),
+            _ => None,
+        }
+    }
+
+    pub fn to_builder(&self) -> builder::Operand {
+        match *self {
+            Operand::Register(ref register) => register.to_builder(),
+            Operand::Imm32(ref values) => {
+                let value = |i: usize| values.get(i).cloned().unwrap_or(0);
+                let ty = match values.len() {
+                    1 => builder::OperandType::Imm32(value(0)),
+                    _ => builder::OperandType::Imm32x4(value(0), value(1), value(2), value(3)),
+                };
+                builder::Operand::new(ty, Modifier::None, NumComponent::D1)
+            }
+            Operand::Imm64(ref values) => {
+                builder::Operand::new(builder::OperandType::Imm64(values.clone()), Modifier::None, NumComponent::D1)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub opcode: u32,
+    pub saturate: bool,
+    /// Condition of `if`, `breakc`, `continuec`, `retc`, `discard` and `callc`.
+    pub test: Option<TestBoolean>,
+    /// Remaining opcode specific bits in their opcode token position, the
+    /// flags of `sync` and return type of `resinfo` and `sampleinfo`.
+    pub controls: u32,
+    pub extended: Vec<OpcodeEx>,
+    pub operands: Vec<Operand>,
+}
+
+impl Instruction {
+    pub fn new(opcode: u32, operands: Vec<Operand>) -> Self {
+        Instruction {
+            opcode,
+            saturate: false,
+            test: None,
+            controls: 0,
+            extended: Vec::new(),
+            operands,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        opcode_name(self.opcode).unwrap_or("unknown")
+    }
+
+    pub fn to_builder(&self) -> builder::Instruction {
+        let mut controls = self.controls | ENCODE_D3D10_SB_INSTRUCTION_SATURATE(self.saturate as u32);
+        if let Some(test) = self.test {
+            controls |= ENCODE_D3D10_SB_INSTRUCTION_TEST_BOOLEAN(test as u32);
+        }
+
+        builder::Instruction::Generic {
+            opcode: self.opcode,
+            controls,
+            extended: self.extended.clone(),
+            operands: self.operands.iter().map(Operand::to_builder).collect(),
+            data: Vec::new(),
+        }
+    }
+
+    // Operands are decoded straight from the token stream so every
+    // instruction is lowered the same way regardless of its typed layout.
+    fn lower(offset: usize, opcode: &OpcodeToken0) -> Result<Self, Error> {
+        let word = unsafe { opcode.word.read_unaligned() };
+        let ty = opcode.get_opcode_type();
+
+        let test = match ty {
+            D3D10_SB_OPCODE_IF | D3D10_SB_OPCODE_BREAKC | D3D10_SB_OPCODE_CONTINUEC |
+            D3D10_SB_OPCODE_RETC | D3D10_SB_OPCODE_DISCARD | D3D10_SB_OPCODE_CALLC => Some(opcode.get_test_type()),
+            _ => None,
+        };
+        let controls = match ty {
+            // the sync flags overlap the saturate bit
+            D3D11_SB_OPCODE_SYNC => return Ok(Instruction { controls: DECODE_D3D11_SB_SYNC_FLAGS(word), ..Instruction::new(ty, Vec::new()) }),
+            D3D10_SB_OPCODE_RESINFO => word & D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE_MASK,
+            D3D10_1_SB_OPCODE_SAMPLE_INFO => word & D3D10_SB_INSTRUCTION_RETURN_TYPE_MASK,
+            _ => 0,
+        };
+
+        let mut start = 1;
+        let mut extended = Vec::new();
+        let mut token = opcode.get_extended_opcode();
+        while let Some(ex) = token {
+            start += 1;
+            extended.extend(lower_extended_opcode(offset, &ex)?);
+            token = ex.get_extended_opcode();
+        }
+
+        // the parser already checked that the whole instruction is in bounds
+        let length = (opcode.get_instruction_length() as usize).saturating_sub(start);
+        let bytes = unsafe { slice::from_raw_parts(opcode.word.add(start) as *const u8, length * 4) };
+
+        Ok(Instruction {
+            opcode: ty,
+            saturate: opcode.is_saturated(),
+            test,
+            controls,
+            extended,
+            operands: lower_operands(offset, bytes)?,
+        })
+    }
+
+    fn mnemonic(&self) -> String {
+        let mut name = self.name().to_owned();
+
+        if let Some(test) = self.test {
+            name.push_str(match test {
+                TestBoolean::Zero => "_z",
+                TestBoolean::NonZero => "_nz",
+            });
+        }
+
+        if self.opcode == D3D11_SB_OPCODE_SYNC {
+            let flags = [
+                (D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GLOBAL, "_uglobal"),
+                (D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GROUP, "_ugroup"),
+                (D3D11_SB_SYNC_THREAD_GROUP_SHARED_MEMORY, "_g"),
+                (D3D11_SB_SYNC_THREADS_IN_GROUP, "_t"),
+            ];
+            for &(flag, suffix) in flags.iter() {
+                if self.controls & flag != 0 {
+                    name.push_str(suffix);
+                }
+            }
+            return name;
+        }
+
+        if self.saturate {
+            name.push_str("_sat");
+        }
+
+        let mut arguments = String::new();
+        for ex in &self.extended {
+            match *ex {
+                OpcodeEx::UvOffset(u, v, w) => {
+                    name.push_str("_aoffimmi");
+                    let _ = write!(arguments, "({},{},{})", u as i32, v as i32, w as i32);
+                }
+                OpcodeEx::Dimension(dimension, stride) => {
+                    name.push_str("_indexable");
+                    let dimension = dimension as u32;
+                    if dimension == D3D11_SB_RESOURCE_DIMENSION_STRUCTURED_BUFFER {
+                        let _ = write!(arguments, "({}, stride={})", resource_dimension_name(dimension), stride);
+                    } else {
+                        let _ = write!(arguments, "({})", resource_dimension_name(dimension));
+                    }
+                }
+                OpcodeEx::ResourceReturnType(x, y, z, w) => {
+                    arguments.push_str(&format_return_types([x as u32, y as u32, z as u32, w as u32]));
+                }
+            }
+        }
+        name.push_str(&arguments);
+
+        match self.opcode {
+            D3D10_SB_OPCODE_RESINFO => match DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(self.controls) {
+                D3D10_SB_RESINFO_INSTRUCTION_RETURN_RCPFLOAT => name.push_str("_rcpFloat"),
+                D3D10_SB_RESINFO_INSTRUCTION_RETURN_UINT => name.push_str("_uint"),
+                _ => {}
+            },
+            D3D10_1_SB_OPCODE_SAMPLE_INFO
+                if DECODE_D3D10_SB_INSTRUCTION_RETURN_TYPE(self.controls) == D3D10_SB_INSTRUCTION_RETURN_UINT => {
+                name.push_str("_uint")
+            }
+            _ => {}
+        }
+
+        name
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let literal = Literal::from_opcode(self.opcode);
+        let operands: Vec<String> = self.operands
+            .iter()
+            .map(|operand| format_operand(operand, literal, f.alternate()))
+            .collect();
+
+        f.write_str(&self.mnemonic())?;
+        if !operands.is_empty() {
+            write!(f, " {}", operands.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Texel offsets are kept sign extended, the builder masks them back to
+// four bits.
+fn lower_extended_opcode(offset: usize, token: &OpcodeToken1) -> Result<Option<OpcodeEx>, Error> {
+    let word = unsafe { token.word.read_unaligned() };
+
+    Ok(Some(match DECODE_D3D10_SB_EXTENDED_OPCODE_TYPE(word) {
+        D3D10_SB_EXTENDED_OPCODE_SAMPLE_CONTROLS => OpcodeEx::UvOffset(
+            token.get_address_offset(D3D10_SB_IMMEDIATE_ADDRESS_OFFSET_U) as u32,
+            token.get_address_offset(D3D10_SB_IMMEDIATE_ADDRESS_OFFSET_V) as u32,
+            token.get_address_offset(D3D10_SB_IMMEDIATE_ADDRESS_OFFSET_W) as u32,
+        ),
+        D3D11_SB_EXTENDED_OPCODE_RESOURCE_DIM => {
+            let dimension = ResourceDimension::from_word(DECODE_D3D11_SB_EXTENDED_RESOURCE_DIMENSION(word))
+                .ok_or(Error::InvalidToken(offset, word))?;
+
+            OpcodeEx::Dimension(dimension, DECODE_D3D11_SB_EXTENDED_RESOURCE_DIMENSION_STRUCTURE_STRIDE(word))
+        }
+        D3D11_SB_EXTENDED_OPCODE_RESOURCE_RETURN_TYPE => {
+            let mut types = [ResourceReturnType::Unused; 4];
+            for (c, ty) in types.iter_mut().enumerate() {
+                *ty = ResourceReturnType::from_word(DECODE_D3D11_SB_EXTENDED_RESOURCE_RETURN_TYPE(word, c as u32))
+                    .ok_or(Error::InvalidToken(offset, word))?;
+            }
+
+            OpcodeEx::ResourceReturnType(types[0], types[1], types[2], types[3])
+        }
+        _ => return Ok(None),
+    }))
+}
+
+pub(crate) fn lower_operands(offset: usize, bytes: &[u8]) -> Result<Vec<Operand>, Error> {
+    let mut decoder = decoder::Decoder::new(bytes);
+
+    // trailing tokens that aren't operands, like the function index of
+    // `fcall`, are left out
+    let mut operands = Vec::new();
+    while !decoder.eof() {
+        match OperandToken0::parse(&mut decoder) {
+            Ok(operand) => operands.push(lower_operand(offset, &operand)?),
+            Err(_) => break,
+        }
+    }
+
+    Ok(operands)
+}
+
+fn lower_operand(offset: usize, operand: &OperandToken0) -> Result<Operand, Error> {
+    let word = unsafe { operand.word.read_unaligned() };
+    let data = unsafe { operand.word.offset(if operand.is_extended() { 2 } else { 1 }) };
+    let count = match DECODE_D3D10_SB_OPERAND_NUM_COMPONENTS(word) {
+        D3D10_SB_OPERAND_1_COMPONENT => 1,
+        D3D10_SB_OPERAND_4_COMPONENT => 4,
+        _ => 0,
+    };
+
+    match operand.get_operand_type() {
+        OperandType::Immediate32 => {
+            return Ok(Operand::Imm32((0..count).map(|i| unsafe { data.offset(i).read_unaligned() }).collect()));
+        }
+        OperandType::Immediate64 => {
+            return Ok(Operand::Imm64(
+                (0..count).map(|i| unsafe { (data.offset(2 * i) as *const u64).read_unaligned() }).collect(),
+            ));
+        }
+        _ => {}
+    }
+
+    let relative = |operand: &OperandToken0| match lower_operand(offset, operand)? {
+        Operand::Register(register) => Ok(Box::new(register)),
+        _ => Err(Error::InvalidToken(offset, word)),
+    };
+
+    let index = operand
+        .get_immediates()
+        .iter()
+        .map(|index| Ok(match *index {
+            Immediate::U32(index) => Index::U32(index),
+            Immediate::U64(index) => Index::U64(index),
+            Immediate::Relative(ref operand) => Index::Relative(relative(operand)?),
+            Immediate::U32Relative(index, ref operand) => Index::U32Relative(index, relative(operand)?),
+            Immediate::U64Relative(index, ref operand) => Index::U64Relative(index, relative(operand)?),
+        }))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    // the consumers of the IR index these without checking
+    let dimension = match operand.get_operand_type() {
+        OperandType::IndexableTemp | OperandType::ConstantBuffer => 2,
+        OperandType::Temp |
+        OperandType::Input |
+        OperandType::Output |
+        OperandType::Sampler |
+        OperandType::Resource |
+        OperandType::ImmediateConstantBuffer |
+        OperandType::Label |
+        OperandType::UnorderedAccessView |
+        OperandType::ThreadGroupSharedMemory => 1,
+        _ => 0,
+    };
+    if index.len() < dimension {
+        return Err(Error::InvalidToken(offset, word));
+    }
+
+    let mut register = Register::new(operand.get_operand_type(), index, Components::from_word(word));
+
+    if let Some(extended) = operand.get_extended_operand() {
+        let extended = unsafe { extended.word.read_unaligned() };
+
+        if DECODE_D3D10_SB_EXTENDED_OPERAND_TYPE(extended) == D3D10_SB_EXTENDED_OPERAND_MODIFIER {
+            register.modifier = match DECODE_D3D10_SB_OPERAND_MODIFIER(extended) {
+                D3D10_SB_OPERAND_MODIFIER_NEG => Modifier::Neg,
+                D3D10_SB_OPERAND_MODIFIER_ABS => Modifier::Abs,
+                D3D10_SB_OPERAND_MODIFIER_ABSNEG => Modifier::AbsNeg,
+                _ => Modifier::None,
+            };
+            register.precision = match DECODE_D3D11_SB_OPERAND_MIN_PRECISION(extended) {
+                D3D11_SB_OPERAND_MIN_PRECISION_FLOAT_16 => MinPrecision::Float16,
+                D3D11_SB_OPERAND_MIN_PRECISION_FLOAT_2_8 => MinPrecision::Float2_8,
+                D3D11_SB_OPERAND_MIN_PRECISION_SINT_16 => MinPrecision::Sint16,
+                D3D11_SB_OPERAND_MIN_PRECISION_UINT_16 => MinPrecision::Uint16,
+                _ => MinPrecision::Default,
+            };
+        }
+    }
+
+    Ok(Operand::Register(register))
+}
+
+/// Declarations, system values, interpolation modes and the like are kept
+/// as their token values.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Declaration {
+    GlobalFlags(GlobalFlags),
+    ImmediateConstantBuffer(Vec<u32>),
+    ConstantBuffer { register: Register, access: ConstantBufferIndexPattern },
+    Sampler { register: Register, mode: u32 },
+    Resource { register: Register, dimension: u32, sample_count: u32, return_type: [u32; 4] },
+    ResourceRaw { register: Register },
+    ResourceStructured { register: Register, stride: u32 },
+    UnorderedAccessViewTyped { register: Register, dimension: u32, return_type: [u32; 4], globally_coherent: bool, has_counter: bool },
+    UnorderedAccessViewRaw { register: Register, globally_coherent: bool, has_counter: bool },
+    UnorderedAccessViewStructured { register: Register, stride: u32, globally_coherent: bool, has_counter: bool },
+    ThreadGroupSharedMemoryRaw { register: Register, byte_count: u32 },
+    ThreadGroupSharedMemoryStructured { register: Register, stride: u32, count: u32 },
+    Input { register: Register },
+    InputSgv { register: Register, name: u32 },
+    InputSiv { register: Register, name: u32 },
+    InputPs { register: Register, interpolation: u32 },
+    InputPsSgv { register: Register, interpolation: u32, name: u32 },
+    InputPsSiv { register: Register, interpolation: u32, name: u32 },
+    Output { register: Register },
+    OutputSgv { register: Register, name: u32 },
+    OutputSiv { register: Register, name: u32 },
+    IndexRange { register: Register, count: u32 },
+    Temps(u32),
+    IndexableTemp { index: u32, count: u32, components: u32 },
+    GsInputPrimitive(u32),
+    GsOutputPrimitiveTopology(u32),
+    MaxOutputVertexCount(u32),
+    GsInstanceCount(u32),
+    Stream { register: Register },
+    InputControlPointCount(u32),
+    OutputControlPointCount(u32),
+    TessDomain(u32),
+    TessPartitioning(u32),
+    TessOutputPrimitive(u32),
+    HsMaxTessfactor(f32),
+    HsForkPhaseInstanceCount(u32),
+    HsJoinPhaseInstanceCount(u32),
+    ThreadGroup(u32, u32, u32),
+    FunctionBody(u32),
+    FunctionTable { table: u32, bodies: Vec<u32> },
+    Interface { interface: u32, dynamically_indexed: bool, expected_table_length: u32, array_length: u32, tables: Vec<u32> },
+}
+
+impl Declaration {
+    pub fn opcode(&self) -> u32 {
+        match *self {
+            Declaration::GlobalFlags(..) => D3D10_SB_OPCODE_DCL_GLOBAL_FLAGS,
+            Declaration::ImmediateConstantBuffer(..) => D3D10_SB_OPCODE_CUSTOMDATA,
+            Declaration::ConstantBuffer { .. } => D3D10_SB_OPCODE_DCL_CONSTANT_BUFFER,
+            Declaration::Sampler { .. } => D3D10_SB_OPCODE_DCL_SAMPLER,
+            Declaration::Resource { .. } => D3D10_SB_OPCODE_DCL_RESOURCE,
+            Declaration::ResourceRaw { .. } => D3D11_SB_OPCODE_DCL_RESOURCE_RAW,
+            Declaration::ResourceStructured { .. } => D3D11_SB_OPCODE_DCL_RESOURCE_STRUCTURED,
+            Declaration::UnorderedAccessViewTyped { .. } => D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_TYPED,
+            Declaration::UnorderedAccessViewRaw { .. } => D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_RAW,
+            Declaration::UnorderedAccessViewStructured { .. } => D3D11_SB_OPCODE_DCL_UNORDERED_ACCESS_VIEW_STRUCTURED,
+            Declaration::ThreadGroupSharedMemoryRaw { .. } => D3D11_SB_OPCODE_DCL_THREAD_GROUP_SHARED_MEMORY_RAW,
+            Declaration::ThreadGroupSharedMemoryStructured { .. } => D3D11_SB_OPCODE_DCL_THREAD_GROUP_SHARED_MEMORY_STRUCTURED,
+            Declaration::Input { .. } => D3D10_SB_OPCODE_DCL_INPUT,
+            Declaration::InputSgv { .. } => D3D10_SB_OPCODE_DCL_INPUT_SGV,
+            Declaration::InputSiv { .. } => D3D10_SB_OPCODE_DCL_INPUT_SIV,
+            Declaration::InputPs { .. } => D3D10_SB_OPCODE_DCL_INPUT_PS,
+            Declaration::InputPsSgv { .. } => D3D10_SB_OPCODE_DCL_INPUT_PS_SGV,
+            Declaration::InputPsSiv { .. } => D3D10_SB_OPCODE_DCL_INPUT_PS_SIV,
+            Declaration::Output { .. } => D3D10_SB_OPCODE_DCL_OUTPUT,
+            Declaration::OutputSgv { .. } => D3D10_SB_OPCODE_DCL_OUTPUT_SGV,
+            Declaration::OutputSiv { .. } => D3D10_SB_OPCODE_DCL_OUTPUT_SIV,
+            Declaration::IndexRange { .. } => D3D10_SB_OPCODE_DCL_INDEX_RANGE,
+            Declaration::Temps(..) => D3D10_SB_OPCODE_DCL_TEMPS,
+            Declaration::IndexableTemp { .. } => D3D10_SB_OPCODE_DCL_INDEXABLE_TEMP,
+            Declaration::GsInputPrimitive(..) => D3D10_SB_OPCODE_DCL_GS_INPUT_PRIMITIVE,
+            Declaration::GsOutputPrimitiveTopology(..) => D3D10_SB_OPCODE_DCL_GS_OUTPUT_PRIMITIVE_TOPOLOGY,
+            Declaration::MaxOutputVertexCount(..) => D3D10_SB_OPCODE_DCL_MAX_OUTPUT_VERTEX_COUNT,
+            Declaration::GsInstanceCount(..) => D3D11_SB_OPCODE_DCL_GS_INSTANCE_COUNT,
+            Declaration::Stream { .. } => D3D11_SB_OPCODE_DCL_STREAM,
+            Declaration::InputControlPointCount(..) => D3D11_SB_OPCODE_DCL_INPUT_CONTROL_POINT_COUNT,
+            Declaration::OutputControlPointCount(..) => D3D11_SB_OPCODE_DCL_OUTPUT_CONTROL_POINT_COUNT,
+            Declaration::TessDomain(..) => D3D11_SB_OPCODE_DCL_TESS_DOMAIN,
+            Declaration::TessPartitioning(..) => D3D11_SB_OPCODE_DCL_TESS_PARTITIONING,
+            Declaration::TessOutputPrimitive(..) => D3D11_SB_OPCODE_DCL_TESS_OUTPUT_PRIMITIVE,
+            Declaration::HsMaxTessfactor(..) => D3D11_SB_OPCODE_DCL_HS_MAX_TESSFACTOR,
+            Declaration::HsForkPhaseInstanceCount(..) => D3D11_SB_OPCODE_DCL_HS_FORK_PHASE_INSTANCE_COUNT,
+            Declaration::HsJoinPhaseInstanceCount(..) => D3D11_SB_OPCODE_DCL_HS_JOIN_PHASE_INSTANCE_COUNT,
+            Declaration::ThreadGroup(..) => D3D11_SB_OPCODE_DCL_THREAD_GROUP,
+            Declaration::FunctionBody(..) => D3D11_SB_OPCODE_DCL_FUNCTION_BODY,
+            Declaration::FunctionTable { .. } => D3D11_SB_OPCODE_DCL_FUNCTION_TABLE,
+            Declaration::Interface { .. } => D3D11_SB_OPCODE_DCL_INTERFACE,
+        }
+    }
+
+    /// The declared register, if the declaration has one.
+    pub fn register(&self) -> Option<&Register> {
+        match *self {
+            Declaration::ConstantBuffer { ref register, .. } |
+            Declaration::Sampler { ref register, .. } |
+            Declaration::Resource { ref register, .. } |
+            Declaration::ResourceRaw { ref register } |
+            Declaration::ResourceStructured { ref register, .. } |
+            Declaration::UnorderedAccessViewTyped { ref register, .. } |
+            Declaration::UnorderedAccessViewRaw { ref register, .. } |
+            Declaration::UnorderedAccessViewStructured { ref register, .. } |
+            Declaration::ThreadGroupSharedMemoryRaw { ref register, .. } |
+            Declaration::ThreadGroupSharedMemoryStructured { ref register, .. } |
+            Declaration::Input { ref register } |
+            Declaration::InputSgv { ref register, .. } |
+            Declaration::InputSiv { ref register, .. } |
+            Declaration::InputPs { ref register, .. } |
+            Declaration::InputPsSgv { ref register, .. } |
+            Declaration::InputPsSiv { ref register, .. } |
+            Declaration::Output { ref register } |
+            Declaration::OutputSgv { ref register, .. } |
+            Declaration::OutputSiv { ref register, .. } |
+            Declaration::IndexRange { ref register, .. } |
+            Declaration::Stream { ref register } => Some(register),
+            _ => None,
+        }
+    }
+
+    pub fn to_builder(&self) -> builder::Instruction {
+        let uav_flags = |globally_coherent: bool, has_counter: bool| {
+            let mut flags = 0;
+            if globally_coherent {
+                flags |= ENCODE_D3D11_SB_ACCESS_COHERENCY_FLAGS(D3D11_SB_GLOBALLY_COHERENT_ACCESS);
+            }
+            if has_counter {
+                flags |= ENCODE_D3D11_SB_UAV_FLAGS(D3D11_SB_UAV_HAS_ORDER_PRESERVING_COUNTER);
+            }
+            flags
+        };
+        let return_types = |types: &[u32; 4]| {
+            (0..4).fold(0, |token, c| token | ENCODE_D3D10_SB_RESOURCE_RETURN_TYPE(types[c], c as u32))
+        };
+
+        let (controls, data) = match *self {
+            Declaration::ImmediateConstantBuffer(ref data) => {
+                return builder::Instruction::CustomData {
+                    class: D3D10_SB_CUSTOMDATA_DCL_IMMEDIATE_CONSTANT_BUFFER,
+                    data: data.clone(),
+                };
+            }
+            Declaration::GlobalFlags(flags) => (flags.bits(), vec![]),
+            Declaration::ConstantBuffer { access, .. } => {
+                (ENCODE_D3D10_SB_D3D10_SB_CONSTANT_BUFFER_ACCESS_PATTERN(access as u32), vec![])
+            }
+            Declaration::Sampler { mode, .. } => (ENCODE_D3D10_SB_SAMPLER_MODE(mode), vec![]),
+            Declaration::Resource { dimension, sample_count, ref return_type, .. } => (
+                ENCODE_D3D10_SB_RESOURCE_DIMENSION(dimension) | ENCODE_D3D10_SB_RESOURCE_SAMPLE_COUNT(sample_count),
+                vec![return_types(return_type)],
+            ),
+            Declaration::ResourceStructured { stride, .. } => (0, vec![stride]),
+            Declaration::UnorderedAccessViewTyped { dimension, ref return_type, globally_coherent, has_counter, .. } => (
+                ENCODE_D3D10_SB_RESOURCE_DIMENSION(dimension) | uav_flags(globally_coherent, has_counter),
+                vec![return_types(return_type)],
+            ),
+            Declaration::UnorderedAccessViewRaw { globally_coherent, has_counter, .. } => {
+                (uav_flags(globally_coherent, has_counter), vec![])
+            }
+            Declaration::UnorderedAccessViewStructured { stride, globally_coherent, has_counter, .. } => {
+                (uav_flags(globally_coherent, has_counter), vec![stride])
+            }
+            Declaration::ThreadGroupSharedMemoryRaw { byte_count, .. } => (0, vec![byte_count]),
+            Declaration::ThreadGroupSharedMemoryStructured { stride, count, .. } => (0, vec![stride, count]),
+            Declaration::InputSgv { name, .. } |
+            Declaration::InputSiv { name, .. } |
+            Declaration::OutputSgv { name, .. } |
+            Declaration::OutputSiv { name, .. } => (0, vec![ENCODE_D3D10_SB_NAME(name)]),
+            Declaration::InputPs { interpolation, .. } => (ENCODE_D3D10_SB_INPUT_INTERPOLATION_MODE(interpolation), vec![]),
+            Declaration::InputPsSgv { interpolation, name, .. } |
+            Declaration::InputPsSiv { interpolation, name, .. } => {
+                (ENCODE_D3D10_SB_INPUT_INTERPOLATION_MODE(interpolation), vec![ENCODE_D3D10_SB_NAME(name)])
+            }
+            Declaration::IndexRange { count, .. } => (0, vec![count]),
+            Declaration::Temps(count) |
+            Declaration::MaxOutputVertexCount(count) |
+            Declaration::GsInstanceCount(count) |
+            Declaration::HsForkPhaseInstanceCount(count) |
+            Declaration::HsJoinPhaseInstanceCount(count) => (0, vec![count]),
+            Declaration::IndexableTemp { index, count, components } => (0, vec![index, count, components]),
+            Declaration::GsInputPrimitive(primitive) => (ENCODE_D3D10_SB_GS_INPUT_PRIMITIVE(primitive), vec![]),
+            Declaration::GsOutputPrimitiveTopology(topology) => (ENCODE_D3D10_SB_GS_OUTPUT_PRIMITIVE_TOPOLOGY(topology), vec![]),
+            Declaration::InputControlPointCount(count) => (ENCODE_D3D11_SB_INPUT_CONTROL_POINT_COUNT(count), vec![]),
+            Declaration::OutputControlPointCount(count) => (ENCODE_D3D11_SB_OUTPUT_CONTROL_POINT_COUNT(count), vec![]),
+            Declaration::TessDomain(domain) => (ENCODE_D3D11_SB_TESS_DOMAIN(domain), vec![]),
+            Declaration::TessPartitioning(partitioning) => (ENCODE_D3D11_SB_TESS_PARTITIONING(partitioning), vec![]),
+            Declaration::TessOutputPrimitive(primitive) => (ENCODE_D3D11_SB_TESS_OUTPUT_PRIMITIVE(primitive), vec![]),
+            Declaration::HsMaxTessfactor(factor) => (0, vec![factor.to_bits()]),
+            Declaration::ThreadGroup(x, y, z) => (0, vec![x, y, z]),
+            Declaration::FunctionBody(body) => (0, vec![body]),
+            Declaration::FunctionTable { table, ref bodies } => {
+                let mut data = vec![table, bodies.len() as u32];
+                data.extend(bodies);
+                (0, data)
+            }
+            Declaration::Interface { interface, dynamically_indexed, expected_table_length, array_length, ref tables } => {
+                let mut data = vec![
+                    interface,
+                    expected_table_length,
+                    ENCODE_D3D11_SB_INTERFACE_ARRAY_LENGTH(array_length) | ENCODE_D3D11_SB_INTERFACE_TABLE_LENGTH(tables.len() as u32),
+                ];
+                data.extend(tables);
+                (ENCODE_D3D11_SB_INTERFACE_INDEXED_BIT(dynamically_indexed as u32), data)
+            }
+            _ => (0, vec![]),
+        };
+
+        builder::Instruction::Generic {
+            opcode: self.opcode(),
+            controls,
+            extended: Vec::new(),
+            operands: self.register().map(Register::to_builder).into_iter().collect(),
+            data,
+        }
+    }
+
+    fn lower(offset: usize, opcode: &OpcodeToken0, operands: &Operands) -> Result<Option<Self>, Error> {
+        let word = unsafe { opcode.word.read_unaligned() };
+        let register = |operand: &OperandToken0| match lower_operand(offset, operand)? {
+            Operand::Register(register) => Ok(register),
+            _ => Err(Error::InvalidToken(offset, word)),
+        };
+        let return_types = |token: u32| {
+            let mut types = [0; 4];
+            for (c, ty) in types.iter_mut().enumerate() {
+                *ty = DECODE_D3D10_SB_RESOURCE_RETURN_TYPE(token, c as u32);
+            }
+            types
+        };
+        let globally_coherent = DECODE_D3D11_SB_ACCESS_COHERENCY_FLAGS(word) & D3D11_SB_GLOBALLY_COHERENT_ACCESS != 0;
+        let has_counter = DECODE_D3D11_SB_UAV_FLAGS(word) & D3D11_SB_UAV_HAS_ORDER_PRESERVING_COUNTER != 0;
+        let interpolation = DECODE_D3D10_SB_INPUT_INTERPOLATION_MODE(word);
+
+        Ok(Some(match *operands {
+            Operands::DclGlobalFlags(ref dcl) => Declaration::GlobalFlags(GlobalFlags::from_bits_truncate(dcl.global_flags)),
+            Operands::CustomData(ref data) => {
+                if data.class != D3D10_SB_CUSTOMDATA_DCL_IMMEDIATE_CONSTANT_BUFFER {
+                    return Ok(None);
+                }
+                Declaration::ImmediateConstantBuffer(data.data.clone())
+            }
+            Operands::DclConstantBuffer(ref dcl) => Declaration::ConstantBuffer {
+                register: register(&dcl.operand)?,
+                access: dcl.get_access_pattern(),
+            },
+            Operands::DclSampler(ref dcl) => Declaration::Sampler {
+                register: register(&dcl.operand)?,
+                mode: DECODE_D3D10_SB_SAMPLER_MODE(word),
+            },
+            Operands::DclResource(ref dcl) => Declaration::Resource {
+                register: register(&dcl.register)?,
+                dimension: DECODE_D3D10_SB_RESOURCE_DIMENSION(word),
+                sample_count: DECODE_D3D10_SB_RESOURCE_SAMPLE_COUNT(word),
+                return_type: return_types(unsafe { dcl.return_type.word.read_unaligned() }),
+            },
+            Operands::DclResourceRaw(ref dcl) => Declaration::ResourceRaw { register: register(&dcl.operand)? },
+            Operands::DclResourceStructured(ref dcl) => Declaration::ResourceStructured {
+                register: register(&dcl.operand)?,
+                stride: dcl.stride,
+            },
+            Operands::DclUnorderedAccessViewTyped(ref dcl) => Declaration::UnorderedAccessViewTyped {
+                register: register(&dcl.operand)?,
+                dimension: DECODE_D3D10_SB_RESOURCE_DIMENSION(word),
+                return_type: return_types(unsafe { dcl.return_type.word.read_unaligned() }),
+                globally_coherent,
+                has_counter,
+            },
+            Operands::DclUnorderedAccessViewRaw(ref dcl) => Declaration::UnorderedAccessViewRaw {
+                register: register(&dcl.operand)?,
+                globally_coherent,
+                has_counter,
+            },
+            Operands::DclUnorderedAccessViewStructured(ref dcl) => Declaration::UnorderedAccessViewStructured {
+                register: register(&dcl.operand)?,
+                stride: dcl.stride,
+                globally_coherent,
+                has_counter,
+            },
+            Operands::DclThreadGroupSharedMemoryRaw(ref dcl) => Declaration::ThreadGroupSharedMemoryRaw {
+                register: register(&dcl.operand)?,
+                byte_count: dcl.byte_count,
+            },
+            Operands::DclThreadGroupSharedMemoryStructured(ref dcl) => Declaration::ThreadGroupSharedMemoryStructured {
+                register: register(&dcl.operand)?,
+                stride: dcl.stride,
+                count: dcl.count,
+            },
+            Operands::DclInput(ref dcl) => Declaration::Input { register: register(&dcl.operand)? },
+            Operands::DclInputSgv(ref dcl) => Declaration::InputSgv { register: register(&dcl.operand)?, name: dcl.name.to_word() },
+            Operands::DclInputSiv(ref dcl) => Declaration::InputSiv { register: register(&dcl.operand)?, name: dcl.name.to_word() },
+            Operands::DclInputPs(ref dcl) => Declaration::InputPs { register: register(&dcl.operand)?, interpolation },
+            Operands::DclInputPsSgv(ref dcl) => Declaration::InputPsSgv {
+                register: register(&dcl.operand)?,
+                interpolation,
+                name: dcl.name.to_word(),
+            },
+            Operands::DclInputPsSiv(ref dcl) => Declaration::InputPsSiv {
+                register: register(&dcl.operand)?,
+                interpolation,
+                name: dcl.name.to_word(),
+            },
+            Operands::DclOutput(ref dcl) => Declaration::Output { register: register(&dcl.operand)? },
+            Operands::DclOutputSgv(ref dcl) => Declaration::OutputSgv { register: register(&dcl.operand)?, name: dcl.name.to_word() },
+            Operands::DclOutputSiv(ref dcl) => Declaration::OutputSiv { register: register(&dcl.operand)?, name: dcl.name.to_word() },
+            Operands::DclIndexRange(ref dcl) => Declaration::IndexRange {
+                register: register(&dcl.operand)?,
+                count: dcl.register_count,
+            },
+            Operands::DclTemps(ref dcl) => Declaration::Temps(dcl.register_count),
+            Operands::DclIndexableTemp(ref dcl) => Declaration::IndexableTemp {
+                index: dcl.register_index,
+                count: dcl.register_count,
+                components: dcl.num_components,
+            },
+            Operands::DclGsInputPrimitive(ref dcl) => Declaration::GsInputPrimitive(dcl.primitive),
+            Operands::DclGsOutputPrimitiveTopology(ref dcl) => Declaration::GsOutputPrimitiveTopology(dcl.primitive_topology),
+            Operands::DclMaxOutputVertexCount(ref dcl) => Declaration::MaxOutputVertexCount(dcl.count),
+            Operands::DclGsInstanceCount(ref dcl) => Declaration::GsInstanceCount(dcl.instance_count),
+            Operands::DclStream(ref dcl) => Declaration::Stream { register: register(&dcl.operand)? },
+            Operands::DclInputControlPointCount(ref dcl) => Declaration::InputControlPointCount(dcl.count),
+            Operands::DclOutputControlPointCount(ref dcl) => Declaration::OutputControlPointCount(dcl.count),
+            Operands::DclTessDomain(ref dcl) => Declaration::TessDomain(dcl.domain),
+            Operands::DclTessPartitioning(ref dcl) => Declaration::TessPartitioning(dcl.partitioning),
+            Operands::DclTessOutputPrimitive(ref dcl) => Declaration::TessOutputPrimitive(dcl.primitive),
+            Operands::DclHsMaxTessfactor(ref dcl) => Declaration::HsMaxTessfactor(dcl.max_tessfactor),
+            Operands::DclHsForkPhaseInstanceCount(ref dcl) => Declaration::HsForkPhaseInstanceCount(dcl.instance_count),
+            Operands::DclHsJoinPhaseInstanceCount(ref dcl) => Declaration::HsJoinPhaseInstanceCount(dcl.instance_count),
+            Operands::DclThreadGroup(ref dcl) => Declaration::ThreadGroup(dcl.x, dcl.y, dcl.z),
+            Operands::DclFunctionBody(ref dcl) => Declaration::FunctionBody(dcl.function_body),
+            Operands::DclFunctionTable(ref dcl) => Declaration::FunctionTable {
+                table: dcl.function_table,
+                bodies: dcl.function_bodies.clone(),
+            },
+            Operands::DclInterface(ref dcl) => Declaration::Interface {
+                interface: dcl.interface,
+                dynamically_indexed: dcl.dynamically_indexed,
+                expected_table_length: dcl.expected_table_length,
+                array_length: dcl.array_length,
+                tables: dcl.function_tables.clone(),
+            },
+            _ => return Ok(None),
+        }))
+    }
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let uav_flags = |globally_coherent: bool, has_counter: bool| match (globally_coherent, has_counter) {
+            (true, true) => "_glc_opc",
+            (true, false) => "_glc",
+            (false, true) => "_opc",
+            (false, false) => "",
+        };
+
+        match *self {
+            Declaration::GlobalFlags(flags) => {
+                let names = [
+                    (GlobalFlags::REFACTORING_ALLOWED, "refactoringAllowed"),
+                    (GlobalFlags::ENABLE_DOUBLE_PRECISION_FLOAT_OPS, "enableDoublePrecisionFloatOps"),
+                    (GlobalFlags::FORCE_EARLY_DEPTH_STENCIL, "forceEarlyDepthStencil"),
+                    (GlobalFlags::ENABLE_RAW_AND_STRUCTURED_BUFFERS, "enableRawAndStructuredBuffers"),
+                    (GlobalFlags::SKIP_OPTIMIZATION, "skipOptimization"),
+                    (GlobalFlags::ENABLE_MINIMUM_PRECISION, "enableMinimumPrecision"),
+                    (GlobalFlags::ENABLE_DOUBLE_EXTENSIONS, "enable11_1DoubleExtensions"),
+                    (GlobalFlags::ENABLE_SHADER_EXTENSIONS, "enable11_1ShaderExtensions"),
+                ];
+                let names: Vec<&str> = names
+                    .iter()
+                    .filter(|&&(flag, _)| flags.contains(flag))
+                    .map(|&(_, name)| name)
+                    .collect();
+
+                write!(f, "dcl_globalFlags {}", names.join(" | "))
+            }
+            Declaration::ImmediateConstantBuffer(ref data) => {
+                let rows: Vec<String> = data
+                    .chunks(4)
+                    .map(|row| {
+                        let values: Vec<String> = row.iter().map(|&v| format_literal(v, Literal::Typeless, f.alternate())).collect();
+                        format!("{{ {}}}", values.join(", "))
+                    })
+                    .collect();
+
+                if rows.is_empty() {
+                    return write!(f, "dcl_immediateConstantBuffer {{ }}");
+                }
+
+                let last = rows.len() - 1;
+                for (i, row) in rows.iter().enumerate() {
+                    let prefix = if i == 0 { "dcl_immediateConstantBuffer { " } else { "\n                              " };
+                    let suffix = if i == last { " }" } else { "," };
+                    write!(f, "{}{}{}", prefix, row, suffix)?;
+                }
+                Ok(())
+            }
+            Declaration::ConstantBuffer { ref register, access } => {
+                let pattern = match access {
+                    ConstantBufferIndexPattern::Immediate => "immediateIndexed",
+                    ConstantBufferIndexPattern::Dynamic => "dynamicIndexed",
+                };
+
+                // the declaration spells the register in upper case
+                write!(f, "dcl_constantbuffer {}, {}", format_index(register).replacen("cb", "CB", 1), pattern)
+            }
+            Declaration::Sampler { ref register, mode } => {
+                let mode = match mode {
+                    D3D10_SB_SAMPLER_MODE_COMPARISON => "mode_comparison",
+                    D3D10_SB_SAMPLER_MODE_MONO => "mode_mono",
+                    _ => "mode_default",
+                };
+
+                write!(f, "dcl_sampler {}, {}", format_register(register), mode)
+            }
+            Declaration::Resource { ref register, dimension, sample_count, ref return_type } => {
+                let samples = match dimension {
+                    D3D10_SB_RESOURCE_DIMENSION_TEXTURE2DMS |
+                    D3D10_SB_RESOURCE_DIMENSION_TEXTURE2DMSARRAY => format!("({})", sample_count),
+                    _ => String::new(),
+                };
+
+                write!(
+                    f,
+                    "dcl_resource_{}{} {} {}",
+                    resource_dimension_name(dimension),
+                    samples,
+                    format_return_types(*return_type),
+                    format_register(register),
+                )
+            }
+            Declaration::ResourceRaw { ref register } => write!(f, "dcl_resource_raw {}", format_register(register)),
+            Declaration::ResourceStructured { ref register, stride } => {
+                write!(f, "dcl_resource_structured {}, {}", format_register(register), stride)
+            }
+            Declaration::UnorderedAccessViewTyped { ref register, dimension, ref return_type, globally_coherent, has_counter } => {
+                write!(
+                    f,
+                    "dcl_uav_typed_{}{} {} {}",
+                    resource_dimension_name(dimension),
+                    uav_flags(globally_coherent, has_counter),
+                    format_return_types(*return_type),
+                    format_register(register),
+                )
+            }
+            Declaration::UnorderedAccessViewRaw { ref register, globally_coherent, has_counter } => {
+                write!(f, "dcl_uav_raw{} {}", uav_flags(globally_coherent, has_counter), format_register(register))
+            }
+            Declaration::UnorderedAccessViewStructured { ref register, stride, globally_coherent, has_counter } => {
+                write!(f, "dcl_uav_structured{} {}, {}", uav_flags(globally_coherent, has_counter), format_register(register), stride)
+            }
+            Declaration::ThreadGroupSharedMemoryRaw { ref register, byte_count } => {
+                write!(f, "dcl_tgsm_raw {}, {}", format_register(register), byte_count)
+            }
+            Declaration::ThreadGroupSharedMemoryStructured { ref register, stride, count } => {
+                write!(f, "dcl_tgsm_structured {}, {}, {}", format_register(register), stride, count)
+            }
+            Declaration::Input { ref register } => write!(f, "dcl_input {}", format_register(register)),
+            Declaration::Output { ref register } => write!(f, "dcl_output {}", format_register(register)),
+            Declaration::InputPs { ref register, interpolation } => {
+                write!(f, "dcl_input_ps {} {}", interpolation_name(interpolation), format_register(register))
+            }
+            Declaration::InputPsSiv { ref register, interpolation, name } => {
+                write!(f, "dcl_input_ps_siv {} {}, {}", interpolation_name(interpolation), format_register(register), system_value_name(name))
+            }
+            Declaration::InputPsSgv { ref register, interpolation, name } => {
+                write!(f, "dcl_input_ps_sgv {} {}, {}", interpolation_name(interpolation), format_register(register), system_value_name(name))
+            }
+            Declaration::InputSiv { ref register, name } => write!(f, "dcl_input_siv {}, {}", format_register(register), system_value_name(name)),
+            Declaration::InputSgv { ref register, name } => write!(f, "dcl_input_sgv {}, {}", format_register(register), system_value_name(name)),
+            Declaration::OutputSiv { ref register, name } => write!(f, "dcl_output_siv {}, {}", format_register(register), system_value_name(name)),
+            Declaration::OutputSgv { ref register, name } => write!(f, "dcl_output_sgv {}, {}", format_register(register), system_value_name(name)),
+            Declaration::IndexRange { ref register, count } => write!(f, "dcl_indexrange {} {}", format_register(register), count),
+            Declaration::Temps(count) => write!(f, "dcl_temps {}", count),
+            Declaration::IndexableTemp { index, count, components } => {
+                write!(f, "dcl_indexableTemp x{}[{}], {}", index, count, components)
+            }
+            Declaration::GsInputPrimitive(primitive) => write!(f, "dcl_inputprimitive {}", primitive_name(primitive)),
+            Declaration::GsOutputPrimitiveTopology(topology) => write!(f, "dcl_outputtopology {}", topology_name(topology)),
+            Declaration::MaxOutputVertexCount(count) => write!(f, "dcl_maxout {}", count),
+            Declaration::GsInstanceCount(count) => write!(f, "dcl_gsinstances {}", count),
+            Declaration::Stream { ref register } => write!(f, "dcl_stream {}", format_register(register)),
+            Declaration::InputControlPointCount(count) => write!(f, "dcl_input_control_point_count {}", count),
+            Declaration::OutputControlPointCount(count) => write!(f, "dcl_output_control_point_count {}", count),
+            Declaration::TessDomain(domain) => {
+                let domain = match domain {
+                    D3D11_SB_TESSELLATOR_DOMAIN_ISOLINE => "domain_isoline",
+                    D3D11_SB_TESSELLATOR_DOMAIN_TRI => "domain_tri",
+                    D3D11_SB_TESSELLATOR_DOMAIN_QUAD => "domain_quad",
+                    _ => "domain_undefined",
+                };
+
+                write!(f, "dcl_tessellator_domain {}", domain)
+            }
+            Declaration::TessPartitioning(partitioning) => {
+                let partitioning = match partitioning {
+                    D3D11_SB_TESSELLATOR_PARTITIONING_INTEGER => "partitioning_integer",
+                    D3D11_SB_TESSELLATOR_PARTITIONING_POW2 => "partitioning_pow2",
+                    D3D11_SB_TESSELLATOR_PARTITIONING_FRACTIONAL_ODD => "partitioning_fractional_odd",
+                    D3D11_SB_TESSELLATOR_PARTITIONING_FRACTIONAL_EVEN => "partitioning_fractional_even",
+                    _ => "partitioning_undefined",
+                };
+
+                write!(f, "dcl_tessellator_partitioning {}", partitioning)
+            }
+            Declaration::TessOutputPrimitive(primitive) => {
+                let primitive = match primitive {
+                    D3D11_SB_TESSELLATOR_OUTPUT_POINT => "output_point",
+                    D3D11_SB_TESSELLATOR_OUTPUT_LINE => "output_line",
+                    D3D11_SB_TESSELLATOR_OUTPUT_TRIANGLE_CW => "output_triangle_cw",
+                    D3D11_SB_TESSELLATOR_OUTPUT_TRIANGLE_CCW => "output_triangle_ccw",
+                    _ => "output_undefined",
+                };
+
+                write!(f, "dcl_tessellator_output_primitive {}", primitive)
+            }
+            Declaration::HsMaxTessfactor(factor) => write!(f, "dcl_hs_max_tessfactor l({})", format_float(factor.to_bits(), f.alternate())),
+            Declaration::HsForkPhaseInstanceCount(count) => write!(f, "dcl_hs_fork_phase_instance_count {}", count),
+            Declaration::HsJoinPhaseInstanceCount(count) => write!(f, "dcl_hs_join_phase_instance_count {}", count),
+            Declaration::ThreadGroup(x, y, z) => write!(f, "dcl_thread_group {}, {}, {}", x, y, z),
+            Declaration::FunctionBody(body) => write!(f, "dcl_function_body fb{}", body),
+            Declaration::FunctionTable { table, ref bodies } => {
+                let bodies: Vec<String> = bodies.iter().map(|body| format!("fb{}", body)).collect();
+                write!(f, "dcl_function_table ft{} = {{{}}}", table, bodies.join(", "))
+            }
+            Declaration::Interface { interface, dynamically_indexed, expected_table_length, array_length, ref tables } => {
+                let tables: Vec<String> = tables.iter().map(|table| format!("ft{}", table)).collect();
+                write!(
+                    f,
+                    "dcl_interface{} fp{}[{}][{}] = {{{}}}",
+                    if dynamically_indexed { "_dynamicindexed" } else { "" },
+                    interface,
+                    array_length,
+                    expected_table_length,
+                    tables.join(", "),
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Declaration(Declaration),
+    Instruction(Instruction),
+}
+
+/// Lowers a parsed instruction, `offset` is only used for errors. Custom data
+/// other than immediate constant buffers has no representation and gives
+/// `None`.
+pub fn lower(offset: u32, instruction: &SparseInstruction) -> Result<Option<Statement>, Error> {
+    let offset = offset as usize;
+
+    if let Some(declaration) = Declaration::lower(offset, &instruction.opcode, &instruction.operands)? {
+        return Ok(Some(Statement::Declaration(declaration)));
+    }
+
+    match instruction.operands {
+        Operands::CustomData(..) => Ok(None),
+        _ => Ok(Some(Statement::Instruction(Instruction::lower(offset, &instruction.opcode)?))),
+    }
+}
+
+/// A SHEX program with its declarations separated from the code.
+///
+/// Displays as the code part of an fxc listing, the alternate `{:#}` format
+/// prints float literals with all their bits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub program_type: u16,
+    pub major: u8,
+    pub minor: u8,
+    pub decls: Vec<Declaration>,
+    /// Declarations inside hull shader phases, with the number of
+    /// instructions in front of them.
+    pub phase_decls: Vec<(usize, Declaration)>,
+    pub instructions: Vec<Instruction>,
+}
+
+impl Program {
+    pub fn new(program_type: u16, major: u8, minor: u8) -> Self {
+        Program {
+            program_type,
+            major,
+            minor,
+            decls: Vec::new(),
+            phase_decls: Vec::new(),
+            instructions: Vec::new(),
+        }
+    }
+
+    /// Parses the SHEX chunk of a shader, `None` if there isn't one.
+    pub fn parse(bytes: &[u8]) -> Result<Option<Program>, State> {
+        let mut consumer = ProgramConsumer { program: None };
+
+        {
+            let mut parser = Parser::new(bytes, &mut consumer);
+            parser.parse()?;
+        }
+
+        Ok(consumer.program)
+    }
+
+    pub fn add_statement(&mut self, statement: Statement) {
+        match statement {
+            Statement::Declaration(declaration) => {
+                if self.instructions.is_empty() {
+                    self.decls.push(declaration);
+                } else {
+                    self.phase_decls.push((self.instructions.len(), declaration));
+                }
+            }
+            Statement::Instruction(instruction) => self.instructions.push(instruction),
+        }
+    }
+
+    /// All statements in program order.
+    pub fn statements(&self) -> Vec<Statement> {
+        let mut statements: Vec<Statement> = self.decls.iter().cloned().map(Statement::Declaration).collect();
+        let mut next = 0;
+
+        for (i, instruction) in self.instructions.iter().enumerate() {
+            while next < self.phase_decls.len() && self.phase_decls[next].0 <= i {
+                statements.push(Statement::Declaration(self.phase_decls[next].1.clone()));
+                next += 1;
+            }
+            statements.push(Statement::Instruction(instruction.clone()));
+        }
+        for (_, declaration) in &self.phase_decls[next..] {
+            statements.push(Statement::Declaration(declaration.clone()));
+        }
+
+        statements
+    }
+
+    pub fn to_builder(&self) -> ShexChunk {
+        let mut shex = ShexChunk::new();
+        shex.set_version(self.program_type, self.major, self.minor);
+
+        for statement in self.statements() {
+            shex.add_instruction(match statement {
+                Statement::Declaration(ref declaration) => declaration.to_builder(),
+                Statement::Instruction(ref instruction) => instruction.to_builder(),
+            });
+        }
+
+        shex
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}_{}_{}", shader_prefix(self.program_type as u32), self.major, self.minor)?;
+
+        let mut indent = 0usize;
+        for statement in self.statements() {
+            let opcode = match statement {
+                Statement::Declaration(ref declaration) => declaration.opcode(),
+                Statement::Instruction(ref instruction) => instruction.opcode,
+            };
+
+            match opcode {
+                D3D10_SB_OPCODE_ELSE | D3D10_SB_OPCODE_ENDIF | D3D10_SB_OPCODE_ENDLOOP | D3D10_SB_OPCODE_ENDSWITCH => {
+                    indent = indent.saturating_sub(1);
+                }
+                _ => {}
+            }
+
+            let text = if f.alternate() { format!("{:#}", statement) } else { statement.to_string() };
+            for line in text.lines() {
+                writeln!(f, "{:width$}{}", "", line, width = 2 * indent)?;
+            }
+
+            match opcode {
+                D3D10_SB_OPCODE_IF | D3D10_SB_OPCODE_ELSE | D3D10_SB_OPCODE_LOOP | D3D10_SB_OPCODE_SWITCH => indent += 1,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct ProgramConsumer {
+    program: Option<Program>,
+}
+
+impl Consumer for ProgramConsumer {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn consume_shex(&mut self, header: &ShexHeader) -> Action {
+        self.program = Some(Program::new(header.program_type, header.major, header.minor));
+        Action::Continue
+    }
+
+    fn consume_instruction(&mut self, offset: u32, instruction: SparseInstruction) -> Action {
+        let program = match self.program {
+            Some(ref mut program) => program,
+            None => return Action::Continue,
+        };
+
+        match lower(offset, &instruction) {
+            Ok(Some(statement)) => program.add_statement(statement),
+            Ok(None) => {}
+            Err(err) => return Action::Error(Box::new(err)),
+        }
+
+        Action::Continue
+    }
+}
+
+// How immediate operands of an instruction are printed, fxc picks the
+// representation from the type the instruction operates on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Literal {
+    Float,
+    Int,
+    Uint,
+    Hex,
+    Typeless,
+}
+
+impl Literal {
+    fn from_opcode(opcode: u32) -> Self {
+        match opcode {
+            D3D10_SB_OPCODE_AND | D3D10_SB_OPCODE_OR | D3D10_SB_OPCODE_XOR | D3D10_SB_OPCODE_NOT |
+            D3D11_SB_OPCODE_ATOMIC_AND | D3D11_SB_OPCODE_ATOMIC_OR | D3D11_SB_OPCODE_ATOMIC_XOR |
+            D3D11_SB_OPCODE_IMM_ATOMIC_AND | D3D11_SB_OPCODE_IMM_ATOMIC_OR | D3D11_SB_OPCODE_IMM_ATOMIC_XOR => Literal::Hex,
+
+            D3D10_SB_OPCODE_IADD | D3D10_SB_OPCODE_IEQ | D3D10_SB_OPCODE_IGE | D3D10_SB_OPCODE_ILT |
+            D3D10_SB_OPCODE_IMAD | D3D10_SB_OPCODE_IMAX | D3D10_SB_OPCODE_IMIN | D3D10_SB_OPCODE_IMUL |
+            D3D10_SB_OPCODE_INE | D3D10_SB_OPCODE_INEG | D3D10_SB_OPCODE_ISHL | D3D10_SB_OPCODE_ISHR |
+            D3D10_SB_OPCODE_ITOF | D3D11_SB_OPCODE_IBFE | D3D10_SB_OPCODE_SWITCH | D3D10_SB_OPCODE_CASE |
+            D3D10_SB_OPCODE_LD | D3D10_SB_OPCODE_LD_MS | D3D10_SB_OPCODE_RESINFO | D3D10_1_SB_OPCODE_SAMPLE_POS |
+            D3D11_SB_OPCODE_BUFINFO | D3D11_SB_OPCODE_LD_UAV_TYPED | D3D11_SB_OPCODE_LD_RAW |
+            D3D11_SB_OPCODE_LD_STRUCTURED | D3D11_SB_OPCODE_ATOMIC_IADD | D3D11_SB_OPCODE_ATOMIC_IMAX |
+            D3D11_SB_OPCODE_ATOMIC_IMIN | D3D11_SB_OPCODE_IMM_ATOMIC_IADD | D3D11_SB_OPCODE_IMM_ATOMIC_IMAX |
+            D3D11_SB_OPCODE_IMM_ATOMIC_IMIN | D3D11_1_SB_OPCODE_ITOD | D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX |
+            D3D11_SB_OPCODE_EVAL_SNAPPED => Literal::Int,
+
+            D3D10_SB_OPCODE_UDIV | D3D10_SB_OPCODE_ULT | D3D10_SB_OPCODE_UGE | D3D10_SB_OPCODE_UMUL |
+            D3D10_SB_OPCODE_UMAD | D3D10_SB_OPCODE_UMAX | D3D10_SB_OPCODE_UMIN | D3D10_SB_OPCODE_USHR |
+            D3D10_SB_OPCODE_UTOF | D3D11_SB_OPCODE_UBFE | D3D11_SB_OPCODE_BFI | D3D11_SB_OPCODE_BFREV |
+            D3D11_SB_OPCODE_COUNTBITS | D3D11_SB_OPCODE_FIRSTBIT_HI | D3D11_SB_OPCODE_FIRSTBIT_LO |
+            D3D11_SB_OPCODE_FIRSTBIT_SHI | D3D11_SB_OPCODE_UADDC | D3D11_SB_OPCODE_USUBB |
+            D3D11_SB_OPCODE_F16TOF32 | D3D11_SB_OPCODE_ATOMIC_UMAX | D3D11_SB_OPCODE_ATOMIC_UMIN |
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_IMM_ATOMIC_UMAX | D3D11_SB_OPCODE_IMM_ATOMIC_UMIN |
+            D3D11_SB_OPCODE_IMM_ATOMIC_EXCH | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH | D3D11_1_SB_OPCODE_UTOD |
+            D3D11_1_SB_OPCODE_MSAD => Literal::Uint,
+
+            D3D10_SB_OPCODE_MOV | D3D10_SB_OPCODE_MOVC | D3D11_SB_OPCODE_SWAPC |
+            D3D11_SB_OPCODE_STORE_UAV_TYPED | D3D11_SB_OPCODE_STORE_RAW | D3D11_SB_OPCODE_STORE_STRUCTURED => Literal::Typeless,
+
+            _ => Literal::Float,
+        }
+    }
+
+    fn separator(&self) -> &'static str {
+        match *self {
+            Literal::Typeless => ",",
+            _ => ", ",
+        }
+    }
+}
+
+// Spelling of fxc for NaN and infinity.
+fn format_special(val: f64) -> Option<String> {
+    let sign = if val.is_sign_negative() { "-" } else { "" };
+    if val.is_nan() {
+        Some(format!("{}1.#QNAN0", sign))
+    } else if val.is_infinite() {
+        Some(format!("{}1.#INF00", sign))
+    } else {
+        None
+    }
+}
+
+// fxc prints six decimals. Exact listings, the alternate `{:#}` format of
+// the IR, print values those don't hold in full and NaNs with a payload in
+// hex so the assembler reads back the same bits.
+fn format_float(bits: u32, exact: bool) -> String {
+    let val = f32::from_bits(bits);
+    if exact && val.is_nan() && bits & 0x7fffffff != 0x7fc00000 {
+        return format!("0x{:08x}", bits);
+    }
+
+    let text = format!("{:.6}", val);
+    match format_special(val as f64) {
+        Some(text) => text,
+        None if !exact || text.parse::<f32>().ok().map(f32::to_bits) == Some(bits) => text,
+        None => format!("{:?}", val),
+    }
+}
+
+fn format_double(bits: u64, exact: bool) -> String {
+    let val = f64::from_bits(bits);
+    if exact && val.is_nan() && bits & !(1 << 63) != 0x7ff8000000000000 {
+        return format!("0x{:016x}", bits);
+    }
+
+    let text = format!("{:.6}", val);
+    match format_special(val) {
+        Some(text) => text,
+        None if !exact || text.parse::<f64>().ok().map(f64::to_bits) == Some(bits) => text,
+        None => format!("{:?}", val),
+    }
+}
+
+fn format_literal(bits: u32, literal: Literal, exact: bool) -> String {
+    match literal {
+        Literal::Float => format_float(bits, exact),
+        Literal::Int => format!("{}", bits as i32),
+        Literal::Uint => format!("{}", bits),
+        Literal::Hex => format!("0x{:08x}", bits),
+        Literal::Typeless => {
+            // untyped moves keep whatever looks like an integer as one,
+            // mirroring what fxc prints for them
+            let exponent = bits & 0x7f800000;
+            if exponent == 0 || (exponent == 0x7f800000 && bits & 0x007fffff != 0) {
+                format!("{}", bits as i32)
+            } else {
+                format_float(bits, exact)
+            }
+        }
+    }
+}
+
+fn format_return_types(types: [u32; 4]) -> String {
+    let names: Vec<&str> = types.iter().map(|&ty| return_type_name(ty)).collect();
+    format!("({})", names.join(","))
+}
+
+// Register name and indices without component selection or modifiers.
+fn format_index(register: &Register) -> String {
+    let mut text = operand_prefix(register.file as u32).to_owned();
+
+    // multi dimensional input arrays put every index in brackets, `v[3][0]`
+    let bracketed = match register.file {
+        OperandType::Input | OperandType::InputControlPoint | OperandType::OutputControlPoint => register.index.len() > 1,
+        OperandType::ImmediateConstantBuffer => true,
+        _ => false,
+    };
+
+    for (i, index) in register.index.iter().enumerate() {
+        let _ = match *index {
+            Index::U32(index) if i == 0 && !bracketed => write!(text, "{}", index),
+            Index::U32(index) => write!(text, "[{}]", index),
+            Index::U64(index) => write!(text, "[{}]", index),
+            Index::Relative(ref relative) => write!(text, "[{} + 0]", format_register(relative)),
+            Index::U32Relative(index, ref relative) => write!(text, "[{} + {}]", format_register(relative), index),
+            Index::U64Relative(index, ref relative) => write!(text, "[{} + {}]", format_register(relative), index),
+        };
+    }
+
+    text
+}
+
+fn format_register(register: &Register) -> String {
+    let mut text = format_index(register);
+
+    let components: String = match register.components {
+        Components::Mask(mask) => (0..4).filter(|c| mask & (1 << c) != 0).map(|c| COMPONENTS[c]).collect(),
+        Components::Swizzle(swizzle) => swizzle.iter().map(|&c| COMPONENTS[c as usize]).collect(),
+        Components::Select(c) => COMPONENTS[c as usize].to_string(),
+        Components::None | Components::Scalar => String::new(),
+    };
+    if !components.is_empty() {
+        text.push('.');
+        text.push_str(&components);
+    }
+
+    text = match register.modifier {
+        Modifier::Neg => format!("-{}", text),
+        Modifier::Abs => format!("|{}|", text),
+        Modifier::AbsNeg => format!("-|{}|", text),
+        Modifier::None => text,
+    };
+
+    match register.precision {
+        MinPrecision::Float16 => text.push_str(" {min16f}"),
+        MinPrecision::Float2_8 => text.push_str(" {min2_8f}"),
+        MinPrecision::Sint16 => text.push_str(" {min16i}"),
+        MinPrecision::Uint16 => text.push_str(" {min16u}"),
+        MinPrecision::Default => {}
+    }
+
+    text
+}
+
+fn format_operand(operand: &Operand, literal: Literal, exact: bool) -> String {
+    match *operand {
+        Operand::Register(ref register) => format_register(register),
+        Operand::Imm32(ref values) => {
+            let values: Vec<String> = values.iter().map(|&value| format_literal(value, literal, exact)).collect();
+            format!("l({})", values.join(literal.separator()))
+        }
+        Operand::Imm64(ref values) => {
+            let values: Vec<String> = values.iter().map(|&value| format_double(value, exact)).collect();
+            format!("d({})", values.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format_register(self))
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format_operand(self, Literal::Typeless, f.alternate()))
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Statement::Declaration(ref declaration) => declaration.fmt(f),
+            Statement::Instruction(ref instruction) => instruction.fmt(f),
+        }
+    }
+}
@@ -1,5 +1,3 @@
-#![feature(libc)]
-
 extern crate byteorder;
 #[macro_use]
 extern crate bitflags;
@@ -10,4 +8,17 @@ pub mod dr;
 mod md5;
 pub mod checksum;
 pub use checksum::*;
-mod d3d11tokenizedprogramformat;
+pub mod glsl;
+pub mod hlsl;
+pub mod msl;
+pub mod wgsl;
+pub mod disasm;
+pub mod asm;
+pub mod ir;
+pub mod cfg;
+pub mod backend;
+pub mod ssa;
+pub mod interp;
+pub mod expr;
+pub mod types;
+pub mod d3d11tokenizedprogramformat;
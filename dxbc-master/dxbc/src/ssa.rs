@@ -0,0 +1,718 @@
+use cfg::{self, Cfg, Function, FunctionKind};
+use dr::shex::OperandType;
+use d3d11tokenizedprogramformat::*;
+use disasm::COMPONENTS;
+use ir::{Components, Declaration, Index, Instruction, Operand, Program, Register};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// One component of a temp register, `r3.y`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Temp {
+    pub register: u32,
+    pub component: u8,
+}
+
+impl fmt::Display for Temp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "r{}.{}", self.register, COMPONENTS[self.component as usize])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Def {
+    /// Whatever the temp holds when the function is entered.
+    Entry,
+    Instruction(usize),
+    /// Merge at the start of a block, the version coming from each
+    /// predecessor block.
+    Phi(usize, Vec<(usize, usize)>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Use {
+    Instruction(usize),
+    /// Operand of the phi defining the value.
+    Phi(usize),
+}
+
+/// Read or write of a temp component by an operand of an instruction. The
+/// temps touched by calls and returns have no operand.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Access {
+    pub operand: Option<usize>,
+    pub temp: Temp,
+    pub value: usize,
+}
+
+/// A version of a temp component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Value {
+    pub temp: Temp,
+    /// Version 0 is the entry value, the others count the definitions of
+    /// the temp in the function.
+    pub version: u32,
+    pub def: Def,
+    pub uses: Vec<Use>,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "r{}_{}_{}", self.temp.register, COMPONENTS[self.temp.component as usize], self.version)
+    }
+}
+
+/// SSA form of the temps of a function, values are indices into `values`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSsa {
+    pub values: Vec<Value>,
+    /// Values read by each instruction of the function, by instruction.
+    pub reads: HashMap<usize, Vec<Access>>,
+    /// Values written by each instruction of the function, by instruction.
+    pub writes: HashMap<usize, Vec<Access>>,
+    /// Phis at the start of each block.
+    pub phis: Vec<Vec<usize>>,
+    pub live_in: Vec<Vec<usize>>,
+    pub live_out: Vec<Vec<usize>>,
+}
+
+impl FunctionSsa {
+    /// Values written by destination operands and never read afterwards.
+    pub fn dead_writes(&self) -> Vec<usize> {
+        (0..self.values.len())
+            .filter(|&value| match self.values[value].def {
+                Def::Instruction(i) => {
+                    self.values[value].uses.is_empty() &&
+                        self.writes[&i].iter().any(|access| access.value == value && access.operand.is_some())
+                }
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Version of the temp read by the operand of the instruction.
+    pub fn read(&self, instruction: usize, operand: usize, temp: Temp) -> Option<usize> {
+        self.reads
+            .get(&instruction)?
+            .iter()
+            .find(|access| access.operand == Some(operand) && access.temp == temp)
+            .map(|access| access.value)
+    }
+
+    /// Version of the temp written by the operand of the instruction.
+    pub fn written(&self, instruction: usize, operand: usize, temp: Temp) -> Option<usize> {
+        self.writes
+            .get(&instruction)?
+            .iter()
+            .find(|access| access.operand == Some(operand) && access.temp == temp)
+            .map(|access| access.value)
+    }
+}
+
+/// SSA form of the `r#` temps of a program, each component of a temp is
+/// versioned on its own. Indexable temps are left alone.
+///
+/// Temps are shared with subroutines, so calls read and write every
+/// declared temp and returning from a subroutine reads them all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ssa {
+    pub cfg: Cfg,
+    /// One for each function of the graph.
+    pub functions: Vec<FunctionSsa>,
+}
+
+impl Ssa {
+    pub fn build(program: &Program) -> Result<Ssa, cfg::Error> {
+        let cfg = Cfg::build(program)?;
+
+        let temps = program
+            .decls
+            .iter()
+            .chain(program.phase_decls.iter().map(|(_, declaration)| declaration))
+            .filter_map(|declaration| match *declaration {
+                Declaration::Temps(count) => Some(count),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let all: Vec<Temp> = (0..temps)
+            .flat_map(|register| (0..4).map(move |component| Temp { register, component }))
+            .collect();
+
+        let functions = cfg
+            .functions
+            .iter()
+            .map(|function| FunctionBuilder::new(program, function, &all).finish())
+            .collect();
+
+        Ok(Ssa { cfg, functions })
+    }
+}
+
+pub(crate) fn destinations(opcode: u32) -> usize {
+    match opcode {
+        D3D10_SB_OPCODE_SINCOS | D3D10_SB_OPCODE_UDIV | D3D10_SB_OPCODE_IMUL | D3D10_SB_OPCODE_UMUL |
+        D3D11_SB_OPCODE_UADDC | D3D11_SB_OPCODE_USUBB | D3D11_SB_OPCODE_SWAPC => 2,
+
+        D3D10_SB_OPCODE_BREAK | D3D10_SB_OPCODE_BREAKC | D3D10_SB_OPCODE_CALL | D3D10_SB_OPCODE_CALLC |
+        D3D10_SB_OPCODE_CASE | D3D10_SB_OPCODE_CONTINUE | D3D10_SB_OPCODE_CONTINUEC | D3D10_SB_OPCODE_CUT |
+        D3D10_SB_OPCODE_DEFAULT | D3D10_SB_OPCODE_DISCARD | D3D10_SB_OPCODE_ELSE | D3D10_SB_OPCODE_EMIT |
+        D3D10_SB_OPCODE_EMITTHENCUT | D3D10_SB_OPCODE_ENDIF | D3D10_SB_OPCODE_ENDLOOP | D3D10_SB_OPCODE_ENDSWITCH |
+        D3D10_SB_OPCODE_IF | D3D10_SB_OPCODE_LABEL | D3D10_SB_OPCODE_LOOP | D3D10_SB_OPCODE_NOP |
+        D3D10_SB_OPCODE_RET | D3D10_SB_OPCODE_RETC | D3D10_SB_OPCODE_SWITCH | D3D11_SB_OPCODE_HS_DECLS |
+        D3D11_SB_OPCODE_HS_CONTROL_POINT_PHASE | D3D11_SB_OPCODE_HS_FORK_PHASE | D3D11_SB_OPCODE_HS_JOIN_PHASE |
+        D3D11_SB_OPCODE_EMIT_STREAM | D3D11_SB_OPCODE_CUT_STREAM | D3D11_SB_OPCODE_EMITTHENCUT_STREAM |
+        D3D11_SB_OPCODE_INTERFACE_CALL | D3D11_SB_OPCODE_STORE_UAV_TYPED | D3D11_SB_OPCODE_STORE_RAW |
+        D3D11_SB_OPCODE_STORE_STRUCTURED | D3D11_SB_OPCODE_ATOMIC_AND | D3D11_SB_OPCODE_ATOMIC_OR |
+        D3D11_SB_OPCODE_ATOMIC_XOR | D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_ATOMIC_IADD |
+        D3D11_SB_OPCODE_ATOMIC_IMAX | D3D11_SB_OPCODE_ATOMIC_IMIN | D3D11_SB_OPCODE_ATOMIC_UMAX |
+        D3D11_SB_OPCODE_ATOMIC_UMIN | D3D11_SB_OPCODE_SYNC | D3D11_SB_OPCODE_ABORT | D3D11_SB_OPCODE_DEBUG_BREAK => 0,
+
+        _ => 1,
+    }
+}
+
+// Instructions whose result component `c` only depends on component `c` of
+// their sources after swizzling.
+pub(crate) fn is_componentwise(opcode: u32) -> bool {
+    matches!(opcode, D3D10_SB_OPCODE_ADD | D3D10_SB_OPCODE_AND | D3D10_SB_OPCODE_DERIV_RTX | D3D10_SB_OPCODE_DERIV_RTY |
+        D3D10_SB_OPCODE_DIV | D3D10_SB_OPCODE_EQ | D3D10_SB_OPCODE_EXP | D3D10_SB_OPCODE_FRC |
+        D3D10_SB_OPCODE_FTOI | D3D10_SB_OPCODE_FTOU | D3D10_SB_OPCODE_GE | D3D10_SB_OPCODE_IADD |
+        D3D10_SB_OPCODE_IEQ | D3D10_SB_OPCODE_IGE | D3D10_SB_OPCODE_ILT | D3D10_SB_OPCODE_IMAD |
+        D3D10_SB_OPCODE_IMAX | D3D10_SB_OPCODE_IMIN | D3D10_SB_OPCODE_IMUL | D3D10_SB_OPCODE_INE |
+        D3D10_SB_OPCODE_INEG | D3D10_SB_OPCODE_ISHL | D3D10_SB_OPCODE_ISHR | D3D10_SB_OPCODE_ITOF |
+        D3D10_SB_OPCODE_LOG | D3D10_SB_OPCODE_LT | D3D10_SB_OPCODE_MAD | D3D10_SB_OPCODE_MIN |
+        D3D10_SB_OPCODE_MAX | D3D10_SB_OPCODE_MOV | D3D10_SB_OPCODE_MOVC | D3D10_SB_OPCODE_MUL |
+        D3D10_SB_OPCODE_NE | D3D10_SB_OPCODE_NOT | D3D10_SB_OPCODE_OR | D3D10_SB_OPCODE_ROUND_NE |
+        D3D10_SB_OPCODE_ROUND_NI | D3D10_SB_OPCODE_ROUND_PI | D3D10_SB_OPCODE_ROUND_Z | D3D10_SB_OPCODE_RSQ |
+        D3D10_SB_OPCODE_SQRT | D3D10_SB_OPCODE_SINCOS | D3D10_SB_OPCODE_UDIV | D3D10_SB_OPCODE_ULT |
+        D3D10_SB_OPCODE_UGE | D3D10_SB_OPCODE_UMUL | D3D10_SB_OPCODE_UMAD | D3D10_SB_OPCODE_UMAX |
+        D3D10_SB_OPCODE_UMIN | D3D10_SB_OPCODE_USHR | D3D10_SB_OPCODE_UTOF | D3D10_SB_OPCODE_XOR |
+        D3D11_SB_OPCODE_DERIV_RTX_COARSE | D3D11_SB_OPCODE_DERIV_RTX_FINE | D3D11_SB_OPCODE_DERIV_RTY_COARSE |
+        D3D11_SB_OPCODE_DERIV_RTY_FINE | D3D11_SB_OPCODE_RCP | D3D11_SB_OPCODE_F32TOF16 | D3D11_SB_OPCODE_F16TOF32 |
+        D3D11_SB_OPCODE_UADDC | D3D11_SB_OPCODE_USUBB | D3D11_SB_OPCODE_COUNTBITS | D3D11_SB_OPCODE_FIRSTBIT_HI |
+        D3D11_SB_OPCODE_FIRSTBIT_LO | D3D11_SB_OPCODE_FIRSTBIT_SHI | D3D11_SB_OPCODE_UBFE | D3D11_SB_OPCODE_IBFE |
+        D3D11_SB_OPCODE_BFI | D3D11_SB_OPCODE_BFREV | D3D11_SB_OPCODE_SWAPC | D3D11_1_SB_OPCODE_MSAD)
+}
+
+fn temps(register: &Register, components: u8) -> Vec<Temp> {
+    (0..4)
+        .filter(|&c| components & (1 << c) != 0)
+        .filter_map(|component| match register.index.first() {
+            Some(&Index::U32(index)) => Some(Temp { register: index, component }),
+            _ => None,
+        })
+        .collect()
+}
+
+// Temps read through relative indices of the register.
+fn index_reads(register: &Register, reads: &mut Vec<Temp>) {
+    for index in &register.index {
+        if let Some(relative) = index.relative() {
+            source_reads(relative, 0xf, reads);
+        }
+    }
+}
+
+// Temps read by a source operand when the instruction uses the given
+// lanes of it.
+fn source_reads(register: &Register, lanes: u8, reads: &mut Vec<Temp>) {
+    index_reads(register, reads);
+
+    if register.file != OperandType::Temp {
+        return;
+    }
+
+    let components = match register.components {
+        Components::Swizzle(swizzle) => (0..4).filter(|&l| lanes & (1 << l) != 0).fold(0, |c, l| c | (1 << swizzle[l])),
+        Components::Select(c) => 1 << c,
+        Components::Mask(mask) => mask,
+        Components::None | Components::Scalar => 1,
+    };
+
+    reads.extend(temps(register, components));
+}
+
+pub type Accesses = Vec<(Option<usize>, Temp)>;
+
+/// Temps read and written by an instruction, with the operand accessing
+/// them. `all` are the temps touched by calls.
+pub fn accesses(instruction: &Instruction, all: &[Temp]) -> (Accesses, Accesses) {
+    let mut reads = Vec::new();
+    let mut writes = Vec::new();
+
+    let count = destinations(instruction.opcode).min(instruction.operands.len());
+    let lanes = instruction.operands[..count]
+        .iter()
+        .filter_map(Operand::register)
+        .fold(0, |lanes, register| lanes | register.components.mask());
+    let lanes = if is_componentwise(instruction.opcode) && lanes != 0 { lanes } else { 0xf };
+
+    for (i, operand) in instruction.operands.iter().enumerate() {
+        let register = match operand.register() {
+            Some(register) => register,
+            None => continue,
+        };
+
+        let mut temps_read = Vec::new();
+        if i < count {
+            index_reads(register, &mut temps_read);
+            if register.file == OperandType::Temp {
+                writes.extend(temps(register, register.components.mask()).into_iter().map(|temp| (Some(i), temp)));
+            }
+        } else {
+            source_reads(register, lanes, &mut temps_read);
+        }
+
+        for temp in temps_read {
+            if !reads.contains(&(Some(i), temp)) {
+                reads.push((Some(i), temp));
+            }
+        }
+    }
+
+    match instruction.opcode {
+        D3D10_SB_OPCODE_CALL | D3D10_SB_OPCODE_CALLC | D3D11_SB_OPCODE_INTERFACE_CALL => {
+            reads.extend(all.iter().map(|&temp| (None, temp)));
+            writes.extend(all.iter().map(|&temp| (None, temp)));
+        }
+        _ => {}
+    }
+
+    (reads, writes)
+}
+
+struct FunctionBuilder<'a> {
+    program: &'a Program,
+    function: &'a Function,
+    all: &'a [Temp],
+    values: Vec<Value>,
+    reads: HashMap<usize, Vec<Access>>,
+    writes: HashMap<usize, Vec<Access>>,
+    phis: Vec<Vec<usize>>,
+    // version of each temp at the entry of the function
+    entries: HashMap<Temp, usize>,
+}
+
+impl<'a> FunctionBuilder<'a> {
+    fn new(program: &'a Program, function: &'a Function, all: &'a [Temp]) -> Self {
+        FunctionBuilder {
+            program,
+            function,
+            all,
+            values: Vec::new(),
+            reads: HashMap::new(),
+            writes: HashMap::new(),
+            phis: vec![Vec::new(); function.blocks.len()],
+            entries: HashMap::new(),
+        }
+    }
+
+    fn accesses(&self, instruction: usize) -> (Accesses, Accesses) {
+        let (mut reads, writes) = accesses(&self.program.instructions[instruction], self.all);
+
+        // subroutines hand all temps back to their caller
+        if let FunctionKind::Subroutine(_) = self.function.kind {
+            match self.program.instructions[instruction].opcode {
+                D3D10_SB_OPCODE_RET | D3D10_SB_OPCODE_RETC => {
+                    reads.extend(self.all.iter().map(|&temp| (None, temp)));
+                }
+                _ => {}
+            }
+        }
+
+        (reads, writes)
+    }
+
+    fn value(&mut self, temp: Temp, def: Def) -> usize {
+        // numbered once renaming is done
+        self.values.push(Value { temp, version: 0, def, uses: Vec::new() });
+        self.values.len() - 1
+    }
+
+    fn entry(&mut self, temp: Temp) -> usize {
+        if let Some(&value) = self.entries.get(&temp) {
+            return value;
+        }
+
+        self.values.push(Value { temp, version: 0, def: Def::Entry, uses: Vec::new() });
+        let value = self.values.len() - 1;
+        self.entries.insert(temp, value);
+        value
+    }
+
+    fn finish(mut self) -> FunctionSsa {
+        let blocks = &self.function.blocks;
+        let order = reverse_postorder(self.function);
+        let idom = dominators(self.function, &order);
+        let frontiers = frontiers(self.function, &idom);
+        let reachable: Vec<bool> = (0..blocks.len()).map(|b| idom[b].is_some()).collect();
+
+        // pruned SSA: phis at the iterated dominance frontier of every
+        // block writing a temp, where the temp is live
+        let mut written: HashMap<Temp, Vec<usize>> = HashMap::new();
+        let mut exposed: Vec<HashSet<Temp>> = vec![HashSet::new(); blocks.len()];
+        let mut killed: Vec<HashSet<Temp>> = vec![HashSet::new(); blocks.len()];
+        for b in 0..blocks.len() {
+            if !reachable[b] {
+                continue;
+            }
+            for i in blocks[b].start..blocks[b].end {
+                let (reads, writes) = self.accesses(i);
+                for (_, temp) in reads {
+                    if !killed[b].contains(&temp) {
+                        exposed[b].insert(temp);
+                    }
+                }
+                for (_, temp) in writes {
+                    killed[b].insert(temp);
+                    let blocks = written.entry(temp).or_default();
+                    if blocks.last() != Some(&b) {
+                        blocks.push(b);
+                    }
+                }
+            }
+        }
+
+        let mut live = exposed.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for b in (0..blocks.len()).rev() {
+                for &successor in &blocks[b].successors {
+                    let incoming: Vec<Temp> = live[successor]
+                        .iter()
+                        .cloned()
+                        .filter(|temp| !killed[b].contains(temp) && !live[b].contains(temp))
+                        .collect();
+                    changed |= !incoming.is_empty();
+                    live[b].extend(incoming);
+                }
+            }
+        }
+
+        let mut temps: Vec<Temp> = written.keys().cloned().collect();
+        temps.sort();
+        for temp in temps {
+            let mut work = written[&temp].clone();
+            let mut placed = vec![false; blocks.len()];
+            while let Some(b) = work.pop() {
+                for &frontier in &frontiers[b] {
+                    if !placed[frontier] && live[frontier].contains(&temp) {
+                        placed[frontier] = true;
+                        let phi = self.value(temp, Def::Phi(frontier, Vec::new()));
+                        self.phis[frontier].push(phi);
+                        work.push(frontier);
+                    }
+                }
+            }
+        }
+
+        // rename along the dominator tree, unreachable blocks start from the
+        // entry values
+        let mut children = vec![Vec::new(); blocks.len()];
+        for (b, parent) in idom.iter().enumerate().skip(1) {
+            if let Some(parent) = *parent {
+                if parent != b {
+                    children[parent].push(b);
+                }
+            }
+        }
+
+        let mut roots = vec![0];
+        roots.extend((0..blocks.len()).filter(|&b| !reachable[b]));
+        for root in roots {
+            if root >= blocks.len() {
+                continue;
+            }
+
+            let mut current: HashMap<Temp, Vec<usize>> = HashMap::new();
+            let mut stack = vec![(root, false)];
+            let mut pushed: Vec<Vec<Temp>> = vec![Vec::new(); blocks.len()];
+
+            while let Some((b, done)) = stack.pop() {
+                if done {
+                    for temp in pushed[b].drain(..) {
+                        current.get_mut(&temp).unwrap().pop();
+                    }
+                    continue;
+                }
+
+                stack.push((b, true));
+                self.rename(b, &reachable, &mut current, &mut pushed[b]);
+                if reachable[b] {
+                    stack.extend(children[b].iter().rev().map(|&child| (child, false)));
+                }
+            }
+        }
+
+        self.renumber();
+        let (live_in, live_out) = self.liveness(&reachable);
+
+        FunctionSsa {
+            values: self.values,
+            reads: self.reads,
+            writes: self.writes,
+            phis: self.phis,
+            live_in,
+            live_out,
+        }
+    }
+
+    // Versions count the definitions in program order, phis first.
+    fn renumber(&mut self) {
+        let blocks = &self.function.blocks;
+        let mut order: Vec<usize> = (0..self.values.len()).collect();
+        order.sort_by_key(|&value| match self.values[value].def {
+            Def::Entry => (0, 0),
+            Def::Phi(b, _) => (blocks[b].start, 0),
+            Def::Instruction(i) => (i, 1),
+        });
+
+        let mut versions: HashMap<Temp, u32> = HashMap::new();
+        for value in order {
+            if let Def::Entry = self.values[value].def {
+                continue;
+            }
+            let version = versions.entry(self.values[value].temp).or_insert(0);
+            *version += 1;
+            self.values[value].version = *version;
+        }
+    }
+
+    fn current(&mut self, current: &HashMap<Temp, Vec<usize>>, temp: Temp) -> usize {
+        match current.get(&temp).and_then(|versions| versions.last()) {
+            Some(&value) => value,
+            None => self.entry(temp),
+        }
+    }
+
+    fn rename(&mut self, b: usize, reachable: &[bool], current: &mut HashMap<Temp, Vec<usize>>, pushed: &mut Vec<Temp>) {
+        let block = &self.function.blocks[b];
+
+        for &phi in &self.phis[b] {
+            let temp = self.values[phi].temp;
+            current.entry(temp).or_default().push(phi);
+            pushed.push(temp);
+        }
+
+        for i in block.start..block.end {
+            let (reads, writes) = self.accesses(i);
+
+            let mut accesses = Vec::new();
+            for (operand, temp) in reads {
+                let value = self.current(current, temp);
+                self.values[value].uses.push(Use::Instruction(i));
+                accesses.push(Access { operand, temp, value });
+            }
+            if !accesses.is_empty() {
+                self.reads.insert(i, accesses);
+            }
+
+            let mut accesses = Vec::new();
+            for (operand, temp) in writes {
+                let value = self.value(temp, Def::Instruction(i));
+                current.entry(temp).or_default().push(value);
+                pushed.push(temp);
+                accesses.push(Access { operand, temp, value });
+            }
+            if !accesses.is_empty() {
+                self.writes.insert(i, accesses);
+            }
+        }
+
+        // unreachable predecessors don't feed phis
+        if !reachable[b] {
+            return;
+        }
+
+        for &successor in &block.successors {
+            for p in 0..self.phis[successor].len() {
+                let phi = self.phis[successor][p];
+                let value = self.current(current, self.values[phi].temp);
+
+                self.values[value].uses.push(Use::Phi(phi));
+                if let Def::Phi(_, ref mut incoming) = self.values[phi].def {
+                    incoming.push((b, value));
+                }
+            }
+        }
+    }
+
+    fn liveness(&self, reachable: &[bool]) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
+        let blocks = &self.function.blocks;
+        let count = blocks.len();
+
+        // values read in a block before being written there, and values
+        // written in it
+        let mut uses = vec![Vec::new(); count];
+        let mut defs = vec![Vec::new(); count];
+        for b in 0..count {
+            defs[b].extend(self.phis[b].iter().cloned());
+            for i in blocks[b].start..blocks[b].end {
+                for access in self.reads.get(&i).map(|reads| &reads[..]).unwrap_or(&[]) {
+                    if !defs[b].contains(&access.value) && !uses[b].contains(&access.value) {
+                        uses[b].push(access.value);
+                    }
+                }
+                for access in self.writes.get(&i).map(|writes| &writes[..]).unwrap_or(&[]) {
+                    defs[b].push(access.value);
+                }
+            }
+        }
+
+        let mut live_in: Vec<Vec<usize>> = vec![Vec::new(); count];
+        let mut live_out: Vec<Vec<usize>> = vec![Vec::new(); count];
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for b in (0..count).rev() {
+                if !reachable[b] {
+                    continue;
+                }
+
+                let mut out = Vec::new();
+                for &successor in &blocks[b].successors {
+                    for &value in &live_in[successor] {
+                        out.push(value);
+                    }
+                    for &phi in &self.phis[successor] {
+                        if let Def::Phi(_, ref incoming) = self.values[phi].def {
+                            out.extend(incoming.iter().filter(|&&(from, _)| from == b).map(|&(_, value)| value));
+                        }
+                    }
+                }
+                out.sort();
+                out.dedup();
+
+                let mut inputs: Vec<usize> = out.iter().cloned().filter(|value| !defs[b].contains(value)).collect();
+                inputs.extend(uses[b].iter().cloned());
+                inputs.sort();
+                inputs.dedup();
+
+                if out != live_out[b] || inputs != live_in[b] {
+                    live_out[b] = out;
+                    live_in[b] = inputs;
+                    changed = true;
+                }
+            }
+        }
+
+        (live_in, live_out)
+    }
+}
+
+fn reverse_postorder(function: &Function) -> Vec<usize> {
+    let count = function.blocks.len();
+    let mut order = Vec::with_capacity(count);
+    if count == 0 {
+        return order;
+    }
+
+    let mut visited = vec![false; count];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+
+    while let Some(&mut (b, ref mut next)) = stack.last_mut() {
+        match function.blocks[b].successors.get(*next) {
+            Some(&successor) => {
+                *next += 1;
+                if !visited[successor] {
+                    visited[successor] = true;
+                    stack.push((successor, 0));
+                }
+            }
+            None => {
+                order.push(b);
+                stack.pop();
+            }
+        }
+    }
+
+    order.reverse();
+    order
+}
+
+// Immediate dominators of the blocks reachable from the entry, which is its
+// own dominator (Cooper, Harvey and Kennedy).
+fn dominators(function: &Function, order: &[usize]) -> Vec<Option<usize>> {
+    let count = function.blocks.len();
+    let mut position = vec![usize::MAX; count];
+    for (i, &b) in order.iter().enumerate() {
+        position[b] = i;
+    }
+
+    let mut idom = vec![None; count];
+    if count == 0 {
+        return idom;
+    }
+    idom[0] = Some(0);
+
+    let intersect = |idom: &[Option<usize>], mut a: usize, mut b: usize| {
+        while a != b {
+            while position[a] > position[b] {
+                a = idom[a].unwrap();
+            }
+            while position[b] > position[a] {
+                b = idom[b].unwrap();
+            }
+        }
+        a
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for &b in order.iter().skip(1) {
+            let mut new = None;
+            for &predecessor in &function.blocks[b].predecessors {
+                if idom[predecessor].is_none() {
+                    continue;
+                }
+                new = Some(match new {
+                    None => predecessor,
+                    Some(new) => intersect(&idom, predecessor, new),
+                });
+            }
+
+            if new.is_some() && idom[b] != new {
+                idom[b] = new;
+                changed = true;
+            }
+        }
+    }
+
+    idom
+}
+
+fn frontiers(function: &Function, idom: &[Option<usize>]) -> Vec<Vec<usize>> {
+    let count = function.blocks.len();
+    let mut frontiers = vec![Vec::new(); count];
+
+    for b in 0..count {
+        if idom[b].is_none() {
+            continue;
+        }
+
+        let predecessors: Vec<usize> = function.blocks[b].predecessors.iter().cloned().filter(|&p| idom[p].is_some()).collect();
+        if predecessors.len() < 2 {
+            continue;
+        }
+
+        for predecessor in predecessors {
+            let mut runner = predecessor;
+            while Some(runner) != idom[b] {
+                if !frontiers[runner].contains(&b) {
+                    frontiers[runner].push(b);
+                }
+                runner = idom[runner].unwrap();
+            }
+        }
+    }
+
+    frontiers
+}
@@ -0,0 +1,656 @@
+use binary::*;
+use dr;
+use dr::rdef::{ShaderType, ShaderVariableClass, ShaderVariableType};
+use dr::shex::{OperandType, ResourceReturnType};
+use dr::builder::OpcodeEx;
+use d3d11tokenizedprogramformat::*;
+use ir::{Components, Declaration, Index, Instruction, Operand, Program, Register};
+use ssa::{self, Def, FunctionSsa, Ssa, Temp};
+
+use std::collections::HashMap;
+
+/// Type of a 32-bit component. Booleans are all ones or all zeros, like the
+/// results of comparisons.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Float,
+    Int,
+    Uint,
+    Bool,
+}
+
+const TYPES: [Type; 4] = [Type::Float, Type::Int, Type::Uint, Type::Bool];
+
+impl Type {
+    fn from_component_type(ty: dr::RegisterComponentType) -> Option<Self> {
+        match ty {
+            dr::RegisterComponentType::Float32 |
+            dr::RegisterComponentType::Float16 => Some(Type::Float),
+            dr::RegisterComponentType::Int32 |
+            dr::RegisterComponentType::Int16 => Some(Type::Int),
+            dr::RegisterComponentType::Uint32 |
+            dr::RegisterComponentType::Uint16 => Some(Type::Uint),
+            _ => None,
+        }
+    }
+
+    fn from_return_type(ty: u32) -> Option<Self> {
+        match ty {
+            x if x == ResourceReturnType::Sint as u32 => Some(Type::Int),
+            x if x == ResourceReturnType::Uint as u32 => Some(Type::Uint),
+            x if x == ResourceReturnType::Float as u32 ||
+                x == ResourceReturnType::Unorm as u32 ||
+                x == ResourceReturnType::Snorm as u32 => Some(Type::Float),
+            _ => None,
+        }
+    }
+
+    fn from_variable_type(ty: ShaderVariableType) -> Option<Self> {
+        match ty {
+            ShaderVariableType::Float => Some(Type::Float),
+            ShaderVariableType::Int => Some(Type::Int),
+            // HLSL booleans are stored as 0 and 1
+            ShaderVariableType::UInt | ShaderVariableType::UInt8 | ShaderVariableType::Bool => Some(Type::Uint),
+            _ => None,
+        }
+    }
+}
+
+/// Component types declared outside the shader code, in the signatures and
+/// the constant buffer layouts of RDEF.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interface {
+    /// By register and component.
+    pub inputs: HashMap<(u32, u8), Type>,
+    pub outputs: HashMap<(u32, u8), Type>,
+    /// By constant buffer slot and 32-bit offset.
+    pub constants: HashMap<(u32, u32), Type>,
+}
+
+impl Interface {
+    pub fn parse(bytes: &[u8]) -> Result<Interface, State> {
+        let mut consumer = InterfaceConsumer { interface: Interface::default() };
+
+        {
+            let mut parser = Parser::new(bytes, &mut consumer);
+            parser.parse()?;
+        }
+
+        Ok(consumer.interface)
+    }
+
+    /// Declared type of a component of an input, output or constant buffer
+    /// register with immediate indices.
+    pub fn register_type(&self, register: &Register, component: u8) -> Option<Type> {
+        match (register.file, &register.index[..]) {
+            (OperandType::Input, &[Index::U32(index)]) => self.inputs.get(&(index, component)).cloned(),
+            (OperandType::Output, &[Index::U32(index)]) => self.outputs.get(&(index, component)).cloned(),
+            (OperandType::ConstantBuffer, &[Index::U32(buffer), Index::U32(row)]) => {
+                self.constants.get(&(buffer, row * 4 + component as u32)).cloned()
+            }
+            _ => None,
+        }
+    }
+
+    fn add_variable(&mut self, buffer: u32, offset: u32, ty: &ShaderType) {
+        // arrays and matrices start every element and row on a register
+        let (registers, components) = match ty.class {
+            ShaderVariableClass::MatrixRows => (ty.rows as u32, ty.columns as u32),
+            ShaderVariableClass::MatrixColumns => (ty.columns as u32, ty.rows as u32),
+            ShaderVariableClass::Scalar | ShaderVariableClass::Vector => (1, ty.columns as u32),
+            ShaderVariableClass::Struct => {
+                let stride = ty.members.iter().map(|member| member.offset).max().unwrap_or(0) / 16 * 16 + 16;
+                for element in 0..(ty.count as u32).max(1) {
+                    for member in &ty.members {
+                        self.add_variable(buffer, offset + element * stride + member.offset, &member.ty);
+                    }
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        let scalar = match Type::from_variable_type(ty.ty) {
+            Some(scalar) => scalar,
+            None => return,
+        };
+
+        for element in 0..(ty.count as u32).max(1) {
+            for register in 0..registers {
+                let start = offset / 4 + (element * registers + register) * 4;
+                for component in 0..components {
+                    self.constants.insert((buffer, start + component), scalar);
+                }
+            }
+        }
+    }
+}
+
+struct InterfaceConsumer {
+    interface: Interface,
+}
+
+fn signature_types(chunk: &dr::IOsgnChunk) -> HashMap<(u32, u8), Type> {
+    let mut types = HashMap::new();
+
+    for element in &chunk.elements {
+        let ty = match Type::from_component_type(element.component_type) {
+            Some(ty) if element.register != !0 => ty,
+            _ => continue,
+        };
+        for component in (0..4).filter(|&c| element.component_mask & (1 << c) != 0) {
+            types.insert((element.register, component), ty);
+        }
+    }
+
+    types
+}
+
+impl Consumer for InterfaceConsumer {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn consume_rdef(&mut self, rdef: &dr::RdefChunk) -> Action {
+        for binding in &rdef.resource_bindings {
+            // constant buffers
+            if binding.input_type != 0 {
+                continue;
+            }
+            let buffer = match rdef.constant_buffers.iter().find(|buffer| buffer.name == binding.name) {
+                Some(buffer) => buffer,
+                None => continue,
+            };
+            for variable in &buffer.variables {
+                self.interface.add_variable(binding.bind_point, variable.start_offset, &variable.ty);
+            }
+        }
+
+        Action::Continue
+    }
+
+    fn consume_isgn(&mut self, isgn: &dr::IOsgnChunk) -> Action {
+        self.interface.inputs = signature_types(isgn);
+        Action::Continue
+    }
+
+    fn consume_osgn(&mut self, osgn: &dr::IOsgnChunk) -> Action {
+        self.interface.outputs = signature_types(osgn);
+        Action::Continue
+    }
+}
+
+/// Reinterpretation of the bits of a component read or written by an
+/// operand, `from` is the type of the value and `to` the type the
+/// instruction or register has there.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bitcast {
+    pub instruction: usize,
+    pub operand: usize,
+    pub component: u8,
+    pub from: Type,
+    pub to: Type,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionTypes {
+    /// Type of each SSA value of the function.
+    pub values: Vec<Type>,
+    pub bitcasts: Vec<Bitcast>,
+}
+
+/// Types of the temp values of a program. Values take the type that needs
+/// the fewest bitcasts given what the instructions writing and reading them
+/// operate on; copies, selects, bitwise operations and phis don't care and
+/// pass the type along.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Types {
+    /// One for each function of the SSA form.
+    pub functions: Vec<FunctionTypes>,
+    resources: HashMap<(OperandType, u32), [Option<Type>; 4]>,
+}
+
+// Instructions whose results have the type of their sources.
+fn is_polymorphic(opcode: u32) -> bool {
+    matches!(opcode, D3D10_SB_OPCODE_MOV | D3D10_SB_OPCODE_MOVC | D3D11_SB_OPCODE_SWAPC | D3D10_SB_OPCODE_AND |
+        D3D10_SB_OPCODE_OR | D3D10_SB_OPCODE_XOR | D3D10_SB_OPCODE_NOT | D3D10_SB_OPCODE_ISHL)
+}
+
+fn is_resource(file: OperandType) -> bool {
+    matches!(file, OperandType::Resource | OperandType::UnorderedAccessView | OperandType::Sampler |
+        OperandType::ThreadGroupSharedMemory)
+}
+
+// Lanes read from a source for the written components of a componentwise
+// instruction, `None` if the source isn't swizzled by component.
+fn lane(register: &Register, component: u8) -> Option<u8> {
+    match register.components {
+        Components::Swizzle(swizzle) => Some(swizzle[component as usize]),
+        Components::Select(select) => Some(select),
+        Components::Mask(mask) if mask & (1 << component) != 0 => Some(component),
+        _ => None,
+    }
+}
+
+fn is_index_read(register: &Register, temp: Temp) -> bool {
+    let main = register.file == OperandType::Temp && register.index.first() == Some(&Index::U32(temp.register));
+    !main && register.index.iter().filter_map(Index::relative).any(|relative| {
+        relative.file == OperandType::Temp && relative.index.first() == Some(&Index::U32(temp.register))
+    })
+}
+
+// Union-find over the values of a function.
+fn find(parents: &mut [usize], value: usize) -> usize {
+    let mut root = value;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut value = value;
+    while parents[value] != root {
+        let next = parents[value];
+        parents[value] = root;
+        value = next;
+    }
+    root
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parents, a), find(parents, b));
+    if a != b {
+        parents[b] = a;
+    }
+}
+
+impl Types {
+    pub fn infer(program: &Program, ssa: &Ssa, interface: &Interface) -> Types {
+        let mut types = Types::new(program);
+        types.functions = ssa
+            .cfg
+            .functions
+            .iter()
+            .zip(&ssa.functions)
+            .map(|(function, values)| {
+                let instructions: Vec<usize> = function.blocks.iter().flat_map(|block| block.start..block.end).collect();
+                types.function(program, &instructions, values, interface)
+            })
+            .collect();
+
+        types
+    }
+
+    // Only the operand types of the program's instructions, without any
+    // values.
+    pub(crate) fn new(program: &Program) -> Types {
+        let mut resources = HashMap::new();
+        for declaration in program.decls.iter().chain(program.phase_decls.iter().map(|(_, declaration)| declaration)) {
+            let (register, return_type) = match *declaration {
+                Declaration::Resource { ref register, return_type, .. } |
+                Declaration::UnorderedAccessViewTyped { ref register, return_type, .. } => (register, return_type),
+                _ => continue,
+            };
+            if let Some(&Index::U32(index)) = register.index.first() {
+                let types = [
+                    Type::from_return_type(return_type[0]),
+                    Type::from_return_type(return_type[1]),
+                    Type::from_return_type(return_type[2]),
+                    Type::from_return_type(return_type[3]),
+                ];
+                resources.insert((register.file, index), types);
+            }
+        }
+
+        Types { functions: Vec::new(), resources }
+    }
+
+    // Type of a resource component, from the extended opcode of indexable
+    // instructions or the declaration.
+    fn resource_type(&self, instruction: &Instruction, component: u8) -> Option<Type> {
+        for extended in &instruction.extended {
+            if let OpcodeEx::ResourceReturnType(x, y, z, w) = *extended {
+                return Type::from_return_type([x, y, z, w][component as usize] as u32);
+            }
+        }
+
+        let register = instruction
+            .operands
+            .iter()
+            .filter_map(Operand::register)
+            .find(|register| register.file == OperandType::Resource || register.file == OperandType::UnorderedAccessView)?;
+        let lane = lane(register, component).unwrap_or(component);
+        match register.index.first() {
+            Some(&Index::U32(index)) => self.resources.get(&(register.file, index)).and_then(|types| types[lane as usize]),
+            _ => None,
+        }
+    }
+
+    /// Type an instruction reads a source operand as, `None` if any type
+    /// will do or the result takes the type of the source.
+    pub fn source_type(&self, program: &Program, instruction: usize, operand: usize) -> Option<Type> {
+        let instruction = &program.instructions[instruction];
+
+        if let Some(register) = instruction.operands[operand].register() {
+            if is_resource(register.file) {
+                return None;
+            }
+        }
+
+        let ty = match instruction.opcode {
+            D3D10_SB_OPCODE_ADD | D3D10_SB_OPCODE_DIV | D3D10_SB_OPCODE_EXP | D3D10_SB_OPCODE_FRC |
+            D3D10_SB_OPCODE_LOG | D3D10_SB_OPCODE_MAD | D3D10_SB_OPCODE_MAX | D3D10_SB_OPCODE_MIN |
+            D3D10_SB_OPCODE_MUL | D3D10_SB_OPCODE_ROUND_NE | D3D10_SB_OPCODE_ROUND_NI | D3D10_SB_OPCODE_ROUND_PI |
+            D3D10_SB_OPCODE_ROUND_Z | D3D10_SB_OPCODE_RSQ | D3D10_SB_OPCODE_SQRT | D3D10_SB_OPCODE_SINCOS |
+            D3D10_SB_OPCODE_DERIV_RTX | D3D10_SB_OPCODE_DERIV_RTY | D3D11_SB_OPCODE_DERIV_RTX_COARSE |
+            D3D11_SB_OPCODE_DERIV_RTX_FINE | D3D11_SB_OPCODE_DERIV_RTY_COARSE | D3D11_SB_OPCODE_DERIV_RTY_FINE |
+            D3D11_SB_OPCODE_RCP | D3D10_SB_OPCODE_DP2 | D3D10_SB_OPCODE_DP3 | D3D10_SB_OPCODE_DP4 |
+            D3D10_SB_OPCODE_EQ | D3D10_SB_OPCODE_GE | D3D10_SB_OPCODE_LT | D3D10_SB_OPCODE_NE |
+            D3D10_SB_OPCODE_FTOI | D3D10_SB_OPCODE_FTOU | D3D11_SB_OPCODE_F32TOF16 | D3D10_SB_OPCODE_SAMPLE |
+            D3D10_SB_OPCODE_SAMPLE_B | D3D10_SB_OPCODE_SAMPLE_L | D3D10_SB_OPCODE_SAMPLE_D | D3D10_SB_OPCODE_SAMPLE_C |
+            D3D10_SB_OPCODE_SAMPLE_C_LZ | D3D10_1_SB_OPCODE_GATHER4 | D3D10_1_SB_OPCODE_LOD |
+            D3D11_SB_OPCODE_EVAL_CENTROID => Type::Float,
+
+            D3D11_SB_OPCODE_GATHER4_C => Type::Float,
+            D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C if operand == 2 => Type::Int,
+            D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C => Type::Float,
+
+            D3D10_SB_OPCODE_ISHR | D3D10_SB_OPCODE_ISHL if operand == 2 => Type::Uint,
+            D3D10_SB_OPCODE_ITOF | D3D10_SB_OPCODE_IADD | D3D10_SB_OPCODE_IMAD | D3D10_SB_OPCODE_IMAX |
+            D3D10_SB_OPCODE_IMIN | D3D10_SB_OPCODE_IMUL | D3D10_SB_OPCODE_INEG | D3D10_SB_OPCODE_ISHR |
+            D3D10_SB_OPCODE_IGE | D3D10_SB_OPCODE_ILT | D3D11_SB_OPCODE_FIRSTBIT_SHI | D3D10_SB_OPCODE_SWITCH |
+            D3D10_SB_OPCODE_LD | D3D10_SB_OPCODE_LD_MS | D3D10_1_SB_OPCODE_SAMPLE_POS |
+            D3D11_SB_OPCODE_LD_UAV_TYPED | D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX | D3D11_SB_OPCODE_EVAL_SNAPPED |
+            D3D11_SB_OPCODE_ATOMIC_IADD | D3D11_SB_OPCODE_ATOMIC_IMAX | D3D11_SB_OPCODE_ATOMIC_IMIN |
+            D3D11_SB_OPCODE_IMM_ATOMIC_IADD | D3D11_SB_OPCODE_IMM_ATOMIC_IMAX | D3D11_SB_OPCODE_IMM_ATOMIC_IMIN => {
+                Type::Int
+            }
+            D3D11_SB_OPCODE_IBFE if operand == 3 => Type::Int,
+
+            D3D10_SB_OPCODE_UTOF | D3D11_SB_OPCODE_F16TOF32 | D3D10_SB_OPCODE_UDIV | D3D10_SB_OPCODE_UMUL |
+            D3D10_SB_OPCODE_UMAD | D3D10_SB_OPCODE_UMAX | D3D10_SB_OPCODE_UMIN | D3D10_SB_OPCODE_USHR |
+            D3D10_SB_OPCODE_UGE | D3D10_SB_OPCODE_ULT | D3D11_SB_OPCODE_UADDC | D3D11_SB_OPCODE_USUBB |
+            D3D11_SB_OPCODE_UBFE | D3D11_SB_OPCODE_IBFE | D3D11_SB_OPCODE_BFI | D3D11_SB_OPCODE_BFREV |
+            D3D11_SB_OPCODE_COUNTBITS | D3D11_SB_OPCODE_FIRSTBIT_HI | D3D11_SB_OPCODE_FIRSTBIT_LO |
+            D3D10_SB_OPCODE_RESINFO | D3D11_SB_OPCODE_LD_RAW | D3D11_SB_OPCODE_LD_STRUCTURED |
+            D3D11_SB_OPCODE_ATOMIC_UMAX | D3D11_SB_OPCODE_ATOMIC_UMIN | D3D11_SB_OPCODE_IMM_ATOMIC_UMAX |
+            D3D11_SB_OPCODE_IMM_ATOMIC_UMIN | D3D11_1_SB_OPCODE_MSAD => Type::Uint,
+
+            // addresses, the stored values are untyped
+            D3D11_SB_OPCODE_STORE_RAW | D3D11_SB_OPCODE_ATOMIC_AND | D3D11_SB_OPCODE_ATOMIC_OR |
+            D3D11_SB_OPCODE_ATOMIC_XOR | D3D11_SB_OPCODE_ATOMIC_CMP_STORE if operand == 1 => Type::Uint,
+            D3D11_SB_OPCODE_IMM_ATOMIC_AND | D3D11_SB_OPCODE_IMM_ATOMIC_OR | D3D11_SB_OPCODE_IMM_ATOMIC_XOR |
+            D3D11_SB_OPCODE_IMM_ATOMIC_EXCH | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH if operand == 2 => Type::Uint,
+            D3D11_SB_OPCODE_STORE_STRUCTURED if operand == 1 || operand == 2 => Type::Uint,
+            D3D11_SB_OPCODE_STORE_UAV_TYPED if operand == 1 => Type::Int,
+            D3D11_SB_OPCODE_STORE_UAV_TYPED => return self.resource_type(instruction, 0),
+
+            D3D10_SB_OPCODE_MOVC if operand == 1 => Type::Bool,
+            D3D11_SB_OPCODE_SWAPC if operand == 2 => Type::Bool,
+            D3D10_SB_OPCODE_IF | D3D10_SB_OPCODE_BREAKC | D3D10_SB_OPCODE_CONTINUEC | D3D10_SB_OPCODE_RETC |
+            D3D10_SB_OPCODE_DISCARD | D3D10_SB_OPCODE_CALLC if operand == 0 => Type::Bool,
+
+            _ => return None,
+        };
+
+        Some(ty)
+    }
+
+    /// Type of a component an instruction writes to a destination, `None` if
+    /// it is the type of the sources or the bits are untyped.
+    pub fn result_type(&self, program: &Program, instruction: usize, component: u8) -> Option<Type> {
+        let instruction = &program.instructions[instruction];
+
+        let ty = match instruction.opcode {
+            D3D10_SB_OPCODE_ADD | D3D10_SB_OPCODE_DIV | D3D10_SB_OPCODE_EXP | D3D10_SB_OPCODE_FRC |
+            D3D10_SB_OPCODE_LOG | D3D10_SB_OPCODE_MAD | D3D10_SB_OPCODE_MAX | D3D10_SB_OPCODE_MIN |
+            D3D10_SB_OPCODE_MUL | D3D10_SB_OPCODE_ROUND_NE | D3D10_SB_OPCODE_ROUND_NI | D3D10_SB_OPCODE_ROUND_PI |
+            D3D10_SB_OPCODE_ROUND_Z | D3D10_SB_OPCODE_RSQ | D3D10_SB_OPCODE_SQRT | D3D10_SB_OPCODE_SINCOS |
+            D3D10_SB_OPCODE_DERIV_RTX | D3D10_SB_OPCODE_DERIV_RTY | D3D11_SB_OPCODE_DERIV_RTX_COARSE |
+            D3D11_SB_OPCODE_DERIV_RTX_FINE | D3D11_SB_OPCODE_DERIV_RTY_COARSE | D3D11_SB_OPCODE_DERIV_RTY_FINE |
+            D3D11_SB_OPCODE_RCP | D3D10_SB_OPCODE_DP2 | D3D10_SB_OPCODE_DP3 | D3D10_SB_OPCODE_DP4 |
+            D3D10_SB_OPCODE_ITOF | D3D10_SB_OPCODE_UTOF | D3D11_SB_OPCODE_F16TOF32 | D3D10_1_SB_OPCODE_LOD |
+            D3D10_1_SB_OPCODE_SAMPLE_POS | D3D11_SB_OPCODE_EVAL_CENTROID | D3D11_SB_OPCODE_EVAL_SAMPLE_INDEX |
+            D3D11_SB_OPCODE_EVAL_SNAPPED | D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ => Type::Float,
+
+            D3D10_SB_OPCODE_EQ | D3D10_SB_OPCODE_GE | D3D10_SB_OPCODE_LT | D3D10_SB_OPCODE_NE |
+            D3D10_SB_OPCODE_IEQ | D3D10_SB_OPCODE_IGE | D3D10_SB_OPCODE_ILT | D3D10_SB_OPCODE_INE |
+            D3D10_SB_OPCODE_UGE | D3D10_SB_OPCODE_ULT => Type::Bool,
+
+            D3D10_SB_OPCODE_FTOI | D3D10_SB_OPCODE_IADD | D3D10_SB_OPCODE_IMAD | D3D10_SB_OPCODE_IMAX |
+            D3D10_SB_OPCODE_IMIN | D3D10_SB_OPCODE_IMUL | D3D10_SB_OPCODE_INEG | D3D10_SB_OPCODE_ISHR |
+            D3D11_SB_OPCODE_IBFE | D3D11_SB_OPCODE_FIRSTBIT_SHI | D3D11_SB_OPCODE_IMM_ATOMIC_IADD |
+            D3D11_SB_OPCODE_IMM_ATOMIC_IMAX | D3D11_SB_OPCODE_IMM_ATOMIC_IMIN => Type::Int,
+
+            D3D10_SB_OPCODE_FTOU | D3D11_SB_OPCODE_F32TOF16 | D3D10_SB_OPCODE_UDIV | D3D10_SB_OPCODE_UMUL |
+            D3D10_SB_OPCODE_UMAD | D3D10_SB_OPCODE_UMAX | D3D10_SB_OPCODE_UMIN | D3D10_SB_OPCODE_USHR |
+            D3D11_SB_OPCODE_UADDC | D3D11_SB_OPCODE_USUBB | D3D11_SB_OPCODE_UBFE | D3D11_SB_OPCODE_BFI |
+            D3D11_SB_OPCODE_BFREV | D3D11_SB_OPCODE_COUNTBITS | D3D11_SB_OPCODE_FIRSTBIT_HI |
+            D3D11_SB_OPCODE_FIRSTBIT_LO | D3D11_SB_OPCODE_BUFINFO | D3D11_SB_OPCODE_IMM_ATOMIC_UMAX |
+            D3D11_SB_OPCODE_IMM_ATOMIC_UMIN | D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC |
+            D3D11_SB_OPCODE_IMM_ATOMIC_CONSUME | D3D11_1_SB_OPCODE_MSAD => Type::Uint,
+
+            D3D10_SB_OPCODE_RESINFO => {
+                match DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(instruction.controls) {
+                    D3D10_SB_RESINFO_INSTRUCTION_RETURN_UINT => Type::Uint,
+                    _ => Type::Float,
+                }
+            }
+            D3D10_1_SB_OPCODE_SAMPLE_INFO => {
+                match DECODE_D3D10_SB_INSTRUCTION_RETURN_TYPE(instruction.controls) {
+                    D3D10_SB_INSTRUCTION_RETURN_UINT => Type::Uint,
+                    _ => Type::Float,
+                }
+            }
+
+            D3D10_SB_OPCODE_SAMPLE | D3D10_SB_OPCODE_SAMPLE_B | D3D10_SB_OPCODE_SAMPLE_L | D3D10_SB_OPCODE_SAMPLE_D |
+            D3D10_1_SB_OPCODE_GATHER4 | D3D11_SB_OPCODE_GATHER4_C | D3D11_SB_OPCODE_GATHER4_PO |
+            D3D11_SB_OPCODE_GATHER4_PO_C | D3D10_SB_OPCODE_LD | D3D10_SB_OPCODE_LD_MS |
+            D3D11_SB_OPCODE_LD_UAV_TYPED => return self.resource_type(instruction, component),
+
+            _ => return None,
+        };
+
+        Some(ty)
+    }
+
+    fn function(&self, program: &Program, instructions: &[usize], ssa: &FunctionSsa, interface: &Interface) -> FunctionTypes {
+        let count = ssa.values.len();
+        let mut parents: Vec<usize> = (0..count).collect();
+        let mut votes = vec![[0u32; 4]; count];
+        // first type a producer gave each value, wins ties
+        let mut produced: Vec<Option<Type>> = vec![None; count];
+
+        let vote = |votes: &mut Vec<[u32; 4]>, value: usize, ty: Type| {
+            votes[value][TYPES.iter().position(|&t| t == ty).unwrap()] += 1;
+        };
+
+        for (value, info) in ssa.values.iter().enumerate() {
+            if let Def::Phi(_, ref incoming) = info.def {
+                for &(_, from) in incoming {
+                    union(&mut parents, value, from);
+                }
+            }
+        }
+
+        for &i in instructions {
+            let instruction = &program.instructions[i];
+            let destinations = ssa::destinations(instruction.opcode).min(instruction.operands.len());
+            let polymorphic = is_polymorphic(instruction.opcode);
+
+            for access in ssa.reads.get(&i).map(|reads| &reads[..]).unwrap_or(&[]) {
+                let operand = match access.operand {
+                    Some(operand) => operand,
+                    None => continue,
+                };
+                let register = instruction.operands[operand].register().unwrap();
+
+                if operand < destinations || is_index_read(register, access.temp) {
+                    vote(&mut votes, access.value, Type::Int);
+                } else if let Some(ty) = self.source_type(program, i, operand) {
+                    vote(&mut votes, access.value, ty);
+                } else if polymorphic {
+                    // the same type as what it's copied to
+                    for destination in 0..destinations {
+                        let target = match instruction.operands[destination].register() {
+                            Some(target) => target,
+                            None => continue,
+                        };
+                        for component in (0..4).filter(|&c| target.components.mask() & (1 << c) != 0) {
+                            if lane(register, component) != Some(access.temp.component) {
+                                continue;
+                            }
+                            if target.file == OperandType::Temp {
+                                let written = Temp { register: target.index[0].offset() as u32, component };
+                                if let Some(value) = ssa.written(i, destination, written) {
+                                    union(&mut parents, value, access.value);
+                                }
+                            } else if let Some(ty) = interface.register_type(target, component) {
+                                vote(&mut votes, access.value, ty);
+                            }
+                        }
+                    }
+                }
+            }
+
+            for access in ssa.writes.get(&i).map(|writes| &writes[..]).unwrap_or(&[]) {
+                if access.operand.is_none() {
+                    continue;
+                }
+                if let Some(ty) = self.result_type(program, i, access.temp.component) {
+                    vote(&mut votes, access.value, ty);
+                    produced[access.value].get_or_insert(ty);
+                } else if polymorphic {
+                    // copies of registers with a declared type
+                    for operand in destinations..instruction.operands.len() {
+                        let source = match instruction.operands[operand].register() {
+                            Some(source) if source.file != OperandType::Temp => source,
+                            _ => continue,
+                        };
+                        if self.source_type(program, i, operand).is_some() {
+                            continue;
+                        }
+                        if let Some(ty) = lane(source, access.temp.component).and_then(|c| interface.register_type(source, c)) {
+                            vote(&mut votes, access.value, ty);
+                            produced[access.value].get_or_insert(ty);
+                        }
+                    }
+                }
+            }
+        }
+
+        // pick the type with the most votes in each class
+        let mut totals: HashMap<usize, ([u32; 4], Option<Type>)> = HashMap::new();
+        for value in 0..count {
+            let root = find(&mut parents, value);
+            let total = totals.entry(root).or_insert(([0; 4], None));
+            for (sum, votes) in total.0.iter_mut().zip(&votes[value]) {
+                *sum += votes;
+            }
+            if total.1.is_none() {
+                total.1 = produced[value];
+            }
+        }
+
+        let values: Vec<Type> = (0..count)
+            .map(|value| {
+                let (total, produced) = totals[&find(&mut parents, value)];
+                let best = *total.iter().max().unwrap();
+                match produced {
+                    Some(ty) if total[TYPES.iter().position(|&t| t == ty).unwrap()] == best => ty,
+                    _ => TYPES[total.iter().position(|&votes| votes == best).unwrap()],
+                }
+            })
+            .collect();
+
+        let bitcasts = self.bitcasts(program, instructions, ssa, interface, &values);
+        FunctionTypes { values, bitcasts }
+    }
+
+    // Reads and writes where the type of the value differs from the one the
+    // instruction or register wants.
+    fn bitcasts(&self, program: &Program, instructions: &[usize], ssa: &FunctionSsa, interface: &Interface, values: &[Type]) -> Vec<Bitcast> {
+        let mut bitcasts = Vec::new();
+
+        for &i in instructions {
+            let instruction = &program.instructions[i];
+            let destinations = ssa::destinations(instruction.opcode).min(instruction.operands.len());
+
+            for operand in destinations..instruction.operands.len() {
+                let register = match instruction.operands[operand].register() {
+                    Some(register) => register,
+                    None => continue,
+                };
+
+                let components: Vec<u8> = match register.components {
+                    // componentwise instructions only read the lanes they write
+                    Components::Swizzle(swizzle) => {
+                        let mut components: Vec<u8> = match instruction.operands.first().and_then(Operand::register) {
+                            Some(target) if destinations > 0 && ssa::is_componentwise(instruction.opcode) => {
+                                (0..4).filter(|&c| target.components.mask() & (1 << c) != 0).map(|c| swizzle[c as usize]).collect()
+                            }
+                            _ => swizzle.to_vec(),
+                        };
+                        components.sort();
+                        components.dedup();
+                        components
+                    }
+                    Components::Select(component) => vec![component],
+                    Components::Mask(mask) => (0..4).filter(|&c| mask & (1 << c) != 0).collect(),
+                    Components::None | Components::Scalar => vec![0],
+                };
+
+                let expected = match self.source_type(program, i, operand) {
+                    Some(ty) => ty,
+                    None => continue,
+                };
+
+                for component in components {
+                    let actual = if register.file == OperandType::Temp {
+                        let temp = Temp { register: register.index[0].offset() as u32, component };
+                        ssa.read(i, operand, temp).map(|value| values[value])
+                    } else {
+                        interface.register_type(register, component)
+                    };
+
+                    match actual {
+                        Some(from) if from != expected => {
+                            bitcasts.push(Bitcast { instruction: i, operand, component, from, to: expected })
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            for destination in 0..destinations {
+                let register = match instruction.operands[destination].register() {
+                    Some(register) => register,
+                    None => continue,
+                };
+
+                for component in (0..4).filter(|&c| register.components.mask() & (1 << c) != 0) {
+                    let from = match self.result_type(program, i, component) {
+                        Some(from) => from,
+                        None => continue,
+                    };
+                    let to = if register.file == OperandType::Temp {
+                        let temp = Temp { register: register.index[0].offset() as u32, component };
+                        ssa.written(i, destination, temp).map(|value| values[value])
+                    } else {
+                        interface.register_type(register, component)
+                    };
+
+                    match to {
+                        Some(to) if to != from => {
+                            bitcasts.push(Bitcast { instruction: i, operand: destination, component, from, to })
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        bitcasts
+    }
+}
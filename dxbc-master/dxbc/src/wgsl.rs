@@ -0,0 +1,1618 @@
+use binary::*;
+use backend::{self, Chunks, Constant, ConstantArray, Destination, Element, Error, Language, Layout, Resource, ResourceKind,
+              Shader, Writer, MAX_REGISTERS};
+use dr;
+use dr::rdef::ShaderVariableClass;
+use dr::shex::{OperandType, ResourceDimension, ShexHeader, SparseInstruction};
+use dr::builder::{GlobalFlags, Modifier};
+use d3d11tokenizedprogramformat::*;
+use disasm::COMPONENTS;
+use ir::{Components, Declaration, Index, Operand, Program, Register};
+use types::Type;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Bind group of each register file, the binding is the register index.
+pub const CONSTANT_BUFFER_GROUP: u32 = 0;
+pub const RESOURCE_GROUP: u32 = 1;
+pub const SAMPLER_GROUP: u32 = 2;
+pub const UNORDERED_ACCESS_VIEW_GROUP: u32 = 3;
+
+fn scalar_name(ty: Type) -> &'static str {
+    match ty {
+        Type::Float => "f32",
+        Type::Int => "i32",
+        Type::Uint => "u32",
+        Type::Bool => "bool",
+    }
+}
+
+fn vector(ty: Type, width: usize) -> String {
+    if width == 1 {
+        scalar_name(ty).to_owned()
+    } else {
+        format!("vec{}<{}>", width, scalar_name(ty))
+    }
+}
+
+// WGSL converts nothing implicitly, every change of type is spelled out.
+fn convert(expr: String, from: Type, to: Type, width: usize) -> String {
+    match (from, backend::storage(to)) {
+        (from, to) if from == to => expr,
+        (Type::Bool, to) => {
+            let mask = if width == 1 {
+                format!("select(0u, 0xffffffffu, {})", expr)
+            } else {
+                let ones = vector(Type::Uint, width);
+                format!("select({}(0u), {}(0xffffffffu), {})", ones, ones, expr)
+            };
+            convert(mask, Type::Uint, to, width)
+        }
+        (_, to) => format!("bitcast<{}>({})", vector(to, width), expr),
+    }
+}
+
+fn format_literal(bits: u32, ty: Type) -> String {
+    match backend::storage(ty) {
+        Type::Float => {
+            let val = f32::from_bits(bits);
+
+            // denormals and non-finite values don't survive a trip through
+            // a decimal literal, keep their exact bit pattern instead
+            if val.is_normal() || val == 0.0 {
+                format!("{:?}", val)
+            } else {
+                format!("bitcast<f32>(0x{:08x}u)", bits)
+            }
+        }
+        Type::Int if bits == 0x8000_0000 => "bitcast<i32>(0x80000000u)".to_owned(),
+        Type::Int => format!("{}i", bits as i32),
+        _ => format!("{}u", bits),
+    }
+}
+
+// Condition testing each component of an unsigned value, WGSL doesn't
+// compare vectors with scalars.
+fn nonzero(value: &str, width: usize) -> String {
+    if width == 1 {
+        format!("{} != 0u", value)
+    } else {
+        format!("{} != {}(0u)", value, vector(Type::Uint, width))
+    }
+}
+
+fn texture_name(dimension: ResourceDimension, depth: bool) -> Option<&'static str> {
+    Some(match (dimension, depth) {
+        (ResourceDimension::Texture2D, true) => "texture_depth_2d",
+        (ResourceDimension::Texture2DArray, true) => "texture_depth_2d_array",
+        (ResourceDimension::Texture2DMS, true) => "texture_depth_multisampled_2d",
+        (ResourceDimension::TextureCube, true) => "texture_depth_cube",
+        (ResourceDimension::TextureCubeArray, true) => "texture_depth_cube_array",
+        (ResourceDimension::Texture1D, _) => "texture_1d",
+        (ResourceDimension::Texture2D, _) => "texture_2d",
+        (ResourceDimension::Texture2DArray, _) => "texture_2d_array",
+        (ResourceDimension::Texture2DMS, _) => "texture_multisampled_2d",
+        (ResourceDimension::Texture3D, _) => "texture_3d",
+        (ResourceDimension::TextureCube, _) => "texture_cube",
+        (ResourceDimension::TextureCubeArray, _) => "texture_cube_array",
+        _ => return None,
+    })
+}
+
+fn storage_texture_name(dimension: ResourceDimension) -> Option<&'static str> {
+    Some(match dimension {
+        ResourceDimension::Texture1D => "texture_storage_1d",
+        ResourceDimension::Texture2D => "texture_storage_2d",
+        ResourceDimension::Texture2DArray => "texture_storage_2d_array",
+        ResourceDimension::Texture3D => "texture_storage_3d",
+        _ => return None,
+    })
+}
+
+// DXBC doesn't know the format of a view, storage textures take the
+// widest one of their channel type.
+fn storage_format(ty: Type) -> &'static str {
+    match ty {
+        Type::Int => "rgba32sint",
+        Type::Uint => "rgba32uint",
+        _ => "rgba32float",
+    }
+}
+
+// One dimensional textures take no texel offsets in WGSL.
+fn texture_coordinates(dimension: ResourceDimension) -> (usize, usize) {
+    match backend::texture_coordinates(dimension) {
+        (coordinates, _) if dimension == ResourceDimension::Texture1D || dimension == ResourceDimension::Texture1DArray => {
+            (coordinates, 0)
+        }
+        coordinates => coordinates,
+    }
+}
+
+fn interpolation_attribute(mode: u32) -> Option<&'static str> {
+    match mode {
+        D3D10_SB_INTERPOLATION_CONSTANT => Some("@interpolate(flat)"),
+        D3D10_SB_INTERPOLATION_LINEAR_CENTROID => Some("@interpolate(perspective, centroid)"),
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE => Some("@interpolate(linear)"),
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_CENTROID => Some("@interpolate(linear, centroid)"),
+        D3D10_SB_INTERPOLATION_LINEAR_SAMPLE => Some("@interpolate(perspective, sample)"),
+        D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_SAMPLE => Some("@interpolate(linear, sample)"),
+        _ => None,
+    }
+}
+
+// Assignment of `value` to some `components` of a four component
+// register, WGSL can only assign whole vectors or single components.
+fn partial_write(base: &str, ty: Type, components: &[u8], value: &str) -> Vec<String> {
+    let (first, last) = match (components.first(), components.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Vec::new(),
+    };
+
+    if components.len() == 4 {
+        return vec![format!("{} = {};", base, value)];
+    }
+    if components.len() == 1 {
+        return vec![format!("{}.{} = {};", base, COMPONENTS[first as usize], value)];
+    }
+
+    // a contiguous run is put together with the components around it
+    if (last - first) as usize + 1 == components.len() {
+        let mut parts = Vec::new();
+        if first > 0 {
+            let before: Vec<u8> = (0..first).collect();
+            parts.push(format!("{}.{}", base, backend::swizzle(&before)));
+        }
+        parts.push(value.to_owned());
+        if last < 3 {
+            let after: Vec<u8> = (last + 1..4).collect();
+            parts.push(format!("{}.{}", base, backend::swizzle(&after)));
+        }
+        return vec![format!("{} = {}({});", base, vector(ty, 4), parts.join(", "))];
+    }
+
+    let mut lines = vec!["{".to_owned(), format!("    let value = {};", value)];
+    for (n, &c) in components.iter().enumerate() {
+        lines.push(format!("    {}.{} = value.{};", base, COMPONENTS[c as usize], COMPONENTS[n]));
+    }
+    lines.push("}".to_owned());
+    lines
+}
+
+impl Element {
+    fn builtin(&self, program_type: u32, input: bool) -> Option<&'static str> {
+        let pixel = program_type == D3D10_SB_PIXEL_SHADER;
+        Some(match self.semantic_type {
+            dr::SemanticName::Depth | dr::SemanticName::DepthGreaterEqual | dr::SemanticName::DepthLessEqual => "frag_depth",
+            dr::SemanticName::Coverage => "sample_mask",
+            dr::SemanticName::VertexId if input && !pixel => "vertex_index",
+            dr::SemanticName::InstanceId if input && !pixel => "instance_index",
+            dr::SemanticName::Position if pixel == input => "position",
+            dr::SemanticName::IsFrontFace if pixel => "front_facing",
+            dr::SemanticName::SampleIndex if pixel => "sample_index",
+            _ => return None,
+        })
+    }
+
+    // Attributes of the struct member passing the element, the rest goes
+    // through user locations numbered by register.
+    fn attributes(&self, program_type: u32, input: bool, interpolation: u32, flat: bool) -> String {
+        if let Some(builtin) = self.builtin(program_type, input) {
+            return format!("@builtin({})", builtin);
+        }
+
+        let location = format!("@location({})", self.register);
+        let varying = (program_type == D3D10_SB_PIXEL_SHADER) == input;
+        if !varying {
+            return location;
+        }
+
+        // integers can't be interpolated
+        let mode = if flat { D3D10_SB_INTERPOLATION_CONSTANT } else { interpolation };
+        match interpolation_attribute(mode) {
+            Some(attribute) => format!("{} {}", location, attribute),
+            None => location,
+        }
+    }
+}
+
+// Elements packed into one register share its location, declared by the
+// first of them with enough components for all.
+struct Location {
+    owner: usize,
+    width: usize,
+    flat: bool,
+}
+
+fn locations(elements: &[Element], program_type: u32, input: bool) -> HashMap<u32, Location> {
+    let mut locations: HashMap<u32, Location> = HashMap::new();
+    for (n, element) in elements.iter().enumerate() {
+        if element.register == !0 || element.builtin(program_type, input).is_some() {
+            continue;
+        }
+
+        let width = 8 - element.mask.leading_zeros() as usize;
+        let location = locations.entry(element.register).or_insert(Location { owner: n, width: 0, flat: false });
+        location.width = location.width.max(width);
+        location.flat |= element.ty != Type::Float;
+    }
+    locations
+}
+
+// Uniform structs are laid out by the alignment of their members, which
+// matches the HLSL packing for aligned vectors. Gaps become padding
+// members, vectors off their alignment are split into scalars and
+// variables spanning several registers are declared as arrays of
+// registers, any variable packed into their last register is read from
+// there.
+fn add_constant_buffer(shader: &mut Shader, buffer: &dr::ConstantBuffer, slot: u32) {
+    let buffer_name = backend::identifier(buffer.name);
+    let mut text = format!("struct {}_t\n{{\n", buffer_name);
+
+    let mut variables: Vec<&dr::ShaderVariable> = buffer.variables.iter().collect();
+    variables.sort_by_key(|variable| variable.start_offset);
+
+    let mut end = 0;
+    let mut padding = 0;
+    for variable in variables {
+        let ty = &variable.ty;
+        let offset = variable.start_offset;
+        let scalar = match ty.class {
+            ShaderVariableClass::Struct => Type::Float,
+            _ => match backend::variable_type(ty.ty) {
+                Some(scalar) => backend::storage(scalar),
+                None => continue,
+            },
+        };
+        // a buffer holds at most `MAX_REGISTERS` registers, anything past
+        // them is corrupt
+        if offset / 16 >= MAX_REGISTERS {
+            continue;
+        }
+        let name = backend::identifier(variable.name);
+
+        if offset < end {
+            if let Some(array) = shader.arrays.iter().rev().find(|array| array.buffer == slot) {
+                let element = offset / 16 - array.start;
+                writeln!(text, "    // {} {} is in {}[{}]", backend::type_name(ty), name, array.name, element).unwrap();
+            }
+            continue;
+        }
+
+        if offset > end {
+            writeln!(text, "    @size({}) _pad{}: u32,", offset - end, padding).unwrap();
+            padding += 1;
+        }
+
+        let single = ty.count == 0 && (ty.class == ShaderVariableClass::Scalar || ty.class == ShaderVariableClass::Vector);
+        if single {
+            let width = ty.columns.clamp(1, 4) as usize;
+            let member = format!("{}.{}", buffer_name, name);
+            let aligned = match width {
+                2 => offset % 8 == 0,
+                3 | 4 => offset % 16 == 0,
+                _ => true,
+            };
+
+            if aligned {
+                writeln!(text, "    {}: {},", name, vector(scalar, width)).unwrap();
+                for c in 0..width as u32 {
+                    let lane = if width == 1 { None } else { Some(c as u8) };
+                    shader.constants.insert((slot, offset / 4 + c), Constant { base: member.clone(), lane, ty: scalar });
+                }
+            } else {
+                writeln!(text, "    // {} {}", backend::type_name(ty), name).unwrap();
+                for c in 0..width as u32 {
+                    writeln!(text, "    {}_{}: {},", name, c, scalar_name(scalar)).unwrap();
+                    let base = format!("{}_{}", member, c);
+                    shader.constants.insert((slot, offset / 4 + c), Constant { base, lane: None, ty: scalar });
+                }
+            }
+            end = offset + 4 * width as u32;
+        } else {
+            let start = offset / 16;
+            let registers = backend::type_size(ty).div_ceil(16).min(MAX_REGISTERS - start);
+            let array = if ty.count > 0 { format!("[{}]", ty.count) } else { String::new() };
+            writeln!(text, "    {}: array<{}, {}>, // {} {}{}", name, vector(scalar, 4), registers, backend::type_name(ty), name, array).unwrap();
+
+            let array_name = format!("{}.{}", buffer_name, name);
+            for dword in start * 4..(start + registers) * 4 {
+                let base = format!("{}[{}]", array_name, dword / 4 - start);
+                shader.constants.insert((slot, dword), Constant { base, lane: Some((dword % 4) as u8), ty: scalar });
+            }
+            shader.arrays.push(ConstantArray {
+                buffer: slot,
+                name: array_name,
+                start,
+                registers,
+                count: 0,
+                layout: Layout::Vector,
+                ty: scalar,
+            });
+            end = (start + registers) * 16;
+        }
+    }
+
+    text.push_str("};\n");
+    shader.constant_buffers.push((slot, buffer_name, text));
+}
+
+
+const HELPERS: [(&str, &str); 4] = [
+    // WGSL has no 64 bit integers, the high half of a product is summed
+    // up from 16 bit halves
+    ("dxbc_umul_hi", "\
+fn dxbc_umul_hi(a: u32, b: u32) -> u32
+{
+    let lo = (a & 0xffffu) * (b & 0xffffu);
+    let mid0 = (a >> 16u) * (b & 0xffffu);
+    let mid1 = (a & 0xffffu) * (b >> 16u);
+    let carry = ((lo >> 16u) + (mid0 & 0xffffu) + (mid1 & 0xffffu)) >> 16u;
+    return (a >> 16u) * (b >> 16u) + (mid0 >> 16u) + (mid1 >> 16u) + carry;
+}
+"),
+    ("dxbc_imul_hi", "\
+fn dxbc_imul_hi(a: i32, b: i32) -> i32
+{
+    let hi = dxbc_umul_hi(bitcast<u32>(a), bitcast<u32>(b));
+    return bitcast<i32>(hi - select(0u, bitcast<u32>(b), a < 0i) - select(0u, bitcast<u32>(a), b < 0i));
+}
+"),
+    // WebGPU can't query the render target, the pipeline sets its sample count
+    ("dxbc_sample_count", "\
+override dxbc_sample_count: u32 = 1u;
+"),
+    // the standard D3D sample patterns in sixteenths of a pixel
+    ("dxbc_sample_position", "\
+var<private> dxbc_sample_positions: array<vec2<i32>, 31> = array<vec2<i32>, 31>(
+    vec2<i32>(0, 0),
+    vec2<i32>(4, 4), vec2<i32>(-4, -4),
+    vec2<i32>(-2, -6), vec2<i32>(6, -2), vec2<i32>(-6, 2), vec2<i32>(2, 6),
+    vec2<i32>(1, -3), vec2<i32>(-1, 3), vec2<i32>(5, 1), vec2<i32>(-3, -5),
+    vec2<i32>(-5, 5), vec2<i32>(-7, -1), vec2<i32>(3, 7), vec2<i32>(7, -7),
+    vec2<i32>(1, 1), vec2<i32>(-1, -3), vec2<i32>(-3, 2), vec2<i32>(4, -1),
+    vec2<i32>(-5, -2), vec2<i32>(2, 5), vec2<i32>(5, 3), vec2<i32>(3, -5),
+    vec2<i32>(-2, 6), vec2<i32>(0, -7), vec2<i32>(-4, -6), vec2<i32>(-6, 4),
+    vec2<i32>(-8, 0), vec2<i32>(7, -4), vec2<i32>(6, 7), vec2<i32>(-7, -8),
+);
+
+fn dxbc_sample_position(count: u32, index: u32) -> vec2<f32>
+{
+    if (index >= count || count > 16u || (count & (count - 1u)) != 0u) {
+        return vec2<f32>(0.0);
+    }
+    return vec2<f32>(dxbc_sample_positions[count - 1u + index]) / 16.0;
+}
+"),
+];
+
+struct Wgsl {
+    /// Open cases and whether there was a default, of each switch.
+    switches: Vec<(bool, bool)>,
+}
+
+impl Language for Wgsl {
+    const LOOP: &'static str = "loop {";
+    const DISCARD: &'static str = "discard;";
+
+    fn vector(ty: Type, width: usize) -> String {
+        vector(ty, width)
+    }
+
+    fn convert(expr: String, from: Type, to: Type, width: usize) -> String {
+        convert(expr, from, to, width)
+    }
+
+    fn literal(bits: u32, ty: Type) -> String {
+        format_literal(bits, ty)
+    }
+
+    fn system_value(file: OperandType) -> Option<(&'static str, Type, usize, &'static str)> {
+        Some(match file {
+            OperandType::InputCoverageMask => ("vCoverage", Type::Uint, 1, "sample_mask"),
+            OperandType::InputThreadId => ("vThreadID", Type::Uint, 3, "global_invocation_id"),
+            OperandType::InputThreadGroupId => ("vThreadGroupID", Type::Uint, 3, "workgroup_id"),
+            OperandType::InputThreadIdInGroup => ("vThreadIDInGroup", Type::Uint, 3, "local_invocation_id"),
+            OperandType::InputThreadIdInGroupFlattened => ("vThreadIDInGroupFlattened", Type::Uint, 1, "local_invocation_index"),
+            OperandType::OutputDepth | OperandType::OutputDepthGreaterEqual | OperandType::OutputDepthLessEqual => {
+                ("oDepth", Type::Float, 1, "")
+            }
+            OperandType::OutputCoverageMask => ("oMask", Type::Uint, 1, ""),
+            _ => return None,
+        })
+    }
+
+    fn add_constant_buffer(shader: &mut Shader, buffer: &dr::ConstantBuffer, slot: u32) {
+        add_constant_buffer(shader, buffer, slot)
+    }
+
+    fn local(name: &str, _ty: Type, _width: usize, value: &str) -> String {
+        format!("let {} = {};", name, value)
+    }
+
+    fn ret(writer: &mut Writer<Self>) -> Result<(), Error> {
+        writer.ret();
+        Ok(())
+    }
+
+    fn operation(writer: &mut Writer<Self>, i: usize) -> Result<(), Error> {
+        writer.operation(i)
+    }
+
+    // unsigned values have no negation
+    fn modify(value: String, modifier: Modifier, ty: Type) -> String {
+        match (modifier, backend::storage(ty)) {
+            (Modifier::None, _) => value,
+            (Modifier::Neg, Type::Uint) => format!("(0u - {})", value),
+            (Modifier::Neg, _) => backend::negate(&value),
+            (Modifier::Abs, _) => format!("abs({})", value),
+            (Modifier::AbsNeg, Type::Uint) => format!("(0u - abs({}))", value),
+            (Modifier::AbsNeg, _) => format!("-abs({})", value),
+        }
+    }
+
+    fn guarded(condition: &str, statement: &str) -> String {
+        format!("if ({}) {{ {} }}", condition, statement)
+    }
+
+    // System values are members of the input, unless the subroutines need
+    // them as private globals.
+    fn variable(writer: &Writer<Self>, name: &str, system: bool) -> String {
+        if system && !writer.global && !name.starts_with('o') {
+            format!("input.{}", name)
+        } else {
+            name.to_owned()
+        }
+    }
+
+    fn store(writer: &mut Writer<Self>, dst: &Destination, value: String) {
+        if dst.components.is_empty() {
+            let text = format!("{} = {};", dst.base, value);
+            writer.line(&text);
+            return;
+        }
+
+        for text in partial_write(&dst.base, dst.ty, &dst.components, &value) {
+            writer.line(&text);
+        }
+    }
+
+    // Cases don't fall through and a switch needs a default.
+    fn switch(writer: &mut Writer<Self>, i: usize) -> Result<(), Error> {
+        match writer.program.instructions[i].opcode {
+            D3D10_SB_OPCODE_SWITCH => {
+                let text = format!("switch ({}) {{", writer.src(writer.operand(i, 0)?, &[0], Type::Int));
+                writer.line(&text);
+                writer.indent += 1;
+                writer.language.switches.push((false, false));
+            }
+            D3D10_SB_OPCODE_CASE | D3D10_SB_OPCODE_DEFAULT => writer.case(i)?,
+            _ => {
+                let (open, default) = writer.language.switches.pop().unwrap_or((false, false));
+                if open {
+                    writer.dedent(i, 1)?;
+                    writer.line("}");
+                }
+                if !default {
+                    writer.line("default: {}");
+                }
+                writer.dedent(i, 1)?;
+                writer.line("}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Writer<'a, Wgsl> {
+    fn pixel(&self) -> bool {
+        self.shader.program_type == D3D10_SB_PIXEL_SHADER
+    }
+
+    // D3D only uses the low five bits of a shift, WGSL rejects constant
+    // shifts past the width.
+    fn shift(&mut self, i: usize, op: &str) -> Result<(), Error> {
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let count = match *self.operand(i, 2)? {
+                Operand::Imm32(ref values) => Operand::Imm32(values.iter().map(|value| value & 31).collect()),
+                ref operand => operand.clone(),
+            };
+            let value = self.src(self.operand(i, 1)?, &dst.written(), self.source_type(i, 1));
+            let count = self.src(&count, &dst.written(), Type::Uint);
+            let ty = self.result_type(i, dst.first());
+            self.assign(i, &dst, ty, format!("{} {} {}", value, op, count));
+        }
+
+        Ok(())
+    }
+
+    // Writes a comparison as the all ones or all zeros mask produced by the
+    // D3D comparison instructions.
+    fn compare(&mut self, i: usize, op: &str) -> Result<(), Error> {
+        let width = self.dst(self.operand(i, 0)?).map_or(1, |dst| dst.width());
+        let ty = vector(Type::Uint, width);
+        if width == 1 {
+            self.componentwise(i, &[1, 2], &|args| format!("select(0u, 0xffffffffu, {} {} {})", args[0], op, args[1]))
+        } else {
+            self.componentwise(i, &[1, 2], &|args| format!("select({}(0u), {}(0xffffffffu), {} {} {})", ty, ty, args[0], op, args[1]))
+        }
+    }
+
+    // Texture coordinates without the array slice, and the slice.
+    fn texture_address(&self, dimension: ResourceDimension, address: &Operand, ty: Type) -> (String, Option<String>) {
+        let (coordinates, _) = texture_coordinates(dimension);
+        if !backend::arrayed(dimension) {
+            let components: Vec<u8> = (0..coordinates as u8).collect();
+            return (self.src(address, &components, ty), None);
+        }
+
+        let components: Vec<u8> = (0..coordinates as u8 - 1).collect();
+        let slice = self.src(address, &[coordinates as u8 - 1], ty);
+        let slice = if ty == Type::Float { format!("i32(round({}))", slice) } else { slice };
+        (self.src(address, &components, ty), Some(slice))
+    }
+
+    fn sample(&mut self, i: usize) -> Result<(), Error> {
+        let opcode = self.program.instructions[i].opcode;
+
+        // texel offsets have to be constant in WGSL
+        if opcode == D3D11_SB_OPCODE_GATHER4_PO || opcode == D3D11_SB_OPCODE_GATHER4_PO_C {
+            return Err(self.unsupported(i, " with a programmable offset"));
+        }
+        let (address, resource, sampler) = (1, 2, 3);
+        let extra = sampler + 1;
+
+        let texture = self.register(i, resource)?;
+        let sampler_register = self.register(i, sampler)?;
+        let dimension = self.dimension(texture);
+        let depth = self.resource(texture).is_some_and(|resource| resource.depth);
+        let (_, offsets) = texture_coordinates(dimension);
+        let pixel = self.pixel();
+
+        let (coordinates, slice) = self.texture_address(dimension, self.operand(i, address)?, Type::Float);
+        let mut args = vec![self.resource_name(texture), self.resource_name(sampler_register), coordinates];
+        args.extend(slice);
+        let float = |operand: usize| -> Result<String, Error> { Ok(self.src(self.operand(i, operand)?, &[0], Type::Float)) };
+
+        // implicit derivatives only exist in pixel shaders
+        let function = match opcode {
+            D3D10_SB_OPCODE_SAMPLE | D3D10_SB_OPCODE_SAMPLE_B if !pixel => {
+                args.push(if depth { "0i" } else { "0.0" }.to_owned());
+                "textureSampleLevel"
+            }
+            D3D10_SB_OPCODE_SAMPLE => "textureSample",
+            D3D10_SB_OPCODE_SAMPLE_B => {
+                args.push(float(extra)?);
+                "textureSampleBias"
+            }
+            D3D10_SB_OPCODE_SAMPLE_L => {
+                // depth textures only have whole mip levels
+                let level = float(extra)?;
+                args.push(if depth { format!("i32({})", level) } else { level });
+                "textureSampleLevel"
+            }
+            D3D10_SB_OPCODE_SAMPLE_D => {
+                let gradients = match dimension {
+                    ResourceDimension::TextureCube | ResourceDimension::TextureCubeArray | ResourceDimension::Texture3D => 3,
+                    _ => offsets,
+                };
+                let components: Vec<u8> = (0..gradients as u8).collect();
+                args.push(self.src(self.operand(i, extra)?, &components, Type::Float));
+                args.push(self.src(self.operand(i, extra + 1)?, &components, Type::Float));
+                "textureSampleGrad"
+            }
+            D3D10_SB_OPCODE_SAMPLE_C if pixel => {
+                args.push(float(extra)?);
+                "textureSampleCompare"
+            }
+            D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ => {
+                args.push(float(extra)?);
+                "textureSampleCompareLevel"
+            }
+            D3D10_1_SB_OPCODE_GATHER4 => {
+                if !depth {
+                    args.insert(0, format!("{}", sampler_register.components.swizzle()[0] & 3));
+                }
+                "textureGather"
+            }
+            _ => {
+                args.push(float(extra)?);
+                "textureGatherCompare"
+            }
+        };
+
+        args.extend(self.immediate_offset(i, offsets));
+
+        let value = format!("{}({})", function, args.join(", "));
+        match function {
+            "textureSampleCompare" | "textureSampleCompareLevel" => {
+                if let Some(dst) = self.dst(self.operand(i, 0)?) {
+                    self.assign(i, &dst, Type::Float, value);
+                }
+                Ok(())
+            }
+            "textureGather" | "textureGatherCompare" => self.assign_resource(i, resource, value),
+            _ => {
+                let value = self.depth_texel(texture, value);
+                self.assign_resource(i, resource, value)
+            }
+        }
+    }
+
+    fn load(&mut self, i: usize) -> Result<(), Error> {
+        let texture = self.register(i, 2)?;
+        let address = self.operand(i, 1)?;
+        let dimension = self.dimension(texture);
+        let (_, offsets) = texture_coordinates(dimension);
+        let multisampled = self.program.instructions[i].opcode == D3D10_SB_OPCODE_LD_MS;
+
+        let (coordinates, slice) = self.texture_address(dimension, address, Type::Int);
+        let coordinates = match self.immediate_offset(i, offsets) {
+            Some(offset) => format!("{} + {}", coordinates, offset),
+            None => coordinates,
+        };
+
+        // the mip level is always in the last address component
+        let mut args = vec![self.resource_name(texture), coordinates];
+        args.extend(slice);
+        if multisampled {
+            args.push(self.src(self.operand(i, 3)?, &[0], Type::Int));
+        } else {
+            args.push(self.src(address, &[3], Type::Int));
+        }
+
+        let value = format!("textureLoad({})", args.join(", "));
+        let value = self.depth_texel(texture, value);
+        self.assign_resource(i, 2, value)
+    }
+
+    fn typed_address(&self, uav: &Register, address: &Operand) -> String {
+        let (coordinates, slice) = self.texture_address(self.dimension(uav), address, Type::Int);
+        match slice {
+            Some(slice) => format!("{}, {}", coordinates, slice),
+            None => coordinates,
+        }
+    }
+
+    // Element of structured or raw memory holding a dword.
+    fn memory(&self, resource: &Register, index: Option<&Operand>, offset: &Operand, component: u8) -> String {
+        let name = self.resource_name(resource);
+        let dword = match *offset {
+            Operand::Imm32(ref values) => format!("{}u", values.first().cloned().unwrap_or(0) / 4 + component as u32),
+            _ => {
+                let offset = self.src(offset, &[0], Type::Uint);
+                match component {
+                    0 => format!("({} >> 2u)", offset),
+                    c => format!("({} >> 2u) + {}u", offset, c),
+                }
+            }
+        };
+
+        match index {
+            Some(index) => {
+                let stride = self.resource(resource).map_or(1, |resource| resource.stride);
+                format!("{}[{} * {}u + {}]", name, self.src(index, &[0], Type::Uint), stride, dword)
+            }
+            None => format!("{}[{}]", name, dword),
+        }
+    }
+
+    fn atomic_memory(&self, resource: &Register) -> bool {
+        self.resource(resource).is_some_and(|resource| resource.atomic)
+    }
+
+    fn load_memory(&mut self, i: usize) -> Result<(), Error> {
+        let structured = self.program.instructions[i].opcode == D3D11_SB_OPCODE_LD_STRUCTURED;
+        let resource = if structured { 3 } else { 2 };
+        let register = self.register(i, resource)?;
+        let atomic = self.atomic_memory(register);
+
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            let picks = self.resource_picks(self.operand(i, resource)?, &dst.written());
+            let index = if structured { Some(self.operand(i, 1)?) } else { None };
+            let offset = self.operand(i, if structured { 2 } else { 1 })?;
+            let parts: Vec<String> = picks
+                .iter()
+                .map(|&c| {
+                    let element = self.memory(register, index, offset, c);
+                    if atomic { format!("atomicLoad(&{})", element) } else { element }
+                })
+                .collect();
+            let expr = if parts.len() == 1 {
+                parts[0].clone()
+            } else {
+                format!("{}({})", vector(Type::Uint, parts.len()), parts.join(", "))
+            };
+            self.assign(i, &dst, Type::Uint, expr);
+        }
+
+        Ok(())
+    }
+
+    fn store_memory(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 0)?;
+        let components = backend::mask_components(register.components.mask());
+        let structured = self.program.instructions[i].opcode == D3D11_SB_OPCODE_STORE_STRUCTURED;
+        let value = self.operand(i, if structured { 3 } else { 2 })?;
+        let atomic = self.atomic_memory(register);
+
+        let index = if structured { Some(self.operand(i, 1)?) } else { None };
+        let offset = self.operand(i, if structured { 2 } else { 1 })?;
+        for (n, &c) in components.iter().enumerate() {
+            let element = self.memory(register, index, offset, n as u8);
+            let value = self.src(value, &[c], Type::Uint);
+            let text = if atomic {
+                format!("atomicStore(&{}, {});", element, value)
+            } else {
+                format!("{} = {};", element, value)
+            };
+            self.line(&text);
+        }
+
+        Ok(())
+    }
+
+    fn store_typed(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 0)?;
+        let ty = self.resource(register).map_or(Type::Float, |resource| resource.ty);
+
+        let text = format!(
+            "textureStore({}, {}, {});",
+            self.resource_name(register),
+            self.typed_address(register, self.operand(i, 1)?),
+            self.src(self.operand(i, 2)?, &[0, 1, 2, 3], ty)
+        );
+        self.line(&text);
+        Ok(())
+    }
+
+    fn atomic(&mut self, i: usize) -> Result<(), Error> {
+        let opcode = self.program.instructions[i].opcode;
+        let immediate = opcode >= D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC;
+        let first = if immediate { 1 } else { 0 };
+        let register = self.register(i, first)?;
+
+        if opcode == D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC || opcode == D3D11_SB_OPCODE_IMM_ATOMIC_CONSUME {
+            return Err(self.unsupported(i, " without a hidden counter in WebGPU"));
+        }
+        let kind = self.resource(register).map_or(ResourceKind::Raw, |resource| resource.kind);
+        if register.file == OperandType::UnorderedAccessView && kind == ResourceKind::Typed {
+            return Err(self.unsupported(i, " on a texture"));
+        }
+        // memory is declared as unsigned atomics
+        if matches!(
+            opcode,
+            D3D11_SB_OPCODE_ATOMIC_IMAX | D3D11_SB_OPCODE_IMM_ATOMIC_IMAX | D3D11_SB_OPCODE_ATOMIC_IMIN | D3D11_SB_OPCODE_IMM_ATOMIC_IMIN
+        ) {
+            return Err(self.unsupported(i, " on unsigned atomics"));
+        }
+
+        let values = match opcode {
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => 2,
+            _ => 1,
+        };
+        let args = ((first + 2)..(first + 2 + values))
+            .map(|operand| Ok(self.src(self.operand(i, operand)?, &[0], Type::Uint)))
+            .collect::<Result<Vec<String>, Error>>()?;
+
+        let address = self.operand(i, first + 1)?;
+        let target = if kind == ResourceKind::Structured {
+            let offset = match *address {
+                Operand::Register(ref address) => {
+                    let mut offset = address.clone();
+                    offset.components = Components::Select(address.components.swizzle()[1]);
+                    Operand::Register(offset)
+                }
+                Operand::Imm32(ref values) => Operand::Imm32(vec![values.get(1).cloned().unwrap_or(0)]),
+                ref other => other.clone(),
+            };
+            self.memory(register, Some(address), &offset, 0)
+        } else {
+            self.memory(register, None, address, 0)
+        };
+
+        let function = match opcode {
+            D3D11_SB_OPCODE_ATOMIC_AND | D3D11_SB_OPCODE_IMM_ATOMIC_AND => "atomicAnd",
+            D3D11_SB_OPCODE_ATOMIC_OR | D3D11_SB_OPCODE_IMM_ATOMIC_OR => "atomicOr",
+            D3D11_SB_OPCODE_ATOMIC_XOR | D3D11_SB_OPCODE_IMM_ATOMIC_XOR => "atomicXor",
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => {
+                // the weak exchange may fail spuriously, retry while the
+                // value still matches
+                self.line("loop {");
+                self.indent += 1;
+                let text = format!("let result = atomicCompareExchangeWeak(&{}, {}, {});", target, args[0], args[1]);
+                self.line(&text);
+                let text = format!("if (result.exchanged || result.old_value != {}) {{", args[0]);
+                self.line(&text);
+                self.indent += 1;
+                if let Some(dst) = self.dst(self.operand(i, 0)?).filter(|_| immediate) {
+                    self.assign(i, &dst, Type::Uint, "result.old_value".to_owned());
+                }
+                self.line("break;");
+                self.dedent(i, 1)?;
+                self.line("}");
+                self.dedent(i, 1)?;
+                self.line("}");
+                return Ok(());
+            }
+            D3D11_SB_OPCODE_IMM_ATOMIC_EXCH => "atomicExchange",
+            D3D11_SB_OPCODE_ATOMIC_IADD | D3D11_SB_OPCODE_IMM_ATOMIC_IADD => "atomicAdd",
+            D3D11_SB_OPCODE_ATOMIC_UMAX | D3D11_SB_OPCODE_IMM_ATOMIC_UMAX => "atomicMax",
+            _ => "atomicMin",
+        };
+
+        let call = format!("{}(&{}, {})", function, target, args[0]);
+        if !immediate {
+            let text = format!("_ = {};", call);
+            self.line(&text);
+        } else if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            self.assign(i, &dst, Type::Uint, call);
+        }
+
+        Ok(())
+    }
+
+    fn resinfo(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 2)?;
+        let dimension = self.dimension(register);
+        let uav = register.file == OperandType::UnorderedAccessView;
+        let multisampled = dimension == ResourceDimension::Texture2DMS;
+        let name = self.resource_name(register);
+
+        let (sizes, elements) = match dimension {
+            ResourceDimension::Texture1D => (1, false),
+            ResourceDimension::Texture2D | ResourceDimension::TextureCube | ResourceDimension::Texture2DMS => (2, false),
+            ResourceDimension::Texture3D => (3, false),
+            _ => (2, true),
+        };
+
+        let mipmapped = !uav && !multisampled;
+        let dimensions = if mipmapped {
+            format!("textureDimensions({}, {})", name, self.src(self.operand(i, 1)?, &[0], Type::Uint))
+        } else {
+            format!("textureDimensions({})", name)
+        };
+
+        let return_type = DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(self.program.instructions[i].controls);
+        let rcp = return_type == D3D10_SB_RESINFO_INSTRUCTION_RETURN_RCPFLOAT;
+        let ty = if return_type == D3D10_SB_RESINFO_INSTRUCTION_RETURN_UINT { Type::Uint } else { Type::Float };
+        let value = |value: String, width: usize| {
+            if ty == Type::Uint { value } else { format!("{}({})", vector(Type::Float, width), value) }
+        };
+
+        let mut parts = vec![if rcp {
+            format!("1.0 / {}", value(dimensions, sizes))
+        } else {
+            value(dimensions, sizes)
+        }];
+        if elements {
+            parts.push(value(format!("textureNumLayers({})", name), 1));
+        }
+        for _ in sizes + elements as usize..3 {
+            parts.push(format_literal(0, ty));
+        }
+        parts.push(if mipmapped {
+            value(format!("textureNumLevels({})", name), 1)
+        } else {
+            format_literal(if ty == Type::Uint { 1 } else { 1.0f32.to_bits() }, ty)
+        });
+
+        let value = format!("{}({})", vector(ty, 4), parts.join(", "));
+        self.assign_resource(i, 2, value)
+    }
+
+    fn bufinfo(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let (kind, stride) = self.resource(register).map_or((ResourceKind::Raw, 1), |resource| (resource.kind, resource.stride));
+
+        let length = format!("arrayLength(&{})", self.resource_name(register));
+        let value = match kind {
+            ResourceKind::Raw => format!("{} * 4u", length),
+            ResourceKind::Structured => format!("{} / {}u", length, stride),
+            ResourceKind::Typed => return Err(self.unsupported(i, " of a typed buffer, WebGPU has no texel buffers")),
+        };
+
+        if let Some(dst) = self.dst(self.operand(i, 0)?) {
+            self.assign(i, &dst, Type::Uint, value);
+        }
+
+        Ok(())
+    }
+
+    // Discarding is as close as WebGPU gets to ending the draw.
+    fn abort(&mut self, i: usize) -> Result<(), Error> {
+        if self.shader.program_type != D3D10_SB_PIXEL_SHADER {
+            return Err(self.unsupported(i, " outside a pixel shader"));
+        }
+        self.line(Wgsl::DISCARD);
+        Ok(())
+    }
+
+    // Samples of a multisampled texture or of the render target.
+    fn sample_count(&mut self, register: &Register) -> String {
+        if register.file == OperandType::Rasterizer {
+            self.helpers.insert("dxbc_sample_count");
+            "dxbc_sample_count".to_owned()
+        } else {
+            format!("textureNumSamples({})", self.resource_name(register))
+        }
+    }
+
+    fn sample_info(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let ty = self.result_type(i, 0);
+
+        let samples = self.sample_count(register);
+        let samples = if ty == Type::Uint { samples } else { format!("{}({})", scalar_name(ty), samples) };
+        let zero = format_literal(0, ty);
+        let value = format!("{}({}, {}, {}, {})", vector(ty, 4), samples, zero, zero, zero);
+        self.assign_resource(i, 1, value)
+    }
+
+    fn sample_position(&mut self, i: usize) -> Result<(), Error> {
+        let register = self.register(i, 1)?;
+        let index = self.src(self.operand(i, 2)?, &[0], Type::Uint);
+        let samples = self.sample_count(register);
+        self.helpers.insert("dxbc_sample_position");
+        let position = format!("dxbc_sample_position({}, {})", samples, index);
+        self.assign_resource(i, 1, format!("vec4<f32>({}, 0.0, 0.0)", position))
+    }
+
+    fn sync(&mut self, i: usize) -> Result<(), Error> {
+        let flags = self.program.instructions[i].controls;
+        if flags & D3D11_SB_SYNC_THREADS_IN_GROUP == 0 {
+            return Err(self.unsupported(i, " without a workgroup barrier"));
+        }
+
+        if flags & (D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GLOBAL | D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GROUP) != 0 {
+            self.line("storageBarrier();");
+            self.line("textureBarrier();");
+        }
+        if flags & D3D11_SB_SYNC_THREAD_GROUP_SHARED_MEMORY != 0 || flags & !D3D11_SB_SYNC_THREADS_IN_GROUP == 0 {
+            self.line("workgroupBarrier();");
+        }
+
+        Ok(())
+    }
+
+    fn write_output_copies(&mut self) {
+        let outputs = &self.shader.outputs;
+        let locations = locations(outputs, self.shader.program_type, false);
+
+        for (n, output) in outputs.iter().enumerate() {
+            let components = backend::mask_components(output.mask);
+
+            let text = match locations.get(&output.register) {
+                _ if output.register == !0 => match output.system_register() {
+                    Some(name) => {
+                        let from = if name == "oMask" { Type::Uint } else { Type::Float };
+                        format!("output.{} = {};", output.member(), convert(name.to_owned(), from, output.ty, 1))
+                    }
+                    None => continue,
+                },
+                Some(location) if location.owner != n => continue,
+                Some(location) if components.len() != location.width || components[0] != 0 => {
+                    // put together from the elements sharing the register,
+                    // unwritten components are zero
+                    let parts: Vec<String> = (0..location.width as u8)
+                        .map(|c| {
+                            let written = outputs.iter().any(|element| element.register == output.register && element.mask & (1 << c) != 0);
+                            if written {
+                                convert(format!("o{}.{}", output.register, COMPONENTS[c as usize]), Type::Float, output.ty, 1)
+                            } else {
+                                format_literal(0, output.ty)
+                            }
+                        })
+                        .collect();
+                    format!("output.{} = {}({});", output.member(), vector(output.ty, location.width), parts.join(", "))
+                }
+                _ => {
+                    let value = format!("o{}.{}", output.register, backend::swizzle(&components));
+                    format!("output.{} = {};", output.member(), convert(value, Type::Float, output.ty, components.len()))
+                }
+            };
+            self.line(&text);
+        }
+    }
+
+    fn ret(&mut self) {
+        if self.in_subroutine {
+            self.line("return;");
+        } else if self.shader.outputs.is_empty() {
+            // vertex shaders have to output a position
+            let vertex = self.shader.program_type != D3D10_SB_PIXEL_SHADER && self.shader.program_type != D3D11_SB_COMPUTE_SHADER;
+            self.line(if vertex { "return vec4<f32>(0.0);" } else { "return;" });
+        } else {
+            self.line("var output: Output;");
+            self.write_output_copies();
+            self.line("return output;");
+        }
+    }
+
+    // Opens the case of a switch, consecutive labels share one case.
+    fn case(&mut self, i: usize) -> Result<(), Error> {
+        let program = self.program;
+        let is_label = |opcode: u32| opcode == D3D10_SB_OPCODE_CASE || opcode == D3D10_SB_OPCODE_DEFAULT;
+        if i > 0 && is_label(program.instructions[i - 1].opcode) {
+            return Ok(());
+        }
+
+        let mut selectors = Vec::new();
+        for (n, instruction) in program.instructions.iter().enumerate().skip(i).take_while(|&(_, instruction)| is_label(instruction.opcode)) {
+            if instruction.opcode == D3D10_SB_OPCODE_DEFAULT {
+                selectors.push("default".to_owned());
+            } else {
+                selectors.push(self.src(self.operand(n, 0)?, &[0], Type::Int));
+            }
+        }
+
+        let has_default = selectors.iter().any(|selector| selector == "default");
+        if let Some((open, default)) = self.language.switches.pop() {
+            if open {
+                self.dedent(i, 1)?;
+                self.line("}");
+            }
+            self.language.switches.push((true, default || has_default));
+        }
+
+        let text = format!("case {}: {{", selectors.join(", "));
+        let text = text.replace("case default:", "default:");
+        self.line(&text);
+        self.indent += 1;
+        Ok(())
+    }
+
+    fn operation(&mut self, i: usize) -> Result<(), Error> {
+        // declarations WGSL has no type for leave their resources undeclared
+        let undeclared = self.program.instructions[i]
+            .operands
+            .iter()
+            .filter_map(Operand::register)
+            .filter(|register| {
+                matches!(
+                    register.file,
+                    OperandType::Resource | OperandType::UnorderedAccessView | OperandType::ThreadGroupSharedMemory
+                )
+            })
+            .any(|register| self.resource(register).is_none());
+        if undeclared {
+            return Err(self.unsupported(i, " on an undeclared resource"));
+        }
+
+        match self.program.instructions[i].opcode {
+            D3D10_SB_OPCODE_ADD | D3D10_SB_OPCODE_IADD => self.binary(i, "+"),
+            D3D10_SB_OPCODE_MUL => self.binary(i, "*"),
+            D3D10_SB_OPCODE_DIV => self.binary(i, "/"),
+            D3D10_SB_OPCODE_AND => self.binary(i, "&"),
+            D3D10_SB_OPCODE_OR => self.binary(i, "|"),
+            D3D10_SB_OPCODE_XOR => self.binary(i, "^"),
+            D3D10_SB_OPCODE_ISHL => self.shift(i, "<<"),
+            D3D10_SB_OPCODE_ISHR | D3D10_SB_OPCODE_USHR => self.shift(i, ">>"),
+            D3D10_SB_OPCODE_MAD => self.call(i, "fma"),
+            D3D10_SB_OPCODE_IMAD | D3D10_SB_OPCODE_UMAD => {
+                self.componentwise(i, &[1, 2, 3], &|args| format!("{} * {} + {}", args[0], args[1], args[2]))
+            }
+            D3D10_SB_OPCODE_MOV => self.componentwise(i, &[1], &|args| args[0].clone()),
+            D3D10_SB_OPCODE_MOVC => {
+                let width = self.dst(self.operand(i, 0)?).map_or(1, |dst| dst.width());
+                self.componentwise(i, &[1, 2, 3], &|args| format!("select({}, {}, {})", args[2], args[1], nonzero(&args[0], width)))
+            }
+            D3D10_SB_OPCODE_NOT => self.componentwise(i, &[1], &|args| format!("~{}", args[0])),
+            D3D10_SB_OPCODE_INEG => self.componentwise(i, &[1], &|args| backend::negate(&args[0])),
+            D3D10_SB_OPCODE_ITOF | D3D10_SB_OPCODE_UTOF | D3D10_SB_OPCODE_FTOI | D3D10_SB_OPCODE_FTOU => {
+                if let Some(dst) = self.dst(self.operand(i, 0)?) {
+                    let ty = self.result_type(i, dst.first());
+                    let value = self.src(self.operand(i, 1)?, &dst.written(), self.source_type(i, 1));
+                    self.assign(i, &dst, ty, format!("{}({})", vector(ty, dst.width()), value));
+                }
+                Ok(())
+            }
+            D3D10_SB_OPCODE_MIN | D3D10_SB_OPCODE_IMIN | D3D10_SB_OPCODE_UMIN => self.call(i, "min"),
+            D3D10_SB_OPCODE_MAX | D3D10_SB_OPCODE_IMAX | D3D10_SB_OPCODE_UMAX => self.call(i, "max"),
+            D3D10_SB_OPCODE_DP2 => self.dot(i, 2),
+            D3D10_SB_OPCODE_DP3 => self.dot(i, 3),
+            D3D10_SB_OPCODE_DP4 => self.dot(i, 4),
+            D3D10_SB_OPCODE_EXP => self.call(i, "exp2"),
+            D3D10_SB_OPCODE_LOG => self.call(i, "log2"),
+            D3D10_SB_OPCODE_FRC => self.call(i, "fract"),
+            D3D10_SB_OPCODE_SQRT => self.call(i, "sqrt"),
+            D3D10_SB_OPCODE_RSQ => self.call(i, "inverseSqrt"),
+            D3D11_SB_OPCODE_RCP => self.componentwise(i, &[1], &|args| format!("1.0 / {}", args[0])),
+            D3D10_SB_OPCODE_ROUND_NE => self.call(i, "round"),
+            D3D10_SB_OPCODE_ROUND_NI => self.call(i, "floor"),
+            D3D10_SB_OPCODE_ROUND_PI => self.call(i, "ceil"),
+            D3D10_SB_OPCODE_ROUND_Z => self.call(i, "trunc"),
+            D3D10_SB_OPCODE_DERIV_RTX => self.call(i, "dpdx"),
+            D3D10_SB_OPCODE_DERIV_RTY => self.call(i, "dpdy"),
+            D3D11_SB_OPCODE_DERIV_RTX_COARSE => self.call(i, "dpdxCoarse"),
+            D3D11_SB_OPCODE_DERIV_RTX_FINE => self.call(i, "dpdxFine"),
+            D3D11_SB_OPCODE_DERIV_RTY_COARSE => self.call(i, "dpdyCoarse"),
+            D3D11_SB_OPCODE_DERIV_RTY_FINE => self.call(i, "dpdyFine"),
+            D3D11_SB_OPCODE_COUNTBITS => self.call(i, "countOneBits"),
+            D3D11_SB_OPCODE_BFREV => self.call(i, "reverseBits"),
+            D3D11_SB_OPCODE_FIRSTBIT_LO => self.call(i, "firstTrailingBit"),
+            // D3D counts the bit position from the most significant end
+            D3D11_SB_OPCODE_FIRSTBIT_HI => {
+                self.scalar(i, &[1], &|args| format!("select(countLeadingZeros({}), 0xffffffffu, {} == 0u)", args[0], args[0]))
+            }
+            D3D11_SB_OPCODE_FIRSTBIT_SHI => self.scalar(i, &[1], &|args| {
+                format!("select(31u - bitcast<u32>(firstLeadingBit({})), 0xffffffffu, {} == 0i || {} == -1i)", args[0], args[0], args[0])
+            }),
+            D3D11_SB_OPCODE_F32TOF16 => self.scalar(i, &[1], &|args| format!("pack2x16float(vec2<f32>({}, 0.0))", args[0])),
+            D3D11_SB_OPCODE_F16TOF32 => self.scalar(i, &[1], &|args| format!("unpack2x16float({}).x", args[0])),
+            D3D10_SB_OPCODE_EQ | D3D10_SB_OPCODE_IEQ => self.compare(i, "=="),
+            D3D10_SB_OPCODE_NE | D3D10_SB_OPCODE_INE => self.compare(i, "!="),
+            D3D10_SB_OPCODE_LT | D3D10_SB_OPCODE_ILT | D3D10_SB_OPCODE_ULT => self.compare(i, "<"),
+            D3D10_SB_OPCODE_GE | D3D10_SB_OPCODE_IGE | D3D10_SB_OPCODE_UGE => self.compare(i, ">="),
+            // both clamp the field to the 32 bits like D3D
+            D3D11_SB_OPCODE_UBFE | D3D11_SB_OPCODE_IBFE => {
+                self.scalar(i, &[1, 2, 3], &|args| format!("extractBits({}, {} & 31u, {} & 31u)", args[2], args[1], args[0]))
+            }
+            D3D11_SB_OPCODE_BFI => self.scalar(i, &[1, 2, 3, 4], &|args| {
+                format!("insertBits({}, {}, {} & 31u, {} & 31u)", args[3], args[2], args[1], args[0])
+            }),
+            D3D10_SB_OPCODE_SINCOS => self.multiple(i, &[
+                &|args| format!("sin({})", args[0]),
+                &|args| format!("cos({})", args[0]),
+            ], 1, false),
+            D3D10_SB_OPCODE_UDIV => self.multiple(i, &[
+                &|args| format!("{} / {}", args[0], args[1]),
+                &|args| format!("{} % {}", args[0], args[1]),
+            ], 2, false),
+            D3D10_SB_OPCODE_IMUL => {
+                self.helpers.insert("dxbc_umul_hi");
+                self.helpers.insert("dxbc_imul_hi");
+                self.multiple(i, &[
+                    &|args| format!("dxbc_imul_hi({}, {})", args[0], args[1]),
+                    &|args| format!("{} * {}", args[0], args[1]),
+                ], 2, true)
+            }
+            D3D10_SB_OPCODE_UMUL => {
+                self.helpers.insert("dxbc_umul_hi");
+                self.multiple(i, &[
+                    &|args| format!("dxbc_umul_hi({}, {})", args[0], args[1]),
+                    &|args| format!("{} * {}", args[0], args[1]),
+                ], 2, true)
+            }
+            D3D11_SB_OPCODE_UADDC => self.multiple(i, &[
+                &|args| format!("{} + {}", args[0], args[1]),
+                &|args| format!("select({}(0u), {}(1u), {} + {} < {})", args[2], args[2], args[0], args[1], args[0]),
+            ], 2, false),
+            D3D11_SB_OPCODE_USUBB => self.multiple(i, &[
+                &|args| format!("{} - {}", args[0], args[1]),
+                &|args| format!("select({}(0u), {}(1u), {} < {})", args[2], args[2], args[0], args[1]),
+            ], 2, false),
+            D3D11_SB_OPCODE_SWAPC => self.multiple(i, &[
+                &|args| format!("select({}, {}, {} != {}(0u))", args[1], args[2], args[0], args[3]),
+                &|args| format!("select({}, {}, {} != {}(0u))", args[2], args[1], args[0], args[3]),
+            ], 3, false),
+            D3D10_SB_OPCODE_SAMPLE | D3D10_SB_OPCODE_SAMPLE_B | D3D10_SB_OPCODE_SAMPLE_L | D3D10_SB_OPCODE_SAMPLE_D |
+            D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ | D3D10_1_SB_OPCODE_GATHER4 |
+            D3D11_SB_OPCODE_GATHER4_C | D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C => self.sample(i),
+            D3D10_SB_OPCODE_LD | D3D10_SB_OPCODE_LD_MS => self.load(i),
+            D3D11_SB_OPCODE_LD_UAV_TYPED => {
+                let register = self.register(i, 2)?;
+                let value = format!("textureLoad({}, {})", self.resource_name(register), self.typed_address(register, self.operand(i, 1)?));
+                self.assign_resource(i, 2, value)
+            }
+            D3D11_SB_OPCODE_STORE_UAV_TYPED => self.store_typed(i),
+            D3D11_SB_OPCODE_LD_RAW | D3D11_SB_OPCODE_LD_STRUCTURED => self.load_memory(i),
+            D3D11_SB_OPCODE_STORE_RAW | D3D11_SB_OPCODE_STORE_STRUCTURED => self.store_memory(i),
+            D3D11_SB_OPCODE_ATOMIC_AND..=D3D11_SB_OPCODE_IMM_ATOMIC_UMIN => self.atomic(i),
+            D3D11_SB_OPCODE_SYNC => self.sync(i),
+            D3D10_SB_OPCODE_RESINFO => self.resinfo(i),
+            D3D11_SB_OPCODE_BUFINFO => self.bufinfo(i),
+            D3D10_1_SB_OPCODE_SAMPLE_INFO => self.sample_info(i),
+            D3D10_1_SB_OPCODE_SAMPLE_POS => self.sample_position(i),
+            D3D11_SB_OPCODE_ABORT => self.abort(i),
+            _ => Err(self.unsupported(i, "")),
+        }
+    }
+
+    fn write_resources(&mut self, out: &mut String) {
+        let shader = self.shader;
+        let program = self.program;
+
+        let comparisons = [D3D10_SB_OPCODE_SAMPLE_C, D3D10_SB_OPCODE_SAMPLE_C_LZ, D3D11_SB_OPCODE_GATHER4_C, D3D11_SB_OPCODE_GATHER4_PO_C];
+        let compared = backend::used_resources(program, &comparisons, OperandType::Resource);
+        let comparison_samplers = backend::used_resources(program, &comparisons, OperandType::Sampler);
+        let read = backend::used_resources(program, &[D3D11_SB_OPCODE_LD_UAV_TYPED], OperandType::UnorderedAccessView);
+        let atomics: Vec<u32> = (D3D11_SB_OPCODE_ATOMIC_AND..=D3D11_SB_OPCODE_IMM_ATOMIC_UMIN).collect();
+        let atomic_views = backend::used_resources(program, &atomics, OperandType::UnorderedAccessView);
+        let atomic_memory = backend::used_resources(program, &atomics, OperandType::ThreadGroupSharedMemory);
+
+        for declaration in &program.decls {
+            let register = match declaration.register() {
+                Some(register) => register,
+                None => continue,
+            };
+            let index = backend::resource_index(register);
+            let file = register.file;
+            let resource_name = match file {
+                OperandType::ThreadGroupSharedMemory => format!("g{}", index),
+                _ => shader.resource_name(file, index),
+            };
+
+            let mut resource = Resource::new(resource_name.clone(), ResourceDimension::Unknown, ResourceKind::Typed);
+            let word = |atomic: bool| if atomic { "atomic<u32>" } else { "u32" };
+
+            let text = match *declaration {
+                Declaration::ConstantBuffer { .. } => {
+                    let group = format!("@group({}) @binding({})", CONSTANT_BUFFER_GROUP, index);
+                    match shader.constant_buffers.iter().find(|&&(slot, _, _)| slot == index) {
+                        Some((_, buffer, _)) => writeln!(out, "{} var<uniform> {}: {}_t;", group, buffer, buffer).unwrap(),
+                        None => {
+                            let size = register.index.get(1).map_or(0, Index::offset);
+                            writeln!(out, "{} var<uniform> cb{}: array<vec4<f32>, {}>;", group, index, size).unwrap()
+                        }
+                    }
+                    continue;
+                }
+                Declaration::Sampler { .. } => {
+                    let ty = if comparison_samplers.contains(&index) { "sampler_comparison" } else { "sampler" };
+                    writeln!(out, "@group({}) @binding({}) var {}: {};", SAMPLER_GROUP, index, resource_name, ty).unwrap();
+                    continue;
+                }
+                Declaration::Resource { dimension, return_type: ref returns, .. } => {
+                    resource.dimension = ResourceDimension::from_word(dimension).unwrap_or(ResourceDimension::Unknown);
+                    resource.ty = backend::return_type(returns[0]);
+                    resource.depth = compared.contains(&index) && texture_name(resource.dimension, true).is_some_and(|name| name.contains("depth"));
+                    match texture_name(resource.dimension, resource.depth) {
+                        Some(texture) if resource.depth => format!("{}: {}", resource_name, texture),
+                        Some(texture) => format!("{}: {}<{}>", resource_name, texture, scalar_name(resource.ty)),
+                        None => {
+                            writeln!(out, "// unsupported {:?} {}", resource.dimension, resource_name).unwrap();
+                            continue;
+                        }
+                    }
+                }
+                Declaration::UnorderedAccessViewTyped { dimension, return_type: ref returns, .. } => {
+                    resource.dimension = ResourceDimension::from_word(dimension).unwrap_or(ResourceDimension::Unknown);
+                    resource.ty = backend::return_type(returns[0]);
+                    let access = if read.contains(&index) { "read_write" } else { "write" };
+                    match storage_texture_name(resource.dimension) {
+                        Some(texture) => format!("{}: {}<{}, {}>", resource_name, texture, storage_format(resource.ty), access),
+                        None => {
+                            writeln!(out, "// unsupported {:?} {}", resource.dimension, resource_name).unwrap();
+                            continue;
+                        }
+                    }
+                }
+                Declaration::ResourceRaw { .. } | Declaration::ResourceStructured { .. } => {
+                    if let Declaration::ResourceStructured { stride, .. } = *declaration {
+                        resource.kind = ResourceKind::Structured;
+                        resource.stride = stride / 4;
+                    } else {
+                        resource.kind = ResourceKind::Raw;
+                    }
+                    format!("<storage, read> {}: array<u32>", resource_name)
+                }
+                Declaration::UnorderedAccessViewRaw { .. } | Declaration::UnorderedAccessViewStructured { .. } => {
+                    if let Declaration::UnorderedAccessViewStructured { stride, .. } = *declaration {
+                        resource.kind = ResourceKind::Structured;
+                        resource.stride = stride / 4;
+                    } else {
+                        resource.kind = ResourceKind::Raw;
+                    }
+                    resource.atomic = atomic_views.contains(&index);
+                    format!("<storage, read_write> {}: array<{}>", resource_name, word(resource.atomic))
+                }
+                Declaration::ThreadGroupSharedMemoryRaw { byte_count, .. } => {
+                    resource.kind = ResourceKind::Raw;
+                    resource.atomic = atomic_memory.contains(&index);
+                    writeln!(out, "var<workgroup> {}: array<{}, {}>;", resource_name, word(resource.atomic), byte_count / 4).unwrap();
+                    self.resources.insert((file, index), resource);
+                    continue;
+                }
+                Declaration::ThreadGroupSharedMemoryStructured { stride, count, .. } => {
+                    resource.kind = ResourceKind::Structured;
+                    resource.stride = stride / 4;
+                    resource.atomic = atomic_memory.contains(&index);
+                    let size = count as u64 * stride as u64 / 4;
+                    writeln!(out, "var<workgroup> {}: array<{}, {}>;", resource_name, word(resource.atomic), size).unwrap();
+                    self.resources.insert((file, index), resource);
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let group = match file {
+                OperandType::UnorderedAccessView => UNORDERED_ACCESS_VIEW_GROUP,
+                _ => RESOURCE_GROUP,
+            };
+            let text = if text.starts_with('<') { text } else { format!(" {}", text) };
+            writeln!(out, "@group({}) @binding({}) var{};", group, index, text).unwrap();
+            self.resources.insert((file, index), resource);
+        }
+    }
+}
+
+fn write(shader: &Shader, program: &Program) -> Result<String, Error> {
+    let mut writer = Writer::new(shader, program, Wgsl { switches: Vec::new() })?;
+    let mut out = String::new();
+
+    let program_type = shader.program_type;
+    let (stage, partial) = match program_type {
+        D3D10_SB_VERTEX_SHADER => ("@vertex", None),
+        D3D10_SB_PIXEL_SHADER => ("@fragment", None),
+        D3D11_SB_COMPUTE_SHADER => ("@compute", None),
+        D3D10_SB_GEOMETRY_SHADER => ("@vertex", Some("geometry")),
+        D3D11_SB_HULL_SHADER => ("@vertex", Some("hull")),
+        _ => ("@vertex", Some("domain")),
+    };
+    if let Some(partial) = partial {
+        writeln!(out, "// {} shaders are only translated partially\n", partial).unwrap();
+    }
+    // D3D has no uniformity rules for derivatives and discards
+    if program_type == D3D10_SB_PIXEL_SHADER {
+        writeln!(out, "diagnostic(off, derivative_uniformity);\n").unwrap();
+    }
+
+    let system_values = writer.system_values();
+    let interpolation = backend::interpolation(program);
+    let input_locations = locations(&shader.inputs, program_type, true);
+    let output_locations = locations(&shader.outputs, program_type, false);
+
+    let has_input = !shader.inputs.is_empty() || !system_values.is_empty();
+    if has_input {
+        writeln!(out, "struct Input\n{{").unwrap();
+        for (n, input) in shader.inputs.iter().enumerate() {
+            let mode = interpolation.get(&input.register).cloned().unwrap_or(D3D10_SB_INTERPOLATION_UNDEFINED);
+            let (width, flat) = match input_locations.get(&input.register) {
+                Some(location) if input.builtin(program_type, true).is_none() => {
+                    if location.owner != n {
+                        continue;
+                    }
+                    (location.width, location.flat)
+                }
+                _ => (input.width(), false),
+            };
+            let attributes = input.attributes(program_type, true, mode, flat);
+            writeln!(out, "    {} {}: {},", attributes, input.member(), vector(input.ty, width)).unwrap();
+        }
+        for &(name, width, builtin) in &system_values {
+            writeln!(out, "    @builtin({}) {}: {},", builtin, name, vector(Type::Uint, width)).unwrap();
+        }
+        writeln!(out, "}};\n").unwrap();
+    }
+
+    if !shader.outputs.is_empty() {
+        writeln!(out, "struct Output\n{{").unwrap();
+        for (n, output) in shader.outputs.iter().enumerate() {
+            let (width, flat) = match output_locations.get(&output.register) {
+                Some(location) if output.builtin(program_type, false).is_none() => {
+                    if location.owner != n {
+                        continue;
+                    }
+                    (location.width, location.flat)
+                }
+                _ => (output.width(), false),
+            };
+            let attributes = output.attributes(program_type, false, D3D10_SB_INTERPOLATION_UNDEFINED, flat);
+            writeln!(out, "    {} {}: {},", attributes, output.member(), vector(output.ty, width)).unwrap();
+        }
+        writeln!(out, "}};\n").unwrap();
+    }
+
+    for (_, _, text) in &shader.constant_buffers {
+        writeln!(out, "{}", text).unwrap();
+    }
+
+    let mut resources = String::new();
+    writer.write_resources(&mut resources);
+    if !resources.is_empty() {
+        writeln!(out, "{}", resources).unwrap();
+    }
+
+    if let Some(vectors) = writer.immediate_constants() {
+        let ty = format!("array<vec4<u32>, {}>", vectors.len());
+        writeln!(out, "var<private> icb: {} = {}(\n{},\n);\n", ty, ty, vectors.join(",\n")).unwrap();
+    }
+
+    // registers, private globals if the subroutines share them
+    let mut registers: Vec<String> = Vec::new();
+    for register in backend::declared_registers(program, OperandType::Input, &shader.inputs) {
+        registers.push(format!("v{}: vec4<f32>", register));
+    }
+    if writer.global {
+        for &(name, width, _) in &system_values {
+            registers.push(format!("{}: {}", name, vector(Type::Uint, width)));
+        }
+    }
+    for register in backend::declared_registers(program, OperandType::Output, &shader.outputs) {
+        registers.push(format!("o{}: vec4<f32>", register));
+    }
+    for output in shader.outputs.iter().filter(|output| output.register == !0) {
+        match output.system_register() {
+            Some("oMask") => registers.push("oMask: u32".to_owned()),
+            Some(name) => registers.push(format!("{}: f32", name)),
+            None => {}
+        }
+    }
+    for declaration in &program.decls {
+        match *declaration {
+            Declaration::Temps(count) => {
+                for register in 0..count {
+                    registers.push(format!("r{}: vec4<f32>", register));
+                }
+            }
+            Declaration::IndexableTemp { index, count, .. } => registers.push(format!("x{}: array<vec4<f32>, {}>", index, count)),
+            _ => {}
+        }
+    }
+
+    if writer.global {
+        for register in &registers {
+            writeln!(out, "var<private> {};", register).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+
+    let (main, subroutines) = backend::functions(program);
+    let mut functions = String::new();
+    for &(label, ref instructions) in &subroutines {
+        let body = writer.function(instructions.clone(), true)?;
+        writeln!(functions, "fn label{}()\n{{\n{}}}\n", label, body).unwrap();
+    }
+
+    let mut body = writer.function(main.clone(), false)?;
+    // the entry point has to end in a return of its outputs
+    if program.instructions[main].last().is_none_or(|instruction| instruction.opcode != D3D10_SB_OPCODE_RET) {
+        writer.ret();
+        body.push_str(&writer.body);
+    }
+
+    writer.write_helpers(&HELPERS, &mut out);
+    out.push_str(&functions);
+
+    let mut attributes = vec![stage.to_owned()];
+    for declaration in &program.decls {
+        match *declaration {
+            Declaration::ThreadGroup(x, y, z) => attributes.push(format!("@workgroup_size({}, {}, {})", x, y, z)),
+            Declaration::GlobalFlags(flags) if flags.contains(GlobalFlags::FORCE_EARLY_DEPTH_STENCIL) => {
+                writeln!(out, "// WebGPU decides on early depth and stencil tests by itself").unwrap()
+            }
+            _ => {}
+        }
+    }
+
+    let parameters = if has_input { "input: Input" } else { "" };
+    let result = match program_type {
+        _ if !shader.outputs.is_empty() => " -> Output",
+        D3D10_SB_PIXEL_SHADER | D3D11_SB_COMPUTE_SHADER => "",
+        _ => " -> @builtin(position) vec4<f32>",
+    };
+    writeln!(out, "{}\nfn main({}){}\n{{", attributes.join(" "), parameters, result).unwrap();
+
+    if !writer.global {
+        for register in &registers {
+            writeln!(out, "    var {};", register).unwrap();
+        }
+        if !registers.is_empty() {
+            writeln!(out).unwrap();
+        }
+    }
+
+    let pixel = program_type == D3D10_SB_PIXEL_SHADER;
+    for input in &shader.inputs {
+        if input.register == !0 {
+            continue;
+        }
+        let components = backend::mask_components(input.mask);
+        let location = input_locations.get(&input.register).filter(|_| input.builtin(program_type, true).is_none());
+        let (source, ty) = match location {
+            Some(location) => {
+                let owner = &shader.inputs[location.owner];
+                let source = format!("input.{}", owner.member());
+                if location.width == 1 || (components.len() == location.width && components[0] == 0) {
+                    (source, owner.ty)
+                } else {
+                    (format!("{}.{}", source, backend::swizzle(&components)), owner.ty)
+                }
+            }
+            None => (format!("input.{}", input.member()), input.ty),
+        };
+        let value = if pixel && matches!(input.semantic_type, dr::SemanticName::Position) {
+            // D3D has the w of the vertex where WebGPU has its reciprocal
+            let position = format!("vec4<f32>({}.xyz, 1.0 / {}.w)", source, source);
+            match components.len() {
+                4 => position,
+                _ => format!("{}.{}", position, backend::swizzle(&components)),
+            }
+        } else {
+            convert(source, ty, Type::Float, components.len())
+        };
+        for text in partial_write(&format!("v{}", input.register), Type::Float, &components, &value) {
+            writeln!(out, "    {}", text).unwrap();
+        }
+    }
+    if writer.global {
+        for &(name, _, _) in &system_values {
+            writeln!(out, "    {} = input.{};", name, name).unwrap();
+        }
+    }
+    if !shader.inputs.is_empty() || (writer.global && !system_values.is_empty()) {
+        writeln!(out).unwrap();
+    }
+
+    out.push_str(&body);
+    writeln!(out, "}}").unwrap();
+
+    Ok(out)
+}
+
+/// Translates a parsed shader into a WGSL module with an entry point named
+/// `main`.
+///
+/// Resources are bound in the group of their register file, see the
+/// `*_GROUP` constants, at the binding of their register. Storage textures
+/// take the 32 bit RGBA format of their channel type. The sample count of
+/// the render target is the `dxbc_sample_count` override, 1 unless the
+/// pipeline sets it. Instructions WGSL has no counterpart for end the parse
+/// with a `backend::Error`.
+pub struct WgslConsumer {
+    chunks: Chunks,
+    source: String,
+}
+
+impl WgslConsumer {
+    pub fn new() -> Self {
+        WgslConsumer {
+            chunks: Chunks::default(),
+            source: String::new(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Default for WgslConsumer {
+    fn default() -> Self {
+        WgslConsumer::new()
+    }
+}
+
+impl Consumer for WgslConsumer {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        self.chunks.finalize(&mut self.source, write)
+    }
+
+    fn consume_rdef(&mut self, rdef: &dr::RdefChunk) -> Action {
+        self.chunks.consume_rdef::<Wgsl>(rdef)
+    }
+
+    fn consume_isgn(&mut self, isgn: &dr::IOsgnChunk) -> Action {
+        self.chunks.consume_isgn(isgn)
+    }
+
+    fn consume_osgn(&mut self, osgn: &dr::IOsgnChunk) -> Action {
+        self.chunks.consume_osgn(osgn)
+    }
+
+    fn consume_shex(&mut self, header: &ShexHeader) -> Action {
+        self.chunks.consume_shex(header)
+    }
+
+    fn consume_instruction(&mut self, offset: u32, instruction: SparseInstruction) -> Action {
+        self.chunks.consume_instruction(offset, instruction)
+    }
+}
+
+pub fn translate(bytes: &[u8]) -> Result<String, State> {
+    let mut consumer = WgslConsumer::new();
+
+    {
+        let mut parser = Parser::new(bytes, &mut consumer);
+        parser.parse()?;
+    }
+
+    Ok(consumer.source)
+}
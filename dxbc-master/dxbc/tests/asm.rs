@@ -0,0 +1,144 @@
+extern crate dxbc;
+
+use dxbc::{asm, disasm};
+
+use std::fs;
+
+const SAMPLES: &[&str] = &[
+    "../dxbcd/complex_shader.dxbc",
+    "../dxbcd/reference.dxbc",
+    "../dxbcd/shader.dxbc",
+    "../dxbcd/shader_abort.dxbc",
+    "../dxbcross/tests/compute.dxbc",
+    "../dxbcross/tests/layout.dxbc",
+];
+
+#[test]
+fn round_trip() {
+    for path in SAMPLES {
+        let mut bytes = fs::read(path).unwrap();
+        // some samples were edited after compiling, the assembler always
+        // writes a valid checksum
+        dxbc::fix_checksum(&mut bytes).unwrap();
+
+        let source = disasm::disassemble_lossless(&bytes).unwrap();
+        let module = asm::assemble(&source).unwrap_or_else(|err| panic!("{}: {:?}", path, err));
+        assert!(module.as_bytes() == &bytes[..], "{} doesn't assemble back to the same container", path);
+    }
+}
+
+#[test]
+fn exact_floats() {
+    let bytes = fs::read("../dxbcd/complex_shader.dxbc").unwrap();
+
+    // fxc prints six decimals and leaves out the chunks without a listing
+    let source = disasm::disassemble(&bytes).unwrap();
+    assert!(source.contains("0.333333)") && !source.contains("0.33333334"), "{}", source);
+    assert!(!source.contains("// Chunk STAT:"), "{}", source);
+
+    // 1/3 doesn't survive six decimals
+    let source = disasm::disassemble_lossless(&bytes).unwrap();
+    assert!(source.contains("0.33333334"), "{}", source);
+    assert!(source.contains("// Chunk STAT:"), "{}", source);
+}
+
+#[test]
+fn unbalanced_parentheses() {
+    for mnemonic in &["mov)(", "mov(", "mov)", "mov(x))", "sample_indexable(texture2d)(float"] {
+        let source = format!("ps_5_0\ndcl_temps 1\n{} r0.x, l(1.0)\nret\n", mnemonic);
+        match asm::assemble(&source) {
+            Ok(_) => panic!("{} assembled", mnemonic),
+            Err(err) => assert!(err.message.contains("unbalanced parentheses"), "{}: {}", mnemonic, err),
+        }
+    }
+}
+
+#[test]
+fn d3d12_system_values() {
+    let source = "ps_5_0
+dcl_input_ps_siv linear v0.xyz, barycentrics
+dcl_input_ps_sgv constant v1.x, shadingrate
+dcl_input_ps_sgv constant v2.x, cullprimitive
+ret
+";
+    let module = asm::assemble(source).unwrap();
+    let disassembly = disasm::disassemble(module.as_bytes()).unwrap();
+    assert!(disassembly.ends_with(source), "{}", disassembly);
+}
+
+const GEOMETRY: &str = "//
+// Input signature:
+//
+// Name                 Index   Mask Register SysValue  Format   Used
+// -------------------- ----- ------ -------- -------- ------- ------
+// SV_Position              0   xyzw        0      POS   float   xyzw
+//
+//
+// Output signature:
+//
+// Stream Name                 Index   Mask Register SysValue  Format   Used
+// ------ -------------------- ----- ------ -------- -------- ------- ------
+//      0 SV_Position              0   xyzw        0      POS   float   xyzw
+//      1 TEXCOORD                 0   xyzw        0     NONE   float   xyzw
+//
+gs_5_0
+dcl_input_siv v[1][0].xyzw, position
+dcl_inputprimitive point
+dcl_stream m0
+dcl_outputtopology pointlist
+dcl_output_siv o0.xyzw, position
+dcl_stream m1
+dcl_outputtopology pointlist
+dcl_output o0.xyzw
+dcl_maxout 1
+mov o0.xyzw, v[0][0].xyzw
+emit_stream m0
+mov o0.xyzw, v[0][0].xyzw
+emit_stream m1
+ret
+";
+
+const PATCH_CONSTANTS: &str = "//
+// Patch Constant signature:
+//
+// Name                 Index   Mask Register SysValue  Format   Used
+// -------------------- ----- ------ -------- -------- ------- ------
+// SV_TessFactor            0   x           0  TRIEDGE   float   x   
+// SV_TessFactor            1   x           1  TRIEDGE   float   x   
+// SV_TessFactor            2   x           2  TRIEDGE   float   x   
+// SV_InsideTessFactor      0   x           3   TRIINT   float       
+//
+";
+
+// Header and rows of the titled signature table.
+fn signature<'a>(source: &'a str, title: &str) -> Vec<&'a str> {
+    source
+        .lines()
+        .skip_while(|line| line.trim_start_matches('/').trim() != title)
+        .skip(2)
+        .take_while(|line| *line != "//")
+        .collect()
+}
+
+#[test]
+fn stream_and_patch_constant_signatures() {
+    let hull = format!("{}hs_5_0\nret\n", PATCH_CONSTANTS);
+    let domain = format!("{}ds_5_0\nret\n", PATCH_CONSTANTS);
+
+    let tables = [
+        (GEOMETRY, b"OSG5", "Output signature:"),
+        (&*hull, b"PCSG", "Patch Constant signature:"),
+        (&*domain, b"PCSG", "Patch Constant signature:"),
+    ];
+
+    for &(source, fourcc, title) in &tables {
+        let module = asm::assemble(source).unwrap();
+        assert!(module.as_bytes().windows(4).any(|bytes| bytes == fourcc), "{}", source);
+
+        // the used column of patch constants means written components for
+        // hull shaders and read ones for domain shaders
+        let disassembly = disasm::disassemble(module.as_bytes()).unwrap();
+        assert!(signature(source, title).len() > 2);
+        assert_eq!(signature(&disassembly, title), signature(source, title), "{}", disassembly);
+    }
+}
@@ -0,0 +1,170 @@
+extern crate dxbc;
+
+mod common;
+
+use dxbc::cfg::{Case, Cfg, Error, FunctionKind, Region};
+use dxbc::d3d11tokenizedprogramformat::*;
+use dxbc::ir::Program;
+
+const HEADER: &str = "ps_5_0
+dcl_input_ps constant v0.x
+dcl_output o0.xyzw
+dcl_temps 1
+";
+
+fn parse(body: &str) -> Program {
+    common::program(HEADER, body)
+}
+
+fn build(body: &str) -> Result<Cfg, Error> {
+    Cfg::build(&parse(body))
+}
+
+// Start, end and successors of each block.
+fn blocks(cfg: &Cfg, function: usize) -> Vec<(usize, usize, Vec<usize>)> {
+    cfg.functions[function].blocks.iter().map(|block| (block.start, block.end, block.successors.clone())).collect()
+}
+
+fn sequence(blocks: &[usize]) -> Box<Region> {
+    Box::new(Region::Sequence(blocks.iter().map(|&block| Region::Block(block)).collect()))
+}
+
+#[test]
+fn if_and_loop() {
+    let cfg = build(
+        "mov r0.x, l(0)
+if_nz v0.x
+  mov r0.x, l(1)
+else
+  mov r0.x, l(2)
+endif
+loop
+  breakc_nz r0.x
+  iadd r0.x, r0.x, l(-1)
+endloop
+mov o0.xyzw, r0.xxxx
+ret
+",
+    )
+    .unwrap();
+
+    assert_eq!(cfg.functions.len(), 1);
+    assert_eq!(
+        blocks(&cfg, 0),
+        vec![
+            (0, 2, vec![1, 2]),
+            (2, 4, vec![3]),
+            (4, 5, vec![3]),
+            (5, 6, vec![4]),
+            // the break leaves past the `endloop`
+            (6, 8, vec![6, 5]),
+            (8, 10, vec![4]),
+            (10, 12, vec![]),
+        ]
+    );
+    assert_eq!(cfg.functions[0].blocks[4].predecessors, vec![3, 5]);
+    assert_eq!(cfg.functions[0].block_of(9), Some(5));
+    assert_eq!(cfg.functions[0].block_of(12), None);
+
+    assert_eq!(
+        cfg.functions[0].region,
+        Region::Sequence(vec![
+            Region::If { header: 0, then: sequence(&[1]), otherwise: Some(sequence(&[2])) },
+            Region::Block(3),
+            Region::Loop { body: sequence(&[4, 5]) },
+            Region::Block(6),
+        ])
+    );
+}
+
+#[test]
+fn switch_and_subroutines() {
+    let cfg = build(
+        "switch v0.x
+  case l(0)
+  case l(1)
+    mov r0.x, l(1)
+  case l(2)
+    mov r0.x, l(2)
+    break
+  default
+    mov r0.x, l(3)
+    break
+endswitch
+call l0
+mov o0.xyzw, r0.xxxx
+ret
+label l0
+retc_z r0.x
+mov r0.x, l(4)
+ret
+",
+    )
+    .unwrap();
+
+    assert_eq!(cfg.functions.len(), 2);
+    assert_eq!(cfg.functions[0].kind, FunctionKind::Main);
+    assert_eq!(cfg.functions[0].calls, vec![(11, 0)]);
+    assert_eq!(cfg.functions[1].kind, FunctionKind::Subroutine(0));
+
+    // the first case falls through into the second, with a default there is
+    // no edge from the switch to its end
+    assert_eq!(
+        blocks(&cfg, 0),
+        vec![(0, 1, vec![1, 2, 3]), (1, 4, vec![2]), (4, 7, vec![4]), (7, 10, vec![4]), (10, 14, vec![])]
+    );
+    assert_eq!(
+        cfg.functions[0].region,
+        Region::Sequence(vec![
+            Region::Switch {
+                header: 0,
+                cases: vec![
+                    Case { labels: vec![Some(0), Some(1)], body: *sequence(&[1]) },
+                    Case { labels: vec![Some(2)], body: *sequence(&[2]) },
+                    Case { labels: vec![None], body: *sequence(&[3]) },
+                ],
+            },
+            Region::Block(4),
+        ])
+    );
+
+    assert_eq!(blocks(&cfg, 1), vec![(14, 16, vec![1]), (16, 18, vec![])]);
+}
+
+#[test]
+fn nesting_errors() {
+    assert_eq!(build("else\nret\n"), Err(Error::Unexpected(0, D3D10_SB_OPCODE_ELSE)));
+    assert_eq!(build("if_nz v0.x\nret\n"), Err(Error::Unterminated(0, D3D10_SB_OPCODE_IF)));
+    assert_eq!(build("break\nret\n"), Err(Error::BreakOutsideLoop(0)));
+    assert_eq!(build("continue\nret\n"), Err(Error::ContinueOutsideLoop(0)));
+    assert_eq!(build("switch v0.x\nmov r0.x, l(0)\nendswitch\nret\n"), Err(Error::OutsideCase(1)));
+    assert_eq!(build("call l3\nret\n"), Err(Error::UndefinedLabel(0, 3)));
+
+    // loops can't be left through a switch's end
+    assert_eq!(
+        build("loop\nswitch v0.x\ndefault\nendloop\nendswitch\nret\n"),
+        Err(Error::Unexpected(3, D3D10_SB_OPCODE_ENDLOOP))
+    );
+}
+
+#[test]
+fn dot() {
+    let program = parse(
+        "if_nz v0.x
+  call l0
+endif
+ret
+label l0
+mov o0.xyzw, l(1.000000, 0.000000, 0.000000, 0.000000)
+ret
+",
+    );
+    let dot = Cfg::build(&program).unwrap().to_dot(&program);
+
+    assert!(dot.starts_with("digraph cfg {\n"), "{}", dot);
+    assert!(dot.contains("label=\"main\";"), "{}", dot);
+    assert!(dot.contains("label=\"l0\";"), "{}", dot);
+    assert!(dot.contains("f0_b0 [label=\"if_nz v0.x\\l\"];"), "{}", dot);
+    assert!(dot.contains("f0_b0 -> f0_b1;\n        f0_b0 -> f0_b2;"), "{}", dot);
+    assert!(dot.contains("f0_b1 -> f1_b0 [style=dashed];"), "{}", dot);
+}
@@ -0,0 +1,40 @@
+extern crate dxbc;
+
+use dxbc::ChecksumMismatch;
+
+use std::fs;
+
+#[test]
+fn shipped_samples() {
+    for path in &["../dxbcd/reference.dxbc", "../dxbcd/shader.dxbc"] {
+        let bytes = fs::read(path).unwrap();
+        assert_eq!(dxbc::verify(&bytes), Ok(()), "{}", path);
+    }
+}
+
+#[test]
+fn fix_corrupted_checksum() {
+    let mut bytes = fs::read("../dxbcd/reference.dxbc").unwrap();
+    bytes[4] ^= 0xff;
+
+    match dxbc::verify(&bytes) {
+        Err(ChecksumMismatch::Contents { .. }) => {}
+        other => panic!("corrupted checksum verified as {:?}", other),
+    }
+    assert_eq!(dxbc::fix_checksum(&mut bytes), Ok(true));
+    assert_eq!(dxbc::fix_checksum(&mut bytes), Ok(false));
+    assert_eq!(dxbc::verify(&bytes), Ok(()));
+}
+
+#[test]
+fn truncated_containers() {
+    for len in 0..20 {
+        let mut bytes = vec![0; len];
+        assert_eq!(dxbc::try_checksum(&bytes), Err(ChecksumMismatch::Truncated(len)));
+        assert_eq!(dxbc::verify(&bytes), Err(ChecksumMismatch::Truncated(len)));
+        assert_eq!(dxbc::fix_checksum(&mut bytes), Err(ChecksumMismatch::Truncated(len)));
+    }
+
+    // an empty container still has a checksum
+    assert_eq!(dxbc::try_checksum(&[0; 20]), Ok(dxbc::checksum(&[0; 20])));
+}
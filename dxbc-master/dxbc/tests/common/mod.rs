@@ -0,0 +1,10 @@
+// Listings assembled on the fly, shared by the analysis tests.
+
+use dxbc::asm;
+use dxbc::ir::Program;
+
+// Parses the program made of `header` followed by `body`.
+pub fn program(header: &str, body: &str) -> Program {
+    let source = format!("{}{}", header, body);
+    Program::parse(asm::assemble(&source).unwrap().as_bytes()).unwrap().unwrap()
+}
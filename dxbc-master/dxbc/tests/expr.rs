@@ -0,0 +1,249 @@
+extern crate dxbc;
+
+mod common;
+
+use dxbc::d3d11tokenizedprogramformat::*;
+use dxbc::expr::{Expr, Expressions, Function, Op, Statement};
+
+const HEADER: &str = "vs_5_0
+dcl_constantbuffer CB0[4], immediateIndexed
+dcl_input v0.xyzw
+dcl_input v1.xyzw
+dcl_output o0.xyzw
+dcl_temps 2
+";
+
+fn build(body: &str) -> Function {
+    let program = common::program(HEADER, &format!("{}ret\n", body));
+    Expressions::build(&program).unwrap().functions.remove(0)
+}
+
+// Expression written by instruction `i`.
+fn written(function: &Function, i: usize) -> &Expr {
+    let statement = function.blocks.iter().flatten().find(|statement| match **statement {
+        Statement::Assign { instruction, .. } | Statement::Store { instruction, .. } => instruction == i,
+        Statement::Effect { .. } => false,
+    });
+
+    match statement {
+        Some(&Statement::Assign { ref expr, .. }) | Some(&Statement::Store { ref expr, .. }) => expr,
+        _ => panic!("instruction {} writes nothing: {:?}", i, function),
+    }
+}
+
+fn readers(function: &Function) -> Vec<(usize, usize)> {
+    function.folded.iter().map(|(&i, &(reader, _))| (i, reader)).collect()
+}
+
+fn op(expr: &Expr) -> Option<Op> {
+    match *expr {
+        Expr::Op(op, _) => Some(op),
+        Expr::Swizzle(ref inner, _) => op(inner),
+        _ => None,
+    }
+}
+
+fn opcode(expr: &Expr) -> Option<u32> {
+    match op(expr) {
+        Some(Op::Instruction { opcode, .. }) => Some(opcode),
+        _ => None,
+    }
+}
+
+#[test]
+fn folding() {
+    let function = build(
+        "mul r0.xy, v0.xyxx, v1.xyxx
+add r0.xy, r0.xyxx, l(1.000000, 2.000000, 0.000000, 0.000000)
+mov o0.xy, r0.xyxx
+",
+    );
+
+    assert_eq!(readers(&function), vec![(0, 1), (1, 2)]);
+    assert_eq!(function.blocks[0].len(), 2);
+
+    // the mov disappears into the store, the multiply into the add
+    let expr = written(&function, 2);
+    assert_eq!(opcode(expr), Some(D3D10_SB_OPCODE_ADD));
+    match *expr {
+        Expr::Op(_, ref args) => assert_eq!(opcode(&args[0]), Some(D3D10_SB_OPCODE_MUL)),
+        _ => panic!("{:?}", expr),
+    }
+}
+
+#[test]
+fn single_use() {
+    // read twice by the same instruction
+    let function = build(
+        "mul r0.x, v0.x, v1.x
+add o0.x, r0.x, r0.x
+",
+    );
+    assert!(readers(&function).is_empty());
+
+    // read by two instructions
+    let function = build(
+        "mul r0.x, v0.x, v1.x
+add o0.x, r0.x, l(1.000000)
+add o0.y, r0.x, l(2.000000)
+",
+    );
+    assert!(readers(&function).is_empty());
+
+    // read in another block
+    let function = build(
+        "mul r0.x, v0.x, v1.x
+if_nz v0.y
+  add o0.x, r0.x, l(1.000000)
+endif
+",
+    );
+    assert!(readers(&function).is_empty());
+
+    // with side effects
+    let function = build(
+        "mov r0.x, o0.x
+add o0.x, r0.x, l(1.000000)
+",
+    );
+    assert!(readers(&function).is_empty());
+}
+
+#[test]
+fn overwritten_sources() {
+    // r1.x changes between the add and its reader
+    let function = build(
+        "add r0.x, r1.x, v0.x
+mov r1.x, v1.x
+mul o0.x, r0.x, r1.x
+mov o0.y, r1.x
+",
+    );
+    assert!(readers(&function).is_empty());
+
+    // unless the instruction changing it is folded as well
+    let function = build(
+        "add r0.x, r1.x, v0.x
+mul r1.x, v1.x, v1.y
+mul o0.x, r0.x, r1.x
+",
+    );
+    assert_eq!(readers(&function), vec![(0, 2), (1, 2)]);
+
+    // or the reader is folded before the change
+    let function = build(
+        "add r0.x, r1.x, v0.x
+mul r0.y, r0.x, v0.y
+mov r1.x, v1.x
+add o0.x, r0.y, r1.x
+mov o0.y, r1.x
+",
+    );
+    assert_eq!(readers(&function), vec![(1, 3)]);
+}
+
+#[test]
+fn empty_mask() {
+    // `r0` without components is valid bytecode that writes nothing
+    let function = build(
+        "ftoi r0, v0.x
+mov o0.x, v1.x
+",
+    );
+    assert!(function.blocks[0].iter().all(|statement| match *statement {
+        Statement::Assign { instruction, .. } | Statement::Store { instruction, .. } => instruction != 0,
+        Statement::Effect { .. } => true,
+    }));
+    written(&function, 1);
+}
+
+#[test]
+fn normalize() {
+    let function = build(
+        "dp3 r0.x, v0.xyzx, v0.xyzx
+rsq r0.x, r0.x
+mul o0.xyz, r0.xxxx, v0.xyzx
+",
+    );
+    assert_eq!(op(written(&function, 2)), Some(Op::Normalize));
+}
+
+#[test]
+fn length() {
+    let function = build(
+        "dp2 r0.x, v0.xyxx, v0.xyxx
+sqrt o0.x, r0.x
+",
+    );
+    assert_eq!(op(written(&function, 1)), Some(Op::Length));
+
+    // not the length of one vector
+    let function = build(
+        "dp2 r0.x, v0.xyxx, v1.xyxx
+sqrt o0.x, r0.x
+",
+    );
+    assert_eq!(opcode(written(&function, 1)), Some(D3D10_SB_OPCODE_SQRT));
+}
+
+#[test]
+fn clamp() {
+    let function = build(
+        "max r0.x, v0.x, cb0[0].x
+min o0.x, r0.x, cb0[0].y
+",
+    );
+    assert_eq!(op(written(&function, 1)), Some(Op::Clamp));
+
+    let function = build(
+        "min r0.x, l(2.000000), v0.x
+max o0.x, r0.x, l(-1.000000)
+",
+    );
+    match *written(&function, 1) {
+        Expr::Op(Op::Clamp, ref args) => {
+            assert_eq!(args[1], Expr::Imm32(vec![(-1.0f32).to_bits()]));
+            assert_eq!(args[2], Expr::Imm32(vec![2.0f32.to_bits()]));
+        }
+        ref expr => panic!("{:?}", expr),
+    }
+
+    // `max(min(x, hi), lo)` only clamps for `lo <= hi`
+    let function = build(
+        "min r0.x, v0.x, l(-1.000000)
+max o0.x, r0.x, l(2.000000)
+",
+    );
+    assert_eq!(opcode(written(&function, 1)), Some(D3D10_SB_OPCODE_MAX));
+
+    let function = build(
+        "min r0.x, v0.x, cb0[0].y
+max o0.x, r0.x, cb0[0].x
+",
+    );
+    assert_eq!(opcode(written(&function, 1)), Some(D3D10_SB_OPCODE_MAX));
+}
+
+#[test]
+fn matrix_multiply() {
+    let function = build(
+        "mul r0.xyzw, v0.yyyy, cb0[1].xyzw
+mad r0.xyzw, cb0[0].xyzw, v0.xxxx, r0.xyzw
+mad r0.xyzw, cb0[2].xyzw, v0.zzzz, r0.xyzw
+add o0.xyzw, r0.xyzw, cb0[3].xyzw
+",
+    );
+    match *written(&function, 3) {
+        Expr::Op(Op::MatrixMultiply, ref args) => assert_eq!(args.len(), 5),
+        ref expr => panic!("{:?}", expr),
+    }
+
+    // rows that don't follow each other
+    let function = build(
+        "mul r0.xyzw, v0.yyyy, cb0[1].xyzw
+mad r0.xyzw, cb0[0].xyzw, v0.xxxx, r0.xyzw
+mad o0.xyzw, cb0[3].xyzw, v0.zzzz, r0.xyzw
+",
+    );
+    assert_eq!(opcode(written(&function, 2)), Some(D3D10_SB_OPCODE_MAD));
+}
@@ -0,0 +1,228 @@
+extern crate dxbc;
+
+use dxbc::asm;
+use dxbc::glsl::{self, GlslOptions, GlslVersion};
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const VERSIONS: &[(GlslVersion, &str)] = &[(GlslVersion::V330, "330"), (GlslVersion::V430, "430"), (GlslVersion::Es300, "es")];
+
+fn translate(bytes: &[u8], version: GlslVersion) -> Result<String, String> {
+    glsl::translate(bytes, GlslOptions { version }).map_err(|err| err.to_string())
+}
+
+fn assemble(source: &str) -> Vec<u8> {
+    asm::assemble(source).unwrap().as_bytes().to_vec()
+}
+
+// An integer loop counter next to float accumulators, shared with the HLSL
+// tests.
+const TYPED_TEMPS: &str = include_str!("typed_temps.asm");
+
+// Compares the translation of a sample for every version with
+// `tests/glsl/<name>.<version>.glsl`, set `DXBC_BLESS` to write the
+// translations there instead.
+fn check(sample: &str, name: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let bytes = fs::read(root.join(sample)).unwrap();
+
+    for &(version, suffix) in VERSIONS {
+        let source = translate(&bytes, version).unwrap_or_else(|err| panic!("{} for {}: {}", sample, suffix, err));
+        let golden = root.join("tests/glsl").join(format!("{}.{}.glsl", name, suffix));
+        if env::var_os("DXBC_BLESS").is_some() {
+            fs::write(&golden, &source).unwrap();
+            continue;
+        }
+
+        let expected = fs::read_to_string(&golden).unwrap();
+        assert!(source == expected, "{} differs from {}:\n{}", sample, golden.display(), source);
+    }
+}
+
+// Checks that the translation for `version` fails with `message`.
+fn unsupported(bytes: &[u8], version: GlslVersion, message: &str) {
+    match translate(bytes, version) {
+        Ok(source) => panic!("translated for {:?}:\n{}", version, source),
+        Err(err) => assert!(err.contains(message), "{:?}: {}", version, err),
+    }
+}
+
+#[test]
+fn vertex_shader() {
+    check("../dxbcd/reference.dxbc", "reference");
+}
+
+#[test]
+fn empty_shader() {
+    check("../dxbcd/assembled.dxbc", "assembled");
+}
+
+#[test]
+fn pixel_shader() {
+    check("../dxbcd/shader.dxbc", "shader");
+}
+
+#[test]
+fn sampling() {
+    let bytes = fs::read("../dxbcd/complex_shader.dxbc").unwrap();
+    unsupported(&bytes, GlslVersion::V430, "cannot translate `abort` at instruction");
+    unsupported(&bytes, GlslVersion::V330, "cannot translate gl_SampleMaskIn[0] in GLSL 330");
+}
+
+#[test]
+fn sample_positions() {
+    let bytes = fs::read("../dxbcross/tests/layout.dxbc").unwrap();
+    let source = translate(&bytes, GlslVersion::V430).unwrap();
+    assert!(source.contains("dxbc_sample_position(uint(gl_NumSamples), 1u)"), "{}", source);
+    assert!(source.contains("layout(std140, binding = 0) uniform Globals_block"), "{}", source);
+
+    unsupported(&bytes, GlslVersion::V330, "cannot translate `samplepos` in GLSL 330");
+    unsupported(&bytes, GlslVersion::Es300, "cannot translate `samplepos` in GLSL ES 300");
+}
+
+#[test]
+fn typed_temps() {
+    let bytes = assemble(TYPED_TEMPS);
+    for &(version, _) in VERSIONS {
+        let source = translate(&bytes, version).unwrap();
+        assert!(!source.contains("BitsTo"), "{}", source);
+        assert!(source.contains("ivec4 r0_i = ivec4(0);\n    uvec4 r0_u = uvec4(0u);"), "{}", source);
+        assert!(source.contains("r0_u.y = (r0_i.x >= 10) ? 0xffffffffu : 0u;"), "{}", source);
+        assert!(source.contains("r0_i.x = r0_i.x + 1;"), "{}", source);
+        assert!(source.contains("o0.w = float(uint((r0_i.x << 2u) & 12));"), "{}", source);
+        assert!(source.contains("r1.x = r0.z;\n    r1_i.y = r0_i.x;"), "{}", source);
+    }
+}
+
+#[test]
+fn folding() {
+    let bytes = assemble(include_str!("idioms.asm"));
+    for &(version, _) in VERSIONS {
+        let source = translate(&bytes, version).unwrap();
+        assert!(source.contains("o0.xyzw = mat4(cb0[0].xyzw, cb0[1].xyzw, cb0[2].xyzw, cb0[3].xyzw) * vec4(v0.xyz, 1.0);"), "{}", source);
+        assert!(source.contains("o1.xyz = normalize(v1.xyz);"), "{}", source);
+        assert!(source.contains("o2.x = clamp(v0.x, 0.0, 1.0);"), "{}", source);
+        assert!(source.contains("o2.y = clamp(v0.y, -1.0, 2.0) + length(v0.xy);"), "{}", source);
+        assert!(!source.contains("r0.x ="), "{}", source);
+    }
+}
+
+#[test]
+fn cube_map_arrays() {
+    let bytes = assemble(
+        "ps_5_0
+dcl_sampler s0, mode_default
+dcl_resource_texturecubearray (float,float,float,float) t0
+dcl_input_ps linear v0.xyzw
+dcl_output o0.xyzw
+sample o0.xyzw, v0.xyzw, t0.xyzw, s0
+ret
+",
+    );
+
+    let source = translate(&bytes, GlslVersion::V430).unwrap();
+    assert!(source.contains("layout(binding = 0) uniform samplerCubeArray t0_s0;"), "{}", source);
+    assert!(source.contains("o0.xyzw = texture(t0_s0, v0.xyzw).xyzw;"), "{}", source);
+
+    unsupported(&bytes, GlslVersion::V330, "cannot translate `sample` in GLSL 330 at instruction 0");
+    unsupported(&bytes, GlslVersion::Es300, "cannot translate `sample` in GLSL ES 300 at instruction 0");
+}
+
+#[test]
+fn desktop_textures() {
+    let bytes = assemble(
+        "ps_5_0
+dcl_resource_texture1d (float,float,float,float) t0
+dcl_resource_buffer (uint,uint,uint,uint) t1
+dcl_resource_texture2dms(4) (sint,sint,sint,sint) t2
+dcl_input_ps linear v0.xyzw
+dcl_output o0.xyzw
+dcl_temps 1
+ld_indexable(texture1d)(float,float,float,float) o0.xyzw, l(0, 0, 0, 0), t0.xyzw
+ld_indexable(buffer)(uint,uint,uint,uint) r0.xyzw, l(1, 0, 0, 0), t1.xyzw
+ld_ms_indexable(texture2dms)(sint,sint,sint,sint) r0.xyzw, l(0, 0, 0, 0), t2.xyzw, l(3)
+ret
+",
+    );
+
+    for &version in &[GlslVersion::V330, GlslVersion::V430] {
+        let source = translate(&bytes, version).unwrap();
+        assert!(source.contains("uniform sampler1D t0;"), "{}", source);
+        assert!(source.contains("uniform usamplerBuffer t1;"), "{}", source);
+        assert!(source.contains("uniform isampler2DMS t2;"), "{}", source);
+        assert!(source.contains("texelFetch(t2, ivec2(0, 0), 3)"), "{}", source);
+    }
+
+    unsupported(&bytes, GlslVersion::Es300, "cannot translate `ld` in GLSL ES 300 at instruction 0");
+}
+
+#[test]
+fn wide_multiplication() {
+    let bytes = assemble(
+        "ps_5_0
+dcl_input_ps constant v0.xy
+dcl_output o0.xyzw
+imul o0.x, o0.y, v0.x, v0.y
+umul null, o0.z, v0.x, v0.y
+ret
+",
+    );
+
+    let source = translate(&bytes, GlslVersion::V430).unwrap();
+    assert!(source.contains("imulExtended(a, b, hi, lo);"), "{}", source);
+    assert!(source.contains("dxbc_imul_hi("), "{}", source);
+    assert!(!source.contains("dxbc_umul_hi"), "{}", source);
+
+    unsupported(&bytes, GlslVersion::V330, "cannot translate `imul` in GLSL 330 at instruction 0");
+    unsupported(&bytes, GlslVersion::Es300, "cannot translate `imul` in GLSL ES 300 at instruction 0");
+
+    // the low half alone is a plain multiplication everywhere
+    let bytes = assemble(
+        "ps_5_0
+dcl_input_ps constant v0.xy
+dcl_output o0.xyzw
+umul null, o0.x, v0.x, v0.y
+ret
+",
+    );
+    for &(version, _) in VERSIONS {
+        let source = translate(&bytes, version).unwrap();
+        assert!(!source.contains("umulExtended"), "{}", source);
+    }
+}
+
+#[test]
+fn compute_shaders() {
+    let bytes = assemble(
+        "cs_5_0
+dcl_globalFlags refactoringAllowed | enableRawAndStructuredBuffers
+dcl_resource_structured t0, 16
+dcl_uav_typed_texture2d (float,float,float,float) u0
+dcl_uav_raw u1
+dcl_tgsm_raw g0, 64
+dcl_input vThreadID.xyz
+dcl_temps 1
+dcl_thread_group 8, 8, 1
+ld_structured r0.xyzw, vThreadID.x, l(0), t0.xyzw
+store_uav_typed u0.xyzw, vThreadID.xyyy, r0.xyzw
+imm_atomic_iadd r0.x, u1, l(0), l(1)
+store_raw g0.x, l(4), r0.x
+sync_g_t
+ret
+",
+    );
+
+    let source = translate(&bytes, GlslVersion::V430).unwrap();
+    assert!(source.contains("layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;"), "{}", source);
+    assert!(source.contains("layout(std430, binding = 64) readonly buffer t0_block"), "{}", source);
+    assert!(source.contains("layout(binding = 0, rgba32f) uniform writeonly image2D u0;"), "{}", source);
+    assert!(source.contains("imageStore(u0, "), "{}", source);
+    assert!(source.contains("atomicAdd(u1[0u], 1u)"), "{}", source);
+    assert!(source.contains("shared uint g0[16];"), "{}", source);
+    assert!(source.contains("memoryBarrierShared();\n    barrier();"), "{}", source);
+
+    unsupported(&bytes, GlslVersion::V330, "cannot translate compute shaders in GLSL 330");
+    unsupported(&bytes, GlslVersion::Es300, "cannot translate compute shaders in GLSL ES 300");
+}
@@ -0,0 +1,8 @@
+#version 300 es
+precision highp float;
+precision highp int;
+
+void main()
+{
+    return;
+}
@@ -0,0 +1,15 @@
+#version 330 core
+
+layout(location = 0) in vec4 in_COLOR0;
+
+void main()
+{
+    vec4 v0 = vec4(0.0);
+    vec4 o0 = vec4(0.0);
+
+    v0.xyzw = in_COLOR0;
+
+    o0.xyzw = v0.xyzw + -abs(v0.xxyy);
+    gl_Position = o0.xyzw;
+    return;
+}
@@ -0,0 +1,33 @@
+#version 300 es
+precision highp float;
+precision highp int;
+
+layout(std140) uniform CB_block
+{
+    vec4 A[10]; // float4 A[10]
+} CB;
+
+uniform highp sampler2D T_S;
+
+in vec4 vs_COLOR0;
+in vec2 vs_UV0;
+layout(location = 0) out vec2 SV_Target0;
+
+void main()
+{
+    vec4 v0 = vec4(0.0);
+    vec4 v1 = vec4(0.0);
+    vec4 o0 = vec4(0.0);
+    vec4 r0 = vec4(0.0);
+    ivec4 r0_i = ivec4(0);
+
+    v0.xyzw = vs_COLOR0;
+    v1.xy = vs_UV0;
+
+    r0.x = trunc(v1.x) * v1.y;
+    r0_i.y = int(r0.x);
+    r0_i.y = r0_i.y + r0_i.y;
+    o0.xy = vec2(trunc(r0.x)) * textureLod(T_S, CB.A[r0_i.y + 2].yx, 0.0).xy;
+    SV_Target0 = o0.xy;
+    return;
+}
@@ -0,0 +1,64 @@
+// Golden file checks shared by the MSL and WGSL tests.
+
+use dxbc::asm;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+pub struct Target {
+    /// Directory under `tests` holding the golden files.
+    pub dir: &'static str,
+    pub extension: &'static str,
+    pub translate: fn(&[u8]) -> Result<String, String>,
+    /// Checks every translation on top of comparing it, such as running it
+    /// through a validator.
+    pub validate: fn(&str) -> Result<(), String>,
+}
+
+impl Target {
+    fn read(&self, sample: &str) -> Vec<u8> {
+        fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join(sample)).unwrap()
+    }
+
+    // Compares the translation of a sample with `tests/<dir>/<name>.<extension>`,
+    // set `DXBC_BLESS` to write the translation there instead.
+    pub fn check(&self, sample: &str, name: &str) {
+        self.compare(&self.read(sample), sample, name);
+    }
+
+    // Same as `check` for a listing assembled on the fly.
+    pub fn check_listing(&self, listing: &str, name: &str) {
+        let bytes = asm::assemble(listing).unwrap().as_bytes().to_vec();
+        self.compare(&bytes, name, name);
+    }
+
+    fn compare(&self, bytes: &[u8], sample: &str, name: &str) {
+        let source = (self.translate)(bytes).unwrap_or_else(|err| panic!("{}: {}", sample, err));
+        if let Err(err) = (self.validate)(&source) {
+            panic!("{} is invalid: {}\n{}", sample, err, source);
+        }
+
+        let golden = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join(self.dir)
+            .join(format!("{}.{}", name, self.extension));
+        if env::var_os("DXBC_BLESS").is_some() {
+            fs::write(&golden, &source).unwrap();
+            return;
+        }
+
+        let expected = fs::read_to_string(&golden).unwrap();
+        assert!(source == expected, "{} differs from {}:\n{}", sample, golden.display(), source);
+    }
+
+    // Checks that the translation of a listing assembled on the fly fails
+    // with `message`.
+    pub fn unsupported_listing(&self, listing: &str, message: &str) {
+        let bytes = asm::assemble(listing).unwrap().as_bytes().to_vec();
+        match (self.translate)(&bytes) {
+            Ok(source) => panic!("{} translated:\n{}", listing, source),
+            Err(err) => assert!(err.contains(message), "{}: {}", listing, err),
+        }
+    }
+}
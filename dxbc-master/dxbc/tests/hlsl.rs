@@ -0,0 +1,113 @@
+extern crate dxbc;
+
+use dxbc::asm;
+use dxbc::hlsl;
+
+use std::fs;
+
+fn translate(bytes: &[u8]) -> String {
+    hlsl::translate(bytes).unwrap_or_else(|err| panic!("{:?}", err))
+}
+
+fn casts(source: &str) -> usize {
+    source.lines().filter(|line| line.contains("asfloat(") || line.contains("asint(") || line.contains("asuint(")).count()
+}
+
+#[test]
+fn pixel_shader() {
+    let source = translate(&fs::read("../dxbcd/shader.dxbc").unwrap());
+    assert_eq!(
+        source,
+        "struct Input
+{
+    float4 COLOR0 : COLOR0;
+    float2 UV0 : UV0;
+};
+
+struct Output
+{
+    float2 SV_TARGET0 : SV_TARGET;
+};
+
+cbuffer CB : register(b0)
+{
+    float4 A[10] : packoffset(c0);
+};
+
+SamplerState S : register(s0);
+Texture2D<float4> T : register(t0);
+
+Output main(Input input)
+{
+    float4 v0 = 0;
+    float4 v1 = 0;
+    float4 o0 = 0;
+    float4 r0 = 0;
+    int4 r0_i = 0;
+
+    v0.xyzw = input.COLOR0;
+    v1.xy = input.UV0;
+
+    r0.x = trunc(v1.x) * v1.y;
+    r0_i.y = (int)r0.x;
+    r0_i.y = r0_i.y + r0_i.y;
+    o0.xy = (float2)trunc(r0.x) * T.SampleLevel(S, A[r0_i.y + 2].yx, 0.0).xy;
+    Output output;
+    output.SV_TARGET0 = o0.xy;
+    return output;
+}
+"
+    );
+}
+
+#[test]
+fn declarations() {
+    let source = translate(&fs::read("../dxbcd/complex_shader.dxbc").unwrap());
+
+    for line in &[
+        "    centroid float4 TEXCOORD1 : TEXCOORD1;\n",
+        "    noperspective float4 SV_POSITION0 : SV_POSITION;\n",
+        "    nointerpolation bool SV_IsFrontFace0 : SV_IsFrontFace;\n",
+        "    uint vCoverage : SV_Coverage;\n",
+        "cbuffer cbuf0 : register(b0)\n",
+        "    int2 arr[127] : packoffset(c2);\n",
+        "SamplerState samp1 : register(s1);\n",
+        "TextureCube<float4> tex1 : register(t1);\n",
+        "Texture2DMS<float4, 2> tex3 : register(t3);\n",
+        "Texture2D<float4> tex4_1_ : register(t5);\n",
+        "Output main(Input input)\n",
+        "    v3.z = asfloat(input.SV_PrimitiveID0);\n",
+    ] {
+        assert!(source.contains(line), "{}\n{}", line, source);
+    }
+}
+
+#[test]
+fn typed_temps() {
+    let bytes = asm::assemble(include_str!("typed_temps.asm")).unwrap().as_bytes().to_vec();
+    let source = translate(&bytes);
+    assert_eq!(casts(&source), 0, "{}", source);
+    assert!(source.contains("int4 r0_i = 0;\n    uint4 r0_u = 0;"), "{}", source);
+    assert!(source.contains("r0_i.x = r0_i.x + 1;"), "{}", source);
+    assert!(source.contains("r1.x = r0.z;\n    r1_i.y = r0_i.x;"), "{}", source);
+}
+
+#[test]
+fn folding() {
+    let bytes = asm::assemble(include_str!("idioms.asm")).unwrap().as_bytes().to_vec();
+    let source = translate(&bytes);
+    assert!(source.contains("o0.xyzw = mul(float4(v0.xyz, 1.0), float4x4(cb0[0].xyzw, cb0[1].xyzw, cb0[2].xyzw, cb0[3].xyzw));"), "{}", source);
+    assert!(source.contains("o1.xyz = normalize(v1.xyz);"), "{}", source);
+    assert!(source.contains("o2.x = saturate(v0.x);"), "{}", source);
+    assert!(source.contains("o2.y = clamp(v0.y, -1.0, 2.0) + length(v0.xy);"), "{}", source);
+}
+
+#[test]
+fn complex_shader() {
+    let bytes = fs::read("../dxbcd/complex_shader.dxbc").unwrap();
+    let source = translate(&bytes);
+
+    // what is left reinterprets inputs, which stay float vectors
+    assert!(casts(&source) <= 20, "{}", source);
+    assert!(source.contains("r0_i.z = r0_i.z + 1;"), "{}", source);
+}
@@ -0,0 +1,23 @@
+vs_5_0
+dcl_constantbuffer CB0[4], immediateIndexed
+dcl_input v0.xyz
+dcl_input v1.xyz
+dcl_output o0.xyzw
+dcl_output o1.xyz
+dcl_output o2.xy
+dcl_temps 2
+mul r0.xyzw, v0.yyyy, cb0[1].xyzw
+mad r0.xyzw, cb0[0].xyzw, v0.xxxx, r0.xyzw
+mad r0.xyzw, cb0[2].xyzw, v0.zzzz, r0.xyzw
+add o0.xyzw, r0.xyzw, cb0[3].xyzw
+dp3 r0.x, v1.xyzx, v1.xyzx
+rsq r0.x, r0.x
+mul o1.xyz, r0.xxxx, v1.xyzx
+max r0.x, v0.x, l(0.000000)
+min o2.x, r0.x, l(1.000000)
+min r1.x, v0.y, l(2.000000)
+max r1.x, r1.x, l(-1.000000)
+dp2 r1.y, v0.xyxx, v0.xyxx
+sqrt r1.y, r1.y
+add o2.y, r1.x, r1.y
+ret
@@ -0,0 +1,201 @@
+extern crate dxbc;
+
+use dxbc::asm;
+use dxbc::dr::shex::OperandType;
+use dxbc::interp::{Dimensions, Error, Interpreter, Lod, Sample, Textures, UnorderedAccessViews, Value};
+use dxbc::ir::Program;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+fn sample(name: &str) -> Program {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let bytes = fs::read(root.join(name)).unwrap();
+    Program::parse(&bytes).unwrap().unwrap()
+}
+
+fn assemble(source: &str) -> Program {
+    let module = asm::assemble(source).unwrap();
+    Program::parse(module.as_bytes()).unwrap().unwrap()
+}
+
+fn floats(values: [f32; 4]) -> Value {
+    [values[0].to_bits(), values[1].to_bits(), values[2].to_bits(), values[3].to_bits()]
+}
+
+#[derive(Default)]
+struct Memory {
+    samples: Vec<Sample>,
+    writes: HashMap<(u32, u32), u32>,
+}
+
+impl Textures for Memory {
+    fn sample(&mut self, sample: &Sample) -> Value {
+        self.samples.push(sample.clone());
+        floats([1.0, 2.0, 3.0, 4.0])
+    }
+
+    fn read(&mut self, texture: u32, offset: u32) -> u32 {
+        texture * 1000 + offset
+    }
+
+    fn dimensions(&mut self, _texture: u32, _level: u32) -> Dimensions {
+        Dimensions { samples: 4, ..Dimensions::default() }
+    }
+}
+
+impl UnorderedAccessViews for Memory {
+    fn read(&mut self, uav: u32, offset: u32) -> u32 {
+        self.writes.get(&(uav, offset)).cloned().unwrap_or(0)
+    }
+
+    fn write(&mut self, uav: u32, offset: u32, value: u32) {
+        self.writes.insert((uav, offset), value);
+    }
+}
+
+#[test]
+fn source_modifiers() {
+    let program = sample("../dxbcd/reference.dxbc");
+    let mut interpreter = Interpreter::new(&program).unwrap();
+
+    // `add o0.xyzw, v0.xyzw, -|v0.xxyy|` with a denormal in w
+    interpreter.inputs[0] = [(-1.0f32).to_bits(), 2.0f32.to_bits(), 0.5f32.to_bits(), 1];
+    let outputs = interpreter.run(&mut (), &mut ()).unwrap();
+    assert_eq!(outputs, vec![floats([-2.0, 1.0, -1.5, -2.0])]);
+}
+
+#[test]
+fn dynamic_constant_buffer_and_sampling() {
+    let program = sample("../dxbcd/shader.dxbc");
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    let mut constants = vec![[0; 4]; 10];
+    constants[8] = floats([0.25, 0.75, 0.0, 0.0]);
+    interpreter.constant_buffers.insert(0, constants);
+    interpreter.inputs[1] = floats([2.5, 1.5, 0.0, 0.0]);
+
+    let mut memory = Memory::default();
+    let outputs = interpreter.run(&mut memory, &mut ()).unwrap();
+
+    // trunc(2.5) * 1.5 is 3, which indexes cb0[2 + 3 * 2], the texel is
+    // swizzled to zxyw
+    assert_eq!(memory.samples.len(), 1);
+    assert_eq!(memory.samples[0].coordinates, [0.75, 0.25, 0.75, 0.75]);
+    assert_eq!(memory.samples[0].lod, Lod::Level(0.0));
+    assert_eq!(outputs[0][..2], floats([3.0, 6.0, 0.0, 0.0])[..2]);
+}
+
+#[test]
+fn compute_memory() {
+    let program = assemble(
+        "cs_5_0
+        dcl_resource_raw t2
+        dcl_resource_structured t3, 16
+        dcl_uav_raw u1
+        dcl_tgsm_raw g0, 64
+        dcl_temps 2
+        dcl_thread_group 8, 8, 1
+        ld_raw r0.x, l(0), t2.xxxx
+        ld_structured r0.y, r0.x, l(4), t3.xxxx
+        store_raw u1.x, l(0), r0.x
+        imm_atomic_iadd r0.z, u1, l(0), l(1)
+        store_raw g0.xy, l(8), r0.xyxx
+        atomic_umax g0, l(12), l(70000)
+        ld_raw r1.xyzw, l(8), g0.xyzw
+        sync_g_t
+        ret",
+    );
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    let mut textures = Memory::default();
+    let mut uavs = Memory::default();
+    interpreter.run(&mut textures, &mut uavs).unwrap();
+
+    // the dword of t2 is stored to u1 and incremented by the atomic
+    assert_eq!(uavs.writes.get(&(1, 0)), Some(&2001));
+    assert_eq!(interpreter.temps[0][..3], [2000, 3000 + 2000 * 16 + 4, 2000]);
+    assert_eq!(interpreter.temps[1], [2000, 70000, 0, 0]);
+}
+
+#[test]
+fn out_of_bounds() {
+    let program = sample("../dxbcross/tests/compute.dxbc");
+    let mut interpreter = Interpreter::new(&program).unwrap();
+
+    // `x0[1 + r0.x]` with r0.x holding the bits of 1.0
+    let result = interpreter.run(&mut (), &mut ());
+    assert_eq!(result, Err(Error::OutOfBounds(4, OperandType::IndexableTemp, 0x3f80_0001)));
+}
+
+#[test]
+fn control_flow() {
+    let program = assemble(
+        "ps_5_0
+        dcl_input_ps linear v0.xyzw
+        dcl_output o0.xyzw
+        dcl_output o1.xyzw
+        dcl_temps 2
+        loop
+          iadd r1.x, r1.x, l(1)
+          uge r1.y, r1.x, l(5)
+          breakc_nz r1.y
+        endloop
+        switch r1.x
+          case l(5)
+          iadd o1.x, r1.x, l(2)
+          break
+          default
+          mov o1.x, l(9)
+          break
+        endswitch
+        udiv r0.z, r0.w, r1.x, l(0)
+        mov o1.yz, r0.zzwz
+        ret",
+    );
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    let outputs = interpreter.run(&mut (), &mut ()).unwrap();
+    assert_eq!(outputs[1][..3], [7, !0, !0]);
+}
+
+#[test]
+fn float_rules() {
+    let mut program = assemble(
+        "ps_5_0
+        dcl_input_ps linear v0.xyzw
+        dcl_output o0.xyzw
+        dcl_temps 1
+        min r0.x, v0.x, v0.y
+        max r0.y, v0.y, v0.x
+        mul r0.z, v0.z, v0.z
+        ftoi r0.w, v0.y
+        mov o0.xyzw, r0.xyzw
+        mov o0.yz, v0.yyzy
+        ret",
+    );
+    program.instructions[5].saturate = true;
+
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.inputs[0] = [3.0f32.to_bits(), 0x7fc0_0000, 2.0f32.to_bits(), 0];
+    let outputs = interpreter.run(&mut (), &mut ()).unwrap();
+
+    // NaN loses against numbers, saturates to 0 and converts to 0
+    assert_eq!(outputs[0], [3.0f32.to_bits(), 0, 1.0f32.to_bits(), 0]);
+
+    // the square of 1e-20 is a denormal
+    interpreter.inputs[0] = [0, 0, 1e-20f32.to_bits(), 0];
+    interpreter.run(&mut (), &mut ()).unwrap();
+    assert_eq!(interpreter.temps[0][2], 0);
+}
+
+#[test]
+fn limits() {
+    let program = assemble(
+        "ps_5_0
+        loop
+        endloop
+        ret",
+    );
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.limit = 100;
+    assert_eq!(interpreter.run(&mut (), &mut ()), Err(Error::LimitReached(1)));
+}
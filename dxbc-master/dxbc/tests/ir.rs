@@ -0,0 +1,102 @@
+extern crate dxbc;
+
+use dxbc::asm;
+use dxbc::dr::{Builder, Modifier, OperandType, TestBoolean};
+use dxbc::d3d11tokenizedprogramformat::*;
+use dxbc::ir::{Components, Declaration, Index, Operand, Program, Register};
+
+use std::fs;
+
+const SAMPLES: &[&str] = &[
+    "../dxbcd/complex_shader.dxbc",
+    "../dxbcd/reference.dxbc",
+    "../dxbcd/shader.dxbc",
+    "../dxbcross/tests/compute.dxbc",
+];
+
+fn parse(source: &str) -> Program {
+    Program::parse(asm::assemble(source).unwrap().as_bytes()).unwrap().unwrap()
+}
+
+fn register(operand: &Operand) -> &Register {
+    operand.register().unwrap_or_else(|| panic!("not a register: {:?}", operand))
+}
+
+#[test]
+fn lowering() {
+    let program = parse(
+        "ps_5_0
+dcl_constantbuffer CB0[8], dynamicIndexed
+dcl_input_ps linear v0.xyzw
+dcl_output o0.xyzw
+dcl_temps 2
+mov_sat r0.xy, -|v0.zwzz|
+mov r1.x, cb0[r0.x + 2].y
+if_z r1.x
+  mov o0.xyzw, l(1.000000, 0.000000, 0.000000, 1.000000)
+endif
+ret
+",
+    );
+
+    assert_eq!(program.decls.len(), 4);
+    assert_eq!(program.decls[3], Declaration::Temps(2));
+    assert_eq!(program.instructions.len(), 6);
+
+    let mov = &program.instructions[0];
+    assert_eq!(mov.opcode, D3D10_SB_OPCODE_MOV);
+    assert!(mov.saturate);
+    let destination = register(&mov.operands[0]);
+    assert_eq!(destination.file, OperandType::Temp);
+    assert_eq!(destination.index, vec![Index::U32(0)]);
+    assert_eq!(destination.components, Components::Mask(0b0011));
+    let source = register(&mov.operands[1]);
+    assert_eq!(source.file, OperandType::Input);
+    assert_eq!(source.modifier, Modifier::AbsNeg);
+    assert_eq!(source.components, Components::Swizzle([2, 3, 2, 2]));
+
+    let source = register(&program.instructions[1].operands[1]);
+    assert_eq!(source.file, OperandType::ConstantBuffer);
+    assert_eq!(source.index[0], Index::U32(0));
+    assert_eq!(source.index[1].offset(), 2);
+    let relative = source.index[1].relative().unwrap();
+    assert_eq!(relative.file, OperandType::Temp);
+    assert_eq!(relative.components, Components::Select(0));
+    assert_eq!(source.components, Components::Select(1));
+
+    assert_eq!(program.instructions[2].test, Some(TestBoolean::Zero));
+    assert_eq!(program.instructions[3].operands[1], Operand::Imm32(vec![0x3f80_0000, 0, 0, 0x3f80_0000]));
+}
+
+#[test]
+fn display() {
+    let source = "vs_5_0
+dcl_input v0.xyzw
+dcl_output_siv o0.xyzw, position
+dcl_temps 1
+mul r0.xyzw, v0.xyzw, l(2.000000, 2.000000, 2.000000, 1.000000)
+loop
+  breakc_nz r0.w
+  add r0.w, r0.w, l(-1.000000)
+endloop
+mov o0.xyzw, r0.xyzw
+ret
+";
+    assert_eq!(parse(source).to_string(), source);
+}
+
+#[test]
+fn round_trip() {
+    for path in SAMPLES {
+        let program = Program::parse(&fs::read(path).unwrap()).unwrap().unwrap();
+
+        // through the text
+        assert_eq!(parse(&format!("{:#}", program)), program, "{}", path);
+
+        // through the builder
+        let mut builder = Builder::new();
+        builder.set_shex(program.to_builder());
+        let module = builder.module().unwrap();
+        assert_eq!(Program::parse(module.as_bytes()).unwrap().unwrap(), program, "{}", path);
+    }
+}
@@ -0,0 +1,60 @@
+extern crate dxbc;
+
+mod golden;
+
+use dxbc::msl;
+use golden::Target;
+
+// Without a Metal compiler the goldens are only compared.
+const MSL: Target = Target {
+    dir: "msl",
+    extension: "metal",
+    translate,
+    validate: |_| Ok(()),
+};
+
+fn translate(bytes: &[u8]) -> Result<String, String> {
+    msl::translate(bytes).map_err(|err| err.to_string())
+}
+
+#[test]
+fn vertex_shader() {
+    MSL.check("../dxbcd/reference.dxbc", "reference");
+}
+
+#[test]
+fn empty_shader() {
+    MSL.check("../dxbcd/assembled.dxbc", "assembled");
+}
+
+#[test]
+fn pixel_shader() {
+    MSL.check("../dxbcd/shader.dxbc", "shader");
+}
+
+#[test]
+fn sampling() {
+    MSL.check("../dxbcd/complex_shader.dxbc", "complex_shader");
+}
+
+#[test]
+fn sample_positions() {
+    MSL.check("../dxbcross/tests/layout.dxbc", "layout");
+}
+
+// Only a pixel shader can end its invocation like `abort`.
+#[test]
+fn abort_outside_pixel_shader() {
+    MSL.unsupported_listing("vs_5_0\nabort\nret\n", "cannot translate `abort` outside a pixel shader");
+}
+
+// HLSL packing that Metal's alignment rules don't reproduce on their own.
+#[test]
+fn constant_buffer_layout() {
+    MSL.check_listing(include_str!("packing.asm"), "packing");
+}
+
+#[test]
+fn compute_shader() {
+    MSL.check("../dxbcross/tests/compute.dxbc", "compute");
+}
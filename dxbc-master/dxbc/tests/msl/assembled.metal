@@ -0,0 +1,7 @@
+#include <metal_stdlib>
+
+using namespace metal;
+
+vertex void main0()
+{
+}
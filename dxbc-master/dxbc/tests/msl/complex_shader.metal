@@ -0,0 +1,293 @@
+#include <metal_stdlib>
+
+using namespace metal;
+
+struct Input
+{
+    float4 TEXCOORD0 [[user(TEXCOORD0)]];
+    float4 TEXCOORD1 [[user(TEXCOORD1), centroid_perspective]];
+    float4 SV_POSITION0 [[position]];
+    uint SV_RenderTargetArrayIndex0 [[render_target_array_index]];
+    uint SV_ViewportArrayIndex0 [[viewport_array_index]];
+    float SV_CullDistance0 [[user(SV_CullDistance0)]];
+    float SV_ClipDistance0 [[user(SV_ClipDistance0)]];
+};
+
+struct Output
+{
+    float4 SV_TARGET0 [[color(0)]];
+};
+
+struct cbuf0_t
+{
+    float4 cool;
+    int4 zeek;
+    int4 arr[127]; // int2 arr[127]
+};
+
+fragment Output main0(
+    Input input [[stage_in]],
+    uint SV_PrimitiveID0 [[primitive_id]],
+    uint SV_SampleIndex0 [[sample_id]],
+    bool SV_IsFrontFace0 [[front_facing]],
+    uint vCoverage [[sample_mask]],
+    constant cbuf0_t& cbuf0 [[buffer(0)]],
+    sampler samp0 [[sampler(0)]],
+    sampler samp1 [[sampler(1)]],
+    texture2d<float> tex0 [[texture(0)]],
+    texturecube<float> tex1 [[texture(1)]],
+    texture3d<float> tex2 [[texture(2)]],
+    texture2d_ms<float> tex3 [[texture(3)]],
+    texture2d<float> tex4_0_ [[texture(4)]],
+    texture2d<float> tex4_1_ [[texture(5)]])
+{
+    float4 v0 = float4(0.0);
+    float4 v1 = float4(0.0);
+    float4 v2 = float4(0.0);
+    float4 v3 = float4(0.0);
+    float4 v4 = float4(0.0);
+    float4 v5 = float4(0.0);
+    float4 o0 = float4(0.0);
+    float4 r0 = float4(0.0);
+    float4 r1 = float4(0.0);
+    float4 r2 = float4(0.0);
+    float4 r3 = float4(0.0);
+    float4 r4 = float4(0.0);
+    float4 r5 = float4(0.0);
+    float4 r6 = float4(0.0);
+    float4 r7 = float4(0.0);
+    float4 r8 = float4(0.0);
+    float4 r9 = float4(0.0);
+    float4 r10 = float4(0.0);
+    float4 r11 = float4(0.0);
+    float4 r12 = float4(0.0);
+    float4 x0[8];
+    float4 x1[4];
+    float4 x2[4];
+
+    v0.xyzw = input.TEXCOORD0;
+    v1.xyzw = input.TEXCOORD1;
+    v2.xyzw = float4(input.SV_POSITION0.xyz, 1.0 / input.SV_POSITION0.w);
+    v3.x = as_type<float>(input.SV_RenderTargetArrayIndex0);
+    v3.y = as_type<float>(input.SV_ViewportArrayIndex0);
+    v3.z = as_type<float>(SV_PrimitiveID0);
+    v4.x = input.SV_CullDistance0;
+    v4.y = input.SV_ClipDistance0;
+    v5.x = as_type<float>(SV_SampleIndex0);
+    v5.y = as_type<float>(SV_IsFrontFace0 ? 0xffffffffu : 0u);
+
+    r0.x = as_type<float>(uint(v2.x));
+    r0.x = float(as_type<uint>(r0.x));
+    r0.y = dot(v0.yzw, float3(1.0, 2.0, 3.0));
+    r0.x = r0.y + r0.x;
+    r0.x = r0.x + v1.x;
+    r0.y = as_type<float>(as_type<uint>(cbuf0.zeek.y) ^ 2u);
+    r0.y = float(as_type<int>(r0.y));
+    r0.x = r0.y + r0.x;
+    r0.y = r0.x;
+    r0.z = 0.0;
+    while (true) {
+        r0.w = as_type<float>((as_type<int>(r0.z) >= 10) ? 0xffffffffu : 0u);
+        if (as_type<uint>(r0.w) != 0u) break;
+        r0.w = float(as_type<int>(r0.z));
+        r1.x = r0.w + 1.001;
+        r1.x = 1.0 / r1.x;
+        r1.y = sqrt(r0.y);
+        r0.w = fma(r0.w, r1.x, r1.y);
+        r0.w = r0.w + r0.y;
+        r1.x = as_type<float>((r0.w < 0.0) ? 0xffffffffu : 0u);
+        if (as_type<uint>(r1.x) != 0u) {
+            r0.y = r0.w;
+            break;
+        }
+        r0.z = as_type<float>(as_type<int>(r0.z) + 1);
+        r0.yz = r0.wz;
+    }
+    r0.x = r0.y + 27.0;
+    r0.y = float(as_type<uint>(v3.x));
+    r0.x = r0.y + r0.x;
+    r0.x = r0.x + v4.x;
+    r0.y = float(as_type<uint>(v3.y));
+    r0.x = r0.y + r0.x;
+    r0.y = as_type<float>(as_type<uint>(v3.y) | as_type<uint>(v5.x));
+    r0.y = float(as_type<uint>(r0.y));
+    r0.x = r0.y + r0.x;
+    r0.y = float(as_type<uint>(v5.x));
+    r0.x = r0.y + r0.x;
+    r0.y = r0.x + 4.0;
+    r0.x = select(r0.x, r0.y, as_type<uint>(v5.y) != 0u);
+    r0.x = r0.x + v4.y;
+    r0.y = float(vCoverage);
+    r0.x = r0.y + r0.x;
+    r0.y = float(as_type<uint>(v3.z));
+    r0.x = r0.y + r0.x;
+    r0.y = as_type<float>(int(r0.x));
+    r0.w = as_type<float>((as_type<int>(r0.y) < 0) ? 0xffffffffu : 0u);
+    if (as_type<uint>(r0.w) != 0u) {
+        r0.w = as_type<float>(as_type<uint>(r0.y) ^ 50u);
+    } else {
+        r1.x = as_type<float>((5 < as_type<int>(r0.y)) ? 0xffffffffu : 0u);
+        if (as_type<uint>(r1.x) != 0u) {
+            r0.w = as_type<float>(as_type<uint>(r0.y) & 2222u);
+        } else {
+            r0.w = as_type<float>(as_type<int>(r0.y) + -cbuf0.arr[as_type<int>(r0.y)].x);
+        }
+    }
+    r0.y = cbuf0.cool.y + cbuf0.cool.x;
+    r0.y = r0.y + cbuf0.cool.z;
+    r0.y = r0.y + cbuf0.cool.w;
+    r0.x = r0.y + r0.x;
+    r0.y = tex0.sample(samp0, float2(0.125, 5.0)).x;
+    r0.x = r0.y + r0.x;
+    r0.y = tex0.sample(samp1, float2(0.777, 1234.5)).x;
+    r0.x = r0.y + r0.x;
+    r0.y = tex1.sample(samp0, float3(0.125, 5.0, 1.0)).x;
+    r0.x = r0.y + r0.x;
+    r0.y = tex2.sample(samp0, float3(0.125, 5.0, 1.0)).z;
+    r0.x = r0.y + r0.x;
+    r0.y = tex3.read(uint2(int2(0, 5) + int2(1, 1)), 0u).x;
+    r0.x = r0.y + r0.x;
+    r0.y = 1.0 / r0.x;
+    r0.y = tex0.sample(samp0, r0.xy, bias(-15.0)).y;
+    r0.x = r0.y + r0.x;
+    r0.y = tex4_0_.sample(samp0, float2(0.0, 5.0)).z;
+    r0.x = r0.y + r0.x;
+    r0.y = tex4_1_.sample(samp0, float2(0.0, 5.0)).z;
+    r0.x = r0.y + r0.x;
+    r0.yz = float2(as_type<int2>(r0.wz));
+    x1[3].x = r0.y;
+    x2[2].x = r0.y;
+    x1[2].x = r0.x;
+    x2[1].x = r0.x;
+    r0.y = as_type<float>(as_type<uint>(r0.w) & 556677u);
+    r0.w = float(as_type<int>(r0.y));
+    x0[0].x = r0.w;
+    x2[0].x = r0.w;
+    r1.x = as_type<float>(as_type<int>(r0.y) + 42);
+    r1.x = float(as_type<int>(r1.x));
+    x0[1].x = r1.x;
+    x2[3].x = r1.x;
+    r0.y = as_type<float>(as_type<int>(r0.y) >> 76u);
+    r1.x = float(as_type<int>(r0.y));
+    x1[1].x = r1.x;
+    x0[2].x = r1.x;
+    r0.y = as_type<float>(as_type<int>(r0.y) << 22u);
+    x0[3].x = 0.0;
+    x1[0].x = 0.0;
+    x0[4].x = 0.0;
+    x0[5].x = 0.0;
+    r1.x = as_type<float>(0u ^ as_type<uint>(cbuf0.arr[0].x));
+    r1.y = float(as_type<uint>(r1.x));
+    x0[6].x = r1.y;
+    r1.y = as_type<float>(as_type<uint>(r1.x) & 127u);
+    r1.x = as_type<float>(as_type<uint>(r1.x) & as_type<uint>(cbuf0.arr[as_type<int>(r1.y)].x));
+    r1.y = float(as_type<uint>(r1.x));
+    r1.xz = as_type<float2>(as_type<int2>(r1.xx) + int2(66, 1));
+    {
+        int4 src0 = cbuf0.arr[as_type<int>(r1.z)].xxxx;
+        int4 src1 = cbuf0.arr[as_type<int>(r1.x)].yyyy;
+        r1.x = as_type<float>(src0.x * src1.x);
+    }
+    r1.x = float(as_type<int>(r1.x));
+    r0.x = r0.x + r1.x;
+    r1.x = r0.x + r1.y;
+    x0[7].x = r1.x;
+    r0.z = r0.w + r0.z;
+    r0.w = as_type<float>(int(r0.z));
+    r0.w = as_type<float>(as_type<uint>(r0.w) & 7u);
+    r0.w = x0[as_type<int>(r0.w)].x;
+    r0.y = float(as_type<int>(r0.y));
+    r0.y = r0.w + r0.y;
+    r0.w = as_type<float>(int(r0.y));
+    r0.w = as_type<float>(as_type<uint>(r0.w) & 7u);
+    r1.x = x1[as_type<int>(r0.w)].x;
+    r0.z = r0.z + r1.x;
+    r0.w = x2[as_type<int>(r0.w)].x;
+    r0.z = trunc(r0.z);
+    r0.z = r0.w + r0.z;
+    r2.z = r0.x * 0.2;
+    r2.w = r1.y + 0.5;
+    r2.xy = trunc(r0.zy);
+    r3.xyw = float4(float(tex0.get_width(0u)), float(tex0.get_height(0u)), 0.0, float(tex0.get_num_mip_levels())).xyw;
+    r0.y = as_type<float>(uint(r3.y));
+    r3.y = float(as_type<uint>(r0.y));
+    r4.xy = float4(get_sample_position(as_type<uint>(r3.y)) - 0.5, 0.0, 0.0).xy;
+    r0.z = r3.w;
+    r4.z = 55566.2;
+    r5.xy = as_type<float2>(popcount(as_type<uint2>(r3.xy)));
+    r6.z = r0.x;
+    r6.w = r1.y;
+    r6.xy = r2.xy;
+    r0.y = 0.0;
+    while (true) {
+        r1.x = as_type<float>(int(r6.x));
+        r1.x = as_type<float>((as_type<int>(r0.y) >= as_type<int>(r1.x)) ? 0xffffffffu : 0u);
+        if (as_type<uint>(r1.x) != 0u) break;
+        r7.x = float(as_type<int>(r0.y));
+        r8.xyzw = r6.xyzw;
+        r1.x = 0.0;
+        while (true) {
+            r1.z = as_type<float>(int(r8.y));
+            r1.z = as_type<float>((as_type<int>(r1.x) >= as_type<int>(r1.z)) ? 0xffffffffu : 0u);
+            if (as_type<uint>(r1.z) != 0u) break;
+            r7.y = float(as_type<int>(r1.x));
+            r9.xyzw = tex0.sample(samp0, r7.xy, gradient2d(float2(1.5, 1.5), float2(4.1, 4.1))).xyzw;
+            r7.yzw = r8.wxy;
+            r1.z = 0.0;
+            while (true) {
+                r1.w = as_type<float>(int(r7.z));
+                r1.w = as_type<float>((as_type<int>(r1.z) >= as_type<int>(r1.w)) ? 0xffffffffu : 0u);
+                if (as_type<uint>(r1.w) != 0u) break;
+                r10.x = float(as_type<int>(r1.z));
+                r11.xyz = r7.zwy;
+                r1.w = 0.0;
+                while (true) {
+                    r4.w = as_type<float>(int(r11.y));
+                    r4.w = as_type<float>((as_type<int>(r1.w) >= as_type<int>(r4.w)) ? 0xffffffffu : 0u);
+                    if (as_type<uint>(r4.w) != 0u) break;
+                    r10.y = float(as_type<int>(r1.w));
+                    r10.yzw = tex0.sample(samp0, r10.xy, gradient2d(float2(1.5, 1.5), float2(4.1, 4.1))).xyw;
+                    r11.xyz = r10.yzw + r11.xyz;
+                    r1.w = as_type<float>(as_type<int>(r1.w) + 1);
+                }
+                r7.yzw = r11.zxy;
+                r1.z = as_type<float>(as_type<int>(r1.z) + 1);
+            }
+            r1.z = fma(r7.y, 2.0, 4.4);
+            r0.w = r4.y * r1.z;
+            r3.zw = r0.zw * r4.xz;
+            r5.zw = as_type<float2>(popcount(as_type<uint2>(r3.zw)));
+            r10.xyzw = float4(as_type<uint4>(r5.xyzw));
+            r10.xyzw = r3.xyzw + r10.xyzw;
+            r12.xyzw = as_type<float4>(reverse_bits(as_type<uint4>(r10.xyzw)));
+            r12.xyzw = float4(as_type<uint4>(r12.xyzw));
+            r10.xyzw = r10.xyzw + r12.xyzw;
+            r0.w = as_type<float>((r10.x == 2.0) ? 0xffffffffu : 0u);
+            if (as_type<uint>(r0.w) != 0u) {
+                discard_fragment();
+            }
+            r12.xyzw = r10.zzzz * float4(4.0, 4.0, 3.0, 3.0);
+            r12.xyzw = as_type<float4>(select(uint4(0u), uint4(0xffffffffu), r12.xyzw >= -r12.yyww));
+            r12.xyzw = select(float4(-4.0, -0.25, -3.0, -0.33333334), float4(4.0, 0.25, 3.0, 0.33333334), as_type<uint4>(r12.xyzw) != 0u);
+            r1.zw = r10.zz * r12.yw;
+            r1.zw = fract(r1.zw);
+            r1.zw = r1.zw * r12.xz;
+            r1.zw = as_type<float2>(select(uint2(0u), uint2(0xffffffffu), r1.zw == float2(0.0, 0.0)));
+            if (as_type<uint>(r1.z) != 0u) {
+            } else {
+            }
+            if (as_type<uint>(r1.w) != 0u) {
+            }
+            r9.xyzw = r9.xyzw + r10.xyzw;
+            r8.xyzw = r9.xyzw + r8.xyzw;
+            r1.x = as_type<float>(as_type<int>(r1.x) + 1);
+        }
+        r6.xyzw = r8.xyzw;
+        r0.y = as_type<float>(as_type<int>(r0.y) + 1);
+    }
+    r0.xyzw = fma(r6.xyzw, float4(2.0, 2.0, 2.0, 2.0), r2.xyzw);
+    o0.xyzw = r0.xyzw + float4(1.1, 2.2, 3.3, 4.4);
+    Output output;
+    output.SV_TARGET0 = o0.xyzw;
+    return output;
+}
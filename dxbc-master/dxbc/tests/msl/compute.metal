@@ -0,0 +1,69 @@
+#include <metal_stdlib>
+
+using namespace metal;
+
+constant uint4 icb[2] =
+{
+    uint4(1065353216u, 0u, 0u, 0u),
+    uint4(0u, 1065353216u, 0u, 0u)
+};
+
+constant int2 dxbc_sample_positions[31] =
+{
+    int2(0, 0),
+    int2(4, 4), int2(-4, -4),
+    int2(-2, -6), int2(6, -2), int2(-6, 2), int2(2, 6),
+    int2(1, -3), int2(-1, 3), int2(5, 1), int2(-3, -5), int2(-5, 5), int2(-7, -1), int2(3, 7), int2(7, -7),
+    int2(1, 1), int2(-1, -3), int2(-3, 2), int2(4, -1), int2(-5, -2), int2(2, 5), int2(5, 3), int2(3, -5),
+    int2(-2, 6), int2(0, -7), int2(-4, -6), int2(-6, 4), int2(-8, 0), int2(7, -4), int2(6, 7), int2(-7, -8),
+};
+
+float2 dxbc_sample_position(uint count, uint index)
+{
+    if (index >= count || count > 16 || (count & (count - 1)) != 0)
+        return float2(0.0);
+    return float2(dxbc_sample_positions[count - 1 + index]) / 16.0;
+}
+
+// dispatched in thread groups of 8x8x1
+kernel void main0(
+    constant float4* cb0 [[buffer(0)]],
+    sampler s0 [[sampler(0)]],
+    texture2d<float> t0 [[texture(0)]],
+    texture2d_ms<uint> t1 [[texture(1)]],
+    const device uint* t2 [[buffer(16)]],
+    const device uint* t3 [[buffer(17)]],
+    texture2d<float, access::write> u0 [[texture(23)]],
+    device uint* u1 [[buffer(23)]],
+    device uint* u2 [[buffer(24)]])
+{
+    threadgroup uint g0[16];
+    threadgroup uint g1[32];
+    float4 r0 = float4(0.0);
+    float4 r1 = float4(0.0);
+    float4 r2 = float4(0.0);
+    float4 x0[4];
+
+    r0.xyzw = float4(1.0, 2.0, -3.0, 0.0);
+    r0.x = as_type<float>(as_type<int>(r0.y) + -5);
+    r1.xy = as_type<float2>(as_type<uint2>(r0.xy) & uint2(65535u, 65535u));
+    r0.xyz = saturate(fma(-abs(r0.xyz), cb0[as_type<int>(r0.x) + 2].xyz, as_type<float3>(icb[as_type<int>(r1.y)].xyz)));
+    x0[as_type<int>(r0.x) + 1].xyzw = r1.xyzw;
+    if (as_type<uint>(r0.x) != 0u) {
+        r2.xyzw = t0.sample(s0, r0.xy, level(0), int2(1, -1)).xyzw;
+    } else {
+        r2.xyzw = t0.read(as_type<uint2>(r0.xy), as_type<uint>(r0.w)).xyzw;
+    }
+    while (true) {
+        if (as_type<uint>(r0.w) == 0u) break;
+        r1.xyzw = as_type<float4>(uint4(t0.get_width(0u), t0.get_height(0u), 0u, t0.get_num_mip_levels()).xyzw);
+    }
+    r0.x = as_type<float>(t2[0]);
+    r0.y = as_type<float>(t3[as_type<uint>(r0.x) * 4 + 1]);
+    u1[0] = as_type<uint>(r0.x);
+    r0.z = as_type<float>(atomic_fetch_add_explicit((device atomic_uint*)&u1[0], 1u, memory_order_relaxed));
+    r1.xy = float4(dxbc_sample_position(t1.get_num_samples(), as_type<uint>(r0.x)), 0.0, 0.0).xy;
+    r1.z = as_type<float>(uint4(t1.get_num_samples(), 0u, 0u, 0u).x);
+    threadgroup_barrier(mem_flags::mem_threadgroup);
+    return;
+}
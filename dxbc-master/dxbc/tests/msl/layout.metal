@@ -0,0 +1,60 @@
+#include <metal_stdlib>
+
+using namespace metal;
+
+struct Input
+{
+    float4 SV_Position0 [[position]];
+    float3 NORMAL0 [[user(NORMAL0)]];
+};
+
+struct Output
+{
+    float4 SV_Target0 [[color(0)]];
+};
+
+struct Globals_t
+{
+    float4 transform[4]; // float4x4 transform
+    packed_float3 tint;
+    float alpha;
+    uint flags;
+};
+
+fragment Output main0(
+    Input input [[stage_in]],
+    constant Globals_t& Globals [[buffer(0)]],
+    sampler S [[sampler(0)]],
+    texturecube<float> T [[texture(1)]])
+{
+    float4 v0 = float4(0.0);
+    float4 v1 = float4(0.0);
+    float4 o0 = float4(0.0);
+    float4 r0 = float4(0.0);
+    float4 r1 = float4(0.0);
+
+    v0.xyzw = float4(input.SV_Position0.xyz, 1.0 / input.SV_Position0.w);
+    v1.xyz = input.NORMAL0;
+
+    r0.x = dot(v1.xyz, Globals.transform[0].xyz);
+    r0.y = dot(v1.xyz, Globals.transform[1].xyz);
+    r0.z = dot(v1.xyz, Globals.transform[2].xyz);
+    r0.xyzw = T.sample(S, r0.xyz).xyzw;
+    r0.xyz = r0.xyz * float3(Globals.tint).xyz;
+    r1.x = as_type<float>(Globals.flags & 1u);
+    if (as_type<uint>(r1.x) != 0u) {
+        o0.xyz = r0.xyz;
+        o0.w = Globals.alpha;
+        Output output;
+        output.SV_Target0 = o0.xyzw;
+        return output;
+    }
+    r1.xy = float4(get_sample_position(1u) - 0.5, 0.0, 0.0).xy;
+    r1.z = float4(float(get_num_samples()), 0.0, 0.0, 0.0).x;
+    r0.xyz = r0.xyz + r1.xyz;
+    o0.xyz = r0.xyz;
+    o0.w = 1.0;
+    Output output;
+    output.SV_Target0 = o0.xyzw;
+    return output;
+}
@@ -0,0 +1,58 @@
+#include <metal_stdlib>
+
+using namespace metal;
+
+struct Input
+{
+    float4 TEXCOORD0 [[user(TEXCOORD0)]];
+};
+
+struct Output
+{
+    float4 SV_Target0 [[color(0)]];
+};
+
+struct Globals_t
+{
+    float scale;
+    packed_float2 offset;
+    float bias;
+    packed_float3 tint;
+    float alpha;
+    float2 uv;
+    uint _pad0[2];
+    float4 weights[3]; // float weights[3]
+    // float last is in Globals.weights[2]
+    float4 basis[3]; // row_major float3x2 basis
+    // uint2 mask is in Globals.basis[2]
+    int4 counts;
+};
+
+fragment Output main0(
+    Input input [[stage_in]],
+    constant Globals_t& Globals [[buffer(0)]])
+{
+    float4 v0 = float4(0.0);
+    float4 o0 = float4(0.0);
+    float4 r0 = float4(0.0);
+    float4 r1 = float4(0.0);
+
+    v0.xyzw = input.TEXCOORD0;
+
+    r0.xy = fma(v0.xy, float2(Globals.scale), float2(Globals.offset).xy);
+    r0.xy = r0.xy + float2(Globals.bias);
+    r0.xy = r0.xy + Globals.uv.xy;
+    r0.z = as_type<float>(uint(v0.z));
+    r0.x = r0.x * Globals.weights[as_type<int>(r0.z)].x;
+    r0.x = r0.x * Globals.weights[2].y;
+    r1.x = dot(r0.xy, Globals.basis[0].xy);
+    r1.y = dot(r0.xy, Globals.basis[1].xy);
+    r1.z = dot(r0.xy, Globals.basis[2].xy);
+    o0.xyz = r1.xyz * float3(Globals.tint).xyz;
+    r1.x = as_type<float>(as_type<uint>(Globals.basis[2].z) & as_type<uint>(Globals.basis[2].w));
+    r1.y = float(Globals.counts.w);
+    o0.w = select(r1.y, Globals.alpha, as_type<uint>(r1.x) != 0u);
+    Output output;
+    output.SV_Target0 = o0.xyzw;
+    return output;
+}
@@ -0,0 +1,26 @@
+#include <metal_stdlib>
+
+using namespace metal;
+
+struct Input
+{
+    float4 COLOR0 [[attribute(0)]];
+};
+
+struct Output
+{
+    float4 SV_Position0 [[position]];
+};
+
+vertex Output main0(Input input [[stage_in]])
+{
+    float4 v0 = float4(0.0);
+    float4 o0 = float4(0.0);
+
+    v0.xyzw = input.COLOR0;
+
+    o0.xyzw = v0.xyzw + -abs(v0.xxyy);
+    Output output;
+    output.SV_Position0 = o0.xyzw;
+    return output;
+}
@@ -0,0 +1,45 @@
+#include <metal_stdlib>
+
+using namespace metal;
+
+struct Input
+{
+    float4 COLOR0 [[user(COLOR0)]];
+    float2 UV0 [[user(UV0)]];
+};
+
+struct Output
+{
+    float2 SV_TARGET0 [[color(0)]];
+};
+
+struct CB_t
+{
+    float4 A[10]; // float4 A[10]
+};
+
+fragment Output main0(
+    Input input [[stage_in]],
+    constant CB_t& CB [[buffer(0)]],
+    sampler S [[sampler(0)]],
+    texture2d<float> T [[texture(0)]])
+{
+    float4 v0 = float4(0.0);
+    float4 v1 = float4(0.0);
+    float4 o0 = float4(0.0);
+    float4 r0 = float4(0.0);
+
+    v0.xyzw = input.COLOR0;
+    v1.xy = input.UV0;
+
+    r0.x = trunc(v1.x);
+    r0.x = r0.x * v1.y;
+    r0.y = as_type<float>(int(r0.x));
+    r0.x = trunc(r0.x);
+    r0.y = as_type<float>(as_type<int>(r0.y) + as_type<int>(r0.y));
+    r0.yz = T.sample(S, CB.A[as_type<int>(r0.y) + 2].yx, level(0.0)).xy;
+    o0.xy = r0.xx * r0.yz;
+    Output output;
+    output.SV_TARGET0 = o0.xy;
+    return output;
+}
@@ -0,0 +1,62 @@
+//
+// Buffer Definitions: 
+//
+// cbuffer Globals
+// {
+//
+//   float scale;                       // Offset:    0 Size:     4
+//   float2 offset;                     // Offset:    4 Size:     8
+//   float bias;                        // Offset:   12 Size:     4
+//   float3 tint;                       // Offset:   16 Size:    12
+//   float alpha;                       // Offset:   28 Size:     4
+//   float2 uv;                         // Offset:   32 Size:     8
+//   float weights[3];                  // Offset:   48 Size:    36
+//   float last;                        // Offset:   84 Size:     4
+//   row_major float3x2 basis;          // Offset:   96 Size:    40
+//   uint2 mask;                        // Offset:  136 Size:     8
+//   int4 counts;                       // Offset:  144 Size:    16
+//
+// }
+//
+//
+// Resource Bindings:
+//
+// Name                                 Type  Format         Dim      HLSL Bind  Count
+// ------------------------------ ---------- ------- ----------- -------------- ------
+// Globals                           cbuffer      NA          NA            cb0      1 
+//
+//
+//
+// Input signature:
+//
+// Name                 Index   Mask Register SysValue  Format   Used
+// -------------------- ----- ------ -------- -------- ------- ------
+// TEXCOORD                 0   xyzw        0     NONE   float   xyzw
+//
+//
+// Output signature:
+//
+// Name                 Index   Mask Register SysValue  Format   Used
+// -------------------- ----- ------ -------- -------- ------- ------
+// SV_Target                0   xyzw        0   TARGET   float   xyzw
+//
+ps_5_0
+dcl_globalFlags refactoringAllowed
+dcl_constantbuffer CB0[10], dynamicIndexed
+dcl_input_ps linear v0.xyzw
+dcl_output o0.xyzw
+dcl_temps 2
+mad r0.xy, v0.xyxx, cb0[0].xxxx, cb0[0].yzyy
+add r0.xy, r0.xyxx, cb0[0].wwww
+add r0.xy, r0.xyxx, cb0[2].xyxx
+ftou r0.z, v0.z
+mul r0.x, r0.x, cb0[r0.z + 3].x
+mul r0.x, r0.x, cb0[5].y
+dp2 r1.x, r0.xyxx, cb0[6].xyxx
+dp2 r1.y, r0.xyxx, cb0[7].xyxx
+dp2 r1.z, r0.xyxx, cb0[8].xyxx
+mul o0.xyz, r1.xyzx, cb0[1].xyzx
+and r1.x, cb0[8].z, cb0[8].w
+itof r1.y, cb0[9].w
+movc o0.w, r1.x, cb0[1].w, r1.y
+ret
@@ -0,0 +1,617 @@
+extern crate dxbc;
+
+use dxbc::asm;
+use dxbc::binary::{Action, Consumer, Decoder, Error, Parser, State};
+use dxbc::disasm;
+use dxbc::dr::builder::{self, Address, ComponentMode, IndexOperandType, Modifier, NumComponent, Operand, X, Y};
+use dxbc::dr::{
+    Builder, ElementPrecision, IOsgnChunk, InputOutputElement, Operands, RdefChunk, RegisterComponentType,
+    SemanticName, ShexChunk, ShexHeader, SparseInstruction,
+};
+use dxbc::ir::Program;
+
+fn assemble(source: &str) -> Vec<u8> {
+    asm::assemble(source).unwrap().as_bytes().to_vec()
+}
+
+#[derive(Default)]
+struct Chunks {
+    shex: usize,
+    shdr: usize,
+    // signatures by fourcc, with the stream and precision of each element
+    signatures: Vec<(&'static str, Vec<(u32, ElementPrecision)>)>,
+    unknown: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Chunks {
+    fn signature(&mut self, fourcc: &'static str, chunk: &IOsgnChunk) -> Action {
+        let elements = chunk.elements.iter().map(|element| (element.stream, element.min_precision)).collect();
+        self.signatures.push((fourcc, elements));
+        Action::Continue
+    }
+}
+
+impl Consumer for Chunks {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn consume_shex(&mut self, _header: &ShexHeader) -> Action {
+        self.shex += 1;
+        Action::Continue
+    }
+
+    fn consume_shdr(&mut self, _header: &ShexHeader) -> Action {
+        self.shdr += 1;
+        Action::Continue
+    }
+
+    fn consume_isgn(&mut self, isgn: &IOsgnChunk) -> Action {
+        self.signature("ISGN", isgn)
+    }
+
+    fn consume_osg5(&mut self, osg5: &IOsgnChunk) -> Action {
+        self.signature("OSG5", osg5)
+    }
+
+    fn consume_osg1(&mut self, osg1: &IOsgnChunk) -> Action {
+        self.signature("OSG1", osg1)
+    }
+
+    fn consume_pcsg(&mut self, pcsg: &IOsgnChunk) -> Action {
+        self.signature("PCSG", pcsg)
+    }
+
+    fn consume_unknown(&mut self, fourcc: &[u8], data: &[u8]) -> Action {
+        self.unknown.push((fourcc.to_vec(), data.to_vec()));
+        Action::Continue
+    }
+}
+
+// Container with a single chunk, its checksum left zeroed.
+fn container(fourcc: &[u8], data: &[u8]) -> Vec<u8> {
+    let words = |values: &[u32]| values.iter().flat_map(|value| value.to_le_bytes().to_vec()).collect::<Vec<u8>>();
+
+    let mut bytes = b"DXBC".to_vec();
+    bytes.extend(words(&[0, 0, 0, 0, 1, (44 + data.len()) as u32, 1, 36]));
+    bytes.extend(fourcc);
+    bytes.extend(words(&[data.len() as u32]));
+    bytes.extend(data);
+    bytes
+}
+
+// Instruction lines of the fxc style listing, without the comment header.
+fn listing(bytes: &[u8]) -> Vec<String> {
+    let source = disasm::disassemble(bytes).unwrap();
+    source
+        .lines()
+        .map(|line| line.trim().to_owned())
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+        .collect()
+}
+
+fn lines(source: &str) -> Vec<String> {
+    source.lines().map(|line| line.trim().to_owned()).collect()
+}
+
+fn element(name: &str, register: u32, stream: u32, min_precision: ElementPrecision) -> InputOutputElement {
+    InputOutputElement {
+        name: name.to_owned(),
+        semantic_index: 0,
+        semantic_type: SemanticName::Undefined,
+        component_type: RegisterComponentType::Float32,
+        register,
+        component_mask: 0xf,
+        rw_mask: 0,
+        stream,
+        min_precision,
+    }
+}
+
+fn signatures(builder: &Builder) -> Vec<(&'static str, Vec<(u32, ElementPrecision)>)> {
+    let module = builder.module().unwrap();
+    let mut chunks = Chunks::default();
+    Parser::new(module.as_bytes(), &mut chunks).parse().unwrap();
+    chunks.signatures
+}
+
+#[test]
+fn shader_model_4_program() {
+    let bytes = assemble(
+        "ps_4_0
+        dcl_input_ps linear v0.xy
+        dcl_output o0.xyzw
+        dcl_temps 1
+        add r0.xy, v0.xyxx, v0.yxyy
+        mov o0.xyzw, r0.xyxy
+        ret",
+    );
+    assert!(bytes.windows(4).any(|fourcc| fourcc == b"SHDR"));
+
+    let mut chunks = Chunks::default();
+    Parser::new(&bytes, &mut chunks).parse().unwrap();
+    assert_eq!((chunks.shex, chunks.shdr), (0, 1));
+
+    // consumers only implementing `consume_shex` see the program as well
+    let program = Program::parse(&bytes).unwrap().unwrap();
+    assert_eq!((program.major, program.minor), (4, 0));
+    assert_eq!(program.instructions.len(), 3);
+}
+
+#[test]
+fn shader_model_5_opcode() {
+    let bytes = assemble(
+        "ps_4_0
+        dcl_input_ps linear v0.xy
+        dcl_output o0.xyzw
+        bfrev o0.x, v0.x
+        ret",
+    );
+
+    match Program::parse(&bytes) {
+        Err(State::DecoderError(Error::UnavailableOpcode(_, opcode, 4, 0))) => assert_eq!(opcode, 141),
+        result => panic!("unexpected result {:?}", result.map(|program| program.is_some())),
+    }
+}
+
+#[test]
+fn shader_model_4_1_opcode() {
+    let source = "ps_4_X
+        dcl_input_ps linear v0.xy
+        dcl_output o0.xyzw
+        dcl_resource_texture2d (float,float,float,float) t0
+        dcl_sampler s0, mode_default
+        lod o0.xy, v0.xyxx, t0.xyzw, s0
+        ret";
+
+    assert!(Program::parse(&assemble(&source.replace("X", "0"))).is_err());
+    assert!(Program::parse(&assemble(&source.replace("X", "1"))).unwrap().is_some());
+}
+
+#[test]
+fn shader_model_4_compute() {
+    let bytes = assemble(
+        "cs_4_0
+        dcl_resource_raw t0
+        dcl_uav_raw u0
+        dcl_temps 1
+        dcl_thread_group 64, 1, 1
+        ld_raw r0.x, l(0), t0.xxxx
+        store_raw u0.x, l(0), r0.x
+        ret",
+    );
+
+    let program = Program::parse(&bytes).unwrap().unwrap();
+    assert_eq!(program.instructions.len(), 3);
+}
+
+#[test]
+fn stream_signatures() {
+    let mut builder = Builder::new();
+    builder.set_isgn(IOsgnChunk { elements: vec![element("POSITION", 0, 0, ElementPrecision::Default)] });
+    builder.set_osg5(IOsgnChunk {
+        elements: vec![
+            element("TEXCOORD", 0, 0, ElementPrecision::Default),
+            element("TEXCOORD", 0, 1, ElementPrecision::Default),
+        ],
+    });
+
+    let expected = vec![
+        ("ISGN", vec![(0, ElementPrecision::Default)]),
+        ("OSG5", vec![(0, ElementPrecision::Default), (1, ElementPrecision::Default)]),
+    ];
+    assert_eq!(signatures(&builder), expected);
+}
+
+#[test]
+fn precision_and_patch_constant_signatures() {
+    let mut builder = Builder::new();
+    builder.set_isg1(IOsgnChunk {
+        elements: vec![element("COLOR", 0, 0, ElementPrecision::Float16), element("COLOR", 1, 0, ElementPrecision::Other(3))],
+    });
+    builder.set_osg1(IOsgnChunk {
+        elements: vec![element("SV_Target", 0, 0, ElementPrecision::Any16), element("SV_Target", 1, 2, ElementPrecision::Uint16)],
+    });
+    builder.set_pcsg(IOsgnChunk { elements: vec![element("SV_TessFactor", 0, 0, ElementPrecision::Default)] });
+
+    // `ISG1` goes through `consume_isgn` by default
+    let expected = vec![
+        ("ISGN", vec![(0, ElementPrecision::Float16), (0, ElementPrecision::Other(3))]),
+        ("OSG1", vec![(0, ElementPrecision::Any16), (2, ElementPrecision::Uint16)]),
+        ("PCSG", vec![(0, ElementPrecision::Default)]),
+    ];
+    assert_eq!(signatures(&builder), expected);
+}
+
+#[derive(Default)]
+struct Semantics(Vec<(SemanticName, RegisterComponentType)>);
+
+impl Consumer for Semantics {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn consume_isgn(&mut self, isgn: &IOsgnChunk) -> Action {
+        self.0.extend(isgn.elements.iter().map(|element| (element.semantic_type, element.component_type)));
+        Action::Continue
+    }
+}
+
+#[test]
+fn unknown_system_values() {
+    let semantics = vec![
+        (SemanticName::Barycentrics, RegisterComponentType::Float16),
+        (SemanticName::StencilRef, RegisterComponentType::Uint64),
+        (SemanticName::Other(200), RegisterComponentType::Other(42)),
+    ];
+
+    let mut builder = Builder::new();
+    builder.set_isgn(IOsgnChunk {
+        elements: semantics
+            .iter()
+            .enumerate()
+            .map(|(register, &(semantic_type, component_type))| InputOutputElement {
+                semantic_type,
+                component_type,
+                ..element("INPUT", register as u32, 0, ElementPrecision::Default)
+            })
+            .collect(),
+    });
+
+    let module = builder.module().unwrap();
+    let mut consumer = Semantics::default();
+    Parser::new(module.as_bytes(), &mut consumer).parse().unwrap();
+    assert_eq!(consumer.0, semantics);
+}
+
+#[test]
+fn hull_shader_phases() {
+    let source = "hs_5_0
+        hs_decls
+        dcl_input_control_point_count 3
+        dcl_output_control_point_count 3
+        dcl_tessellator_domain domain_tri
+        dcl_tessellator_partitioning partitioning_fractional_odd
+        dcl_tessellator_output_primitive output_triangle_cw
+        dcl_hs_max_tessfactor l(64.000000)
+        hs_control_point_phase
+        dcl_input vOutputControlPointID
+        dcl_input v[3][0].xyz
+        dcl_output o0.xyz
+        mov o0.xyz, vicp[vOutputControlPointID + 0][0].xyzx
+        ret
+        hs_fork_phase
+        dcl_hs_fork_phase_instance_count 3
+        dcl_input vForkInstanceID
+        dcl_output_siv o0.x, finalTriUeq0EdgeTessFactor
+        dcl_output_siv o1.x, finalTriVeq0EdgeTessFactor
+        dcl_output_siv o2.x, finalTriWeq0EdgeTessFactor
+        dcl_temps 1
+        dcl_indexrange o0.x 3
+        mov r0.x, vForkInstanceID.x
+        mov o[r0.x + 0].x, vocp[r0.x + 0][0].x
+        ret
+        hs_join_phase
+        dcl_output_siv o3.x, finalTriInsideTessFactor
+        mov o3.x, vpc0.x
+        ret";
+
+    let bytes = assemble(source);
+    assert_eq!(listing(&bytes), lines(source));
+
+    // fxc leaves out the offset of control point indices
+    assert_eq!(assemble(&source.replace("vOutputControlPointID + 0", "vOutputControlPointID")), bytes);
+}
+
+#[test]
+fn domain_and_geometry_shaders() {
+    let domain = "ds_5_0
+        dcl_input_control_point_count 3
+        dcl_tessellator_domain domain_tri
+        dcl_input vDomain.xyz
+        dcl_input vicp[3][0].xyz
+        dcl_input vpc3.x
+        dcl_output_siv o0.xyzw, position
+        dcl_temps 1
+        mul r0.xyz, vDomain.yyyy, vicp[1][0].xyzx
+        mad o0.xyz, vicp[0][0].xyzx, vDomain.xxxx, r0.xyzx
+        mov o0.w, vpc3.x
+        ret";
+    assert_eq!(listing(&assemble(domain)), lines(domain));
+
+    let geometry = "gs_5_0
+        dcl_input v[3][0].xyzw
+        dcl_inputprimitive triangle
+        dcl_stream m0
+        dcl_outputtopology trianglestrip
+        dcl_output_siv o0.xyzw, position
+        dcl_stream m1
+        dcl_outputtopology pointlist
+        dcl_output o0.xyzw
+        dcl_maxout 6
+        dcl_gsinstances 2
+        mov o0.xyzw, v[0][0].xyzw
+        emit_stream m0
+        cut_stream m0
+        mov o0.xyzw, v[1][0].xyzw
+        emitThenCut_stream m1
+        ret";
+    assert_eq!(listing(&assemble(geometry)), lines(geometry));
+}
+
+#[test]
+fn phase_operands() {
+    let scalar = |ty| Operand::new(ty, Modifier::None, NumComponent::D4(ComponentMode::Select(X)));
+    let output = |register| Operand::output(register, Modifier::None, NumComponent::D4(ComponentMode::Mask(X)));
+    let mov = |dst, src| builder::Instruction::Mov { dst, src, saturated: false };
+
+    let mut shex = ShexChunk::new();
+    shex.set_version(3, 5, 0);
+    shex.add_instruction(builder::Instruction::HsControlPointPhase);
+    shex.add_instruction(mov(
+        output(0),
+        scalar(builder::OperandType::InputControlPoint(Address::Relative(IndexOperandType::OutputControlPointId, 0), 1)),
+    ));
+    shex.add_instruction(builder::Instruction::Ret);
+    shex.add_instruction(builder::Instruction::HsForkPhase);
+    shex.add_instruction(mov(output(0), scalar(builder::OperandType::OutputControlPoint(Address::Constant(2), 3))));
+    shex.add_instruction(mov(
+        output(1),
+        scalar(builder::OperandType::InputPatchConstant(Address::Relative(IndexOperandType::ForkInstanceId, 0))),
+    ));
+    shex.add_instruction(builder::Instruction::Ret);
+
+    let mut builder = Builder::new();
+    builder.set_shex(shex);
+    let module = builder.module().unwrap();
+
+    assert_eq!(
+        listing(module.as_bytes()),
+        lines(
+            "hs_5_0
+            hs_control_point_phase
+            mov o0.x, vicp[vOutputControlPointID + 0][1].x
+            ret
+            hs_fork_phase
+            mov o0.x, vocp[2][3].x
+            mov o1.x, vpc[vForkInstanceID + 0].x
+            ret"
+        )
+    );
+}
+
+#[test]
+fn compute_shader() {
+    let compute = "cs_5_0
+        dcl_globalFlags refactoringAllowed
+        dcl_resource_raw t0
+        dcl_resource_structured t1, 16
+        dcl_uav_typed_texture2d (float,float,float,float) u0
+        dcl_uav_raw_glc u1
+        dcl_uav_structured_opc u2, 8
+        dcl_input vThreadID.xy
+        dcl_input vThreadGroupID.x
+        dcl_input vThreadIDInGroupFlattened
+        dcl_temps 2
+        dcl_tgsm_raw g0, 256
+        dcl_tgsm_structured g1, 4, 64
+        dcl_thread_group 8, 8, 1
+        ld_raw r0.x, l(0), t0.xxxx
+        ld_structured r0.y, vThreadGroupID.x, l(4), t1.xxxx
+        ld_uav_typed r1.xyzw, vThreadID.xyyy, u0.xyzw
+        store_uav_typed u0.xyzw, vThreadID.xyyy, r1.xyzw
+        store_structured g1.x, vThreadIDInGroupFlattened, l(0), r0.x
+        sync_g_t
+        atomic_iadd g0, l(0), l(1)
+        atomic_cmp_store u1, l(0), r0.x, r0.y
+        imm_atomic_alloc r0.z, u2
+        imm_atomic_umax r0.x, g0, l(0), l(1)
+        imm_atomic_cmp_exch r0.x, u1, l(0), l(1), l(2)
+        sync_uglobal_g_t
+        ret";
+    assert_eq!(listing(&assemble(compute)), lines(compute));
+}
+
+#[derive(Default)]
+struct Flags(Vec<String>);
+
+impl Consumer for Flags {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn consume_instruction(&mut self, _offset: u32, instruction: SparseInstruction) -> Action {
+        let opcode = instruction.opcode;
+        match instruction.operands {
+            Operands::DclUnorderedAccessViewRaw(_) | Operands::DclUnorderedAccessViewStructured(_) => {
+                self.0.push(format!(
+                    "glc={} opc={}",
+                    opcode.is_globally_coherent(),
+                    opcode.has_order_preserving_counter()
+                ));
+            }
+            Operands::Sync(sync) => {
+                self.0.push(format!(
+                    "uglobal={} ugroup={} g={} t={}",
+                    sync.is_unordered_access_view_memory_global(),
+                    sync.is_unordered_access_view_memory_group(),
+                    sync.is_thread_group_shared_memory(),
+                    sync.is_threads_in_group()
+                ));
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+}
+
+#[test]
+fn unordered_access_view_flags() {
+    let bytes = assemble(
+        "cs_5_0
+        dcl_uav_raw_glc u0
+        dcl_uav_structured_opc u1, 4
+        dcl_uav_structured_glc_opc u2, 4
+        dcl_thread_group 1, 1, 1
+        sync_ugroup_t
+        sync_uglobal_g
+        ret",
+    );
+
+    let mut flags = Flags::default();
+    Parser::new(&bytes, &mut flags).parse().unwrap();
+    assert_eq!(
+        flags.0,
+        vec![
+            "glc=true opc=false",
+            "glc=false opc=true",
+            "glc=true opc=true",
+            "uglobal=false ugroup=true g=false t=true",
+            "uglobal=true ugroup=false g=true t=false",
+        ]
+    );
+}
+
+#[test]
+fn compute_operands() {
+    let operand = |ty, component_mode| Operand::new(ty, Modifier::None, component_mode);
+    let scalar = |ty| operand(ty, NumComponent::D4(ComponentMode::Select(X)));
+    let mask = |ty| operand(ty, NumComponent::D4(ComponentMode::Mask(X)));
+    let imm = |value| operand(builder::OperandType::Imm32(value), NumComponent::D1);
+
+    let mut shex = ShexChunk::new();
+    shex.set_version(5, 5, 0);
+    shex.add_instruction(builder::Instruction::DclUnorderedAccessViewRaw {
+        register: operand(builder::OperandType::UnorderedAccessView(0), NumComponent::D0),
+        globally_coherent: true,
+    });
+    shex.add_instruction(builder::Instruction::DclInput {
+        register: operand(builder::OperandType::InputThreadId, NumComponent::D4(ComponentMode::Mask(X | Y))),
+    });
+    shex.add_instruction(builder::Instruction::DclInput {
+        register: operand(builder::OperandType::InputThreadGroupId, NumComponent::D4(ComponentMode::Mask(X))),
+    });
+    shex.add_instruction(builder::Instruction::DclInput {
+        register: operand(builder::OperandType::InputThreadIdInGroupFlattened, NumComponent::D1),
+    });
+    shex.add_instruction(builder::Instruction::DclThreadGroupSharedMemoryRaw {
+        register: operand(builder::OperandType::ThreadGroupSharedMemory(0), NumComponent::D0),
+        byte_count: 64,
+    });
+    shex.add_instruction(builder::Instruction::DclThreadGroup { x: 16, y: 1, z: 1 });
+    shex.add_instruction(builder::Instruction::StoreRaw {
+        dst: mask(builder::OperandType::ThreadGroupSharedMemory(0)),
+        src_byte_offset: operand(builder::OperandType::InputThreadIdInGroupFlattened, NumComponent::D1),
+        src_value: scalar(builder::OperandType::InputThreadGroupId),
+    });
+    shex.add_instruction(builder::Instruction::Sync {
+        flags: builder::SyncFlags::THREAD_GROUP_SHARED_MEMORY | builder::SyncFlags::THREADS_IN_GROUP,
+    });
+    shex.add_instruction(builder::Instruction::ImmAtomicIadd {
+        dst: mask(builder::OperandType::Register(0)),
+        dst_uav: operand(builder::OperandType::UnorderedAccessView(0), NumComponent::D0),
+        src_address: scalar(builder::OperandType::InputThreadId),
+        src_value: imm(1),
+    });
+    shex.add_instruction(builder::Instruction::Ret);
+
+    let mut builder = Builder::new();
+    builder.set_shex(shex);
+    let module = builder.module().unwrap();
+
+    assert_eq!(
+        listing(module.as_bytes()),
+        lines(
+            "cs_5_0
+            dcl_uav_raw_glc u0
+            dcl_input vThreadID.xy
+            dcl_input vThreadGroupID.x
+            dcl_input vThreadIDInGroupFlattened
+            dcl_tgsm_raw g0, 64
+            dcl_thread_group 16, 1, 1
+            store_raw g0.x, vThreadIDInGroupFlattened, vThreadGroupID.x
+            sync_g_t
+            imm_atomic_iadd r0.x, u0, vThreadID.x, l(1)
+            ret"
+        )
+    );
+}
+
+#[test]
+fn unknown_chunks() {
+    let bytes = container(b"SFI0", &[1, 0, 0, 0, 0, 0, 0, 0]);
+
+    let mut chunks = Chunks::default();
+    Parser::new(&bytes, &mut chunks).parse().unwrap();
+    assert_eq!(chunks.unknown, vec![(b"SFI0".to_vec(), vec![1, 0, 0, 0, 0, 0, 0, 0])]);
+}
+
+#[test]
+fn truncated_header() {
+    let bytes = container(b"SFI0", &[]);
+
+    for len in 0..32 {
+        match Parser::new(&bytes[..len], &mut Chunks::default()).parse() {
+            Err(State::DecoderError(Error::StreamExpected(_))) => {}
+            other => panic!("{} byte header parsed as {:?}", len, other),
+        }
+    }
+}
+
+fn words(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes().to_vec()).collect()
+}
+
+// Shader model 4 RDEF with one buffer holding one variable, whose type is a
+// chain of `depth` structs with two members sharing the next type.
+fn shared_types(depth: u32) -> Vec<u8> {
+    let types = 76;
+    let mut bytes = words(&[1, 28, 0, 0, 0x0400, 0, 0]);
+    bytes.extend(words(&[0, 1, 52, 16, 0, 0]));
+    bytes.extend(words(&[0, 0, 16, 0, types, 0]));
+    for level in 0..depth {
+        let ty = types + 40 * level;
+        let class_and_members = if level + 1 == depth { 0 } else { 2 };
+        bytes.extend(words(&[5, 0, class_and_members << 16, ty + 16]));
+        bytes.extend(words(&[0, ty + 40, 0, 0, ty + 40, 0]));
+    }
+    bytes.push(0);
+    bytes
+}
+
+#[test]
+fn shared_type_descriptors() {
+    let bytes = shared_types(8);
+    let mut decoder = Decoder::new(&bytes);
+    let rdef = RdefChunk::parse(&mut decoder).unwrap();
+    assert_eq!(rdef.constant_buffers[0].variables[0].ty.type_count(), 255);
+
+    match RdefChunk::parse(&mut Decoder::new(&shared_types(40))) {
+        Err(State::ChunkIncorrect) => {}
+        other => panic!("2^40 types parsed as {:?}", other.map(|rdef| rdef.constant_buffers.len())),
+    }
+}
+
+#[test]
+fn zero_member_stride() {
+    let mut bytes = words(&[0, 0, 0, 0, 0x0500, 0, 0]);
+    bytes.extend(b"RD11");
+    bytes.extend(words(&[60, 24, 32, 40, 36, 0, 4]));
+
+    match RdefChunk::parse(&mut Decoder::new(&bytes)) {
+        Err(State::ChunkIncorrect) => {}
+        other => panic!("zero member stride parsed as {:?}", other.map(|rdef| rdef.constant_buffers.len())),
+    }
+}
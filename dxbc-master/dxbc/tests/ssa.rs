@@ -0,0 +1,120 @@
+extern crate dxbc;
+
+mod common;
+
+use dxbc::ssa::{Def, FunctionSsa, Ssa, Temp, Use};
+
+const HEADER: &str = "ps_5_0
+dcl_input_ps constant v0.x
+dcl_output o0.xyzw
+dcl_temps 2
+";
+
+fn build(body: &str) -> Ssa {
+    Ssa::build(&common::program(HEADER, body)).unwrap()
+}
+
+const R0X: Temp = Temp { register: 0, component: 0 };
+const R0Z: Temp = Temp { register: 0, component: 2 };
+
+const COUNTER: &str = "mov r0.x, l(0)
+mov r0.y, l(5)
+mov r1.x, v0.x
+loop
+  ige r0.z, r0.x, l(10)
+  breakc_nz r0.z
+  iadd r0.x, r0.x, l(1)
+endloop
+mov o0.xyzw, r0.xxxx
+ret
+";
+
+fn names(function: &FunctionSsa, values: &[usize]) -> Vec<String> {
+    values.iter().map(|&value| function.values[value].to_string()).collect()
+}
+
+#[test]
+fn versions() {
+    let ssa = build(COUNTER);
+    let function = &ssa.functions[0];
+
+    // the loop header merges the initial value with the incremented one
+    let before = function.written(0, 0, R0X).unwrap();
+    let after = function.written(6, 0, R0X).unwrap();
+    let header = function.read(4, 1, R0X).unwrap();
+    assert_eq!(function.phis[1], vec![header]);
+    assert_eq!(function.values[header].def, Def::Phi(1, vec![(0, before), (2, after)]));
+    assert_eq!(names(function, &[before, header, after]), vec!["r0_x_1", "r0_x_2", "r0_x_3"]);
+
+    // every read of r0.x in and after the loop sees the phi
+    assert_eq!(function.read(6, 1, R0X), Some(header));
+    assert_eq!(function.read(8, 1, R0X), Some(header));
+    assert_eq!(
+        function.values[header].uses,
+        vec![Use::Instruction(4), Use::Instruction(6), Use::Instruction(8)]
+    );
+    assert_eq!(function.values[before].uses, vec![Use::Phi(header)]);
+    assert_eq!(function.values[after].uses, vec![Use::Phi(header)]);
+
+    let compare = function.written(4, 0, R0Z).unwrap();
+    assert_eq!(function.read(5, 0, R0Z), Some(compare));
+    assert_eq!(function.written(4, 1, R0X), None);
+}
+
+#[test]
+fn liveness() {
+    let ssa = build(COUNTER);
+    let function = &ssa.functions[0];
+    let header = function.read(4, 1, R0X).unwrap();
+
+    assert_eq!(ssa.cfg.functions[0].blocks.len(), 4);
+    assert_eq!(names(function, &function.live_out[0]), vec!["r0_x_1"]);
+    // phis are defined at the start of their block, not live into it
+    assert!(function.live_in[1].is_empty());
+    assert_eq!(function.live_out[1], vec![header]);
+    assert_eq!(function.live_in[2], vec![header]);
+    assert_eq!(names(function, &function.live_out[2]), vec!["r0_x_3"]);
+    assert_eq!(function.live_in[3], vec![header]);
+    assert!(function.live_out[3].is_empty());
+}
+
+#[test]
+fn dead_writes() {
+    let ssa = build(COUNTER);
+    let function = &ssa.functions[0];
+
+    assert_eq!(names(function, &function.dead_writes()), vec!["r0_y_1", "r1_x_1"]);
+}
+
+#[test]
+fn calls() {
+    let ssa = build(
+        "mov r0.x, l(1)
+call l0
+mov o0.xyzw, r0.xxxx
+ret
+label l0
+mov r0.x, l(2)
+ret
+",
+    );
+    let main = &ssa.functions[0];
+
+    // calls read and write every declared temp, so nothing is dead before
+    // them and reads after them see the call
+    assert_eq!(main.reads[&1].len(), 8);
+    assert!(main.reads[&1].iter().all(|access| access.operand.is_none()));
+    assert_eq!(main.writes[&1].len(), 8);
+    assert!(main.dead_writes().is_empty());
+    assert_eq!(main.values[main.read(2, 1, R0X).unwrap()].def, Def::Instruction(1));
+
+    let untouched = main.reads[&1].iter().find(|access| access.temp.register == 1).unwrap();
+    assert_eq!(main.values[untouched.value].def, Def::Entry);
+    assert_eq!(main.values[untouched.value].version, 0);
+
+    // the return of the subroutine reads what it wrote
+    let subroutine = &ssa.functions[1];
+    let written = subroutine.written(5, 0, R0X).unwrap();
+    assert_eq!(subroutine.values[written].uses, vec![Use::Instruction(6)]);
+    assert!(subroutine.dead_writes().is_empty());
+}
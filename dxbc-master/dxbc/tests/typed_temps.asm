@@ -0,0 +1,20 @@
+ps_5_0
+dcl_input_ps constant v0.x
+dcl_output o0.xyzw
+dcl_temps 2
+mov r0.x, l(0)
+mov r1.x, l(0.000000)
+loop
+  ige r0.y, r0.x, l(10)
+  breakc_nz r0.y
+  itof r0.z, r0.x
+  add r1.x, r1.x, r0.z
+  iadd r0.x, r0.x, l(1)
+endloop
+ishl r0.w, r0.x, l(2)
+and r0.w, r0.w, l(12)
+mov r1.xy, r0.zxxx
+itof r1.y, r1.y
+mov o0.xyz, r1.xyxx
+utof o0.w, r0.w
+ret
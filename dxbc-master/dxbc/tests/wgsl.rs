@@ -0,0 +1,72 @@
+extern crate dxbc;
+extern crate naga;
+
+mod golden;
+
+use dxbc::wgsl;
+use golden::Target;
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+const WGSL: Target = Target {
+    dir: "wgsl",
+    extension: "wgsl",
+    translate,
+    validate,
+};
+
+fn translate(bytes: &[u8]) -> Result<String, String> {
+    wgsl::translate(bytes).map_err(|err| err.to_string())
+}
+
+// naga doesn't know the `diagnostic` directive, the uniformity analysis it
+// turns off is skipped instead.
+fn validate(source: &str) -> Result<(), String> {
+    let source = source.replace("diagnostic(off, derivative_uniformity);\n", "");
+    let module = naga::front::wgsl::parse_str(&source).map_err(|err| err.emit_to_string(&source))?;
+    Validator::new(ValidationFlags::all() - ValidationFlags::CONTROL_FLOW_UNIFORMITY, Capabilities::all())
+        .validate(&module)
+        .map(|_| ())
+        .map_err(|err| format!("{:?}", err.into_inner()))
+}
+
+#[test]
+fn vertex_shader() {
+    WGSL.check("../dxbcd/reference.dxbc", "reference");
+}
+
+#[test]
+fn empty_shader() {
+    WGSL.check("../dxbcd/assembled.dxbc", "assembled");
+}
+
+#[test]
+fn pixel_shader() {
+    WGSL.check("../dxbcd/shader.dxbc", "shader");
+}
+
+#[test]
+fn sampling() {
+    WGSL.check("../dxbcd/complex_shader.dxbc", "complex_shader");
+}
+
+#[test]
+fn sample_positions() {
+    WGSL.check("../dxbcross/tests/layout.dxbc", "layout");
+}
+
+// Only a pixel shader can end its invocation like `abort`.
+#[test]
+fn abort_outside_pixel_shader() {
+    WGSL.unsupported_listing("vs_5_0\nabort\nret\n", "cannot translate `abort` outside a pixel shader");
+}
+
+// HLSL packing that WGSL's alignment rules don't reproduce on their own.
+#[test]
+fn constant_buffer_layout() {
+    WGSL.check_listing(include_str!("packing.asm"), "packing");
+}
+
+#[test]
+fn compute_shader() {
+    WGSL.check("../dxbcross/tests/compute.dxbc", "compute");
+}
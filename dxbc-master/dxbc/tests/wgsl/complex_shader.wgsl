@@ -0,0 +1,335 @@
+diagnostic(off, derivative_uniformity);
+
+struct Input
+{
+    @location(0) TEXCOORD0: vec4<f32>,
+    @location(1) @interpolate(perspective, centroid) TEXCOORD1: vec4<f32>,
+    @builtin(position) SV_POSITION0: vec4<f32>,
+    @location(3) @interpolate(flat) SV_RenderTargetArrayIndex0: vec3<u32>,
+    @location(4) SV_CullDistance0: vec2<f32>,
+    @builtin(sample_index) SV_SampleIndex0: u32,
+    @builtin(front_facing) SV_IsFrontFace0: bool,
+    @builtin(sample_mask) vCoverage: u32,
+};
+
+struct Output
+{
+    @location(0) SV_TARGET0: vec4<f32>,
+};
+
+struct cbuf0_t
+{
+    cool: vec4<f32>,
+    zeek: vec4<i32>,
+    arr: array<vec4<i32>, 127>, // int2 arr[127]
+};
+
+@group(0) @binding(0) var<uniform> cbuf0: cbuf0_t;
+@group(2) @binding(0) var samp0: sampler;
+@group(2) @binding(1) var samp1: sampler;
+@group(1) @binding(0) var tex0: texture_2d<f32>;
+@group(1) @binding(1) var tex1: texture_cube<f32>;
+@group(1) @binding(2) var tex2: texture_3d<f32>;
+@group(1) @binding(3) var tex3: texture_multisampled_2d<f32>;
+@group(1) @binding(4) var tex4_0_: texture_2d<f32>;
+@group(1) @binding(5) var tex4_1_: texture_2d<f32>;
+
+fn dxbc_umul_hi(a: u32, b: u32) -> u32
+{
+    let lo = (a & 0xffffu) * (b & 0xffffu);
+    let mid0 = (a >> 16u) * (b & 0xffffu);
+    let mid1 = (a & 0xffffu) * (b >> 16u);
+    let carry = ((lo >> 16u) + (mid0 & 0xffffu) + (mid1 & 0xffffu)) >> 16u;
+    return (a >> 16u) * (b >> 16u) + (mid0 >> 16u) + (mid1 >> 16u) + carry;
+}
+
+fn dxbc_imul_hi(a: i32, b: i32) -> i32
+{
+    let hi = dxbc_umul_hi(bitcast<u32>(a), bitcast<u32>(b));
+    return bitcast<i32>(hi - select(0u, bitcast<u32>(b), a < 0i) - select(0u, bitcast<u32>(a), b < 0i));
+}
+
+override dxbc_sample_count: u32 = 1u;
+
+var<private> dxbc_sample_positions: array<vec2<i32>, 31> = array<vec2<i32>, 31>(
+    vec2<i32>(0, 0),
+    vec2<i32>(4, 4), vec2<i32>(-4, -4),
+    vec2<i32>(-2, -6), vec2<i32>(6, -2), vec2<i32>(-6, 2), vec2<i32>(2, 6),
+    vec2<i32>(1, -3), vec2<i32>(-1, 3), vec2<i32>(5, 1), vec2<i32>(-3, -5),
+    vec2<i32>(-5, 5), vec2<i32>(-7, -1), vec2<i32>(3, 7), vec2<i32>(7, -7),
+    vec2<i32>(1, 1), vec2<i32>(-1, -3), vec2<i32>(-3, 2), vec2<i32>(4, -1),
+    vec2<i32>(-5, -2), vec2<i32>(2, 5), vec2<i32>(5, 3), vec2<i32>(3, -5),
+    vec2<i32>(-2, 6), vec2<i32>(0, -7), vec2<i32>(-4, -6), vec2<i32>(-6, 4),
+    vec2<i32>(-8, 0), vec2<i32>(7, -4), vec2<i32>(6, 7), vec2<i32>(-7, -8),
+);
+
+fn dxbc_sample_position(count: u32, index: u32) -> vec2<f32>
+{
+    if (index >= count || count > 16u || (count & (count - 1u)) != 0u) {
+        return vec2<f32>(0.0);
+    }
+    return vec2<f32>(dxbc_sample_positions[count - 1u + index]) / 16.0;
+}
+
+@fragment
+fn main(input: Input) -> Output
+{
+    var v0: vec4<f32>;
+    var v1: vec4<f32>;
+    var v2: vec4<f32>;
+    var v3: vec4<f32>;
+    var v4: vec4<f32>;
+    var v5: vec4<f32>;
+    var o0: vec4<f32>;
+    var r0: vec4<f32>;
+    var r1: vec4<f32>;
+    var r2: vec4<f32>;
+    var r3: vec4<f32>;
+    var r4: vec4<f32>;
+    var r5: vec4<f32>;
+    var r6: vec4<f32>;
+    var r7: vec4<f32>;
+    var r8: vec4<f32>;
+    var r9: vec4<f32>;
+    var r10: vec4<f32>;
+    var r11: vec4<f32>;
+    var r12: vec4<f32>;
+    var x0: array<vec4<f32>, 8>;
+    var x1: array<vec4<f32>, 4>;
+    var x2: array<vec4<f32>, 4>;
+
+    v0 = input.TEXCOORD0;
+    v1 = input.TEXCOORD1;
+    v2 = vec4<f32>(input.SV_POSITION0.xyz, 1.0 / input.SV_POSITION0.w);
+    v3.x = bitcast<f32>(input.SV_RenderTargetArrayIndex0.x);
+    v3.y = bitcast<f32>(input.SV_RenderTargetArrayIndex0.y);
+    v3.z = bitcast<f32>(input.SV_RenderTargetArrayIndex0.z);
+    v4.x = input.SV_CullDistance0.x;
+    v4.y = input.SV_CullDistance0.y;
+    v5.x = bitcast<f32>(input.SV_SampleIndex0);
+    v5.y = bitcast<f32>(select(0u, 0xffffffffu, input.SV_IsFrontFace0));
+
+    r0.x = bitcast<f32>(u32(v2.x));
+    r0.x = f32(bitcast<u32>(r0.x));
+    r0.y = dot(v0.yzw, vec3<f32>(1.0, 2.0, 3.0));
+    r0.x = r0.y + r0.x;
+    r0.x = r0.x + v1.x;
+    r0.y = bitcast<f32>(bitcast<u32>(cbuf0.zeek.y) ^ 2u);
+    r0.y = f32(bitcast<i32>(r0.y));
+    r0.x = r0.y + r0.x;
+    r0.y = r0.x;
+    r0.z = 0.0;
+    loop {
+        r0.w = bitcast<f32>(select(0u, 0xffffffffu, bitcast<i32>(r0.z) >= 10i));
+        if (bitcast<u32>(r0.w) != 0u) { break; }
+        r0.w = f32(bitcast<i32>(r0.z));
+        r1.x = r0.w + 1.001;
+        r1.x = 1.0 / r1.x;
+        r1.y = sqrt(r0.y);
+        r0.w = fma(r0.w, r1.x, r1.y);
+        r0.w = r0.w + r0.y;
+        r1.x = bitcast<f32>(select(0u, 0xffffffffu, r0.w < 0.0));
+        if (bitcast<u32>(r1.x) != 0u) {
+            r0.y = r0.w;
+            break;
+        }
+        r0.z = bitcast<f32>(bitcast<i32>(r0.z) + 1i);
+        r0 = vec4<f32>(r0.x, r0.wz, r0.w);
+    }
+    r0.x = r0.y + 27.0;
+    r0.y = f32(bitcast<u32>(v3.x));
+    r0.x = r0.y + r0.x;
+    r0.x = r0.x + v4.x;
+    r0.y = f32(bitcast<u32>(v3.y));
+    r0.x = r0.y + r0.x;
+    r0.y = bitcast<f32>(bitcast<u32>(v3.y) | bitcast<u32>(v5.x));
+    r0.y = f32(bitcast<u32>(r0.y));
+    r0.x = r0.y + r0.x;
+    r0.y = f32(bitcast<u32>(v5.x));
+    r0.x = r0.y + r0.x;
+    r0.y = r0.x + 4.0;
+    r0.x = select(r0.x, r0.y, bitcast<u32>(v5.y) != 0u);
+    r0.x = r0.x + v4.y;
+    r0.y = f32(input.vCoverage);
+    r0.x = r0.y + r0.x;
+    r0.y = f32(bitcast<u32>(v3.z));
+    r0.x = r0.y + r0.x;
+    r0.y = bitcast<f32>(i32(r0.x));
+    r0.w = bitcast<f32>(select(0u, 0xffffffffu, bitcast<i32>(r0.y) < 0i));
+    if (bitcast<u32>(r0.w) != 0u) {
+        r0.w = bitcast<f32>(bitcast<u32>(r0.y) ^ 50u);
+    } else {
+        r1.x = bitcast<f32>(select(0u, 0xffffffffu, 5i < bitcast<i32>(r0.y)));
+        if (bitcast<u32>(r1.x) != 0u) {
+            r0.w = bitcast<f32>(bitcast<u32>(r0.y) & 2222u);
+        } else {
+            r0.w = bitcast<f32>(bitcast<i32>(r0.y) + -cbuf0.arr[bitcast<i32>(r0.y)].x);
+        }
+    }
+    r0.y = cbuf0.cool.y + cbuf0.cool.x;
+    r0.y = r0.y + cbuf0.cool.z;
+    r0.y = r0.y + cbuf0.cool.w;
+    r0.x = r0.y + r0.x;
+    r0.y = textureSample(tex0, samp0, vec2<f32>(0.125, 5.0)).x;
+    r0.x = r0.y + r0.x;
+    r0.y = textureSample(tex0, samp1, vec2<f32>(0.777, 1234.5)).x;
+    r0.x = r0.y + r0.x;
+    r0.y = textureSample(tex1, samp0, vec3<f32>(0.125, 5.0, 1.0)).x;
+    r0.x = r0.y + r0.x;
+    r0.y = textureSample(tex2, samp0, vec3<f32>(0.125, 5.0, 1.0)).z;
+    r0.x = r0.y + r0.x;
+    r0.y = textureLoad(tex3, vec2<i32>(0i, 5i) + vec2<i32>(1, 1), 0i).x;
+    r0.x = r0.y + r0.x;
+    r0.y = 1.0 / r0.x;
+    r0.y = textureSampleBias(tex0, samp0, r0.xy, -15.0).y;
+    r0.x = r0.y + r0.x;
+    r0.y = textureSample(tex4_0_, samp0, vec2<f32>(0.0, 5.0)).z;
+    r0.x = r0.y + r0.x;
+    r0.y = textureSample(tex4_1_, samp0, vec2<f32>(0.0, 5.0)).z;
+    r0.x = r0.y + r0.x;
+    r0 = vec4<f32>(r0.x, vec2<f32>(bitcast<vec2<i32>>(r0.wz)), r0.w);
+    x1[3].x = r0.y;
+    x2[2].x = r0.y;
+    x1[2].x = r0.x;
+    x2[1].x = r0.x;
+    r0.y = bitcast<f32>(bitcast<u32>(r0.w) & 556677u);
+    r0.w = f32(bitcast<i32>(r0.y));
+    x0[0].x = r0.w;
+    x2[0].x = r0.w;
+    r1.x = bitcast<f32>(bitcast<i32>(r0.y) + 42i);
+    r1.x = f32(bitcast<i32>(r1.x));
+    x0[1].x = r1.x;
+    x2[3].x = r1.x;
+    r0.y = bitcast<f32>(bitcast<i32>(r0.y) >> 12u);
+    r1.x = f32(bitcast<i32>(r0.y));
+    x1[1].x = r1.x;
+    x0[2].x = r1.x;
+    r0.y = bitcast<f32>(bitcast<i32>(r0.y) << 22u);
+    x0[3].x = 0.0;
+    x1[0].x = 0.0;
+    x0[4].x = 0.0;
+    x0[5].x = 0.0;
+    r1.x = bitcast<f32>(0u ^ bitcast<u32>(cbuf0.arr[0].x));
+    r1.y = f32(bitcast<u32>(r1.x));
+    x0[6].x = r1.y;
+    r1.y = bitcast<f32>(bitcast<u32>(r1.x) & 127u);
+    r1.x = bitcast<f32>(bitcast<u32>(r1.x) & bitcast<u32>(cbuf0.arr[bitcast<i32>(r1.y)].x));
+    r1.y = f32(bitcast<u32>(r1.x));
+    {
+        let value = bitcast<vec2<f32>>(bitcast<vec2<i32>>(r1.xx) + vec2<i32>(66i, 1i));
+        r1.x = value.x;
+        r1.z = value.y;
+    }
+    {
+        let src0 = cbuf0.arr[bitcast<i32>(r1.z)].xxxx;
+        let src1 = cbuf0.arr[bitcast<i32>(r1.x)].yyyy;
+        r1.x = bitcast<f32>(src0.x * src1.x);
+    }
+    r1.x = f32(bitcast<i32>(r1.x));
+    r0.x = r0.x + r1.x;
+    r1.x = r0.x + r1.y;
+    x0[7].x = r1.x;
+    r0.z = r0.w + r0.z;
+    r0.w = bitcast<f32>(i32(r0.z));
+    r0.w = bitcast<f32>(bitcast<u32>(r0.w) & 7u);
+    r0.w = x0[bitcast<i32>(r0.w)].x;
+    r0.y = f32(bitcast<i32>(r0.y));
+    r0.y = r0.w + r0.y;
+    r0.w = bitcast<f32>(i32(r0.y));
+    r0.w = bitcast<f32>(bitcast<u32>(r0.w) & 7u);
+    r1.x = x1[bitcast<i32>(r0.w)].x;
+    r0.z = r0.z + r1.x;
+    r0.w = x2[bitcast<i32>(r0.w)].x;
+    r0.z = trunc(r0.z);
+    r0.z = r0.w + r0.z;
+    r2.z = r0.x * 0.2;
+    r2.w = r1.y + 0.5;
+    r2 = vec4<f32>(trunc(r0.zy), r2.zw);
+    {
+        let value = vec4<f32>(vec2<f32>(textureDimensions(tex0, 0u)), 0.0, f32(textureNumLevels(tex0))).xyw;
+        r3.x = value.x;
+        r3.y = value.y;
+        r3.w = value.z;
+    }
+    r0.y = bitcast<f32>(u32(r3.y));
+    r3.y = f32(bitcast<u32>(r0.y));
+    r4 = vec4<f32>(vec4<f32>(dxbc_sample_position(dxbc_sample_count, bitcast<u32>(r3.y)), 0.0, 0.0).xy, r4.zw);
+    r0.z = r3.w;
+    r4.z = 55566.2;
+    r5 = vec4<f32>(bitcast<vec2<f32>>(countOneBits(bitcast<vec2<u32>>(r3.xy))), r5.zw);
+    r6.z = r0.x;
+    r6.w = r1.y;
+    r6 = vec4<f32>(r2.xy, r6.zw);
+    r0.y = 0.0;
+    loop {
+        r1.x = bitcast<f32>(i32(r6.x));
+        r1.x = bitcast<f32>(select(0u, 0xffffffffu, bitcast<i32>(r0.y) >= bitcast<i32>(r1.x)));
+        if (bitcast<u32>(r1.x) != 0u) { break; }
+        r7.x = f32(bitcast<i32>(r0.y));
+        r8 = r6.xyzw;
+        r1.x = 0.0;
+        loop {
+            r1.z = bitcast<f32>(i32(r8.y));
+            r1.z = bitcast<f32>(select(0u, 0xffffffffu, bitcast<i32>(r1.x) >= bitcast<i32>(r1.z)));
+            if (bitcast<u32>(r1.z) != 0u) { break; }
+            r7.y = f32(bitcast<i32>(r1.x));
+            r9 = textureSampleGrad(tex0, samp0, r7.xy, vec2<f32>(1.5, 1.5), vec2<f32>(4.1, 4.1)).xyzw;
+            r7 = vec4<f32>(r7.x, r8.wxy);
+            r1.z = 0.0;
+            loop {
+                r1.w = bitcast<f32>(i32(r7.z));
+                r1.w = bitcast<f32>(select(0u, 0xffffffffu, bitcast<i32>(r1.z) >= bitcast<i32>(r1.w)));
+                if (bitcast<u32>(r1.w) != 0u) { break; }
+                r10.x = f32(bitcast<i32>(r1.z));
+                r11 = vec4<f32>(r7.zwy, r11.w);
+                r1.w = 0.0;
+                loop {
+                    r4.w = bitcast<f32>(i32(r11.y));
+                    r4.w = bitcast<f32>(select(0u, 0xffffffffu, bitcast<i32>(r1.w) >= bitcast<i32>(r4.w)));
+                    if (bitcast<u32>(r4.w) != 0u) { break; }
+                    r10.y = f32(bitcast<i32>(r1.w));
+                    r10 = vec4<f32>(r10.x, textureSampleGrad(tex0, samp0, r10.xy, vec2<f32>(1.5, 1.5), vec2<f32>(4.1, 4.1)).xyw);
+                    r11 = vec4<f32>(r10.yzw + r11.xyz, r11.w);
+                    r1.w = bitcast<f32>(bitcast<i32>(r1.w) + 1i);
+                }
+                r7 = vec4<f32>(r7.x, r11.zxy);
+                r1.z = bitcast<f32>(bitcast<i32>(r1.z) + 1i);
+            }
+            r1.z = fma(r7.y, 2.0, 4.4);
+            r0.w = r4.y * r1.z;
+            r3 = vec4<f32>(r3.xy, r0.zw * r4.xz);
+            r5 = vec4<f32>(r5.xy, bitcast<vec2<f32>>(countOneBits(bitcast<vec2<u32>>(r3.zw))));
+            r10 = vec4<f32>(bitcast<vec4<u32>>(r5.xyzw));
+            r10 = r3.xyzw + r10.xyzw;
+            r12 = bitcast<vec4<f32>>(reverseBits(bitcast<vec4<u32>>(r10.xyzw)));
+            r12 = vec4<f32>(bitcast<vec4<u32>>(r12.xyzw));
+            r10 = r10.xyzw + r12.xyzw;
+            r0.w = bitcast<f32>(select(0u, 0xffffffffu, r10.x == 2.0));
+            if (bitcast<u32>(r0.w) != 0u) {
+                discard;
+            }
+            r12 = r10.zzzz * vec4<f32>(4.0, 4.0, 3.0, 3.0);
+            r12 = bitcast<vec4<f32>>(select(vec4<u32>(0u), vec4<u32>(0xffffffffu), r12.xyzw >= -r12.yyww));
+            r12 = select(vec4<f32>(-4.0, -0.25, -3.0, -0.33333334), vec4<f32>(4.0, 0.25, 3.0, 0.33333334), bitcast<vec4<u32>>(r12.xyzw) != vec4<u32>(0u));
+            r1 = vec4<f32>(r1.xy, r10.zz * r12.yw);
+            r1 = vec4<f32>(r1.xy, fract(r1.zw));
+            r1 = vec4<f32>(r1.xy, r1.zw * r12.xz);
+            r1 = vec4<f32>(r1.xy, bitcast<vec2<f32>>(select(vec2<u32>(0u), vec2<u32>(0xffffffffu), r1.zw == vec2<f32>(0.0, 0.0))));
+            if (bitcast<u32>(r1.z) != 0u) {
+            } else {
+            }
+            if (bitcast<u32>(r1.w) != 0u) {
+            }
+            r9 = r9.xyzw + r10.xyzw;
+            r8 = r9.xyzw + r8.xyzw;
+            r1.x = bitcast<f32>(bitcast<i32>(r1.x) + 1i);
+        }
+        r6 = r8.xyzw;
+        r0.y = bitcast<f32>(bitcast<i32>(r0.y) + 1i);
+    }
+    r0 = fma(r6.xyzw, vec4<f32>(2.0, 2.0, 2.0, 2.0), r2.xyzw);
+    o0 = r0.xyzw + vec4<f32>(1.1, 2.2, 3.3, 4.4);
+    var output: Output;
+    output.SV_TARGET0 = o0.xyzw;
+    return output;
+}
@@ -0,0 +1,68 @@
+@group(0) @binding(0) var<uniform> cb0: array<vec4<f32>, 4>;
+@group(2) @binding(0) var s0: sampler;
+@group(1) @binding(0) var t0: texture_2d<f32>;
+@group(1) @binding(1) var t1: texture_multisampled_2d<u32>;
+@group(1) @binding(2) var<storage, read> t2: array<u32>;
+@group(1) @binding(3) var<storage, read> t3: array<u32>;
+@group(3) @binding(0) var u0: texture_storage_2d<rgba32float, write>;
+@group(3) @binding(1) var<storage, read_write> u1: array<atomic<u32>>;
+@group(3) @binding(2) var<storage, read_write> u2: array<u32>;
+var<workgroup> g0: array<u32, 16>;
+var<workgroup> g1: array<u32, 32>;
+
+var<private> icb: array<vec4<u32>, 2> = array<vec4<u32>, 2>(
+    vec4<u32>(1065353216u, 0u, 0u, 0u),
+    vec4<u32>(0u, 1065353216u, 0u, 0u),
+);
+
+var<private> dxbc_sample_positions: array<vec2<i32>, 31> = array<vec2<i32>, 31>(
+    vec2<i32>(0, 0),
+    vec2<i32>(4, 4), vec2<i32>(-4, -4),
+    vec2<i32>(-2, -6), vec2<i32>(6, -2), vec2<i32>(-6, 2), vec2<i32>(2, 6),
+    vec2<i32>(1, -3), vec2<i32>(-1, 3), vec2<i32>(5, 1), vec2<i32>(-3, -5),
+    vec2<i32>(-5, 5), vec2<i32>(-7, -1), vec2<i32>(3, 7), vec2<i32>(7, -7),
+    vec2<i32>(1, 1), vec2<i32>(-1, -3), vec2<i32>(-3, 2), vec2<i32>(4, -1),
+    vec2<i32>(-5, -2), vec2<i32>(2, 5), vec2<i32>(5, 3), vec2<i32>(3, -5),
+    vec2<i32>(-2, 6), vec2<i32>(0, -7), vec2<i32>(-4, -6), vec2<i32>(-6, 4),
+    vec2<i32>(-8, 0), vec2<i32>(7, -4), vec2<i32>(6, 7), vec2<i32>(-7, -8),
+);
+
+fn dxbc_sample_position(count: u32, index: u32) -> vec2<f32>
+{
+    if (index >= count || count > 16u || (count & (count - 1u)) != 0u) {
+        return vec2<f32>(0.0);
+    }
+    return vec2<f32>(dxbc_sample_positions[count - 1u + index]) / 16.0;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main()
+{
+    var r0: vec4<f32>;
+    var r1: vec4<f32>;
+    var r2: vec4<f32>;
+    var x0: array<vec4<f32>, 4>;
+
+    r0 = vec4<f32>(1.0, 2.0, -3.0, 0.0);
+    r0.x = bitcast<f32>(bitcast<i32>(r0.y) + -5i);
+    r1 = vec4<f32>(bitcast<vec2<f32>>(bitcast<vec2<u32>>(r0.xy) & vec2<u32>(65535u, 65535u)), r1.zw);
+    r0 = vec4<f32>(saturate(fma(-abs(r0.xyz), cb0[bitcast<i32>(r0.x) + 2].xyz, bitcast<vec3<f32>>(icb[bitcast<i32>(r1.y)].xyz))), r0.w);
+    x0[bitcast<i32>(r0.x) + 1] = r1.xyzw;
+    if (bitcast<u32>(r0.x) != 0u) {
+        r2 = textureSampleLevel(t0, s0, r0.xy, 0.0, vec2<i32>(1, -1)).xyzw;
+    } else {
+        r2 = textureLoad(t0, bitcast<vec2<i32>>(r0.xy), bitcast<i32>(r0.w)).xyzw;
+    }
+    loop {
+        if (bitcast<u32>(r0.w) == 0u) { break; }
+        r1 = bitcast<vec4<f32>>(vec4<u32>(textureDimensions(t0, 0u), 0u, textureNumLevels(t0)).xyzw);
+    }
+    r0.x = bitcast<f32>(t2[0u]);
+    r0.y = bitcast<f32>(t3[bitcast<u32>(r0.x) * 4u + 1u]);
+    atomicStore(&u1[0u], bitcast<u32>(r0.x));
+    r0.z = bitcast<f32>(atomicAdd(&u1[0u], 1u));
+    r1 = vec4<f32>(vec4<f32>(dxbc_sample_position(textureNumSamples(t1), bitcast<u32>(r0.x)), 0.0, 0.0).xy, r1.zw);
+    r1.z = bitcast<f32>(vec4<u32>(textureNumSamples(t1), 0u, 0u, 0u).x);
+    workgroupBarrier();
+    return;
+}
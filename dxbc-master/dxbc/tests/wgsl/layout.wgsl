@@ -0,0 +1,81 @@
+diagnostic(off, derivative_uniformity);
+
+struct Input
+{
+    @builtin(position) SV_Position0: vec4<f32>,
+    @location(1) NORMAL0: vec3<f32>,
+};
+
+struct Output
+{
+    @location(0) SV_Target0: vec4<f32>,
+};
+
+struct Globals_t
+{
+    transform: array<vec4<f32>, 4>, // float4x4 transform
+    tint: vec3<f32>,
+    alpha: f32,
+    flags: u32,
+};
+
+@group(0) @binding(0) var<uniform> Globals: Globals_t;
+@group(2) @binding(0) var S: sampler;
+@group(1) @binding(1) var T: texture_cube<f32>;
+
+override dxbc_sample_count: u32 = 1u;
+
+var<private> dxbc_sample_positions: array<vec2<i32>, 31> = array<vec2<i32>, 31>(
+    vec2<i32>(0, 0),
+    vec2<i32>(4, 4), vec2<i32>(-4, -4),
+    vec2<i32>(-2, -6), vec2<i32>(6, -2), vec2<i32>(-6, 2), vec2<i32>(2, 6),
+    vec2<i32>(1, -3), vec2<i32>(-1, 3), vec2<i32>(5, 1), vec2<i32>(-3, -5),
+    vec2<i32>(-5, 5), vec2<i32>(-7, -1), vec2<i32>(3, 7), vec2<i32>(7, -7),
+    vec2<i32>(1, 1), vec2<i32>(-1, -3), vec2<i32>(-3, 2), vec2<i32>(4, -1),
+    vec2<i32>(-5, -2), vec2<i32>(2, 5), vec2<i32>(5, 3), vec2<i32>(3, -5),
+    vec2<i32>(-2, 6), vec2<i32>(0, -7), vec2<i32>(-4, -6), vec2<i32>(-6, 4),
+    vec2<i32>(-8, 0), vec2<i32>(7, -4), vec2<i32>(6, 7), vec2<i32>(-7, -8),
+);
+
+fn dxbc_sample_position(count: u32, index: u32) -> vec2<f32>
+{
+    if (index >= count || count > 16u || (count & (count - 1u)) != 0u) {
+        return vec2<f32>(0.0);
+    }
+    return vec2<f32>(dxbc_sample_positions[count - 1u + index]) / 16.0;
+}
+
+@fragment
+fn main(input: Input) -> Output
+{
+    var v0: vec4<f32>;
+    var v1: vec4<f32>;
+    var o0: vec4<f32>;
+    var r0: vec4<f32>;
+    var r1: vec4<f32>;
+
+    v0 = vec4<f32>(input.SV_Position0.xyz, 1.0 / input.SV_Position0.w);
+    v1 = vec4<f32>(input.NORMAL0, v1.w);
+
+    r0.x = dot(v1.xyz, Globals.transform[0].xyz);
+    r0.y = dot(v1.xyz, Globals.transform[1].xyz);
+    r0.z = dot(v1.xyz, Globals.transform[2].xyz);
+    r0 = textureSample(T, S, r0.xyz).xyzw;
+    r0 = vec4<f32>(r0.xyz * Globals.tint.xyz, r0.w);
+    r1.x = bitcast<f32>(Globals.flags & 1u);
+    if (bitcast<u32>(r1.x) != 0u) {
+        o0 = vec4<f32>(r0.xyz, o0.w);
+        o0.w = Globals.alpha;
+        var output: Output;
+        output.SV_Target0 = o0.xyzw;
+        return output;
+    }
+    r1 = vec4<f32>(vec4<f32>(dxbc_sample_position(dxbc_sample_count, 1u), 0.0, 0.0).xy, r1.zw);
+    r1.z = vec4<f32>(f32(dxbc_sample_count), 0.0, 0.0, 0.0).x;
+    r0 = vec4<f32>(r0.xyz + r1.xyz, r0.w);
+    o0 = vec4<f32>(r0.xyz, o0.w);
+    o0.w = 1.0;
+    var output: Output;
+    output.SV_Target0 = o0.xyzw;
+    return output;
+}
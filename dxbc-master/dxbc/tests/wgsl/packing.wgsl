@@ -0,0 +1,59 @@
+diagnostic(off, derivative_uniformity);
+
+struct Input
+{
+    @location(0) TEXCOORD0: vec4<f32>,
+};
+
+struct Output
+{
+    @location(0) SV_Target0: vec4<f32>,
+};
+
+struct Globals_t
+{
+    scale: f32,
+    // float2 offset
+    offset_0: f32,
+    offset_1: f32,
+    bias: f32,
+    tint: vec3<f32>,
+    alpha: f32,
+    uv: vec2<f32>,
+    @size(8) _pad0: u32,
+    weights: array<vec4<f32>, 3>, // float weights[3]
+    // float last is in Globals.weights[2]
+    basis: array<vec4<f32>, 3>, // row_major float3x2 basis
+    // uint2 mask is in Globals.basis[2]
+    counts: vec4<i32>,
+};
+
+@group(0) @binding(0) var<uniform> Globals: Globals_t;
+
+@fragment
+fn main(input: Input) -> Output
+{
+    var v0: vec4<f32>;
+    var o0: vec4<f32>;
+    var r0: vec4<f32>;
+    var r1: vec4<f32>;
+
+    v0 = input.TEXCOORD0;
+
+    r0 = vec4<f32>(fma(v0.xy, vec2<f32>(Globals.scale), vec2<f32>(Globals.offset_0, Globals.offset_1)), r0.zw);
+    r0 = vec4<f32>(r0.xy + vec2<f32>(Globals.bias), r0.zw);
+    r0 = vec4<f32>(r0.xy + Globals.uv.xy, r0.zw);
+    r0.z = bitcast<f32>(u32(v0.z));
+    r0.x = r0.x * Globals.weights[bitcast<i32>(r0.z)].x;
+    r0.x = r0.x * Globals.weights[2].y;
+    r1.x = dot(r0.xy, Globals.basis[0].xy);
+    r1.y = dot(r0.xy, Globals.basis[1].xy);
+    r1.z = dot(r0.xy, Globals.basis[2].xy);
+    o0 = vec4<f32>(r1.xyz * Globals.tint.xyz, o0.w);
+    r1.x = bitcast<f32>(bitcast<u32>(Globals.basis[2].z) & bitcast<u32>(Globals.basis[2].w));
+    r1.y = f32(Globals.counts.w);
+    o0.w = select(r1.y, Globals.alpha, bitcast<u32>(r1.x) != 0u);
+    var output: Output;
+    output.SV_Target0 = o0.xyzw;
+    return output;
+}
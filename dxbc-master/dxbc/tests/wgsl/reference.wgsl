@@ -0,0 +1,23 @@
+struct Input
+{
+    @location(0) COLOR0: vec4<f32>,
+};
+
+struct Output
+{
+    @builtin(position) SV_Position0: vec4<f32>,
+};
+
+@vertex
+fn main(input: Input) -> Output
+{
+    var v0: vec4<f32>;
+    var o0: vec4<f32>;
+
+    v0 = input.COLOR0;
+
+    o0 = v0.xyzw + -abs(v0.xxyy);
+    var output: Output;
+    output.SV_Position0 = o0.xyzw;
+    return output;
+}
@@ -0,0 +1,44 @@
+diagnostic(off, derivative_uniformity);
+
+struct Input
+{
+    @location(0) COLOR0: vec4<f32>,
+    @location(1) UV0: vec2<f32>,
+};
+
+struct Output
+{
+    @location(0) SV_TARGET0: vec2<f32>,
+};
+
+struct CB_t
+{
+    A: array<vec4<f32>, 10>, // float4 A[10]
+};
+
+@group(0) @binding(0) var<uniform> CB: CB_t;
+@group(2) @binding(0) var S: sampler;
+@group(1) @binding(0) var T: texture_2d<f32>;
+
+@fragment
+fn main(input: Input) -> Output
+{
+    var v0: vec4<f32>;
+    var v1: vec4<f32>;
+    var o0: vec4<f32>;
+    var r0: vec4<f32>;
+
+    v0 = input.COLOR0;
+    v1 = vec4<f32>(input.UV0, v1.zw);
+
+    r0.x = trunc(v1.x);
+    r0.x = r0.x * v1.y;
+    r0.y = bitcast<f32>(i32(r0.x));
+    r0.x = trunc(r0.x);
+    r0.y = bitcast<f32>(bitcast<i32>(r0.y) + bitcast<i32>(r0.y));
+    r0 = vec4<f32>(r0.x, textureSampleLevel(T, S, CB.A[bitcast<i32>(r0.y) + 2].yx, 0.0).xy, r0.w);
+    o0 = vec4<f32>(r0.xx * r0.yz, o0.zw);
+    var output: Output;
+    output.SV_TARGET0 = o0.xy;
+    return output;
+}
@@ -0,0 +1,102 @@
+extern crate dxbc;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, IsTerminal, Read, Write};
+use std::process;
+
+const USAGE: &str = "\
+usage: dxbca [options] <file>
+
+Assembles a shader listing printed by `dxbcd --fxc` into a DXBC container,
+`-` reads from stdin. Listings of `dxbcd --asm` assemble into the same
+container they were printed from.
+
+options:
+    -o <file>          write the container to <file> instead of stdout
+    -h, --help         print this help";
+
+struct Options {
+    input: String,
+    output: Option<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut input = None;
+    let mut output = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-o" => {
+                output = Some(args.next().ok_or("missing file name after `-o`")?);
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option `{}`", arg));
+            }
+            _ if input.is_some() => {
+                return Err("more than one input file".to_owned());
+            }
+            _ => input = Some(arg),
+        }
+    }
+
+    let input = input.ok_or("no input file")?;
+    Ok(Options { input, output })
+}
+
+fn read_input(path: &str) -> io::Result<String> {
+    let mut text = String::new();
+
+    if path == "-" {
+        io::stdin().read_to_string(&mut text)?;
+    } else {
+        File::open(path)?.read_to_string(&mut text)?;
+    }
+
+    Ok(text)
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let source = match read_input(&options.input) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("error: cannot read `{}`: {}", options.input, err);
+            process::exit(1);
+        }
+    };
+
+    let module = match dxbc::asm::assemble(&source) {
+        Ok(module) => module,
+        Err(err) => {
+            eprintln!("error: {}: {}", options.input, err);
+            process::exit(1);
+        }
+    };
+
+    let result = match options.output {
+        Some(ref path) => File::create(path).and_then(|mut file| file.write_all(module.as_bytes())),
+        None if io::stdout().is_terminal() => {
+            eprintln!("error: refusing to write a binary to the terminal, use `-o <file>`");
+            process::exit(2);
+        }
+        None => io::stdout().write_all(module.as_bytes()),
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: cannot write the container: {}", err);
+        process::exit(1);
+    }
+}
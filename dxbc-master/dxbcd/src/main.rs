@@ -2,14 +2,92 @@ extern crate dxbc;
 extern crate term;
 
 use dxbc::dr::*;
-use dxbc::dr::shex::*;
 use dxbc::binary::*;
 
-use std::mem;
+use std::env;
+use std::fs::File;
+use std::io::{self, IsTerminal, Read, Write};
+use std::{mem, process};
+
+// Listings are rendered in memory, so the consumer's writes can't fail.
+type Listing = Box<dyn term::Terminal<Output = Vec<u8>>>;
+
+/// Terminal that drops all formatting, used for files, pipes and `--no-color`.
+struct PlainTerminal<T: Write> {
+    out: T,
+}
+
+impl<T: Write> Write for PlainTerminal<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl<T: Write> term::Terminal for PlainTerminal<T> {
+    type Output = T;
+
+    fn fg(&mut self, _color: term::color::Color) -> term::Result<()> { Ok(()) }
+    fn bg(&mut self, _color: term::color::Color) -> term::Result<()> { Ok(()) }
+    fn attr(&mut self, _attr: term::Attr) -> term::Result<()> { Ok(()) }
+    fn supports_attr(&self, _attr: term::Attr) -> bool { false }
+    fn reset(&mut self) -> term::Result<()> { Ok(()) }
+    fn supports_reset(&self) -> bool { false }
+    fn supports_color(&self) -> bool { false }
+    fn cursor_up(&mut self) -> term::Result<()> { Err(term::Error::NotSupported) }
+    fn delete_line(&mut self) -> term::Result<()> { Err(term::Error::NotSupported) }
+    fn carriage_return(&mut self) -> term::Result<()> { Err(term::Error::NotSupported) }
+    fn get_ref(&self) -> &T { &self.out }
+    fn get_mut(&mut self) -> &mut T { &mut self.out }
+    fn into_inner(self) -> T { self.out }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Chunk {
+    Rdef,
+    Isgn,
+    Osgn,
+    Isg1,
+    Osg1,
+    Osg5,
+    Pcsg,
+    Shex,
+}
+
+impl Chunk {
+    const ALL: [Chunk; 8] = [
+        Chunk::Rdef,
+        Chunk::Isgn,
+        Chunk::Osgn,
+        Chunk::Isg1,
+        Chunk::Osg1,
+        Chunk::Osg5,
+        Chunk::Pcsg,
+        Chunk::Shex,
+    ];
+
+    fn from_name(name: &str) -> Option<Chunk> {
+        match &*name.to_lowercase() {
+            "rdef" => Some(Chunk::Rdef),
+            "isgn" => Some(Chunk::Isgn),
+            "osgn" => Some(Chunk::Osgn),
+            "isg1" => Some(Chunk::Isg1),
+            "osg1" => Some(Chunk::Osg1),
+            "osg5" => Some(Chunk::Osg5),
+            "pcsg" => Some(Chunk::Pcsg),
+            "shex" | "shdr" => Some(Chunk::Shex),
+            _ => None,
+        }
+    }
+}
 
 struct DisasmConsumer {
-    out: Box<term::StdoutTerminal>,
+    out: Listing,
     indent: u32,
+    chunks: Vec<Chunk>,
 }
 
 const COMMENT_COLOR: term::color::Color = term::color::BRIGHT_BLACK;
@@ -29,20 +107,92 @@ fn get_name_token_name(mode: NameToken) -> &'static str {
         NameToken::InstanceId => "instance_id",
         NameToken::IsFrontFace => "is_front_face",
         NameToken::SampleIndex => "sampleIndex",
-        _ => "TODO"
+        NameToken::FinalQuadUEq0EdgeTessfactor => "finalQuadUeq0EdgeTessFactor",
+        NameToken::FinalQuadVEq0EdgeTessfactor => "finalQuadVeq0EdgeTessFactor",
+        NameToken::FinalQuadUEq1EdgeTessfactor => "finalQuadUeq1EdgeTessFactor",
+        NameToken::FinalQuadVEq1EdgeTessfactor => "finalQuadVeq1EdgeTessFactor",
+        NameToken::FinalQuadUInsideTessfactor => "finalQuadUInsideTessFactor",
+        NameToken::FinalQuadVInsideTessfactor => "finalQuadVInsideTessFactor",
+        NameToken::FinalTriUEq0EdgeTessfactor => "finalTriUeq0EdgeTessFactor",
+        NameToken::FinalTriVEq0EdgeTessfactor => "finalTriVeq0EdgeTessFactor",
+        NameToken::FinalTriWEq0EdgeTessfactor => "finalTriWeq0EdgeTessFactor",
+        NameToken::FinalTriinsidetessfactor => "finalTriInsideTessFactor",
+        NameToken::FinalLineDetailTessfactor => "finalLineDetailTessFactor",
+        NameToken::FinalLineDensityTessfactor => "finalLineDensityTessFactor",
+        NameToken::Barycentrics => "barycentrics",
+        NameToken::ShadingRate => "shadingrate",
+        NameToken::CullPrimitive => "cullprimitive",
+        NameToken::Other(_) => "unknown",
     }
 }
 
-fn get_interpolation_mode_name(mode: InterpolationMode) -> &'static str {
+fn get_interpolation_mode_name(mode: Option<InterpolationMode>) -> &'static str {
     match mode {
-        InterpolationMode::Undefined => "undefined",
-        InterpolationMode::Constant => "constant",
-        InterpolationMode::Linear => "linear",
-        InterpolationMode::LinearCentroid => "linear centroid",
-        InterpolationMode::LinearNoPerspective => "linear noperspective",
-        InterpolationMode::LinearNoPerspectiveCentroid => "linear noperspective centroid",
-        InterpolationMode::LinearSample => "linear sample",
-        InterpolationMode::LinearNoPerspectiveSample => "linear noperspective sample",
+        Some(InterpolationMode::Undefined) => "undefined",
+        Some(InterpolationMode::Constant) => "constant",
+        Some(InterpolationMode::Linear) => "linear",
+        Some(InterpolationMode::LinearCentroid) => "linear centroid",
+        Some(InterpolationMode::LinearNoPerspective) => "linear noperspective",
+        Some(InterpolationMode::LinearNoPerspectiveCentroid) => "linear noperspective centroid",
+        Some(InterpolationMode::LinearSample) => "linear sample",
+        Some(InterpolationMode::LinearNoPerspectiveSample) => "linear noperspective sample",
+        None => "unknown",
+    }
+}
+
+fn get_primitive_name(primitive: u32) -> String {
+    match primitive {
+        1 => "point".to_owned(),
+        2 => "line".to_owned(),
+        3 => "triangle".to_owned(),
+        6 => "lineadj".to_owned(),
+        7 => "triangleadj".to_owned(),
+        8..=39 => format!("patch{}", primitive - 7),
+        _ => "undefined".to_owned(),
+    }
+}
+
+fn get_topology_name(topology: u32) -> &'static str {
+    match topology {
+        1 => "pointlist",
+        2 => "linelist",
+        3 => "linestrip",
+        4 => "trianglelist",
+        5 => "trianglestrip",
+        10 => "linelist_adj",
+        11 => "linestrip_adj",
+        12 => "trianglelist_adj",
+        13 => "trianglestrip_adj",
+        _ => "undefined",
+    }
+}
+
+fn get_tess_domain_name(domain: u32) -> &'static str {
+    match domain {
+        1 => "domain_isoline",
+        2 => "domain_tri",
+        3 => "domain_quad",
+        _ => "domain_undefined",
+    }
+}
+
+fn get_tess_partitioning_name(partitioning: u32) -> &'static str {
+    match partitioning {
+        1 => "partitioning_integer",
+        2 => "partitioning_pow2",
+        3 => "partitioning_fractional_odd",
+        4 => "partitioning_fractional_even",
+        _ => "partitioning_undefined",
+    }
+}
+
+fn get_tess_output_primitive_name(primitive: u32) -> &'static str {
+    match primitive {
+        1 => "output_point",
+        2 => "output_line",
+        3 => "output_triangle_cw",
+        4 => "output_triangle_ccw",
+        _ => "output_undefined",
     }
 }
 
@@ -54,14 +204,65 @@ fn get_test_boolean_name(test: TestBoolean) -> &'static str {
 }
 
 impl DisasmConsumer {
-    fn new() -> Self {
+    fn new(out: Listing, chunks: Vec<Chunk>) -> Self {
         Self {
-            out: term::stdout().unwrap(),
+            out,
             indent: 0,
+            chunks,
+        }
+    }
+
+    fn prints(&self, chunk: Chunk) -> bool {
+        self.chunks.contains(&chunk)
+    }
+
+    fn write_signature(&mut self, chunk: Chunk, title: &str, signature: &dxbc::dr::IOsgnChunk) -> Action {
+        if !self.prints(chunk) {
+            return Action::Continue;
         }
+
+        self.out.fg(COMMENT_COLOR).unwrap();
+
+        writeln!(self.out, "//").unwrap();
+        writeln!(self.out, "// {}:", title).unwrap();
+        writeln!(self.out, "//").unwrap();
+        writeln!(self.out, "// Name                 Index   Mask Register SysValue  Format   Used").unwrap();
+        writeln!(self.out, "// -------------------- ----- ------ -------- -------- ------- ------").unwrap();
+
+        for elem in &signature.elements {
+            writeln!(
+                self.out,
+                "// {:20} {:5} {:6} {:8} {:8?} {:7} {:6}",
+                elem.name,
+                elem.semantic_index,
+                elem.component_mask,
+                elem.register,
+                elem.semantic_type,
+                match elem.component_type {
+                    RegisterComponentType::Unknown => "NONE",
+                    RegisterComponentType::Uint32 => "uint",
+                    RegisterComponentType::Int32 => "int",
+                    RegisterComponentType::Float32 => "float",
+                    RegisterComponentType::Uint16 => "uint16",
+                    RegisterComponentType::Int16 => "int16",
+                    RegisterComponentType::Float16 => "fp16",
+                    RegisterComponentType::Uint64 => "uint64",
+                    RegisterComponentType::Int64 => "int64",
+                    RegisterComponentType::Float64 => "double",
+                    RegisterComponentType::Other(_) => "NONE",
+                },
+                elem.rw_mask,
+            ).unwrap();
+        }
+        writeln!(self.out, "//").unwrap();
+        writeln!(self.out, "//").unwrap();
+
+        self.out.reset().unwrap();
+
+        Action::Continue
     }
 
-    fn begin_instruction<'a>(&mut self, opcode: OpcodeToken0<'a>, offset: u32, instruction: &str) {
+    fn begin_instruction<'a>(&mut self, _opcode: OpcodeToken0<'a>, offset: u32, instruction: &str) {
         self.out.fg(COMMENT_COLOR).unwrap();
         write!(self.out, "{:#08x}: ", offset).unwrap();
         self.out.reset().unwrap();
@@ -95,16 +296,28 @@ impl DisasmConsumer {
         self.end_instruction();
     }
 
-    fn write_resource_return_type<'a>(&mut self, opcode: OpcodeToken0<'a>, return_type: ResourceReturnTypeToken0<'a>) {
+    fn write_resource_return_type<'a>(&mut self, _opcode: OpcodeToken0<'a>, return_type: ResourceReturnTypeToken0<'a>) {
+
+        let name = |component| return_type.get_return_type(component)
+            .map_or("unknown".to_owned(), |ty| format!("{:?}", ty));
 
         write!(self.out, "(").unwrap();
-        write!(self.out, "{:?}, ", return_type.get_return_type(ComponentName::X)).unwrap();
-        write!(self.out, "{:?}, ", return_type.get_return_type(ComponentName::Y)).unwrap();
-        write!(self.out, "{:?}, ", return_type.get_return_type(ComponentName::Z)).unwrap();
-        write!(self.out, "{:?}", return_type.get_return_type(ComponentName::W)).unwrap();
+        write!(self.out, "{}, ", name(ComponentName::X)).unwrap();
+        write!(self.out, "{}, ", name(ComponentName::Y)).unwrap();
+        write!(self.out, "{}, ", name(ComponentName::Z)).unwrap();
+        write!(self.out, "{}", name(ComponentName::W)).unwrap();
         write!(self.out, ")").unwrap();
     }
 
+    fn write_uav_flags<'a>(&mut self, opcode: OpcodeToken0<'a>) {
+        if opcode.is_globally_coherent() {
+            write!(self.out, "_glc").unwrap();
+        }
+        if opcode.has_order_preserving_counter() {
+            write!(self.out, "_opc").unwrap();
+        }
+    }
+
     fn write_mask(&mut self, mask: ComponentMask) {
         if mask.contains(ComponentMask::COMPONENT_MASK_R) {
             write!(self.out, "x").unwrap();
@@ -126,18 +339,32 @@ impl DisasmConsumer {
             Immediate::U64(val) => { write!(self.out, "{}", val).unwrap(); },
             Immediate::Relative(operand) => {
                 self.write_operand(&operand);
+                write!(self.out, " + 0").unwrap();
             },
             Immediate::U32Relative(val, operand) => {
-                write!(self.out, "{} + ", val).unwrap();
                 self.write_operand(&operand);
+                write!(self.out, " + {}", val).unwrap();
             },
             Immediate::U64Relative(val, operand) => {
-                write!(self.out, "{} + ", val).unwrap();
                 self.write_operand(&operand);
+                write!(self.out, " + {}", val).unwrap();
             },
         }
     }
 
+    // the first index follows the register prefix unless it is relative,
+    // `o[r0.x + 0]`
+    fn write_first_immediate<'a>(&mut self, imm: Immediate<'a>) {
+        match imm {
+            Immediate::U32(_) | Immediate::U64(_) => self.write_immediate(imm),
+            _ => {
+                write!(self.out, "[").unwrap();
+                self.write_immediate(imm);
+                write!(self.out, "]").unwrap();
+            }
+        }
+    }
+
     fn write_operands<'a>(&mut self, operands: &[OperandToken0<'a>]) {
         let len = operands.len();
 
@@ -148,7 +375,7 @@ impl DisasmConsumer {
             }
         }
 
-        writeln!(self.out, "").unwrap();
+        writeln!(self.out).unwrap();
     }
 
     fn write_operand<'a>(&mut self, operand: &OperandToken0<'a>) {
@@ -188,16 +415,16 @@ impl DisasmConsumer {
 
         if let Some(operand) = operand.get_extended_operand() {
             match operand.get_operand_modifier() {
-                OperandModifier::None => {},
-                OperandModifier::Neg => {
+                Some(OperandModifier::Neg) => {
                     write!(self.out, "-").unwrap();
                 },
-                OperandModifier::Abs => {
+                Some(OperandModifier::Abs) => {
                     write!(self.out, "|").unwrap();
                 },
-                OperandModifier::AbsNeg => {
+                Some(OperandModifier::AbsNeg) => {
                     write!(self.out, "-|").unwrap();
                 },
+                Some(OperandModifier::None) | None => {},
             }
         }
 
@@ -208,6 +435,22 @@ impl DisasmConsumer {
             OperandType::Resource => "t",
             OperandType::Sampler => "s",
             OperandType::ConstantBuffer => "cb",
+            OperandType::Stream => "m",
+            OperandType::InputControlPoint => "vicp",
+            OperandType::OutputControlPoint => "vocp",
+            OperandType::InputPatchConstant => "vpc",
+            OperandType::InputDomainPoint => "vDomain",
+            OperandType::InputPrimitiveId => "vPrim",
+            OperandType::OutputControlPointId => "vOutputControlPointID",
+            OperandType::InputForkInstanceId => "vForkInstanceID",
+            OperandType::InputJoinInstanceId => "vJoinInstanceID",
+            OperandType::InputGsinstanceid => "vGSInstanceID",
+            OperandType::UnorderedAccessView => "u",
+            OperandType::ThreadGroupSharedMemory => "g",
+            OperandType::InputThreadId => "vThreadID",
+            OperandType::InputThreadGroupId => "vThreadGroupID",
+            OperandType::InputThreadIdInGroup => "vThreadIDInGroup",
+            OperandType::InputThreadIdInGroupFlattened => "vThreadIDInGroupFlattened",
 
             OperandType::Immediate32 | OperandType::Immediate64 => { return; }
             _ => ""
@@ -219,13 +462,17 @@ impl DisasmConsumer {
 
         match dim {
             IndexDimension::D1 => {
-                self.write_immediate(operand.get_immediate(0));
+                if let Some(imm) = operand.get_immediate(0) {
+                    self.write_first_immediate(imm);
+                }
             },
             IndexDimension::D2 => {
-                self.write_immediate(operand.get_immediate(0));
-                write!(self.out, "[").unwrap();
-                self.write_immediate(operand.get_immediate(1));
-                write!(self.out, "]").unwrap();
+                if let (Some(first), Some(second)) = (operand.get_immediate(0), operand.get_immediate(1)) {
+                    self.write_first_immediate(first);
+                    write!(self.out, "[").unwrap();
+                    self.write_immediate(second);
+                    write!(self.out, "]").unwrap();
+                }
             },
             _ => {}
         }
@@ -270,7 +517,7 @@ impl DisasmConsumer {
         }
 
         match operand.get_component_select_mode() {
-            ComponentSelectMode::Mask => {
+            Some(ComponentSelectMode::Mask) => {
                 let mask = operand.get_component_mask();
 
                 if !mask.is_empty() {
@@ -295,7 +542,7 @@ impl DisasmConsumer {
                 }
                 self.out.reset().unwrap();
             }
-            ComponentSelectMode::Swizzle => {
+            Some(ComponentSelectMode::Swizzle) => {
                 write!(self.out, ".").unwrap();
 
                 let swizzle = operand.get_component_swizzle();
@@ -305,21 +552,21 @@ impl DisasmConsumer {
                 write_swizzle_component(self, swizzle.2);
                 write_swizzle_component(self, swizzle.3);
             }
-            ComponentSelectMode::Select1 => {
+            Some(ComponentSelectMode::Select1) => {
                 write!(self.out, ".").unwrap();
 
                 let swizzle = operand.get_component_swizzle();
                 write_swizzle_component(self, swizzle.0);
             }
+            None => {}
         }
 
         if let Some(operand) = operand.get_extended_operand() {
             match operand.get_operand_modifier() {
-                OperandModifier::None => {},
-                OperandModifier::Neg => {},
-                OperandModifier::Abs | OperandModifier::AbsNeg => {
+                Some(OperandModifier::Abs) | Some(OperandModifier::AbsNeg) => {
                     write!(self.out, "|").unwrap();
                 },
+                _ => {},
             }
         }
     }
@@ -338,11 +585,15 @@ impl Consumer for DisasmConsumer {
         Action::Continue
     }
 
-    fn consume_header(&mut self, header: &dxbc::dr::DxbcHeader) -> Action {
+    fn consume_header(&mut self, _header: &dxbc::dr::DxbcHeader) -> Action {
         Action::Continue
     }
 
     fn consume_rdef(&mut self, rdef: &dxbc::dr::RdefChunk) -> Action {
+        if !self.prints(Chunk::Rdef) {
+            return Action::Continue;
+        }
+
         self.out.fg(COMMENT_COLOR).unwrap();
         writeln!(self.out, "//").unwrap();
         writeln!(self.out, "// Generated by {}", rdef.author).unwrap();
@@ -364,7 +615,7 @@ impl Consumer for DisasmConsumer {
         writeln!(self.out, "// Name                                 Type  Format         Dim      HLSL Bind  Count").unwrap();
         writeln!(self.out, "// ------------------------------ ---------- ------- ----------- -------------- ------").unwrap();
 
-        for bind in &rdef.resource_bindings {
+        for _bind in &rdef.resource_bindings {
             // writeln!(self.out, "// {:30} {:10}", bind.name, return_type, bind.).unwrap();
         }
         writeln!(self.out, "//").unwrap();
@@ -376,76 +627,30 @@ impl Consumer for DisasmConsumer {
     }
 
     fn consume_isgn(&mut self, isgn: &dxbc::dr::IOsgnChunk) -> Action {
-        self.out.fg(COMMENT_COLOR).unwrap();
-
-        writeln!(self.out, "//").unwrap();
-        writeln!(self.out, "// Input signature:").unwrap();
-        writeln!(self.out, "//").unwrap();
-        writeln!(self.out, "// Name                 Index   Mask Register SysValue  Format   Used").unwrap();
-        writeln!(self.out, "// -------------------- ----- ------ -------- -------- ------- ------").unwrap();
-
-        for elem in &isgn.elements {
-            writeln!(
-                self.out,
-                "// {:20} {:5} {:6} {:8} {:8?} {:7} {:6}",
-                elem.name,
-                elem.semantic_index,
-                elem.component_mask,
-                elem.register,
-                elem.semantic_type,
-                match elem.component_type {
-                    RegisterComponentType::Unknown => "NONE",
-                    RegisterComponentType::Uint32 => "uint",
-                    RegisterComponentType::Int32 => "int",
-                    RegisterComponentType::Float32 => "float",
-                },
-                elem.rw_mask,
-            ).unwrap();
-        }
-        writeln!(self.out, "//").unwrap();
-        writeln!(self.out, "//").unwrap();
-
-        self.out.reset().unwrap();
-
-        Action::Continue
+        self.write_signature(Chunk::Isgn, "Input signature", isgn)
     }
 
     fn consume_osgn(&mut self, osgn: &dxbc::dr::IOsgnChunk) -> Action {
-        self.out.fg(COMMENT_COLOR).unwrap();
+        self.write_signature(Chunk::Osgn, "Output signature", osgn)
+    }
 
-        writeln!(self.out, "//").unwrap();
-        writeln!(self.out, "// Output signature:").unwrap();
-        writeln!(self.out, "//").unwrap();
-        writeln!(self.out, "// Name                 Index   Mask Register SysValue  Format   Used").unwrap();
-        writeln!(self.out, "// -------------------- ----- ------ -------- -------- ------- ------").unwrap();
+    fn consume_isg1(&mut self, isg1: &dxbc::dr::IOsgnChunk) -> Action {
+        self.write_signature(Chunk::Isg1, "Input signature", isg1)
+    }
 
-        for elem in &osgn.elements {
-            writeln!(
-                self.out,
-                "// {:20} {:5} {:6} {:8} {:8?} {:7} {:6}",
-                elem.name,
-                elem.semantic_index,
-                elem.component_mask,
-                elem.register,
-                elem.semantic_type,
-                match elem.component_type {
-                    RegisterComponentType::Unknown => "NONE",
-                    RegisterComponentType::Uint32 => "uint",
-                    RegisterComponentType::Int32 => "int",
-                    RegisterComponentType::Float32 => "float",
-                },
-                elem.rw_mask,
-            ).unwrap();
-        }
-        writeln!(self.out, "//").unwrap();
-        writeln!(self.out, "//").unwrap();
+    fn consume_osg1(&mut self, osg1: &dxbc::dr::IOsgnChunk) -> Action {
+        self.write_signature(Chunk::Osg1, "Output signature", osg1)
+    }
 
-        self.out.reset().unwrap();
+    fn consume_osg5(&mut self, osg5: &dxbc::dr::IOsgnChunk) -> Action {
+        self.write_signature(Chunk::Osg5, "Output signature", osg5)
+    }
 
-        Action::Continue
+    fn consume_pcsg(&mut self, pcsg: &dxbc::dr::IOsgnChunk) -> Action {
+        self.write_signature(Chunk::Pcsg, "Patch Constant signature", pcsg)
     }
 
-    fn consume_shex(&mut self, osgn: &dxbc::dr::ShexHeader) -> Action {
+    fn consume_shex(&mut self, _osgn: &dxbc::dr::ShexHeader) -> Action {
 
         Action::Continue
     }
@@ -453,6 +658,10 @@ impl Consumer for DisasmConsumer {
     fn consume_instruction(&mut self, offset: u32, instruction: dxbc::dr::SparseInstruction) -> Action {
         use dxbc::dr::Operands::*;
 
+        if !self.prints(Chunk::Shex) {
+            return Action::Continue;
+        }
+
         let opcode = instruction.opcode;
 
         match instruction.operands {
@@ -460,22 +669,25 @@ impl Consumer for DisasmConsumer {
                 self.write_instruction(opcode, offset, "dcl_globalFlags");
 
                 if flags.is_refactoring_allowed() {
-                    write!(self.out, "{}", "refactoringAllowed").unwrap();
+                    write!(self.out, "refactoringAllowed").unwrap();
                 }
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
             DclInput(input) => {
                 self.write_instruction(opcode, offset, "dcl_input");
                 match input.operand.get_operand_type() {
 
-                    OperandType::Input => { write!(self.out, "v{}.", input.get_input_register()).unwrap(); }
+                    OperandType::Input if matches!(input.operand.get_index_dimension(), IndexDimension::D1) => {
+                        write!(self.out, "v{}.", input.get_input_register()).unwrap();
+                        self.write_mask(input.operand.get_component_mask());
+                    }
                     OperandType::InputCoverageMask => {
                         write!(self.out, "vCoverage").unwrap();
                     }
-                    _ => { write!(self.out, "TODO").unwrap(); }
+                    // control points, patch constants and phase system values
+                    _ => self.write_operand(&input.operand),
                 };
-                self.write_mask(input.operand.get_component_mask());
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
             DclInputPs(input) => {
                 self.write_instruction(opcode, offset, "dcl_input_ps");
@@ -491,7 +703,7 @@ impl Consumer for DisasmConsumer {
                     _ => { write!(self.out, "TODO").unwrap(); }
                 };
                 self.write_mask(input.operand.get_component_mask());
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
             DclInputPsSiv(input) => {
                 self.write_instruction(opcode, offset, "dcl_input_ps_siv");
@@ -508,7 +720,7 @@ impl Consumer for DisasmConsumer {
 
                 write!(self.out, " {}", get_name_token_name(input.get_system_name())).unwrap();
 
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
             DclInputPsSgv(input) => {
                 self.write_instruction(opcode, offset, "dcl_input_ps_sgv");
@@ -524,13 +736,13 @@ impl Consumer for DisasmConsumer {
                 self.write_mask(input.operand.get_component_mask());
 
                 write!(self.out, " {}", get_name_token_name(input.get_system_name())).unwrap();
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
             DclOutput(output) => {
                 self.write_instruction(opcode, offset, "dcl_output");
                 write!(self.out, "o{}.", output.get_output_register()).unwrap();
                 self.write_mask(output.operand.get_component_mask());
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
             DclConstantBuffer(cb) => {
                 self.write_instruction(opcode, offset, "dcl_constantbuffer");
@@ -540,11 +752,11 @@ impl Consumer for DisasmConsumer {
             DclResource(resource) => {
                 self.begin_instruction(opcode, offset, "dcl_resource");
                 write!(self.out, "{}", match opcode.get_resource_dimension() {
-                    ResourceDimension::Texture1D => "_texture1d",
-                    ResourceDimension::Texture2D => "_texture2d",
-                    ResourceDimension::Texture3D => "_texture3d",
-                    ResourceDimension::TextureCube => "_texturecube",
-                    ResourceDimension::Texture2DMS => "_texture2dms",
+                    Some(ResourceDimension::Texture1D) => "_texture1d",
+                    Some(ResourceDimension::Texture2D) => "_texture2d",
+                    Some(ResourceDimension::Texture3D) => "_texture3d",
+                    Some(ResourceDimension::TextureCube) => "_texturecube",
+                    Some(ResourceDimension::Texture2DMS) => "_texture2dms",
                     _ => "",
                 }).unwrap();
                 self.end_instruction();
@@ -558,10 +770,18 @@ impl Consumer for DisasmConsumer {
                 self.write_instruction(opcode, offset, "dcl_sampler");
 
                 write!(self.out, "s{}, ", sampler.get_register()).unwrap();
-                writeln!(self.out, "{:?}", opcode.get_sampler_mode()).unwrap();
+                match opcode.get_sampler_mode() {
+                    Some(mode) => writeln!(self.out, "{:?}", mode).unwrap(),
+                    None => writeln!(self.out, "unknown").unwrap(),
+                }
 
                 // TODO: mode
             }
+            DclIndexRange(range) => {
+                self.write_instruction(opcode, offset, "dcl_indexrange");
+                self.write_operand(&range.operand);
+                writeln!(self.out, " {}", range.register_count).unwrap();
+            }
             DclTemps(temps) => {
                 self.write_instruction(opcode, offset, "dcl_temps");
 
@@ -619,35 +839,35 @@ impl Consumer for DisasmConsumer {
                 self.indent += 1;
             }
             Else => {
-                self.indent -= 1;
+                self.indent = self.indent.saturating_sub(1);
                 self.write_instruction(opcode, offset, "else");
                 self.indent += 1;
 
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
             EndIf => {
-                self.indent -= 1;
+                self.indent = self.indent.saturating_sub(1);
                 self.write_instruction(opcode, offset, "endif");
 
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
 
             Loop => {
                 self.write_instruction(opcode, offset, "loop");
                 self.indent += 1;
 
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
             EndLoop => {
-                self.indent -= 1;
+                self.indent = self.indent.saturating_sub(1);
                 self.write_instruction(opcode, offset, "endloop");
 
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
             Break => {
                 self.write_instruction(opcode, offset, "break");
 
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
             }
             BreakC(breakc) => {
                 self.begin_instruction(opcode, offset, "breakc");
@@ -668,10 +888,272 @@ impl Consumer for DisasmConsumer {
             }
             Ret => {
                 self.write_instruction(opcode, offset, "ret");
-                writeln!(self.out, "").unwrap();
+                writeln!(self.out).unwrap();
+            }
+            DclThreadGroup(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_thread_group");
+                writeln!(self.out, "{}, {}, {}", dcl.x, dcl.y, dcl.z).unwrap();
+            }
+            DclThreadGroupSharedMemoryRaw(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_tgsm_raw");
+                self.write_operand(&dcl.operand);
+                writeln!(self.out, ", {}", dcl.byte_count).unwrap();
+            }
+            DclThreadGroupSharedMemoryStructured(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_tgsm_structured");
+                self.write_operand(&dcl.operand);
+                writeln!(self.out, ", {}, {}", dcl.stride, dcl.count).unwrap();
+            }
+            DclResourceRaw(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_resource_raw");
+                self.write_operands(&[dcl.operand]);
+            }
+            DclResourceStructured(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_resource_structured");
+                self.write_operand(&dcl.operand);
+                writeln!(self.out, ", {}", dcl.stride).unwrap();
+            }
+            DclUnorderedAccessViewTyped(dcl) => {
+                self.begin_instruction(opcode, offset, "dcl_uav_typed");
+                write!(self.out, "{}", match opcode.get_resource_dimension() {
+                    Some(ResourceDimension::Buffer) => "_buffer",
+                    Some(ResourceDimension::Texture1D) => "_texture1d",
+                    Some(ResourceDimension::Texture1DArray) => "_texture1darray",
+                    Some(ResourceDimension::Texture2D) => "_texture2d",
+                    Some(ResourceDimension::Texture2DArray) => "_texture2darray",
+                    Some(ResourceDimension::Texture3D) => "_texture3d",
+                    _ => "",
+                }).unwrap();
+                self.write_uav_flags(opcode);
+                self.end_instruction();
+
+                self.write_resource_return_type(opcode, dcl.return_type);
+                write!(self.out, " ").unwrap();
+                self.write_operands(&[dcl.operand]);
+            }
+            DclUnorderedAccessViewRaw(dcl) => {
+                self.begin_instruction(opcode, offset, "dcl_uav_raw");
+                self.write_uav_flags(opcode);
+                self.end_instruction();
+                self.write_operands(&[dcl.operand]);
+            }
+            DclUnorderedAccessViewStructured(dcl) => {
+                self.begin_instruction(opcode, offset, "dcl_uav_structured");
+                self.write_uav_flags(opcode);
+                self.end_instruction();
+                self.write_operand(&dcl.operand);
+                writeln!(self.out, ", {}", dcl.stride).unwrap();
+            }
+            LdRaw(ld) => {
+                self.write_instruction(opcode, offset, "ld_raw");
+                self.write_operands(&[ld.dst, ld.src_byte_offset, ld.src]);
+            }
+            LdStructured(ld) => {
+                self.write_instruction(opcode, offset, "ld_structured");
+                self.write_operands(&[ld.dst, ld.src_index, ld.src_byte_offset, ld.src]);
+            }
+            LdUavTyped(ld) => {
+                self.write_instruction(opcode, offset, "ld_uav_typed");
+                self.write_operands(&[ld.dst, ld.src_address, ld.src_uav]);
+            }
+            StoreRaw(store) => {
+                self.write_instruction(opcode, offset, "store_raw");
+                self.write_operands(&[store.dst, store.src_byte_offset, store.src_value]);
+            }
+            StoreStructured(store) => {
+                self.write_instruction(opcode, offset, "store_structured");
+                self.write_operands(&[store.dst, store.src_index, store.src_byte_offset, store.src_value]);
+            }
+            StoreUavTyped(store) => {
+                self.write_instruction(opcode, offset, "store_uav_typed");
+                self.write_operands(&[store.dst_uav, store.src_address, store.src_value]);
+            }
+            AtomicAnd(atomic) => {
+                self.write_instruction(opcode, offset, "atomic_and");
+                self.write_operands(&[atomic.dst, atomic.src_address, atomic.src_value]);
+            }
+            AtomicOr(atomic) => {
+                self.write_instruction(opcode, offset, "atomic_or");
+                self.write_operands(&[atomic.dst, atomic.src_address, atomic.src_value]);
+            }
+            AtomicXor(atomic) => {
+                self.write_instruction(opcode, offset, "atomic_xor");
+                self.write_operands(&[atomic.dst, atomic.src_address, atomic.src_value]);
+            }
+            AtomicCmpStore(atomic) => {
+                self.write_instruction(opcode, offset, "atomic_cmp_store");
+                self.write_operands(&[atomic.dst, atomic.src_address, atomic.src_compare, atomic.src_value]);
+            }
+            AtomicIadd(atomic) => {
+                self.write_instruction(opcode, offset, "atomic_iadd");
+                self.write_operands(&[atomic.dst, atomic.src_address, atomic.src_value]);
+            }
+            AtomicImax(atomic) => {
+                self.write_instruction(opcode, offset, "atomic_imax");
+                self.write_operands(&[atomic.dst, atomic.src_address, atomic.src_value]);
+            }
+            AtomicImin(atomic) => {
+                self.write_instruction(opcode, offset, "atomic_imin");
+                self.write_operands(&[atomic.dst, atomic.src_address, atomic.src_value]);
+            }
+            AtomicUmax(atomic) => {
+                self.write_instruction(opcode, offset, "atomic_umax");
+                self.write_operands(&[atomic.dst, atomic.src_address, atomic.src_value]);
+            }
+            AtomicUmin(atomic) => {
+                self.write_instruction(opcode, offset, "atomic_umin");
+                self.write_operands(&[atomic.dst, atomic.src_address, atomic.src_value]);
+            }
+            ImmAtomicAlloc(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_alloc");
+                self.write_operands(&[atomic.dst, atomic.src_uav]);
+            }
+            ImmAtomicConsume(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_consume");
+                self.write_operands(&[atomic.dst, atomic.src_uav]);
+            }
+            ImmAtomicIadd(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_iadd");
+                self.write_operands(&[atomic.dst, atomic.dst_uav, atomic.src_address, atomic.src_value]);
+            }
+            ImmAtomicAnd(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_and");
+                self.write_operands(&[atomic.dst, atomic.dst_uav, atomic.src_address, atomic.src_value]);
+            }
+            ImmAtomicOr(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_or");
+                self.write_operands(&[atomic.dst, atomic.dst_uav, atomic.src_address, atomic.src_value]);
+            }
+            ImmAtomicXor(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_xor");
+                self.write_operands(&[atomic.dst, atomic.dst_uav, atomic.src_address, atomic.src_value]);
+            }
+            ImmAtomicExch(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_exch");
+                self.write_operands(&[atomic.dst, atomic.dst_uav, atomic.src_address, atomic.src_value]);
+            }
+            ImmAtomicCmpExch(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_cmp_exch");
+                self.write_operands(&[atomic.dst, atomic.dst_uav, atomic.src_address, atomic.src_compare, atomic.src_value]);
+            }
+            ImmAtomicImax(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_imax");
+                self.write_operands(&[atomic.dst, atomic.dst_uav, atomic.src_address, atomic.src_value]);
+            }
+            ImmAtomicImin(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_imin");
+                self.write_operands(&[atomic.dst, atomic.dst_uav, atomic.src_address, atomic.src_value]);
+            }
+            ImmAtomicUmax(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_umax");
+                self.write_operands(&[atomic.dst, atomic.dst_uav, atomic.src_address, atomic.src_value]);
+            }
+            ImmAtomicUmin(atomic) => {
+                self.write_instruction(opcode, offset, "imm_atomic_umin");
+                self.write_operands(&[atomic.dst, atomic.dst_uav, atomic.src_address, atomic.src_value]);
+            }
+            Sync(sync) => {
+                self.begin_instruction(opcode, offset, "sync");
+                if sync.is_unordered_access_view_memory_global() {
+                    write!(self.out, "_uglobal").unwrap();
+                }
+                if sync.is_unordered_access_view_memory_group() {
+                    write!(self.out, "_ugroup").unwrap();
+                }
+                if sync.is_thread_group_shared_memory() {
+                    write!(self.out, "_g").unwrap();
+                }
+                if sync.is_threads_in_group() {
+                    write!(self.out, "_t").unwrap();
+                }
+                self.end_instruction();
+                writeln!(self.out).unwrap();
+            }
+            HsDecls | HsControlPointPhase | HsForkPhase | HsJoinPhase => {
+                let phase = match instruction.operands {
+                    HsDecls => "hs_decls",
+                    HsControlPointPhase => "hs_control_point_phase",
+                    HsForkPhase => "hs_fork_phase",
+                    _ => "hs_join_phase",
+                };
+                self.write_instruction(opcode, offset, phase);
+                writeln!(self.out).unwrap();
+            }
+            DclInputControlPointCount(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_input_control_point_count");
+                writeln!(self.out, "{}", dcl.count).unwrap();
+            }
+            DclOutputControlPointCount(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_output_control_point_count");
+                writeln!(self.out, "{}", dcl.count).unwrap();
+            }
+            DclTessDomain(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_tessellator_domain");
+                writeln!(self.out, "{}", get_tess_domain_name(dcl.domain)).unwrap();
+            }
+            DclTessPartitioning(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_tessellator_partitioning");
+                writeln!(self.out, "{}", get_tess_partitioning_name(dcl.partitioning)).unwrap();
+            }
+            DclTessOutputPrimitive(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_tessellator_output_primitive");
+                writeln!(self.out, "{}", get_tess_output_primitive_name(dcl.primitive)).unwrap();
+            }
+            DclHsMaxTessfactor(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_hs_max_tessfactor");
+                writeln!(self.out, "l({:.6})", dcl.max_tessfactor).unwrap();
+            }
+            DclHsForkPhaseInstanceCount(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_hs_fork_phase_instance_count");
+                writeln!(self.out, "{}", dcl.instance_count).unwrap();
+            }
+            DclHsJoinPhaseInstanceCount(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_hs_join_phase_instance_count");
+                writeln!(self.out, "{}", dcl.instance_count).unwrap();
+            }
+            DclGsInputPrimitive(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_inputprimitive");
+                writeln!(self.out, "{}", get_primitive_name(dcl.primitive)).unwrap();
+            }
+            DclGsOutputPrimitiveTopology(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_outputtopology");
+                writeln!(self.out, "{}", get_topology_name(dcl.primitive_topology)).unwrap();
+            }
+            DclMaxOutputVertexCount(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_maxout");
+                writeln!(self.out, "{}", dcl.count).unwrap();
+            }
+            DclGsInstanceCount(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_gsinstances");
+                writeln!(self.out, "{}", dcl.instance_count).unwrap();
+            }
+            DclStream(dcl) => {
+                self.write_instruction(opcode, offset, "dcl_stream");
+                self.write_operands(&[dcl.operand]);
+            }
+            EmitStream(emit) => {
+                self.write_instruction(opcode, offset, "emit_stream");
+                self.write_operands(&[emit.stream]);
+            }
+            CutStream(cut) => {
+                self.write_instruction(opcode, offset, "cut_stream");
+                self.write_operands(&[cut.stream]);
+            }
+            EmitThenCutStream(emit) => {
+                self.write_instruction(opcode, offset, "emitThenCut_stream");
+                self.write_operands(&[emit.stream]);
+            }
+            Emit | Cut | EmitThenCut => {
+                let name = match instruction.operands {
+                    Emit => "emit",
+                    Cut => "cut",
+                    _ => "emitThenCut",
+                };
+                self.write_instruction(opcode, offset, name);
+                writeln!(self.out).unwrap();
             }
             _ => {
-                println!("  {:?}", instruction);
+                writeln!(self.out, "  {:?}", instruction).unwrap();
             }
         }
 
@@ -681,15 +1163,229 @@ impl Consumer for DisasmConsumer {
     }
 }
 
+const USAGE: &str = "\
+usage: dxbcd [options] <file>...
+
+Disassembles DXBC shader binaries, `-` reads from stdin.
+
+options:
+    -o <file>          write the disassembly to <file> instead of stdout
+    --no-color         disable colored output
+    --chunks <list>    comma separated chunks to print
+                       (rdef,isgn,osgn,isg1,osg1,osg5,pcsg,shex)
+    --fxc              print the listing in the format of the Microsoft compiler
+    --asm              print the --fxc listing with what dxbca needs to
+                       assemble the same container
+    --cfg              print the control flow graph in Graphviz DOT format
+    --hlsl             print HLSL source that compiles back to the shader
+    --msl              print Metal Shading Language source for the shader
+    --wgsl             print WGSL source for the shader
+    -h, --help         print this help";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    Disassemble,
+    Fxc,
+    Asm,
+    Cfg,
+    Hlsl,
+    Msl,
+    Wgsl,
+}
+
+struct Options {
+    inputs: Vec<String>,
+    output: Option<String>,
+    color: bool,
+    chunks: Vec<Chunk>,
+    mode: Mode,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        inputs: Vec::new(),
+        output: None,
+        color: true,
+        chunks: Chunk::ALL.to_vec(),
+        mode: Mode::Disassemble,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-o" => {
+                options.output = Some(args.next().ok_or("missing file name after `-o`")?);
+            }
+            "--no-color" => {
+                options.color = false;
+            }
+            "--fxc" => {
+                options.mode = Mode::Fxc;
+            }
+            "--asm" => {
+                options.mode = Mode::Asm;
+            }
+            "--cfg" => {
+                options.mode = Mode::Cfg;
+            }
+            "--hlsl" => {
+                options.mode = Mode::Hlsl;
+            }
+            "--msl" => {
+                options.mode = Mode::Msl;
+            }
+            "--wgsl" => {
+                options.mode = Mode::Wgsl;
+            }
+            "--chunks" => {
+                let list = args.next().ok_or("missing chunk list after `--chunks`")?;
+                options.chunks = list
+                    .split(',')
+                    .filter(|name| !name.is_empty())
+                    .map(|name| Chunk::from_name(name).ok_or(format!("unknown chunk `{}`", name)))
+                    .collect::<Result<_, _>>()?;
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option `{}`", arg));
+            }
+            _ => options.inputs.push(arg),
+        }
+    }
+
+    if options.inputs.is_empty() {
+        return Err("no input files".to_owned());
+    }
+
+    Ok(options)
+}
+
+fn read_input(path: &str) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    if path == "-" {
+        io::stdin().read_to_end(&mut bytes)?;
+    } else {
+        File::open(path)?.read_to_end(&mut bytes)?;
+    }
+
+    Ok(bytes)
+}
+
+fn open_output(options: &Options) -> io::Result<Box<dyn Write>> {
+    Ok(match options.output {
+        Some(ref path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    })
+}
+
+// Colors are only used when writing to a terminal.
+fn listing(options: &Options) -> Listing {
+    let color = options.color && options.output.is_none() && io::stdout().is_terminal();
+    if color {
+        if let Some(terminal) = term::TerminfoTerminal::new(Vec::new()) {
+            return Box::new(terminal);
+        }
+    }
+
+    // no terminfo entry, fall back to plain output
+    Box::new(PlainTerminal { out: Vec::new() })
+}
+
+// The listing is kept up to a parse error.
+fn disassemble(options: &Options, bytes: &[u8]) -> (Vec<u8>, Result<(), State>) {
+    let mut consumer = DisasmConsumer::new(listing(options), options.chunks.clone());
+    let result = Parser::new(bytes, &mut consumer).parse();
+    let _ = consumer.out.reset();
+
+    (mem::take(consumer.out.get_mut()), result)
+}
+
+fn control_flow_graph(bytes: &[u8]) -> Result<String, String> {
+    let program = dxbc::ir::Program::parse(bytes)
+        .map_err(|state| state.to_string())?
+        .ok_or("no shader code")?;
+    let cfg = dxbc::cfg::Cfg::build(&program).map_err(|err| err.to_string())?;
+
+    Ok(cfg.to_dot(&program))
+}
+
+// Writes what the mode prints for one file, `Ok(false)` when the file can't
+// be translated.
+fn write_file(out: &mut dyn Write, options: &Options, path: &str, bytes: &[u8]) -> io::Result<bool> {
+    if options.inputs.len() > 1 {
+        writeln!(out, "// {}", path)?;
+    }
+
+    let parsed = |result: Result<String, State>| result.map_err(|state| format!("cannot parse `{}`: {}", path, state));
+
+    let result = match options.mode {
+        Mode::Disassemble => {
+            let (listing, result) = disassemble(options, bytes);
+            out.write_all(&listing)?;
+            parsed(result.map(|_| String::new()))
+        }
+        Mode::Fxc => parsed(dxbc::disasm::disassemble(bytes)),
+        Mode::Asm => parsed(dxbc::disasm::disassemble_lossless(bytes)),
+        Mode::Cfg => control_flow_graph(bytes)
+            .map_err(|err| format!("cannot build the control flow graph of `{}`: {}", path, err)),
+        Mode::Hlsl => parsed(dxbc::hlsl::translate(bytes)),
+        Mode::Msl => parsed(dxbc::msl::translate(bytes)),
+        Mode::Wgsl => parsed(dxbc::wgsl::translate(bytes)),
+    };
+
+    match result {
+        Ok(ref source) => out.write_all(source.as_bytes())?,
+        Err(ref err) => eprintln!("error: {}", err),
+    }
+    out.flush()?;
+
+    Ok(result.is_ok())
+}
+
 fn main() {
-    let mut shader_bytes = include_bytes!("..\\assembled.dxbc");
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
 
-    let start = 0x4;
-    println!("Real Checksum: {:?}", unsafe { ::std::slice::from_raw_parts(&shader_bytes[start..(start+16)] as *const _ as *const u32, 4) });
-    println!("???? Checksum: {:?}", dxbc::checksum(shader_bytes));
+    let mut out = match open_output(&options) {
+        Ok(out) => out,
+        Err(err) => {
+            eprintln!("error: cannot open `{}`: {}", options.output.as_ref().unwrap(), err);
+            process::exit(1);
+        }
+    };
+
+    let mut failed = false;
+    for path in &options.inputs {
+        let bytes = match read_input(path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                eprintln!("error: cannot read `{}`: {}", path, err);
+                failed = true;
+                continue;
+            }
+        };
 
-    let mut consumer = DisasmConsumer::new();
-    let mut parser = Parser::new(shader_bytes, &mut consumer);
+        match write_file(&mut *out, &options, path, &bytes) {
+            Ok(written) => failed |= !written,
+            // the reader went away, like `head` does
+            Err(ref err) if err.kind() == io::ErrorKind::BrokenPipe => break,
+            Err(err) => {
+                eprintln!("error: cannot write the output: {}", err);
+                process::exit(1);
+            }
+        }
+    }
 
-    parser.parse().unwrap();
+    if failed {
+        process::exit(1);
+    }
 }
@@ -0,0 +1,32 @@
+//! Numbers of the GLSL.std.450 extended instructions.
+
+pub const GLSL_ROUND_EVEN: u32 = 2;
+pub const GLSL_TRUNC: u32 = 3;
+pub const GLSL_FABS: u32 = 4;
+pub const GLSL_SABS: u32 = 5;
+pub const GLSL_FLOOR: u32 = 8;
+pub const GLSL_CEIL: u32 = 9;
+pub const GLSL_FRACT: u32 = 10;
+pub const GLSL_SIN: u32 = 13;
+pub const GLSL_COS: u32 = 14;
+pub const GLSL_POW: u32 = 26;
+pub const GLSL_EXP2: u32 = 29;
+pub const GLSL_LOG2: u32 = 30;
+pub const GLSL_SQRT: u32 = 31;
+pub const GLSL_INVERSE_SQRT: u32 = 32;
+pub const GLSL_FMIN: u32 = 37;
+pub const GLSL_UMIN: u32 = 38;
+pub const GLSL_SMIN: u32 = 39;
+pub const GLSL_FMAX: u32 = 40;
+pub const GLSL_UMAX: u32 = 41;
+pub const GLSL_SMAX: u32 = 42;
+pub const GLSL_FCLAMP: u32 = 43;
+pub const GLSL_FMIX: u32 = 46;
+pub const GLSL_FMA: u32 = 50;
+pub const GLSL_PACK_HALF_2X16: u32 = 58;
+pub const GLSL_UNPACK_HALF_2X16: u32 = 62;
+pub const GLSL_LENGTH: u32 = 66;
+pub const GLSL_NORMALIZE: u32 = 69;
+pub const GLSL_FIND_I_LSB: u32 = 73;
+pub const GLSL_FIND_S_MSB: u32 = 74;
+pub const GLSL_FIND_U_MSB: u32 = 75;
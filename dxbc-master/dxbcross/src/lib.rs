@@ -9,8 +9,50 @@ use rspirv::mr;
 use rspirv::sr;
 
 use dxbc::dr;
+use dxbc::dr::builder::{BuildError, ComponentMode, Modifier, NumComponent, Operand};
 
-use std::slice;
+use std::collections::HashMap;
+use std::{error, fmt};
+
+mod glsl_std450;
+mod loader;
+mod translate;
+pub mod to_spirv;
+
+use translate::{FunctionTranslator, Location, Pointer as IoPointer};
+
+/// Why an entry point can't be translated.
+#[derive(Debug)]
+pub enum Error {
+    /// The module has no entry point of the given name.
+    MissingEntryPoint(String),
+    /// The module breaks a rule of SPIR-V the translation relies on.
+    Invalid(String),
+    /// Types, instructions and control flow without a translation.
+    Unsupported(String),
+    Build(BuildError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::MissingEntryPoint(ref name) => write!(f, "no entry point named `{}`", name),
+            Error::Invalid(ref what) => write!(f, "invalid module: {}", what),
+            Error::Unsupported(ref what) => write!(f, "unsupported {}", what),
+            Error::Build(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+fn unsupported<T>(what: &str) -> Result<T, Error> {
+    Err(Error::Unsupported(what.to_owned()))
+}
+
+fn invalid<T>(what: &str) -> Result<T, Error> {
+    Err(Error::Invalid(what.to_owned()))
+}
 
 #[derive(Debug, Copy, Clone)]
 pub enum TargetVersion {
@@ -162,6 +204,9 @@ impl Metadata {
                 spirv::Op::TypeVoid => {
                     types[result_id as usize] = Some(Ty::Void);
                 }
+                spirv::Op::TypeBool => {
+                    types[result_id as usize] = Some(Ty::Bool);
+                }
                 spirv::Op::TypeInt => {
                     // TODO: sign
                     types[result_id as usize] = Some(Ty::Integer(Integer::Int32));
@@ -319,17 +364,17 @@ pub struct SpirvModule {
 }
 
 impl SpirvModule {
-    fn conv_variable(&self, ty: &Ty, type_id: u32) -> dr::InputOutputElement {
-        let (component_type, component_count) = match ty {
-            Ty::Vector(Vector { ty, count }) => {
-                match ty {
+    fn conv_variable(&self, ty: &Ty, type_id: u32) -> Result<dr::InputOutputElement, Error> {
+        let (component_type, &component_count) = match ty {
+            Ty::Vector(Vector { ty: scalar, count }) => {
+                match scalar {
                     Scalar::Numerical(Numerical::Integer(Integer::Uint32)) => (dr::RegisterComponentType::Uint32, count),
                     Scalar::Numerical(Numerical::Integer(Integer::Int32)) => (dr::RegisterComponentType::Int32, count),
                     Scalar::Numerical(Numerical::Float(Float::Float32)) => (dr::RegisterComponentType::Float32, count),
-                    _ => unimplemented!()
+                    _ => return unsupported(&format!("interface variables of type {:?}", ty)),
                 }
             }
-            _ => unimplemented!()
+            _ => return unsupported(&format!("interface variables of type {:?}", ty)),
         };
 
         let mut elem = dr::InputOutputElement {
@@ -338,8 +383,10 @@ impl SpirvModule {
             semantic_type: dr::SemanticName::Undefined,
             component_type,
             register: 0,
-            component_mask: 0,
+            component_mask: (1 << component_count) - 1,
             rw_mask: 0,
+            stream: 0,
+            min_precision: dr::ElementPrecision::Default,
         };
 
         for decoration in self.meta.get_decorations(type_id) {
@@ -347,6 +394,7 @@ impl SpirvModule {
                 sr::Decoration::BuiltIn(builtin) => {
                     match builtin {
                         spirv::BuiltIn::Position => {
+                            elem.name = String::from("SV_Position");
                             elem.semantic_type = dr::SemanticName::Position;
                         }
                         // TODO:
@@ -354,8 +402,8 @@ impl SpirvModule {
                     }
                 },
                 &sr::Decoration::Location(location) => {
-                    // TODO:
                     elem.semantic_index = location;
+                    elem.register = location;
                 }
                 _ => {}
             }
@@ -363,12 +411,12 @@ impl SpirvModule {
 
         println!("{:#?}", elem);
 
-        elem
+        Ok(elem)
     }
 
     // TODO: result
     pub fn from_bytes(bytes: &[u8]) -> Self {
-        let mut loader = rspirv::mr::Loader::new();
+        let mut loader = loader::Loader::new();
         rspirv::binary::parse_bytes(bytes, &mut loader).unwrap();
 
         let module = loader.module();
@@ -401,103 +449,160 @@ impl SpirvModule {
         })
     }
 
-    fn add_io_element(&self, inputs: &mut Vec<dr::InputOutputElement>, outputs: &mut Vec<dr::InputOutputElement>, variable_id: u32) {
-        for decl in &self.module.types_global_values {
-            if decl.class.opcode == spirv::Op::Variable {
-                let id = if let Some(id) = decl.result_id {
-                    id
-                } else {
-                    unimplemented!()
-                };
-
-                if variable_id != id { continue; }
-
-                let ty_id = if let Some(id) = decl.result_type {
-                    id
-                } else {
-                    unimplemented!()
-                };
-                let ty = self.meta.get_type(ty_id).unwrap();
+    fn io_variable(&self, variable_id: u32) -> Result<Option<(spirv::StorageClass, dr::InputOutputElement)>, Error> {
+        let decl = match self.module.types_global_values.iter().find(|decl| {
+            decl.class.opcode == spirv::Op::Variable && decl.result_id == Some(variable_id)
+        }) {
+            Some(decl) => decl,
+            None => return Ok(None),
+        };
 
-                if let Ty::Pointer(Pointer { storage_class, ty }) = &ty {
-                    let elem = self.conv_variable(&ty, id);
+        let ty_id = match decl.result_type {
+            Some(id) => id,
+            None => return invalid(&format!("variable {} without a type", variable_id)),
+        };
 
-                    match storage_class {
-                        spirv::StorageClass::Input => { inputs.push(elem); }
-                        spirv::StorageClass::Output => { outputs.push(elem); }
-                        _ => {}
-                    }
-                }
-            }
+        match self.meta.get_type(ty_id) {
+            Some(Ty::Pointer(Pointer { storage_class, ty })) => Ok(Some((*storage_class, self.conv_variable(&ty, variable_id)?))),
+            _ => Ok(None),
         }
     }
 
-    fn get_iosgn(&self, entrypoint: &mr::Instruction, globals: &[mr::Instruction], decorations: &[mr::Instruction]) -> (dr::IOsgnChunk, dr::IOsgnChunk) {
+    fn has_location(&self, variable_id: u32) -> bool {
+        self.meta.get_decorations(variable_id).iter().any(|decoration| match decoration {
+            sr::Decoration::Location(_) => true,
+            _ => false,
+        })
+    }
+
+    fn get_iosgn(&self, entrypoint: &mr::Instruction) -> Result<(dr::IOsgnChunk, dr::IOsgnChunk, HashMap<u32, IoPointer>), Error> {
         let mut isgn = dr::IOsgnChunk {
             elements: Vec::new()
         };
         let mut osgn = dr::IOsgnChunk {
             elements: Vec::new()
         };
+        let mut io = HashMap::new();
 
-        // go through all inputs/outputs of the entrypoint and append to our
-        // chunks
+        let mut variables = Vec::new();
         for operand in &entrypoint.operands[3..] {
-            match operand {
-                &mr::Operand::IdRef(id) => {
-                    self.add_io_element(
-                        &mut isgn.elements,
-                        &mut osgn.elements,
-                        id
-                    );
+            if let &mr::Operand::IdRef(id) = operand {
+                if let Some((class, elem)) = self.io_variable(id)? {
+                    variables.push((id, class, elem));
+                }
+            }
+        }
+
+        // variables keep the register of their location, the builtins get
+        // one each after the highest location
+        let first_free = |class| variables.iter()
+            .filter(|&&(id, variable_class, _)| variable_class == class && self.has_location(id))
+            .map(|&(_, _, ref elem)| elem.register + 1)
+            .max()
+            .unwrap_or(0);
+        let mut next_input = first_free(spirv::StorageClass::Input);
+        let mut next_output = first_free(spirv::StorageClass::Output);
+
+        for (id, class, mut elem) in variables {
+            let located = self.has_location(id);
+            let count = elem.component_mask.count_ones();
+
+            match class {
+                spirv::StorageClass::Input => {
+                    if !located {
+                        elem.register = next_input;
+                        next_input += 1;
+                    }
+                    elem.rw_mask = elem.component_mask;
+                    io.insert(id, IoPointer { location: Location::Input(elem.register), component: None, count });
+                    isgn.elements.push(elem);
+                }
+                spirv::StorageClass::Output => {
+                    if !located {
+                        elem.register = next_output;
+                        next_output += 1;
+                    }
+                    io.insert(id, IoPointer { location: Location::Output(elem.register), component: None, count });
+                    osgn.elements.push(elem);
                 }
                 _ => {}
             }
         }
 
-        (isgn, osgn)
+        isgn.elements.sort_by_key(|elem| elem.register);
+        osgn.elements.sort_by_key(|elem| elem.register);
+
+        Ok((isgn, osgn, io))
     }
 
-    pub fn translate_entrypoint(&self, entrypoint: &str, target: TargetVersion) -> Vec<u32> {
-        let entrypoint = self.module.entry_points.iter().find(|e| {
+    pub fn translate_entrypoint(&self, entrypoint: &str, target: TargetVersion) -> Result<Vec<u32>, Error> {
+        let entrypoint = match self.module.entry_points.iter().find(|e| {
             if let mr::Operand::LiteralString(ref name) = e.operands[2] {
                 entrypoint == name
             } else {
                 false
             }
-        }).unwrap();
-
-        let function = self.find_function(&entrypoint.operands[1]).unwrap();
+        }) {
+            Some(entrypoint) => entrypoint,
+            None => return Err(Error::MissingEntryPoint(entrypoint.to_owned())),
+        };
 
-        println!("{:#?}", function.basic_blocks);
+        let function = match self.find_function(&entrypoint.operands[1]) {
+            Some(function) => function,
+            None => return invalid("entry point without a function"),
+        };
 
         let mut builder = dr::Builder::new();
 
         builder.set_rdef(dr::RdefChunk {
             constant_buffers: Vec::new(),
             resource_bindings: Vec::new(),
-            shader_ty: 1,
+            shader_ty: 0xfffe,
             minor: 0,
             major: 5,
             flags: 0,
             author: &"DXBCross 0",
-            rd11: Some([0u32; 7]),
+            rd11: Some(dr::RD11_HEADER),
         });
 
-        let (isgn, osgn) = self.get_iosgn(entrypoint, &self.module.types_global_values, &self.module.annotations);
-
-        builder.set_isgn(isgn);
-        builder.set_osgn(osgn);
+        let (isgn, osgn, io) = self.get_iosgn(entrypoint)?;
+        let (code, temps) = FunctionTranslator::new(&self.meta, &self.module, &io)?.translate(function)?;
 
         let mut shex = dr::ShexChunk::new();
         shex.add_instruction(dr::Instruction::DclGlobalFlags {
             flags: dr::GlobalFlags::REFACTORING_ALLOWED,
         });
 
+        for elem in &isgn.elements {
+            let mask = NumComponent::D4(ComponentMode::Mask(elem.component_mask << 4));
+            shex.add_instruction(dr::Instruction::DclInput {
+                register: Operand::input(elem.register, Modifier::None, mask),
+            });
+        }
+
+        for elem in &osgn.elements {
+            let mask = NumComponent::D4(ComponentMode::Mask(elem.component_mask << 4));
+            let register = Operand::output(elem.register, Modifier::None, mask);
+            shex.add_instruction(match elem.semantic_type {
+                dr::SemanticName::Position => dr::Instruction::DclOutputSiv { register, semantic: dr::NameToken::Position },
+                _ => dr::Instruction::DclOutput { register },
+            });
+        }
+
+        if temps > 0 {
+            shex.add_instruction(dr::Instruction::DclTemps { count: temps });
+        }
+
+        for instruction in code {
+            shex.add_instruction(instruction);
+        }
+
+        builder.set_isgn(isgn);
+        builder.set_osgn(osgn);
 
         builder.set_shex(shex);
 
-        builder.module().unwrap().dwords
+        Ok(builder.module().map_err(Error::Build)?.dwords)
     }
 }
 
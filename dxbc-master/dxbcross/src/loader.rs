@@ -0,0 +1,113 @@
+use rspirv::binary::{Consumer, ParseAction};
+use rspirv::grammar::reflect;
+use rspirv::mr::{self, Error};
+use spirv;
+
+// Same as `mr::Loader`, which ends a basic block at an `OpPhi` and then
+// rejects the rest of the block as detached instructions.
+pub struct Loader {
+    module: mr::Module,
+    function: Option<mr::Function>,
+    block: Option<mr::BasicBlock>,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Loader {
+            module: mr::Module::new(),
+            function: None,
+            block: None,
+        }
+    }
+
+    pub fn module(self) -> mr::Module {
+        self.module
+    }
+}
+
+fn is_terminator(opcode: spirv::Op) -> bool {
+    match opcode {
+        spirv::Op::Branch |
+        spirv::Op::BranchConditional |
+        spirv::Op::Switch |
+        spirv::Op::Kill |
+        spirv::Op::Return |
+        spirv::Op::ReturnValue |
+        spirv::Op::Unreachable => true,
+        _ => false,
+    }
+}
+
+macro_rules! if_ret_err {
+    ($condition: expr, $error: ident) => (if $condition {
+        return ParseAction::Error(Box::new(Error::$error))
+    });
+}
+
+impl Consumer for Loader {
+    fn initialize(&mut self) -> ParseAction {
+        ParseAction::Continue
+    }
+
+    fn finalize(&mut self) -> ParseAction {
+        if_ret_err!(self.block.is_some(), UnclosedBasicBlock);
+        if_ret_err!(self.function.is_some(), UnclosedFunction);
+        ParseAction::Continue
+    }
+
+    fn consume_header(&mut self, header: mr::ModuleHeader) -> ParseAction {
+        self.module.header = Some(header);
+        ParseAction::Continue
+    }
+
+    fn consume_instruction(&mut self, inst: mr::Instruction) -> ParseAction {
+        match inst.class.opcode {
+            spirv::Op::Capability => self.module.capabilities.push(inst),
+            spirv::Op::Extension => self.module.extensions.push(inst),
+            spirv::Op::ExtInstImport => self.module.ext_inst_imports.push(inst),
+            spirv::Op::MemoryModel => self.module.memory_model = Some(inst),
+            spirv::Op::EntryPoint => self.module.entry_points.push(inst),
+            spirv::Op::ExecutionMode => self.module.execution_modes.push(inst),
+            opcode if reflect::is_nonlocation_debug(opcode) => self.module.debugs.push(inst),
+            opcode if reflect::is_annotation(opcode) => self.module.annotations.push(inst),
+            opcode if reflect::is_type(opcode) || reflect::is_constant(opcode) => self.module.types_global_values.push(inst),
+            spirv::Op::Variable | spirv::Op::Undef if self.function.is_none() => self.module.types_global_values.push(inst),
+            spirv::Op::Function => {
+                if_ret_err!(self.function.is_some(), NestedFunction);
+                let mut function = mr::Function::new();
+                function.def = Some(inst);
+                self.function = Some(function);
+            }
+            spirv::Op::FunctionEnd => {
+                if_ret_err!(self.function.is_none(), MismatchedFunctionEnd);
+                if_ret_err!(self.block.is_some(), UnclosedBasicBlock);
+                let mut function = self.function.take().unwrap();
+                function.end = Some(inst);
+                self.module.functions.push(function);
+            }
+            spirv::Op::FunctionParameter => {
+                if_ret_err!(self.function.is_none(), DetachedFunctionParameter);
+                self.function.as_mut().unwrap().parameters.push(inst);
+            }
+            spirv::Op::Label => {
+                if_ret_err!(self.function.is_none(), DetachedBasicBlock);
+                if_ret_err!(self.block.is_some(), NestedBasicBlock);
+                let mut block = mr::BasicBlock::new();
+                block.label = Some(inst);
+                self.block = Some(block);
+            }
+            opcode if is_terminator(opcode) => {
+                if_ret_err!(self.block.is_none(), MismatchedTerminator);
+                let mut block = self.block.take().unwrap();
+                block.instructions.push(inst);
+                self.function.as_mut().unwrap().basic_blocks.push(block);
+            }
+            _ => {
+                if_ret_err!(self.block.is_none(), DetachedInstruction);
+                self.block.as_mut().unwrap().instructions.push(inst);
+            }
+        }
+
+        ParseAction::Continue
+    }
+}
@@ -0,0 +1,3159 @@
+//! Translation of DXBC shaders to SPIR-V for Vulkan.
+//!
+//! Registers of the shader become `Private` variables of four floats that
+//! the instructions bitcast as they need, inputs and outputs are copied
+//! between the registers and the interface variables at the start and at
+//! each return of the entry point.
+
+use dxbc::binary::{Action, Consumer, Parser, State};
+use dxbc::dr;
+use dxbc::dr::rdef::{ShaderType, ShaderVariableClass, ShaderVariableType};
+use dxbc::dr::shex::{ConstantBufferIndexPattern, OperandType, ResourceDimension, ResourceReturnType, ShexHeader, SparseInstruction, TestBoolean};
+use dxbc::dr::builder::{GlobalFlags, Modifier, OpcodeEx};
+use dxbc::d3d11tokenizedprogramformat::*;
+use dxbc::disasm::operand_prefix;
+use dxbc::ir::{self, Components, Declaration, Index, Operand, Program, Register};
+use dxbc::types::Type;
+
+use spirv;
+
+use glsl_std450::*;
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Descriptor set of each register file, the binding is the register index.
+pub const CONSTANT_BUFFER_SET: u32 = 0;
+pub const RESOURCE_SET: u32 = 1;
+pub const SAMPLER_SET: u32 = 2;
+pub const UNORDERED_ACCESS_VIEW_SET: u32 = 3;
+
+/// Specialization constant giving the rasterizer's sample count, defaults to 1.
+pub const SAMPLE_COUNT_SPEC_ID: u32 = 0;
+
+// In sixteenths of a pixel from the pixel center.
+const STANDARD_SAMPLE_POSITIONS: [(i8, i8); 31] = [
+    (0, 0),
+    (4, 4), (-4, -4),
+    (-2, -6), (6, -2), (-6, 2), (2, 6),
+    (1, -3), (-1, 3), (5, 1), (-3, -5), (-5, 5), (-7, -1), (3, 7), (7, -7),
+    (1, 1), (-1, -3), (-3, 2), (4, -1), (-5, -2), (2, 5), (5, 3), (3, -5),
+    (-2, 6), (0, -7), (-4, -6), (-6, 4), (-8, 0), (7, -4), (6, 7), (-7, -8),
+];
+
+#[derive(Debug)]
+pub enum Error {
+    Parse(State),
+    /// The container has no SHEX or SHDR chunk.
+    MissingProgram,
+    /// Stages, declarations and instructions without a translation.
+    Unsupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Parse(ref state) => write!(f, "{}", state),
+            Error::MissingProgram => write!(f, "no shader program"),
+            Error::Unsupported(ref what) => write!(f, "unsupported {}", what),
+        }
+    }
+}
+
+impl From<State> for Error {
+    fn from(state: State) -> Self {
+        Error::Parse(state)
+    }
+}
+
+fn unsupported<T>(what: &str) -> Result<T, Error> {
+    Err(Error::Unsupported(what.to_owned()))
+}
+
+fn mask_components(mask: u8) -> Vec<u8> {
+    (0..4).filter(|c| mask & (1 << c) != 0).collect()
+}
+
+// Number of address and texel offset components of a resource.
+fn texture_coordinates(dimension: ResourceDimension) -> (u32, u32) {
+    match dimension {
+        ResourceDimension::Texture1D => (1, 1),
+        ResourceDimension::Texture1DArray => (2, 1),
+        ResourceDimension::Texture2D | ResourceDimension::Texture2DMS => (2, 2),
+        ResourceDimension::Texture2DArray | ResourceDimension::Texture2DMSArray => (3, 2),
+        ResourceDimension::Texture3D => (3, 3),
+        ResourceDimension::TextureCube => (3, 0),
+        ResourceDimension::TextureCubeArray => (4, 0),
+        _ => (1, 0),
+    }
+}
+
+// Dimension, arrayed and multisampled operands of an image type.
+fn image_dimension(dimension: ResourceDimension) -> (spirv::Dim, bool, bool) {
+    match dimension {
+        ResourceDimension::Buffer => (spirv::Dim::DimBuffer, false, false),
+        ResourceDimension::Texture1D => (spirv::Dim::Dim1D, false, false),
+        ResourceDimension::Texture1DArray => (spirv::Dim::Dim1D, true, false),
+        ResourceDimension::Texture2DMS => (spirv::Dim::Dim2D, false, true),
+        ResourceDimension::Texture2DArray => (spirv::Dim::Dim2D, true, false),
+        ResourceDimension::Texture2DMSArray => (spirv::Dim::Dim2D, true, true),
+        ResourceDimension::Texture3D => (spirv::Dim::Dim3D, false, false),
+        ResourceDimension::TextureCube => (spirv::Dim::DimCube, false, false),
+        ResourceDimension::TextureCubeArray => (spirv::Dim::DimCube, true, false),
+        _ => (spirv::Dim::Dim2D, false, false),
+    }
+}
+
+fn return_type(ty: u32) -> Type {
+    match ty {
+        x if x == ResourceReturnType::Sint as u32 => Type::Int,
+        x if x == ResourceReturnType::Uint as u32 => Type::Uint,
+        _ => Type::Float,
+    }
+}
+
+fn component_type(ty: dr::RegisterComponentType) -> Type {
+    match ty {
+        dr::RegisterComponentType::Uint32 => Type::Uint,
+        dr::RegisterComponentType::Int32 => Type::Int,
+        _ => Type::Float,
+    }
+}
+
+fn variable_type(ty: ShaderVariableType) -> Option<Type> {
+    match ty {
+        ShaderVariableType::Float => Some(Type::Float),
+        ShaderVariableType::Int => Some(Type::Int),
+        // HLSL booleans are stored as 0 and 1
+        ShaderVariableType::UInt | ShaderVariableType::UInt8 | ShaderVariableType::Bool => Some(Type::Uint),
+        _ => None,
+    }
+}
+
+#[derive(Clone)]
+struct Element {
+    name: String,
+    semantic_type: dr::SemanticName,
+    semantic_index: u32,
+    ty: Type,
+    register: u32,
+    mask: u8,
+}
+
+impl Element {
+    fn from_element(element: &dr::InputOutputElement) -> Self {
+        Element {
+            name: element.name.clone(),
+            semantic_type: element.semantic_type,
+            semantic_index: element.semantic_index,
+            ty: component_type(element.component_type),
+            register: element.register,
+            mask: element.component_mask,
+        }
+    }
+}
+
+// Owned copy of a constant buffer variable or struct member of RDEF, with
+// its offset in bytes.
+struct Variable {
+    name: String,
+    offset: u32,
+    class: ShaderVariableClass,
+    ty: ShaderVariableType,
+    rows: u32,
+    columns: u32,
+    count: u32,
+    members: Vec<Variable>,
+}
+
+impl Variable {
+    fn from_type(name: &str, offset: u32, ty: &ShaderType) -> Self {
+        Variable {
+            name: name.to_owned(),
+            offset,
+            class: ty.class,
+            ty: ty.ty,
+            rows: ty.rows as u32,
+            columns: ty.columns as u32,
+            count: ty.count as u32,
+            members: ty.members.iter().map(|member| Variable::from_type(member.name, member.offset, &member.ty)).collect(),
+        }
+    }
+
+    // Registers taken by one array element.
+    fn registers(&self) -> u32 {
+        match self.class {
+            ShaderVariableClass::MatrixRows => self.rows,
+            ShaderVariableClass::MatrixColumns => self.columns,
+            ShaderVariableClass::Struct => {
+                let end = self.members.iter().map(|member| member.offset + member.size()).max().unwrap_or(0);
+                end.div_ceil(16)
+            }
+            _ => 1,
+        }
+    }
+
+    // Size in bytes, the last element of an array isn't padded.
+    fn size(&self) -> u32 {
+        let element = match self.class {
+            ShaderVariableClass::MatrixRows => 16 * self.rows.saturating_sub(1) + 4 * self.columns,
+            ShaderVariableClass::MatrixColumns => 16 * self.columns.saturating_sub(1) + 4 * self.rows,
+            ShaderVariableClass::Struct => self.members.iter().map(|member| member.offset + member.size()).max().unwrap_or(0),
+            _ => 4 * self.columns,
+        };
+
+        if self.count > 1 {
+            self.registers() * 16 * (self.count - 1) + element
+        } else {
+            element
+        }
+    }
+}
+
+struct ConstantBuffer {
+    name: String,
+    variables: Vec<Variable>,
+}
+
+// Everything the shader declares outside of its code.
+#[derive(Default)]
+struct Shader {
+    program_type: u32,
+    inputs: Vec<Element>,
+    outputs: Vec<Element>,
+    /// Constant buffers described in RDEF, by slot.
+    constant_buffers: HashMap<u32, ConstantBuffer>,
+    /// Names of the resources, samplers and views in RDEF.
+    names: HashMap<(OperandType, u32), String>,
+}
+
+struct SpirvConsumer {
+    shader: Shader,
+    program: Option<Program>,
+}
+
+impl Consumer for SpirvConsumer {
+    fn initialize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn finalize(&mut self) -> Action {
+        Action::Continue
+    }
+
+    fn consume_rdef(&mut self, rdef: &dr::RdefChunk) -> Action {
+        for binding in &rdef.resource_bindings {
+            let file = match binding.input_type {
+                0 => {
+                    if let Some(buffer) = rdef.constant_buffers.iter().find(|buffer| buffer.name == binding.name) {
+                        let variables = buffer
+                            .variables
+                            .iter()
+                            .map(|variable| Variable::from_type(variable.name, variable.start_offset, &variable.ty))
+                            .collect();
+                        self.shader.constant_buffers.insert(binding.bind_point, ConstantBuffer {
+                            name: buffer.name.to_owned(),
+                            variables,
+                        });
+                    }
+                    continue;
+                }
+                3 => OperandType::Sampler,
+                4 | 6 | 8 | 9 | 10 | 11 => OperandType::UnorderedAccessView,
+                _ => OperandType::Resource,
+            };
+            if binding.bind_count <= 1 {
+                self.shader.names.insert((file, binding.bind_point), binding.name.to_owned());
+            }
+        }
+
+        Action::Continue
+    }
+
+    fn consume_isgn(&mut self, isgn: &dr::IOsgnChunk) -> Action {
+        self.shader.inputs = isgn
+            .elements
+            .iter()
+            .filter(|element| element.component_mask != 0)
+            .map(Element::from_element)
+            .collect();
+
+        Action::Continue
+    }
+
+    fn consume_osgn(&mut self, osgn: &dr::IOsgnChunk) -> Action {
+        self.shader.outputs = osgn
+            .elements
+            .iter()
+            .filter(|element| element.component_mask != 0)
+            .map(Element::from_element)
+            .collect();
+
+        Action::Continue
+    }
+
+    fn consume_shex(&mut self, header: &ShexHeader) -> Action {
+        self.shader.program_type = header.program_type as u32;
+        self.program = Some(Program::new(header.program_type, header.major, header.minor));
+
+        Action::Continue
+    }
+
+    fn consume_instruction(&mut self, offset: u32, instruction: SparseInstruction) -> Action {
+        let program = match self.program {
+            Some(ref mut program) => program,
+            None => return Action::Continue,
+        };
+
+        match ir::lower(offset, &instruction) {
+            Ok(Some(statement)) => program.add_statement(statement),
+            Ok(None) => {}
+            Err(err) => return Action::Error(Box::new(err)),
+        }
+
+        Action::Continue
+    }
+}
+
+fn instruction(words: &mut Vec<u32>, op: spirv::Op, operands: &[u32]) {
+    words.push(((operands.len() as u32 + 1) << 16) | op as u32);
+    words.extend_from_slice(operands);
+}
+
+// Nul terminated UTF-8 packed into little endian words.
+fn string(text: &str) -> Vec<u32> {
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.push(0);
+    while bytes.len() % 4 != 0 {
+        bytes.push(0);
+    }
+
+    bytes
+        .chunks(4)
+        .map(|c| c[0] as u32 | (c[1] as u32) << 8 | (c[2] as u32) << 16 | (c[3] as u32) << 24)
+        .collect()
+}
+
+/// An SSA value of a scalar or vector type.
+#[derive(Debug, Copy, Clone)]
+struct Value {
+    id: u32,
+    ty: Type,
+    width: u32,
+}
+
+// Builds an instruction from the result type and the argument ids.
+type Operation<'f> = &'f Fn(&mut Module, u32, &[u32]) -> u32;
+
+/// The sections of a module in their logical layout, the code of the
+/// functions is written block after block.
+struct Module {
+    bound: u32,
+    capabilities: Vec<u32>,
+    glsl: u32,
+    debug: Vec<u32>,
+    annotations: Vec<u32>,
+    globals: Vec<u32>,
+    code: Vec<u32>,
+    declared: HashMap<Vec<u32>, u32>,
+    /// Scalar type and width of the value types.
+    shapes: HashMap<u32, (Type, u32)>,
+    /// Whether the current block has no terminator yet.
+    open: bool,
+}
+
+impl Module {
+    fn new() -> Self {
+        let mut module = Module {
+            bound: 1,
+            capabilities: Vec::new(),
+            glsl: 0,
+            debug: Vec::new(),
+            annotations: Vec::new(),
+            globals: Vec::new(),
+            code: Vec::new(),
+            declared: HashMap::new(),
+            shapes: HashMap::new(),
+            open: false,
+        };
+        module.glsl = module.id();
+        module.capability(spirv::Capability::Shader);
+        module
+    }
+
+    fn id(&mut self) -> u32 {
+        let id = self.bound;
+        self.bound += 1;
+        id
+    }
+
+    fn capability(&mut self, capability: spirv::Capability) {
+        let capability = capability as u32;
+        if !self.capabilities.contains(&capability) {
+            self.capabilities.push(capability);
+        }
+    }
+
+    fn name(&mut self, id: u32, name: &str) {
+        let mut operands = vec![id];
+        operands.extend(string(name));
+        instruction(&mut self.debug, spirv::Op::Name, &operands);
+    }
+
+    fn member_name(&mut self, id: u32, member: u32, name: &str) {
+        let mut operands = vec![id, member];
+        operands.extend(string(name));
+        instruction(&mut self.debug, spirv::Op::MemberName, &operands);
+    }
+
+    fn decorate(&mut self, id: u32, decoration: spirv::Decoration, literals: &[u32]) {
+        let mut operands = vec![id, decoration as u32];
+        operands.extend_from_slice(literals);
+        instruction(&mut self.annotations, spirv::Op::Decorate, &operands);
+    }
+
+    fn member_decorate(&mut self, id: u32, member: u32, decoration: spirv::Decoration, literals: &[u32]) {
+        let mut operands = vec![id, member, decoration as u32];
+        operands.extend_from_slice(literals);
+        instruction(&mut self.annotations, spirv::Op::MemberDecorate, &operands);
+    }
+
+    // Types and constants are declared once for each set of operands, `key`
+    // tells apart types that only differ in their decorations. Returns
+    // whether the declaration is new.
+    fn declare(&mut self, op: spirv::Op, ty: Option<u32>, operands: &[u32], key: u32) -> (u32, bool) {
+        let mut declaration = vec![op as u32, ty.unwrap_or(0), key];
+        declaration.extend_from_slice(operands);
+        if let Some(&id) = self.declared.get(&declaration) {
+            return (id, false);
+        }
+
+        let id = self.id();
+        let mut words = Vec::new();
+        words.extend(ty);
+        words.push(id);
+        words.extend_from_slice(operands);
+        instruction(&mut self.globals, op, &words);
+        self.declared.insert(declaration, id);
+
+        (id, true)
+    }
+
+    fn ty(&mut self, op: spirv::Op, operands: &[u32]) -> u32 {
+        self.declare(op, None, operands, 0).0
+    }
+
+    fn void(&mut self) -> u32 {
+        self.ty(spirv::Op::TypeVoid, &[])
+    }
+
+    fn scalar(&mut self, ty: Type) -> u32 {
+        let id = match ty {
+            Type::Float => self.ty(spirv::Op::TypeFloat, &[32]),
+            Type::Int => self.ty(spirv::Op::TypeInt, &[32, 1]),
+            Type::Uint => self.ty(spirv::Op::TypeInt, &[32, 0]),
+            Type::Bool => self.ty(spirv::Op::TypeBool, &[]),
+        };
+        self.shapes.insert(id, (ty, 1));
+        id
+    }
+
+    fn vector(&mut self, ty: Type, width: u32) -> u32 {
+        let scalar = self.scalar(ty);
+        if width == 1 {
+            return scalar;
+        }
+
+        let id = self.ty(spirv::Op::TypeVector, &[scalar, width]);
+        self.shapes.insert(id, (ty, width));
+        id
+    }
+
+    fn pointer(&mut self, class: spirv::StorageClass, ty: u32) -> u32 {
+        self.ty(spirv::Op::TypePointer, &[class as u32, ty])
+    }
+
+    fn array(&mut self, element: u32, length: u32, stride: Option<u32>) -> u32 {
+        let length = self.constant(Type::Uint, length);
+        let (id, new) = self.declare(spirv::Op::TypeArray, None, &[element, length], stride.unwrap_or(0));
+        if let (Some(stride), true) = (stride, new) {
+            self.decorate(id, spirv::Decoration::ArrayStride, &[stride]);
+        }
+        id
+    }
+
+    fn runtime_array(&mut self, element: u32, stride: u32) -> u32 {
+        let (id, new) = self.declare(spirv::Op::TypeRuntimeArray, None, &[element], stride);
+        if new {
+            self.decorate(id, spirv::Decoration::ArrayStride, &[stride]);
+        }
+        id
+    }
+
+    // Structs are never shared as their members are decorated one by one.
+    fn structure(&mut self, members: &[u32]) -> u32 {
+        let id = self.id();
+        let mut operands = vec![id];
+        operands.extend_from_slice(members);
+        instruction(&mut self.globals, spirv::Op::TypeStruct, &operands);
+        id
+    }
+
+    fn function_type(&mut self, result: u32) -> u32 {
+        self.ty(spirv::Op::TypeFunction, &[result])
+    }
+
+    fn constant(&mut self, ty: Type, bits: u32) -> u32 {
+        let scalar = self.scalar(ty);
+        match ty {
+            Type::Bool if bits != 0 => self.declare(spirv::Op::ConstantTrue, Some(scalar), &[], 0).0,
+            Type::Bool => self.declare(spirv::Op::ConstantFalse, Some(scalar), &[], 0).0,
+            _ => self.declare(spirv::Op::Constant, Some(scalar), &[bits], 0).0,
+        }
+    }
+
+    fn splat(&mut self, ty: Type, bits: u32, width: u32) -> u32 {
+        let scalar = self.constant(ty, bits);
+        if width == 1 {
+            return scalar;
+        }
+
+        let vector = self.vector(ty, width);
+        self.declare(spirv::Op::ConstantComposite, Some(vector), &vec![scalar; width as usize], 0).0
+    }
+
+    // Constant with the given bits in every component of a value type.
+    fn constant_as(&mut self, ty: u32, bits: u32) -> u32 {
+        let (scalar, width) = self.shapes[&ty];
+        self.splat(scalar, bits, width)
+    }
+
+    fn composite(&mut self, ty: u32, constituents: &[u32]) -> u32 {
+        self.declare(spirv::Op::ConstantComposite, Some(ty), constituents, 0).0
+    }
+
+    fn null(&mut self, ty: u32) -> u32 {
+        self.declare(spirv::Op::ConstantNull, Some(ty), &[], 0).0
+    }
+
+    fn variable(&mut self, ty: u32, class: spirv::StorageClass, initializer: Option<u32>) -> u32 {
+        let pointer = self.pointer(class, ty);
+        let id = self.id();
+        let mut operands = vec![pointer, id, class as u32];
+        operands.extend(initializer);
+        instruction(&mut self.globals, spirv::Op::Variable, &operands);
+        id
+    }
+
+    // Code after a terminator starts a new block nothing branches to.
+    fn ensure_block(&mut self) {
+        if !self.open {
+            let label = self.id();
+            instruction(&mut self.code, spirv::Op::Label, &[label]);
+            self.open = true;
+        }
+    }
+
+    fn emit(&mut self, op: spirv::Op, ty: u32, operands: &[u32]) -> u32 {
+        self.ensure_block();
+        let id = self.id();
+        let mut words = vec![ty, id];
+        words.extend_from_slice(operands);
+        instruction(&mut self.code, op, &words);
+        id
+    }
+
+    fn emit_void(&mut self, op: spirv::Op, operands: &[u32]) {
+        self.ensure_block();
+        instruction(&mut self.code, op, operands);
+    }
+
+    fn terminate(&mut self, op: spirv::Op, operands: &[u32]) {
+        self.emit_void(op, operands);
+        self.open = false;
+    }
+
+    // Starts a block, the current one falls through to it if it is open.
+    fn block(&mut self, label: u32) {
+        if self.open {
+            self.terminate(spirv::Op::Branch, &[label]);
+        }
+        instruction(&mut self.code, spirv::Op::Label, &[label]);
+        self.open = true;
+    }
+
+    fn ext(&mut self, ty: u32, instruction: u32, arguments: &[u32]) -> u32 {
+        let mut operands = vec![self.glsl, instruction];
+        operands.extend_from_slice(arguments);
+        self.emit(spirv::Op::ExtInst, ty, &operands)
+    }
+
+    fn load(&mut self, ty: Type, width: u32, pointer: u32) -> Value {
+        let result = self.vector(ty, width);
+        Value { id: self.emit(spirv::Op::Load, result, &[pointer]), ty, width }
+    }
+
+    /// Reinterprets the bits of a value, booleans are all ones or all zeros
+    /// as integers.
+    fn bitcast(&mut self, value: Value, ty: Type) -> Value {
+        let width = value.width;
+        if value.ty == ty {
+            return value;
+        }
+
+        let value = if value.ty == Type::Bool {
+            let uint = self.vector(Type::Uint, width);
+            let ones = self.splat(Type::Uint, !0, width);
+            let zero = self.splat(Type::Uint, 0, width);
+            let id = self.emit(spirv::Op::Select, uint, &[value.id, ones, zero]);
+            Value { id, ty: Type::Uint, width }
+        } else {
+            value
+        };
+
+        match ty {
+            _ if value.ty == ty => value,
+            Type::Bool => {
+                let value = self.bitcast(value, Type::Uint);
+                let result = self.vector(Type::Bool, width);
+                let zero = self.splat(Type::Uint, 0, width);
+                Value { id: self.emit(spirv::Op::INotEqual, result, &[value.id, zero]), ty, width }
+            }
+            _ => {
+                let result = self.vector(ty, width);
+                Value { id: self.emit(spirv::Op::Bitcast, result, &[value.id]), ty, width }
+            }
+        }
+    }
+
+    fn extract(&mut self, value: Value, component: u32) -> Value {
+        if value.width == 1 {
+            return value;
+        }
+
+        let scalar = self.scalar(value.ty);
+        let id = self.emit(spirv::Op::CompositeExtract, scalar, &[value.id, component]);
+        Value { id, ty: value.ty, width: 1 }
+    }
+
+    fn construct(&mut self, parts: &[Value]) -> Value {
+        if parts.len() == 1 {
+            return parts[0];
+        }
+
+        let ty = parts[0].ty;
+        let width = parts.len() as u32;
+        let result = self.vector(ty, width);
+        let ids: Vec<u32> = parts.iter().map(|part| part.id).collect();
+        Value { id: self.emit(spirv::Op::CompositeConstruct, result, &ids), ty, width }
+    }
+
+    fn shuffle(&mut self, value: Value, picks: &[u8]) -> Value {
+        let width = picks.len() as u32;
+        if value.width == 1 {
+            return self.construct(&vec![value; picks.len()]);
+        }
+        if width == 1 {
+            return self.extract(value, picks[0] as u32);
+        }
+        if width == value.width && picks.iter().enumerate().all(|(c, &pick)| c == pick as usize) {
+            return value;
+        }
+
+        let result = self.vector(value.ty, width);
+        let mut operands = vec![value.id, value.id];
+        operands.extend(picks.iter().map(|&pick| pick as u32));
+        Value { id: self.emit(spirv::Op::VectorShuffle, result, &operands), ty: value.ty, width }
+    }
+
+    // Replaces the `components` of a vector with those of `value`.
+    fn insert(&mut self, vector: Value, components: &[u8], value: Value) -> Value {
+        let result = self.vector(vector.ty, vector.width);
+
+        let id = if value.width == 1 {
+            self.emit(spirv::Op::CompositeInsert, result, &[value.id, vector.id, components[0] as u32])
+        } else {
+            let mut operands = vec![vector.id, value.id];
+            operands.extend((0..vector.width).map(|c| match components.iter().position(|&component| component as u32 == c) {
+                Some(n) => vector.width + n as u32,
+                None => c,
+            }));
+            self.emit(spirv::Op::VectorShuffle, result, &operands)
+        };
+
+        Value { id, ty: vector.ty, width: vector.width }
+    }
+
+    fn words(&self, header: &[u32]) -> Vec<u32> {
+        let mut words = vec![spirv::MAGIC_NUMBER, 0x0001_0000, 0, self.bound, 0];
+        for &capability in &self.capabilities {
+            instruction(&mut words, spirv::Op::Capability, &[capability]);
+        }
+        let mut import = vec![self.glsl];
+        import.extend(string("GLSL.std.450"));
+        instruction(&mut words, spirv::Op::ExtInstImport, &import);
+        instruction(&mut words, spirv::Op::MemoryModel, &[
+            spirv::AddressingModel::Logical as u32,
+            spirv::MemoryModel::GLSL450 as u32,
+        ]);
+        words.extend_from_slice(header);
+        words.extend_from_slice(&self.debug);
+        words.extend_from_slice(&self.annotations);
+        words.extend_from_slice(&self.globals);
+        words.extend_from_slice(&self.code);
+        words
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum ResourceKind {
+    Typed,
+    Raw,
+    Structured,
+}
+
+#[derive(Copy, Clone)]
+struct Resource {
+    variable: u32,
+    /// Image or sampler type of typed resources.
+    ty: u32,
+    kind: ResourceKind,
+    dimension: ResourceDimension,
+    sampled: Type,
+    /// Stride of structured buffers in dwords.
+    stride: u32,
+    class: spirv::StorageClass,
+}
+
+// Access chain and type of a dword of a constant buffer laid out as in RDEF.
+#[derive(Clone)]
+struct Member {
+    indices: Vec<u32>,
+    ty: Type,
+}
+
+struct ConstantBufferVariable {
+    variable: u32,
+    /// Members by dword, `None` if the buffer is an array of vectors.
+    members: Option<HashMap<u32, Member>>,
+}
+
+// An `Input` or `Output` variable and the register components it is copied
+// to or from.
+#[derive(Clone)]
+struct InterfaceVariable {
+    variable: u32,
+    ty: Type,
+    width: u32,
+    register: (OperandType, u32),
+    components: Vec<u8>,
+    /// Component of the variable for each register component.
+    picks: Vec<u8>,
+    /// First element in a clip or cull distance array.
+    array: Option<u32>,
+    /// Pixel shaders get 1/w in the w of `SV_Position`.
+    reciprocal_w: bool,
+}
+
+enum Construct {
+    If { else_label: u32, merge: u32, has_else: bool },
+    Loop { header: u32, continue_target: u32, merge: u32 },
+    /// Labels of the first `case` or `default` of each group.
+    Switch { merge: u32, cases: Vec<(usize, u32)> },
+}
+
+// What instructions like `breakc` and `retc` do when their condition holds.
+enum Jump {
+    Branch(u32),
+    Return,
+    Kill,
+    Call(u32),
+}
+
+// Components a destination writes, `None` for `null` destinations.
+fn written(operand: &Operand) -> Option<Vec<u8>> {
+    match *operand {
+        Operand::Register(ref register) if register.file != OperandType::Null => {
+            Some(mask_components(register.components.mask())).filter(|components| !components.is_empty())
+        }
+        _ => None,
+    }
+}
+
+// Components of a resource read for each destination component.
+fn resource_picks(resource: &Operand, components: &[u8]) -> Vec<u8> {
+    let swizzle = resource.register().map_or([0, 1, 2, 3], |register| register.components.swizzle());
+    components.iter().map(|&c| swizzle[c as usize]).collect()
+}
+
+fn label(operand: &Operand) -> u64 {
+    operand.register().and_then(|register| register.index.first()).map_or(0, Index::offset)
+}
+
+fn resource_index(register: &Register) -> u32 {
+    register.index.first().map_or(0, Index::offset) as u32
+}
+
+// Vectors of a matrix and their width, each vector takes a register.
+fn matrix_shape(variable: &Variable) -> (u32, u32) {
+    match variable.class {
+        ShaderVariableClass::MatrixRows => (variable.rows, variable.columns),
+        _ => (variable.columns, variable.rows),
+    }
+}
+
+// Records the access chain of each dword of a variable at dword `base`.
+fn record(variable: &Variable, base: u32, path: &[u32], members: &mut HashMap<u32, Member>) {
+    let ty = variable_type(variable.ty).unwrap_or(Type::Float);
+    let lane = |path: &[u32], width: u32, c: u32| {
+        let mut indices = path.to_vec();
+        if width > 1 {
+            indices.push(c);
+        }
+        Member { indices, ty }
+    };
+
+    for element in 0..variable.count.max(1) {
+        let mut path = path.to_vec();
+        if variable.count > 1 {
+            path.push(element);
+        }
+        let base = base + element * variable.registers() * 4;
+
+        match variable.class {
+            ShaderVariableClass::Scalar | ShaderVariableClass::Vector => {
+                for c in 0..variable.columns {
+                    members.insert(base + c, lane(&path, variable.columns, c));
+                }
+            }
+            ShaderVariableClass::MatrixRows | ShaderVariableClass::MatrixColumns => {
+                let (vectors, width) = matrix_shape(variable);
+                for v in 0..vectors {
+                    let mut path = path.clone();
+                    path.push(v);
+                    for c in 0..width {
+                        members.insert(base + 4 * v + c, lane(&path, width, c));
+                    }
+                }
+            }
+            ShaderVariableClass::Struct => {
+                for (m, member) in variable.members.iter().enumerate() {
+                    let mut path = path.clone();
+                    path.push(m as u32);
+                    record(member, base + member.offset / 4, &path, members);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+struct Translator<'a> {
+    shader: &'a Shader,
+    program: &'a Program,
+    module: Module,
+    model: spirv::ExecutionModel,
+    main: u32,
+    registers: HashMap<(OperandType, u32), u32>,
+    indexable: HashMap<u32, u32>,
+    immediate_constant_buffer: Option<u32>,
+    sample_positions: Option<u32>,
+    constant_buffers: HashMap<u32, ConstantBufferVariable>,
+    resources: HashMap<(OperandType, u32), Resource>,
+    inputs: Vec<InterfaceVariable>,
+    outputs: Vec<InterfaceVariable>,
+    /// Variables of the entry point interface.
+    interface: Vec<u32>,
+    modes: Vec<(spirv::ExecutionMode, Vec<u32>)>,
+    functions: HashMap<u64, u32>,
+    constructs: Vec<Construct>,
+    in_subroutine: bool,
+}
+
+impl<'a> Translator<'a> {
+    fn new(shader: &'a Shader, program: &'a Program) -> Result<Self, Error> {
+        let mut modes = Vec::new();
+        let model = match program.program_type as u32 {
+            D3D10_SB_VERTEX_SHADER => spirv::ExecutionModel::Vertex,
+            D3D10_SB_PIXEL_SHADER => {
+                modes.push((spirv::ExecutionMode::OriginUpperLeft, Vec::new()));
+                spirv::ExecutionModel::Fragment
+            }
+            D3D11_SB_COMPUTE_SHADER => spirv::ExecutionModel::GLCompute,
+            _ => return unsupported("shader stage"),
+        };
+
+        let mut module = Module::new();
+        let main = module.id();
+        module.name(main, "main");
+
+        Ok(Translator {
+            shader,
+            program,
+            module,
+            model,
+            main,
+            registers: HashMap::new(),
+            indexable: HashMap::new(),
+            immediate_constant_buffer: None,
+            sample_positions: None,
+            constant_buffers: HashMap::new(),
+            resources: HashMap::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            interface: Vec::new(),
+            modes,
+            functions: HashMap::new(),
+            constructs: Vec::new(),
+            in_subroutine: false,
+        })
+    }
+
+    fn translate(mut self, entrypoint: &str) -> Result<Vec<u32>, Error> {
+        self.declarations()?;
+        self.interface()?;
+
+        let main = self.main;
+        self.begin_function(main);
+        self.copy_inputs()?;
+        for i in 0..self.program.instructions.len() {
+            self.instruction(i)?;
+        }
+        if !self.constructs.is_empty() {
+            return unsupported("unterminated control flow");
+        }
+        self.end_function()?;
+
+        let mut header = Vec::new();
+        let mut entry = vec![self.model as u32, self.main];
+        entry.extend(string(entrypoint));
+        entry.extend_from_slice(&self.interface);
+        instruction(&mut header, spirv::Op::EntryPoint, &entry);
+        for &(mode, ref literals) in &self.modes {
+            let mut operands = vec![self.main, mode as u32];
+            operands.extend_from_slice(literals);
+            instruction(&mut header, spirv::Op::ExecutionMode, &operands);
+        }
+
+        Ok(self.module.words(&header))
+    }
+
+    fn declarations(&mut self) -> Result<(), Error> {
+        let program = self.program;
+
+        for declaration in &program.decls {
+            match *declaration {
+                Declaration::GlobalFlags(flags) => {
+                    if flags.contains(GlobalFlags::FORCE_EARLY_DEPTH_STENCIL) {
+                        self.modes.push((spirv::ExecutionMode::EarlyFragmentTests, Vec::new()));
+                    }
+                }
+                Declaration::ImmediateConstantBuffer(ref data) => self.declare_immediate_constant_buffer(data),
+                Declaration::ConstantBuffer { ref register, access } => self.declare_constant_buffer(register, access),
+                Declaration::Sampler { ref register, .. } => {
+                    let ty = self.module.ty(spirv::Op::TypeSampler, &[]);
+                    let variable = self.module.variable(ty, spirv::StorageClass::UniformConstant, None);
+                    self.bind(variable, register, SAMPLER_SET);
+                    self.resources.insert((register.file, resource_index(register)), Resource {
+                        variable,
+                        ty,
+                        kind: ResourceKind::Typed,
+                        dimension: ResourceDimension::Unknown,
+                        sampled: Type::Float,
+                        stride: 0,
+                        class: spirv::StorageClass::UniformConstant,
+                    });
+                }
+                Declaration::Resource { ref register, dimension, return_type: returns, .. } => {
+                    self.declare_image(register, ResourceDimension::from_word(dimension).unwrap_or(ResourceDimension::Unknown), return_type(returns[0]), false)
+                }
+                Declaration::UnorderedAccessViewTyped { ref register, dimension, return_type: returns, has_counter, .. } => {
+                    if has_counter {
+                        return unsupported("UAV counters");
+                    }
+                    self.declare_image(register, ResourceDimension::from_word(dimension).unwrap_or(ResourceDimension::Unknown), return_type(returns[0]), true)
+                }
+                Declaration::ResourceRaw { ref register } => self.declare_buffer(register, ResourceKind::Raw, 0),
+                Declaration::ResourceStructured { ref register, stride } => {
+                    self.declare_buffer(register, ResourceKind::Structured, stride / 4)
+                }
+                Declaration::UnorderedAccessViewRaw { has_counter: true, .. } |
+                Declaration::UnorderedAccessViewStructured { has_counter: true, .. } => return unsupported("UAV counters"),
+                Declaration::UnorderedAccessViewRaw { ref register, .. } => self.declare_buffer(register, ResourceKind::Raw, 0),
+                Declaration::UnorderedAccessViewStructured { ref register, stride, .. } => {
+                    self.declare_buffer(register, ResourceKind::Structured, stride / 4)
+                }
+                Declaration::ThreadGroupSharedMemoryRaw { ref register, byte_count } => {
+                    self.declare_shared(register, ResourceKind::Raw, 0, byte_count / 4)
+                }
+                Declaration::ThreadGroupSharedMemoryStructured { ref register, stride, count } => {
+                    self.declare_shared(register, ResourceKind::Structured, stride / 4, stride / 4 * count)
+                }
+                Declaration::IndexableTemp { index, count, .. } => {
+                    let vec4 = self.module.vector(Type::Float, 4);
+                    let array = self.module.array(vec4, count, None);
+                    let null = self.module.null(array);
+                    let variable = self.module.variable(array, spirv::StorageClass::Private, Some(null));
+                    self.module.name(variable, &format!("x{}", index));
+                    self.indexable.insert(index, variable);
+                }
+                Declaration::ThreadGroup(x, y, z) => self.modes.push((spirv::ExecutionMode::LocalSize, vec![x, y, z])),
+                Declaration::Temps(_) |
+                Declaration::Input { .. } |
+                Declaration::InputSgv { .. } |
+                Declaration::InputSiv { .. } |
+                Declaration::InputPs { .. } |
+                Declaration::InputPsSgv { .. } |
+                Declaration::InputPsSiv { .. } |
+                Declaration::Output { .. } |
+                Declaration::OutputSgv { .. } |
+                Declaration::OutputSiv { .. } |
+                Declaration::IndexRange { .. } => {}
+                _ => return unsupported(&declaration.to_string()),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Sets the descriptor set and binding of a resource variable, named
+    // after its RDEF binding.
+    fn bind(&mut self, variable: u32, register: &Register, set: u32) {
+        let index = resource_index(register);
+        let name = match self.shader.names.get(&(register.file, index)) {
+            Some(name) => name.clone(),
+            None => format!("{}{}", operand_prefix(register.file as u32), index),
+        };
+
+        self.module.name(variable, &name);
+        self.module.decorate(variable, spirv::Decoration::DescriptorSet, &[set]);
+        self.module.decorate(variable, spirv::Decoration::Binding, &[index]);
+    }
+
+    fn declare_immediate_constant_buffer(&mut self, data: &[u32]) {
+        let uvec4 = self.module.vector(Type::Uint, 4);
+        let entries: Vec<u32> = data
+            .chunks(4)
+            .map(|entry| {
+                let parts: Vec<u32> = (0..4).map(|c| self.module.constant(Type::Uint, entry.get(c).cloned().unwrap_or(0))).collect();
+                self.module.composite(uvec4, &parts)
+            })
+            .collect();
+
+        let array = self.module.array(uvec4, entries.len().max(1) as u32, None);
+        let initializer = if entries.is_empty() { self.module.null(array) } else { self.module.composite(array, &entries) };
+        let variable = self.module.variable(array, spirv::StorageClass::Private, Some(initializer));
+        self.module.name(variable, "icb");
+        self.immediate_constant_buffer = Some(variable);
+    }
+
+    fn declare_constant_buffer(&mut self, register: &Register, access: ConstantBufferIndexPattern) {
+        let slot = resource_index(register);
+        let size = register.index.get(1).map_or(1, |index| index.offset() as u32).max(1);
+        let buffer = self.shader.constant_buffers.get(&slot);
+
+        // dynamically indexed buffers can't map indices to members
+        let layout = match buffer {
+            Some(buffer) if access == ConstantBufferIndexPattern::Immediate => self.layout_block(buffer),
+            _ => None,
+        };
+        let (block, members) = match layout {
+            Some((block, members)) => (block, Some(members)),
+            None => {
+                let vec4 = self.module.vector(Type::Float, 4);
+                let array = self.module.array(vec4, size, Some(16));
+                let block = self.module.structure(&[array]);
+                self.module.member_decorate(block, 0, spirv::Decoration::Offset, &[0]);
+                self.module.member_name(block, 0, "data");
+                (block, None)
+            }
+        };
+        self.module.decorate(block, spirv::Decoration::Block, &[]);
+
+        let variable = self.module.variable(block, spirv::StorageClass::Uniform, None);
+        match buffer {
+            Some(buffer) => self.module.name(variable, &buffer.name),
+            None => self.module.name(variable, &format!("cb{}", slot)),
+        }
+        self.module.decorate(variable, spirv::Decoration::DescriptorSet, &[CONSTANT_BUFFER_SET]);
+        self.module.decorate(variable, spirv::Decoration::Binding, &[slot]);
+        self.constant_buffers.insert(slot, ConstantBufferVariable { variable, members });
+    }
+
+    // Block with the variables of a constant buffer at their RDEF offsets.
+    fn layout_block(&mut self, buffer: &ConstantBuffer) -> Option<(u32, HashMap<u32, Member>)> {
+        if buffer.variables.is_empty() {
+            return None;
+        }
+
+        let mut types = Vec::new();
+        for variable in &buffer.variables {
+            types.push(self.layout(variable)?);
+        }
+
+        let block = self.module.structure(&types);
+        let mut members = HashMap::new();
+        for (m, variable) in buffer.variables.iter().enumerate() {
+            self.module.member_decorate(block, m as u32, spirv::Decoration::Offset, &[variable.offset]);
+            self.module.member_name(block, m as u32, &variable.name);
+            record(variable, variable.offset / 4, &[m as u32], &mut members);
+        }
+
+        Some((block, members))
+    }
+
+    // Type of a constant buffer variable, matrices are arrays of vectors
+    // with a register for each vector.
+    fn layout(&mut self, variable: &Variable) -> Option<u32> {
+        let element = match variable.class {
+            ShaderVariableClass::Scalar | ShaderVariableClass::Vector => {
+                let ty = variable_type(variable.ty)?;
+                self.module.vector(ty, variable.columns)
+            }
+            ShaderVariableClass::MatrixRows | ShaderVariableClass::MatrixColumns => {
+                let ty = variable_type(variable.ty)?;
+                let (vectors, width) = matrix_shape(variable);
+                let vector = self.module.vector(ty, width);
+                self.module.array(vector, vectors, Some(16))
+            }
+            ShaderVariableClass::Struct => {
+                let mut types = Vec::new();
+                for member in &variable.members {
+                    types.push(self.layout(member)?);
+                }
+                let structure = self.module.structure(&types);
+                for (m, member) in variable.members.iter().enumerate() {
+                    self.module.member_decorate(structure, m as u32, spirv::Decoration::Offset, &[member.offset]);
+                    self.module.member_name(structure, m as u32, &member.name);
+                }
+                structure
+            }
+            _ => return None,
+        };
+
+        if variable.count > 1 {
+            Some(self.module.array(element, variable.count, Some(variable.registers() * 16)))
+        } else {
+            Some(element)
+        }
+    }
+
+    fn declare_image(&mut self, register: &Register, dimension: ResourceDimension, sampled: Type, storage: bool) {
+        let (dim, arrayed, multisampled) = image_dimension(dimension);
+        let capability = match (dim, storage) {
+            (spirv::Dim::Dim1D, false) => Some(spirv::Capability::Sampled1D),
+            (spirv::Dim::Dim1D, true) => Some(spirv::Capability::Image1D),
+            (spirv::Dim::DimBuffer, false) => Some(spirv::Capability::SampledBuffer),
+            (spirv::Dim::DimBuffer, true) => Some(spirv::Capability::ImageBuffer),
+            (spirv::Dim::DimCube, false) if arrayed => Some(spirv::Capability::SampledCubeArray),
+            (spirv::Dim::DimCube, true) if arrayed => Some(spirv::Capability::ImageCubeArray),
+            _ => None,
+        };
+        if let Some(capability) = capability {
+            self.module.capability(capability);
+        }
+        if storage {
+            if multisampled {
+                self.module.capability(spirv::Capability::StorageImageMultisample);
+            }
+            self.module.capability(spirv::Capability::StorageImageReadWithoutFormat);
+            self.module.capability(spirv::Capability::StorageImageWriteWithoutFormat);
+        }
+
+        let scalar = self.module.scalar(sampled);
+        let ty = self.module.ty(spirv::Op::TypeImage, &[
+            scalar,
+            dim as u32,
+            0,
+            arrayed as u32,
+            multisampled as u32,
+            if storage { 2 } else { 1 },
+            spirv::ImageFormat::Unknown as u32,
+        ]);
+        let variable = self.module.variable(ty, spirv::StorageClass::UniformConstant, None);
+        self.bind(variable, register, if storage { UNORDERED_ACCESS_VIEW_SET } else { RESOURCE_SET });
+
+        self.resources.insert((register.file, resource_index(register)), Resource {
+            variable,
+            ty,
+            kind: ResourceKind::Typed,
+            dimension,
+            sampled,
+            stride: 0,
+            class: spirv::StorageClass::UniformConstant,
+        });
+    }
+
+    // Raw and structured buffers are runtime arrays of dwords.
+    fn declare_buffer(&mut self, register: &Register, kind: ResourceKind, stride: u32) {
+        let uav = register.file == OperandType::UnorderedAccessView;
+        let uint = self.module.scalar(Type::Uint);
+        let array = self.module.runtime_array(uint, 4);
+        let block = self.module.structure(&[array]);
+        self.module.member_decorate(block, 0, spirv::Decoration::Offset, &[0]);
+        if !uav {
+            self.module.member_decorate(block, 0, spirv::Decoration::NonWritable, &[]);
+        }
+        self.module.member_name(block, 0, "data");
+        self.module.decorate(block, spirv::Decoration::BufferBlock, &[]);
+
+        let variable = self.module.variable(block, spirv::StorageClass::Uniform, None);
+        self.bind(variable, register, if uav { UNORDERED_ACCESS_VIEW_SET } else { RESOURCE_SET });
+
+        self.resources.insert((register.file, resource_index(register)), Resource {
+            variable,
+            ty: block,
+            kind,
+            dimension: if kind == ResourceKind::Raw { ResourceDimension::RawBuffer } else { ResourceDimension::StructuredBuffer },
+            sampled: Type::Uint,
+            stride,
+            class: spirv::StorageClass::Uniform,
+        });
+    }
+
+    fn declare_shared(&mut self, register: &Register, kind: ResourceKind, stride: u32, dwords: u32) {
+        let uint = self.module.scalar(Type::Uint);
+        let array = self.module.array(uint, dwords.max(1), None);
+        let variable = self.module.variable(array, spirv::StorageClass::Workgroup, None);
+        let index = resource_index(register);
+        self.module.name(variable, &format!("g{}", index));
+
+        self.resources.insert((register.file, index), Resource {
+            variable,
+            ty: array,
+            kind,
+            dimension: ResourceDimension::Unknown,
+            sampled: Type::Uint,
+            stride,
+            class: spirv::StorageClass::Workgroup,
+        });
+    }
+
+    fn interface(&mut self) -> Result<(), Error> {
+        let program = self.program;
+
+        let mut interpolations = HashMap::new();
+        let mut declared_inputs = Vec::new();
+        let mut declared_outputs = Vec::new();
+        for declaration in &program.decls {
+            match *declaration {
+                Declaration::Input { ref register } => declared_inputs.push((register, D3D10_SB_NAME_UNDEFINED)),
+                Declaration::InputPs { ref register, interpolation } => {
+                    interpolations.insert(resource_index(register), interpolation);
+                    declared_inputs.push((register, D3D10_SB_NAME_UNDEFINED));
+                }
+                Declaration::InputPsSgv { ref register, interpolation, name } |
+                Declaration::InputPsSiv { ref register, interpolation, name } => {
+                    interpolations.insert(resource_index(register), interpolation);
+                    declared_inputs.push((register, name));
+                }
+                Declaration::InputSgv { ref register, name } | Declaration::InputSiv { ref register, name } => {
+                    declared_inputs.push((register, name))
+                }
+                Declaration::Output { ref register } => declared_outputs.push((register, D3D10_SB_NAME_UNDEFINED)),
+                Declaration::OutputSgv { ref register, name } | Declaration::OutputSiv { ref register, name } => {
+                    declared_outputs.push((register, name))
+                }
+                _ => {}
+            }
+        }
+
+        for &(register, _) in &declared_inputs {
+            if register.file != OperandType::Input {
+                self.special_input(register.file)?;
+            }
+        }
+
+        // shaders without signatures get elements from the declarations
+        let inputs = if self.shader.inputs.is_empty() { synthesize(&declared_inputs) } else { self.shader.inputs.clone() };
+        let outputs = if self.shader.outputs.is_empty() { synthesize(&declared_outputs) } else { self.shader.outputs.clone() };
+
+        self.distances(&inputs, spirv::StorageClass::Input);
+        for element in &inputs {
+            match element.semantic_type {
+                dr::SemanticName::ClipDistance | dr::SemanticName::CullDistance => {}
+                _ => self.input(element, interpolations.get(&element.register).cloned())?,
+            }
+        }
+
+        self.distances(&outputs, spirv::StorageClass::Output);
+        for element in &outputs {
+            match element.semantic_type {
+                dr::SemanticName::ClipDistance | dr::SemanticName::CullDistance => {}
+                _ => self.output(element)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    // Clip and cull distances of all elements go into one array each.
+    fn distances(&mut self, elements: &[Element], class: spirv::StorageClass) {
+        for &clip in &[true, false] {
+            let mut distances: Vec<&Element> = elements
+                .iter()
+                .filter(|element| match element.semantic_type {
+                    dr::SemanticName::ClipDistance => clip,
+                    dr::SemanticName::CullDistance => !clip,
+                    _ => false,
+                })
+                .collect();
+            if distances.is_empty() {
+                continue;
+            }
+            distances.sort_by_key(|element| element.semantic_index);
+
+            let (builtin, capability, name) = if clip {
+                (spirv::BuiltIn::ClipDistance, spirv::Capability::ClipDistance, "SV_ClipDistance")
+            } else {
+                (spirv::BuiltIn::CullDistance, spirv::Capability::CullDistance, "SV_CullDistance")
+            };
+            self.module.capability(capability);
+
+            let total = distances.iter().map(|element| element.mask.count_ones()).sum();
+            let float = self.module.scalar(Type::Float);
+            let array = self.module.array(float, total, None);
+            let variable = self.module.variable(array, class, None);
+            self.module.name(variable, name);
+            self.module.decorate(variable, spirv::Decoration::BuiltIn, &[builtin as u32]);
+            self.interface.push(variable);
+
+            let mut base = 0;
+            for element in distances {
+                let components = mask_components(element.mask);
+                let count = components.len() as u32;
+                let (file, list) = match class {
+                    spirv::StorageClass::Input => (OperandType::Input, &mut self.inputs),
+                    _ => (OperandType::Output, &mut self.outputs),
+                };
+                list.push(InterfaceVariable {
+                    variable,
+                    ty: Type::Float,
+                    width: 1,
+                    register: (file, element.register),
+                    picks: (0..count as u8).collect(),
+                    components,
+                    array: Some(base),
+                    reciprocal_w: false,
+                });
+                base += count;
+            }
+        }
+    }
+
+    fn input(&mut self, element: &Element, interpolation: Option<u32>) -> Result<(), Error> {
+        let pixel = self.model == spirv::ExecutionModel::Fragment;
+        if element.register == !0 {
+            return Ok(());
+        }
+
+        let builtin = match element.semantic_type {
+            dr::SemanticName::Position if pixel => Some((spirv::BuiltIn::FragCoord, Type::Float, 4)),
+            dr::SemanticName::VertexId => Some((spirv::BuiltIn::VertexIndex, Type::Int, 1)),
+            dr::SemanticName::InstanceId => Some((spirv::BuiltIn::InstanceIndex, Type::Int, 1)),
+            dr::SemanticName::IsFrontFace => Some((spirv::BuiltIn::FrontFacing, Type::Bool, 1)),
+            dr::SemanticName::SampleIndex => {
+                self.module.capability(spirv::Capability::SampleRateShading);
+                Some((spirv::BuiltIn::SampleId, Type::Int, 1))
+            }
+            dr::SemanticName::PrimitiveId => {
+                self.module.capability(spirv::Capability::Geometry);
+                Some((spirv::BuiltIn::PrimitiveId, Type::Int, 1))
+            }
+            dr::SemanticName::RenderTargetArrayIndex => {
+                self.module.capability(spirv::Capability::Geometry);
+                Some((spirv::BuiltIn::Layer, Type::Int, 1))
+            }
+            dr::SemanticName::ViewportArrayIndex => {
+                self.module.capability(spirv::Capability::MultiViewport);
+                Some((spirv::BuiltIn::ViewportIndex, Type::Int, 1))
+            }
+            dr::SemanticName::Undefined | dr::SemanticName::Position | dr::SemanticName::Target => None,
+            _ => return unsupported(&format!("input {}", element.name)),
+        };
+
+        let components = mask_components(element.mask);
+        let (variable, ty, width, picks) = match builtin {
+            Some((builtin, ty, width)) => {
+                let vector = self.module.vector(ty, width);
+                let variable = self.module.variable(vector, spirv::StorageClass::Input, None);
+                self.module.decorate(variable, spirv::Decoration::BuiltIn, &[builtin as u32]);
+                let picks = if width == 1 { vec![0; components.len()] } else { components.clone() };
+                (variable, ty, width, picks)
+            }
+            None => {
+                let width = components.len() as u32;
+                let variable = self.location(element, spirv::StorageClass::Input);
+                if pixel {
+                    match element.ty {
+                        Type::Float => self.interpolate(variable, interpolation),
+                        _ => self.module.decorate(variable, spirv::Decoration::Flat, &[]),
+                    }
+                }
+                (variable, element.ty, width, (0..width as u8).collect())
+            }
+        };
+        self.module.name(variable, &element.name);
+        self.interface.push(variable);
+
+        let reciprocal_w = match element.semantic_type {
+            dr::SemanticName::Position => pixel,
+            _ => false,
+        };
+        self.inputs.push(InterfaceVariable {
+            variable,
+            ty,
+            width,
+            register: (OperandType::Input, element.register),
+            components,
+            picks,
+            array: None,
+            reciprocal_w,
+        });
+
+        Ok(())
+    }
+
+    fn output(&mut self, element: &Element) -> Result<(), Error> {
+        let pixel = self.model == spirv::ExecutionModel::Fragment;
+        let components = mask_components(element.mask);
+
+        let depth = |mode: Option<spirv::ExecutionMode>| Some((spirv::BuiltIn::FragDepth, OperandType::OutputDepth, mode));
+        let builtin = match element.semantic_type {
+            dr::SemanticName::Position if !pixel => Some((spirv::BuiltIn::Position, OperandType::Output, None)),
+            dr::SemanticName::Depth => depth(None),
+            dr::SemanticName::DepthGreaterEqual => depth(Some(spirv::ExecutionMode::DepthGreater)),
+            dr::SemanticName::DepthLessEqual => depth(Some(spirv::ExecutionMode::DepthLess)),
+            dr::SemanticName::Coverage => {
+                let variable = self.sample_mask(spirv::StorageClass::Output);
+                self.outputs.push(InterfaceVariable {
+                    variable,
+                    ty: Type::Int,
+                    width: 1,
+                    register: (OperandType::OutputCoverageMask, 0),
+                    components: vec![0],
+                    picks: vec![0],
+                    array: Some(0),
+                    reciprocal_w: false,
+                });
+                return Ok(());
+            }
+            dr::SemanticName::Undefined | dr::SemanticName::Target => None,
+            _ => return unsupported(&format!("output {}", element.name)),
+        };
+
+        let (variable, width, register) = match builtin {
+            Some((builtin, file, mode)) => {
+                let width = if file == OperandType::Output { 4 } else { 1 };
+                let vector = self.module.vector(Type::Float, width);
+                let variable = self.module.variable(vector, spirv::StorageClass::Output, None);
+                self.module.decorate(variable, spirv::Decoration::BuiltIn, &[builtin as u32]);
+                if file == OperandType::OutputDepth {
+                    self.modes.push((spirv::ExecutionMode::DepthReplacing, Vec::new()));
+                    self.modes.extend(mode.map(|mode| (mode, Vec::new())));
+                }
+                let register = if file == OperandType::Output { element.register } else { 0 };
+                (variable, width, (file, register))
+            }
+            None => {
+                let variable = self.location(element, spirv::StorageClass::Output);
+                (variable, components.len() as u32, (OperandType::Output, element.register))
+            }
+        };
+        self.module.name(variable, &element.name);
+        self.interface.push(variable);
+
+        let picks = if width == 4 { components.clone() } else { (0..components.len() as u8).collect() };
+        self.outputs.push(InterfaceVariable {
+            variable,
+            ty: if builtin.is_some() { Type::Float } else { element.ty },
+            width,
+            register,
+            components,
+            picks,
+            array: None,
+            reciprocal_w: false,
+        });
+
+        Ok(())
+    }
+
+    // A user varying or render target at the location of its register.
+    fn location(&mut self, element: &Element, class: spirv::StorageClass) -> u32 {
+        let components = mask_components(element.mask);
+        let vector = self.module.vector(element.ty, components.len() as u32);
+        let variable = self.module.variable(vector, class, None);
+        self.module.decorate(variable, spirv::Decoration::Location, &[element.register]);
+        if components[0] != 0 {
+            self.module.decorate(variable, spirv::Decoration::Component, &[components[0] as u32]);
+        }
+        variable
+    }
+
+    fn interpolate(&mut self, variable: u32, interpolation: Option<u32>) {
+        let decorations: &[spirv::Decoration] = match interpolation.unwrap_or(D3D10_SB_INTERPOLATION_LINEAR) {
+            D3D10_SB_INTERPOLATION_CONSTANT => &[spirv::Decoration::Flat],
+            D3D10_SB_INTERPOLATION_LINEAR_CENTROID => &[spirv::Decoration::Centroid],
+            D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE => &[spirv::Decoration::NoPerspective],
+            D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_CENTROID => {
+                &[spirv::Decoration::NoPerspective, spirv::Decoration::Centroid]
+            }
+            D3D10_SB_INTERPOLATION_LINEAR_SAMPLE => &[spirv::Decoration::Sample],
+            D3D10_SB_INTERPOLATION_LINEAR_NOPERSPECTIVE_SAMPLE => {
+                &[spirv::Decoration::NoPerspective, spirv::Decoration::Sample]
+            }
+            _ => &[],
+        };
+
+        for &decoration in decorations {
+            if decoration == spirv::Decoration::Sample {
+                self.module.capability(spirv::Capability::SampleRateShading);
+            }
+            self.module.decorate(variable, decoration, &[]);
+        }
+    }
+
+    fn sample_mask(&mut self, class: spirv::StorageClass) -> u32 {
+        let int = self.module.scalar(Type::Int);
+        let array = self.module.array(int, 1, None);
+        let variable = self.module.variable(array, class, None);
+        self.module.name(variable, "SV_Coverage");
+        self.module.decorate(variable, spirv::Decoration::BuiltIn, &[spirv::BuiltIn::SampleMask as u32]);
+        self.interface.push(variable);
+        variable
+    }
+
+    // Compute shader thread ids and the input coverage of pixel shaders.
+    fn special_input(&mut self, file: OperandType) -> Result<(), Error> {
+        let (builtin, width) = match file {
+            OperandType::InputThreadId => (spirv::BuiltIn::GlobalInvocationId, 3),
+            OperandType::InputThreadGroupId => (spirv::BuiltIn::WorkgroupId, 3),
+            OperandType::InputThreadIdInGroup => (spirv::BuiltIn::LocalInvocationId, 3),
+            OperandType::InputThreadIdInGroupFlattened => (spirv::BuiltIn::LocalInvocationIndex, 1),
+            OperandType::InputCoverageMask => {
+                let variable = self.sample_mask(spirv::StorageClass::Input);
+                self.inputs.push(InterfaceVariable {
+                    variable,
+                    ty: Type::Int,
+                    width: 1,
+                    register: (file, 0),
+                    components: vec![0],
+                    picks: vec![0],
+                    array: Some(0),
+                    reciprocal_w: false,
+                });
+                return Ok(());
+            }
+            _ => return unsupported(&format!("input {}", operand_prefix(file as u32))),
+        };
+
+        let vector = self.module.vector(Type::Uint, width);
+        let variable = self.module.variable(vector, spirv::StorageClass::Input, None);
+        self.module.name(variable, operand_prefix(file as u32));
+        self.module.decorate(variable, spirv::Decoration::BuiltIn, &[builtin as u32]);
+        self.interface.push(variable);
+        self.inputs.push(InterfaceVariable {
+            variable,
+            ty: Type::Uint,
+            width,
+            register: (file, 0),
+            components: (0..width as u8).collect(),
+            picks: (0..width as u8).collect(),
+            array: None,
+            reciprocal_w: false,
+        });
+
+        Ok(())
+    }
+
+    // Pointer to a component of a clip distance, cull distance or sample
+    // mask array.
+    fn array_element(&mut self, interface: &InterfaceVariable, class: spirv::StorageClass, k: u32) -> u32 {
+        let scalar = self.module.scalar(interface.ty);
+        let pointer = self.module.pointer(class, scalar);
+        let index = self.module.constant(Type::Uint, interface.array.unwrap_or(0) + k);
+        self.module.emit(spirv::Op::AccessChain, pointer, &[interface.variable, index])
+    }
+
+    fn copy_inputs(&mut self) -> Result<(), Error> {
+        for input in self.inputs.clone() {
+            let value = match input.array {
+                Some(_) => {
+                    let parts: Vec<Value> = (0..input.components.len() as u32)
+                        .map(|k| {
+                            let pointer = self.array_element(&input, spirv::StorageClass::Input, k);
+                            self.module.load(input.ty, 1, pointer)
+                        })
+                        .collect();
+                    self.module.construct(&parts)
+                }
+                None => {
+                    let value = self.module.load(input.ty, input.width, input.variable);
+                    self.module.shuffle(value, &input.picks)
+                }
+            };
+            let mut value = self.module.bitcast(value, Type::Float);
+
+            if input.reciprocal_w {
+                if let Some(n) = input.components.iter().position(|&c| c == 3) {
+                    let float = self.module.scalar(Type::Float);
+                    let one = self.module.constant(Type::Float, 1.0f32.to_bits());
+                    let w = self.module.extract(value, n as u32);
+                    let id = self.module.emit(spirv::Op::FDiv, float, &[one, w.id]);
+                    let reciprocal = Value { id, ty: Type::Float, width: 1 };
+                    value = if value.width == 1 { reciprocal } else { self.module.insert(value, &[n as u8], reciprocal) };
+                }
+            }
+
+            let (file, index) = input.register;
+            let pointer = self.register_variable(file, index);
+            self.write(pointer, &input.components, value);
+        }
+
+        Ok(())
+    }
+
+    fn copy_outputs(&mut self) -> Result<(), Error> {
+        for output in self.outputs.clone() {
+            let (file, index) = output.register;
+            let pointer = self.register_variable(file, index);
+            let register = self.module.load(Type::Float, 4, pointer);
+            let value = self.module.shuffle(register, &output.components);
+            let value = self.module.bitcast(value, output.ty);
+
+            match output.array {
+                Some(_) => {
+                    for k in 0..output.components.len() as u32 {
+                        let pointer = self.array_element(&output, spirv::StorageClass::Output, k);
+                        let part = self.module.extract(value, k);
+                        self.module.emit_void(spirv::Op::Store, &[pointer, part.id]);
+                    }
+                }
+                None => {
+                    let whole = output.picks.len() as u32 == output.width &&
+                        output.picks.iter().enumerate().all(|(c, &pick)| c == pick as usize);
+                    let value = if whole {
+                        value
+                    } else {
+                        let current = self.module.load(output.ty, output.width, output.variable);
+                        self.module.insert(current, &output.picks, value)
+                    };
+                    self.module.emit_void(spirv::Op::Store, &[output.variable, value.id]);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn register_variable(&mut self, file: OperandType, index: u32) -> u32 {
+        let file = match file {
+            OperandType::OutputDepthGreaterEqual | OperandType::OutputDepthLessEqual => OperandType::OutputDepth,
+            file => file,
+        };
+        if let Some(&variable) = self.registers.get(&(file, index)) {
+            return variable;
+        }
+
+        let vec4 = self.module.vector(Type::Float, 4);
+        let null = self.module.null(vec4);
+        let variable = self.module.variable(vec4, spirv::StorageClass::Private, Some(null));
+        let name = match file {
+            OperandType::Temp | OperandType::Input | OperandType::Output => {
+                format!("{}{}", operand_prefix(file as u32), index)
+            }
+            _ => operand_prefix(file as u32).to_owned(),
+        };
+        self.module.name(variable, &name);
+        self.registers.insert((file, index), variable);
+        variable
+    }
+
+    // Pointer to the four floats of a register.
+    fn register_pointer(&mut self, register: &Register) -> Result<u32, Error> {
+        match register.file {
+            OperandType::IndexableTemp => {
+                let array = match self.indexable.get(&resource_index(register)) {
+                    Some(&array) => array,
+                    None => return unsupported("undeclared indexable temp"),
+                };
+                let index = match register.index.get(1) {
+                    Some(index) => self.index(index)?,
+                    None => self.module.constant(Type::Uint, 0),
+                };
+                let vec4 = self.module.vector(Type::Float, 4);
+                let pointer = self.module.pointer(spirv::StorageClass::Private, vec4);
+                Ok(self.module.emit(spirv::Op::AccessChain, pointer, &[array, index]))
+            }
+            file => {
+                if register.index.iter().any(|index| index.relative().is_some()) {
+                    return unsupported(&format!("relative indexing of {} registers", operand_prefix(file as u32)));
+                }
+                Ok(self.register_variable(file, resource_index(register)))
+            }
+        }
+    }
+
+    // An index as a uint, adding the value of its relative register.
+    fn index(&mut self, index: &Index) -> Result<u32, Error> {
+        let offset = self.module.constant(Type::Uint, index.offset() as u32);
+        match index.relative() {
+            Some(register) => {
+                let pick = match register.components {
+                    Components::Scalar | Components::None => 0,
+                    components => components.swizzle()[0],
+                };
+                let value = self.register_value(register, &[pick])?;
+                let value = self.module.bitcast(value, Type::Uint);
+                if index.offset() == 0 {
+                    return Ok(value.id);
+                }
+                let uint = self.module.scalar(Type::Uint);
+                Ok(self.module.emit(spirv::Op::IAdd, uint, &[value.id, offset]))
+            }
+            None => Ok(offset),
+        }
+    }
+
+    // The `picks` components of a register, before modifiers.
+    fn register_value(&mut self, register: &Register, picks: &[u8]) -> Result<Value, Error> {
+        match register.file {
+            OperandType::ConstantBuffer => self.constant_buffer_value(register, picks),
+            OperandType::ImmediateConstantBuffer => {
+                let array = match self.immediate_constant_buffer {
+                    Some(array) => array,
+                    None => return unsupported("undeclared immediate constant buffer"),
+                };
+                let index = match register.index.first() {
+                    Some(index) => self.index(index)?,
+                    None => self.module.constant(Type::Uint, 0),
+                };
+                let uvec4 = self.module.vector(Type::Uint, 4);
+                let pointer = self.module.pointer(spirv::StorageClass::Private, uvec4);
+                let pointer = self.module.emit(spirv::Op::AccessChain, pointer, &[array, index]);
+                let value = self.module.load(Type::Uint, 4, pointer);
+                Ok(self.module.shuffle(value, picks))
+            }
+            _ => {
+                let pointer = self.register_pointer(register)?;
+                let value = self.module.load(Type::Float, 4, pointer);
+                Ok(self.module.shuffle(value, picks))
+            }
+        }
+    }
+
+    fn constant_buffer_value(&mut self, register: &Register, picks: &[u8]) -> Result<Value, Error> {
+        let slot = resource_index(register);
+        if register.index.first().and_then(Index::relative).is_some() {
+            return unsupported("dynamic constant buffer slots");
+        }
+        let location = match register.index.get(1) {
+            Some(location) => location,
+            None => return unsupported("constant buffer operand without an index"),
+        };
+
+        let (variable, members) = match self.constant_buffers.get(&slot) {
+            Some(buffer) => {
+                let members = buffer.members.as_ref().map(|members| {
+                    let base = location.offset() as u32 * 4;
+                    picks.iter().map(|&c| members.get(&(base + c as u32)).cloned()).collect::<Vec<_>>()
+                });
+                (buffer.variable, members)
+            }
+            None => return unsupported("undeclared constant buffer"),
+        };
+
+        match members {
+            None => {
+                let zero = self.module.constant(Type::Uint, 0);
+                let index = self.index(location)?;
+                let vec4 = self.module.vector(Type::Float, 4);
+                let pointer = self.module.pointer(spirv::StorageClass::Uniform, vec4);
+                let pointer = self.module.emit(spirv::Op::AccessChain, pointer, &[variable, zero, index]);
+                let value = self.module.load(Type::Float, 4, pointer);
+                Ok(self.module.shuffle(value, picks))
+            }
+            Some(_) if location.relative().is_some() => unsupported("relative indexing of a constant buffer with members"),
+            Some(members) => {
+                // mixed types are read as uint, the caller bitcasts anyway
+                let mut types = members.iter().filter_map(|member| member.as_ref().map(|member| member.ty));
+                let first = types.next().unwrap_or(Type::Uint);
+                let ty = if types.all(|ty| ty == first) { first } else { Type::Uint };
+
+                let mut parts = Vec::new();
+                for member in members {
+                    // padding between the variables reads as zero
+                    let part = match member {
+                        Some(member) => {
+                            let mut operands = vec![variable];
+                            for &index in &member.indices {
+                                operands.push(self.module.constant(Type::Uint, index));
+                            }
+                            let scalar = self.module.scalar(member.ty);
+                            let pointer = self.module.pointer(spirv::StorageClass::Uniform, scalar);
+                            let pointer = self.module.emit(spirv::Op::AccessChain, pointer, &operands);
+                            let value = self.module.load(member.ty, 1, pointer);
+                            self.module.bitcast(value, ty)
+                        }
+                        None => Value { id: self.module.constant(ty, 0), ty, width: 1 },
+                    };
+                    parts.push(part);
+                }
+                Ok(self.module.construct(&parts))
+            }
+        }
+    }
+
+    // Reads the register components for each of `components` as `ty`, with
+    // the operand modifier applied.
+    fn register_source(&mut self, register: &Register, components: &[u8], ty: Type) -> Result<Value, Error> {
+        let picks: Vec<u8> = match register.components {
+            Components::Scalar | Components::None => vec![0; components.len()],
+            _ => {
+                let swizzle = register.components.swizzle();
+                components.iter().map(|&c| swizzle[c as usize]).collect()
+            }
+        };
+        let value = self.register_value(register, &picks)?;
+
+        let value = match register.modifier {
+            Modifier::None => value,
+            modifier => {
+                let float = ty == Type::Float;
+                let mut value = self.module.bitcast(value, if float { Type::Float } else { Type::Int });
+                let result = self.module.vector(value.ty, value.width);
+                if modifier == Modifier::Abs || modifier == Modifier::AbsNeg {
+                    value.id = self.module.ext(result, if float { GLSL_FABS } else { GLSL_SABS }, &[value.id]);
+                }
+                if modifier == Modifier::Neg || modifier == Modifier::AbsNeg {
+                    let op = if float { spirv::Op::FNegate } else { spirv::Op::SNegate };
+                    value.id = self.module.emit(op, result, &[value.id]);
+                }
+                value
+            }
+        };
+
+        Ok(self.module.bitcast(value, ty))
+    }
+
+    fn source(&mut self, operand: &Operand, components: &[u8], ty: Type) -> Result<Value, Error> {
+        match *operand {
+            Operand::Register(ref register) => self.register_source(register, components, ty),
+            Operand::Imm32(ref values) => {
+                let parts: Vec<Value> = components
+                    .iter()
+                    .map(|&c| {
+                        let bits = values.get(c as usize).or_else(|| values.first()).cloned().unwrap_or(0);
+                        Value { id: self.module.constant(ty, bits), ty, width: 1 }
+                    })
+                    .collect();
+                Ok(self.module.construct(&parts))
+            }
+            Operand::Imm64(_) => unsupported("64-bit immediates"),
+        }
+    }
+
+    // Writes a value to the destination operand, saturating it if the
+    // instruction asks for it.
+    fn store(&mut self, i: usize, operand: usize, value: Value) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let register = match instruction.operands[operand] {
+            Operand::Register(ref register) if register.file != OperandType::Null => register,
+            _ => return Ok(()),
+        };
+        let components = mask_components(register.components.mask());
+
+        let mut value = self.module.bitcast(value, Type::Float);
+        if instruction.saturate {
+            let result = self.module.vector(Type::Float, value.width);
+            let zero = self.module.splat(Type::Float, 0, value.width);
+            let one = self.module.splat(Type::Float, 1.0f32.to_bits(), value.width);
+            value.id = self.module.ext(result, GLSL_FCLAMP, &[value.id, zero, one]);
+        }
+
+        let pointer = self.register_pointer(register)?;
+        self.write(pointer, &components, value);
+        Ok(())
+    }
+
+    // Writes the `components` of the four floats behind `pointer`.
+    fn write(&mut self, pointer: u32, components: &[u8], value: Value) {
+        let value = if components == [0, 1, 2, 3] {
+            value
+        } else {
+            let current = self.module.load(Type::Float, 4, pointer);
+            self.module.insert(current, components, value)
+        };
+        self.module.emit_void(spirv::Op::Store, &[pointer, value.id]);
+    }
+
+    fn resource(&self, operand: &Operand) -> Result<Resource, Error> {
+        let register = match operand.register() {
+            Some(register) => register,
+            None => return unsupported("resource operand"),
+        };
+
+        match self.resources.get(&(register.file, resource_index(register))) {
+            Some(&resource) => Ok(resource),
+            None => unsupported(&format!("undeclared {}{}", operand_prefix(register.file as u32), resource_index(register))),
+        }
+    }
+
+    // Applies `f` to the sources read for the written components.
+    fn componentwise(&mut self, i: usize, sources: &[(usize, Type)], result: Type, f: Operation) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let components = match written(&instruction.operands[0]) {
+            Some(components) => components,
+            None => return Ok(()),
+        };
+
+        let mut arguments = Vec::new();
+        for &(operand, ty) in sources {
+            arguments.push(self.source(&instruction.operands[operand], &components, ty)?.id);
+        }
+        let width = components.len() as u32;
+        let ty = self.module.vector(result, width);
+        let id = f(&mut self.module, ty, &arguments);
+        self.store(i, 0, Value { id, ty: result, width })
+    }
+
+    // Applies `f` to each written component on its own, for instructions
+    // without a vector form in SPIR-V.
+    fn scalarwise(&mut self, i: usize, sources: &[(usize, Type)], result: Type, f: Operation) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let components = match written(&instruction.operands[0]) {
+            Some(components) => components,
+            None => return Ok(()),
+        };
+
+        let mut parts = Vec::new();
+        for &c in &components {
+            let mut arguments = Vec::new();
+            for &(operand, ty) in sources {
+                arguments.push(self.source(&instruction.operands[operand], &[c], ty)?.id);
+            }
+            let ty = self.module.scalar(result);
+            let id = f(&mut self.module, ty, &arguments);
+            parts.push(Value { id, ty: result, width: 1 });
+        }
+        let value = self.module.construct(&parts);
+        self.store(i, 0, value)
+    }
+
+    fn unary(&mut self, i: usize, from: Type, to: Type, op: spirv::Op) -> Result<(), Error> {
+        self.componentwise(i, &[(1, from)], to, &|module, ty, arguments| module.emit(op, ty, arguments))
+    }
+
+    fn binary(&mut self, i: usize, ty: Type, op: spirv::Op) -> Result<(), Error> {
+        self.componentwise(i, &[(1, ty), (2, ty)], ty, &|module, ty, arguments| module.emit(op, ty, arguments))
+    }
+
+    fn compare(&mut self, i: usize, ty: Type, op: spirv::Op) -> Result<(), Error> {
+        self.componentwise(i, &[(1, ty), (2, ty)], Type::Bool, &|module, ty, arguments| module.emit(op, ty, arguments))
+    }
+
+    fn extended(&mut self, i: usize, ty: Type, instruction: u32) -> Result<(), Error> {
+        let count = self.program.instructions[i].operands.len();
+        let sources: Vec<(usize, Type)> = (1..count).map(|operand| (operand, ty)).collect();
+        self.componentwise(i, &sources, ty, &|module, ty, arguments| module.ext(ty, instruction, arguments))
+    }
+
+    // Shift counts only use their low five bits in D3D.
+    fn shift(&mut self, i: usize, ty: Type, op: spirv::Op) -> Result<(), Error> {
+        self.componentwise(i, &[(1, ty), (2, Type::Uint)], ty, &|module, ty, arguments| {
+            let width = module.shapes[&ty].1;
+            let uint = module.vector(Type::Uint, width);
+            let mask = module.splat(Type::Uint, 31, width);
+            let count = module.emit(spirv::Op::BitwiseAnd, uint, &[arguments[1], mask]);
+            module.emit(op, ty, &[arguments[0], count])
+        })
+    }
+
+    // D3D counts the bit position of `firstbit_hi` from the most
+    // significant end.
+    fn first_bit_high(&mut self, i: usize, ty: Type, instruction: u32) -> Result<(), Error> {
+        self.componentwise(i, &[(1, ty)], Type::Uint, &|module, ty, arguments| {
+            let width = module.shapes[&ty].1;
+            let boolean = module.vector(Type::Bool, width);
+            let msb = module.ext(ty, instruction, arguments);
+            let none = module.constant_as(ty, !0);
+            let top = module.constant_as(ty, 31);
+            let missing = module.emit(spirv::Op::IEqual, boolean, &[msb, none]);
+            let position = module.emit(spirv::Op::ISub, ty, &[top, msb]);
+            module.emit(spirv::Op::Select, ty, &[missing, none, position])
+        })
+    }
+
+    fn dot(&mut self, i: usize, count: u8) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let components = match written(&instruction.operands[0]) {
+            Some(components) => components,
+            None => return Ok(()),
+        };
+
+        let lanes: Vec<u8> = (0..count).collect();
+        let a = self.source(&instruction.operands[1], &lanes, Type::Float)?;
+        let b = self.source(&instruction.operands[2], &lanes, Type::Float)?;
+        let float = self.module.scalar(Type::Float);
+        let id = if count == 1 {
+            self.module.emit(spirv::Op::FMul, float, &[a.id, b.id])
+        } else {
+            self.module.emit(spirv::Op::Dot, float, &[a.id, b.id])
+        };
+        let value = self.module.shuffle(Value { id, ty: Type::Float, width: 1 }, &vec![0; components.len()]);
+        self.store(i, 0, value)
+    }
+
+    // Instructions with two destinations read the sources of both before
+    // writing either as the destinations may overlap the sources.
+    fn multiple(&mut self, i: usize, sources: &[(usize, Type)], results: &[(Type, Operation)]) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+
+        let mut values = Vec::new();
+        for (d, &(result, f)) in results.iter().enumerate() {
+            let components = match written(&instruction.operands[d]) {
+                Some(components) => components,
+                None => continue,
+            };
+            let mut arguments = Vec::new();
+            for &(operand, ty) in sources {
+                arguments.push(self.source(&instruction.operands[operand], &components, ty)?.id);
+            }
+            let width = components.len() as u32;
+            let ty = self.module.vector(result, width);
+            let id = f(&mut self.module, ty, &arguments);
+            values.push((d, Value { id, ty: result, width }));
+        }
+
+        for (d, value) in values {
+            self.store(i, d, value)?;
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self, i: usize) -> Result<u32, Error> {
+        let instruction = &self.program.instructions[i];
+        let value = self.source(&instruction.operands[0], &[0], Type::Uint)?;
+        let zero = self.module.constant(Type::Uint, 0);
+        let boolean = self.module.scalar(Type::Bool);
+        let op = match instruction.test {
+            Some(TestBoolean::Zero) => spirv::Op::IEqual,
+            _ => spirv::Op::INotEqual,
+        };
+        Ok(self.module.emit(op, boolean, &[value.id, zero]))
+    }
+
+    fn immediate_offset(&mut self, i: usize, offsets: u32) -> Option<u32> {
+        let program = self.program;
+        for extended in &program.instructions[i].extended {
+            if let OpcodeEx::UvOffset(u, v, w) = *extended {
+                if offsets == 0 || (u, v, w) == (0, 0, 0) {
+                    return None;
+                }
+                let parts: Vec<u32> = [u, v, w][..offsets as usize].iter().map(|&x| self.module.constant(Type::Int, x)).collect();
+                if offsets == 1 {
+                    return Some(parts[0]);
+                }
+                let ty = self.module.vector(Type::Int, offsets);
+                return Some(self.module.composite(ty, &parts));
+            }
+        }
+
+        None
+    }
+
+    // Writes the destination components picked by the resource swizzle.
+    fn assign_resource(&mut self, i: usize, resource: usize, value: Value) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let components = match written(&instruction.operands[0]) {
+            Some(components) => components,
+            None => return Ok(()),
+        };
+        let picks = resource_picks(&instruction.operands[resource], &components);
+        let value = self.module.shuffle(value, &picks);
+        self.store(i, 0, value)
+    }
+
+    fn load_image(&mut self, resource: &Resource) -> u32 {
+        self.module.emit(spirv::Op::Load, resource.ty, &[resource.variable])
+    }
+
+    fn sample(&mut self, i: usize) -> Result<(), Error> {
+        let program = self.program;
+        let instruction = &program.instructions[i];
+        let opcode = instruction.opcode;
+        let operands = &instruction.operands;
+
+        let (offset, resource, sampler) = match opcode {
+            D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C => (Some(2), 3, 4),
+            _ => (None, 2, 3),
+        };
+        let extra = sampler + 1;
+
+        let texture = self.resource(&operands[resource])?;
+        let (coordinates, offsets) = texture_coordinates(texture.dimension);
+        let coordinate_components: Vec<u8> = (0..coordinates as u8).collect();
+        let coordinate = self.source(&operands[1], &coordinate_components, Type::Float)?.id;
+
+        let sampler_resource = self.resource(&operands[sampler])?;
+        let image = self.load_image(&texture);
+        let sampler_value = self.load_image(&sampler_resource);
+        let sampled_image_type = self.module.ty(spirv::Op::TypeSampledImage, &[texture.ty]);
+        let sampled_image = self.module.emit(spirv::Op::SampledImage, sampled_image_type, &[image, sampler_value]);
+
+        let mut arguments = vec![sampled_image, coordinate];
+        // image operands follow in the order of their mask bits
+        let mut mask = spirv::ImageOperands::NONE;
+        let mut image_operands = Vec::new();
+
+        let op = match opcode {
+            D3D10_SB_OPCODE_SAMPLE => spirv::Op::ImageSampleImplicitLod,
+            D3D10_SB_OPCODE_SAMPLE_B => {
+                mask |= spirv::ImageOperands::BIAS;
+                image_operands.push(self.source(&operands[extra], &[0], Type::Float)?.id);
+                spirv::Op::ImageSampleImplicitLod
+            }
+            D3D10_SB_OPCODE_SAMPLE_L => {
+                mask |= spirv::ImageOperands::LOD;
+                image_operands.push(self.source(&operands[extra], &[0], Type::Float)?.id);
+                spirv::Op::ImageSampleExplicitLod
+            }
+            D3D10_SB_OPCODE_SAMPLE_D => {
+                let gradients = match texture.dimension {
+                    ResourceDimension::TextureCube | ResourceDimension::TextureCubeArray => 3,
+                    _ => offsets.max(1),
+                };
+                let components: Vec<u8> = (0..gradients as u8).collect();
+                mask |= spirv::ImageOperands::GRAD;
+                image_operands.push(self.source(&operands[extra], &components, Type::Float)?.id);
+                image_operands.push(self.source(&operands[extra + 1], &components, Type::Float)?.id);
+                spirv::Op::ImageSampleExplicitLod
+            }
+            D3D10_SB_OPCODE_SAMPLE_C => {
+                arguments.push(self.source(&operands[extra], &[0], Type::Float)?.id);
+                spirv::Op::ImageSampleDrefImplicitLod
+            }
+            D3D10_SB_OPCODE_SAMPLE_C_LZ => {
+                arguments.push(self.source(&operands[extra], &[0], Type::Float)?.id);
+                mask |= spirv::ImageOperands::LOD;
+                image_operands.push(self.module.constant(Type::Float, 0));
+                spirv::Op::ImageSampleDrefExplicitLod
+            }
+            D3D10_1_SB_OPCODE_GATHER4 | D3D11_SB_OPCODE_GATHER4_PO => {
+                let channel = operands[sampler].register().map_or(0, |register| register.components.swizzle()[0]);
+                arguments.push(self.module.constant(Type::Uint, channel as u32));
+                spirv::Op::ImageGather
+            }
+            _ => {
+                arguments.push(self.source(&operands[extra], &[0], Type::Float)?.id);
+                spirv::Op::ImageDrefGather
+            }
+        };
+
+        match offset {
+            Some(offset) => {
+                let components: Vec<u8> = (0..offsets as u8).collect();
+                self.module.capability(spirv::Capability::ImageGatherExtended);
+                mask |= spirv::ImageOperands::OFFSET;
+                image_operands.push(self.source(&operands[offset], &components, Type::Int)?.id);
+            }
+            None => {
+                if let Some(offset) = self.immediate_offset(i, offsets) {
+                    mask |= spirv::ImageOperands::CONST_OFFSET;
+                    image_operands.push(offset);
+                }
+            }
+        }
+
+        if !image_operands.is_empty() {
+            arguments.push(mask.bits());
+            arguments.extend(image_operands);
+        }
+
+        match op {
+            spirv::Op::ImageSampleDrefImplicitLod | spirv::Op::ImageSampleDrefExplicitLod => {
+                let float = self.module.scalar(Type::Float);
+                let id = self.module.emit(op, float, &arguments);
+                let components = match written(&operands[0]) {
+                    Some(components) => components,
+                    None => return Ok(()),
+                };
+                let value = self.module.shuffle(Value { id, ty: Type::Float, width: 1 }, &vec![0; components.len()]);
+                self.store(i, 0, value)
+            }
+            _ => {
+                let ty = if op == spirv::Op::ImageDrefGather { Type::Float } else { texture.sampled };
+                let vec4 = self.module.vector(ty, 4);
+                let id = self.module.emit(op, vec4, &arguments);
+                self.assign_resource(i, resource, Value { id, ty, width: 4 })
+            }
+        }
+    }
+
+    fn load(&mut self, i: usize) -> Result<(), Error> {
+        let program = self.program;
+        let instruction = &program.instructions[i];
+        let operands = &instruction.operands;
+        let texture = self.resource(&operands[2])?;
+        let (coordinates, offsets) = texture_coordinates(texture.dimension);
+        let multisampled = instruction.opcode == D3D10_SB_OPCODE_LD_MS;
+
+        let components: Vec<u8> = (0..coordinates as u8).collect();
+        let coordinate = self.source(&operands[1], &components, Type::Int)?.id;
+        let image = self.load_image(&texture);
+
+        let mut mask = spirv::ImageOperands::NONE;
+        let mut image_operands = Vec::new();
+        // the mip level is always in the last address component
+        if texture.dimension != ResourceDimension::Buffer && !multisampled {
+            mask |= spirv::ImageOperands::LOD;
+            image_operands.push(self.source(&operands[1], &[3], Type::Int)?.id);
+        }
+        if texture.dimension != ResourceDimension::Buffer {
+            if let Some(offset) = self.immediate_offset(i, offsets) {
+                mask |= spirv::ImageOperands::CONST_OFFSET;
+                image_operands.push(offset);
+            }
+        }
+        if multisampled {
+            mask |= spirv::ImageOperands::SAMPLE;
+            image_operands.push(self.source(&operands[3], &[0], Type::Int)?.id);
+        }
+
+        let mut arguments = vec![image, coordinate];
+        if !image_operands.is_empty() {
+            arguments.push(mask.bits());
+            arguments.extend(image_operands);
+        }
+
+        let vec4 = self.module.vector(texture.sampled, 4);
+        let id = self.module.emit(spirv::Op::ImageFetch, vec4, &arguments);
+        self.assign_resource(i, 2, Value { id, ty: texture.sampled, width: 4 })
+    }
+
+    fn typed_coordinate(&mut self, uav: &Resource, address: &Operand) -> Result<u32, Error> {
+        let (coordinates, _) = texture_coordinates(uav.dimension);
+        let components: Vec<u8> = (0..coordinates as u8).collect();
+        Ok(self.source(address, &components, Type::Int)?.id)
+    }
+
+    fn load_typed(&mut self, i: usize) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let uav = self.resource(&instruction.operands[2])?;
+        let coordinate = self.typed_coordinate(&uav, &instruction.operands[1])?;
+        let image = self.load_image(&uav);
+        let vec4 = self.module.vector(uav.sampled, 4);
+        let id = self.module.emit(spirv::Op::ImageRead, vec4, &[image, coordinate]);
+        self.assign_resource(i, 2, Value { id, ty: uav.sampled, width: 4 })
+    }
+
+    fn store_typed(&mut self, i: usize) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let uav = self.resource(&instruction.operands[0])?;
+        let coordinate = self.typed_coordinate(&uav, &instruction.operands[1])?;
+        let texel = self.source(&instruction.operands[2], &[0, 1, 2, 3], uav.sampled)?;
+        let image = self.load_image(&uav);
+        self.module.emit_void(spirv::Op::ImageWrite, &[image, coordinate, texel.id]);
+        Ok(())
+    }
+
+    // Dword index of a byte offset into a raw buffer or of an element and a
+    // byte offset into a structured buffer.
+    fn address(&mut self, resource: &Resource, index: Option<&Operand>, offset: &Operand, lane: u8) -> Result<u32, Error> {
+        let uint = self.module.scalar(Type::Uint);
+        let two = self.module.constant(Type::Uint, 2);
+        let offset = self.source(offset, &[lane], Type::Uint)?.id;
+        let mut dword = self.module.emit(spirv::Op::ShiftRightLogical, uint, &[offset, two]);
+
+        if let Some(index) = index {
+            let index = self.source(index, &[0], Type::Uint)?.id;
+            let stride = self.module.constant(Type::Uint, resource.stride);
+            let base = self.module.emit(spirv::Op::IMul, uint, &[index, stride]);
+            dword = self.module.emit(spirv::Op::IAdd, uint, &[base, dword]);
+        }
+
+        Ok(dword)
+    }
+
+    // Pointer to dword `dword + component` of a buffer or shared memory.
+    fn memory(&mut self, resource: &Resource, dword: u32, component: u8) -> u32 {
+        let uint = self.module.scalar(Type::Uint);
+        let dword = if component == 0 {
+            dword
+        } else {
+            let component = self.module.constant(Type::Uint, component as u32);
+            self.module.emit(spirv::Op::IAdd, uint, &[dword, component])
+        };
+
+        let pointer = self.module.pointer(resource.class, uint);
+        if resource.class == spirv::StorageClass::Workgroup {
+            self.module.emit(spirv::Op::AccessChain, pointer, &[resource.variable, dword])
+        } else {
+            let zero = self.module.constant(Type::Uint, 0);
+            self.module.emit(spirv::Op::AccessChain, pointer, &[resource.variable, zero, dword])
+        }
+    }
+
+    fn load_memory(&mut self, i: usize) -> Result<(), Error> {
+        let program = self.program;
+        let instruction = &program.instructions[i];
+        let operands = &instruction.operands;
+        let structured = instruction.opcode == D3D11_SB_OPCODE_LD_STRUCTURED;
+        let (index, offset, operand) = if structured { (Some(&operands[1]), &operands[2], 3) } else { (None, &operands[1], 2) };
+
+        let components = match written(&operands[0]) {
+            Some(components) => components,
+            None => return Ok(()),
+        };
+        let resource = self.resource(&operands[operand])?;
+        let dword = self.address(&resource, index, offset, 0)?;
+
+        let mut parts = Vec::new();
+        for pick in resource_picks(&operands[operand], &components) {
+            let pointer = self.memory(&resource, dword, pick);
+            parts.push(self.module.load(Type::Uint, 1, pointer));
+        }
+        let value = self.module.construct(&parts);
+        self.store(i, 0, value)
+    }
+
+    fn store_memory(&mut self, i: usize) -> Result<(), Error> {
+        let program = self.program;
+        let instruction = &program.instructions[i];
+        let operands = &instruction.operands;
+        let structured = instruction.opcode == D3D11_SB_OPCODE_STORE_STRUCTURED;
+        let (index, offset, value) = if structured { (Some(&operands[1]), &operands[2], 3) } else { (None, &operands[1], 2) };
+
+        let resource = self.resource(&operands[0])?;
+        let components = mask_components(operands[0].register().map_or(1, |register| register.components.mask()));
+        let dword = self.address(&resource, index, offset, 0)?;
+        let value = self.source(&operands[value], &components, Type::Uint)?;
+
+        for (n, &c) in components.iter().enumerate() {
+            let pointer = self.memory(&resource, dword, c);
+            let part = self.module.extract(value, n as u32);
+            self.module.emit_void(spirv::Op::Store, &[pointer, part.id]);
+        }
+        Ok(())
+    }
+
+    fn atomic(&mut self, i: usize) -> Result<(), Error> {
+        let program = self.program;
+        let instruction = &program.instructions[i];
+        let operands = &instruction.operands;
+        let opcode = instruction.opcode;
+        let immediate = opcode >= D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC;
+        let first = if immediate { 1 } else { 0 };
+
+        if opcode == D3D11_SB_OPCODE_IMM_ATOMIC_ALLOC || opcode == D3D11_SB_OPCODE_IMM_ATOMIC_CONSUME {
+            return unsupported("UAV counters");
+        }
+
+        let resource = self.resource(&operands[first])?;
+        let address = &operands[first + 1];
+        let (ty, pointer) = match resource.kind {
+            ResourceKind::Typed => {
+                let coordinate = self.typed_coordinate(&resource, address)?;
+                let sample = self.module.constant(Type::Uint, 0);
+                let scalar = self.module.scalar(resource.sampled);
+                let pointer = self.module.pointer(spirv::StorageClass::Image, scalar);
+                let pointer = self.module.emit(spirv::Op::ImageTexelPointer, pointer, &[resource.variable, coordinate, sample]);
+                (resource.sampled, pointer)
+            }
+            ResourceKind::Raw => {
+                let dword = self.address(&resource, None, address, 0)?;
+                (Type::Uint, self.memory(&resource, dword, 0))
+            }
+            ResourceKind::Structured => {
+                let dword = self.address(&resource, Some(address), address, 1)?;
+                (Type::Uint, self.memory(&resource, dword, 0))
+            }
+        };
+
+        let scope = if resource.class == spirv::StorageClass::Workgroup { spirv::Scope::Workgroup } else { spirv::Scope::Device };
+        let scope = self.module.constant(Type::Uint, scope as u32);
+        let relaxed = self.module.constant(Type::Uint, spirv::MemorySemantics::NONE.bits());
+
+        let mut values = Vec::new();
+        for operand in &operands[first + 2..] {
+            values.push(self.source(operand, &[0], ty)?.id);
+        }
+
+        let op = match opcode {
+            D3D11_SB_OPCODE_ATOMIC_AND | D3D11_SB_OPCODE_IMM_ATOMIC_AND => spirv::Op::AtomicAnd,
+            D3D11_SB_OPCODE_ATOMIC_OR | D3D11_SB_OPCODE_IMM_ATOMIC_OR => spirv::Op::AtomicOr,
+            D3D11_SB_OPCODE_ATOMIC_XOR | D3D11_SB_OPCODE_IMM_ATOMIC_XOR => spirv::Op::AtomicXor,
+            D3D11_SB_OPCODE_ATOMIC_CMP_STORE | D3D11_SB_OPCODE_IMM_ATOMIC_CMP_EXCH => spirv::Op::AtomicCompareExchange,
+            D3D11_SB_OPCODE_IMM_ATOMIC_EXCH => spirv::Op::AtomicExchange,
+            D3D11_SB_OPCODE_ATOMIC_IADD | D3D11_SB_OPCODE_IMM_ATOMIC_IADD => spirv::Op::AtomicIAdd,
+            D3D11_SB_OPCODE_ATOMIC_IMAX | D3D11_SB_OPCODE_IMM_ATOMIC_IMAX => spirv::Op::AtomicSMax,
+            D3D11_SB_OPCODE_ATOMIC_IMIN | D3D11_SB_OPCODE_IMM_ATOMIC_IMIN => spirv::Op::AtomicSMin,
+            D3D11_SB_OPCODE_ATOMIC_UMAX | D3D11_SB_OPCODE_IMM_ATOMIC_UMAX => spirv::Op::AtomicUMax,
+            _ => spirv::Op::AtomicUMin,
+        };
+
+        // the compare value comes first in D3D and last in SPIR-V
+        let arguments = if op == spirv::Op::AtomicCompareExchange {
+            vec![pointer, scope, relaxed, relaxed, values[1], values[0]]
+        } else {
+            vec![pointer, scope, relaxed, values[0]]
+        };
+        let scalar = self.module.scalar(ty);
+        let id = self.module.emit(op, scalar, &arguments);
+
+        if immediate {
+            if let Some(components) = written(&operands[0]) {
+                let value = self.module.shuffle(Value { id, ty, width: 1 }, &vec![0; components.len()]);
+                self.store(i, 0, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self, i: usize) {
+        let flags = self.program.instructions[i].controls;
+
+        let mut scope = spirv::Scope::Workgroup;
+        let mut semantics = spirv::MemorySemantics::ACQUIRE_RELEASE;
+        if flags & D3D11_SB_SYNC_THREAD_GROUP_SHARED_MEMORY != 0 {
+            semantics |= spirv::MemorySemantics::WORKGROUP_MEMORY;
+        }
+        if flags & (D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GROUP | D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GLOBAL) != 0 {
+            semantics |= spirv::MemorySemantics::UNIFORM_MEMORY | spirv::MemorySemantics::IMAGE_MEMORY;
+        }
+        if flags & D3D11_SB_SYNC_UNORDERED_ACCESS_VIEW_MEMORY_GLOBAL != 0 {
+            scope = spirv::Scope::Device;
+        }
+
+        let memory = self.module.constant(Type::Uint, scope as u32);
+        let semantics = self.module.constant(Type::Uint, semantics.bits());
+        if flags & D3D11_SB_SYNC_THREADS_IN_GROUP != 0 {
+            let execution = self.module.constant(Type::Uint, spirv::Scope::Workgroup as u32);
+            self.module.emit_void(spirv::Op::ControlBarrier, &[execution, memory, semantics]);
+        } else {
+            self.module.emit_void(spirv::Op::MemoryBarrier, &[memory, semantics]);
+        }
+    }
+
+    fn resinfo(&mut self, i: usize) -> Result<(), Error> {
+        let program = self.program;
+        let instruction = &program.instructions[i];
+        let operands = &instruction.operands;
+        let texture = self.resource(&operands[2])?;
+        let uav = operands[2].register().is_some_and(|register| register.file == OperandType::UnorderedAccessView);
+        let multisampled = texture.dimension == ResourceDimension::Texture2DMS || texture.dimension == ResourceDimension::Texture2DMSArray;
+        self.module.capability(spirv::Capability::ImageQuery);
+
+        let (sizes, elements) = match texture.dimension {
+            ResourceDimension::Texture1D => (1, 0),
+            ResourceDimension::Texture1DArray => (1, 1),
+            ResourceDimension::Texture2D | ResourceDimension::TextureCube | ResourceDimension::Texture2DMS => (2, 0),
+            ResourceDimension::Texture3D => (3, 0),
+            _ => (2, 1),
+        };
+        let width = sizes + elements;
+        let uint = self.module.scalar(Type::Uint);
+        let vector = self.module.vector(Type::Uint, width);
+        let image = self.load_image(&texture);
+
+        let (size, levels) = if uav || multisampled {
+            let size = self.module.emit(spirv::Op::ImageQuerySize, vector, &[image]);
+            let levels = if multisampled {
+                self.module.emit(spirv::Op::ImageQuerySamples, uint, &[image])
+            } else {
+                self.module.constant(Type::Uint, 1)
+            };
+            (size, levels)
+        } else {
+            let lod = self.source(&operands[1], &[0], Type::Uint)?.id;
+            let size = self.module.emit(spirv::Op::ImageQuerySizeLod, vector, &[image, lod]);
+            (size, self.module.emit(spirv::Op::ImageQueryLevels, uint, &[image]))
+        };
+
+        let size = Value { id: size, ty: Type::Uint, width };
+        let mut parts: Vec<Value> = (0..width).map(|c| self.module.extract(size, c)).collect();
+        while parts.len() < 3 {
+            parts.push(Value { id: self.module.constant(Type::Uint, 0), ty: Type::Uint, width: 1 });
+        }
+        parts.push(Value { id: levels, ty: Type::Uint, width: 1 });
+
+        let value = match DECODE_D3D10_SB_RESINFO_INSTRUCTION_RETURN_TYPE(instruction.controls) {
+            D3D10_SB_RESINFO_INSTRUCTION_RETURN_UINT => self.module.construct(&parts),
+            ty => {
+                let rcp = ty == D3D10_SB_RESINFO_INSTRUCTION_RETURN_RCPFLOAT;
+                let float = self.module.scalar(Type::Float);
+                let one = self.module.constant(Type::Float, 1.0f32.to_bits());
+                let parts: Vec<Value> = parts
+                    .iter()
+                    .enumerate()
+                    .map(|(c, part)| {
+                        let mut id = self.module.emit(spirv::Op::ConvertUToF, float, &[part.id]);
+                        if rcp && (c as u32) < sizes {
+                            id = self.module.emit(spirv::Op::FDiv, float, &[one, id]);
+                        }
+                        Value { id, ty: Type::Float, width: 1 }
+                    })
+                    .collect();
+                self.module.construct(&parts)
+            }
+        };
+        self.assign_resource(i, 2, value)
+    }
+
+    fn bufinfo(&mut self, i: usize) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let resource = self.resource(&instruction.operands[1])?;
+        let components = match written(&instruction.operands[0]) {
+            Some(components) => components,
+            None => return Ok(()),
+        };
+
+        let uint = self.module.scalar(Type::Uint);
+        let id = match resource.kind {
+            ResourceKind::Typed => {
+                self.module.capability(spirv::Capability::ImageQuery);
+                let image = self.load_image(&resource);
+                self.module.emit(spirv::Op::ImageQuerySize, uint, &[image])
+            }
+            _ if resource.class == spirv::StorageClass::Workgroup => return unsupported("bufinfo of shared memory"),
+            kind => {
+                let dwords = self.module.emit(spirv::Op::ArrayLength, uint, &[resource.variable, 0]);
+                if kind == ResourceKind::Raw {
+                    let four = self.module.constant(Type::Uint, 4);
+                    self.module.emit(spirv::Op::IMul, uint, &[dwords, four])
+                } else {
+                    let stride = self.module.constant(Type::Uint, resource.stride.max(1));
+                    self.module.emit(spirv::Op::UDiv, uint, &[dwords, stride])
+                }
+            }
+        };
+
+        let value = self.module.shuffle(Value { id, ty: Type::Uint, width: 1 }, &vec![0; components.len()]);
+        self.store(i, 0, value)
+    }
+
+    // The rasterizer's sample count isn't known until pipeline creation.
+    fn sample_count(&mut self, operand: &Operand) -> Result<u32, Error> {
+        let uint = self.module.scalar(Type::Uint);
+        if operand.register().is_some_and(|register| register.file == OperandType::Rasterizer) {
+            let (count, new) = self.module.declare(spirv::Op::SpecConstant, Some(uint), &[1], 0);
+            if new {
+                self.module.name(count, "sample_count");
+                self.module.decorate(count, spirv::Decoration::SpecId, &[SAMPLE_COUNT_SPEC_ID]);
+            }
+            return Ok(count);
+        }
+
+        let texture = self.resource(operand)?;
+        self.module.capability(spirv::Capability::ImageQuery);
+        let image = self.load_image(&texture);
+        Ok(self.module.emit(spirv::Op::ImageQuerySamples, uint, &[image]))
+    }
+
+    fn sample_info(&mut self, i: usize) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let samples = self.sample_count(&instruction.operands[1])?;
+
+        let uint_result = DECODE_D3D10_SB_INSTRUCTION_RETURN_TYPE(instruction.controls) == D3D10_SB_INSTRUCTION_RETURN_UINT;
+        let (ty, samples) = if uint_result {
+            (Type::Uint, samples)
+        } else {
+            let float = self.module.scalar(Type::Float);
+            (Type::Float, self.module.emit(spirv::Op::ConvertUToF, float, &[samples]))
+        };
+        let zero = self.module.constant(ty, 0);
+        let parts: Vec<Value> = [samples, zero, zero, zero].iter().map(|&id| Value { id, ty, width: 1 }).collect();
+        let value = self.module.construct(&parts);
+        self.assign_resource(i, 1, value)
+    }
+
+    // The standard patterns for 1, 2, 4, 8 and 16 samples, back to back so
+    // the pattern for `n` samples starts at `n - 1`, followed by a zero entry.
+    fn sample_positions(&mut self) -> u32 {
+        if let Some(variable) = self.sample_positions {
+            return variable;
+        }
+
+        let vec2 = self.module.vector(Type::Float, 2);
+        let entries: Vec<u32> = STANDARD_SAMPLE_POSITIONS
+            .iter()
+            .chain(Some(&(0, 0)))
+            .map(|&(x, y)| {
+                let x = self.module.constant(Type::Float, (f32::from(x) / 16.0).to_bits());
+                let y = self.module.constant(Type::Float, (f32::from(y) / 16.0).to_bits());
+                self.module.composite(vec2, &[x, y])
+            })
+            .collect();
+        let array = self.module.array(vec2, entries.len() as u32, None);
+        let initializer = self.module.composite(array, &entries);
+        let variable = self.module.variable(array, spirv::StorageClass::Private, Some(initializer));
+        self.module.name(variable, "sample_positions");
+        self.sample_positions = Some(variable);
+        variable
+    }
+
+    fn sample_position(&mut self, i: usize) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let count = self.sample_count(&instruction.operands[1])?;
+        let index = self.source(&instruction.operands[2], &[0], Type::Uint)?.id;
+        let positions = self.sample_positions();
+
+        // out of range indices and nonstandard counts read as zero
+        let uint = self.module.scalar(Type::Uint);
+        let boolean = self.module.scalar(Type::Bool);
+        let one = self.module.constant(Type::Uint, 1);
+        let largest = self.module.constant(Type::Uint, 16);
+        let none = self.module.constant(Type::Uint, STANDARD_SAMPLE_POSITIONS.len() as u32);
+        let in_pattern = self.module.emit(spirv::Op::ULessThan, boolean, &[index, count]);
+        let standard = self.module.emit(spirv::Op::ULessThanEqual, boolean, &[count, largest]);
+        let valid = self.module.emit(spirv::Op::LogicalAnd, boolean, &[in_pattern, standard]);
+        let start = self.module.emit(spirv::Op::ISub, uint, &[count, one]);
+        let entry = self.module.emit(spirv::Op::IAdd, uint, &[start, index]);
+        let entry = self.module.emit(spirv::Op::Select, uint, &[valid, entry, none]);
+
+        let vec2 = self.module.vector(Type::Float, 2);
+        let pointer = self.module.pointer(spirv::StorageClass::Private, vec2);
+        let pointer = self.module.emit(spirv::Op::AccessChain, pointer, &[positions, entry]);
+        let position = self.module.load(Type::Float, 2, pointer);
+        let x = self.module.extract(position, 0);
+        let y = self.module.extract(position, 1);
+        let zero = Value { id: self.module.constant(Type::Float, 0), ty: Type::Float, width: 1 };
+        let value = self.module.construct(&[x, y, zero, zero]);
+        self.assign_resource(i, 1, value)
+    }
+
+    fn lod(&mut self, i: usize) -> Result<(), Error> {
+        let instruction = &self.program.instructions[i];
+        let texture = self.resource(&instruction.operands[2])?;
+        let sampler = self.resource(&instruction.operands[3])?;
+        let (coordinates, _) = texture_coordinates(texture.dimension);
+        let components: Vec<u8> = (0..coordinates as u8).collect();
+        let coordinate = self.source(&instruction.operands[1], &components, Type::Float)?.id;
+        self.module.capability(spirv::Capability::ImageQuery);
+
+        let image = self.load_image(&texture);
+        let sampler = self.load_image(&sampler);
+        let sampled_image_type = self.module.ty(spirv::Op::TypeSampledImage, &[texture.ty]);
+        let sampled_image = self.module.emit(spirv::Op::SampledImage, sampled_image_type, &[image, sampler]);
+        let vec2 = self.module.vector(Type::Float, 2);
+        let lod = self.module.emit(spirv::Op::ImageQueryLod, vec2, &[sampled_image, coordinate]);
+
+        // D3D puts the clamped level first like SPIR-V
+        let lod = Value { id: lod, ty: Type::Float, width: 2 };
+        let clamped = self.module.extract(lod, 0);
+        let unclamped = self.module.extract(lod, 1);
+        let zero = Value { id: self.module.constant(Type::Float, 0), ty: Type::Float, width: 1 };
+        let value = self.module.construct(&[clamped, unclamped, zero, zero]);
+        self.assign_resource(i, 2, value)
+    }
+
+    fn begin_function(&mut self, id: u32) {
+        let void = self.module.void();
+        let ty = self.module.function_type(void);
+        instruction(&mut self.module.code, spirv::Op::Function, &[void, id, spirv::FunctionControl::NONE.bits(), ty]);
+        self.module.open = false;
+        self.module.ensure_block();
+    }
+
+    fn end_function(&mut self) -> Result<(), Error> {
+        if self.module.open {
+            self.ret()?;
+        }
+        instruction(&mut self.module.code, spirv::Op::FunctionEnd, &[]);
+        Ok(())
+    }
+
+    // Subroutines are void functions named after their label.
+    fn function(&mut self, label: u64) -> u32 {
+        if let Some(&id) = self.functions.get(&label) {
+            return id;
+        }
+
+        let id = self.module.id();
+        self.module.name(id, &format!("l{}", label));
+        self.functions.insert(label, id);
+        id
+    }
+
+    fn ret(&mut self) -> Result<(), Error> {
+        if !self.in_subroutine {
+            self.copy_outputs()?;
+        }
+        self.module.terminate(spirv::Op::Return, &[]);
+        Ok(())
+    }
+
+    fn branch(&mut self, label: u32) {
+        if self.module.open {
+            self.module.terminate(spirv::Op::Branch, &[label]);
+        }
+    }
+
+    fn jump(&mut self, jump: Jump) -> Result<(), Error> {
+        match jump {
+            Jump::Branch(label) => self.module.terminate(spirv::Op::Branch, &[label]),
+            Jump::Return => self.ret()?,
+            Jump::Kill => self.module.terminate(spirv::Op::Kill, &[]),
+            Jump::Call(function) => {
+                let void = self.module.void();
+                self.module.emit(spirv::Op::FunctionCall, void, &[function]);
+            }
+        }
+        Ok(())
+    }
+
+    // Takes `jump` in a selection of its own if the condition holds.
+    fn conditional(&mut self, i: usize, jump: Jump) -> Result<(), Error> {
+        let condition = self.condition(i)?;
+        let then = self.module.id();
+        let merge = self.module.id();
+        self.module.emit_void(spirv::Op::SelectionMerge, &[merge, spirv::SelectionControl::NONE.bits()]);
+        self.module.terminate(spirv::Op::BranchConditional, &[condition, then, merge]);
+        self.module.block(then);
+        self.jump(jump)?;
+        self.module.block(merge);
+        Ok(())
+    }
+
+    // Merge block of the innermost loop or switch.
+    fn break_target(&self) -> Result<u32, Error> {
+        for construct in self.constructs.iter().rev() {
+            match *construct {
+                Construct::Loop { merge, .. } | Construct::Switch { merge, .. } => return Ok(merge),
+                Construct::If { .. } => {}
+            }
+        }
+        unsupported("break outside of a loop or switch")
+    }
+
+    fn continue_target(&self) -> Result<u32, Error> {
+        for construct in self.constructs.iter().rev() {
+            if let Construct::Loop { continue_target, .. } = *construct {
+                return Ok(continue_target);
+            }
+        }
+        unsupported("continue outside of a loop")
+    }
+
+    // Groups the cases of the switch at `i`, consecutive labels share a
+    // block.
+    fn switch(&mut self, i: usize) -> Result<(), Error> {
+        let program = self.program;
+        let selector = self.source(&program.instructions[i].operands[0], &[0], Type::Int)?.id;
+        let merge = self.module.id();
+
+        let mut depth = 0;
+        let mut cases = Vec::new();
+        let mut targets = Vec::new();
+        let mut default = None;
+        let mut group = None;
+        for (j, instruction) in program.instructions.iter().enumerate().skip(i + 1) {
+            match instruction.opcode {
+                D3D10_SB_OPCODE_SWITCH => depth += 1,
+                D3D10_SB_OPCODE_ENDSWITCH if depth == 0 => break,
+                D3D10_SB_OPCODE_ENDSWITCH => depth -= 1,
+                D3D10_SB_OPCODE_CASE | D3D10_SB_OPCODE_DEFAULT if depth == 0 => {
+                    let label = match group {
+                        Some(label) => label,
+                        None => {
+                            let label = self.module.id();
+                            cases.push((j, label));
+                            label
+                        }
+                    };
+                    group = Some(label);
+
+                    if instruction.opcode == D3D10_SB_OPCODE_DEFAULT {
+                        default = Some(label);
+                    } else {
+                        match instruction.operands[0] {
+                            Operand::Imm32(ref values) => targets.extend_from_slice(&[values[0], label]),
+                            _ => return unsupported("case without an immediate"),
+                        }
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            group = None;
+        }
+
+        let mut operands = vec![selector, default.unwrap_or(merge)];
+        operands.extend(targets);
+        self.module.emit_void(spirv::Op::SelectionMerge, &[merge, spirv::SelectionControl::NONE.bits()]);
+        self.module.terminate(spirv::Op::Switch, &operands);
+        self.constructs.push(Construct::Switch { merge, cases });
+        Ok(())
+    }
+
+    fn instruction(&mut self, i: usize) -> Result<(), Error> {
+        use spirv::Op;
+
+        let instruction = &self.program.instructions[i];
+        let opcode = instruction.opcode;
+
+        match opcode {
+            D3D10_SB_OPCODE_ADD => self.binary(i, Type::Float, Op::FAdd),
+            D3D10_SB_OPCODE_MUL => self.binary(i, Type::Float, Op::FMul),
+            D3D10_SB_OPCODE_DIV => self.binary(i, Type::Float, Op::FDiv),
+            D3D10_SB_OPCODE_MAD => self.componentwise(i, &[(1, Type::Float), (2, Type::Float), (3, Type::Float)], Type::Float, &|module, ty, arguments| {
+                let product = module.emit(Op::FMul, ty, &arguments[..2]);
+                module.emit(Op::FAdd, ty, &[product, arguments[2]])
+            }),
+            D3D10_SB_OPCODE_MIN => self.extended(i, Type::Float, GLSL_FMIN),
+            D3D10_SB_OPCODE_MAX => self.extended(i, Type::Float, GLSL_FMAX),
+            D3D10_SB_OPCODE_EXP => self.extended(i, Type::Float, GLSL_EXP2),
+            D3D10_SB_OPCODE_LOG => self.extended(i, Type::Float, GLSL_LOG2),
+            D3D10_SB_OPCODE_SQRT => self.extended(i, Type::Float, GLSL_SQRT),
+            D3D10_SB_OPCODE_RSQ => self.extended(i, Type::Float, GLSL_INVERSE_SQRT),
+            D3D10_SB_OPCODE_FRC => self.extended(i, Type::Float, GLSL_FRACT),
+            D3D10_SB_OPCODE_ROUND_NE => self.extended(i, Type::Float, GLSL_ROUND_EVEN),
+            D3D10_SB_OPCODE_ROUND_NI => self.extended(i, Type::Float, GLSL_FLOOR),
+            D3D10_SB_OPCODE_ROUND_PI => self.extended(i, Type::Float, GLSL_CEIL),
+            D3D10_SB_OPCODE_ROUND_Z => self.extended(i, Type::Float, GLSL_TRUNC),
+            D3D11_SB_OPCODE_RCP => self.componentwise(i, &[(1, Type::Float)], Type::Float, &|module, ty, arguments| {
+                let one = module.constant_as(ty, 1.0f32.to_bits());
+                module.emit(Op::FDiv, ty, &[one, arguments[0]])
+            }),
+            D3D10_SB_OPCODE_DERIV_RTX => self.unary(i, Type::Float, Type::Float, Op::DPdx),
+            D3D10_SB_OPCODE_DERIV_RTY => self.unary(i, Type::Float, Type::Float, Op::DPdy),
+            D3D11_SB_OPCODE_DERIV_RTX_COARSE | D3D11_SB_OPCODE_DERIV_RTX_FINE |
+            D3D11_SB_OPCODE_DERIV_RTY_COARSE | D3D11_SB_OPCODE_DERIV_RTY_FINE => {
+                self.module.capability(spirv::Capability::DerivativeControl);
+                let op = match opcode {
+                    D3D11_SB_OPCODE_DERIV_RTX_COARSE => Op::DPdxCoarse,
+                    D3D11_SB_OPCODE_DERIV_RTX_FINE => Op::DPdxFine,
+                    D3D11_SB_OPCODE_DERIV_RTY_COARSE => Op::DPdyCoarse,
+                    _ => Op::DPdyFine,
+                };
+                self.unary(i, Type::Float, Type::Float, op)
+            }
+            D3D10_SB_OPCODE_DP2 => self.dot(i, 2),
+            D3D10_SB_OPCODE_DP3 => self.dot(i, 3),
+            D3D10_SB_OPCODE_DP4 => self.dot(i, 4),
+            D3D10_SB_OPCODE_EQ => self.compare(i, Type::Float, Op::FOrdEqual),
+            D3D10_SB_OPCODE_NE => self.compare(i, Type::Float, Op::FUnordNotEqual),
+            D3D10_SB_OPCODE_LT => self.compare(i, Type::Float, Op::FOrdLessThan),
+            D3D10_SB_OPCODE_GE => self.compare(i, Type::Float, Op::FOrdGreaterThanEqual),
+            D3D10_SB_OPCODE_IEQ => self.compare(i, Type::Uint, Op::IEqual),
+            D3D10_SB_OPCODE_INE => self.compare(i, Type::Uint, Op::INotEqual),
+            D3D10_SB_OPCODE_ILT => self.compare(i, Type::Int, Op::SLessThan),
+            D3D10_SB_OPCODE_IGE => self.compare(i, Type::Int, Op::SGreaterThanEqual),
+            D3D10_SB_OPCODE_ULT => self.compare(i, Type::Uint, Op::ULessThan),
+            D3D10_SB_OPCODE_UGE => self.compare(i, Type::Uint, Op::UGreaterThanEqual),
+            D3D10_SB_OPCODE_IADD => self.binary(i, Type::Uint, Op::IAdd),
+            D3D10_SB_OPCODE_IMAD | D3D10_SB_OPCODE_UMAD => self.componentwise(i, &[(1, Type::Uint), (2, Type::Uint), (3, Type::Uint)], Type::Uint, &|module, ty, arguments| {
+                let product = module.emit(Op::IMul, ty, &arguments[..2]);
+                module.emit(Op::IAdd, ty, &[product, arguments[2]])
+            }),
+            D3D10_SB_OPCODE_INEG => self.unary(i, Type::Int, Type::Int, Op::SNegate),
+            D3D10_SB_OPCODE_IMIN => self.extended(i, Type::Int, GLSL_SMIN),
+            D3D10_SB_OPCODE_IMAX => self.extended(i, Type::Int, GLSL_SMAX),
+            D3D10_SB_OPCODE_UMIN => self.extended(i, Type::Uint, GLSL_UMIN),
+            D3D10_SB_OPCODE_UMAX => self.extended(i, Type::Uint, GLSL_UMAX),
+            D3D10_SB_OPCODE_AND => self.binary(i, Type::Uint, Op::BitwiseAnd),
+            D3D10_SB_OPCODE_OR => self.binary(i, Type::Uint, Op::BitwiseOr),
+            D3D10_SB_OPCODE_XOR => self.binary(i, Type::Uint, Op::BitwiseXor),
+            D3D10_SB_OPCODE_NOT => self.unary(i, Type::Uint, Type::Uint, Op::Not),
+            D3D10_SB_OPCODE_ISHL => self.shift(i, Type::Uint, Op::ShiftLeftLogical),
+            D3D10_SB_OPCODE_ISHR => self.shift(i, Type::Int, Op::ShiftRightArithmetic),
+            D3D10_SB_OPCODE_USHR => self.shift(i, Type::Uint, Op::ShiftRightLogical),
+            D3D10_SB_OPCODE_ITOF => self.unary(i, Type::Int, Type::Float, Op::ConvertSToF),
+            D3D10_SB_OPCODE_UTOF => self.unary(i, Type::Uint, Type::Float, Op::ConvertUToF),
+            D3D10_SB_OPCODE_FTOI => self.unary(i, Type::Float, Type::Int, Op::ConvertFToS),
+            D3D10_SB_OPCODE_FTOU => self.unary(i, Type::Float, Type::Uint, Op::ConvertFToU),
+            D3D10_SB_OPCODE_MOV | D3D10_SB_OPCODE_MOVC => {
+                // moves keep the bits unless they saturate or modify floats
+                let modified = instruction.operands[1..].iter().any(|operand| match *operand {
+                    Operand::Register(ref register) => register.modifier != Modifier::None,
+                    _ => false,
+                });
+                let ty = if instruction.saturate || modified { Type::Float } else { Type::Uint };
+                if opcode == D3D10_SB_OPCODE_MOV {
+                    self.componentwise(i, &[(1, ty)], ty, &|_, _, arguments| arguments[0])
+                } else {
+                    self.componentwise(i, &[(1, Type::Bool), (2, ty), (3, ty)], ty, &|module, ty, arguments| {
+                        module.emit(Op::Select, ty, arguments)
+                    })
+                }
+            }
+            D3D11_SB_OPCODE_COUNTBITS => self.unary(i, Type::Uint, Type::Uint, Op::BitCount),
+            D3D11_SB_OPCODE_BFREV => self.unary(i, Type::Uint, Type::Uint, Op::BitReverse),
+            D3D11_SB_OPCODE_FIRSTBIT_LO => self.extended(i, Type::Uint, GLSL_FIND_I_LSB),
+            D3D11_SB_OPCODE_FIRSTBIT_HI => self.first_bit_high(i, Type::Uint, GLSL_FIND_U_MSB),
+            D3D11_SB_OPCODE_FIRSTBIT_SHI => self.first_bit_high(i, Type::Int, GLSL_FIND_S_MSB),
+            D3D11_SB_OPCODE_UBFE | D3D11_SB_OPCODE_IBFE => {
+                let (ty, op) = if opcode == D3D11_SB_OPCODE_UBFE {
+                    (Type::Uint, Op::BitFieldUExtract)
+                } else {
+                    (Type::Int, Op::BitFieldSExtract)
+                };
+                // D3D clamps the width to the bits left above the offset
+                self.scalarwise(i, &[(1, Type::Uint), (2, Type::Uint), (3, ty)], ty, &|module, ty, arguments| {
+                    let uint = module.scalar(Type::Uint);
+                    let mask = module.constant(Type::Uint, 31);
+                    let bits = module.constant(Type::Uint, 32);
+                    let width = module.emit(Op::BitwiseAnd, uint, &[arguments[0], mask]);
+                    let offset = module.emit(Op::BitwiseAnd, uint, &[arguments[1], mask]);
+                    let room = module.emit(Op::ISub, uint, &[bits, offset]);
+                    let count = module.ext(uint, GLSL_UMIN, &[width, room]);
+                    module.emit(op, ty, &[arguments[2], offset, count])
+                })
+            }
+            D3D11_SB_OPCODE_BFI => {
+                let sources = [(1, Type::Uint), (2, Type::Uint), (3, Type::Uint), (4, Type::Uint)];
+                self.componentwise(i, &sources, Type::Uint, &|module, ty, arguments| {
+                    let mask = module.constant_as(ty, 31);
+                    let one = module.constant_as(ty, 1);
+                    let width = module.emit(Op::BitwiseAnd, ty, &[arguments[0], mask]);
+                    let offset = module.emit(Op::BitwiseAnd, ty, &[arguments[1], mask]);
+                    let bits = module.emit(Op::ShiftLeftLogical, ty, &[one, width]);
+                    let bits = module.emit(Op::ISub, ty, &[bits, one]);
+                    let bits = module.emit(Op::ShiftLeftLogical, ty, &[bits, offset]);
+                    let insert = module.emit(Op::ShiftLeftLogical, ty, &[arguments[2], offset]);
+                    let insert = module.emit(Op::BitwiseAnd, ty, &[insert, bits]);
+                    let keep = module.emit(Op::Not, ty, &[bits]);
+                    let base = module.emit(Op::BitwiseAnd, ty, &[arguments[3], keep]);
+                    module.emit(Op::BitwiseOr, ty, &[insert, base])
+                })
+            }
+            D3D11_SB_OPCODE_F32TOF16 => self.scalarwise(i, &[(1, Type::Float)], Type::Uint, &|module, ty, arguments| {
+                let vec2 = module.vector(Type::Float, 2);
+                let zero = module.constant(Type::Float, 0);
+                let pair = module.emit(Op::CompositeConstruct, vec2, &[arguments[0], zero]);
+                module.ext(ty, GLSL_PACK_HALF_2X16, &[pair])
+            }),
+            D3D11_SB_OPCODE_F16TOF32 => self.scalarwise(i, &[(1, Type::Uint)], Type::Float, &|module, ty, arguments| {
+                let vec2 = module.vector(Type::Float, 2);
+                let pair = module.ext(vec2, GLSL_UNPACK_HALF_2X16, arguments);
+                module.emit(Op::CompositeExtract, ty, &[pair, 0])
+            }),
+            D3D10_SB_OPCODE_SINCOS => self.multiple(i, &[(2, Type::Float)], &[
+                (Type::Float, &|module, ty, arguments| module.ext(ty, GLSL_SIN, arguments)),
+                (Type::Float, &|module, ty, arguments| module.ext(ty, GLSL_COS, arguments)),
+            ]),
+            D3D10_SB_OPCODE_UDIV => self.multiple(i, &[(2, Type::Uint), (3, Type::Uint)], &[
+                (Type::Uint, &|module, ty, arguments| module.emit(Op::UDiv, ty, arguments)),
+                (Type::Uint, &|module, ty, arguments| module.emit(Op::UMod, ty, arguments)),
+            ]),
+            D3D11_SB_OPCODE_UADDC => self.multiple(i, &[(2, Type::Uint), (3, Type::Uint)], &[
+                (Type::Uint, &|module, ty, arguments| module.emit(Op::IAdd, ty, arguments)),
+                (Type::Uint, &|module, ty, arguments| {
+                    let pair = module.ty(Op::TypeStruct, &[ty, ty]);
+                    let result = module.emit(Op::IAddCarry, pair, arguments);
+                    module.emit(Op::CompositeExtract, ty, &[result, 1])
+                }),
+            ]),
+            D3D11_SB_OPCODE_USUBB => self.multiple(i, &[(2, Type::Uint), (3, Type::Uint)], &[
+                (Type::Uint, &|module, ty, arguments| module.emit(Op::ISub, ty, arguments)),
+                (Type::Uint, &|module, ty, arguments| {
+                    let pair = module.ty(Op::TypeStruct, &[ty, ty]);
+                    let result = module.emit(Op::ISubBorrow, pair, arguments);
+                    module.emit(Op::CompositeExtract, ty, &[result, 1])
+                }),
+            ]),
+            D3D10_SB_OPCODE_IMUL | D3D10_SB_OPCODE_UMUL => {
+                let (ty, op) = if opcode == D3D10_SB_OPCODE_IMUL {
+                    (Type::Int, Op::SMulExtended)
+                } else {
+                    (Type::Uint, Op::UMulExtended)
+                };
+                self.multiple(i, &[(2, ty), (3, ty)], &[
+                    (ty, &|module, ty, arguments| {
+                        let pair = module.ty(Op::TypeStruct, &[ty, ty]);
+                        let result = module.emit(op, pair, arguments);
+                        module.emit(Op::CompositeExtract, ty, &[result, 1])
+                    }),
+                    (ty, &|module, ty, arguments| module.emit(Op::IMul, ty, arguments)),
+                ])
+            }
+            D3D11_SB_OPCODE_SWAPC => self.multiple(i, &[(2, Type::Bool), (3, Type::Uint), (4, Type::Uint)], &[
+                (Type::Uint, &|module, ty, arguments| module.emit(Op::Select, ty, &[arguments[0], arguments[2], arguments[1]])),
+                (Type::Uint, &|module, ty, arguments| module.emit(Op::Select, ty, arguments)),
+            ]),
+            D3D10_SB_OPCODE_SAMPLE | D3D10_SB_OPCODE_SAMPLE_B | D3D10_SB_OPCODE_SAMPLE_L | D3D10_SB_OPCODE_SAMPLE_D |
+            D3D10_SB_OPCODE_SAMPLE_C | D3D10_SB_OPCODE_SAMPLE_C_LZ | D3D10_1_SB_OPCODE_GATHER4 |
+            D3D11_SB_OPCODE_GATHER4_C | D3D11_SB_OPCODE_GATHER4_PO | D3D11_SB_OPCODE_GATHER4_PO_C => self.sample(i),
+            D3D10_SB_OPCODE_LD | D3D10_SB_OPCODE_LD_MS => self.load(i),
+            D3D11_SB_OPCODE_LD_UAV_TYPED => self.load_typed(i),
+            D3D11_SB_OPCODE_STORE_UAV_TYPED => self.store_typed(i),
+            D3D11_SB_OPCODE_LD_RAW | D3D11_SB_OPCODE_LD_STRUCTURED => self.load_memory(i),
+            D3D11_SB_OPCODE_STORE_RAW | D3D11_SB_OPCODE_STORE_STRUCTURED => self.store_memory(i),
+            D3D11_SB_OPCODE_ATOMIC_AND..=D3D11_SB_OPCODE_IMM_ATOMIC_UMIN => self.atomic(i),
+            D3D11_SB_OPCODE_SYNC => {
+                self.sync(i);
+                Ok(())
+            }
+            D3D10_SB_OPCODE_RESINFO => self.resinfo(i),
+            D3D11_SB_OPCODE_BUFINFO => self.bufinfo(i),
+            D3D10_1_SB_OPCODE_SAMPLE_INFO => self.sample_info(i),
+            D3D10_1_SB_OPCODE_SAMPLE_POS => self.sample_position(i),
+            D3D10_1_SB_OPCODE_LOD => self.lod(i),
+            D3D10_SB_OPCODE_IF => {
+                let condition = self.condition(i)?;
+                let then = self.module.id();
+                let else_label = self.module.id();
+                let merge = self.module.id();
+                self.module.emit_void(Op::SelectionMerge, &[merge, spirv::SelectionControl::NONE.bits()]);
+                self.module.terminate(Op::BranchConditional, &[condition, then, else_label]);
+                self.module.block(then);
+                self.constructs.push(Construct::If { else_label, merge, has_else: false });
+                Ok(())
+            }
+            D3D10_SB_OPCODE_ELSE => {
+                let (else_label, merge) = match self.constructs.last_mut() {
+                    Some(&mut Construct::If { else_label, merge, ref mut has_else }) => {
+                        *has_else = true;
+                        (else_label, merge)
+                    }
+                    _ => return unsupported("else outside of an if"),
+                };
+                self.branch(merge);
+                self.module.block(else_label);
+                Ok(())
+            }
+            D3D10_SB_OPCODE_ENDIF => match self.constructs.pop() {
+                Some(Construct::If { else_label, merge, has_else }) => {
+                    self.branch(merge);
+                    if !has_else {
+                        self.module.block(else_label);
+                        self.module.terminate(Op::Branch, &[merge]);
+                    }
+                    self.module.block(merge);
+                    Ok(())
+                }
+                _ => unsupported("endif outside of an if"),
+            },
+            D3D10_SB_OPCODE_LOOP => {
+                let header = self.module.id();
+                let body = self.module.id();
+                let continue_target = self.module.id();
+                let merge = self.module.id();
+                self.module.block(header);
+                self.module.emit_void(Op::LoopMerge, &[merge, continue_target, spirv::LoopControl::NONE.bits()]);
+                self.module.terminate(Op::Branch, &[body]);
+                self.module.block(body);
+                self.constructs.push(Construct::Loop { header, continue_target, merge });
+                Ok(())
+            }
+            D3D10_SB_OPCODE_ENDLOOP => match self.constructs.pop() {
+                Some(Construct::Loop { header, continue_target, merge }) => {
+                    self.module.block(continue_target);
+                    self.module.terminate(Op::Branch, &[header]);
+                    self.module.block(merge);
+                    Ok(())
+                }
+                _ => unsupported("endloop outside of a loop"),
+            },
+            D3D10_SB_OPCODE_BREAK => {
+                let target = self.break_target()?;
+                self.branch(target);
+                Ok(())
+            }
+            D3D10_SB_OPCODE_BREAKC => {
+                let target = self.break_target()?;
+                self.conditional(i, Jump::Branch(target))
+            }
+            D3D10_SB_OPCODE_CONTINUE => {
+                let target = self.continue_target()?;
+                self.branch(target);
+                Ok(())
+            }
+            D3D10_SB_OPCODE_CONTINUEC => {
+                let target = self.continue_target()?;
+                self.conditional(i, Jump::Branch(target))
+            }
+            D3D10_SB_OPCODE_SWITCH => self.switch(i),
+            D3D10_SB_OPCODE_CASE | D3D10_SB_OPCODE_DEFAULT => {
+                let label = match self.constructs.last() {
+                    Some(Construct::Switch { cases, .. }) => cases.iter().find(|&&(j, _)| j == i).map(|&(_, label)| label),
+                    _ => return unsupported("case outside of a switch"),
+                };
+                // later labels of a group share the block of the first
+                if let Some(label) = label {
+                    self.module.block(label);
+                }
+                Ok(())
+            }
+            D3D10_SB_OPCODE_ENDSWITCH => match self.constructs.pop() {
+                Some(Construct::Switch { merge, .. }) => {
+                    self.module.block(merge);
+                    Ok(())
+                }
+                _ => unsupported("endswitch outside of a switch"),
+            },
+            D3D10_SB_OPCODE_RET => self.ret(),
+            D3D10_SB_OPCODE_RETC => self.conditional(i, Jump::Return),
+            D3D10_SB_OPCODE_DISCARD => self.conditional(i, Jump::Kill),
+            D3D10_SB_OPCODE_CALL => {
+                let function = self.function(label(&instruction.operands[0]));
+                self.jump(Jump::Call(function))
+            }
+            D3D10_SB_OPCODE_CALLC => {
+                let function = self.function(label(&instruction.operands[1]));
+                self.conditional(i, Jump::Call(function))
+            }
+            D3D10_SB_OPCODE_LABEL => {
+                if !self.constructs.is_empty() {
+                    return unsupported("label inside control flow");
+                }
+                self.end_function()?;
+                self.in_subroutine = true;
+                let function = self.function(label(&instruction.operands[0]));
+                self.begin_function(function);
+                Ok(())
+            }
+            D3D10_SB_OPCODE_NOP => Ok(()),
+            _ => unsupported(instruction.name()),
+        }
+    }
+}
+
+// Elements for the registers of shaders without signature chunks.
+fn synthesize(declared: &[(&Register, u32)]) -> Vec<Element> {
+    declared
+        .iter()
+        .filter_map(|&(register, name)| {
+            let (semantic_type, index) = match register.file {
+                OperandType::Input | OperandType::Output if name <= D3D10_SB_NAME_SAMPLE_INDEX => {
+                    (dr::SemanticName::from_word(name), resource_index(register))
+                }
+                OperandType::Input | OperandType::Output => (dr::SemanticName::Undefined, resource_index(register)),
+                OperandType::OutputDepth => (dr::SemanticName::Depth, !0),
+                OperandType::OutputDepthGreaterEqual => (dr::SemanticName::DepthGreaterEqual, !0),
+                OperandType::OutputDepthLessEqual => (dr::SemanticName::DepthLessEqual, !0),
+                OperandType::OutputCoverageMask => (dr::SemanticName::Coverage, !0),
+                _ => return None,
+            };
+
+            Some(Element {
+                name: format!("{}{}", operand_prefix(register.file as u32), resource_index(register)),
+                semantic_type,
+                semantic_index: 0,
+                ty: Type::Float,
+                register: index,
+                mask: register.components.mask(),
+            })
+        })
+        .collect()
+}
+
+/// Translates the shader of a DXBC container to a SPIR-V module for Vulkan
+/// with an entry point named `entrypoint`.
+pub fn translate(bytes: &[u8], entrypoint: &str) -> Result<Vec<u32>, Error> {
+    let mut consumer = SpirvConsumer {
+        shader: Shader::default(),
+        program: None,
+    };
+
+    {
+        let mut parser = Parser::new(bytes, &mut consumer);
+        parser.parse()?;
+    }
+
+    let program = match consumer.program.take() {
+        Some(program) => program,
+        None => return Err(Error::MissingProgram),
+    };
+    Translator::new(&consumer.shader, &program)?.translate(entrypoint)
+}
@@ -0,0 +1,1100 @@
+//! Instruction selection for SPIR-V function bodies.
+//!
+//! Every SSA value gets a temp register of its own, components start at `x`.
+//! Structured control flow maps onto `if`/`loop` blocks directly and phis are
+//! resolved by moves at the end of their predecessors.
+//!
+//! The body is translated twice: the first pass records where each temp is
+//! accessed, which is used to pack the temps into as few `r#` registers as
+//! possible for the second one.
+
+use rspirv::mr;
+use spirv;
+
+use dxbc::dr::builder::{ComponentMode, Instruction, Modifier, NumComponent, Operand, OperandType, X, Y, Z, W};
+use dxbc::dr::shex::TestBoolean;
+
+use super::{invalid, unsupported, Error, Metadata, Ty, Vector};
+use glsl_std450::*;
+
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+
+const COMPONENTS: [u8; 4] = [X, Y, Z, W];
+
+/// Register file of a value, temps are named by the id owning them until
+/// they are allocated.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Location {
+    Temp(u32),
+    Input(u32),
+    Output(u32),
+    Imm([u32; 4]),
+}
+
+/// Value of an SSA id, `swizzle` selects its `count` components.
+#[derive(Debug, Copy, Clone)]
+struct Value {
+    location: Location,
+    swizzle: [u8; 4],
+    count: u32,
+}
+
+impl Value {
+    fn new(location: Location, count: u32) -> Self {
+        Value {
+            location,
+            swizzle: [0, 1, 2, 3],
+            count,
+        }
+    }
+
+    fn component(&self, idx: u32) -> Value {
+        let comp = self.swizzle[idx as usize];
+        Value {
+            location: self.location,
+            swizzle: [comp; 4],
+            count: 1,
+        }
+    }
+}
+
+/// Memory a pointer refers to, either a whole register or one component.
+#[derive(Debug, Copy, Clone)]
+pub struct Pointer {
+    pub location: Location,
+    pub component: Option<u32>,
+    pub count: u32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Loop {
+    header: u32,
+    merge: u32,
+    continue_target: u32,
+}
+
+pub struct FunctionTranslator<'a> {
+    meta: &'a Metadata,
+    blocks: HashMap<u32, &'a mr::BasicBlock>,
+    constants: HashMap<u32, Value>,
+    io: &'a HashMap<u32, Pointer>,
+
+    values: HashMap<u32, Value>,
+    pointers: HashMap<u32, Pointer>,
+    loops: Vec<Loop>,
+    code: Vec<Instruction>,
+    next_id: u32,
+
+    // live range of every temp, as positions in `code`
+    ranges: HashMap<u32, (usize, usize)>,
+    loop_ranges: Vec<(usize, usize)>,
+    variables: HashSet<u32>,
+    registers: HashMap<u32, u32>,
+}
+
+impl<'a> FunctionTranslator<'a> {
+    pub fn new(meta: &'a Metadata, module: &'a mr::Module, io: &'a HashMap<u32, Pointer>) -> Result<Self, Error> {
+        let next_id = match module.header {
+            Some(ref header) => header.bound,
+            None => return invalid("module without a header"),
+        };
+        let mut translator = FunctionTranslator {
+            meta,
+            blocks: HashMap::new(),
+            constants: HashMap::new(),
+            io,
+            values: HashMap::new(),
+            pointers: HashMap::new(),
+            loops: Vec::new(),
+            code: Vec::new(),
+            next_id,
+            ranges: HashMap::new(),
+            loop_ranges: Vec::new(),
+            variables: HashSet::new(),
+            registers: HashMap::new(),
+        };
+
+        translator.conv_constants(module)?;
+        Ok(translator)
+    }
+
+    fn conv_constants(&mut self, module: &mr::Module) -> Result<(), Error> {
+        for inst in &module.types_global_values {
+            let id = match inst.result_id {
+                Some(id) => id,
+                None => continue,
+            };
+
+            let value = match inst.class.opcode {
+                spirv::Op::Constant => {
+                    let bits = match inst.operands[0] {
+                        mr::Operand::LiteralInt32(val) => val,
+                        mr::Operand::LiteralFloat32(val) => val.to_bits(),
+                        ref operand => return unsupported(&format!("constant {:?}", operand)),
+                    };
+                    Value::new(Location::Imm([bits; 4]), 1)
+                }
+                spirv::Op::ConstantTrue => Value::new(Location::Imm([!0; 4]), 1),
+                spirv::Op::ConstantFalse => Value::new(Location::Imm([0; 4]), 1),
+                spirv::Op::ConstantNull | spirv::Op::Undef => {
+                    let count = self.count(result_type(inst)?)?;
+                    Value::new(Location::Imm([0; 4]), count)
+                }
+                spirv::Op::ConstantComposite => {
+                    let mut imm = [0; 4];
+                    let mut count = 0;
+                    for operand in &inst.operands {
+                        let constant = match self.constants.get(&id_of(operand)?) {
+                            Some(&constant) => constant,
+                            None => return invalid(&format!("composite {} of undefined constants", id)),
+                        };
+                        for idx in 0..constant.count {
+                            if let Location::Imm(bits) = constant.location {
+                                imm[count] = bits[constant.swizzle[idx as usize] as usize];
+                            }
+                            count += 1;
+                        }
+                    }
+                    Value::new(Location::Imm(imm), count as u32)
+                }
+                _ => continue,
+            };
+
+            self.constants.insert(id, value);
+        }
+
+        Ok(())
+    }
+
+    /// Translates the body of `function`, returns the instructions and the
+    /// number of temp registers they use.
+    pub fn translate(mut self, function: &'a mr::Function) -> Result<(Vec<Instruction>, u32), Error> {
+        for block in &function.basic_blocks {
+            let label = match block.label {
+                Some(ref label) => result_id(label)?,
+                None => return invalid("block without a label"),
+            };
+            self.blocks.insert(label, block);
+        }
+
+        let entry = match function.basic_blocks.first().and_then(|block| block.label.as_ref()) {
+            Some(label) => result_id(label)?,
+            None => return invalid("function without blocks"),
+        };
+        let bound = self.next_id;
+
+        self.emit_region(entry, None)?;
+        self.allocate_registers();
+
+        // second pass with the allocated registers
+        self.next_id = bound;
+        self.values.clear();
+        self.pointers.clear();
+        self.code.clear();
+        self.emit_region(entry, None)?;
+
+        let temps = self.registers.values().map(|&reg| reg + 1).max().unwrap_or(0);
+        Ok((self.code, temps))
+    }
+
+    fn allocate_registers(&mut self) {
+        let mut ranges = self.ranges.clone();
+
+        // temps live across loop iterations: values from before a loop must
+        // survive until its end and variables can be carried over by memory
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &(start, end) in &self.loop_ranges {
+                for (id, range) in ranges.iter_mut() {
+                    let extended = if self.variables.contains(id) {
+                        if range.0 <= end && range.1 >= start {
+                            (cmp::min(range.0, start), cmp::max(range.1, end))
+                        } else {
+                            *range
+                        }
+                    } else if range.0 < start && range.1 > start {
+                        (range.0, cmp::max(range.1, end))
+                    } else {
+                        *range
+                    };
+
+                    if extended != *range {
+                        *range = extended;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let mut ranges = ranges.into_iter().collect::<Vec<_>>();
+        ranges.sort_by_key(|&(id, (start, _))| (start, id));
+
+        // linear scan, ranges are inclusive
+        let mut active: Vec<(usize, u32)> = Vec::new();
+        for (id, (start, end)) in ranges {
+            active.retain(|&(active_end, _)| active_end >= start);
+
+            let reg = (0..).find(|reg| active.iter().all(|&(_, active_reg)| active_reg != *reg)).unwrap();
+            active.push((end, reg));
+            self.registers.insert(id, reg);
+        }
+    }
+
+    fn touch(&mut self, id: u32) {
+        let pos = self.code.len();
+        let range = self.ranges.entry(id).or_insert((pos, pos));
+        range.0 = cmp::min(range.0, pos);
+        range.1 = cmp::max(range.1, pos);
+    }
+
+    fn temp(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn count(&self, type_id: u32) -> Result<u32, Error> {
+        match self.meta.get_type(type_id) {
+            Some(&Ty::Vector(Vector { count, .. })) => Ok(count),
+            Some(&Ty::Bool) | Some(&Ty::Integer(..)) | Some(&Ty::Float(..)) => Ok(1),
+            Some(ty) => unsupported(&format!("values of type {:?}", ty)),
+            None => invalid(&format!("undefined type {}", type_id)),
+        }
+    }
+
+    fn value(&self, operand: &mr::Operand) -> Result<Value, Error> {
+        let id = id_of(operand)?;
+        match self.values.get(&id).or_else(|| self.constants.get(&id)) {
+            Some(&value) => Ok(value),
+            None => invalid(&format!("value {} used before its definition", id)),
+        }
+    }
+
+    fn pointer(&self, operand: &mr::Operand) -> Result<Pointer, Error> {
+        let id = id_of(operand)?;
+        match self.pointers.get(&id).or_else(|| self.io.get(&id)) {
+            Some(&pointer) => Ok(pointer),
+            None => unsupported(&format!("pointer {} to a variable outside the function and interface", id)),
+        }
+    }
+
+    fn block(&self, label: u32) -> Result<&'a mr::BasicBlock, Error> {
+        match self.blocks.get(&label) {
+            Some(&block) => Ok(block),
+            None => invalid(&format!("branch to {}, which isn't a block of the function", label)),
+        }
+    }
+
+    fn src(&mut self, value: Value, modifier: Modifier) -> Result<Operand, Error> {
+        let swizzle = if value.count == 1 {
+            [value.swizzle[0]; 4]
+        } else {
+            let last = value.swizzle[value.count as usize - 1];
+            let mut swizzle = [last; 4];
+            swizzle[..value.count as usize].copy_from_slice(&value.swizzle[..value.count as usize]);
+            swizzle
+        };
+
+        let component_mode = NumComponent::D4(ComponentMode::Swizzle(
+            COMPONENTS[swizzle[0] as usize],
+            COMPONENTS[swizzle[1] as usize],
+            COMPONENTS[swizzle[2] as usize],
+            COMPONENTS[swizzle[3] as usize],
+        ));
+
+        Ok(match value.location {
+            Location::Temp(id) => {
+                self.touch(id);
+                let reg = self.registers.get(&id).cloned().unwrap_or(0);
+                Operand::register(reg, modifier, component_mode)
+            }
+            Location::Input(reg) => Operand::input(reg, modifier, component_mode),
+            Location::Imm(bits) => {
+                let ty = if value.count == 1 {
+                    OperandType::Imm32(bits[swizzle[0] as usize])
+                } else {
+                    OperandType::Imm32x4(
+                        bits[swizzle[0] as usize],
+                        bits[swizzle[1] as usize],
+                        bits[swizzle[2] as usize],
+                        bits[swizzle[3] as usize],
+                    )
+                };
+                let component_mode = if value.count == 1 { NumComponent::D1 } else { NumComponent::D4(ComponentMode::Mask(0)) };
+                Operand::new(ty, modifier, component_mode)
+            }
+            // outputs are write only
+            Location::Output(..) => return unsupported("reading an output"),
+        })
+    }
+
+    // source reading a single component, for conditions
+    fn select(&mut self, value: Value) -> Result<Operand, Error> {
+        match value.location {
+            Location::Temp(id) => {
+                self.touch(id);
+                let reg = self.registers.get(&id).cloned().unwrap_or(0);
+                let component_mode = NumComponent::D4(ComponentMode::Select(COMPONENTS[value.swizzle[0] as usize]));
+                Ok(Operand::register(reg, Modifier::None, component_mode))
+            }
+            _ => self.src(value.component(0), Modifier::None),
+        }
+    }
+
+    fn dst(&mut self, location: Location, mask: u8) -> Result<Operand, Error> {
+        let component_mode = NumComponent::D4(ComponentMode::Mask(mask));
+        match location {
+            Location::Temp(id) => {
+                self.touch(id);
+                let reg = self.registers.get(&id).cloned().unwrap_or(0);
+                Ok(Operand::register(reg, Modifier::None, component_mode))
+            }
+            Location::Output(reg) => Ok(Operand::output(reg, Modifier::None, component_mode)),
+            Location::Input(..) => invalid("store to an input"),
+            Location::Imm(..) => invalid("store to a constant"),
+        }
+    }
+
+    fn null() -> Operand {
+        Operand::new(OperandType::Null, Modifier::None, NumComponent::D0)
+    }
+
+    // defines the result of `inst` in a new temp, returns the destination operand
+    fn def(&mut self, inst: &mr::Instruction) -> Result<Operand, Error> {
+        let id = result_id(inst)?;
+        let count = self.count(result_type(inst)?)?;
+        self.def_id(id, count)
+    }
+
+    fn def_id(&mut self, id: u32, count: u32) -> Result<Operand, Error> {
+        self.values.insert(id, Value::new(Location::Temp(id), count));
+        self.dst(Location::Temp(id), mask(count))
+    }
+
+    fn alias(&mut self, inst: &mr::Instruction, value: Value) -> Result<(), Error> {
+        self.values.insert(result_id(inst)?, value);
+        Ok(())
+    }
+
+    fn mov(&mut self, dst: Operand, value: Value, modifier: Modifier) -> Result<(), Error> {
+        let src = self.src(value, modifier)?;
+        self.code.push(Instruction::Mov { dst, src, saturated: false });
+        Ok(())
+    }
+
+    fn unary<F>(&mut self, inst: &mr::Instruction, args: &[mr::Operand], modifier: Modifier, build: F) -> Result<(), Error>
+    where
+        F: FnOnce(Operand, Operand) -> Instruction,
+    {
+        let value = self.value(&args[0])?;
+        let src = self.src(value, modifier)?;
+        let dst = self.def(inst)?;
+        self.code.push(build(dst, src));
+        Ok(())
+    }
+
+    fn binary<F>(&mut self, inst: &mr::Instruction, args: &[mr::Operand], swap: bool, modifier: Modifier, build: F) -> Result<(), Error>
+    where
+        F: FnOnce(Operand, Operand, Operand) -> Instruction,
+    {
+        let (a, b) = if swap { (&args[1], &args[0]) } else { (&args[0], &args[1]) };
+        let a = self.value(a)?;
+        let b = self.value(b)?;
+        let a = self.src(a, Modifier::None)?;
+        let b = self.src(b, modifier)?;
+        let dst = self.def(inst)?;
+        self.code.push(build(dst, a, b));
+        Ok(())
+    }
+
+    fn ternary<F>(&mut self, inst: &mr::Instruction, args: &[mr::Operand], build: F) -> Result<(), Error>
+    where
+        F: FnOnce(Operand, Operand, Operand, Operand) -> Instruction,
+    {
+        let a = self.value(&args[0])?;
+        let b = self.value(&args[1])?;
+        let c = self.value(&args[2])?;
+        let a = self.src(a, Modifier::None)?;
+        let b = self.src(b, Modifier::None)?;
+        let c = self.src(c, Modifier::None)?;
+        let dst = self.def(inst)?;
+        self.code.push(build(dst, a, b, c));
+        Ok(())
+    }
+
+    fn dot(&mut self, dst: Operand, a: Value, b: Value, modifier: Modifier) -> Result<(), Error> {
+        let count = a.count;
+        let a = self.src(a, modifier)?;
+        let b = self.src(b, modifier)?;
+        self.code.push(match count {
+            2 => Instruction::Dp2 { dst, a, b, saturated: false },
+            3 => Instruction::Dp3 { dst, a, b, saturated: false },
+            4 => Instruction::Dp4 { dst, a, b, saturated: false },
+            _ => Instruction::Mul { dst, a, b, saturated: false },
+        });
+        Ok(())
+    }
+
+    /// Builds a value from single components, given as value and component
+    /// index for each result component.
+    fn gather(&mut self, inst: &mr::Instruction, components: &[(Value, u32)]) -> Result<(), Error> {
+        let first = components[0].0.location;
+        let single = components.iter().all(|&(value, _)| value.location == first);
+
+        // everything from a single register or constant, no need to copy
+        if single || components.iter().all(|&(value, _)| is_imm(value.location)) {
+            let mut swizzle = [0; 4];
+            let mut bits = [0; 4];
+            for (i, &(value, idx)) in components.iter().enumerate() {
+                swizzle[i] = value.swizzle[idx as usize];
+                if let Location::Imm(imm) = value.location {
+                    bits[i] = imm[swizzle[i] as usize];
+                    swizzle[i] = i as u8;
+                }
+            }
+
+            let location = if is_imm(first) { Location::Imm(bits) } else { first };
+            return self.alias(inst, Value { location, swizzle, count: components.len() as u32 });
+        }
+
+        let id = result_id(inst)?;
+        self.values.insert(id, Value::new(Location::Temp(id), components.len() as u32));
+
+        // one move for each source register, constants are merged
+        let mut done = [false; 4];
+        for i in 0..components.len() {
+            if done[i] {
+                continue;
+            }
+
+            let location = components[i].0.location;
+            let mut write_mask = 0;
+            let mut swizzle = [0; 4];
+            let mut bits = [0; 4];
+
+            for (j, &(value, idx)) in components.iter().enumerate().skip(i) {
+                let same = value.location == location || (is_imm(value.location) && is_imm(location));
+                if done[j] || !same {
+                    continue;
+                }
+
+                done[j] = true;
+                write_mask |= COMPONENTS[j];
+                swizzle[j] = value.swizzle[idx as usize];
+                if let Location::Imm(imm) = value.location {
+                    bits[j] = imm[swizzle[j] as usize];
+                    swizzle[j] = j as u8;
+                }
+            }
+
+            let value = match location {
+                // single constants are written as scalars
+                Location::Imm(..) if write_mask.count_ones() == 1 => {
+                    let comp = write_mask.trailing_zeros() as usize - 4;
+                    Value::new(Location::Imm([bits[comp]; 4]), 1)
+                }
+                Location::Imm(..) => Value { location: Location::Imm(bits), swizzle, count: 4 },
+                _ => Value { location, swizzle, count: 4 },
+            };
+
+            let dst = self.dst(Location::Temp(id), write_mask)?;
+            self.mov(dst, value, Modifier::None)?;
+        }
+
+        Ok(())
+    }
+
+    fn emit_region(&mut self, mut label: u32, stop: Option<u32>) -> Result<(), Error> {
+        loop {
+            if Some(label) == stop {
+                return Ok(());
+            }
+
+            let block = self.block(label)?;
+            let loop_merge = block.instructions.iter().find(|inst| inst.class.opcode == spirv::Op::LoopMerge);
+
+            label = match loop_merge {
+                Some(merge) => {
+                    let l = Loop {
+                        header: label,
+                        merge: id_of(&merge.operands[0])?,
+                        continue_target: id_of(&merge.operands[1])?,
+                    };
+                    self.emit_loop(l)?
+                }
+                _ => match self.emit_block(label, stop)? {
+                    Some(next) => next,
+                    None => return Ok(()),
+                },
+            };
+        }
+    }
+
+    fn emit_loop(&mut self, l: Loop) -> Result<u32, Error> {
+        let start = self.code.len();
+        self.loops.push(l);
+        self.code.push(Instruction::Loop);
+
+        if let Some(next) = self.emit_block(l.header, Some(l.continue_target))? {
+            self.emit_region(next, Some(l.continue_target))?;
+        }
+
+        if l.continue_target != l.header {
+            self.emit_region(l.continue_target, Some(l.header))?;
+        }
+
+        self.code.push(Instruction::EndLoop);
+        self.loops.pop();
+        self.loop_ranges.push((start, self.code.len()));
+
+        Ok(l.merge)
+    }
+
+    // emits a block, returns the block to continue with
+    fn emit_block(&mut self, label: u32, stop: Option<u32>) -> Result<Option<u32>, Error> {
+        let block = self.block(label)?;
+        let (terminator, body) = match block.instructions.split_last() {
+            Some(split) => split,
+            None => return invalid(&format!("block {} without a terminator", label)),
+        };
+
+        let mut selection_merge = None;
+        for inst in body {
+            match inst.class.opcode {
+                spirv::Op::Phi => {
+                    let count = self.count(result_type(inst)?)?;
+                    let id = result_id(inst)?;
+                    self.values.insert(id, Value::new(Location::Temp(id), count));
+                }
+                spirv::Op::SelectionMerge => selection_merge = Some(id_of(&inst.operands[0])?),
+                spirv::Op::LoopMerge => {}
+                _ => self.emit_instruction(inst)?,
+            }
+        }
+
+        let ops = &terminator.operands;
+        match terminator.class.opcode {
+            spirv::Op::Return => {
+                self.code.push(Instruction::Ret);
+                Ok(None)
+            }
+            spirv::Op::Kill => {
+                let src = self.src(Value::new(Location::Imm([!0; 4]), 1), Modifier::None)?;
+                self.code.push(Instruction::Discard { src, test: TestBoolean::NonZero });
+                Ok(None)
+            }
+            spirv::Op::Unreachable => Ok(None),
+            spirv::Op::Branch => self.branch(label, id_of(&ops[0])?, stop),
+            spirv::Op::BranchConditional => {
+                let condition = self.value(&ops[0])?;
+                let (true_label, false_label) = (id_of(&ops[1])?, id_of(&ops[2])?);
+
+                if let Some(merge) = selection_merge {
+                    self.emit_selection(label, condition, true_label, false_label, merge)?;
+                    return Ok(Some(merge));
+                }
+
+                // conditional break or continue out of the innermost loop
+                let l = match self.loops.last() {
+                    Some(&l) => l,
+                    None => return invalid(&format!("conditional branch in block {} without a merge", label)),
+                };
+                let (exit, other, test) = if true_label == l.merge || true_label == l.continue_target {
+                    (true_label, false_label, TestBoolean::NonZero)
+                } else if false_label == l.merge || false_label == l.continue_target {
+                    (false_label, true_label, TestBoolean::Zero)
+                } else {
+                    return unsupported(&format!("conditional branch in block {} leaving an outer loop", label));
+                };
+
+                if exit == l.merge && !self.has_phis(exit)? {
+                    let src = self.select(condition)?;
+                    self.code.push(Instruction::BreakC { src, test });
+                } else {
+                    let src = self.select(condition)?;
+                    self.code.push(Instruction::If { src, test });
+                    self.branch(label, exit, None)?;
+                    self.code.push(Instruction::EndIf);
+                }
+
+                self.branch(label, other, stop)
+            }
+            opcode => unsupported(&format!("terminator {:?}", opcode)),
+        }
+    }
+
+    fn emit_selection(&mut self, label: u32, condition: Value, true_label: u32, false_label: u32, merge: u32) -> Result<(), Error> {
+        let start = self.code.len();
+        let src = self.select(condition)?;
+        self.code.push(Instruction::If { src, test: TestBoolean::NonZero });
+        self.emit_edge(label, true_label, merge)?;
+
+        // empty then branch, test for zero instead
+        let (test, other) = if self.code.len() == start + 1 {
+            self.code.truncate(start);
+            (TestBoolean::Zero, None)
+        } else {
+            (TestBoolean::NonZero, Some(self.code.len()))
+        };
+
+        match other {
+            None => {
+                let src = self.select(condition)?;
+                self.code.push(Instruction::If { src, test });
+                self.emit_edge(label, false_label, merge)?;
+            }
+            Some(else_pos) => {
+                self.code.push(Instruction::Else);
+                self.emit_edge(label, false_label, merge)?;
+                if self.code.len() == else_pos + 1 {
+                    self.code.truncate(else_pos);
+                }
+            }
+        }
+
+        self.code.push(Instruction::EndIf);
+        Ok(())
+    }
+
+    fn emit_edge(&mut self, from: u32, to: u32, merge: u32) -> Result<(), Error> {
+        if let Some(next) = self.branch(from, to, Some(merge))? {
+            self.emit_region(next, Some(merge))?;
+        }
+        Ok(())
+    }
+
+    // moves into the phis of `to` and jumps out of loops if needed, returns
+    // the block to continue with
+    fn branch(&mut self, from: u32, to: u32, stop: Option<u32>) -> Result<Option<u32>, Error> {
+        self.emit_phi_moves(from, to)?;
+
+        if let Some(&l) = self.loops.last() {
+            if to == l.header {
+                return Ok(None);
+            }
+            if to == l.merge {
+                self.code.push(Instruction::Break);
+                return Ok(None);
+            }
+            if to == l.continue_target && stop != Some(to) {
+                self.emit_region(l.continue_target, Some(l.header))?;
+                self.code.push(Instruction::Continue);
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(to))
+    }
+
+    fn has_phis(&self, label: u32) -> Result<bool, Error> {
+        Ok(self.block(label)?.instructions.iter().any(|inst| inst.class.opcode == spirv::Op::Phi))
+    }
+
+    fn emit_phi_moves(&mut self, from: u32, to: u32) -> Result<(), Error> {
+        let block = self.block(to)?;
+        let mut moves = Vec::new();
+
+        for inst in &block.instructions {
+            if inst.class.opcode != spirv::Op::Phi {
+                continue;
+            }
+
+            let count = self.count(result_type(inst)?)?;
+            for pair in inst.operands.chunks(2) {
+                if pair.len() == 2 && id_of(&pair[1])? == from {
+                    moves.push((result_id(inst)?, count, self.value(&pair[0])?));
+                }
+            }
+        }
+
+        // phis reading each other need to go through temps, moves are parallel
+        let targets = moves.iter().map(|&(id, _, _)| Location::Temp(id)).collect::<Vec<_>>();
+        let conflict = moves.iter().any(|&(_, _, value)| targets.contains(&value.location));
+
+        if conflict {
+            let mut copies = Vec::new();
+            for &(id, count, value) in &moves {
+                let tmp = self.temp();
+                let dst = self.dst(Location::Temp(tmp), mask(count))?;
+                self.mov(dst, value, Modifier::None)?;
+                copies.push((id, count, Value::new(Location::Temp(tmp), count)));
+            }
+            moves = copies;
+        }
+
+        for (id, count, value) in moves {
+            let dst = self.dst(Location::Temp(id), mask(count))?;
+            self.mov(dst, value, Modifier::None)?;
+        }
+
+        Ok(())
+    }
+
+    fn emit_instruction(&mut self, inst: &mr::Instruction) -> Result<(), Error> {
+        let args = &inst.operands[..];
+        match inst.class.opcode {
+            spirv::Op::Variable => {
+                let id = result_id(inst)?;
+                let count = match self.meta.get_type(result_type(inst)?) {
+                    Some(&Ty::Pointer(ref pointer)) => match *pointer.ty {
+                        Ty::Vector(Vector { count, .. }) => count,
+                        _ => 1,
+                    },
+                    _ => return invalid(&format!("variable {} of a type other than a pointer", id)),
+                };
+
+                self.variables.insert(id);
+                self.pointers.insert(id, Pointer { location: Location::Temp(id), component: None, count });
+
+                if let Some(initializer) = args.get(1) {
+                    let value = self.value(initializer)?;
+                    let dst = self.dst(Location::Temp(id), mask(count))?;
+                    self.mov(dst, value, Modifier::None)?;
+                }
+            }
+            spirv::Op::Load => {
+                let pointer = self.pointer(&args[0])?;
+                let value = Value::new(pointer.location, pointer.count);
+                let value = match pointer.component {
+                    Some(component) => value.component(component),
+                    None => value,
+                };
+
+                match pointer.location {
+                    // inputs can't change, read them directly
+                    Location::Input(..) => self.alias(inst, value)?,
+                    _ => {
+                        let dst = self.def(inst)?;
+                        self.mov(dst, value, Modifier::None)?;
+                    }
+                }
+            }
+            spirv::Op::Store => {
+                let pointer = self.pointer(&args[0])?;
+                let value = self.value(&args[1])?;
+                let write_mask = match pointer.component {
+                    Some(component) => COMPONENTS[component as usize],
+                    None => mask(pointer.count),
+                };
+                let dst = self.dst(pointer.location, write_mask)?;
+                self.mov(dst, value, Modifier::None)?;
+            }
+            spirv::Op::AccessChain | spirv::Op::InBoundsAccessChain => {
+                let base = self.pointer(&args[0])?;
+                if args.len() != 2 || base.component.is_some() {
+                    return unsupported("access chains of more than one index");
+                }
+
+                let index = match self.constants.get(&id_of(&args[1])?) {
+                    Some(&Value { location: Location::Imm(bits), .. }) => bits[0],
+                    // TODO: dynamic indexing
+                    _ => return unsupported("access chains with a dynamic index"),
+                };
+
+                let pointer = Pointer { component: Some(index), count: 1, ..base };
+                self.pointers.insert(result_id(inst)?, pointer);
+            }
+            spirv::Op::Undef => {
+                let count = self.count(result_type(inst)?)?;
+                self.alias(inst, Value::new(Location::Imm([0; 4]), count))?;
+            }
+            spirv::Op::CopyObject | spirv::Op::Bitcast => {
+                let value = self.value(&args[0])?;
+                self.alias(inst, value)?;
+            }
+            spirv::Op::CompositeExtract => {
+                if args.len() != 2 {
+                    return unsupported("composite extracts of more than one index");
+                }
+                let value = self.value(&args[0])?.component(literal(&args[1])?);
+                self.alias(inst, value)?;
+            }
+            spirv::Op::CompositeInsert => {
+                let object = self.value(&args[0])?;
+                let composite = self.value(&args[1])?;
+                let index = literal(&args[2])?;
+                let components = (0..composite.count)
+                    .map(|i| if i == index { (object, 0) } else { (composite, i) })
+                    .collect::<Vec<_>>();
+                self.gather(inst, &components)?;
+            }
+            spirv::Op::CompositeConstruct => {
+                let mut components = Vec::new();
+                for operand in args {
+                    let value = self.value(operand)?;
+                    for i in 0..value.count {
+                        components.push((value, i));
+                    }
+                }
+                self.gather(inst, &components)?;
+            }
+            spirv::Op::VectorShuffle => {
+                let a = self.value(&args[0])?;
+                let b = self.value(&args[1])?;
+                let mut components = Vec::new();
+                for operand in &args[2..] {
+                    components.push(match literal(operand)? {
+                        idx if idx < a.count => (a, idx),
+                        idx if idx < a.count + b.count => (b, idx - a.count),
+                        // undefined component
+                        _ => (a, 0),
+                    });
+                }
+                self.gather(inst, &components)?;
+            }
+            spirv::Op::FAdd => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Add { dst, a, b, saturated: false })?,
+            spirv::Op::FSub => self.binary(inst, args, false, Modifier::Neg, |dst, a, b| Instruction::Add { dst, a, b, saturated: false })?,
+            spirv::Op::FMul | spirv::Op::VectorTimesScalar => {
+                self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Mul { dst, a, b, saturated: false })?
+            }
+            spirv::Op::FDiv => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Div { dst, a, b, saturated: false })?,
+            spirv::Op::FNegate => self.unary(inst, args, Modifier::Neg, |dst, src| Instruction::Mov { dst, src, saturated: false })?,
+            spirv::Op::IAdd => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Iadd { dst, a, b, saturated: false })?,
+            spirv::Op::ISub => self.binary(inst, args, false, Modifier::Neg, |dst, a, b| Instruction::Iadd { dst, a, b, saturated: false })?,
+            spirv::Op::IMul => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Imul {
+                dst_hi: Self::null(),
+                dst_lo: dst,
+                a,
+                b,
+                saturated: false,
+            })?,
+            spirv::Op::UDiv => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Udiv {
+                dst_quotient: dst,
+                dst_remainder: Self::null(),
+                a,
+                b,
+                saturated: false,
+            })?,
+            spirv::Op::UMod => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Udiv {
+                dst_quotient: Self::null(),
+                dst_remainder: dst,
+                a,
+                b,
+                saturated: false,
+            })?,
+            spirv::Op::SNegate => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Ineg { dst, src, saturated: false })?,
+            spirv::Op::Not | spirv::Op::LogicalNot => {
+                self.unary(inst, args, Modifier::None, |dst, src| Instruction::Not { dst, src, saturated: false })?
+            }
+            spirv::Op::BitwiseAnd | spirv::Op::LogicalAnd => {
+                self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::And { dst, a, b, saturated: false })?
+            }
+            spirv::Op::BitwiseOr | spirv::Op::LogicalOr => {
+                self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Or { dst, a, b, saturated: false })?
+            }
+            spirv::Op::BitwiseXor => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Xor { dst, a, b, saturated: false })?,
+            spirv::Op::ShiftLeftLogical => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Ishl { dst, a, b, saturated: false })?,
+            spirv::Op::ShiftRightLogical => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Ushr { dst, a, b, saturated: false })?,
+            spirv::Op::ShiftRightArithmetic => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Ishr { dst, a, b, saturated: false })?,
+            spirv::Op::Dot => {
+                let a = self.value(&args[0])?;
+                let b = self.value(&args[1])?;
+                let dst = self.def(inst)?;
+                self.dot(dst, a, b, Modifier::None)?;
+            }
+            spirv::Op::Select => self.ternary(inst, args, |dst, a, b, c| Instruction::Movc { dst, a, b, c, saturated: false })?,
+            spirv::Op::FOrdEqual | spirv::Op::FUnordEqual => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Eq { dst, a, b, saturated: false })?,
+            spirv::Op::FOrdNotEqual | spirv::Op::FUnordNotEqual => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Ne { dst, a, b, saturated: false })?,
+            spirv::Op::FOrdLessThan | spirv::Op::FUnordLessThan => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Lt { dst, a, b, saturated: false })?,
+            spirv::Op::FOrdGreaterThan | spirv::Op::FUnordGreaterThan => self.binary(inst, args, true, Modifier::None, |dst, a, b| Instruction::Lt { dst, a, b, saturated: false })?,
+            spirv::Op::FOrdLessThanEqual | spirv::Op::FUnordLessThanEqual => self.binary(inst, args, true, Modifier::None, |dst, a, b| Instruction::Ge { dst, a, b, saturated: false })?,
+            spirv::Op::FOrdGreaterThanEqual | spirv::Op::FUnordGreaterThanEqual => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Ge { dst, a, b, saturated: false })?,
+            spirv::Op::IEqual | spirv::Op::LogicalEqual => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Ieq { dst, a, b, saturated: false })?,
+            spirv::Op::INotEqual | spirv::Op::LogicalNotEqual => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Ine { dst, a, b, saturated: false })?,
+            spirv::Op::SLessThan => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Ilt { dst, a, b, saturated: false })?,
+            spirv::Op::SGreaterThan => self.binary(inst, args, true, Modifier::None, |dst, a, b| Instruction::Ilt { dst, a, b, saturated: false })?,
+            spirv::Op::SLessThanEqual => self.binary(inst, args, true, Modifier::None, |dst, a, b| Instruction::Ige { dst, a, b, saturated: false })?,
+            spirv::Op::SGreaterThanEqual => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Ige { dst, a, b, saturated: false })?,
+            spirv::Op::ULessThan => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Ult { dst, a, b, saturated: false })?,
+            spirv::Op::UGreaterThan => self.binary(inst, args, true, Modifier::None, |dst, a, b| Instruction::Ult { dst, a, b, saturated: false })?,
+            spirv::Op::ULessThanEqual => self.binary(inst, args, true, Modifier::None, |dst, a, b| Instruction::Uge { dst, a, b, saturated: false })?,
+            spirv::Op::UGreaterThanEqual => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Uge { dst, a, b, saturated: false })?,
+            spirv::Op::ConvertSToF => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Itof { dst, src, saturated: false })?,
+            spirv::Op::ConvertUToF => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Utof { dst, src, saturated: false })?,
+            spirv::Op::ConvertFToS => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Ftoi { dst, src, saturated: false })?,
+            spirv::Op::ConvertFToU => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Ftou { dst, src, saturated: false })?,
+            spirv::Op::DPdx => self.unary(inst, args, Modifier::None, |dst, src| Instruction::DerivRtx { dst, src, saturated: false })?,
+            spirv::Op::DPdy => self.unary(inst, args, Modifier::None, |dst, src| Instruction::DerivRty { dst, src, saturated: false })?,
+            spirv::Op::ExtInst => self.emit_glsl(inst, literal(&args[1])?, &args[2..])?,
+            opcode => return unsupported(&format!("instruction {:?}", opcode)),
+        }
+
+        Ok(())
+    }
+
+    fn emit_glsl(&mut self, inst: &mr::Instruction, op: u32, args: &[mr::Operand]) -> Result<(), Error> {
+        match op {
+            GLSL_FABS => self.unary(inst, args, Modifier::Abs, |dst, src| Instruction::Mov { dst, src, saturated: false })?,
+            GLSL_SABS => {
+                // max(x, -x)
+                let id = result_id(inst)?;
+                let x = self.value(&args[0])?;
+
+                let src = self.src(x, Modifier::None)?;
+                let dst = self.def_id(id, x.count)?;
+                self.code.push(Instruction::Ineg { dst, src, saturated: false });
+
+                let result = self.values[&id];
+                let a = self.src(x, Modifier::None)?;
+                let b = self.src(result, Modifier::None)?;
+                let dst = self.dst(Location::Temp(id), mask(x.count))?;
+                self.code.push(Instruction::Imax { dst, a, b, saturated: false });
+            }
+            GLSL_FLOOR => self.unary(inst, args, Modifier::None, |dst, src| Instruction::RoundNi { dst, src, saturated: false })?,
+            GLSL_CEIL => self.unary(inst, args, Modifier::None, |dst, src| Instruction::RoundPi { dst, src, saturated: false })?,
+            GLSL_FRACT => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Frc { dst, src, saturated: false })?,
+            GLSL_SIN => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Sincos {
+                dst_sin: dst,
+                dst_cos: Self::null(),
+                src,
+                saturated: false,
+            })?,
+            GLSL_COS => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Sincos {
+                dst_sin: Self::null(),
+                dst_cos: dst,
+                src,
+                saturated: false,
+            })?,
+            GLSL_EXP2 => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Exp { dst, src, saturated: false })?,
+            GLSL_LOG2 => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Log { dst, src, saturated: false })?,
+            GLSL_SQRT => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Sqrt { dst, src, saturated: false })?,
+            GLSL_INVERSE_SQRT => self.unary(inst, args, Modifier::None, |dst, src| Instruction::Rsq { dst, src, saturated: false })?,
+            GLSL_FMIN => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Min { dst, a, b, saturated: false })?,
+            GLSL_FMAX => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Max { dst, a, b, saturated: false })?,
+            GLSL_UMIN => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Umin { dst, a, b, saturated: false })?,
+            GLSL_UMAX => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Umax { dst, a, b, saturated: false })?,
+            GLSL_SMIN => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Imin { dst, a, b, saturated: false })?,
+            GLSL_SMAX => self.binary(inst, args, false, Modifier::None, |dst, a, b| Instruction::Imax { dst, a, b, saturated: false })?,
+            GLSL_FMA => self.ternary(inst, args, |dst, a, b, c| Instruction::Mad { dst, a, b, c, saturated: false })?,
+            GLSL_FCLAMP => {
+                let id = result_id(inst)?;
+                let count = self.count(result_type(inst)?)?;
+                let (x, min, max) = (self.value(&args[0])?, self.value(&args[1])?, self.value(&args[2])?);
+
+                let dst = self.def_id(id, count)?;
+                let a = self.src(x, Modifier::None)?;
+                let b = self.src(min, Modifier::None)?;
+                self.code.push(Instruction::Max { dst, a, b, saturated: false });
+
+                let result = self.values[&id];
+                let a = self.src(result, Modifier::None)?;
+                let b = self.src(max, Modifier::None)?;
+                let dst = self.dst(Location::Temp(id), mask(count))?;
+                self.code.push(Instruction::Min { dst, a, b, saturated: false });
+            }
+            GLSL_FMIX => {
+                // x + (y - x) * a
+                let id = result_id(inst)?;
+                let count = self.count(result_type(inst)?)?;
+                let (x, y, t) = (self.value(&args[0])?, self.value(&args[1])?, self.value(&args[2])?);
+
+                let dst = self.def_id(id, count)?;
+                let a = self.src(y, Modifier::None)?;
+                let b = self.src(x, Modifier::Neg)?;
+                self.code.push(Instruction::Add { dst, a, b, saturated: false });
+
+                let result = self.values[&id];
+                let a = self.src(result, Modifier::None)?;
+                let b = self.src(t, Modifier::None)?;
+                let c = self.src(x, Modifier::None)?;
+                let dst = self.dst(Location::Temp(id), mask(count))?;
+                self.code.push(Instruction::Mad { dst, a, b, c, saturated: false });
+            }
+            GLSL_POW => {
+                // exp2(log2(x) * y)
+                let id = result_id(inst)?;
+                let count = self.count(result_type(inst)?)?;
+                let (x, y) = (self.value(&args[0])?, self.value(&args[1])?);
+
+                let dst = self.def_id(id, count)?;
+                let src = self.src(x, Modifier::None)?;
+                self.code.push(Instruction::Log { dst, src, saturated: false });
+
+                let result = self.values[&id];
+                let a = self.src(result, Modifier::None)?;
+                let b = self.src(y, Modifier::None)?;
+                let dst = self.dst(Location::Temp(id), mask(count))?;
+                self.code.push(Instruction::Mul { dst, a, b, saturated: false });
+
+                let src = self.src(result, Modifier::None)?;
+                let dst = self.dst(Location::Temp(id), mask(count))?;
+                self.code.push(Instruction::Exp { dst, src, saturated: false });
+            }
+            GLSL_LENGTH => {
+                let id = result_id(inst)?;
+                let x = self.value(&args[0])?;
+
+                let dst = self.def_id(id, 1)?;
+                self.dot(dst, x, x, Modifier::None)?;
+
+                let result = self.values[&id];
+                let src = self.src(result, Modifier::None)?;
+                let dst = self.dst(Location::Temp(id), X)?;
+                self.code.push(Instruction::Sqrt { dst, src, saturated: false });
+            }
+            GLSL_NORMALIZE => {
+                // x * rsq(dot(x, x))
+                let id = result_id(inst)?;
+                let x = self.value(&args[0])?;
+                let tmp = self.temp();
+
+                let dst = self.dst(Location::Temp(tmp), X)?;
+                self.dot(dst, x, x, Modifier::None)?;
+
+                let length = Value::new(Location::Temp(tmp), 1);
+                let src = self.src(length, Modifier::None)?;
+                let dst = self.dst(Location::Temp(tmp), X)?;
+                self.code.push(Instruction::Rsq { dst, src, saturated: false });
+
+                let a = self.src(x, Modifier::None)?;
+                let b = self.src(length, Modifier::None)?;
+                let dst = self.def_id(id, x.count)?;
+                self.code.push(Instruction::Mul { dst, a, b, saturated: false });
+            }
+            _ => return unsupported(&format!("GLSL.std.450 instruction {}", op)),
+        }
+
+        Ok(())
+    }
+}
+
+fn id_of(operand: &mr::Operand) -> Result<u32, Error> {
+    match *operand {
+        mr::Operand::IdRef(id) => Ok(id),
+        _ => invalid(&format!("{:?} instead of an id", operand)),
+    }
+}
+
+fn literal(operand: &mr::Operand) -> Result<u32, Error> {
+    match *operand {
+        mr::Operand::LiteralInt32(val) | mr::Operand::LiteralExtInstInteger(val) => Ok(val),
+        _ => invalid(&format!("{:?} instead of a literal", operand)),
+    }
+}
+
+fn result_id(inst: &mr::Instruction) -> Result<u32, Error> {
+    match inst.result_id {
+        Some(id) => Ok(id),
+        None => invalid(&format!("{:?} without a result id", inst.class.opcode)),
+    }
+}
+
+fn result_type(inst: &mr::Instruction) -> Result<u32, Error> {
+    match inst.result_type {
+        Some(ty) => Ok(ty),
+        None => invalid(&format!("{:?} without a result type", inst.class.opcode)),
+    }
+}
+
+fn is_imm(location: Location) -> bool {
+    match location {
+        Location::Imm(..) => true,
+        _ => false,
+    }
+}
+
+fn mask(count: u32) -> u8 {
+    COMPONENTS[..count as usize].iter().fold(0, |mask, &comp| mask | comp)
+}
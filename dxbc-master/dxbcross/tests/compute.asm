@@ -0,0 +1,41 @@
+cs_5_0
+dcl_globalFlags refactoringAllowed | enableRawAndStructuredBuffers
+dcl_immediateConstantBuffer { { 1.000000, 0, 0, 0},
+                              { 0, 1.000000, 0, 0} }
+dcl_constantbuffer CB0[4], dynamicIndexed
+dcl_sampler s0, mode_comparison
+dcl_resource_texture2d (float,float,float,float) t0
+dcl_resource_texture2dms(4) (uint,uint,uint,uint) t1
+dcl_resource_raw t2
+dcl_resource_structured t3, 16
+dcl_uav_typed_texture2d (float,float,float,float) u0
+dcl_uav_raw_glc u1
+dcl_uav_structured u2, 8
+dcl_tgsm_raw g0, 64
+dcl_tgsm_structured g1, 4, 32
+dcl_input vThreadID.xyz
+dcl_temps 3
+dcl_indexableTemp x0[4], 4
+dcl_thread_group 8, 8, 1
+mov r0.xyzw, l(1.000000,2.000000,-3.000000,0)
+iadd r0.x, r0.y, l(-5)
+and r1.xy, r0.xyxx, l(0x0000ffff, 0x0000ffff, 0, 0)
+mad_sat r0.xyz, -|r0.xyzx|, cb0[r0.x + 2].xyzx, icb[r1.y + 0].xyzx
+mov x0[r0.x + 1].xyzw, r1.xyzw
+if_nz r0.x
+  sample_aoffimmi(1,-1,0) r2.xyzw, r0.xyxx, t0.xyzw, s0
+else 
+  ld_indexable(texture2d)(float,float,float,float) r2.xyzw, r0.xyzw, t0.xyzw
+endif 
+loop 
+  breakc_z r0.w
+  resinfo_uint r1.xyzw, l(0), t0.xyzw
+endloop 
+ld_raw r0.x, l(0), t2.xxxx
+ld_structured r0.y, r0.x, l(4), t3.xxxx
+store_raw u1.x, l(0), r0.x
+imm_atomic_iadd r0.z, u1, l(0), l(1)
+samplepos r1.xy, t1.xyxx, r0.x
+sampleinfo_uint r1.z, t1.x
+sync_g_t
+ret 
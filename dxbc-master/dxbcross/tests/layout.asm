@@ -0,0 +1,63 @@
+//
+// Buffer Definitions: 
+//
+// cbuffer Globals
+// {
+//
+//   float4x4 transform;                // Offset:    0 Size:    64
+//   float3 tint;                       // Offset:   64 Size:    12
+//   float alpha;                       // Offset:   76 Size:     4
+//   uint flags;                        // Offset:   80 Size:     4
+//
+// }
+//
+//
+// Resource Bindings:
+//
+// Name                                 Type  Format         Dim      HLSL Bind  Count
+// ------------------------------ ---------- ------- ----------- -------------- ------
+// S                                 sampler      NA          NA             s0      1 
+// T                                 texture  float4        cube             t1      1 
+// Globals                           cbuffer      NA          NA            cb0      1 
+//
+//
+//
+// Input signature:
+//
+// Name                 Index   Mask Register SysValue  Format   Used
+// -------------------- ----- ------ -------- -------- ------- ------
+// SV_Position              0   xyzw        0      POS   float       
+// NORMAL                   0   xyz         1     NONE   float   xyz 
+//
+//
+// Output signature:
+//
+// Name                 Index   Mask Register SysValue  Format   Used
+// -------------------- ----- ------ -------- -------- ------- ------
+// SV_Target                0   xyzw        0   TARGET   float   xyzw
+//
+ps_5_0
+dcl_globalFlags refactoringAllowed
+dcl_constantbuffer CB0[6], immediateIndexed
+dcl_sampler s0, mode_default
+dcl_resource_texturecube (float,float,float,float) t1
+dcl_input_ps linear v1.xyz
+dcl_output o0.xyzw
+dcl_temps 2
+dp3 r0.x, v1.xyzx, cb0[0].xyzx
+dp3 r0.y, v1.xyzx, cb0[1].xyzx
+dp3 r0.z, v1.xyzx, cb0[2].xyzx
+sample_indexable(texturecube)(float,float,float,float) r0.xyzw, r0.xyzx, t1.xyzw, s0
+mul r0.xyz, r0.xyzx, cb0[4].xyzx
+and r1.x, cb0[5].x, l(1)
+if_nz r1.x
+  mov o0.xyz, r0.xyzx
+  mov o0.w, cb0[4].w
+  ret 
+endif 
+samplepos r1.xy, rasterizer.xyxx, l(1)
+sampleinfo r1.z, rasterizer.x
+add r0.xyz, r0.xyzx, r1.xyzx
+mov o0.xyz, r0.xyzx
+mov o0.w, l(1.000000)
+ret 
@@ -0,0 +1,262 @@
+extern crate dxbcross;
+extern crate rspirv;
+extern crate spirv_headers as spirv;
+
+use dxbcross::to_spirv::{self, Error};
+
+use rspirv::mr;
+
+use std::collections::{HashMap, HashSet};
+
+macro_rules! sample {
+    ($path:expr) => {
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path))
+    };
+}
+
+// Rank of each instruction in the logical layout of a module.
+fn section(opcode: u32) -> usize {
+    use spirv::Op::*;
+    let sections: [&[spirv::Op]; 8] = [
+        &[Capability],
+        &[Extension],
+        &[ExtInstImport],
+        &[MemoryModel],
+        &[EntryPoint],
+        &[ExecutionMode],
+        &[String, SourceExtension, Source, SourceContinued, Name, MemberName],
+        &[Decorate, MemberDecorate, GroupDecorate, GroupMemberDecorate, DecorationGroup],
+    ];
+    sections.iter().position(|ops| ops.iter().any(|&op| op as u32 == opcode)).unwrap_or(sections.len())
+}
+
+fn is_type(op: spirv::Op) -> bool {
+    use spirv::Op::*;
+    matches!(
+        op,
+        TypeVoid | TypeBool | TypeInt | TypeFloat | TypeVector | TypeMatrix | TypeImage | TypeSampler | TypeSampledImage | TypeArray
+            | TypeRuntimeArray | TypeStruct | TypePointer | TypeFunction
+    )
+}
+
+fn is_terminator(op: spirv::Op) -> bool {
+    use spirv::Op::*;
+    matches!(op, Branch | BranchConditional | Switch | Return | ReturnValue | Kill | Unreachable)
+}
+
+fn id(operand: &mr::Operand) -> Option<u32> {
+    match *operand {
+        mr::Operand::IdRef(id) | mr::Operand::IdScope(id) | mr::Operand::IdMemorySemantics(id) => Some(id),
+        _ => None,
+    }
+}
+
+fn instructions(module: &mr::Module) -> Vec<&mr::Instruction> {
+    let mut instructions: Vec<&mr::Instruction> = Vec::new();
+    instructions.extend(&module.capabilities);
+    instructions.extend(&module.extensions);
+    instructions.extend(&module.ext_inst_imports);
+    instructions.extend(&module.memory_model);
+    instructions.extend(&module.entry_points);
+    instructions.extend(&module.execution_modes);
+    instructions.extend(&module.debugs);
+    instructions.extend(&module.annotations);
+    instructions.extend(&module.types_global_values);
+    for function in &module.functions {
+        instructions.extend(&function.def);
+        instructions.extend(&function.parameters);
+        for block in &function.basic_blocks {
+            instructions.extend(&block.label);
+            instructions.extend(&block.instructions);
+        }
+        instructions.extend(&function.end);
+    }
+    instructions
+}
+
+// The checks of spirv-val that don't need the full grammar, panics on the
+// first violation.
+fn validate(words: &[u32]) -> mr::Module {
+    assert_eq!(words[0], spirv::MAGIC_NUMBER);
+
+    let mut rank = 0;
+    let mut i = 5;
+    while i < words.len() {
+        let (count, opcode) = ((words[i] >> 16) as usize, words[i] & 0xffff);
+        assert!(count > 0 && i + count <= words.len(), "truncated opcode {}", opcode);
+        if opcode == spirv::Op::Function as u32 {
+            break;
+        }
+        assert!(section(opcode) >= rank, "opcode {} out of order", opcode);
+        rank = section(opcode);
+        i += count;
+    }
+
+    let mut loader = mr::Loader::new();
+    rspirv::binary::parse_words(words, &mut loader).unwrap();
+    let module = loader.module();
+    let bound = module.header.as_ref().unwrap().bound;
+
+    let mut definitions = HashMap::new();
+    for instruction in instructions(&module) {
+        if let Some(result) = instruction.result_id {
+            assert!(result > 0 && result < bound, "%{} out of bound {}", result, bound);
+            assert!(definitions.insert(result, instruction).is_none(), "%{} defined twice", result);
+        }
+    }
+
+    let op = |id: u32| definitions.get(&id).map(|instruction| instruction.class.opcode);
+    for instruction in instructions(&module) {
+        if let Some(ty) = instruction.result_type {
+            assert!(op(ty).is_some_and(is_type), "result type of {:?} isn't a type", instruction.class.opcode);
+        }
+        for used in instruction.operands.iter().filter_map(id) {
+            assert!(definitions.contains_key(&used), "{:?} uses undefined %{}", instruction.class.opcode, used);
+        }
+    }
+
+    let mut merges = HashSet::new();
+    for function in &module.functions {
+        assert!(!function.basic_blocks.is_empty());
+        for (b, block) in function.basic_blocks.iter().enumerate() {
+            let (terminator, body) = block.instructions.split_last().expect("empty block");
+            assert!(is_terminator(terminator.class.opcode), "block ends in {:?}", terminator.class.opcode);
+
+            for (k, instruction) in body.iter().enumerate() {
+                let opcode = instruction.class.opcode;
+                assert!(!is_terminator(opcode), "{:?} in the middle of a block", opcode);
+                if opcode == spirv::Op::Variable {
+                    assert_eq!(b, 0, "function variable outside the entry block");
+                }
+                if opcode == spirv::Op::SelectionMerge || opcode == spirv::Op::LoopMerge {
+                    assert_eq!(k + 1, body.len(), "{:?} isn't before the terminator", opcode);
+                    let merge = id(&instruction.operands[0]).unwrap();
+                    assert!(merges.insert(merge), "%{} merges two constructs", merge);
+                }
+            }
+
+            for target in terminator.operands.iter().filter_map(id).skip(match terminator.class.opcode {
+                spirv::Op::Branch => 0,
+                spirv::Op::BranchConditional | spirv::Op::Switch => 1,
+                _ => terminator.operands.len(),
+            }) {
+                assert_eq!(op(target), Some(spirv::Op::Label), "branch to a non label");
+            }
+        }
+    }
+
+    assert_eq!(module.entry_points.len(), 1);
+    let entry_point = &module.entry_points[0];
+    assert_eq!(op(id(&entry_point.operands[1]).unwrap()), Some(spirv::Op::Function));
+    for interface in entry_point.operands[3..].iter().filter_map(id) {
+        let variable = definitions[&interface];
+        assert_eq!(variable.class.opcode, spirv::Op::Variable);
+        match variable.operands[0] {
+            mr::Operand::StorageClass(spirv::StorageClass::Input) | mr::Operand::StorageClass(spirv::StorageClass::Output) => {}
+            ref class => panic!("interface variable in {:?}", class),
+        }
+    }
+
+    module
+}
+
+fn translate(bytes: &[u8]) -> mr::Module {
+    validate(&to_spirv::translate(bytes, "main").unwrap())
+}
+
+fn decorations(module: &mr::Module, decoration: spirv::Decoration) -> Vec<&[mr::Operand]> {
+    module
+        .annotations
+        .iter()
+        .filter(|instruction| instruction.class.opcode == spirv::Op::Decorate)
+        .filter(|instruction| instruction.operands[1] == mr::Operand::Decoration(decoration))
+        .map(|instruction| &instruction.operands[..])
+        .collect()
+}
+
+fn has(module: &mr::Module, op: spirv::Op) -> bool {
+    module.types_global_values.iter().any(|instruction| instruction.class.opcode == op)
+}
+
+#[test]
+fn vertex_shader() {
+    let module = translate(sample!("../dxbcd/reference.dxbc"));
+    assert_eq!(module.entry_points[0].operands[0], mr::Operand::ExecutionModel(spirv::ExecutionModel::Vertex));
+    assert_eq!(module.entry_points[0].operands[2], mr::Operand::LiteralString("main".to_owned()));
+
+    let builtins = decorations(&module, spirv::Decoration::BuiltIn);
+    assert_eq!(builtins.len(), 1);
+    assert_eq!(builtins[0][2], mr::Operand::BuiltIn(spirv::BuiltIn::Position));
+    let locations = decorations(&module, spirv::Decoration::Location);
+    assert_eq!(locations.len(), 1);
+    assert_eq!(locations[0][2], mr::Operand::LiteralInt32(0));
+}
+
+#[test]
+fn entry_point_name() {
+    let words = to_spirv::translate(sample!("../dxbcd/assembled.dxbc"), "vs_main").unwrap();
+    let module = validate(&words);
+    assert_eq!(module.entry_points[0].operands[2], mr::Operand::LiteralString("vs_main".to_owned()));
+}
+
+#[test]
+fn pixel_shader() {
+    let module = translate(sample!("../dxbcd/shader.dxbc"));
+    assert_eq!(module.entry_points[0].operands[0], mr::Operand::ExecutionModel(spirv::ExecutionModel::Fragment));
+    assert!(module.execution_modes.iter().any(|mode| mode.operands[1] == mr::Operand::ExecutionMode(spirv::ExecutionMode::OriginUpperLeft)));
+    assert!(has(&module, spirv::Op::TypeImage));
+    assert!(has(&module, spirv::Op::TypeSampler));
+
+    // the dynamically indexed buffer falls back to an array of registers
+    assert_eq!(decorations(&module, spirv::Decoration::Block).len(), 1);
+    let strides = decorations(&module, spirv::Decoration::ArrayStride);
+    assert_eq!(strides.len(), 1);
+    assert_eq!(strides[0][2], mr::Operand::LiteralInt32(16));
+
+    let sets: Vec<_> = decorations(&module, spirv::Decoration::DescriptorSet).iter().map(|operands| operands[2].clone()).collect();
+    assert_eq!(sets.len(), 3);
+    for &set in &[to_spirv::CONSTANT_BUFFER_SET, to_spirv::RESOURCE_SET, to_spirv::SAMPLER_SET] {
+        assert!(sets.contains(&mr::Operand::LiteralInt32(set)));
+    }
+}
+
+#[test]
+fn constant_buffer_layout() {
+    let module = translate(sample!("tests/layout.dxbc"));
+    let offsets: Vec<_> = module
+        .annotations
+        .iter()
+        .filter(|instruction| instruction.class.opcode == spirv::Op::MemberDecorate)
+        .filter(|instruction| instruction.operands[2] == mr::Operand::Decoration(spirv::Decoration::Offset))
+        .map(|instruction| instruction.operands[3].clone())
+        .collect();
+    let expected: Vec<_> = [0, 64, 76, 80].iter().map(|&offset| mr::Operand::LiteralInt32(offset)).collect();
+    assert_eq!(offsets, expected);
+
+    // samplepos of the rasterizer reads the sample count from a specialization constant
+    let ids = decorations(&module, spirv::Decoration::SpecId);
+    assert_eq!(ids.len(), 1);
+    assert_eq!(ids[0][2], mr::Operand::LiteralInt32(to_spirv::SAMPLE_COUNT_SPEC_ID));
+}
+
+#[test]
+fn compute_shader() {
+    let module = translate(sample!("tests/compute.dxbc"));
+    assert_eq!(module.entry_points[0].operands[0], mr::Operand::ExecutionModel(spirv::ExecutionModel::GLCompute));
+    let size = module
+        .execution_modes
+        .iter()
+        .find(|mode| mode.operands[1] == mr::Operand::ExecutionMode(spirv::ExecutionMode::LocalSize))
+        .unwrap();
+    assert_eq!(size.operands[2..], [mr::Operand::LiteralInt32(8), mr::Operand::LiteralInt32(8), mr::Operand::LiteralInt32(1)]);
+    // raw and structured buffers, both views and UAVs
+    assert_eq!(decorations(&module, spirv::Decoration::BufferBlock).len(), 4);
+}
+
+#[test]
+fn unsupported() {
+    match to_spirv::translate(sample!("../dxbcd/shader_abort.dxbc"), "main") {
+        Err(Error::Unsupported(_)) => {}
+        result => panic!("{:?}", result),
+    }
+}
@@ -0,0 +1,143 @@
+extern crate byteorder;
+extern crate dxbc;
+extern crate dxbcross;
+extern crate rspirv;
+extern crate spirv_headers as spirv;
+
+use byteorder::{ByteOrder, LittleEndian};
+use dxbc::disasm;
+use dxbc::interp::{Interpreter, Value};
+use dxbc::ir::Program;
+use dxbcross::{Error, SpirvModule, TargetVersion};
+
+use rspirv::binary::Assemble;
+use rspirv::mr;
+
+fn translate(spirv: &[u8], entrypoint: &str) -> Vec<u8> {
+    let dwords = SpirvModule::from_bytes(spirv).translate_entrypoint(entrypoint, TargetVersion::V5_0).unwrap();
+    let mut bytes = vec![0; dwords.len() * 4];
+    LittleEndian::write_u32_into(&dwords, &mut bytes);
+    assert_eq!(dxbc::verify(&bytes), Ok(()));
+    bytes
+}
+
+fn floats(values: [f32; 4]) -> Value {
+    [values[0].to_bits(), values[1].to_bits(), values[2].to_bits(), values[3].to_bits()]
+}
+
+#[test]
+fn example_shader() {
+    let bytes = translate(include_bytes!("../examples/shader.spirv"), "vs");
+    let program = Program::parse(&bytes).unwrap().unwrap();
+    let mut interpreter = Interpreter::new(&program).unwrap();
+
+    // `x == 1.0` takes the branch negating the absolute value, the other
+    // one reverses the components, the phi picks the result
+    interpreter.inputs[0] = floats([1.0, -2.0, 3.0, -4.0]);
+    assert_eq!(interpreter.run(&mut (), &mut ()).unwrap(), vec![floats([-1.0, -2.0, -3.0, -4.0])]);
+    interpreter.inputs[0] = floats([2.0, -2.0, 3.0, -4.0]);
+    assert_eq!(interpreter.run(&mut (), &mut ()).unwrap(), vec![floats([-4.0, 3.0, -2.0, 2.0])]);
+}
+
+// Passes a `vec2` at location 1 and a `vec4` at location 0 through to a
+// `vec2` at location 2 and the position.
+fn locations() -> Vec<u8> {
+    let mut b = mr::Builder::new();
+    b.capability(spirv::Capability::Shader);
+    b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+
+    let void = b.type_void();
+    let function_type = b.type_function(void, vec![]);
+    let float = b.type_float(32);
+    let vec2 = b.type_vector(float, 2);
+    let vec4 = b.type_vector(float, 4);
+    let vec2_in = b.type_pointer(None, spirv::StorageClass::Input, vec2);
+    let vec4_in = b.type_pointer(None, spirv::StorageClass::Input, vec4);
+    let vec2_out = b.type_pointer(None, spirv::StorageClass::Output, vec2);
+    let vec4_out = b.type_pointer(None, spirv::StorageClass::Output, vec4);
+
+    let uv = b.variable(vec2_in, None, spirv::StorageClass::Input, None);
+    let pos = b.variable(vec4_in, None, spirv::StorageClass::Input, None);
+    let position = b.variable(vec4_out, None, spirv::StorageClass::Output, None);
+    let v_uv = b.variable(vec2_out, None, spirv::StorageClass::Output, None);
+    b.decorate(uv, spirv::Decoration::Location, vec![mr::Operand::LiteralInt32(1)]);
+    b.decorate(pos, spirv::Decoration::Location, vec![mr::Operand::LiteralInt32(0)]);
+    b.decorate(position, spirv::Decoration::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::Position)]);
+    b.decorate(v_uv, spirv::Decoration::Location, vec![mr::Operand::LiteralInt32(2)]);
+
+    let main = b.begin_function(void, None, spirv::FunctionControl::NONE, function_type).unwrap();
+    b.begin_basic_block(None).unwrap();
+    let value = b.load(vec2, None, uv, None, vec![]).unwrap();
+    b.store(v_uv, value, None, vec![]).unwrap();
+    let value = b.load(vec4, None, pos, None, vec![]).unwrap();
+    b.store(position, value, None, vec![]).unwrap();
+    b.ret().unwrap();
+    b.end_function().unwrap();
+    b.entry_point(spirv::ExecutionModel::Vertex, main, "main", vec![uv, pos, position, v_uv]);
+
+    let words = b.module().assemble();
+    let mut bytes = vec![0; words.len() * 4];
+    LittleEndian::write_u32_into(&words, &mut bytes);
+    bytes
+}
+
+#[test]
+fn registers_from_locations() {
+    let bytes = translate(&locations(), "main");
+
+    let source = disasm::disassemble(&bytes).unwrap();
+    for line in &["dcl_input v0.xyzw", "dcl_input v1.xy", "dcl_output o2.xy", "dcl_output_siv o3.xyzw, position"] {
+        assert!(source.contains(line), "{}", source);
+    }
+
+    let program = Program::parse(&bytes).unwrap().unwrap();
+    let mut interpreter = Interpreter::new(&program).unwrap();
+    interpreter.inputs[0] = floats([1.0, 2.0, 3.0, 4.0]);
+    interpreter.inputs[1] = floats([5.0, 6.0, 0.0, 0.0]);
+    let outputs = interpreter.run(&mut (), &mut ()).unwrap();
+    assert_eq!(outputs[2][..2], floats([5.0, 6.0, 0.0, 0.0])[..2]);
+    assert_eq!(outputs[3], floats([1.0, 2.0, 3.0, 4.0]));
+}
+
+// Doubles the position output in place, which reads it back.
+fn reads_output() -> Vec<u8> {
+    let mut b = mr::Builder::new();
+    b.capability(spirv::Capability::Shader);
+    b.memory_model(spirv::AddressingModel::Logical, spirv::MemoryModel::GLSL450);
+
+    let void = b.type_void();
+    let function_type = b.type_function(void, vec![]);
+    let float = b.type_float(32);
+    let vec4 = b.type_vector(float, 4);
+    let vec4_out = b.type_pointer(None, spirv::StorageClass::Output, vec4);
+
+    let position = b.variable(vec4_out, None, spirv::StorageClass::Output, None);
+    b.decorate(position, spirv::Decoration::BuiltIn, vec![mr::Operand::BuiltIn(spirv::BuiltIn::Position)]);
+
+    let main = b.begin_function(void, None, spirv::FunctionControl::NONE, function_type).unwrap();
+    b.begin_basic_block(None).unwrap();
+    let value = b.load(vec4, None, position, None, vec![]).unwrap();
+    let value = b.fadd(vec4, None, value, value).unwrap();
+    b.store(position, value, None, vec![]).unwrap();
+    b.ret().unwrap();
+    b.end_function().unwrap();
+    b.entry_point(spirv::ExecutionModel::Vertex, main, "main", vec![position]);
+
+    let words = b.module().assemble();
+    let mut bytes = vec![0; words.len() * 4];
+    LittleEndian::write_u32_into(&words, &mut bytes);
+    bytes
+}
+
+#[test]
+fn errors() {
+    match SpirvModule::from_bytes(&locations()).translate_entrypoint("vs", TargetVersion::V5_0) {
+        Err(Error::MissingEntryPoint(ref name)) if name == "vs" => {}
+        other => panic!("{:?}", other),
+    }
+
+    match SpirvModule::from_bytes(&reads_output()).translate_entrypoint("main", TargetVersion::V5_0) {
+        Err(err @ Error::Unsupported(..)) => assert_eq!(err.to_string(), "unsupported reading an output"),
+        other => panic!("{:?}", other),
+    }
+}